@@ -0,0 +1,2 @@
+-- Locked accounts can't be used to generate proofs or decrypt values.
+ALTER TABLE accounts ADD COLUMN locked BOOLEAN NOT NULL DEFAULT FALSE;
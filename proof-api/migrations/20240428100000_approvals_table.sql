@@ -0,0 +1,18 @@
+-- Approvals of sensitive operations (dual control).
+CREATE TABLE IF NOT EXISTS approvals
+(
+    approval_id    INTEGER PRIMARY KEY NOT NULL,
+
+    operation      TEXT NOT NULL,
+    target         TEXT NOT NULL,
+    request_hash   TEXT NOT NULL,
+    -- pending, approved, rejected, executed
+    status         TEXT DEFAULT 'pending' NOT NULL,
+    requested_by   TEXT NOT NULL,
+    decided_by     TEXT,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS approvals_status_idx ON approvals(status, created_at);
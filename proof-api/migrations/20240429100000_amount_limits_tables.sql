@@ -0,0 +1,33 @@
+-- Amount limits of confidential accounts.  `asset_id` NULL applies to all assets.
+CREATE TABLE IF NOT EXISTS amount_limits
+(
+    id             INTEGER PRIMARY KEY NOT NULL,
+
+    account_id     INTEGER NOT NULL,
+    asset_id       BLOB,
+    max_transfer   INTEGER,
+    max_daily      INTEGER,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_id) REFERENCES accounts(account_id)
+);
+
+CREATE INDEX IF NOT EXISTS amount_limits_account_idx ON amount_limits(account_id);
+
+-- Amounts sent, for the daily limits.
+CREATE TABLE IF NOT EXISTS amount_usage
+(
+    id             INTEGER PRIMARY KEY NOT NULL,
+
+    account_id     INTEGER NOT NULL,
+    asset_id       BLOB,
+    amount         INTEGER NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_id) REFERENCES accounts(account_id)
+);
+
+CREATE INDEX IF NOT EXISTS amount_usage_account_idx ON amount_usage(account_id, created_at);
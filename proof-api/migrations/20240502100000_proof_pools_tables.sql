@@ -0,0 +1,48 @@
+-- Pools of pre-generated sender proofs.
+CREATE TABLE IF NOT EXISTS proof_pools
+(
+    pool_id            INTEGER PRIMARY KEY NOT NULL,
+
+    account_asset_id   INTEGER NOT NULL,
+    asset_id           BLOB NOT NULL,
+    -- Receiver and auditors (`0x` prefixed hex, auditors sorted and comma separated).
+    receiver           TEXT NOT NULL,
+    auditors           TEXT NOT NULL,
+    amount             INTEGER NOT NULL,
+    size               INTEGER NOT NULL,
+    generated          INTEGER NOT NULL DEFAULT 0,
+    used               INTEGER NOT NULL DEFAULT 0,
+    -- `generating`, `ready`, `exhausted`, `released`, `expired` or `failed`.
+    status             TEXT NOT NULL DEFAULT 'generating',
+    error              TEXT,
+
+    expires_at         TIMESTAMP NOT NULL,
+    created_at         TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at         TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_asset_id) REFERENCES account_assets(account_asset_id)
+);
+
+CREATE INDEX IF NOT EXISTS proof_pools_account_asset_idx ON proof_pools(account_asset_id, status);
+
+-- Pre-generated sender proofs.
+CREATE TABLE IF NOT EXISTS pool_proofs
+(
+    pool_proof_id      INTEGER PRIMARY KEY NOT NULL,
+
+    pool_id            INTEGER NOT NULL,
+    seq                INTEGER NOT NULL,
+    proof              BLOB NOT NULL,
+    -- Encrypted balance the proof was generated from.
+    enc_balance        BLOB NOT NULL,
+    -- Encrypted amount subtracted from the balance.
+    enc_amount         BLOB NOT NULL,
+    -- `available`, `reserved`, `used` or `released`.
+    status             TEXT NOT NULL DEFAULT 'available',
+
+    created_at         TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at         TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    UNIQUE(pool_id, seq),
+    FOREIGN KEY(pool_id) REFERENCES proof_pools(pool_id)
+);
@@ -0,0 +1,21 @@
+-- Time windows during which no sender proofs are issued for an account asset.
+CREATE TABLE IF NOT EXISTS position_locks
+(
+    lock_id        INTEGER PRIMARY KEY NOT NULL,
+
+    account_id     INTEGER NOT NULL,
+    -- NULL locks all of the account's assets.
+    asset_id       BLOB,
+    -- reject, defer
+    mode           TEXT NOT NULL,
+    reason         TEXT,
+
+    starts_at      TIMESTAMP NOT NULL,
+    ends_at        TIMESTAMP NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_id) REFERENCES accounts(account_id)
+);
+
+CREATE INDEX IF NOT EXISTS position_locks_account_idx ON position_locks(account_id, ends_at);
@@ -0,0 +1,5 @@
+-- Local asset metadata.
+ALTER TABLE assets ADD COLUMN name TEXT;
+ALTER TABLE assets ADD COLUMN ticker TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS assets_ticker_idx ON assets(ticker);
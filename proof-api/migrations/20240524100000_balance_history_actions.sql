@@ -0,0 +1,9 @@
+-- What changed the balance: init, mint, send, burn, apply_incoming, update_balance, deposit,
+-- withdrawal, proof_pool_release, refresh, resolve_conflict.
+ALTER TABLE balance_history ADD COLUMN action TEXT;
+-- Amount added to (positive) or removed from (negative) the balance, if known.
+ALTER TABLE balance_history ADD COLUMN amount INTEGER;
+-- Transaction hash of chain changes, or the API call of local changes.
+ALTER TABLE balance_history ADD COLUMN source TEXT;
+
+CREATE INDEX IF NOT EXISTS balance_history_account_idx ON balance_history(account_id, asset_id, id);
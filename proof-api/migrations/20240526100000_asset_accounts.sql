@@ -0,0 +1,6 @@
+-- Master account of accounts derived per asset.
+ALTER TABLE accounts ADD COLUMN parent_account_id INTEGER REFERENCES accounts(account_id);
+-- Asset of a derived account.
+ALTER TABLE accounts ADD COLUMN derived_asset_id BLOB;
+
+CREATE UNIQUE INDEX IF NOT EXISTS accounts_derived_asset_idx ON accounts(parent_account_id, derived_asset_id);
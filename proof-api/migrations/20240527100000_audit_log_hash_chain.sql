@@ -0,0 +1,4 @@
+-- Hash chain of the audit log: each entry's hash covers the entry and the previous
+-- entry's hash.  Entries from before the chain have no hashes.
+ALTER TABLE audit_log ADD COLUMN prev_hash BLOB;
+ALTER TABLE audit_log ADD COLUMN hash BLOB;
@@ -0,0 +1,12 @@
+-- Accounts a user may decrypt (decryption tokens).
+CREATE TABLE IF NOT EXISTS user_account_access
+(
+    user_id        INTEGER NOT NULL,
+    account_id     INTEGER NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    PRIMARY KEY (user_id, account_id),
+    FOREIGN KEY(user_id) REFERENCES users(user_id),
+    FOREIGN KEY(account_id) REFERENCES accounts(account_id)
+);
@@ -0,0 +1,385 @@
+use actix_web::{web::Data, HttpRequest};
+use chrono::Timelike;
+use reqwest::{Client, Url};
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  AccountWithSecret, AddAnomalyAlert, AddAuditLogEntry,
+};
+
+use crate::api_keys::{authenticated_user, API_KEY_HEADER};
+use crate::repo::Repository;
+
+pub type AppAnomalies = Data<Anomalies>;
+
+/// Audit log actions.
+pub const ACTION_SENDER_PROOF: &str = "sender_proof";
+pub const ACTION_DECRYPT: &str = "decrypt";
+
+/// Anomaly detection rules.
+pub const RULE_AMOUNT_ZSCORE: &str = "amount_zscore";
+pub const RULE_PROOF_BURST: &str = "proof_burst";
+pub const RULE_OFF_HOURS_DECRYPT: &str = "off_hours_decrypt";
+
+/// Don't repeat an off-hours decrypt alert for the same account within an hour.
+const OFF_HOURS_ALERT_SECS: u32 = 60 * 60;
+
+/// Anomaly detection configuration.
+#[derive(Clone, Debug)]
+pub struct AnomalyRules {
+  /// Alert when a transfer amount is this many standard deviations above the account's mean.
+  pub zscore: f64,
+  /// Number of earlier transfers needed before the z-score rule applies.
+  pub min_samples: u32,
+  /// Number of earlier transfers used for the mean and standard deviation.
+  pub max_samples: u32,
+  /// Alert when an account generates more than `burst_count` sender proofs in `burst_secs`.
+  pub burst_count: u32,
+  pub burst_secs: u32,
+  /// Business hours (UTC, `start..end`).  Decryptions outside them raise an alert.
+  pub business_hours: Option<(u32, u32)>,
+}
+
+impl Default for AnomalyRules {
+  fn default() -> Self {
+    Self {
+      zscore: 3.0,
+      min_samples: 10,
+      max_samples: 100,
+      burst_count: 20,
+      burst_secs: 60,
+      business_hours: None,
+    }
+  }
+}
+
+/// Account activity anomaly detection.
+///
+/// Sender proofs and decryptions are recorded in the audit log.  With
+/// `ANOMALY_DETECTION=true` each operation is checked against simple rules on the
+/// account's audit log:
+///
+/// * `amount_zscore`: the transfer amount is far above the account's usual amounts.
+/// * `proof_burst`: too many sender proofs in a short time.
+/// * `off_hours_decrypt`: a decryption outside of business hours.
+///
+/// Triggered rules are stored (see `/admin/anomalies`) and posted to `ANOMALY_WEBHOOK_URL`.
+/// Alerts never block the operation.
+pub struct Anomalies {
+  repo: Repository,
+  client: Client,
+  webhook_url: Option<Url>,
+  rules: Option<AnomalyRules>,
+}
+
+impl Anomalies {
+  /// Load the config from `ANOMALY_DETECTION`, `ANOMALY_WEBHOOK_URL`, `ANOMALY_ZSCORE`,
+  /// `ANOMALY_MIN_SAMPLES`, `ANOMALY_BURST_COUNT`, `ANOMALY_BURST_SECS` and
+  /// `ANOMALY_BUSINESS_HOURS` (`start-end` in UTC hours, e.g. `7-19`).
+  pub fn from_env(repo: Repository) -> Result<AppAnomalies> {
+    let enabled = std::env::var("ANOMALY_DETECTION")
+      .map(|v| v == "true" || v == "1")
+      .unwrap_or(false);
+    let webhook_url = match std::env::var("ANOMALY_WEBHOOK_URL") {
+      Ok(url) => Some(Url::parse(&url)?),
+      Err(_) => None,
+    };
+    let rules = if enabled {
+      let defaults = AnomalyRules::default();
+      let business_hours = match std::env::var("ANOMALY_BUSINESS_HOURS") {
+        Ok(hours) => Some(parse_hours(&hours)?),
+        Err(_) => None,
+      };
+      let rules = AnomalyRules {
+        zscore: env_or("ANOMALY_ZSCORE", defaults.zscore),
+        min_samples: env_or("ANOMALY_MIN_SAMPLES", defaults.min_samples),
+        burst_count: env_or("ANOMALY_BURST_COUNT", defaults.burst_count),
+        burst_secs: env_or("ANOMALY_BURST_SECS", defaults.burst_secs),
+        business_hours,
+        ..defaults
+      };
+      log::info!("Anomaly detection: {rules:?}");
+      Some(rules)
+    } else {
+      None
+    };
+    Ok(Data::new(Self {
+      repo,
+      client: Client::new(),
+      webhook_url,
+      rules,
+    }))
+  }
+
+  /// Record a generated sender proof and check the account's transfer rules.
+  pub async fn sender_proof(
+    &self,
+    user: Option<&str>,
+    account: &AccountWithSecret,
+    asset_id: Option<Uuid>,
+    amount: u64,
+  ) {
+    let res = self
+      .check_sender_proof(user, account, asset_id, amount)
+      .await;
+    if let Err(err) = res {
+      log::error!("Failed to record sender proof in the audit log: {err:?}");
+    }
+  }
+
+  /// Record a decryption and check the account's decryption rules.
+  pub async fn decrypt(
+    &self,
+    user: Option<&str>,
+    account: &AccountWithSecret,
+    asset_id: Option<Uuid>,
+  ) {
+    let res = self.check_decrypt(user, account, asset_id).await;
+    if let Err(err) = res {
+      log::error!("Failed to record decryption in the audit log: {err:?}");
+    }
+  }
+
+  async fn check_sender_proof(
+    &self,
+    user: Option<&str>,
+    account: &AccountWithSecret,
+    asset_id: Option<Uuid>,
+    amount: u64,
+  ) -> Result<()> {
+    let amount = i64::try_from(amount).map_err(|_| Error::other("Amount is too large"))?;
+    if let Some(rules) = &self.rules {
+      // Compare against the earlier transfers, before this one is recorded.
+      let amounts = self
+        .repo
+        .get_recent_sender_amounts(account.account_id, asset_id, rules.max_samples)
+        .await?;
+      if let Some(details) = rules.amount_zscore(amount, &amounts) {
+        self
+          .alert(RULE_AMOUNT_ZSCORE, user, account, asset_id, details)
+          .await?;
+      }
+    }
+    self
+      .repo
+      .add_audit_log(&AddAuditLogEntry {
+        account_id: account.account_id,
+        asset_id,
+        action: ACTION_SENDER_PROOF.to_string(),
+        amount: Some(amount),
+        user: user.map(|u| u.to_string()),
+      })
+      .await?;
+    if let Some(rules) = &self.rules {
+      let count = self
+        .repo
+        .count_recent_audit_log(account.account_id, ACTION_SENDER_PROOF, rules.burst_secs)
+        .await?;
+      // Only alert once per burst.
+      if count > rules.burst_count as i64
+        && !self
+          .has_recent_alert(RULE_PROOF_BURST, account, rules.burst_secs)
+          .await?
+      {
+        let details = format!(
+          "{count} sender proofs in the last {} seconds (limit {})",
+          rules.burst_secs, rules.burst_count
+        );
+        self
+          .alert(RULE_PROOF_BURST, user, account, asset_id, details)
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  async fn check_decrypt(
+    &self,
+    user: Option<&str>,
+    account: &AccountWithSecret,
+    asset_id: Option<Uuid>,
+  ) -> Result<()> {
+    self
+      .repo
+      .add_audit_log(&AddAuditLogEntry {
+        account_id: account.account_id,
+        asset_id,
+        action: ACTION_DECRYPT.to_string(),
+        amount: None,
+        user: user.map(|u| u.to_string()),
+      })
+      .await?;
+    if let Some((start, end)) = self.rules.as_ref().and_then(|rules| rules.business_hours) {
+      let hour = chrono::Utc::now().hour();
+      if !in_hours(hour, start, end)
+        && !self
+          .has_recent_alert(RULE_OFF_HOURS_DECRYPT, account, OFF_HOURS_ALERT_SECS)
+          .await?
+      {
+        let details = format!("decryption at {hour}:00 UTC, outside of {start}-{end} UTC");
+        self
+          .alert(RULE_OFF_HOURS_DECRYPT, user, account, asset_id, details)
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  async fn has_recent_alert(
+    &self,
+    rule: &str,
+    account: &AccountWithSecret,
+    secs: u32,
+  ) -> Result<bool> {
+    let account = hex::encode(&account.confidential_account);
+    self
+      .repo
+      .has_recent_anomaly_alert(rule, &account, secs)
+      .await
+  }
+
+  async fn alert(
+    &self,
+    rule: &str,
+    user: Option<&str>,
+    account: &AccountWithSecret,
+    asset_id: Option<Uuid>,
+    details: String,
+  ) -> Result<()> {
+    let alert = self
+      .repo
+      .add_anomaly_alert(&AddAnomalyAlert {
+        rule: rule.to_string(),
+        confidential_account: hex::encode(&account.confidential_account),
+        asset_id,
+        user: user.map(|u| u.to_string()),
+        details,
+      })
+      .await?;
+    log::warn!(
+      "Anomaly alert {} ({rule}) for {}: {}",
+      alert.alert_id,
+      alert.confidential_account,
+      alert.details
+    );
+    if let Some(url) = &self.webhook_url {
+      let res = self
+        .client
+        .post(url.clone())
+        .json(&alert)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status());
+      if let Err(err) = res {
+        log::error!("Failed to send anomaly alert {}: {err:?}", alert.alert_id);
+      }
+    }
+    Ok(())
+  }
+}
+
+impl AnomalyRules {
+  /// Returns the reason if `amount` is more than `zscore` standard deviations above the mean
+  /// of the earlier `amounts`.
+  pub fn amount_zscore(&self, amount: i64, amounts: &[i64]) -> Option<String> {
+    if amounts.len() < self.min_samples.max(2) as usize {
+      return None;
+    }
+    let count = amounts.len() as f64;
+    let mean = amounts.iter().map(|a| *a as f64).sum::<f64>() / count;
+    let variance = amounts
+      .iter()
+      .map(|a| (*a as f64 - mean).powi(2))
+      .sum::<f64>()
+      / (count - 1.0);
+    let std_dev = variance.sqrt();
+    let diff = amount as f64 - mean;
+    if diff <= 0.0 {
+      return None;
+    }
+    if std_dev == 0.0 {
+      // All earlier transfers had the same amount.
+      return Some(format!(
+        "amount {amount} is above the constant amount {mean} ({} samples)",
+        amounts.len()
+      ));
+    }
+    let zscore = diff / std_dev;
+    if zscore > self.zscore {
+      Some(format!(
+        "amount {amount} is {zscore:.1} standard deviations above the mean {mean:.0} ({} samples)",
+        amounts.len()
+      ))
+    } else {
+      None
+    }
+  }
+}
+
+/// User making the request, authenticated by their API key (`X-Api-Key` header).
+///
+/// `None` for anonymous requests without an API key.  An invalid API key is rejected.
+pub async fn request_user(repo: &Repository, http_req: &HttpRequest) -> Result<Option<String>> {
+  if !http_req.headers().contains_key(API_KEY_HEADER) {
+    return Ok(None);
+  }
+  Ok(Some(authenticated_user(repo, http_req).await?.username))
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+  std::env::var(name)
+    .ok()
+    .and_then(|v| v.parse().ok())
+    .unwrap_or(default)
+}
+
+/// Parse `start-end` UTC hours.
+fn parse_hours(hours: &str) -> Result<(u32, u32)> {
+  let invalid = || Error::Other(format!("Invalid ANOMALY_BUSINESS_HOURS: {hours}"));
+  let (start, end) = hours.split_once('-').ok_or_else(invalid)?;
+  let start: u32 = start.trim().parse().map_err(|_| invalid())?;
+  let end: u32 = end.trim().parse().map_err(|_| invalid())?;
+  if start > 23 || end > 24 {
+    return Err(invalid());
+  }
+  Ok((start, end))
+}
+
+/// Is `hour` in `start..end`.  Wraps around midnight if `end` is before `start`.
+fn in_hours(hour: u32, start: u32, end: u32) -> bool {
+  if start <= end {
+    hour >= start && hour < end
+  } else {
+    hour >= start || hour < end
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::test::TestRequest;
+
+  use super::*;
+  use crate::repo::SqliteConfidentialRepository;
+
+  fn repo() -> Repository {
+    let pool = sqlx::SqlitePool::connect_lazy("sqlite::memory:").expect("In-memory pool");
+    SqliteConfidentialRepository::new_app_data(&pool)
+  }
+
+  #[actix_web::test]
+  async fn user_header_is_not_trusted() {
+    let http_req = TestRequest::default()
+      .insert_header(("x-user", "admin"))
+      .to_http_request();
+    assert_eq!(request_user(&repo(), &http_req).await.unwrap(), None);
+  }
+
+  #[actix_web::test]
+  async fn invalid_api_key_is_rejected() {
+    let http_req = TestRequest::default()
+      .insert_header((API_KEY_HEADER, "not hex"))
+      .to_http_request();
+    assert!(request_user(&repo(), &http_req).await.is_err());
+  }
+}
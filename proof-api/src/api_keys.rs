@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+
+use actix_web::{web::Data, HttpRequest};
+use sp_core::hashing::blake2_256;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  CreateUserApiKey, User, UserApiKeyCreated,
+};
+
+use crate::repo::Repository;
+
+pub type AppApiKeyAdmins = Data<ApiKeyAdmins>;
+
+/// Request header with the API key of the user making the request.
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// Request header with the secret for creating the first API keys.
+pub const BOOTSTRAP_SECRET_HEADER: &str = "x-bootstrap-secret";
+
+/// Who may create and revoke API keys.
+///
+/// Either a user listed in `API_KEY_ADMINS` (comma separated) authenticated by their own API
+/// key, or a request with the `API_KEY_BOOTSTRAP_SECRET` in the `X-Bootstrap-Secret` header
+/// (to create the admins' first keys).  Without either, no API keys can be created.
+pub struct ApiKeyAdmins {
+  admins: HashSet<String>,
+  /// Hash of the bootstrap secret.
+  bootstrap_secret: Option<[u8; 32]>,
+}
+
+impl ApiKeyAdmins {
+  pub fn new(admins: HashSet<String>, bootstrap_secret: Option<&str>) -> Self {
+    Self {
+      admins,
+      bootstrap_secret: bootstrap_secret
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| blake2_256(secret.as_bytes())),
+    }
+  }
+
+  /// Load the admins from `API_KEY_ADMINS` and the secret from `API_KEY_BOOTSTRAP_SECRET`.
+  pub fn from_env() -> AppApiKeyAdmins {
+    let admins: HashSet<String> = std::env::var("API_KEY_ADMINS")
+      .unwrap_or_default()
+      .split(',')
+      .map(|name| name.trim().to_string())
+      .filter(|name| !name.is_empty())
+      .collect();
+    let secret = std::env::var("API_KEY_BOOTSTRAP_SECRET").ok();
+    let admins = Self::new(admins, secret.as_deref());
+    if admins.admins.is_empty() && admins.bootstrap_secret.is_none() {
+      log::warn!("No API_KEY_ADMINS or API_KEY_BOOTSTRAP_SECRET, API keys can't be created");
+    }
+    Data::new(admins)
+  }
+
+  /// Check that the request may create or revoke API keys.
+  pub async fn authorize(&self, repo: &Repository, http_req: &HttpRequest) -> Result<()> {
+    let secret = http_req
+      .headers()
+      .get(BOOTSTRAP_SECRET_HEADER)
+      .and_then(|val| val.to_str().ok());
+    if let Some(secret) = secret {
+      return match self.bootstrap_secret {
+        // Compare the hashes, so the comparison doesn't leak the secret.
+        Some(expected) if blake2_256(secret.as_bytes()) == expected => Ok(()),
+        _ => Err(Error::forbidden("Invalid bootstrap secret")),
+      };
+    }
+    let user = authenticated_user(repo, http_req).await?;
+    if !self.admins.contains(&user.username) {
+      return Err(Error::forbidden("User may not manage API keys"));
+    }
+    Ok(())
+  }
+}
+
+/// Create an API key for the user.  Only the key's hash is stored.
+pub async fn create_api_key(
+  repo: &Repository,
+  username: &str,
+  req: &CreateUserApiKey,
+) -> Result<UserApiKeyCreated> {
+  let key = rand::random::<[u8; 32]>();
+  let api_key = repo
+    .create_user_api_key(username, &req.name, &blake2_256(&key))
+    .await?
+    .ok_or_else(|| Error::not_found("User"))?;
+  log::info!(
+    "Created API key {} ({}) for user {username}",
+    api_key.key_id,
+    api_key.name
+  );
+  Ok(UserApiKeyCreated {
+    api_key,
+    key: format!("0x{}", hex::encode(key)),
+  })
+}
+
+/// The user authenticated by the request's API key (`X-Api-Key` header).
+pub async fn authenticated_user(repo: &Repository, http_req: &HttpRequest) -> Result<User> {
+  let key = http_req
+    .headers()
+    .get(API_KEY_HEADER)
+    .and_then(|val| val.to_str().ok())
+    .ok_or_else(|| Error::forbidden("Missing X-Api-Key header"))?;
+  let key = hex::decode(key.trim().trim_start_matches("0x"))
+    .map_err(|_| Error::forbidden("Invalid API key"))?;
+  repo
+    .get_api_key_user(&blake2_256(&key))
+    .await?
+    .ok_or_else(|| Error::forbidden("Invalid API key"))
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::test::TestRequest;
+
+  use super::*;
+  use crate::repo::SqliteConfidentialRepository;
+
+  fn repo() -> Repository {
+    let pool = sqlx::SqlitePool::connect_lazy("sqlite::memory:").expect("In-memory pool");
+    SqliteConfidentialRepository::new_app_data(&pool)
+  }
+
+  #[actix_web::test]
+  async fn unauthenticated_mint_is_rejected() {
+    let admins = ApiKeyAdmins::new(["admin".to_string()].into(), Some("bootstrap"));
+    let http_req = TestRequest::default().to_http_request();
+    assert!(matches!(
+      admins.authorize(&repo(), &http_req).await,
+      Err(Error::Forbidden(_))
+    ));
+  }
+
+  #[actix_web::test]
+  async fn bootstrap_secret_is_checked() {
+    let admins = ApiKeyAdmins::new(HashSet::new(), Some("bootstrap"));
+    let http_req = TestRequest::default()
+      .insert_header((BOOTSTRAP_SECRET_HEADER, "wrong"))
+      .to_http_request();
+    assert!(admins.authorize(&repo(), &http_req).await.is_err());
+    let http_req = TestRequest::default()
+      .insert_header((BOOTSTRAP_SECRET_HEADER, "bootstrap"))
+      .to_http_request();
+    assert!(admins.authorize(&repo(), &http_req).await.is_ok());
+  }
+
+  #[actix_web::test]
+  async fn no_bootstrap_secret_rejects_all() {
+    let admins = ApiKeyAdmins::new(HashSet::new(), None);
+    let http_req = TestRequest::default()
+      .insert_header((BOOTSTRAP_SECRET_HEADER, ""))
+      .to_http_request();
+    assert!(admins.authorize(&repo(), &http_req).await.is_err());
+  }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeSet;
+
+use actix_web::{web::Data, HttpRequest, HttpResponse};
+use serde::Serialize;
+use sp_core::hashing::blake2_256;
+
+use confidential_assets::Balance;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  Approval, ApprovalOperation, CreateApproval,
+};
+
+use crate::api_keys::authenticated_user;
+use crate::repo::Repository;
+
+pub type AppApprovals = Data<Approvals>;
+
+/// Request header with the id of the approval for a sensitive operation.
+pub const APPROVAL_HEADER: &str = "x-approval-id";
+
+/// Default seconds an approval is valid for.
+const DEFAULT_APPROVAL_TTL: i64 = 86400;
+
+/// Dual control for sensitive operations.
+///
+/// Operations listed in `APPROVAL_OPERATIONS` need a second user's approval.  The first
+/// request creates a pending approval (`202 Accepted`), another user approves it with
+/// `POST /approvals/{approval_id}/approve` and the requesting user then resubmits the
+/// same request with the `X-Approval-Id` header.  An approval can only be used once.
+///
+/// Users are authenticated by their API key (`X-Api-Key` header, see
+/// `/admin/users/{user_name}/api_keys`).  The user that requested an operation can't
+/// approve it.
+pub struct Approvals {
+  operations: BTreeSet<ApprovalOperation>,
+  burn_threshold: Balance,
+  ttl: i64,
+}
+
+impl Approvals {
+  /// Load the config from `APPROVAL_OPERATIONS` (comma separated), `APPROVAL_BURN_THRESHOLD`
+  /// and `APPROVAL_TTL` (seconds).
+  pub fn from_env() -> Result<AppApprovals> {
+    let operations = std::env::var("APPROVAL_OPERATIONS")
+      .unwrap_or_default()
+      .split(',')
+      .map(|op| op.trim())
+      .filter(|op| !op.is_empty())
+      .map(|op| op.parse())
+      .collect::<Result<BTreeSet<_>>>()?;
+    let burn_threshold = std::env::var("APPROVAL_BURN_THRESHOLD")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(0);
+    let ttl = std::env::var("APPROVAL_TTL")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_APPROVAL_TTL);
+    if !operations.is_empty() {
+      log::info!("Operations that need approval: {operations:?}");
+    }
+    Ok(Data::new(Self {
+      operations,
+      burn_threshold,
+      ttl,
+    }))
+  }
+
+  pub fn requires_approval(&self, operation: ApprovalOperation) -> bool {
+    self.operations.contains(&operation)
+  }
+
+  /// Burns at or above the threshold need approval.
+  pub fn burn_requires_approval(&self, amount: Balance) -> bool {
+    self.requires_approval(ApprovalOperation::Burn) && amount >= self.burn_threshold
+  }
+
+  /// Check the approval of a sensitive operation.
+  ///
+  /// Returns `None` if the request has an approved (unused) approval and the operation can
+  /// be executed.  Otherwise a pending approval is created and returned as the response.
+  pub async fn authorize<R: Serialize>(
+    &self,
+    repo: &Repository,
+    http_req: &HttpRequest,
+    operation: ApprovalOperation,
+    target: &str,
+    request: &R,
+  ) -> Result<Option<HttpResponse>> {
+    let user = self.user(repo, http_req).await?;
+    let request = serde_json::to_vec(&(operation, target, request))?;
+    let request_hash = format!("0x{}", hex::encode(blake2_256(&request)));
+
+    let approval_id = match header(http_req, APPROVAL_HEADER) {
+      Some(approval_id) => approval_id
+        .parse::<i64>()
+        .map_err(|_| Error::other("Invalid X-Approval-Id header"))?,
+      None => {
+        let approval = repo
+          .create_approval(&CreateApproval {
+            operation: operation.as_str().to_string(),
+            target: target.to_string(),
+            request_hash,
+            requested_by: user,
+          })
+          .await?;
+        log::info!(
+          "Approval {} requested by {} for {} of {target}",
+          approval.approval_id,
+          approval.requested_by,
+          approval.operation
+        );
+        return Ok(Some(HttpResponse::Accepted().json(approval)));
+      }
+    };
+
+    let approval = repo
+      .get_approval(approval_id)
+      .await?
+      .ok_or_else(|| Error::not_found("Approval"))?;
+    if approval.operation != operation.as_str()
+      || approval.target != target
+      || approval.request_hash != request_hash
+    {
+      return Err(Error::forbidden("Approval is for a different request"));
+    }
+    if approval.requested_by != user {
+      return Err(Error::forbidden("Approval was requested by another user"));
+    }
+    self.ensure_not_expired(&approval)?;
+    let approval = repo
+      .set_approval_status(approval_id, "approved", "executed", None)
+      .await?
+      .ok_or_else(|| Error::forbidden("Approval isn't approved or was already used"))?;
+    log::info!(
+      "Executing approval {approval_id}: {} of {target}, approved by {:?}",
+      approval.operation,
+      approval.decided_by
+    );
+    Ok(None)
+  }
+
+  /// Approve or reject a pending approval.  Must be a different user than the requester.
+  pub async fn decide(
+    &self,
+    repo: &Repository,
+    http_req: &HttpRequest,
+    approval_id: i64,
+    approve: bool,
+  ) -> Result<Approval> {
+    let user = self.user(repo, http_req).await?;
+    let approval = repo
+      .get_approval(approval_id)
+      .await?
+      .ok_or_else(|| Error::not_found("Approval"))?;
+    if approval.requested_by == user {
+      return Err(Error::forbidden(
+        "Approvals must be decided by a second user",
+      ));
+    }
+    self.ensure_not_expired(&approval)?;
+    let status = if approve { "approved" } else { "rejected" };
+    let approval = repo
+      .set_approval_status(approval_id, "pending", status, Some(&user))
+      .await?
+      .ok_or_else(|| Error::forbidden("Approval isn't pending"))?;
+    log::info!("Approval {approval_id} {status} by {user}");
+    Ok(approval)
+  }
+
+  /// The authenticated user making the request.
+  async fn user(&self, repo: &Repository, http_req: &HttpRequest) -> Result<String> {
+    Ok(authenticated_user(repo, http_req).await?.username)
+  }
+
+  fn ensure_not_expired(&self, approval: &Approval) -> Result<()> {
+    let age = chrono::Utc::now().naive_utc() - approval.created_at;
+    if age.num_seconds() > self.ttl {
+      return Err(Error::forbidden("Approval expired"));
+    }
+    Ok(())
+  }
+}
+
+fn header<'a>(http_req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+  http_req
+    .headers()
+    .get(name)
+    .and_then(|val| val.to_str().ok())
+}
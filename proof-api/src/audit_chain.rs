@@ -0,0 +1,85 @@
+use codec::Encode;
+use sp_core::hashing::blake2_256;
+
+use polymesh_private_proof_shared::{
+  error::Result, AuditChainError, AuditChainProblem, AuditChainReport, AuditLogEntry,
+};
+
+use crate::repo::Repository;
+
+/// `prev_hash` of the first chained audit log entry.
+pub const GENESIS_HASH: [u8; 32] = [0u8; 32];
+
+/// Entries read at a time while verifying the chain.
+const VERIFY_PAGE_SIZE: u32 = 1000;
+
+/// Hash of an audit log entry linked to the previous entry's hash.
+pub fn entry_hash(prev_hash: &[u8], entry: &AuditLogEntry) -> [u8; 32] {
+  let data = (
+    prev_hash,
+    entry.id,
+    entry.account_id,
+    entry.asset_id.map(|id| *id.as_bytes()),
+    &entry.action,
+    entry.amount,
+    &entry.user,
+    entry.created_at.to_string(),
+  );
+  blake2_256(&data.encode())
+}
+
+/// Check the hash chain of the audit log.
+///
+/// Detects altered entries, deleted or reordered entries (except at the end of the log,
+/// compare `head_hash` with a previously recorded one) and entries added without a hash.
+pub async fn verify_audit_chain(repo: &Repository) -> Result<AuditChainReport> {
+  let mut report = AuditChainReport::default();
+  // Hash of the last chained entry.
+  let mut prev: Option<Vec<u8>> = None;
+  let mut after_id = 0;
+  loop {
+    let entries = repo.get_audit_log_page(after_id, VERIFY_PAGE_SIZE).await?;
+    let last_id = match entries.last() {
+      Some(entry) => entry.id,
+      None => break,
+    };
+    for entry in &entries {
+      report.checked += 1;
+      let problem = match (&entry.hash, &prev) {
+        // Entries from before the hash chain.
+        (None, None) => {
+          report.unchained += 1;
+          continue;
+        }
+        (None, Some(_)) => Some(AuditChainProblem::MissingHash),
+        (Some(hash), _) => {
+          let prev_hash = entry.prev_hash.as_deref().unwrap_or_default();
+          let expected = prev.as_deref().unwrap_or(&GENESIS_HASH);
+          if entry_hash(prev_hash, entry)[..] != hash[..] {
+            Some(AuditChainProblem::HashMismatch)
+          } else if prev_hash != expected {
+            Some(AuditChainProblem::BrokenLink)
+          } else {
+            None
+          }
+        }
+      };
+      if let Some(problem) = problem {
+        log::warn!("Audit log entry {}: {problem:?}", entry.id);
+        report.errors.push(AuditChainError {
+          id: entry.id,
+          problem,
+        });
+      }
+      if let Some(hash) = &entry.hash {
+        prev = Some(hash.clone());
+        report.head_id = Some(entry.id);
+      }
+    }
+    after_id = last_id;
+  }
+  report.valid = report.errors.is_empty();
+  report.head_hash = prev.map(|hash| format!("0x{}", hex::encode(hash)));
+  report.completed_at = Some(chrono::Utc::now().naive_utc());
+  Ok(report)
+}
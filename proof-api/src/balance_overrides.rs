@@ -0,0 +1,109 @@
+use actix_web::{web::Data, HttpRequest, HttpResponse};
+use serde::Serialize;
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  AccountWithSecret, AddAuditLogEntry, ApprovalOperation,
+};
+
+use crate::api_keys::authenticated_user;
+use crate::approvals::Approvals;
+use crate::repo::Repository;
+
+pub type AppBalanceOverrides = Data<BalanceOverrides>;
+
+/// Audit log action of an accepted balance override.
+pub const ACTION_BALANCE_OVERRIDE: &str = "balance_override";
+
+/// What to do with a request that overrides the tracked encrypted balance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OverrideMode {
+  /// Accept the override.
+  Allow,
+  /// The override needs a second user's approval (see `approvals`).
+  Approval,
+  /// Reject the override.
+  Reject,
+}
+
+/// Strict mode for the `encrypted_balance` of the account asset sender/burn proof requests.
+///
+/// The `encrypted_balance` replaces the account asset's tracked balance when generating the
+/// proof, which can silently desync the local balance.  `BALANCE_OVERRIDES` is `allow`
+/// (default), `approval` (dual control with the `balance_override` approval operation) or
+/// `reject`.  Overrides need an API key (`X-Api-Key` header), every accepted override is
+/// recorded in the audit log with the authenticated user.
+pub struct BalanceOverrides {
+  mode: OverrideMode,
+}
+
+impl BalanceOverrides {
+  /// Load the config from `BALANCE_OVERRIDES`.
+  pub fn from_env() -> Result<AppBalanceOverrides> {
+    let mode = match std::env::var("BALANCE_OVERRIDES").as_deref() {
+      Err(_) | Ok("") | Ok("allow") => OverrideMode::Allow,
+      Ok("approval") => OverrideMode::Approval,
+      Ok("reject") => OverrideMode::Reject,
+      Ok(mode) => Err(Error::Other(format!("Invalid BALANCE_OVERRIDES: {mode}")))?,
+    };
+    if mode != OverrideMode::Allow {
+      log::info!("Encrypted balance overrides: {mode:?}");
+    }
+    Ok(Data::new(Self { mode }))
+  }
+
+  /// Check a request that overrides the tracked encrypted balance of an account asset.
+  ///
+  /// Returns `None` if the override is accepted.  In `approval` mode a pending approval is
+  /// returned as the response, until the request is resubmitted with an approved approval.
+  pub async fn check<R: Serialize>(
+    &self,
+    repo: &Repository,
+    approvals: &Approvals,
+    http_req: &HttpRequest,
+    account: &AccountWithSecret,
+    asset_id: Uuid,
+    request: &R,
+  ) -> Result<Option<HttpResponse>> {
+    match self.mode {
+      OverrideMode::Allow => (),
+      OverrideMode::Approval => {
+        let target = format!("{}/{asset_id}", hex::encode(&account.confidential_account));
+        let pending = approvals
+          .authorize(
+            repo,
+            http_req,
+            ApprovalOperation::BalanceOverride,
+            &target,
+            request,
+          )
+          .await?;
+        if pending.is_some() {
+          return Ok(pending);
+        }
+      }
+      OverrideMode::Reject => {
+        return Err(Error::forbidden(
+          "Overriding the tracked encrypted balance isn't allowed",
+        ));
+      }
+    }
+    // Never anonymous, the audit log must say who overrode the balance.
+    let user = authenticated_user(repo, http_req).await?.username;
+    log::warn!(
+      "Encrypted balance override for account {} asset {asset_id} by {user}",
+      account.account_id
+    );
+    repo
+      .add_audit_log(&AddAuditLogEntry {
+        account_id: account.account_id,
+        asset_id: Some(asset_id),
+        action: ACTION_BALANCE_OVERRIDE.to_string(),
+        amount: None,
+        user: Some(user),
+      })
+      .await?;
+    Ok(None)
+  }
+}
@@ -1,6 +1,7 @@
 use actix_cors::Cors;
 use actix_web::middleware::Logger;
 use actix_web::{web, App, HttpServer};
+use sqlx::migrate::Migrator;
 use sqlx::sqlite::SqlitePool;
 
 use utoipa::OpenApi;
@@ -9,16 +10,37 @@ use utoipa_redoc::{Redoc, Servable};
 use utoipa_swagger_ui::SwaggerUi;
 
 use polymesh_private_proof_api as proof_api;
-use polymesh_private_proof_api::{repo, v1::*};
+use polymesh_private_proof_api::{
+  decrypt_tokens::DecryptTokens, feature_flags::FeatureFlags, profile::DeploymentProfile, repo,
+  v1::*, warnings::ResponseEnvelopes,
+};
 use polymesh_private_proof_shared::*;
 
+static MIGRATOR: Migrator = sqlx::migrate!();
+
 async fn get_db_pool() -> anyhow::Result<SqlitePool> {
   let conn_str = std::env::var("DATABASE_URL")?;
   let pool = SqlitePool::connect(&conn_str).await?;
-  sqlx::migrate!().run(&pool).await?;
+  let applied = schema::prepare_schema(&pool, &MIGRATOR, schema::auto_migrate_from_env()).await?;
+  if !applied.is_empty() {
+    log::info!("Applied migrations: {applied:?}");
+  }
   Ok(pool)
 }
 
+/// `migrate` subcommand: apply pending migrations and exit.
+async fn run_migrations() -> anyhow::Result<()> {
+  let conn_str = std::env::var("DATABASE_URL")?;
+  let pool = SqlitePool::connect(&conn_str).await?;
+  let applied = schema::migrate(&pool, &MIGRATOR).await?;
+  if applied.is_empty() {
+    log::info!("Database schema is up to date");
+  } else {
+    log::info!("Applied migrations: {applied:?}");
+  }
+  Ok(())
+}
+
 async fn start_server() -> anyhow::Result<()> {
   // building address
   let port = std::env::var("PORT").unwrap_or("8080".to_string());
@@ -28,8 +50,66 @@ async fn start_server() -> anyhow::Result<()> {
   // Open database.
   let pool = get_db_pool().await?;
   // Repository.
-  let repo = repo::SqliteConfidentialRepository::new_app_data(&pool);
+  let repo = repo::SqliteConfidentialRepository::from_env(&pool).await?;
   log::info!("Repository initialized");
+  // Receipt signer.
+  let receipts = proof_api::receipts::ReceiptSigner::from_env()?;
+  // Replay protection.
+  let replay_guard = proof_api::replay::ReplayGuard::from_env()?;
+  // Background decryption jobs.
+  let decrypt_jobs = proof_api::decrypt_jobs::DecryptJobs::from_env();
+  // Dual control.
+  let approvals = proof_api::approvals::Approvals::from_env()?;
+  // Who may create API keys.
+  let api_key_admins = proof_api::api_keys::ApiKeyAdmins::from_env();
+  // Encrypted balance overrides.
+  let balance_overrides = proof_api::balance_overrides::BalanceOverrides::from_env()?;
+  // Deployment profile.
+  let profile = proof_api::profile::DeploymentProfile::from_env()?;
+  // Endpoints disabled at runtime.
+  let feature_flags = FeatureFlags::from_env(repo.clone()).await?;
+  {
+    let feature_flags = feature_flags.clone();
+    actix_web::rt::spawn(async move {
+      feature_flags.run().await;
+    });
+  }
+  // Decryption tokens.
+  let decrypt_tokens = proof_api::decrypt_tokens::DecryptTokens::from_env()?;
+  // Receiver screening.
+  let screening = proof_api::screening::Screening::from_env(repo.clone())?;
+  // Anomaly detection.
+  let anomalies = proof_api::anomalies::Anomalies::from_env(repo.clone())?;
+  // Balance valuation.
+  let valuation = proof_api::valuation::Valuation::from_env()?;
+  // Sender proof pools.
+  let proof_pools = proof_api::proof_pools::ProofPools::from_env(repo.clone());
+  {
+    let proof_pools = proof_pools.clone();
+    actix_web::rt::spawn(async move {
+      proof_pools.run().await;
+    });
+  }
+  // Account secret integrity checks.
+  let secret_integrity = proof_api::integrity::SecretIntegrity::from_env();
+  {
+    let secret_integrity = secret_integrity.clone();
+    let repo = repo.clone();
+    actix_web::rt::spawn(async move {
+      secret_integrity.run(repo).await;
+    });
+  }
+  // Decryption cache.
+  if let Some(size) = std::env::var("DECRYPTION_CACHE_SIZE")
+    .ok()
+    .and_then(|v| v.parse().ok())
+  {
+    DecryptionCache::set_size(size);
+  }
+  // Decryption baby-step table, built in the background.
+  let decryption_context = DecryptionContext::from_env();
+  // Thread pool for generating and verifying proofs.
+  ProvingThreads::init_from_env();
 
   // starting the server
   log::info!("🚀🚀🚀 Starting Actix server at {}", address);
@@ -44,16 +124,72 @@ async fn start_server() -> anyhow::Result<()> {
           accounts::get_all_accounts,
           accounts::get_account,
           accounts::create_account,
+          accounts::lock_account,
+          accounts::unlock_account,
+          accounts::get_asset_accounts,
+          accounts::create_asset_account,
+          escrow::get_account_escrow,
+          escrow::escrow_account,
+          escrow::reassemble_account,
+          escrow::seal_account,
+          integrity::get_secret_integrity,
+          integrity::check_secret_integrity,
+          approvals::get_approvals,
+          approvals::get_approval,
+          approvals::approve,
+          approvals::reject,
+          api_keys::get_user_api_keys,
+          api_keys::create_user_api_key,
+          api_keys::revoke_user_api_key,
+          user_access::get_user_account_access,
+          user_access::grant_account_access,
+          user_access::revoke_account_access,
+          decrypt_tokens::create_decrypt_token,
+          limits::get_amount_limits,
+          limits::set_amount_limit,
+          position_locks::get_position_locks,
+          position_locks::create_position_lock,
+          position_locks::delete_position_lock,
+          screening::get_all_screening_entries,
+          screening::get_screening_entry,
+          screening::create_screening_entry,
+          screening::update_screening_entry,
+          screening::delete_screening_entry,
+          anomalies::get_anomaly_alerts,
+          anomalies::verify_audit_log,
+          proofs::get_account_proofs,
+          proofs::get_proof,
+          jobs::get_job,
           accounts::auditor_verify_request,
           accounts::request_sender_proof,
           accounts::request_burn_proof,
           accounts::receiver_verify_request,
           accounts::decrypt_request,
+          accounts::decrypt_with_proof_request,
+          decryption_proofs::verify_decryption_proof,
+          receipts::get_receipt_public_key,
+          receipts::verify_receipt,
+          stats::get_proof_stats,
+          selftest::get_selftest,
+          fuzz::fuzz_routes,
+          feature_flags::get_feature_flags,
+          feature_flags::set_feature_flag,
+          feature_flags::delete_feature_flag,
         ),
         components(
           schemas(
             User, CreateUser,
-            Account,
+            Account, AssetAccount,
+            AccountEscrow, EscrowCustodian, EscrowAccountRequest, ReassembleAccountRequest,
+            SecretIntegrityReport, CorruptAccount, SecretProblem,
+            Approval, ApprovalOperation,
+            UserApiKey, CreateUserApiKey, UserApiKeyCreated, UserAccountAccess,
+            CreateDecryptToken, DecryptToken, DecryptTokenClaims,
+            AmountLimit, SetAmountLimit,
+            PositionLock, CreatePositionLock, PositionLockMode,
+            ScreeningEntry, CreateScreeningEntry, UpdateScreeningEntry, ScreeningList,
+            AnomalyAlert, AuditChainReport, AuditChainError, AuditChainProblem,
+            ProofRecord, FormattedProofRecord,
             PublicKey, BurnProof, SenderProof, TransferProofs,
             AuditorVerifyRequest,
             ReceiverVerifyRequest,
@@ -63,6 +199,14 @@ async fn start_server() -> anyhow::Result<()> {
             SenderProofVerifyResult,
             AccountDecryptRequest,
             DecryptedResponse,
+            VerifiableDecryption, DecryptionVerifyResult,
+            DecryptJob, DecryptJobStatus,
+            Receipt, ReceiptVerifyResult,
+            ProofStatsEntry, ProofOperation,
+            SelfTestReport, SelfTestStep,
+            FuzzRequest, FuzzReport, FuzzFailure,
+            ResponseWarning,
+            FeatureFlag, SetFeatureFlag,
           ),
         ),
         servers(
@@ -79,31 +223,105 @@ async fn start_server() -> anyhow::Result<()> {
           assets::get_all_assets,
           assets::get_asset,
           assets::create_asset,
+          assets::get_asset_holders,
           assets::sender_proof_verify,
           accounts::get_all_accounts,
           accounts::get_account,
           accounts::create_account,
+          accounts::lock_account,
+          accounts::unlock_account,
+          accounts::get_asset_accounts,
+          accounts::create_asset_account,
+          escrow::get_account_escrow,
+          escrow::escrow_account,
+          escrow::reassemble_account,
+          escrow::seal_account,
+          integrity::get_secret_integrity,
+          integrity::check_secret_integrity,
+          approvals::get_approvals,
+          approvals::get_approval,
+          approvals::approve,
+          approvals::reject,
+          api_keys::get_user_api_keys,
+          api_keys::create_user_api_key,
+          api_keys::revoke_user_api_key,
+          user_access::get_user_account_access,
+          user_access::grant_account_access,
+          user_access::revoke_account_access,
+          decrypt_tokens::create_decrypt_token,
+          limits::get_amount_limits,
+          limits::set_amount_limit,
+          position_locks::get_position_locks,
+          position_locks::create_position_lock,
+          position_locks::delete_position_lock,
+          screening::get_all_screening_entries,
+          screening::get_screening_entry,
+          screening::create_screening_entry,
+          screening::update_screening_entry,
+          screening::delete_screening_entry,
+          anomalies::get_anomaly_alerts,
+          anomalies::verify_audit_log,
+          proofs::get_account_proofs,
+          proofs::get_proof,
+          jobs::get_job,
           accounts::auditor_verify_request,
           accounts::request_sender_proof,
           accounts::request_burn_proof,
           accounts::receiver_verify_request,
           accounts::decrypt_request,
+          accounts::decrypt_with_proof_request,
+          decryption_proofs::verify_decryption_proof,
+          receipts::get_receipt_public_key,
+          receipts::verify_receipt,
+          stats::get_proof_stats,
+          selftest::get_selftest,
+          fuzz::fuzz_routes,
           account_assets::get_all_account_assets,
           account_assets::get_account_asset,
+          account_assets::get_account_asset_balance_at,
+          account_assets::get_account_asset_history,
+          account_assets::export_account_asset,
           account_assets::create_account_asset,
           account_assets::request_sender_proof,
           account_assets::request_burn_proof,
           account_assets::receiver_verify_request,
           account_assets::update_balance_request,
           account_assets::decrypt_request,
+          proof_pools::get_proof_pools,
+          proof_pools::get_proof_pool,
+          proof_pools::create_proof_pool,
+          proof_pools::release_proof_pool,
+          balance_conflicts::get_balance_conflicts,
+          balance_conflicts::get_balance_conflict,
+          balance_conflicts::resolve_balance_conflict,
+          feature_flags::get_feature_flags,
+          feature_flags::set_feature_flag,
+          feature_flags::delete_feature_flag,
         ),
         components(
           schemas(
             User, CreateUser,
             Asset, AddAsset,
-            Account,
-            AccountAsset, CreateAccountAsset,
+            Account, AssetAccount,
+            AccountEscrow, EscrowCustodian, EscrowAccountRequest, ReassembleAccountRequest,
+            SecretIntegrityReport, CorruptAccount, SecretProblem,
+            Approval, ApprovalOperation,
+            UserApiKey, CreateUserApiKey, UserApiKeyCreated, UserAccountAccess,
+            CreateDecryptToken, DecryptToken, DecryptTokenClaims,
+            AmountLimit, SetAmountLimit,
+            PositionLock, CreatePositionLock, PositionLockMode,
+            ScreeningEntry, CreateScreeningEntry, UpdateScreeningEntry, ScreeningList,
+            AnomalyAlert, AuditChainReport, AuditChainError, AuditChainProblem,
+            ProofRecord, FormattedProofRecord,
+            AccountAsset, CreateAccountAsset, AssetHolder, BalanceHistory, BalanceAction,
+            FormattedBalanceHistory,
+            AccountAssetExportRequest, AccountAssetExport, SignedAccountAssetExport,
+            ChainReference,
             AccountAssetWithProof,
+            ValuedAccountAsset, AssetValuation, AssetPrice,
+            ProofPool, CreateProofPool,
+            BalanceConflict, ResolveBalanceConflict,
+            FeatureFlag, SetFeatureFlag,
             PublicKey, BurnProof, SenderProof, TransferProofs,
             AuditorVerifyRequest,
             ReceiverVerifyRequest,
@@ -113,6 +331,13 @@ async fn start_server() -> anyhow::Result<()> {
             SenderProofVerifyResult,
             AccountDecryptRequest,
             DecryptedResponse,
+            VerifiableDecryption, DecryptionVerifyResult,
+            DecryptJob, DecryptJobStatus,
+            Receipt, ReceiptVerifyResult,
+            ProofStatsEntry, ProofOperation,
+            SelfTestReport, SelfTestStep,
+            FuzzRequest, FuzzReport, FuzzFailure,
+            ResponseWarning,
             UpdateAccountAssetBalanceRequest,
           ),
         ),
@@ -124,10 +349,15 @@ async fn start_server() -> anyhow::Result<()> {
   struct ApiDoc;
 
   let openapi = ApiDoc::openapi();
+  // Staging-only route fuzzer.
+  let fuzzer = proof_api::fuzz::Fuzzer::from_env(&openapi)?;
 
   HttpServer::new(move || {
     // CORS
     let cors = Cors::permissive();
+    let decrypt_tokens = decrypt_tokens.clone();
+    let profile = profile.clone();
+    let feature_flags = feature_flags.clone();
 
     App::new()
       .wrap(cors)
@@ -135,8 +365,27 @@ async fn start_server() -> anyhow::Result<()> {
       .service(
         web::scope("/api")
           .app_data(repo.clone())
+          .app_data(receipts.clone())
+          .app_data(replay_guard.clone())
+          .app_data(decrypt_jobs.clone())
+          .app_data(decryption_context.clone())
+          .app_data(secret_integrity.clone())
+          .app_data(approvals.clone())
+          .app_data(api_key_admins.clone())
+          .app_data(balance_overrides.clone())
+          .app_data(decrypt_tokens.clone())
+          .app_data(screening.clone())
+          .app_data(anomalies.clone())
+          .app_data(valuation.clone())
+          .app_data(proof_pools.clone())
+          .app_data(feature_flags.clone())
+          .app_data(fuzzer.clone())
           .configure(proof_api::health::service)
-          .configure(proof_api::v1::service),
+          .configure(proof_api::v1::service)
+          .wrap_fn(ResponseEnvelopes::middleware)
+          .wrap_fn(move |req, srv| DecryptTokens::middleware(decrypt_tokens.clone(), req, srv))
+          .wrap_fn(move |req, srv| FeatureFlags::middleware(feature_flags.clone(), req, srv))
+          .wrap_fn(move |req, srv| DeploymentProfile::middleware(profile.clone(), req, srv)),
       )
       .service(Redoc::with_url("/redoc", openapi.clone()))
       .service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", openapi.clone()))
@@ -164,7 +413,12 @@ async fn main() -> std::io::Result<()> {
   dotenv::dotenv().ok();
   env_logger::init();
 
-  if let Err(err) = start_server().await {
+  let res = match std::env::args().nth(1).as_deref() {
+    Some("migrate") => run_migrations().await,
+    Some(cmd) => Err(anyhow::anyhow!("Unknown subcommand: {cmd}")),
+    None => start_server().await,
+  };
+  if let Err(err) = res {
     log::error!("Failed to start server: {err:?}");
     return Err(std::io::Error::new(std::io::ErrorKind::Other, err));
   }
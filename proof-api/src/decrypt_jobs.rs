@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use actix_web::{web::Data, HttpResponse};
+use uuid::Uuid;
+
+use confidential_assets::{elgamal::CipherText, Balance};
+
+use polymesh_private_proof_shared::{
+  error::Result, AccountWithSecret, DecryptJob, DecryptedResponse,
+};
+
+pub type AppDecryptJobs = Data<DecryptJobs>;
+
+/// Seconds to keep the results of finished jobs.
+const JOB_RETENTION: i64 = 3600;
+
+/// Decrypts large values in the background.
+///
+/// Decrypting a value is a search over the possible values, which for very large values
+/// can take longer than the request timeout.  When `DECRYPT_JOB_THRESHOLD` is set, the
+/// `decrypt` endpoints only search for values up to the threshold and start a background
+/// job for larger values.  Jobs are only kept in memory.
+pub struct DecryptJobs {
+  threshold: Option<Balance>,
+  jobs: Arc<Mutex<HashMap<Uuid, DecryptJob>>>,
+}
+
+impl DecryptJobs {
+  /// Load the threshold from `DECRYPT_JOB_THRESHOLD`.  Jobs are disabled if not set.
+  pub fn from_env() -> AppDecryptJobs {
+    let threshold = std::env::var("DECRYPT_JOB_THRESHOLD")
+      .ok()
+      .and_then(|v| v.parse().ok());
+    Data::new(Self {
+      threshold,
+      jobs: Default::default(),
+    })
+  }
+
+  /// Decrypt `enc_value`.
+  ///
+  /// Responds with `202 Accepted` and the job if the value is above the threshold.
+  pub fn decrypt(
+    &self,
+    account: &AccountWithSecret,
+    enc_value: CipherText,
+  ) -> Result<HttpResponse> {
+    let value = match self.threshold {
+      Some(threshold) => account.decrypt_in_range(&enc_value, threshold)?,
+      None => Some(account.decrypt(&enc_value)?),
+    };
+    if let Some(value) = value {
+      return Ok(HttpResponse::Ok().json(DecryptedResponse { value }));
+    }
+
+    let job = DecryptJob::new(Uuid::new_v4());
+    let job_id = job.job_id;
+    {
+      let mut jobs = self.jobs.lock().expect("Decrypt jobs lock poisoned");
+      // Drop old results.
+      let now = chrono::Utc::now().naive_utc();
+      jobs.retain(|_, job| match job.completed_at {
+        Some(completed_at) => (now - completed_at).num_seconds() < JOB_RETENTION,
+        None => true,
+      });
+      jobs.insert(job_id, job.clone());
+    }
+
+    let jobs = self.jobs.clone();
+    let account = account.clone();
+    actix_web::rt::task::spawn_blocking(move || {
+      let res = account.decrypt(&enc_value);
+      let mut jobs = jobs.lock().expect("Decrypt jobs lock poisoned");
+      if let Some(job) = jobs.get_mut(&job_id) {
+        job.complete(res);
+      }
+    });
+
+    Ok(HttpResponse::Accepted().json(job))
+  }
+
+  /// Get a job by id.
+  pub fn get_job(&self, job_id: Uuid) -> Option<DecryptJob> {
+    let jobs = self.jobs.lock().expect("Decrypt jobs lock poisoned");
+    jobs.get(&job_id).cloned()
+  }
+}
@@ -0,0 +1,216 @@
+use actix_web::{
+  body::EitherBody,
+  dev::{Service, ServiceRequest, ServiceResponse},
+  http::Method,
+  web::Data,
+  HttpRequest, HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+use sp_core::{ed25519, Pair};
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  CreateDecryptToken, DecryptToken, DecryptTokenClaims, PublicKey,
+};
+
+use crate::api_keys::authenticated_user;
+use crate::repo::Repository;
+
+pub type AppDecryptTokens = Data<DecryptTokens>;
+
+/// Request header with a decryption token.
+pub const DECRYPT_TOKEN_HEADER: &str = "x-decrypt-token";
+
+/// Default minutes a decryption token is valid for.
+const DEFAULT_TOKEN_MINUTES: u32 = 5;
+/// Default maximum minutes a decryption token can be valid for.
+const DEFAULT_MAX_TOKEN_MINUTES: u32 = 60;
+
+/// Short-lived capability tokens for decrypting an account's (or account asset's) values.
+///
+/// Users authenticated by their API key (`X-Api-Key` header) request a token for an account
+/// they were granted access to (`/admin/users/{user_name}/accounts`) with
+/// `POST /decrypt_tokens`.  With
+/// `DECRYPT_TOKENS=true` the endpoints that decrypt values then require a valid token for
+/// the account (and asset) in the `X-Decrypt-Token` header, so a UI only needs the token to
+/// show a balance.
+///
+/// Tokens are signed with `DECRYPT_TOKEN_KEY` and not stored.  Without a key a random one is
+/// generated at startup, so tokens don't survive a restart.
+pub struct DecryptTokens {
+  pair: ed25519::Pair,
+  required: bool,
+  default_minutes: u32,
+  max_minutes: u32,
+}
+
+impl DecryptTokens {
+  /// Load the config from `DECRYPT_TOKENS`, `DECRYPT_TOKEN_KEY` (secret URI or hex seed),
+  /// `DECRYPT_TOKEN_MINUTES` and `DECRYPT_TOKEN_MAX_MINUTES`.
+  pub fn from_env() -> anyhow::Result<AppDecryptTokens> {
+    let required = std::env::var("DECRYPT_TOKENS")
+      .map(|v| v == "true" || v == "1")
+      .unwrap_or(false);
+    let pair = match std::env::var("DECRYPT_TOKEN_KEY") {
+      Ok(secret) => ed25519::Pair::from_string(&secret, None)
+        .map_err(|err| anyhow::anyhow!("Invalid DECRYPT_TOKEN_KEY: {err:?}"))?,
+      Err(_) => ed25519::Pair::generate().0,
+    };
+    let max_minutes = std::env::var("DECRYPT_TOKEN_MAX_MINUTES")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_MAX_TOKEN_MINUTES);
+    let default_minutes = std::env::var("DECRYPT_TOKEN_MINUTES")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_TOKEN_MINUTES)
+      .min(max_minutes);
+    if required {
+      log::info!("Decryption requires a decryption token");
+    }
+    Ok(Data::new(Self {
+      pair,
+      required,
+      default_minutes,
+      max_minutes,
+    }))
+  }
+
+  /// Issue a token to the authenticated user making the request, if the user may decrypt
+  /// the account.
+  pub async fn issue(
+    &self,
+    repo: &Repository,
+    http_req: &HttpRequest,
+    req: &CreateDecryptToken,
+  ) -> Result<DecryptToken> {
+    let user = authenticated_user(repo, http_req).await?;
+    let account = hex::encode(req.confidential_account.0);
+    let account_id = repo
+      .get_account(&account)
+      .await?
+      .ok_or_else(|| Error::not_found("Account"))?
+      .account_id;
+    if !repo.has_account_access(user.user_id, account_id).await? {
+      return Err(Error::forbidden("User may not decrypt this account"));
+    }
+
+    let minutes = req.minutes.unwrap_or(self.default_minutes);
+    if minutes == 0 || minutes > self.max_minutes {
+      return Err(Error::Other(format!(
+        "Token minutes must be between 1 and {}",
+        self.max_minutes
+      )));
+    }
+    let claims = DecryptTokenClaims {
+      confidential_account: req.confidential_account.clone(),
+      asset_id: req.asset_id,
+      user: user.username,
+      expires_at: chrono::Utc::now().timestamp() + minutes as i64 * 60,
+    };
+    let claims_json = serde_json::to_vec(&claims)?;
+    let signature = self.pair.sign(&claims_json);
+    log::info!(
+      "Decryption token for {account} (asset: {:?}) issued to {}, valid for {minutes} minutes",
+      claims.asset_id,
+      claims.user
+    );
+    Ok(DecryptToken {
+      token: format!("{}.{}", hex::encode(claims_json), hex::encode(signature.0)),
+      claims,
+    })
+  }
+
+  /// Check the token's signature and expiry.
+  fn verify(&self, token: &str) -> Result<DecryptTokenClaims, &'static str> {
+    let (claims_json, signature) = token.split_once('.').ok_or("Invalid decryption token")?;
+    let claims_json = hex::decode(claims_json).map_err(|_| "Invalid decryption token")?;
+    let signature = hex::decode(signature)
+      .ok()
+      .and_then(|sig| <[u8; 64]>::try_from(sig).ok())
+      .map(ed25519::Signature::from_raw)
+      .ok_or("Invalid decryption token")?;
+    if !ed25519::Pair::verify(&signature, &claims_json, &self.pair.public()) {
+      return Err("Invalid decryption token");
+    }
+    let claims: DecryptTokenClaims =
+      serde_json::from_slice(&claims_json).map_err(|_| "Invalid decryption token")?;
+    if claims.expires_at < chrono::Utc::now().timestamp() {
+      return Err("Decryption token expired");
+    }
+    Ok(claims)
+  }
+
+  /// Check the request's decryption token.
+  fn check(
+    &self,
+    req: &ServiceRequest,
+    account: &str,
+    asset_id: Option<&str>,
+  ) -> Result<(), &'static str> {
+    let token = req
+      .headers()
+      .get(DECRYPT_TOKEN_HEADER)
+      .and_then(|val| val.to_str().ok())
+      .ok_or("Missing X-Decrypt-Token header")?;
+    let claims = self.verify(token)?;
+    let account = PublicKey::from_str(account).map_err(|_| "Invalid confidential account")?;
+    let asset_id = asset_id
+      .map(Uuid::parse_str)
+      .transpose()
+      .map_err(|_| "Invalid asset id")?;
+    if !claims.allows(&account, asset_id) {
+      return Err("Decryption token isn't valid for this account or asset");
+    }
+    Ok(())
+  }
+
+  /// Middleware for `wrap_fn`.
+  pub fn middleware<S, B>(
+    tokens: AppDecryptTokens,
+    req: ServiceRequest,
+    srv: &S,
+  ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, actix_web::Error>>
+  where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+  {
+    if tokens.required {
+      if let Some((account, asset_id)) = decrypt_target(&req) {
+        if let Err(err) = tokens.check(&req, &account, asset_id.as_deref()) {
+          let res = HttpResponse::Forbidden().body(err);
+          return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+        }
+      }
+    }
+    let fut = srv.call(req);
+    Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+  }
+}
+
+/// Account and asset of the endpoints that decrypt values with an account's secret key.
+fn decrypt_target(req: &ServiceRequest) -> Option<(String, Option<String>)> {
+  let segments = req.path().split('/').collect::<Vec<_>>();
+  let idx = segments.iter().position(|s| *s == "accounts")?;
+  let account = segments.get(idx + 1)?.to_string();
+  let method = req.method().clone();
+  // Balance updates WebSocket (`/ws/accounts/{account}`).
+  if idx > 0 && segments[idx - 1] == "ws" && segments.len() == idx + 2 && method == Method::GET {
+    return Some((account, None));
+  }
+  match &segments[idx + 2..] {
+    ["decrypt" | "decrypt_with_proof"] if method == Method::POST => Some((account, None)),
+    ["incoming_balances"] if method == Method::GET => Some((account, None)),
+    ["assets", asset_id, "decrypt" | "export" | "reconcile"] if method == Method::POST => {
+      Some((account, Some(asset_id.to_string())))
+    }
+    ["assets", asset_id, "incoming_balance" | "balance_at" | "history" | "reconcile", ..]
+      if method == Method::GET =>
+    {
+      Some((account, Some(asset_id.to_string())))
+    }
+    _ => None,
+  }
+}
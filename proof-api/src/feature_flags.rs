@@ -0,0 +1,112 @@
+use std::sync::RwLock;
+use std::time::Duration;
+
+use actix_web::{
+  body::EitherBody,
+  dev::{Service, ServiceRequest, ServiceResponse},
+  web::Data,
+  HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+
+use polymesh_private_proof_shared::{error::Result, FeatureFlag};
+
+use crate::repo::Repository;
+
+pub type AppFeatureFlags = Data<FeatureFlags>;
+
+/// Default seconds between reloads of the flags.
+const DEFAULT_REFRESH_SECS: u64 = 10;
+
+/// Endpoints disabled at runtime with the `feature_flags` table.
+///
+/// A `/v1` request matching a disabled flag is rejected with `403 Forbidden` and the flag's
+/// reason.  The flags are cached and reloaded every `FEATURE_FLAGS_REFRESH_SECS`
+/// (default 10), so changes made on another instance are picked up.  The
+/// `/admin/feature_flags` endpoints can't be disabled.
+pub struct FeatureFlags {
+  repo: Repository,
+  flags: RwLock<Vec<FeatureFlag>>,
+  refresh: Duration,
+}
+
+impl FeatureFlags {
+  /// Load the flags.  The refresh interval is read from `FEATURE_FLAGS_REFRESH_SECS`.
+  pub async fn from_env(repo: Repository) -> Result<AppFeatureFlags> {
+    let refresh = std::env::var("FEATURE_FLAGS_REFRESH_SECS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_REFRESH_SECS)
+      .max(1);
+    let flags = Self {
+      repo,
+      flags: RwLock::new(Vec::new()),
+      refresh: Duration::from_secs(refresh),
+    };
+    flags.reload().await?;
+    Ok(Data::new(flags))
+  }
+
+  /// Reload the flags from the database.
+  pub async fn reload(&self) -> Result<()> {
+    let flags = self.repo.get_feature_flags().await?;
+    let disabled = flags.iter().filter(|flag| !flag.enabled).count();
+    if disabled > 0 {
+      log::debug!("{disabled} endpoint patterns disabled");
+    }
+    *self.flags.write().expect("Feature flags lock poisoned") = flags;
+    Ok(())
+  }
+
+  /// Reload the flags every `FEATURE_FLAGS_REFRESH_SECS`.
+  pub async fn run(&self) {
+    loop {
+      actix_web::rt::time::sleep(self.refresh).await;
+      if let Err(err) = self.reload().await {
+        log::error!("Failed to reload the feature flags: {err:?}");
+      }
+    }
+  }
+
+  /// The disabled flag matching the `/v1` endpoint, if any.  `path` is the part after `/v1`.
+  pub fn disabled(&self, method: &str, path: &[&str]) -> Option<FeatureFlag> {
+    if let ["admin", "feature_flags", ..] = path {
+      return None;
+    }
+    self
+      .flags
+      .read()
+      .expect("Feature flags lock poisoned")
+      .iter()
+      .find(|flag| !flag.enabled && flag.matches(method, path))
+      .cloned()
+  }
+
+  /// Middleware for `wrap_fn`.
+  pub fn middleware<S, B>(
+    flags: AppFeatureFlags,
+    req: ServiceRequest,
+    srv: &S,
+  ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, actix_web::Error>>
+  where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+  {
+    let segments = req.path().split('/').collect::<Vec<_>>();
+    if let Some(idx) = segments.iter().position(|s| *s == "v1") {
+      let path = segments[idx + 1..]
+        .iter()
+        .copied()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+      if let Some(flag) = flags.disabled(req.method().as_str(), &path) {
+        let reason = flag.reason.as_deref().unwrap_or("disabled by the operator");
+        let res = HttpResponse::Forbidden().body(format!("Endpoint disabled: {reason}"));
+        return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+      }
+    }
+    let fut = srv.call(req);
+    Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+  }
+}
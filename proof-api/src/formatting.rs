@@ -0,0 +1,19 @@
+use polymesh_private_proof_shared::{error::Result, AmountFormatter, FormatQuery};
+
+use crate::repo::Repository;
+
+/// Load the amount formatter for the requested `locale`, if any.
+///
+/// The asset decimals are loaded from the local assets.
+pub async fn amount_formatter(
+  repo: &Repository,
+  query: &FormatQuery,
+) -> Result<Option<AmountFormatter>> {
+  match &query.locale {
+    Some(locale) => {
+      let assets = repo.get_assets().await?;
+      Ok(Some(AmountFormatter::new(locale, &assets)?))
+    }
+    None => Ok(None),
+  }
+}
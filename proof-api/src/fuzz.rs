@@ -0,0 +1,294 @@
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+use reqwest::{Client, Method};
+use serde_json::{json, Value};
+use utoipa::openapi::{
+  path::{Operation, ParameterIn, PathItemType},
+  schema::{KnownFormat, Schema, SchemaFormat, SchemaType},
+  OpenApi, RefOr,
+};
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  FuzzFailure, FuzzReport, FuzzRequest,
+};
+
+pub type AppFuzzer = Data<Fuzzer>;
+
+/// Route of the fuzz endpoint, it doesn't fuzz itself.
+const FUZZ_ROUTE: &str = "/admin/fuzz";
+/// Prefix of the admin routes, only fuzzed when allowed.
+const ADMIN_PREFIX: &str = "/admin/";
+/// Maximum nesting of the generated inputs.
+const MAX_DEPTH: usize = 8;
+/// Decoded length of the oversized hex strings.
+const OVERSIZED_HEX_LEN: usize = 4096;
+/// Maximum length of the response bodies in the report.
+const MAX_BODY_LEN: usize = 1024;
+/// Requests taking longer are reported as failed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Schema-derived boundary input.
+#[derive(Clone, Copy, Debug)]
+enum FuzzInput {
+  /// Zero amounts, ids and (short) hex values.
+  Zero,
+  /// `u64::MAX` for numbers and numeric strings.
+  MaxU64,
+  /// Oversized hex strings.
+  OversizedHex,
+}
+
+impl FuzzInput {
+  const ALL: [Self; 3] = [Self::Zero, Self::MaxU64, Self::OversizedHex];
+
+  fn name(&self) -> &'static str {
+    match self {
+      Self::Zero => "zero",
+      Self::MaxU64 => "max_u64",
+      Self::OversizedHex => "oversized_hex",
+    }
+  }
+
+  fn number(&self) -> Value {
+    match self {
+      Self::Zero => json!(0),
+      Self::MaxU64 => json!(u64::MAX),
+      Self::OversizedHex => json!(1),
+    }
+  }
+
+  fn string(&self, format: Option<&SchemaFormat>) -> String {
+    match (self, format) {
+      (Self::Zero, Some(SchemaFormat::KnownFormat(KnownFormat::Uuid))) => Uuid::nil().to_string(),
+      (Self::Zero, _) => "0x00".to_string(),
+      (Self::MaxU64, _) => u64::MAX.to_string(),
+      (Self::OversizedHex, _) => format!("0x{}", "ff".repeat(OVERSIZED_HEX_LEN)),
+    }
+  }
+
+  /// Generate a value of the schema.
+  ///
+  /// Enums use their first value and arrays have one item, so the inputs get past the
+  /// basic deserialization.
+  fn value(&self, doc: &OpenApi, schema: &RefOr<Schema>, depth: usize) -> Value {
+    if depth > MAX_DEPTH {
+      return Value::Null;
+    }
+    let schema = match schema {
+      RefOr::T(schema) => schema,
+      RefOr::Ref(schema_ref) => {
+        let name = schema_ref
+          .ref_location
+          .trim_start_matches("#/components/schemas/");
+        return match doc
+          .components
+          .as_ref()
+          .and_then(|components| components.schemas.get(name))
+        {
+          Some(schema) => self.value(doc, schema, depth + 1),
+          None => Value::Null,
+        };
+      }
+    };
+    match schema {
+      Schema::Object(object) => {
+        if let Some(value) = object
+          .enum_values
+          .as_ref()
+          .and_then(|values| values.first())
+        {
+          return value.clone();
+        }
+        match object.schema_type {
+          SchemaType::Object => Value::Object(
+            object
+              .properties
+              .iter()
+              .map(|(name, schema)| (name.clone(), self.value(doc, schema, depth + 1)))
+              .collect(),
+          ),
+          SchemaType::Integer | SchemaType::Number => self.number(),
+          SchemaType::String => Value::String(self.string(object.format.as_ref())),
+          SchemaType::Boolean => Value::Bool(false),
+          _ => Value::Null,
+        }
+      }
+      Schema::Array(array) => Value::Array(vec![self.value(doc, &array.items, depth + 1)]),
+      Schema::OneOf(one_of) => match one_of.items.first() {
+        Some(schema) => self.value(doc, schema, depth + 1),
+        None => Value::Null,
+      },
+      Schema::AllOf(all_of) => match all_of.items.first() {
+        Some(schema) => self.value(doc, schema, depth + 1),
+        None => Value::Null,
+      },
+      _ => Value::Null,
+    }
+  }
+
+  /// Generate a path or query parameter.
+  fn param(&self, doc: &OpenApi, schema: Option<&RefOr<Schema>>) -> String {
+    match schema.map(|schema| self.value(doc, schema, 0)) {
+      Some(Value::String(value)) => value,
+      Some(value @ (Value::Bool(_) | Value::Number(_))) => value.to_string(),
+      _ => self.string(None),
+    }
+  }
+}
+
+/// Staging-only fuzzer that sends schema-derived boundary inputs to every documented route
+/// and reports the requests that return a `5xx` status.
+///
+/// The requests are sent over HTTP to `FUZZ_BASE_URL` (default
+/// `http://127.0.0.1:{PORT}/api/v1`), so they go through the same middlewares as client
+/// requests.  Only `GET` routes and the proof verification routes are fuzzed by default,
+/// other routes change state (i.e. create accounts) and must be listed in the request's
+/// `allow_routes`, as must `/admin/` routes.  Only enable it with `FUZZ_ENDPOINT=true` on
+/// staging deployments.
+pub struct Fuzzer {
+  enabled: bool,
+  base_url: String,
+  openapi: OpenApi,
+  client: Client,
+}
+
+impl Fuzzer {
+  /// Load the config from `FUZZ_ENDPOINT` and `FUZZ_BASE_URL`.
+  pub fn from_env(openapi: &OpenApi) -> Result<AppFuzzer> {
+    let enabled = std::env::var("FUZZ_ENDPOINT")
+      .map(|v| v == "true")
+      .unwrap_or(false);
+    let base_url = match std::env::var("FUZZ_BASE_URL") {
+      Ok(url) => url.trim_end_matches('/').to_string(),
+      Err(_) => {
+        let port = std::env::var("PORT").unwrap_or("8080".to_string());
+        format!("http://127.0.0.1:{port}/api/v1")
+      }
+    };
+    if enabled {
+      log::warn!("Fuzz endpoint enabled (staging only), fuzzing {base_url}");
+    }
+    Ok(Data::new(Self {
+      enabled,
+      base_url,
+      openapi: openapi.clone(),
+      client: Client::builder().timeout(REQUEST_TIMEOUT).build()?,
+    }))
+  }
+
+  /// Fuzz the documented routes.
+  pub async fn run(&self, req: &FuzzRequest) -> Result<FuzzReport> {
+    if !self.enabled {
+      return Err(Error::forbidden(
+        "The fuzz endpoint is only for staging deployments (see `FUZZ_ENDPOINT`)",
+      ));
+    }
+    let started = Instant::now();
+    let mut report = FuzzReport::default();
+    for (route, item) in &self.openapi.paths.paths {
+      let selected = req
+        .path_prefix
+        .as_deref()
+        .map_or(true, |prefix| route.starts_with(prefix));
+      if route == FUZZ_ROUTE || !selected {
+        continue;
+      }
+      for (method, operation) in &item.operations {
+        let method = match method {
+          PathItemType::Get => Method::GET,
+          PathItemType::Post => Method::POST,
+          PathItemType::Put => Method::PUT,
+          PathItemType::Patch => Method::PATCH,
+          PathItemType::Delete => Method::DELETE,
+          _ => continue,
+        };
+        if !is_safe(route, &method) && !req.allow_routes.iter().any(|allowed| allowed == route) {
+          report.skipped += 1;
+          continue;
+        }
+        report.routes += 1;
+        for input in FuzzInput::ALL {
+          report.requests += 1;
+          if let Some(failure) = self.send(route, &method, operation, input).await {
+            log::warn!(
+              "Fuzz {method} {route} ({}): {:?} {:?}",
+              failure.input,
+              failure.status,
+              failure.error
+            );
+            report.failures.push(failure);
+          }
+        }
+      }
+    }
+    report.duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+    Ok(report)
+  }
+
+  /// Send one input to the route.  Returns the failure, if it failed.
+  async fn send(
+    &self,
+    route: &str,
+    method: &Method,
+    operation: &Operation,
+    input: FuzzInput,
+  ) -> Option<FuzzFailure> {
+    let mut path = route.to_string();
+    let mut query = Vec::new();
+    for param in operation.parameters.iter().flatten() {
+      let value = input.param(&self.openapi, param.schema.as_ref());
+      match param.parameter_in {
+        ParameterIn::Path => path = path.replace(&format!("{{{}}}", param.name), &value),
+        ParameterIn::Query => query.push((param.name.clone(), value)),
+        _ => (),
+      }
+    }
+    // Path parameters that aren't documented.
+    while let Some(start) = path.find('{') {
+      match path[start..].find('}') {
+        Some(len) => path.replace_range(start..=start + len, &input.string(None)),
+        None => break,
+      }
+    }
+
+    let mut request = self
+      .client
+      .request(method.clone(), format!("{}{path}", self.base_url))
+      .query(&query);
+    let body = operation
+      .request_body
+      .as_ref()
+      .and_then(|body| body.content.get("application/json"));
+    if let Some(body) = body {
+      request = request.json(&input.value(&self.openapi, &body.schema, 0));
+    }
+    let (status, error, body) = match request.send().await {
+      Ok(res) if res.status().is_server_error() => {
+        let status = res.status().as_u16();
+        (Some(status), None, res.text().await.unwrap_or_default())
+      }
+      Ok(_) => return None,
+      Err(err) => (None, Some(err.to_string()), String::new()),
+    };
+    Some(FuzzFailure {
+      method: method.to_string(),
+      path: route.to_string(),
+      input: input.name().to_string(),
+      status,
+      error,
+      body: body.chars().take(MAX_BODY_LEN).collect(),
+    })
+  }
+}
+
+/// Routes fuzzed without being allowed: `GET` routes and the proof verification routes,
+/// except for the admin routes.
+fn is_safe(route: &str, method: &Method) -> bool {
+  if route.starts_with(ADMIN_PREFIX) {
+    return false;
+  }
+  *method == Method::GET || (*method == Method::POST && route.ends_with("verify"))
+}
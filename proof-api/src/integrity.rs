@@ -0,0 +1,135 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actix_web::web::Data;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  CorruptAccount, SecretIntegrityReport, SecretProblem,
+};
+
+use crate::repo::Repository;
+
+pub type AppSecretIntegrity = Data<SecretIntegrity>;
+
+/// Default seconds between checks.
+const DEFAULT_CHECK_INTERVAL: u64 = 3600;
+
+/// Checks the stored account secrets in the background.
+///
+/// Corrupt secrets (that don't decode or don't belong to the account's public key) are
+/// reported by the admin endpoint and metrics, instead of being found when the account
+/// is needed for a settlement.
+pub struct SecretIntegrity {
+  interval: Option<Duration>,
+  canary: bool,
+  report: Mutex<SecretIntegrityReport>,
+}
+
+impl SecretIntegrity {
+  /// Load the config from `SECRET_CHECK_INTERVAL` and `SECRET_CHECK_CANARY`.
+  pub fn from_env() -> AppSecretIntegrity {
+    let interval = std::env::var("SECRET_CHECK_INTERVAL")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_CHECK_INTERVAL);
+    let canary = std::env::var("SECRET_CHECK_CANARY")
+      .map(|v| v == "true" || v == "1")
+      .unwrap_or(false);
+    Data::new(Self {
+      interval: (interval > 0).then(|| Duration::from_secs(interval)),
+      canary,
+      report: Default::default(),
+    })
+  }
+
+  /// Time between checks, `None` if the periodic checks are disabled.
+  pub fn interval(&self) -> Option<Duration> {
+    self.interval
+  }
+
+  /// Periodically check the secrets.  Returns at once if the checks are disabled.
+  pub async fn run(&self, repo: Repository) {
+    let interval = match self.interval {
+      Some(interval) => interval,
+      None => return,
+    };
+    loop {
+      if let Err(err) = self.check(&repo).await {
+        log::error!("Failed to check account secrets: {err:?}");
+      }
+      actix_web::rt::time::sleep(interval).await;
+    }
+  }
+
+  /// Check all account secrets now.
+  pub async fn check(&self, repo: &Repository) -> Result<SecretIntegrityReport> {
+    let started_at = chrono::Utc::now().naive_utc();
+    let mut loaded = Vec::new();
+    let mut unreadable = Vec::new();
+    for (confidential_account, res) in repo.get_accounts_with_secret().await? {
+      match res {
+        Ok(account) => loaded.push(account),
+        Err(err) => {
+          log::error!(
+            "Account 0x{} has an unreadable secret: {err:?}",
+            hex::encode(&confidential_account)
+          );
+          unreadable.push(CorruptAccount {
+            confidential_account,
+            problem: SecretProblem::Unreadable,
+          });
+        }
+      }
+    }
+    // Skip accounts with their secret key in escrow.
+    let (escrowed, accounts): (Vec<_>, Vec<_>) = loaded
+      .into_iter()
+      .partition(|account| account.escrowed && account.secret_key.is_empty());
+    let checked = (accounts.len() + unreadable.len()) as u64;
+    let canary = self.canary;
+    // Canary decryption is CPU bound.
+    let mut corrupt = actix_web::rt::task::spawn_blocking(move || {
+      accounts
+        .iter()
+        .filter_map(|account| {
+          let problem = account.check_integrity(canary)?;
+          Some(CorruptAccount {
+            confidential_account: account.confidential_account.clone(),
+            problem,
+          })
+        })
+        .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|err| Error::Other(format!("Secret integrity check failed: {err:?}")))?;
+
+    for account in &corrupt {
+      log::error!(
+        "Account 0x{} has a corrupt secret: {:?}",
+        hex::encode(&account.confidential_account),
+        account.problem
+      );
+    }
+    corrupt.extend(unreadable);
+    let report = SecretIntegrityReport {
+      checked,
+      escrowed: escrowed.len() as u64,
+      corrupt,
+      canary,
+      started_at: Some(started_at),
+      completed_at: Some(chrono::Utc::now().naive_utc()),
+    };
+    *self.report.lock().expect("Secret integrity lock poisoned") = report.clone();
+    Ok(report)
+  }
+
+  /// Report of the last check.
+  pub fn last_report(&self) -> SecretIntegrityReport {
+    self
+      .report
+      .lock()
+      .expect("Secret integrity lock poisoned")
+      .clone()
+  }
+}
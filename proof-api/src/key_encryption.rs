@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::{
+  aead::{Aead, KeyInit, Payload},
+  ChaCha20Poly1305, Key, Nonce,
+};
+use rand::RngCore;
+use reqwest::{header, Client, Url};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use polymesh_private_proof_shared::error::{Error, Result};
+
+/// Prefix of encrypted secret keys.  Plaintext Elgamal secret keys are always 32 bytes.
+const ENVELOPE_MAGIC: &[u8] = b"KEv1";
+const NONCE_LEN: usize = 12;
+/// Default Vault transit key name.
+const DEFAULT_VAULT_KEY: &str = "confidential-accounts";
+
+pub type DataKey = Zeroizing<[u8; 32]>;
+
+/// Key encryption key (KEK) for envelope encryption of the accounts' secret keys.
+///
+/// Each secret key is encrypted with its own data key, which is wrapped by the KEK and
+/// stored next to the encrypted secret key.
+#[async_trait]
+pub trait KeyEncryption: Send + Sync + 'static {
+  /// Generate a data key for the account.  Returns the plaintext and wrapped data key.
+  async fn generate_data_key(&self, account: &[u8]) -> Result<(DataKey, Vec<u8>)>;
+
+  /// Unwrap the account's data key.
+  async fn unwrap_data_key(&self, account: &[u8], wrapped: &[u8]) -> Result<DataKey>;
+}
+
+/// Load the key encryption configured by `KEY_ENCRYPTION`: `none` (default), `local` (master
+/// key from `KEY_ENCRYPTION_MASTER_KEY` or the `KEY_ENCRYPTION_MASTER_KEY_FILE` keyfile) or
+/// `vault` (transit key `KEY_ENCRYPTION_VAULT_KEY` at `VAULT_TRANSIT_URL`, using
+/// `VAULT_TOKEN`).
+pub fn key_encryption_from_env() -> Result<Option<Arc<dyn KeyEncryption>>> {
+  let mode = std::env::var("KEY_ENCRYPTION").unwrap_or_default();
+  let enc: Arc<dyn KeyEncryption> = match mode.as_str() {
+    "" | "none" => return Ok(None),
+    "local" => {
+      let master_key = match std::env::var("KEY_ENCRYPTION_MASTER_KEY") {
+        Ok(key) => Zeroizing::new(key),
+        Err(_) => {
+          let path = std::env::var("KEY_ENCRYPTION_MASTER_KEY_FILE").map_err(|_| {
+            Error::other("KEY_ENCRYPTION_MASTER_KEY or KEY_ENCRYPTION_MASTER_KEY_FILE is required")
+          })?;
+          Zeroizing::new(std::fs::read_to_string(path)?)
+        }
+      };
+      Arc::new(LocalMasterKey::from_hex(master_key.trim())?)
+    }
+    "vault" => {
+      let base = std::env::var("VAULT_TRANSIT_URL")
+        .map_err(|_| Error::other("VAULT_TRANSIT_URL is required"))?;
+      let token =
+        std::env::var("VAULT_TOKEN").map_err(|_| Error::other("VAULT_TOKEN is required"))?;
+      let key =
+        std::env::var("KEY_ENCRYPTION_VAULT_KEY").unwrap_or_else(|_| DEFAULT_VAULT_KEY.to_string());
+      Arc::new(VaultTransitKey::new(&base, &token, &key)?)
+    }
+    _ => return Err(Error::Other(format!("Unknown KEY_ENCRYPTION mode: {mode}"))),
+  };
+  log::info!("Secret keys are encrypted at rest: {mode}");
+  Ok(Some(enc))
+}
+
+/// Is the stored secret key encrypted.
+pub fn is_encrypted(stored: &[u8]) -> bool {
+  stored.len() > 32 && stored.starts_with(ENVELOPE_MAGIC)
+}
+
+/// Encrypt an account's secret key.
+///
+/// Format: `magic || wrapped key length (u16 BE) || wrapped key || nonce || ciphertext`.
+pub async fn seal_secret_key(
+  enc: &dyn KeyEncryption,
+  account: &[u8],
+  secret_key: &[u8],
+) -> Result<Vec<u8>> {
+  let (data_key, wrapped) = enc.generate_data_key(account).await?;
+  let wrapped_len =
+    u16::try_from(wrapped.len()).map_err(|_| Error::other("Wrapped data key is too large"))?;
+  let mut sealed = ENVELOPE_MAGIC.to_vec();
+  sealed.extend(wrapped_len.to_be_bytes());
+  sealed.extend(wrapped);
+  sealed.extend(seal(&data_key, account, secret_key)?);
+  Ok(sealed)
+}
+
+/// Decrypt an account's secret key.
+pub async fn open_secret_key(
+  enc: &dyn KeyEncryption,
+  account: &[u8],
+  sealed: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+  let invalid = || Error::other("Invalid encrypted secret key");
+  let rest = sealed.strip_prefix(ENVELOPE_MAGIC).ok_or_else(invalid)?;
+  if rest.len() < 2 {
+    return Err(invalid());
+  }
+  let (wrapped_len, rest) = rest.split_at(2);
+  let wrapped_len = u16::from_be_bytes([wrapped_len[0], wrapped_len[1]]) as usize;
+  if rest.len() < wrapped_len {
+    return Err(invalid());
+  }
+  let (wrapped, sealed_key) = rest.split_at(wrapped_len);
+  let data_key = enc.unwrap_data_key(account, wrapped).await?;
+  open(&data_key, account, sealed_key)
+}
+
+/// Encrypt with ChaCha20-Poly1305.  The account is the associated data, so an encrypted
+/// key can't be moved to another account.  Returns `nonce || ciphertext`.
+fn seal(key: &[u8; 32], account: &[u8], msg: &[u8]) -> Result<Vec<u8>> {
+  let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+  let mut nonce = [0u8; NONCE_LEN];
+  rand::thread_rng().fill_bytes(&mut nonce);
+  let mut sealed = nonce.to_vec();
+  sealed.extend(
+    cipher
+      .encrypt(Nonce::from_slice(&nonce), Payload { msg, aad: account })
+      .map_err(|_| Error::other("Failed to encrypt secret key"))?,
+  );
+  Ok(sealed)
+}
+
+fn open(key: &[u8; 32], account: &[u8], sealed: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+  if sealed.len() < NONCE_LEN {
+    return Err(Error::other("Invalid encrypted secret key"));
+  }
+  let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+  let (nonce, msg) = sealed.split_at(NONCE_LEN);
+  let plaintext = cipher
+    .decrypt(Nonce::from_slice(nonce), Payload { msg, aad: account })
+    .map_err(|_| Error::other("Failed to decrypt secret key (wrong key encryption key?)"))?;
+  Ok(Zeroizing::new(plaintext))
+}
+
+fn random_data_key() -> DataKey {
+  let mut data_key = Zeroizing::new([0u8; 32]);
+  rand::thread_rng().fill_bytes(data_key.as_mut());
+  data_key
+}
+
+fn to_data_key(key: &[u8]) -> Result<DataKey> {
+  let key: [u8; 32] = key
+    .try_into()
+    .map_err(|_| Error::other("Data keys must be 32 bytes"))?;
+  Ok(Zeroizing::new(key))
+}
+
+/// Local master key (32 bytes).
+pub struct LocalMasterKey {
+  master_key: DataKey,
+}
+
+impl LocalMasterKey {
+  pub fn new(master_key: [u8; 32]) -> Self {
+    Self {
+      master_key: Zeroizing::new(master_key),
+    }
+  }
+
+  /// Master key as hex (with or without `0x` prefix).
+  pub fn from_hex(master_key: &str) -> Result<Self> {
+    let master_key = master_key.strip_prefix("0x").unwrap_or(master_key);
+    let master_key = Zeroizing::new(
+      hex::decode(master_key).map_err(|_| Error::other("Invalid hex encoded master key"))?,
+    );
+    Ok(Self {
+      master_key: to_data_key(&master_key)?,
+    })
+  }
+}
+
+#[async_trait]
+impl KeyEncryption for LocalMasterKey {
+  async fn generate_data_key(&self, account: &[u8]) -> Result<(DataKey, Vec<u8>)> {
+    let data_key = random_data_key();
+    let wrapped = seal(&self.master_key, account, data_key.as_slice())?;
+    Ok((data_key, wrapped))
+  }
+
+  async fn unwrap_data_key(&self, account: &[u8], wrapped: &[u8]) -> Result<DataKey> {
+    let data_key = open(&self.master_key, account, wrapped)?;
+    to_data_key(&data_key)
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultResponse<T> {
+  #[serde(default)]
+  data: Option<T>,
+  #[serde(default)]
+  errors: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct DataKeyRequest {
+  /// Base64 encoded account, bound to the data key.
+  context: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataKeyResponse {
+  plaintext: String,
+  ciphertext: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DecryptRequest<'a> {
+  ciphertext: &'a str,
+  context: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DecryptResponse {
+  plaintext: String,
+}
+
+/// Vault transit key.  Data keys are generated and unwrapped by Vault, the KEK never leaves
+/// Vault.
+///
+/// The transit key must have `derived=true`, so each account's data key is bound to the
+/// account.  Unwrapped data keys are cached in memory to avoid a Vault request for every
+/// proof.
+pub struct VaultTransitKey {
+  client: Client,
+  datakey_url: Url,
+  decrypt_url: Url,
+  cache: RwLock<HashMap<Vec<u8>, DataKey>>,
+}
+
+impl VaultTransitKey {
+  pub fn new(base: &str, token: &str, key: &str) -> Result<Self> {
+    let base = Url::parse(&format!("{}/", base.trim_end_matches('/')))?;
+    let mut headers = header::HeaderMap::new();
+    headers.insert("X-Vault-Token", header::HeaderValue::from_str(token)?);
+    let client = Client::builder().default_headers(headers).build()?;
+    Ok(Self {
+      client,
+      datakey_url: base.join(&format!("./datakey/plaintext/{key}"))?,
+      decrypt_url: base.join(&format!("./decrypt/{key}"))?,
+      cache: Default::default(),
+    })
+  }
+
+  async fn request<T: serde::de::DeserializeOwned>(
+    &self,
+    url: &Url,
+    body: &impl Serialize,
+  ) -> Result<T> {
+    let resp: VaultResponse<T> = self
+      .client
+      .post(url.clone())
+      .json(body)
+      .send()
+      .await?
+      .json()
+      .await?;
+    match resp {
+      VaultResponse {
+        errors: Some(errors),
+        ..
+      } => Err(Error::Other(format!("Vault error: {errors:?}"))),
+      VaultResponse {
+        data: Some(data), ..
+      } => Ok(data),
+      VaultResponse { data: None, .. } => Err(Error::other("Empty Vault response")),
+    }
+  }
+
+  fn decode_key(plaintext: &str) -> Result<DataKey> {
+    let key = Zeroizing::new(
+      STANDARD
+        .decode(plaintext)
+        .map_err(|_| Error::other("Invalid data key from Vault"))?,
+    );
+    to_data_key(&key)
+  }
+}
+
+#[async_trait]
+impl KeyEncryption for VaultTransitKey {
+  async fn generate_data_key(&self, account: &[u8]) -> Result<(DataKey, Vec<u8>)> {
+    let req = DataKeyRequest {
+      context: STANDARD.encode(account),
+    };
+    let resp: DataKeyResponse = self.request(&self.datakey_url, &req).await?;
+    let plaintext = Zeroizing::new(resp.plaintext);
+    let data_key = Self::decode_key(&plaintext)?;
+    Ok((data_key, resp.ciphertext.into_bytes()))
+  }
+
+  async fn unwrap_data_key(&self, account: &[u8], wrapped: &[u8]) -> Result<DataKey> {
+    if let Some(data_key) = self
+      .cache
+      .read()
+      .expect("Data key cache poisoned")
+      .get(wrapped)
+    {
+      return Ok(data_key.clone());
+    }
+    let ciphertext =
+      std::str::from_utf8(wrapped).map_err(|_| Error::other("Invalid wrapped data key"))?;
+    let req = DecryptRequest {
+      ciphertext,
+      context: STANDARD.encode(account),
+    };
+    let resp: DecryptResponse = self.request(&self.decrypt_url, &req).await?;
+    let plaintext = Zeroizing::new(resp.plaintext);
+    let data_key = Self::decode_key(&plaintext)?;
+    self
+      .cache
+      .write()
+      .expect("Data key cache poisoned")
+      .insert(wrapped.to_vec(), data_key.clone());
+    Ok(data_key)
+  }
+}
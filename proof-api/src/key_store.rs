@@ -0,0 +1,263 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::{header, Client, Method, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use zeroize::Zeroizing;
+
+use polymesh_private_proof_shared::error::{Error, Result};
+
+/// Prefix of stored key references.  Plaintext Elgamal secret keys are always 32 bytes.
+const KEY_REF_MAGIC: &[u8] = b"KRv1";
+/// Default Vault KV v2 mount.
+const DEFAULT_KV_MOUNT: &str = "secret";
+/// Default path of the secret keys in the KV mount.
+const DEFAULT_KV_PATH: &str = "polymesh-private/accounts";
+
+/// Store of the accounts' secret keys outside of the database.
+///
+/// The database only stores the account's public key and a reference to its secret key.
+#[async_trait]
+pub trait AccountKeyStore: Send + Sync + 'static {
+  /// Store the account's secret key.  Returns the key reference.
+  ///
+  /// Storing the same secret key again succeeds, a different secret key for the account is
+  /// an error.
+  async fn put_secret(&self, account: &[u8], secret_key: &[u8]) -> Result<String>;
+
+  /// Get a secret key by its reference.
+  async fn get_secret(&self, key_ref: &str) -> Result<Zeroizing<Vec<u8>>>;
+
+  /// Delete a secret key (e.g. the account's secret key was split into escrow shares).
+  async fn delete_secret(&self, key_ref: &str) -> Result<()>;
+}
+
+/// Load the account key store configured by `ACCOUNT_KEY_STORE`: `db` (default, secret keys
+/// are stored in the `accounts` table) or `vault_kv` (Vault KV v2 secrets engine mounted at
+/// `ACCOUNT_KEY_STORE_KV_MOUNT` of `VAULT_ADDR`, using `VAULT_TOKEN` and `VAULT_NAMESPACE`).
+pub fn account_key_store_from_env() -> Result<Option<Arc<dyn AccountKeyStore>>> {
+  let mode = std::env::var("ACCOUNT_KEY_STORE").unwrap_or_default();
+  let store: Arc<dyn AccountKeyStore> = match mode.as_str() {
+    "" | "db" => return Ok(None),
+    "vault_kv" => Arc::new(VaultKvStore::from_env()?),
+    _ => return Err(Error::Other(format!("Unknown ACCOUNT_KEY_STORE: {mode}"))),
+  };
+  log::info!("Secret keys are stored in: {mode}");
+  Ok(Some(store))
+}
+
+/// Encode a key reference for the `secret_key` column.
+pub fn encode_key_ref(key_ref: &str) -> Vec<u8> {
+  let mut stored = KEY_REF_MAGIC.to_vec();
+  stored.extend(key_ref.as_bytes());
+  stored
+}
+
+/// The key reference, if the stored secret key is one.
+pub fn decode_key_ref(stored: &[u8]) -> Option<&str> {
+  if stored.len() <= 32 {
+    return None;
+  }
+  stored
+    .strip_prefix(KEY_REF_MAGIC)
+    .and_then(|key_ref| std::str::from_utf8(key_ref).ok())
+}
+
+#[derive(Debug, Deserialize)]
+struct KvResponse<T> {
+  #[serde(default)]
+  data: Option<T>,
+  #[serde(default)]
+  errors: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KvData {
+  data: KvSecret,
+}
+
+#[derive(Debug, Deserialize)]
+struct KvSecret {
+  /// Hex encoded secret key.
+  secret_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KvList {
+  keys: Vec<String>,
+}
+
+/// Vault KV v2 secrets engine.  Each account's secret key is a secret at
+/// `{path}/{public key}`, which is the key reference.
+pub struct VaultKvStore {
+  client: Client,
+  /// `{addr}/v1/{mount}`.
+  mount: Url,
+  path: String,
+}
+
+impl VaultKvStore {
+  pub fn new(
+    addr: &str,
+    token: &str,
+    namespace: Option<&str>,
+    mount: &str,
+    path: &str,
+  ) -> Result<Self> {
+    let mut headers = header::HeaderMap::new();
+    headers.insert("X-Vault-Token", header::HeaderValue::from_str(token)?);
+    if let Some(namespace) = namespace {
+      headers.insert(
+        "X-Vault-Namespace",
+        header::HeaderValue::from_str(namespace)?,
+      );
+    }
+    let client = Client::builder().default_headers(headers).build()?;
+    let mount = Url::parse(&format!(
+      "{}/v1/{}",
+      addr.trim_end_matches('/'),
+      mount.trim_matches('/')
+    ))?;
+    Ok(Self {
+      client,
+      mount,
+      path: path.trim_matches('/').to_string(),
+    })
+  }
+
+  /// Connect to the KV mount `ACCOUNT_KEY_STORE_KV_MOUNT` (default: `secret`) of
+  /// `VAULT_ADDR`, using `VAULT_TOKEN` and `VAULT_NAMESPACE`.
+  pub fn from_env() -> Result<Self> {
+    let addr = std::env::var("VAULT_ADDR").map_err(|_| Error::other("VAULT_ADDR is required"))?;
+    let token =
+      std::env::var("VAULT_TOKEN").map_err(|_| Error::other("VAULT_TOKEN is required"))?;
+    let namespace = std::env::var("VAULT_NAMESPACE")
+      .ok()
+      .filter(|ns| !ns.is_empty());
+    let mount =
+      std::env::var("ACCOUNT_KEY_STORE_KV_MOUNT").unwrap_or_else(|_| DEFAULT_KV_MOUNT.to_string());
+    let path =
+      std::env::var("ACCOUNT_KEY_STORE_KV_PATH").unwrap_or_else(|_| DEFAULT_KV_PATH.to_string());
+    Self::new(&addr, &token, namespace.as_deref(), &mount, &path)
+  }
+
+  /// References of the secret keys directly under `path` (sub-paths are skipped).
+  pub async fn list_secrets(&self, path: &str) -> Result<Vec<String>> {
+    let path = path.trim_matches('/');
+    let resp = self
+      .client
+      .request(Method::from_bytes(b"LIST")?, self.url("metadata", path)?)
+      .send()
+      .await?;
+    if resp.status() == StatusCode::NOT_FOUND {
+      return Ok(Vec::new());
+    }
+    let keys = Self::check_response::<KvList>(resp)
+      .await?
+      .and_then(|resp| resp.data)
+      .map(|list| list.keys)
+      .unwrap_or_default();
+    Ok(
+      keys
+        .into_iter()
+        .filter(|key| !key.ends_with('/'))
+        .map(|key| format!("{path}/{key}"))
+        .collect(),
+    )
+  }
+
+  /// Url of the key's `data` or `metadata`.
+  fn url(&self, kind: &str, key_ref: &str) -> Result<Url> {
+    let mut url = self.mount.clone();
+    url
+      .path_segments_mut()
+      .map_err(|_| Error::other("Invalid Vault address"))?
+      .pop_if_empty()
+      .push(kind)
+      .extend(key_ref.split('/').filter(|s| !s.is_empty()));
+    Ok(url)
+  }
+
+  async fn check_response<T: DeserializeOwned>(
+    resp: reqwest::Response,
+  ) -> Result<Option<KvResponse<T>>> {
+    let status = resp.status();
+    if status == StatusCode::NO_CONTENT {
+      return Ok(None);
+    }
+    let resp: KvResponse<T> = resp.json().await?;
+    match resp.errors {
+      Some(errors) if !errors.is_empty() => Err(Error::Other(format!("Vault error: {errors:?}"))),
+      _ if !status.is_success() => Err(Error::Other(format!("Vault error: {status}"))),
+      _ => Ok(Some(resp)),
+    }
+  }
+}
+
+#[async_trait]
+impl AccountKeyStore for VaultKvStore {
+  async fn put_secret(&self, account: &[u8], secret_key: &[u8]) -> Result<String> {
+    let key_ref = format!("{}/0x{}", self.path, hex::encode(account));
+    let encoded = Zeroizing::new(format!("0x{}", hex::encode(secret_key)));
+    // Check-and-set `0`: never overwrite another secret key.
+    let body = json!({
+      "options": { "cas": 0 },
+      "data": { "secret_key": *encoded },
+    });
+    let resp = self
+      .client
+      .post(self.url("data", &key_ref)?)
+      .json(&body)
+      .send()
+      .await?;
+    if resp.status() != StatusCode::BAD_REQUEST {
+      Self::check_response::<serde_json::Value>(resp).await?;
+      return Ok(key_ref);
+    }
+    let resp: KvResponse<serde_json::Value> = resp.json().await?;
+    let errors = resp.errors.unwrap_or_default();
+    if !errors.iter().any(|err| err.contains("check-and-set")) {
+      return Err(Error::Other(format!("Vault error: {errors:?}")));
+    }
+    // The key is already stored, i.e. by a concurrent call or before a restart.  Only the
+    // same secret key is accepted.
+    let stored = self.get_secret(&key_ref).await?;
+    if stored.as_slice() != secret_key {
+      return Err(Error::Other(format!(
+        "A different secret key is already stored at {key_ref}"
+      )));
+    }
+    Ok(key_ref)
+  }
+
+  async fn get_secret(&self, key_ref: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let resp = self.client.get(self.url("data", key_ref)?).send().await?;
+    if resp.status() == StatusCode::NOT_FOUND {
+      return Err(Error::Other(format!(
+        "Secret key {key_ref} not found in Vault"
+      )));
+    }
+    let secret = Self::check_response::<KvData>(resp)
+      .await?
+      .and_then(|resp| resp.data)
+      .ok_or_else(|| Error::other("Empty Vault response"))?
+      .data;
+    let secret_key = Zeroizing::new(secret.secret_key);
+    let hex_key = secret_key.strip_prefix("0x").unwrap_or(&secret_key);
+    Ok(Zeroizing::new(hex::decode(hex_key).map_err(|_| {
+      Error::other("Invalid secret key from Vault")
+    })?))
+  }
+
+  async fn delete_secret(&self, key_ref: &str) -> Result<()> {
+    // Delete all versions.
+    let resp = self
+      .client
+      .delete(self.url("metadata", key_ref)?)
+      .send()
+      .await?;
+    Self::check_response::<serde_json::Value>(resp).await?;
+    Ok(())
+  }
+}
@@ -1,3 +1,25 @@
+pub mod anomalies;
+pub mod api_keys;
+pub mod approvals;
+pub mod audit_chain;
+pub mod balance_overrides;
+pub mod decrypt_jobs;
+pub mod decrypt_tokens;
+pub mod feature_flags;
+pub mod formatting;
+pub mod fuzz;
 pub mod health;
+pub mod integrity;
+pub mod key_encryption;
+pub mod key_store;
+pub mod limits;
+pub mod position_locks;
+pub mod profile;
+pub mod proof_pools;
+pub mod receipts;
+pub mod replay;
 pub mod repo;
+pub mod screening;
 pub mod v1;
+pub mod valuation;
+pub mod warnings;
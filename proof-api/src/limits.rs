@@ -0,0 +1,122 @@
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+use uuid::Uuid;
+
+use confidential_assets::Balance;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  AccountWithSecret, ApprovalOperation,
+};
+
+use crate::api_keys::API_KEY_HEADER;
+use crate::approvals::Approvals;
+use crate::repo::Repository;
+
+/// Amount usage reserved by `check_amount_limits`.
+///
+/// Release it if the proof isn't generated or used, otherwise the amount stays counted
+/// against the daily limits.
+#[must_use]
+#[derive(Debug)]
+pub struct AmountReservation {
+  usage_id: i64,
+}
+
+impl AmountReservation {
+  /// Give back the reserved amount.
+  pub async fn release(self, repo: &Repository) {
+    if let Err(err) = repo.release_amount_usage(self.usage_id).await {
+      log::error!("Failed to release amount usage {}: {err:?}", self.usage_id);
+    }
+  }
+
+  pub async fn release_all(reservations: Vec<Self>, repo: &Repository) {
+    for reservation in reservations {
+      reservation.release(repo).await;
+    }
+  }
+}
+
+pub enum AmountLimitCheck {
+  /// The amount is reserved, the transfer can go ahead.
+  Reserved(AmountReservation),
+  /// The pending approval to respond with.
+  Pending(HttpResponse),
+}
+
+/// Enforce the account's amount limits before generating a sender proof.
+///
+/// Both the limits of the asset and the account wide limits (all assets) apply.  Transfers
+/// over a limit need an approved `limit_override` (see `Approvals`), so a leaked API
+/// credential can't drain an account at once.
+///
+/// The amount is reserved against the daily limits in the same database statement that
+/// checks them, so concurrent requests can't exceed a limit together.
+pub async fn check_amount_limits<R: Serialize>(
+  repo: &Repository,
+  approvals: &Approvals,
+  http_req: &HttpRequest,
+  account: &AccountWithSecret,
+  asset_id: Option<Uuid>,
+  amount: Balance,
+  request: &R,
+) -> Result<AmountLimitCheck> {
+  let account_id = account.account_id;
+  if let Some(usage_id) = repo
+    .reserve_amount_usage(account_id, asset_id, amount)
+    .await?
+  {
+    return Ok(AmountLimitCheck::Reserved(AmountReservation { usage_id }));
+  }
+
+  // Find the exceeded limit for the error.
+  let confidential_account = format!("0x{}", hex::encode(&account.confidential_account));
+  let limits = repo.get_amount_limits(&confidential_account).await?;
+  let mut exceeded = None;
+  for limit in limits
+    .iter()
+    .filter(|limit| limit.asset_id.is_none() || limit.asset_id == asset_id)
+  {
+    let daily_used = match limit.max_daily {
+      Some(_) => {
+        repo
+          .get_daily_amount_usage(account_id, limit.asset_id)
+          .await?
+      }
+      None => 0,
+    };
+    exceeded = limit.check(amount, daily_used);
+    if exceeded.is_some() {
+      break;
+    }
+  }
+  let reason = exceeded.unwrap_or_else(|| format!("amount {amount} is over the daily limit"));
+
+  let target = match asset_id {
+    Some(asset_id) => format!("{confidential_account}/{asset_id}"),
+    None => confidential_account,
+  };
+  log::warn!("Amount limit of {target}: {reason}");
+  if http_req.headers().get(API_KEY_HEADER).is_none() {
+    return Err(Error::Forbidden(format!(
+      "Amount limit exceeded, {reason}.  Needs an approved `limit_override`"
+    )));
+  }
+  let pending = approvals
+    .authorize(
+      repo,
+      http_req,
+      ApprovalOperation::LimitOverride,
+      &target,
+      request,
+    )
+    .await?;
+  match pending {
+    Some(pending) => Ok(AmountLimitCheck::Pending(pending)),
+    None => {
+      let usage_id = repo.add_amount_usage(account_id, asset_id, amount).await?;
+      Ok(AmountLimitCheck::Reserved(AmountReservation { usage_id }))
+    }
+  }
+}
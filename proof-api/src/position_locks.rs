@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  PositionLockMode,
+};
+
+use crate::repo::Repository;
+
+/// How often a deferred request checks the locks again, so it is released early when its
+/// lock is removed.
+const DEFER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Enforce the account's position locks before issuing a sender proof.
+///
+/// Operators lock an account asset (or all of the account's assets) for a time window, e.g.
+/// during a NAV strike.  During a `reject` lock the request fails right away.  During a
+/// `defer` lock the request waits until the window ends.  Without an `asset_id` all of the
+/// account's locks apply.
+pub async fn check_position_locks(
+  repo: &Repository,
+  account_id: i64,
+  asset_id: Option<Uuid>,
+) -> Result<()> {
+  let mut deferred = false;
+  while let Some(lock) = repo.get_active_position_lock(account_id, asset_id).await? {
+    let reason = lock.reason.as_deref().unwrap_or("position lock");
+    match lock.mode()? {
+      PositionLockMode::Reject => {
+        return Err(Error::Forbidden(format!(
+          "Position is locked until {} UTC ({reason})",
+          lock.ends_at
+        )));
+      }
+      PositionLockMode::Defer => {
+        if !deferred {
+          log::info!(
+            "Sender proof deferred by position lock {} until {} UTC ({reason})",
+            lock.lock_id,
+            lock.ends_at
+          );
+          deferred = true;
+        }
+        let remaining = (lock.ends_at - chrono::Utc::now().naive_utc())
+          .to_std()
+          .unwrap_or_default();
+        actix_web::rt::time::sleep(remaining.min(DEFER_POLL_INTERVAL)).await;
+      }
+    }
+  }
+  Ok(())
+}
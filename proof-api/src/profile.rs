@@ -0,0 +1,139 @@
+use actix_web::{
+  body::EitherBody,
+  dev::{Service, ServiceRequest, ServiceResponse},
+  http::Method,
+  web::Data,
+  HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+
+pub type AppDeploymentProfile = Data<DeploymentProfile>;
+
+/// Deployment profile, selects which endpoints are enabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeploymentProfile {
+  /// All endpoints.
+  #[default]
+  Full,
+  /// Compliance-only deployments that only hold auditor/mediator keys.
+  ///
+  /// Only the endpoints to manage the keys and signers, verify sender proofs as an auditor,
+  /// affirm settlement legs as a mediator and report on audited legs are enabled.  Everything
+  /// that generates sender/burn proofs or decrypts account balances is rejected with
+  /// `403 Forbidden`.
+  Mediator,
+}
+
+impl DeploymentProfile {
+  /// Load the profile from `DEPLOYMENT_PROFILE` (`full` or `mediator`).
+  pub fn from_env() -> anyhow::Result<AppDeploymentProfile> {
+    let profile = match std::env::var("DEPLOYMENT_PROFILE").as_deref() {
+      Ok("full") | Err(_) => Self::Full,
+      Ok("mediator") => Self::Mediator,
+      Ok(profile) => return Err(anyhow::anyhow!("Unknown DEPLOYMENT_PROFILE: {profile:?}")),
+    };
+    if profile != Self::Full {
+      log::info!("Deployment profile: {profile:?}");
+    }
+    Ok(Data::new(profile))
+  }
+
+  /// Is the `/v1` endpoint enabled.  `path` is the part after `/v1`.
+  pub fn allows(&self, method: &Method, path: &[&str]) -> bool {
+    match self {
+      Self::Full => true,
+      Self::Mediator => mediator_allows(method, path),
+    }
+  }
+
+  /// Middleware for `wrap_fn`.
+  pub fn middleware<S, B>(
+    profile: AppDeploymentProfile,
+    req: ServiceRequest,
+    srv: &S,
+  ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, actix_web::Error>>
+  where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+  {
+    let segments = req.path().split('/').collect::<Vec<_>>();
+    // Only the `/v1` endpoints are restricted, health checks and metrics are always enabled.
+    if let Some(idx) = segments.iter().position(|s| *s == "v1") {
+      if !profile.allows(req.method(), &segments[idx + 1..]) {
+        let res = HttpResponse::Forbidden().body(format!(
+          "Not available in the {profile:?} deployment profile"
+        ));
+        return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+      }
+    }
+    let fut = srv.call(req);
+    Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+  }
+}
+
+/// Endpoints of the mediator profile.
+fn mediator_allows(method: &Method, path: &[&str]) -> bool {
+  let read = method == Method::GET;
+  match path {
+    // Operations.  Admin routes that touch balances or secret keys (escrow, imports,
+    // compromised accounts, fuzzing) are not listed.
+    ["admin", "users", _, "api_keys" | "accounts", ..] => true,
+    ["admin", "feature_flags", ..] | ["admin", "maintenance"] => true,
+    ["admin", "config", "reload"] => true,
+    ["admin", "webhooks", ..] => true,
+    ["admin", "accounts", "integrity", ..] => true,
+    ["admin", "accounts", _, "lock" | "unlock"] => true,
+    ["admin", "nodes" | "leases" | "scheduled_jobs" | "anomalies"] => read,
+    ["admin", "audit_log", "verify"] => read,
+    ["signers", ..] | ["approvals", ..] | ["jobs", ..] => true,
+    ["receipts", ..] | ["decryption_proofs", "verify"] => true,
+    // Auditor/mediator keys.
+    ["accounts"] => true,
+    ["accounts", _] => read,
+    ["accounts", _, "auditor_verify"] => true,
+    // Audited-leg reporting.
+    ["assets"] | ["assets", _] => read,
+    ["assets", _, "audit_report"] => true,
+    // Mediator affirmations.
+    ["tx", "accounts", _, "init_account" | "identity" | "mediator_affirm_leg"] => true,
+    ["tx", "accounts", _, "reject_transaction" | "withdraw_affirmation"] => true,
+    ["tx", "accounts", _, "pending_transactions"] => read,
+    ["tx", "settlements", ..] | ["tx", "settlement_legs"] | ["tx", "transactions", _] => read,
+    ["tx", "transactions", _, "legs", _, "verify" | "auditor_verify"] => true,
+    ["tx", "jobs", ..] => read,
+    ["stats", ..] | ["selftest"] | ["events", "stream"] | ["watcher", "status"] => read,
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn allows(method: Method, path: &str) -> bool {
+    let segments = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    DeploymentProfile::Mediator.allows(&method, &segments)
+  }
+
+  #[test]
+  fn mediator_rejects_secret_admin_routes() {
+    assert!(!allows(
+      Method::POST,
+      "/admin/accounts/0x01/escrow/reassemble"
+    ));
+    assert!(!allows(Method::POST, "/admin/accounts/0x01/escrow"));
+    assert!(!allows(Method::POST, "/admin/import/accounts"));
+    assert!(!allows(Method::POST, "/admin/fuzz"));
+    assert!(!allows(Method::POST, "/admin/accounts/0x01/compromised"));
+  }
+
+  #[test]
+  fn mediator_allows_operational_admin_routes() {
+    assert!(allows(Method::POST, "/admin/users/alice/api_keys"));
+    assert!(allows(Method::GET, "/admin/feature_flags"));
+    assert!(allows(Method::POST, "/admin/maintenance"));
+    assert!(allows(Method::GET, "/admin/nodes"));
+    assert!(!allows(Method::POST, "/admin/nodes"));
+  }
+}
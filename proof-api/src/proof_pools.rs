@@ -0,0 +1,301 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use actix_web::web::Data;
+use codec::Encode;
+
+use confidential_assets::{elgamal::CipherText, Balance, ElgamalPublicKey};
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  AccountAssetWithSecret, CreateProofPool, PooledProof, ProofPool, ProofPoolStatus,
+};
+
+use crate::repo::Repository;
+
+pub type AppProofPools = Data<ProofPools>;
+
+/// Default seconds until unused pooled proofs expire.
+const DEFAULT_POOL_TTL: u64 = 3600;
+/// Default maximum number of proofs in a pool.
+const DEFAULT_MAX_POOL_SIZE: u32 = 100;
+/// Seconds between checks for expired pools.
+pub const EXPIRY_CHECK_INTERVAL: u64 = 30;
+
+/// Pools of pre-generated sender proofs.
+///
+/// Generating a sender proof takes too long for latency-sensitive trading.  Operators can
+/// pre-generate a pool of proofs for a fixed amount to a known receiver in the background,
+/// which the sender proof and `sender_affirm_leg` endpoints then use instantly.
+///
+/// Each proof is generated from the balance left by the previous one, so the proofs must be
+/// used in order and the pool reserves their amounts from the tracked balance.  Other
+/// sender proofs and burns of the account asset are refused while its pool is active.
+/// Unused proofs are dropped, and their amounts added back to the tracked balance, when the
+/// pool expires or is released.
+pub struct ProofPools {
+  repo: Repository,
+  max_size: u32,
+}
+
+impl ProofPools {
+  /// Load the maximum pool size from `PROOF_POOL_MAX_SIZE`.
+  pub fn from_env(repo: Repository) -> AppProofPools {
+    let max_size = std::env::var("PROOF_POOL_MAX_SIZE")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_MAX_POOL_SIZE);
+    Data::new(Self { repo, max_size })
+  }
+
+  /// Periodically release expired pools.
+  pub async fn run(&self) {
+    loop {
+      if let Err(err) = self.release_expired().await {
+        log::error!("Failed to release expired proof pools: {err:?}");
+      }
+      actix_web::rt::time::sleep(Duration::from_secs(EXPIRY_CHECK_INTERVAL)).await;
+    }
+  }
+
+  /// Release the expired pools now.
+  pub async fn release_expired(&self) -> Result<()> {
+    for pool in self.repo.get_expired_proof_pools().await? {
+      log::info!("Proof pool {} expired", pool.pool_id);
+      self
+        .release_pool(pool.pool_id, ProofPoolStatus::Expired, None)
+        .await?;
+    }
+    Ok(())
+  }
+
+  /// Create a pool and start generating its proofs in the background.
+  pub async fn create(
+    &self,
+    account_asset: AccountAssetWithSecret,
+    req: &CreateProofPool,
+  ) -> Result<ProofPool> {
+    if req.amount == 0 {
+      return Err(Error::other("The proof amount must be more than zero"));
+    }
+    if req.size == 0 || req.size > self.max_size {
+      return Err(Error::Other(format!(
+        "The pool size must be between 1 and {}",
+        self.max_size
+      )));
+    }
+    let reserved = req.amount.saturating_mul(req.size as u64);
+    if reserved > account_asset.balance as u64 {
+      return Err(Error::other("Insufficient balance for the proof pool"));
+    }
+    let receiver = req.receiver()?;
+    let auditors = req.auditors()?;
+    self.ensure_no_pool(account_asset.account_asset_id).await?;
+
+    let expires_in = req.expires_in.unwrap_or(DEFAULT_POOL_TTL);
+    let pool = self
+      .repo
+      .create_proof_pool(
+        account_asset.account_asset_id,
+        account_asset.asset_id,
+        req,
+        expires_in,
+      )
+      .await?;
+
+    let repo = self.repo.clone();
+    let pool_id = pool.pool_id;
+    let size = pool.size;
+    let amount = req.amount;
+    actix_web::rt::spawn(async move {
+      let res = generate_proofs(
+        &repo,
+        pool_id,
+        size,
+        account_asset,
+        receiver,
+        auditors,
+        amount,
+      )
+      .await;
+      if let Err(err) = res {
+        log::error!("Failed to generate proof pool {pool_id}: {err:?}");
+        let error = err.to_string();
+        if let Err(err) = release_pool(&repo, pool_id, ProofPoolStatus::Failed, Some(&error)).await
+        {
+          log::error!("Failed to release proof pool {pool_id}: {err:?}");
+        }
+      }
+    });
+    Ok(pool)
+  }
+
+  /// Returns an error if the account asset has an active pool.
+  pub async fn ensure_no_pool(&self, account_asset_id: i64) -> Result<()> {
+    if let Some(pool) = self.repo.get_active_proof_pool(account_asset_id).await? {
+      return Err(Error::Other(format!(
+        "The account asset has an active proof pool ({}), release it first",
+        pool.pool_id
+      )));
+    }
+    Ok(())
+  }
+
+  /// Reserve the next proof of the account asset's active pool for this transfer.
+  ///
+  /// Returns `None` if the account asset doesn't have an active pool.  If `enc_balance`
+  /// (the sender's current balance) is given, it must be the balance the proof was
+  /// generated from.  The reserved proof must be finished with [`Self::finish`].
+  pub async fn take(
+    &self,
+    account_asset_id: i64,
+    receiver: &ElgamalPublicKey,
+    auditors: &BTreeSet<ElgamalPublicKey>,
+    amount: Balance,
+    enc_balance: Option<&CipherText>,
+  ) -> Result<Option<PooledProof>> {
+    let pool = match self.repo.get_active_proof_pool(account_asset_id).await? {
+      Some(pool) => pool,
+      None => return Ok(None),
+    };
+    if !pool.matches(receiver, auditors, amount) {
+      return Err(Error::Other(format!(
+        "The account asset has an active proof pool ({}) for a different transfer",
+        pool.pool_id
+      )));
+    }
+    let proof = self
+      .repo
+      .reserve_pooled_proof(pool.pool_id)
+      .await?
+      .ok_or_else(|| Error::other("No pooled proofs are ready yet"))?;
+    if let Some(enc_balance) = enc_balance {
+      if proof.enc_balance != enc_balance.encode() {
+        self.finish(&proof, false).await?;
+        return Err(Error::Other(format!(
+          "The sender's balance has changed since proof pool ({}) was generated, release it",
+          pool.pool_id
+        )));
+      }
+    }
+    Ok(Some(proof))
+  }
+
+  /// Mark a reserved proof as used, or make it available again if it wasn't used.
+  pub async fn finish(&self, proof: &PooledProof, used: bool) -> Result<()> {
+    if let Some(pool) = self
+      .repo
+      .finish_pooled_proof(proof.pool_proof_id, used)
+      .await?
+    {
+      // The pool was released while the proof was reserved.
+      if !used && !pool.is_active() {
+        release_unused(&self.repo, &pool).await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Release the pool's unused proofs.  Returns `None` if the pool isn't active.
+  pub async fn release_pool(
+    &self,
+    pool_id: i64,
+    status: ProofPoolStatus,
+    error: Option<&str>,
+  ) -> Result<Option<ProofPool>> {
+    release_pool(&self.repo, pool_id, status, error).await
+  }
+}
+
+async fn generate_proofs(
+  repo: &Repository,
+  pool_id: i64,
+  size: i64,
+  mut account_asset: AccountAssetWithSecret,
+  receiver: ElgamalPublicKey,
+  auditors: BTreeSet<ElgamalPublicKey>,
+  amount: Balance,
+) -> Result<()> {
+  for seq in 0..size {
+    let enc_balance = account_asset.enc_balance()?;
+    // Proof generation is CPU bound.
+    let (update, proof) = {
+      let account_asset = account_asset.clone();
+      let auditors = auditors.clone();
+      actix_web::rt::task::spawn_blocking(move || {
+        account_asset.create_send_proof(None, receiver, auditors, amount)
+      })
+      .await
+      .map_err(|err| Error::Other(format!("Proof generation task failed: {err:?}")))??
+    };
+    let pooled = PooledProof {
+      pool_id,
+      seq,
+      proof: proof.as_bytes(),
+      enc_balance: enc_balance.encode(),
+      enc_amount: proof.sender_amount().encode(),
+      status: "available".to_string(),
+      ..Default::default()
+    };
+
+    // Reserve the amount from the tracked balance.
+    let update = update.with_source(format!("proof_pool {pool_id}"));
+    let updated = repo.update_account_asset(&update).await?;
+    match repo.add_pooled_proof(&pooled).await? {
+      Some(_) => {
+        account_asset.balance = updated.balance;
+        account_asset.enc_balance = updated.enc_balance;
+      }
+      None => {
+        // The pool was released while generating, add the amount back.
+        if let Some(pool) = repo.get_proof_pool(pool_id).await? {
+          let update = pool
+            .release_update(&updated, &[pooled])?
+            .with_source(format!("proof_pool {pool_id}"));
+          repo.update_account_asset(&update).await?;
+        }
+        break;
+      }
+    }
+  }
+  Ok(())
+}
+
+async fn release_pool(
+  repo: &Repository,
+  pool_id: i64,
+  status: ProofPoolStatus,
+  error: Option<&str>,
+) -> Result<Option<ProofPool>> {
+  let pool = match repo
+    .set_proof_pool_status(pool_id, status.as_str(), error)
+    .await?
+  {
+    Some(pool) => pool,
+    None => return Ok(None),
+  };
+  release_unused(repo, &pool).await?;
+  Ok(Some(pool))
+}
+
+/// Drop the pool's available proofs and add their amounts back to the tracked balance.
+async fn release_unused(repo: &Repository, pool: &ProofPool) -> Result<()> {
+  let proofs = repo.release_pooled_proofs(pool.pool_id).await?;
+  if proofs.is_empty() {
+    return Ok(());
+  }
+  let account_asset = repo
+    .get_account_asset_by_id(pool.account_asset_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Account Asset"))?;
+  let update = pool
+    .release_update(&account_asset, &proofs)?
+    .with_source(format!("proof_pool {}", pool.pool_id));
+  repo.update_account_asset(&update).await?;
+  log::info!(
+    "Released {} unused proofs of proof pool {}",
+    proofs.len(),
+    pool.pool_id
+  );
+  Ok(())
+}
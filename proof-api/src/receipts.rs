@@ -0,0 +1,103 @@
+use actix_web::{http::header, web::Data, HttpRequest, HttpResponse};
+use serde::Serialize;
+use sp_core::{ed25519, hashing::blake2_256, Pair};
+
+use polymesh_private_proof_shared::{error::Result, Receipt};
+
+pub type AppReceiptSigner = Data<ReceiptSigner>;
+
+/// Response header with the signed receipt (JSON).
+pub const RECEIPT_HEADER: &str = "x-signed-receipt";
+
+/// Signs receipts for transaction submissions and proof generation.
+///
+/// Receipts are only signed when `RECEIPT_SIGNING_KEY` is set.
+pub struct ReceiptSigner {
+  pair: Option<ed25519::Pair>,
+}
+
+impl ReceiptSigner {
+  /// Load the Ed25519 signing key (secret URI or hex seed) from `RECEIPT_SIGNING_KEY`.
+  pub fn from_env() -> anyhow::Result<AppReceiptSigner> {
+    let pair = match std::env::var("RECEIPT_SIGNING_KEY") {
+      Ok(secret) => Some(
+        ed25519::Pair::from_string(&secret, None)
+          .map_err(|err| anyhow::anyhow!("Invalid RECEIPT_SIGNING_KEY: {err:?}"))?,
+      ),
+      Err(_) => None,
+    };
+    Ok(Data::new(Self { pair }))
+  }
+
+  /// Server's public key (hex), if receipts are enabled.
+  pub fn public_key(&self) -> Option<String> {
+    self
+      .pair
+      .as_ref()
+      .map(|pair| format!("0x{}", hex::encode(pair.public().0)))
+  }
+
+  /// Sign a receipt for `request`.  Returns `None` if receipts are disabled.
+  pub fn sign<R: Serialize>(
+    &self,
+    http_req: &HttpRequest,
+    request: &R,
+    tx_hash: Option<&str>,
+  ) -> Result<Option<Receipt>> {
+    let pair = match &self.pair {
+      Some(pair) => pair,
+      None => return Ok(None),
+    };
+    let request = serde_json::to_vec(request)?;
+    let mut receipt = Receipt {
+      action: format!("{} {}", http_req.method(), http_req.path()),
+      request_hash: format!("0x{}", hex::encode(blake2_256(&request))),
+      tx_hash: tx_hash.map(|hash| hash.to_string()),
+      timestamp: chrono::Utc::now().timestamp(),
+      public_key: format!("0x{}", hex::encode(pair.public().0)),
+      ..Default::default()
+    };
+    let signature = pair.sign(&receipt.message());
+    receipt.signature = format!("0x{}", hex::encode(signature.0));
+    Ok(Some(receipt))
+  }
+
+  /// Check that `receipt` was signed by this server.
+  pub fn verify(&self, receipt: &Receipt) -> bool {
+    let public = match &self.pair {
+      Some(pair) => pair.public(),
+      None => return false,
+    };
+    if Some(&receipt.public_key) != self.public_key().as_ref() {
+      return false;
+    }
+    let signature = receipt
+      .signature
+      .strip_prefix("0x")
+      .unwrap_or(&receipt.signature);
+    let signature = match hex::decode(signature)
+      .ok()
+      .and_then(|sig| <[u8; 64]>::try_from(sig).ok())
+    {
+      Some(signature) => ed25519::Signature::from_raw(signature),
+      None => return false,
+    };
+    ed25519::Pair::verify(&signature, receipt.message(), &public)
+  }
+
+  /// JSON response with a signed receipt header.
+  pub fn json_response<R: Serialize, B: Serialize>(
+    &self,
+    http_req: &HttpRequest,
+    request: &R,
+    tx_hash: Option<&str>,
+    body: &B,
+  ) -> Result<HttpResponse> {
+    let mut res = HttpResponse::Ok();
+    if let Some(receipt) = self.sign(http_req, request, tx_hash)? {
+      let receipt = serde_json::to_string(&receipt)?;
+      res.insert_header((RECEIPT_HEADER, header::HeaderValue::from_str(&receipt)?));
+    }
+    Ok(res.json(body))
+  }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+use actix_web::{
+  body::EitherBody,
+  dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+  error::PayloadError,
+  web::{Bytes, Data},
+  HttpResponse,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use futures_util::stream::{self, Stream};
+use sp_core::hashing::blake2_256;
+
+pub type AppReplayGuard = Data<ReplayGuard>;
+
+/// Request header with a unique nonce.
+pub const NONCE_HEADER: &str = "x-nonce";
+/// Request header with the request's unix timestamp (seconds).
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+/// Request header with the request's HMAC.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Default window (in seconds) that a request timestamp must be within.
+const DEFAULT_WINDOW: i64 = 300;
+/// Maximum nonce length.
+const MAX_NONCE_LEN: usize = 128;
+/// Block size of Blake2b, used by the HMAC.
+const HMAC_BLOCK_SIZE: usize = 128;
+
+/// Rejects forged and replayed requests to the endpoints that generate proofs or decrypt
+/// values.  Those endpoints are registered with `wrap = "ReplayProtection"`.
+///
+/// Those requests must include an `X-Nonce`, `X-Timestamp` and `X-Signature` header.  The
+/// signature is the hex encoded HMAC-Blake2b-256 (keyed with `REPLAY_HMAC_KEY`) of:
+///
+/// `{method}\n{path}\n0x{blake2_256(body)}\n{nonce}\n{timestamp}`
+///
+/// where the path includes the query string.  The timestamp must be within the window and
+/// the nonce can only be used once during the window.
+pub struct ReplayGuard {
+  window: i64,
+  /// `None` when replay protection is disabled.
+  key: Option<Vec<u8>>,
+  seen: Mutex<HashMap<String, i64>>,
+}
+
+impl ReplayGuard {
+  pub fn new(key: Option<Vec<u8>>, window: i64) -> Self {
+    Self {
+      window,
+      key,
+      seen: Default::default(),
+    }
+  }
+
+  /// Disabled unless `REPLAY_PROTECTION` is enabled.  The HMAC key is loaded from
+  /// `REPLAY_HMAC_KEY` (hex) and the window can be set with `REPLAY_WINDOW` (seconds).
+  pub fn from_env() -> anyhow::Result<AppReplayGuard> {
+    let enabled = std::env::var("REPLAY_PROTECTION")
+      .map(|v| v == "true" || v == "1")
+      .unwrap_or(false);
+    if !enabled {
+      return Ok(Data::new(Self::new(None, DEFAULT_WINDOW)));
+    }
+    let key = std::env::var("REPLAY_HMAC_KEY")
+      .map_err(|_| anyhow::anyhow!("REPLAY_PROTECTION needs a REPLAY_HMAC_KEY"))?;
+    let key = hex::decode(key.trim().trim_start_matches("0x"))
+      .map_err(|err| anyhow::anyhow!("Invalid REPLAY_HMAC_KEY: {err:?}"))?;
+    if key.len() < 32 {
+      anyhow::bail!("REPLAY_HMAC_KEY must be at least 32 bytes");
+    }
+    let window = std::env::var("REPLAY_WINDOW")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_WINDOW);
+    Ok(Data::new(Self::new(Some(key), window)))
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.key.is_some()
+  }
+
+  /// Check the request's signature, nonce and timestamp.
+  fn check(&self, req: &ServiceRequest, body: &[u8]) -> Result<(), &'static str> {
+    let key = match &self.key {
+      Some(key) => key,
+      None => return Ok(()),
+    };
+    let header = |name| {
+      req
+        .headers()
+        .get(name)
+        .and_then(|val| val.to_str().ok())
+        .map(|val| val.to_string())
+    };
+    let nonce = header(NONCE_HEADER).ok_or("Missing X-Nonce header")?;
+    if nonce.is_empty() || nonce.len() > MAX_NONCE_LEN {
+      return Err("Invalid X-Nonce header");
+    }
+    let timestamp = header(TIMESTAMP_HEADER)
+      .and_then(|ts| ts.parse::<i64>().ok())
+      .ok_or("Missing or invalid X-Timestamp header")?;
+    let signature = header(SIGNATURE_HEADER)
+      .and_then(|sig| hex::decode(sig.trim().trim_start_matches("0x")).ok())
+      .ok_or("Missing or invalid X-Signature header")?;
+    let path = req
+      .uri()
+      .path_and_query()
+      .map(|path| path.as_str())
+      .unwrap_or_else(|| req.path());
+    let msg = format!(
+      "{}\n{path}\n0x{}\n{nonce}\n{timestamp}",
+      req.method(),
+      hex::encode(blake2_256(body)),
+    );
+    if !constant_time_eq(&hmac_blake2_256(key, msg.as_bytes()), &signature) {
+      return Err("Invalid request signature");
+    }
+    self.check_nonce(nonce, timestamp, chrono::Utc::now().timestamp())
+  }
+
+  /// Check that the timestamp is inside the window and the nonce wasn't used before.
+  fn check_nonce(&self, nonce: String, timestamp: i64, now: i64) -> Result<(), &'static str> {
+    if (now - timestamp).abs() > self.window {
+      return Err("Request timestamp outside of the allowed window");
+    }
+
+    let mut seen = self.seen.lock().expect("Replay guard lock poisoned");
+    // Nonces only need to be remembered while their timestamp is inside the window,
+    // which includes the last second of the window.
+    seen.retain(|_, expires| *expires > now);
+    if seen.contains_key(&nonce) {
+      return Err("Nonce already used");
+    }
+    seen.insert(nonce, timestamp + self.window + 1);
+    Ok(())
+  }
+}
+
+/// Middleware that applies the app's `ReplayGuard` to an endpoint.  Add it to the
+/// endpoints that use an account's secret key with `wrap = "ReplayProtection"`.
+///
+/// The request body is buffered to check its signature.
+pub struct ReplayProtection;
+
+impl<S, B> Transform<S, ServiceRequest> for ReplayProtection
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = actix_web::Error;
+  type Transform = ReplayProtectionMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(ReplayProtectionMiddleware {
+      service: Rc::new(service),
+    }))
+  }
+}
+
+pub struct ReplayProtectionMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ReplayProtectionMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = actix_web::Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, mut req: ServiceRequest) -> Self::Future {
+    let guard = req
+      .app_data::<AppReplayGuard>()
+      .filter(|guard| guard.is_enabled())
+      .cloned();
+    let guard = match guard {
+      Some(guard) => guard,
+      None => {
+        let fut = self.service.call(req);
+        return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+      }
+    };
+    let service = self.service.clone();
+    Box::pin(async move {
+      let body = req.extract::<Bytes>().await?;
+      if let Err(err) = guard.check(&req, &body) {
+        let res = HttpResponse::Unauthorized().body(err);
+        return Ok(req.into_response(res).map_into_right_body());
+      }
+      // Give the handler the buffered body.
+      let payload: Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>> =
+        Box::pin(stream::once(ready(Ok(body))));
+      req.set_payload(Payload::Stream { payload });
+      Ok(service.call(req).await?.map_into_left_body())
+    })
+  }
+}
+
+/// HMAC (RFC 2104) with Blake2b-256.
+fn hmac_blake2_256(key: &[u8], msg: &[u8]) -> [u8; 32] {
+  let mut block = [0u8; HMAC_BLOCK_SIZE];
+  if key.len() > HMAC_BLOCK_SIZE {
+    block[..32].copy_from_slice(&blake2_256(key));
+  } else {
+    block[..key.len()].copy_from_slice(key);
+  }
+  let mut inner = block.iter().map(|b| b ^ 0x36).collect::<Vec<_>>();
+  inner.extend_from_slice(msg);
+  let mut outer = block.iter().map(|b| b ^ 0x5c).collect::<Vec<_>>();
+  outer.extend_from_slice(&blake2_256(&inner));
+  blake2_256(&outer)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn nonce_is_rejected_until_the_end_of_the_window() {
+    let guard = ReplayGuard::new(Some(vec![0; 32]), 300);
+    let timestamp = 1_700_000_000;
+    assert_eq!(guard.check_nonce("n1".into(), timestamp, timestamp), Ok(()));
+    // Last second of the window.
+    assert_eq!(
+      guard.check_nonce("n1".into(), timestamp, timestamp + 300),
+      Err("Nonce already used")
+    );
+    // After the window the timestamp is rejected.
+    assert!(guard
+      .check_nonce("n1".into(), timestamp, timestamp + 301)
+      .is_err());
+  }
+
+  #[test]
+  fn future_timestamp_nonce_is_remembered() {
+    let guard = ReplayGuard::new(Some(vec![0; 32]), 300);
+    let now = 1_700_000_000;
+    assert_eq!(guard.check_nonce("n1".into(), now + 300, now), Ok(()));
+    assert_eq!(
+      guard.check_nonce("n1".into(), now + 300, now + 600),
+      Err("Nonce already used")
+    );
+  }
+}
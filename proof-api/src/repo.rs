@@ -1,10 +1,16 @@
 use actix_web::web::Data;
+use chrono::NaiveDateTime;
 use uuid::Uuid;
 
 use async_trait::async_trait;
 use polymesh_private_proof_shared::{
-  error::Result, Account, AccountAsset, AccountAssetWithSecret, AccountWithSecret, AddAsset, Asset,
-  CreateAccount, CreateUser, UpdateAccountAsset, User,
+  error::Result, Account, AccountAsset, AccountAssetWithSecret, AccountEscrow, AccountWithSecret,
+  AddAnomalyAlert, AddAsset, AddAuditLogEntry, AddProof, AmountLimit, AnomalyAlert, Approval,
+  Asset, AssetAccount, AssetHolder, AuditLogEntry, BalanceConflict, BalanceHistory, CreateAccount,
+  CreateApproval, CreatePositionLock, CreateProofPool, CreateScreeningEntry, CreateUser, EnvConfig,
+  EscrowShare, FeatureFlag, PooledProof, PositionLock, ProofPool, ProofRecord, ScreeningEntry,
+  SetAmountLimit, SetFeatureFlag, UpdateAccountAsset, UpdateScreeningEntry, User,
+  UserAccountAccess, UserApiKey,
 };
 
 mod sqlite;
@@ -21,20 +27,83 @@ pub trait ConfidentialRepository: Send + Sync + 'static {
   async fn get_user(&self, name: &str) -> Result<Option<User>>;
   async fn create_user(&self, user: &CreateUser) -> Result<User>;
 
+  // User API keys
+  async fn get_user_api_keys(&self, username: &str) -> Result<Vec<UserApiKey>>;
+  /// Returns `None` if the user doesn't exist.
+  async fn create_user_api_key(
+    &self,
+    username: &str,
+    name: &str,
+    key_hash: &[u8],
+  ) -> Result<Option<UserApiKey>>;
+  /// Returns `None` if the key doesn't exist or was already revoked.
+  async fn revoke_user_api_key(&self, username: &str, key_id: i64) -> Result<Option<UserApiKey>>;
+  /// User of an unrevoked API key.
+  async fn get_api_key_user(&self, key_hash: &[u8]) -> Result<Option<User>>;
+
+  // Accounts users may decrypt
+  async fn get_user_account_access(&self, username: &str) -> Result<Vec<UserAccountAccess>>;
+  /// Returns `false` if the user doesn't exist.
+  async fn grant_account_access(&self, username: &str, account_id: i64) -> Result<bool>;
+  /// Returns `false` if the user didn't have access.
+  async fn revoke_account_access(&self, username: &str, account_id: i64) -> Result<bool>;
+  async fn has_account_access(&self, user_id: i64, account_id: i64) -> Result<bool>;
+
   // Assets
   async fn get_assets(&self) -> Result<Vec<Asset>>;
   async fn get_asset(&self, asset_id: Uuid) -> Result<Option<Asset>>;
+  async fn get_asset_by_ticker(&self, ticker: &str) -> Result<Option<Asset>>;
   async fn create_asset(&self, asset: &AddAsset) -> Result<Asset>;
+  /// Add the asset, or update the metadata of an existing asset.
+  async fn link_asset(&self, asset: &AddAsset) -> Result<Asset>;
 
   // Accounts
   async fn get_accounts(&self) -> Result<Vec<Account>>;
   async fn get_account(&self, pub_key: &str) -> Result<Option<Account>>;
+  async fn get_accounts_by_did(&self, did: &str) -> Result<Vec<Account>>;
   async fn get_account_with_secret(&self, pub_key: &str) -> Result<Option<AccountWithSecret>>;
+  /// All accounts with their secret key, or the error loading it.  Returns
+  /// `(confidential account, secret)`.
+  async fn get_accounts_with_secret(&self) -> Result<Vec<(Vec<u8>, Result<AccountWithSecret>)>>;
   async fn create_account(&self, account: &CreateAccount) -> Result<Account>;
+  async fn set_account_locked(&self, pub_key: &str, locked: bool) -> Result<Option<Account>>;
+  async fn set_account_did(&self, pub_key: &str, did: &str) -> Result<Option<Account>>;
+
+  // Per-asset accounts derived from a master account.
+  async fn get_asset_accounts(&self, pub_key: &str) -> Result<Vec<AssetAccount>>;
+  async fn get_asset_account(&self, pub_key: &str, asset_id: Uuid) -> Result<Option<AssetAccount>>;
+  /// The account holding `asset_id` for `pub_key`: the account derived for the asset if
+  /// `pub_key` is a master account with one, otherwise `pub_key` itself.
+  async fn resolve_asset_account(&self, pub_key: &str, asset_id: Uuid) -> Result<String>;
+  /// Store a derived account, or return the one already stored for the asset.
+  async fn create_asset_account(
+    &self,
+    parent_account_id: i64,
+    asset_id: Uuid,
+    account: &CreateAccount,
+  ) -> Result<AssetAccount>;
+
+  // Account escrow
+  async fn get_account_escrow(&self, pub_key: &str) -> Result<Option<AccountEscrow>>;
+  async fn get_account_escrow_shares(&self, pub_key: &str) -> Result<Vec<EscrowShare>>;
+  /// Store the escrow shares and clear the account's secret key.
+  async fn escrow_account(
+    &self,
+    pub_key: &str,
+    threshold: u8,
+    shares: &[EscrowShare],
+  ) -> Result<Option<AccountEscrow>>;
+  /// Hold the reassembled secret key in memory, or drop it with `None`.
+  async fn set_reassembled_secret(
+    &self,
+    pub_key: &str,
+    secret_key: Option<Vec<u8>>,
+  ) -> Result<Option<AccountEscrow>>;
 
   // Account balances
   async fn get_account_assets(&self, pub_key: &str) -> Result<Vec<AccountAsset>>;
   async fn get_account_asset(&self, pub_key: &str, asset_id: Uuid) -> Result<Option<AccountAsset>>;
+  async fn get_account_asset_by_id(&self, account_asset_id: i64) -> Result<Option<AccountAsset>>;
   async fn get_account_asset_with_secret(
     &self,
     pub_key: &str,
@@ -42,4 +111,195 @@ pub trait ConfidentialRepository: Send + Sync + 'static {
   ) -> Result<Option<AccountAssetWithSecret>>;
   async fn create_account_asset(&self, account_asset: &UpdateAccountAsset) -> Result<AccountAsset>;
   async fn update_account_asset(&self, account_asset: &UpdateAccountAsset) -> Result<AccountAsset>;
+  async fn get_asset_holders(&self, asset_id: Uuid) -> Result<Vec<AssetHolder>>;
+
+  // Balance history
+  /// Balance changes of an account's asset, newest first.
+  async fn get_balance_history(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    limit: u32,
+    offset: u32,
+  ) -> Result<Vec<BalanceHistory>>;
+  /// Balance changes of an account's asset created in `[from, to)`, oldest first.
+  async fn get_balance_history_range(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    from: Option<chrono::NaiveDateTime>,
+    to: Option<chrono::NaiveDateTime>,
+  ) -> Result<Vec<BalanceHistory>>;
+  async fn get_balance_at_block(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    block_number: u32,
+  ) -> Result<Option<BalanceHistory>>;
+  async fn get_balance_at_time(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    timestamp: NaiveDateTime,
+  ) -> Result<Option<BalanceHistory>>;
+
+  // Amount limits
+  async fn get_amount_limits(&self, pub_key: &str) -> Result<Vec<AmountLimit>>;
+  async fn set_amount_limit(
+    &self,
+    pub_key: &str,
+    limit: &SetAmountLimit,
+  ) -> Result<Option<AmountLimit>>;
+  /// Amount sent today (UTC) of one asset, or all assets if `asset_id` is `None`.
+  async fn get_daily_amount_usage(&self, account_id: i64, asset_id: Option<Uuid>) -> Result<u64>;
+  /// Atomically add the amount to the usage, only if it is within all the account's limits
+  /// that apply to the asset.  Returns the usage id, or `None` if a limit would be exceeded.
+  async fn reserve_amount_usage(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+    amount: u64,
+  ) -> Result<Option<i64>>;
+  /// Add the amount to the usage without checking the limits (i.e. an approved override).
+  async fn add_amount_usage(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+    amount: u64,
+  ) -> Result<i64>;
+  async fn release_amount_usage(&self, usage_id: i64) -> Result<()>;
+
+  // Approvals
+  async fn get_approvals(&self, status: Option<&str>) -> Result<Vec<Approval>>;
+  async fn get_approval(&self, approval_id: i64) -> Result<Option<Approval>>;
+  async fn create_approval(&self, approval: &CreateApproval) -> Result<Approval>;
+  /// Change the approval's status, only if it still has the `from` status.
+  async fn set_approval_status(
+    &self,
+    approval_id: i64,
+    from: &str,
+    status: &str,
+    decided_by: Option<&str>,
+  ) -> Result<Option<Approval>>;
+
+  // Screening lists
+  async fn get_screening_entries(&self) -> Result<Vec<ScreeningEntry>>;
+  async fn get_screening_entry(&self, subject: &str) -> Result<Option<ScreeningEntry>>;
+  /// Returns `None` if the subject is already on a list.
+  async fn create_screening_entry(
+    &self,
+    entry: &CreateScreeningEntry,
+  ) -> Result<Option<ScreeningEntry>>;
+  async fn update_screening_entry(
+    &self,
+    subject: &str,
+    entry: &UpdateScreeningEntry,
+  ) -> Result<Option<ScreeningEntry>>;
+  async fn delete_screening_entry(&self, subject: &str) -> Result<bool>;
+
+  // Sender proof pools
+  async fn get_proof_pools(&self, pub_key: &str, asset_id: Uuid) -> Result<Vec<ProofPool>>;
+  async fn get_proof_pool(&self, pool_id: i64) -> Result<Option<ProofPool>>;
+  /// The `generating` or `ready` pool of the account asset.
+  async fn get_active_proof_pool(&self, account_asset_id: i64) -> Result<Option<ProofPool>>;
+  /// Active pools past their expiry.
+  async fn get_expired_proof_pools(&self) -> Result<Vec<ProofPool>>;
+  async fn create_proof_pool(
+    &self,
+    account_asset_id: i64,
+    asset_id: Uuid,
+    pool: &CreateProofPool,
+    expires_in: u64,
+  ) -> Result<ProofPool>;
+  /// Add a generated proof.  The pool becomes `ready` when all proofs have been generated.
+  async fn add_pooled_proof(&self, proof: &PooledProof) -> Result<Option<ProofPool>>;
+  /// Change the pool's status, only if it is still active.
+  async fn set_proof_pool_status(
+    &self,
+    pool_id: i64,
+    status: &str,
+    error: Option<&str>,
+  ) -> Result<Option<ProofPool>>;
+  /// Reserve the pool's next available proof.
+  async fn reserve_pooled_proof(&self, pool_id: i64) -> Result<Option<PooledProof>>;
+  /// Mark a reserved proof `used` (or `available` again if it wasn't used).
+  async fn finish_pooled_proof(&self, pool_proof_id: i64, used: bool) -> Result<Option<ProofPool>>;
+  /// Mark the pool's available proofs `released` and return them.
+  async fn release_pooled_proofs(&self, pool_id: i64) -> Result<Vec<PooledProof>>;
+
+  // Audit log
+  /// Add an entry to the audit log hash chain.
+  async fn add_audit_log(&self, entry: &AddAuditLogEntry) -> Result<()>;
+  /// Up to `limit` entries with ids after `after_id`, oldest first.
+  async fn get_audit_log_page(&self, after_id: i64, limit: u32) -> Result<Vec<AuditLogEntry>>;
+  /// Amounts of the account's last `limit` sender proofs of one asset, or without an asset
+  /// if `asset_id` is `None`.
+  async fn get_recent_sender_amounts(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+    limit: u32,
+  ) -> Result<Vec<i64>>;
+  /// Number of the account's `action` entries in the last `secs` seconds.
+  async fn count_recent_audit_log(&self, account_id: i64, action: &str, secs: u32) -> Result<i64>;
+
+  // Anomaly alerts
+  async fn get_anomaly_alerts(
+    &self,
+    rule: Option<&str>,
+    pub_key: Option<&str>,
+  ) -> Result<Vec<AnomalyAlert>>;
+  /// Has the rule triggered for the account in the last `secs` seconds.
+  async fn has_recent_anomaly_alert(&self, rule: &str, pub_key: &str, secs: u32) -> Result<bool>;
+  async fn add_anomaly_alert(&self, alert: &AddAnomalyAlert) -> Result<AnomalyAlert>;
+
+  // Proof history
+  async fn add_proof(&self, proof: &AddProof) -> Result<i64>;
+  /// The account's generated proofs, newest first.
+  async fn get_account_proofs(
+    &self,
+    pub_key: &str,
+    asset_id: Option<Uuid>,
+    proof_type: Option<&str>,
+  ) -> Result<Vec<ProofRecord>>;
+  async fn get_proof(&self, proof_id: i64) -> Result<Option<ProofRecord>>;
+
+  // Position locks
+  /// The account's current and upcoming position locks.
+  async fn get_position_locks(&self, pub_key: &str) -> Result<Vec<PositionLock>>;
+  /// The account's position lock that is active now and covers the asset.  Locks of all
+  /// assets match any asset, and all locks of the account match if `asset_id` is `None`.
+  /// `reject` locks are returned before `defer` locks.
+  async fn get_active_position_lock(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+  ) -> Result<Option<PositionLock>>;
+  async fn create_position_lock(
+    &self,
+    pub_key: &str,
+    lock: &CreatePositionLock,
+  ) -> Result<Option<PositionLock>>;
+  async fn delete_position_lock(&self, pub_key: &str, lock_id: i64) -> Result<bool>;
+
+  // Balance conflicts
+  /// Balance conflicts, newest first.  All of them if `status` is `None`.
+  async fn get_balance_conflicts(&self, status: Option<&str>) -> Result<Vec<BalanceConflict>>;
+  async fn get_balance_conflict(&self, conflict_id: i64) -> Result<Option<BalanceConflict>>;
+  /// Apply (`accept`) or drop a pending conflicting update.  `None` if the conflict isn't
+  /// pending.
+  async fn resolve_balance_conflict(
+    &self,
+    conflict_id: i64,
+    accept: bool,
+  ) -> Result<Option<BalanceConflict>>;
+  /// Reload the config that can change at runtime: the balance conflict strategy.  The key
+  /// encryption and key store need a restart.
+  fn reload_config(&self, config: &EnvConfig) -> Result<()>;
+
+  // Feature flags
+  async fn get_feature_flags(&self) -> Result<Vec<FeatureFlag>>;
+  /// Add the flag, or update the flag with the same endpoint pattern.
+  async fn set_feature_flag(&self, flag: &SetFeatureFlag) -> Result<FeatureFlag>;
+  async fn delete_feature_flag(&self, flag_id: i64) -> Result<bool>;
 }
@@ -1,29 +1,219 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
+use chrono::NaiveDateTime;
 use uuid::Uuid;
 
 use actix_web::web::Data;
 
+use zeroize::Zeroizing;
+
 use async_trait::async_trait;
 use polymesh_private_proof_shared::{
-  error::Result, Account, AccountAsset, AccountAssetWithSecret, AccountWithSecret, AddAsset, Asset,
-  CreateAccount, CreateUser, PublicKey, UpdateAccountAsset, User,
+  error::{Error, Result},
+  Account, AccountAsset, AccountAssetWithSecret, AccountEscrow, AccountWithSecret, AddAnomalyAlert,
+  AddAsset, AddAuditLogEntry, AddProof, AmountLimit, AnomalyAlert, Approval, Asset, AssetAccount,
+  AssetHolder, BalanceAction, BalanceConflict, BalanceConflictStrategy, BalanceHistory,
+  BalanceSource, CreateAccount, CreateApproval, CreatePositionLock, CreateProofPool,
+  CreateScreeningEntry, CreateUser, DecryptionCache, EnvConfig, EscrowShare, FeatureFlag,
+  PooledProof, PositionLock, ProofPool, ProofRecord, PublicKey, ScreeningEntry, SetAmountLimit,
+  SetFeatureFlag, UpdateAccountAsset, UpdateScreeningEntry, User, UserAccountAccess, UserApiKey,
+  CONFLICT_ACCEPTED, CONFLICT_APPLIED, CONFLICT_DISCARDED, CONFLICT_PENDING, CONFLICT_REJECTED,
 };
 
 use super::{ConfidentialRepository, Repository};
+use crate::audit_chain::{entry_hash, GENESIS_HASH};
+use crate::key_encryption::{
+  is_encrypted, key_encryption_from_env, open_secret_key, seal_secret_key, KeyEncryption,
+};
+use crate::key_store::{
+  account_key_store_from_env, decode_key_ref, encode_key_ref, AccountKeyStore,
+};
 
 pub struct SqliteConfidentialRepository {
   pool: sqlx::SqlitePool,
+  /// Reassembled secret keys of escrowed accounts.  Never written to the database.
+  reassembled: RwLock<HashMap<i64, Zeroizing<Vec<u8>>>>,
+  /// Encrypts the secret keys stored in the database.
+  key_encryption: Option<Arc<dyn KeyEncryption>>,
+  /// Stores the secret keys outside of the database.
+  key_store: Option<Arc<dyn AccountKeyStore>>,
+  /// Resolves balance updates that disagree with the stored balance.
+  conflict_strategy: RwLock<BalanceConflictStrategy>,
 }
 
 impl SqliteConfidentialRepository {
   pub fn new(pool: &sqlx::SqlitePool) -> Arc<dyn ConfidentialRepository> {
-    Arc::new(Self { pool: pool.clone() })
+    Self::with_key_encryption(pool, None)
   }
 
   pub fn new_app_data(pool: &sqlx::SqlitePool) -> Repository {
     Data::from(Self::new(pool))
   }
+
+  /// Encrypt the secret keys with `key_encryption`.
+  pub fn with_key_encryption(
+    pool: &sqlx::SqlitePool,
+    key_encryption: Option<Arc<dyn KeyEncryption>>,
+  ) -> Arc<dyn ConfidentialRepository> {
+    Arc::new(Self {
+      pool: pool.clone(),
+      reassembled: Default::default(),
+      key_encryption,
+      key_store: None,
+      conflict_strategy: Default::default(),
+    })
+  }
+
+  /// Use the key encryption configured by `KEY_ENCRYPTION`, the key store configured by
+  /// `ACCOUNT_KEY_STORE` and the balance conflict strategy from `BALANCE_CONFLICT_STRATEGY`.
+  /// Secret keys that are still stored in the database are moved to the key store, or
+  /// encrypted if they are stored in plaintext.
+  pub async fn from_env(pool: &sqlx::SqlitePool) -> Result<Repository> {
+    let repo = Self {
+      pool: pool.clone(),
+      reassembled: Default::default(),
+      key_encryption: key_encryption_from_env()?,
+      key_store: account_key_store_from_env()?,
+      conflict_strategy: RwLock::new(BalanceConflictStrategy::from_env()?),
+    };
+    let count = repo.move_secrets_to_key_store().await?;
+    if count > 0 {
+      log::info!("Moved {count} secret keys to the key store");
+    }
+    let count = repo.encrypt_plaintext_secrets().await?;
+    if count > 0 {
+      log::info!("Encrypted {count} plaintext secret keys");
+    }
+    let repo: Arc<dyn ConfidentialRepository> = Arc::new(repo);
+    Ok(Data::from(repo))
+  }
+
+  /// Encrypt the secret keys stored in plaintext.  Does nothing without key encryption.
+  async fn encrypt_plaintext_secrets(&self) -> Result<usize> {
+    let enc = match &self.key_encryption {
+      Some(enc) => enc.as_ref(),
+      None => return Ok(0),
+    };
+    let accounts = sqlx::query!(
+      r#"SELECT account_id, public_key, secret_key FROM accounts WHERE length(secret_key) > 0"#,
+    )
+    .fetch_all(&self.pool)
+    .await?;
+    let mut count = 0;
+    for account in accounts {
+      let secret_key = Zeroizing::new(account.secret_key);
+      if is_encrypted(&secret_key) || decode_key_ref(&secret_key).is_some() {
+        continue;
+      }
+      let sealed = seal_secret_key(enc, &account.public_key, &secret_key).await?;
+      sqlx::query!(
+        r#"
+        UPDATE accounts SET secret_key = ?, updated_at = CURRENT_TIMESTAMP
+          WHERE account_id = ? AND secret_key = ?
+        "#,
+        sealed,
+        account.account_id,
+        *secret_key,
+      )
+      .execute(&self.pool)
+      .await?;
+      count += 1;
+    }
+    Ok(count)
+  }
+
+  /// Move the secret keys stored in the database to the key store.  Does nothing without a
+  /// key store.
+  async fn move_secrets_to_key_store(&self) -> Result<usize> {
+    let store = match &self.key_store {
+      Some(store) => store.as_ref(),
+      None => return Ok(0),
+    };
+    let accounts = sqlx::query!(
+      r#"SELECT account_id, public_key, secret_key FROM accounts WHERE length(secret_key) > 0"#,
+    )
+    .fetch_all(&self.pool)
+    .await?;
+    let mut count = 0;
+    for account in accounts {
+      let stored = Zeroizing::new(account.secret_key);
+      if decode_key_ref(&stored).is_some() {
+        continue;
+      }
+      let secret_key = self.open_secret(&account.public_key, &stored).await?;
+      let key_ref = encode_key_ref(&store.put_secret(&account.public_key, &secret_key).await?);
+      sqlx::query!(
+        r#"
+        UPDATE accounts SET secret_key = ?, updated_at = CURRENT_TIMESTAMP
+          WHERE account_id = ? AND secret_key = ?
+        "#,
+        key_ref,
+        account.account_id,
+        *stored,
+      )
+      .execute(&self.pool)
+      .await?;
+      count += 1;
+    }
+    Ok(count)
+  }
+
+  /// Prepare a secret key for storage: a reference to the key in the key store, encrypted
+  /// with the key encryption or as-is.
+  async fn seal_secret(&self, account: &[u8], secret_key: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    if let Some(store) = &self.key_store {
+      let key_ref = store.put_secret(account, secret_key).await?;
+      return Ok(Zeroizing::new(encode_key_ref(&key_ref)));
+    }
+    match &self.key_encryption {
+      Some(enc) => Ok(Zeroizing::new(
+        seal_secret_key(enc.as_ref(), account, secret_key).await?,
+      )),
+      None => Ok(Zeroizing::new(secret_key.to_vec())),
+    }
+  }
+
+  /// Decrypt the stored secret key and fill in the reassembled secret key of an escrowed
+  /// account.
+  async fn load_secret(&self, mut account: AccountWithSecret) -> Result<AccountWithSecret> {
+    if !account.secret_key.is_empty() {
+      let secret_key = self
+        .open_secret(&account.confidential_account, &account.secret_key)
+        .await?;
+      account.secret_key = secret_key.to_vec();
+    }
+    Ok(self.with_reassembled_secret(account))
+  }
+
+  /// The secret key from the key store, decrypted or as-is.
+  async fn open_secret(&self, account: &[u8], stored: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    if let Some(key_ref) = decode_key_ref(stored) {
+      let store = self.key_store.as_deref().ok_or_else(|| {
+        Error::other("Secret keys are in a key store, but `ACCOUNT_KEY_STORE` isn't configured")
+      })?;
+      return store.get_secret(key_ref).await;
+    }
+    if is_encrypted(stored) {
+      let enc = self.key_encryption.as_deref().ok_or_else(|| {
+        Error::other("Secret keys are encrypted, but `KEY_ENCRYPTION` isn't configured")
+      })?;
+      return open_secret_key(enc, account, stored).await;
+    }
+    Ok(Zeroizing::new(stored.to_vec()))
+  }
+
+  /// Fill in the secret key of an escrowed account, if it has been reassembled.
+  fn with_reassembled_secret(&self, mut account: AccountWithSecret) -> AccountWithSecret {
+    if account.escrowed {
+      let reassembled = self.reassembled.read().expect("Escrow lock poisoned");
+      if let Some(secret_key) = reassembled.get(&account.account_id) {
+        account.secret_key = secret_key.to_vec();
+      }
+    }
+    account
+  }
 }
 
 #[async_trait]
@@ -60,12 +250,166 @@ impl ConfidentialRepository for SqliteConfidentialRepository {
     )
   }
 
+  async fn get_user_api_keys(&self, username: &str) -> Result<Vec<UserApiKey>> {
+    Ok(
+      sqlx::query_as!(
+        UserApiKey,
+        r#"
+        SELECT k.key_id, u.username, k.name, k.revoked_at, k.created_at
+        FROM user_api_keys k
+        JOIN users u ON u.user_id = k.user_id
+        WHERE u.username = ?
+        ORDER BY k.key_id
+        "#,
+        username,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_user_api_key(
+    &self,
+    username: &str,
+    name: &str,
+    key_hash: &[u8],
+  ) -> Result<Option<UserApiKey>> {
+    let user = match self.get_user(username).await? {
+      Some(user) => user,
+      None => return Ok(None),
+    };
+    let rec = sqlx::query!(
+      r#"
+      INSERT INTO user_api_keys (user_id, name, key_hash)
+      VALUES (?, ?, ?)
+      RETURNING key_id, created_at
+      "#,
+      user.user_id,
+      name,
+      key_hash,
+    )
+    .fetch_one(&self.pool)
+    .await?;
+    Ok(Some(UserApiKey {
+      key_id: rec.key_id,
+      username: user.username,
+      name: name.to_string(),
+      revoked_at: None,
+      created_at: rec.created_at,
+    }))
+  }
+
+  async fn revoke_user_api_key(&self, username: &str, key_id: i64) -> Result<Option<UserApiKey>> {
+    let rec = sqlx::query!(
+      r#"
+      UPDATE user_api_keys SET revoked_at = CURRENT_TIMESTAMP
+        WHERE key_id = ? AND revoked_at IS NULL
+          AND user_id = (SELECT user_id FROM users WHERE username = ?)
+      RETURNING key_id, name, revoked_at, created_at
+      "#,
+      key_id,
+      username,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    Ok(rec.map(|rec| UserApiKey {
+      key_id: rec.key_id,
+      username: username.to_string(),
+      name: rec.name,
+      revoked_at: rec.revoked_at,
+      created_at: rec.created_at,
+    }))
+  }
+
+  async fn get_api_key_user(&self, key_hash: &[u8]) -> Result<Option<User>> {
+    Ok(
+      sqlx::query_as!(
+        User,
+        r#"
+        SELECT u.user_id, u.username, u.created_at, u.updated_at
+        FROM users u
+        JOIN user_api_keys k ON k.user_id = u.user_id
+        WHERE k.key_hash = ? AND k.revoked_at IS NULL
+        "#,
+        key_hash,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_user_account_access(&self, username: &str) -> Result<Vec<UserAccountAccess>> {
+    Ok(
+      sqlx::query_as!(
+        UserAccountAccess,
+        r#"
+        SELECT u.username, a.public_key as confidential_account, x.created_at
+        FROM user_account_access x
+        JOIN users u ON u.user_id = x.user_id
+        JOIN accounts a ON a.account_id = x.account_id
+        WHERE u.username = ?
+        ORDER BY x.account_id
+        "#,
+        username,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn grant_account_access(&self, username: &str, account_id: i64) -> Result<bool> {
+    let user = match self.get_user(username).await? {
+      Some(user) => user,
+      None => return Ok(false),
+    };
+    sqlx::query!(
+      r#"
+      INSERT INTO user_account_access (user_id, account_id)
+      VALUES (?, ?)
+      ON CONFLICT DO NOTHING
+      "#,
+      user.user_id,
+      account_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(true)
+  }
+
+  async fn revoke_account_access(&self, username: &str, account_id: i64) -> Result<bool> {
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM user_account_access
+        WHERE account_id = ? AND user_id = (SELECT user_id FROM users WHERE username = ?)
+      "#,
+      account_id,
+      username,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  async fn has_account_access(&self, user_id: i64, account_id: i64) -> Result<bool> {
+    let rec = sqlx::query!(
+      r#"
+      SELECT user_id FROM user_account_access WHERE user_id = ? AND account_id = ?
+      "#,
+      user_id,
+      account_id,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    Ok(rec.is_some())
+  }
+
   async fn get_assets(&self) -> Result<Vec<Asset>> {
     Ok(
       sqlx::query_as!(
         Asset,
         r#"
-          SELECT asset_id as "asset_id: Uuid", created_at, updated_at
+          SELECT asset_id as "asset_id: Uuid", name, ticker,
+          decimals as "decimals: u8", created_at, updated_at
           FROM assets
 "#,
       )
@@ -79,7 +423,8 @@ impl ConfidentialRepository for SqliteConfidentialRepository {
       sqlx::query_as!(
         Asset,
         r#"
-        SELECT asset_id as "asset_id: Uuid", created_at, updated_at
+        SELECT asset_id as "asset_id: Uuid", name, ticker,
+          decimals as "decimals: u8", created_at, updated_at
         FROM assets WHERE asset_id = ?"#,
         asset_id
       )
@@ -88,16 +433,60 @@ impl ConfidentialRepository for SqliteConfidentialRepository {
     )
   }
 
+  async fn get_asset_by_ticker(&self, ticker: &str) -> Result<Option<Asset>> {
+    Ok(
+      sqlx::query_as!(
+        Asset,
+        r#"
+        SELECT asset_id as "asset_id: Uuid", name, ticker,
+          decimals as "decimals: u8", created_at, updated_at
+        FROM assets WHERE ticker = ?"#,
+        ticker
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
   async fn create_asset(&self, asset: &AddAsset) -> Result<Asset> {
     Ok(
       sqlx::query_as!(
         Asset,
         r#"
-      INSERT INTO assets (asset_id)
-      VALUES (?)
-      RETURNING asset_id as "asset_id: Uuid", created_at, updated_at
+      INSERT INTO assets (asset_id, name, ticker, decimals)
+      VALUES (?, ?, ?, ?)
+      RETURNING asset_id as "asset_id: Uuid", name, ticker,
+        decimals as "decimals: u8", created_at, updated_at
       "#,
         asset.asset_id,
+        asset.name,
+        asset.ticker,
+        asset.decimals,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn link_asset(&self, asset: &AddAsset) -> Result<Asset> {
+    Ok(
+      sqlx::query_as!(
+        Asset,
+        r#"
+      INSERT INTO assets (asset_id, name, ticker, decimals)
+      VALUES (?, ?, ?, ?)
+      ON CONFLICT(asset_id) DO UPDATE SET
+        name = COALESCE(excluded.name, name),
+        ticker = COALESCE(excluded.ticker, ticker),
+        decimals = COALESCE(excluded.decimals, decimals),
+        updated_at = CURRENT_TIMESTAMP
+      RETURNING asset_id as "asset_id: Uuid", name, ticker,
+        decimals as "decimals: u8", created_at, updated_at
+      "#,
+        asset.asset_id,
+        asset.name,
+        asset.ticker,
+        asset.decimals,
       )
       .fetch_one(&self.pool)
       .await?,
@@ -108,7 +497,7 @@ impl ConfidentialRepository for SqliteConfidentialRepository {
     Ok(
       sqlx::query_as!(
         Account,
-        r#"SELECT account_id, public_key as confidential_account, created_at, updated_at FROM accounts"#,
+        r#"SELECT account_id, public_key as confidential_account, locked as "locked: bool", did, created_at, updated_at FROM accounts"#,
       )
       .fetch_all(&self.pool)
       .await?,
@@ -120,183 +509,1891 @@ impl ConfidentialRepository for SqliteConfidentialRepository {
     let key = pub_key.0.as_slice();
     Ok(sqlx::query_as!(
       Account,
-      r#"SELECT account_id, public_key as confidential_account, created_at, updated_at FROM accounts WHERE public_key = ?"#,
+      r#"SELECT account_id, public_key as confidential_account, locked as "locked: bool", did, created_at, updated_at FROM accounts WHERE public_key = ?"#,
       key
     )
     .fetch_optional(&self.pool)
     .await?)
   }
 
+  async fn get_accounts_by_did(&self, did: &str) -> Result<Vec<Account>> {
+    Ok(sqlx::query_as!(
+      Account,
+      r#"SELECT account_id, public_key as confidential_account, locked as "locked: bool", did, created_at, updated_at FROM accounts WHERE did = ?"#,
+      did
+    )
+    .fetch_all(&self.pool)
+    .await?)
+  }
+
   async fn get_account_with_secret(&self, pub_key: &str) -> Result<Option<AccountWithSecret>> {
     let pub_key = PublicKey::from_str(pub_key)?;
     let key = pub_key.0.as_slice();
-    Ok(
-      sqlx::query_as!(
-        AccountWithSecret,
-        r#"SELECT account_id, public_key as confidential_account, secret_key FROM accounts WHERE public_key = ?"#,
-        key
-      )
-      .fetch_optional(&self.pool)
-      .await?,
+    let account = sqlx::query_as!(
+      AccountWithSecret,
+      r#"SELECT account_id, public_key as confidential_account, secret_key, locked as "locked: bool",
+        escrow_threshold IS NOT NULL as "escrowed!: bool"
+      FROM accounts WHERE public_key = ?"#,
+      key
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    match account {
+      Some(account) => Ok(Some(self.load_secret(account).await?)),
+      None => Ok(None),
+    }
+  }
+
+  async fn get_accounts_with_secret(&self) -> Result<Vec<(Vec<u8>, Result<AccountWithSecret>)>> {
+    let accounts = sqlx::query_as!(
+      AccountWithSecret,
+      r#"SELECT account_id, public_key as confidential_account, secret_key, locked as "locked: bool",
+        escrow_threshold IS NOT NULL as "escrowed!: bool"
+      FROM accounts"#,
     )
+    .fetch_all(&self.pool)
+    .await?;
+    let mut loaded = Vec::with_capacity(accounts.len());
+    for account in accounts {
+      // One unreadable secret mustn't hide the others.
+      let confidential_account = account.confidential_account.clone();
+      loaded.push((confidential_account, self.load_secret(account).await));
+    }
+    Ok(loaded)
   }
 
   async fn create_account(&self, account: &CreateAccount) -> Result<Account> {
+    let secret_key = self
+      .seal_secret(&account.confidential_account, &account.secret_key)
+      .await?;
     Ok(
       sqlx::query_as!(
         Account,
         r#"
       INSERT INTO accounts (public_key, secret_key)
       VALUES (?, ?)
-      RETURNING account_id, public_key as confidential_account, created_at, updated_at
+      RETURNING account_id, public_key as confidential_account, locked as "locked: bool", did, created_at, updated_at
       "#,
         account.confidential_account,
-        account.secret_key,
+        *secret_key,
       )
       .fetch_one(&self.pool)
       .await?,
     )
   }
 
-  async fn get_account_assets(&self, pub_key: &str) -> Result<Vec<AccountAsset>> {
+  async fn set_account_locked(&self, pub_key: &str, locked: bool) -> Result<Option<Account>> {
     let pub_key = PublicKey::from_str(pub_key)?;
     let key = pub_key.0.as_slice();
+    if locked {
+      DecryptionCache::global().invalidate_account(key);
+    }
     Ok(
       sqlx::query_as!(
-        AccountAsset,
+        Account,
         r#"
-          SELECT aa.asset_id as "asset_id: Uuid",
-            aa.account_asset_id, aa.account_id,
-            aa.balance, aa.enc_balance, aa.created_at, aa.updated_at
-          FROM account_assets as aa
-          JOIN accounts as acc using(account_id)
-          WHERE acc.public_key = ?
-        "#,
-        key
+      UPDATE accounts SET locked = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE public_key = ?
+      RETURNING account_id, public_key as confidential_account, locked as "locked: bool", did, created_at, updated_at
+      "#,
+        locked,
+        key,
       )
-      .fetch_all(&self.pool)
+      .fetch_optional(&self.pool)
       .await?,
     )
   }
 
-  async fn get_account_asset(&self, pub_key: &str, asset_id: Uuid) -> Result<Option<AccountAsset>> {
+  async fn set_account_did(&self, pub_key: &str, did: &str) -> Result<Option<Account>> {
     let pub_key = PublicKey::from_str(pub_key)?;
     let key = pub_key.0.as_slice();
     Ok(
       sqlx::query_as!(
-        AccountAsset,
+        Account,
         r#"
-          SELECT aa.asset_id as "asset_id: Uuid",
-            aa.account_asset_id, aa.account_id,
-            aa.balance, aa.enc_balance, aa.created_at, aa.updated_at
-          FROM account_assets as aa
-          JOIN accounts as acc using(account_id)
-          WHERE acc.public_key = ? AND aa.asset_id = ?
-        "#,
+      UPDATE accounts SET did = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE public_key = ?
+      RETURNING account_id, public_key as confidential_account, locked as "locked: bool", did, created_at, updated_at
+      "#,
+        did,
         key,
-        asset_id,
       )
       .fetch_optional(&self.pool)
       .await?,
     )
   }
 
-  async fn get_account_asset_with_secret(
-    &self,
-    pub_key: &str,
-    asset_id: Uuid,
-  ) -> Result<Option<AccountAssetWithSecret>> {
+  async fn get_asset_accounts(&self, pub_key: &str) -> Result<Vec<AssetAccount>> {
     let pub_key = PublicKey::from_str(pub_key)?;
     let key = pub_key.0.as_slice();
     Ok(
-      sqlx::query_as(
+      sqlx::query_as!(
+        AssetAccount,
         r#"
-          SELECT aa.account_asset_id, aa.asset_id, aa.balance, aa.enc_balance,
-            acc.account_id, acc.public_key as confidential_account, acc.secret_key
-          FROM account_assets as aa
-          JOIN accounts as acc using(account_id)
-          WHERE acc.public_key = ? AND aa.asset_id = ?
-        "#,
+      SELECT acc.derived_asset_id as "asset_id!: Uuid", acc.public_key as confidential_account,
+        acc.created_at
+      FROM accounts as acc
+        JOIN accounts as parent ON parent.account_id = acc.parent_account_id
+      WHERE parent.public_key = ?
+      ORDER BY acc.account_id
+      "#,
+        key
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_asset_account(&self, pub_key: &str, asset_id: Uuid) -> Result<Option<AssetAccount>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        AssetAccount,
+        r#"
+      SELECT acc.derived_asset_id as "asset_id!: Uuid", acc.public_key as confidential_account,
+        acc.created_at
+      FROM accounts as acc
+        JOIN accounts as parent ON parent.account_id = acc.parent_account_id
+      WHERE parent.public_key = ? AND acc.derived_asset_id = ?
+      "#,
+        key,
+        asset_id,
       )
-      .bind(key)
-      .bind(asset_id)
       .fetch_optional(&self.pool)
       .await?,
     )
   }
 
-  async fn create_account_asset(&self, account_asset: &UpdateAccountAsset) -> Result<AccountAsset> {
-    let mut conn = self.pool.acquire().await?;
-    let balance = account_asset.balance as i64;
-    let enc_balance = account_asset.enc_balance();
-    let account = sqlx::query!(
+  async fn resolve_asset_account(&self, pub_key: &str, asset_id: Uuid) -> Result<String> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    let derived = sqlx::query_scalar!(
+      r#"
+      SELECT acc.public_key
+      FROM accounts as acc
+        JOIN accounts as parent ON parent.account_id = acc.parent_account_id
+      WHERE parent.public_key = ? AND acc.derived_asset_id = ?
+      "#,
+      key,
+      asset_id,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    let key = derived.as_deref().unwrap_or(key);
+    Ok(format!("0x{}", hex::encode(key)))
+  }
+
+  async fn create_asset_account(
+    &self,
+    parent_account_id: i64,
+    asset_id: Uuid,
+    account: &CreateAccount,
+  ) -> Result<AssetAccount> {
+    let secret_key = self
+      .seal_secret(&account.confidential_account, &account.secret_key)
+      .await?;
+    // The derivation is deterministic, so a concurrent call stores the same secret key (the
+    // key store accepts an identical key) and inserts the same account.
+    sqlx::query!(
       r#"
-      INSERT INTO account_assets (account_id, asset_id, balance, enc_balance)
+      INSERT INTO accounts (public_key, secret_key, parent_account_id, derived_asset_id)
       VALUES (?, ?, ?, ?)
-      ON CONFLICT(account_id, asset_id)
-        DO UPDATE SET balance = excluded.balance, enc_balance = excluded.enc_balance, updated_at = CURRENT_TIMESTAMP
-      RETURNING account_asset_id as id
+      ON CONFLICT DO NOTHING
       "#,
-      account_asset.account_id,
-      account_asset.asset_id,
-      balance,
-      enc_balance,
+      account.confidential_account,
+      *secret_key,
+      parent_account_id,
+      asset_id,
     )
-    .fetch_one(conn.as_mut())
+    .execute(&self.pool)
     .await?;
     Ok(
       sqlx::query_as!(
-        AccountAsset,
+        AssetAccount,
         r#"
-      SELECT asset_id as "asset_id: Uuid",
-        account_asset_id, account_id,
-        balance, enc_balance, created_at, updated_at
-        FROM account_assets
-        WHERE account_asset_id = ?
+      SELECT derived_asset_id as "asset_id!: Uuid", public_key as confidential_account,
+        created_at
+      FROM accounts
+      WHERE parent_account_id = ? AND derived_asset_id = ?
       "#,
-        account.id,
+        parent_account_id,
+        asset_id,
       )
-      .fetch_one(conn.as_mut())
+      .fetch_one(&self.pool)
       .await?,
     )
   }
 
-  async fn update_account_asset(&self, account_asset: &UpdateAccountAsset) -> Result<AccountAsset> {
-    let account_asset_id = if let Some(id) = account_asset.account_asset_id {
-      id
-    } else {
-      return self.create_account_asset(account_asset).await;
-    };
-    let mut conn = self.pool.acquire().await?;
-    let balance = account_asset.balance as i64;
-    let enc_balance = account_asset.enc_balance();
-    sqlx::query!(
+  async fn get_account_escrow(&self, pub_key: &str) -> Result<Option<AccountEscrow>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    let account = sqlx::query!(
       r#"
-      UPDATE account_assets SET balance = ?, enc_balance = ?, updated_at = CURRENT_TIMESTAMP
-        WHERE account_asset_id = ?
-      RETURNING account_asset_id as id
+      SELECT account_id, escrow_threshold as "escrow_threshold!: u8"
+        FROM accounts WHERE public_key = ? AND escrow_threshold IS NOT NULL
       "#,
-      balance,
-      enc_balance,
-      account_asset_id,
+      key
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    let account = match account {
+      Some(account) => account,
+      None => return Ok(None),
+    };
+    let custodians = sqlx::query_scalar!(
+      r#"SELECT custodian FROM account_escrow_shares WHERE account_id = ? ORDER BY share_id"#,
+      account.account_id
     )
-    .fetch_optional(conn.as_mut())
+    .fetch_all(&self.pool)
     .await?;
+    let reassembled = self
+      .reassembled
+      .read()
+      .expect("Escrow lock poisoned")
+      .contains_key(&account.account_id);
+    Ok(Some(AccountEscrow {
+      confidential_account: pub_key,
+      threshold: account.escrow_threshold,
+      custodians,
+      reassembled,
+    }))
+  }
 
+  async fn get_account_escrow_shares(&self, pub_key: &str) -> Result<Vec<EscrowShare>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
     Ok(
       sqlx::query_as!(
-        AccountAsset,
+        EscrowShare,
         r#"
-      SELECT asset_id as "asset_id: Uuid",
-        account_asset_id, account_id,
+          SELECT es.custodian, es.enc_share
+          FROM account_escrow_shares as es
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ?
+          ORDER BY es.share_id
+        "#,
+        key
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn escrow_account(
+    &self,
+    pub_key: &str,
+    threshold: u8,
+    shares: &[EscrowShare],
+  ) -> Result<Option<AccountEscrow>> {
+    let pub_key_bytes = PublicKey::from_str(pub_key)?;
+    let key = pub_key_bytes.0.as_slice();
+    let mut db_tx = self.pool.begin().await?;
+    let stored = sqlx::query_scalar!(
+      r#"SELECT secret_key FROM accounts WHERE public_key = ? AND escrow_threshold IS NULL"#,
+      key,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .map(Zeroizing::new);
+    // Only accounts that are not already in escrow.
+    let account = sqlx::query!(
+      r#"
+      UPDATE accounts SET secret_key = X'', escrow_threshold = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE public_key = ? AND escrow_threshold IS NULL
+      RETURNING account_id
+      "#,
+      threshold,
+      key,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let account_id = match account {
+      Some(account) => account.account_id,
+      None => return Ok(None),
+    };
+    for share in shares {
+      sqlx::query!(
+        r#"
+        INSERT INTO account_escrow_shares (account_id, custodian, enc_share)
+        VALUES (?, ?, ?)
+        "#,
+        account_id,
+        share.custodian,
+        share.enc_share,
+      )
+      .execute(&mut *db_tx)
+      .await?;
+    }
+    db_tx.commit().await?;
+    // The secret key is no longer stored.
+    DecryptionCache::global().invalidate_account(key);
+    if let (Some(store), Some(key_ref)) = (
+      &self.key_store,
+      stored
+        .as_deref()
+        .and_then(|stored| decode_key_ref(stored.as_slice())),
+    ) {
+      store.delete_secret(key_ref).await?;
+    }
+    self.get_account_escrow(pub_key).await
+  }
+
+  async fn set_reassembled_secret(
+    &self,
+    pub_key: &str,
+    secret_key: Option<Vec<u8>>,
+  ) -> Result<Option<AccountEscrow>> {
+    let escrow = match self.get_account_escrow(pub_key).await? {
+      Some(escrow) => escrow,
+      None => return Ok(None),
+    };
+    let key = escrow.confidential_account.0.as_slice();
+    let account_id = sqlx::query_scalar!(
+      r#"SELECT account_id FROM accounts WHERE public_key = ?"#,
+      key
+    )
+    .fetch_one(&self.pool)
+    .await?;
+    let mut reassembled = self.reassembled.write().expect("Escrow lock poisoned");
+    let is_reassembled = match secret_key {
+      Some(secret_key) => {
+        reassembled.insert(account_id, Zeroizing::new(secret_key));
+        true
+      }
+      None => {
+        reassembled.remove(&account_id);
+        DecryptionCache::global().invalidate_account(key);
+        false
+      }
+    };
+    Ok(Some(AccountEscrow {
+      reassembled: is_reassembled,
+      ..escrow
+    }))
+  }
+
+  async fn get_account_assets(&self, pub_key: &str) -> Result<Vec<AccountAsset>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        AccountAsset,
+        r#"
+          SELECT aa.asset_id as "asset_id: Uuid",
+            aa.account_asset_id, aa.account_id,
+            aa.balance, aa.enc_balance, aa.created_at, aa.updated_at
+          FROM account_assets as aa
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ?
+        "#,
+        key
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_account_asset(&self, pub_key: &str, asset_id: Uuid) -> Result<Option<AccountAsset>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        AccountAsset,
+        r#"
+          SELECT aa.asset_id as "asset_id: Uuid",
+            aa.account_asset_id, aa.account_id,
+            aa.balance, aa.enc_balance, aa.created_at, aa.updated_at
+          FROM account_assets as aa
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND aa.asset_id = ?
+        "#,
+        key,
+        asset_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_account_asset_by_id(&self, account_asset_id: i64) -> Result<Option<AccountAsset>> {
+    Ok(
+      sqlx::query_as!(
+        AccountAsset,
+        r#"
+          SELECT asset_id as "asset_id: Uuid",
+            account_asset_id, account_id,
+            balance, enc_balance, created_at, updated_at
+          FROM account_assets
+          WHERE account_asset_id = ?
+        "#,
+        account_asset_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_account_asset_with_secret(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+  ) -> Result<Option<AccountAssetWithSecret>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    let account_asset: Option<AccountAssetWithSecret> = sqlx::query_as(
+      r#"
+          SELECT aa.account_asset_id, aa.asset_id, aa.balance, aa.enc_balance,
+            acc.account_id, acc.public_key as confidential_account, acc.secret_key, acc.locked,
+            acc.escrow_threshold IS NOT NULL as escrowed
+          FROM account_assets as aa
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND aa.asset_id = ?
+        "#,
+    )
+    .bind(key)
+    .bind(asset_id)
+    .fetch_optional(&self.pool)
+    .await?;
+    match account_asset {
+      Some(mut account_asset) => {
+        let account = std::mem::take(&mut account_asset.account);
+        account_asset.account = self.load_secret(account).await?;
+        Ok(Some(account_asset))
+      }
+      None => Ok(None),
+    }
+  }
+
+  async fn create_account_asset(&self, account_asset: &UpdateAccountAsset) -> Result<AccountAsset> {
+    let mut db_tx = self.pool.begin().await?;
+    if let Some(id) = self
+      .check_balance_conflict(&mut db_tx, account_asset)
+      .await?
+    {
+      db_tx.commit().await?;
+      return self
+        .get_account_asset_by_id(id)
+        .await?
+        .ok_or_else(|| Error::not_found("Account Asset"));
+    }
+    let balance = account_asset.balance as i64;
+    let enc_balance = account_asset.enc_balance();
+    let source = account_asset.source.as_str();
+    let account = sqlx::query!(
+      r#"
+      INSERT INTO account_assets (account_id, asset_id, balance, enc_balance, balance_source)
+      VALUES (?, ?, ?, ?, ?)
+      ON CONFLICT(account_id, asset_id)
+        DO UPDATE SET balance = excluded.balance, enc_balance = excluded.enc_balance,
+          balance_source = excluded.balance_source, updated_at = CURRENT_TIMESTAMP
+      RETURNING account_asset_id as id
+      "#,
+      account_asset.account_id,
+      account_asset.asset_id,
+      balance,
+      enc_balance,
+      source,
+    )
+    .fetch_one(&mut *db_tx)
+    .await?;
+    self
+      .add_balance_history(&mut db_tx, account.id, account_asset)
+      .await?;
+    let account_asset = sqlx::query_as!(
+      AccountAsset,
+      r#"
+      SELECT asset_id as "asset_id: Uuid",
+        account_asset_id, account_id,
         balance, enc_balance, created_at, updated_at
         FROM account_assets
         WHERE account_asset_id = ?
       "#,
+      account.id,
+    )
+    .fetch_one(&mut *db_tx)
+    .await?;
+    db_tx.commit().await?;
+    Ok(account_asset)
+  }
+
+  async fn update_account_asset(&self, account_asset: &UpdateAccountAsset) -> Result<AccountAsset> {
+    let account_asset_id = if let Some(id) = account_asset.account_asset_id {
+      id
+    } else {
+      return self.create_account_asset(account_asset).await;
+    };
+    let mut db_tx = self.pool.begin().await?;
+    if self
+      .check_balance_conflict(&mut db_tx, account_asset)
+      .await?
+      .is_none()
+    {
+      let balance = account_asset.balance as i64;
+      let enc_balance = account_asset.enc_balance();
+      let source = account_asset.source.as_str();
+      sqlx::query!(
+        r#"
+        UPDATE account_assets SET balance = ?, enc_balance = ?, balance_source = ?,
+            updated_at = CURRENT_TIMESTAMP
+          WHERE account_asset_id = ?
+        RETURNING account_asset_id as id
+        "#,
+        balance,
+        enc_balance,
+        source,
         account_asset_id,
       )
-      .fetch_one(conn.as_mut())
+      .fetch_optional(&mut *db_tx)
+      .await?;
+      self
+        .add_balance_history(&mut db_tx, account_asset_id, account_asset)
+        .await?;
+    }
+
+    let account_asset = sqlx::query_as!(
+      AccountAsset,
+      r#"
+      SELECT asset_id as "asset_id: Uuid",
+        account_asset_id, account_id,
+        balance, enc_balance, created_at, updated_at
+        FROM account_assets
+        WHERE account_asset_id = ?
+      "#,
+      account_asset_id,
+    )
+    .fetch_one(&mut *db_tx)
+    .await?;
+    db_tx.commit().await?;
+    Ok(account_asset)
+  }
+
+  async fn get_asset_holders(&self, asset_id: Uuid) -> Result<Vec<AssetHolder>> {
+    Ok(
+      sqlx::query_as!(
+        AssetHolder,
+        r#"
+          SELECT acc.public_key as confidential_account,
+            aa.balance, aa.enc_balance, aa.updated_at
+          FROM account_assets as aa
+          JOIN accounts as acc using(account_id)
+          WHERE aa.asset_id = ? AND aa.balance > 0
+          ORDER BY aa.balance DESC
+        "#,
+        asset_id,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_balance_history(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    limit: u32,
+    offset: u32,
+  ) -> Result<Vec<BalanceHistory>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        BalanceHistory,
+        r#"
+          SELECT bh.asset_id as "asset_id: Uuid",
+            bh.action, bh.amount, bh.source, bh.balance, bh.enc_balance,
+            bh.block_number as "block_number: u32", bh.created_at
+          FROM balance_history as bh
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND bh.asset_id = ?
+          ORDER BY bh.id DESC
+          LIMIT ? OFFSET ?
+        "#,
+        key,
+        asset_id,
+        limit,
+        offset,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_balance_history_range(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    from: Option<chrono::NaiveDateTime>,
+    to: Option<chrono::NaiveDateTime>,
+  ) -> Result<Vec<BalanceHistory>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        BalanceHistory,
+        r#"
+          SELECT bh.asset_id as "asset_id: Uuid",
+            bh.action, bh.amount, bh.source, bh.balance, bh.enc_balance,
+            bh.block_number as "block_number: u32", bh.created_at
+          FROM balance_history as bh
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND bh.asset_id = ?
+            AND (? IS NULL OR bh.created_at >= ?)
+            AND (? IS NULL OR bh.created_at < ?)
+          ORDER BY bh.id
+        "#,
+        key,
+        asset_id,
+        from,
+        from,
+        to,
+        to,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_balance_at_block(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    block_number: u32,
+  ) -> Result<Option<BalanceHistory>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        BalanceHistory,
+        r#"
+          SELECT bh.asset_id as "asset_id: Uuid",
+            bh.action, bh.amount, bh.source, bh.balance, bh.enc_balance,
+            bh.block_number as "block_number: u32", bh.created_at
+          FROM balance_history as bh
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND bh.asset_id = ? AND bh.block_number <= ?
+          ORDER BY bh.block_number DESC, bh.id DESC
+          LIMIT 1
+        "#,
+        key,
+        asset_id,
+        block_number,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_balance_at_time(
+    &self,
+    pub_key: &str,
+    asset_id: Uuid,
+    timestamp: NaiveDateTime,
+  ) -> Result<Option<BalanceHistory>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        BalanceHistory,
+        r#"
+          SELECT bh.asset_id as "asset_id: Uuid",
+            bh.action, bh.amount, bh.source, bh.balance, bh.enc_balance,
+            bh.block_number as "block_number: u32", bh.created_at
+          FROM balance_history as bh
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND bh.asset_id = ? AND bh.created_at <= ?
+          ORDER BY bh.created_at DESC, bh.id DESC
+          LIMIT 1
+        "#,
+        key,
+        asset_id,
+        timestamp,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_amount_limits(&self, pub_key: &str) -> Result<Vec<AmountLimit>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        AmountLimit,
+        r#"
+        SELECT l.account_id, l.asset_id as "asset_id: Uuid", l.max_transfer, l.max_daily,
+          l.created_at, l.updated_at
+        FROM amount_limits as l
+          JOIN accounts as acc using(account_id)
+        WHERE acc.public_key = ?
+        ORDER BY l.id
+        "#,
+        key,
+      )
+      .fetch_all(&self.pool)
       .await?,
     )
   }
+
+  async fn set_amount_limit(
+    &self,
+    pub_key: &str,
+    limit: &SetAmountLimit,
+  ) -> Result<Option<AmountLimit>> {
+    let account = match self.get_account(pub_key).await? {
+      Some(account) => account,
+      None => return Ok(None),
+    };
+    let max_transfer = limit.max_transfer.map(|max| max as i64);
+    let max_daily = limit.max_daily.map(|max| max as i64);
+    let mut db_tx = self.pool.begin().await?;
+    let updated = sqlx::query_as!(
+      AmountLimit,
+      r#"
+      UPDATE amount_limits SET max_transfer = ?, max_daily = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE account_id = ? AND asset_id IS ?
+      RETURNING account_id, asset_id as "asset_id: Uuid", max_transfer, max_daily,
+        created_at, updated_at
+      "#,
+      max_transfer,
+      max_daily,
+      account.account_id,
+      limit.asset_id,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let limit = match updated {
+      Some(limit) => limit,
+      None => {
+        sqlx::query_as!(
+          AmountLimit,
+          r#"
+          INSERT INTO amount_limits (account_id, asset_id, max_transfer, max_daily)
+          VALUES (?, ?, ?, ?)
+          RETURNING account_id, asset_id as "asset_id: Uuid", max_transfer, max_daily,
+            created_at, updated_at
+          "#,
+          account.account_id,
+          limit.asset_id,
+          max_transfer,
+          max_daily,
+        )
+        .fetch_one(&mut *db_tx)
+        .await?
+      }
+    };
+    db_tx.commit().await?;
+    Ok(Some(limit))
+  }
+
+  async fn get_daily_amount_usage(&self, account_id: i64, asset_id: Option<Uuid>) -> Result<u64> {
+    let used = sqlx::query_scalar!(
+      r#"
+      SELECT COALESCE(SUM(amount), 0) as "used!: i64" FROM amount_usage
+        WHERE account_id = ? AND (? IS NULL OR asset_id = ?) AND created_at >= date('now')
+      "#,
+      account_id,
+      asset_id,
+      asset_id,
+    )
+    .fetch_one(&self.pool)
+    .await?;
+    Ok(used as u64)
+  }
+
+  async fn reserve_amount_usage(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+    amount: u64,
+  ) -> Result<Option<i64>> {
+    let amount = amount as i64;
+    // One statement, so the limits can't change between the check and the insert.
+    Ok(
+      sqlx::query_scalar!(
+        r#"
+        INSERT INTO amount_usage (account_id, asset_id, amount)
+        SELECT ?1, ?2, ?3
+        WHERE NOT EXISTS (
+          SELECT 1 FROM amount_limits as l
+          WHERE l.account_id = ?1 AND (l.asset_id IS NULL OR l.asset_id = ?2)
+            AND (?3 > l.max_transfer OR ?3 + (
+              SELECT COALESCE(SUM(u.amount), 0) FROM amount_usage as u
+              WHERE u.account_id = ?1 AND (l.asset_id IS NULL OR u.asset_id = l.asset_id)
+                AND u.created_at >= date('now')
+            ) > l.max_daily)
+        )
+        RETURNING id
+        "#,
+        account_id,
+        asset_id,
+        amount,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_amount_usage(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+    amount: u64,
+  ) -> Result<i64> {
+    let amount = amount as i64;
+    Ok(
+      sqlx::query_scalar!(
+        r#"
+        INSERT INTO amount_usage (account_id, asset_id, amount)
+        VALUES (?, ?, ?)
+        RETURNING id
+        "#,
+        account_id,
+        asset_id,
+        amount,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn release_amount_usage(&self, usage_id: i64) -> Result<()> {
+    sqlx::query!(
+      r#"
+      DELETE FROM amount_usage WHERE id = ?
+      "#,
+      usage_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn get_approvals(&self, status: Option<&str>) -> Result<Vec<Approval>> {
+    Ok(
+      sqlx::query_as!(
+        Approval,
+        r#"
+        SELECT * FROM approvals
+          WHERE ? IS NULL OR status = ?
+          ORDER BY approval_id DESC
+        "#,
+        status,
+        status,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_approval(&self, approval_id: i64) -> Result<Option<Approval>> {
+    Ok(
+      sqlx::query_as!(
+        Approval,
+        r#"SELECT * FROM approvals WHERE approval_id = ?"#,
+        approval_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_approval(&self, approval: &CreateApproval) -> Result<Approval> {
+    Ok(
+      sqlx::query_as!(
+        Approval,
+        r#"
+      INSERT INTO approvals (operation, target, request_hash, requested_by)
+      VALUES (?, ?, ?, ?)
+      RETURNING *
+      "#,
+        approval.operation,
+        approval.target,
+        approval.request_hash,
+        approval.requested_by,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_approval_status(
+    &self,
+    approval_id: i64,
+    from: &str,
+    status: &str,
+    decided_by: Option<&str>,
+  ) -> Result<Option<Approval>> {
+    Ok(
+      sqlx::query_as!(
+        Approval,
+        r#"
+      UPDATE approvals SET status = ?, decided_by = COALESCE(?, decided_by), updated_at = CURRENT_TIMESTAMP
+        WHERE approval_id = ? AND status = ?
+      RETURNING *
+      "#,
+        status,
+        decided_by,
+        approval_id,
+        from,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_screening_entries(&self) -> Result<Vec<ScreeningEntry>> {
+    Ok(
+      sqlx::query_as!(
+        ScreeningEntry,
+        r#"SELECT subject, list, reason, created_at, updated_at FROM screening_list ORDER BY subject"#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_screening_entry(&self, subject: &str) -> Result<Option<ScreeningEntry>> {
+    let subject = subject_to_hex(subject)?;
+    Ok(
+      sqlx::query_as!(
+        ScreeningEntry,
+        r#"SELECT subject, list, reason, created_at, updated_at FROM screening_list WHERE subject = ?"#,
+        subject,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_screening_entry(
+    &self,
+    entry: &CreateScreeningEntry,
+  ) -> Result<Option<ScreeningEntry>> {
+    let subject = entry.subject();
+    let list = entry.list.as_str();
+    Ok(
+      sqlx::query_as!(
+        ScreeningEntry,
+        r#"
+      INSERT INTO screening_list (subject, list, reason)
+      VALUES (?, ?, ?)
+      ON CONFLICT(subject) DO NOTHING
+      RETURNING subject, list, reason, created_at, updated_at
+      "#,
+        subject,
+        list,
+        entry.reason,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn update_screening_entry(
+    &self,
+    subject: &str,
+    entry: &UpdateScreeningEntry,
+  ) -> Result<Option<ScreeningEntry>> {
+    let subject = subject_to_hex(subject)?;
+    let list = entry.list.as_str();
+    Ok(
+      sqlx::query_as!(
+        ScreeningEntry,
+        r#"
+      UPDATE screening_list SET list = ?, reason = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE subject = ?
+      RETURNING subject, list, reason, created_at, updated_at
+      "#,
+        list,
+        entry.reason,
+        subject,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn delete_screening_entry(&self, subject: &str) -> Result<bool> {
+    let subject = subject_to_hex(subject)?;
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM screening_list WHERE subject = ?
+      "#,
+      subject,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  async fn get_proof_pools(&self, pub_key: &str, asset_id: Uuid) -> Result<Vec<ProofPool>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        ProofPool,
+        r#"
+        SELECT p.pool_id, p.account_asset_id, p.asset_id as "asset_id: Uuid", p.receiver,
+          p.auditors, p.amount, p.size, p.generated, p.used, p.status, p.error,
+          p.expires_at, p.created_at, p.updated_at
+        FROM proof_pools as p
+          JOIN account_assets as aa using(account_asset_id)
+          JOIN accounts as acc using(account_id)
+        WHERE acc.public_key = ? AND p.asset_id = ?
+        ORDER BY p.pool_id DESC
+        "#,
+        key,
+        asset_id,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_proof_pool(&self, pool_id: i64) -> Result<Option<ProofPool>> {
+    Ok(
+      sqlx::query_as!(
+        ProofPool,
+        r#"
+        SELECT pool_id, account_asset_id, asset_id as "asset_id: Uuid", receiver, auditors,
+          amount, size, generated, used, status, error, expires_at, created_at, updated_at
+        FROM proof_pools
+        WHERE pool_id = ?
+        "#,
+        pool_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_active_proof_pool(&self, account_asset_id: i64) -> Result<Option<ProofPool>> {
+    Ok(
+      sqlx::query_as!(
+        ProofPool,
+        r#"
+        SELECT pool_id, account_asset_id, asset_id as "asset_id: Uuid", receiver, auditors,
+          amount, size, generated, used, status, error, expires_at, created_at, updated_at
+        FROM proof_pools
+        WHERE account_asset_id = ? AND status IN ('generating', 'ready')
+        "#,
+        account_asset_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_expired_proof_pools(&self) -> Result<Vec<ProofPool>> {
+    Ok(
+      sqlx::query_as!(
+        ProofPool,
+        r#"
+        SELECT pool_id, account_asset_id, asset_id as "asset_id: Uuid", receiver, auditors,
+          amount, size, generated, used, status, error, expires_at, created_at, updated_at
+        FROM proof_pools
+        WHERE status IN ('generating', 'ready') AND expires_at <= CURRENT_TIMESTAMP
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_proof_pool(
+    &self,
+    account_asset_id: i64,
+    asset_id: Uuid,
+    pool: &CreateProofPool,
+    expires_in: u64,
+  ) -> Result<ProofPool> {
+    let receiver = pool.receiver_hex();
+    let auditors = pool.auditors_hex()?;
+    let amount = pool.amount as i64;
+    let size = pool.size as i64;
+    let expires_in = format!("+{expires_in} seconds");
+    Ok(
+      sqlx::query_as!(
+        ProofPool,
+        r#"
+      INSERT INTO proof_pools (account_asset_id, asset_id, receiver, auditors, amount, size, expires_at)
+      VALUES (?, ?, ?, ?, ?, ?, datetime('now', ?))
+      RETURNING pool_id, account_asset_id, asset_id as "asset_id: Uuid", receiver, auditors,
+        amount, size, generated, used, status, error, expires_at, created_at, updated_at
+      "#,
+        account_asset_id,
+        asset_id,
+        receiver,
+        auditors,
+        amount,
+        size,
+        expires_in,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_pooled_proof(&self, proof: &PooledProof) -> Result<Option<ProofPool>> {
+    let mut db_tx = self.pool.begin().await?;
+    sqlx::query!(
+      r#"
+      INSERT INTO pool_proofs (pool_id, seq, proof, enc_balance, enc_amount)
+      VALUES (?, ?, ?, ?, ?)
+      "#,
+      proof.pool_id,
+      proof.seq,
+      proof.proof,
+      proof.enc_balance,
+      proof.enc_amount,
+    )
+    .execute(&mut *db_tx)
+    .await?;
+    let pool = sqlx::query_as!(
+      ProofPool,
+      r#"
+      UPDATE proof_pools SET generated = generated + 1,
+        status = CASE WHEN generated + 1 >= size THEN 'ready' ELSE status END,
+        updated_at = CURRENT_TIMESTAMP
+        WHERE pool_id = ? AND status = 'generating'
+      RETURNING pool_id, account_asset_id, asset_id as "asset_id: Uuid", receiver, auditors,
+        amount, size, generated, used, status, error, expires_at, created_at, updated_at
+      "#,
+      proof.pool_id,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    // Don't add proofs to pools that are no longer generating.
+    if pool.is_some() {
+      db_tx.commit().await?;
+    }
+    Ok(pool)
+  }
+
+  async fn set_proof_pool_status(
+    &self,
+    pool_id: i64,
+    status: &str,
+    error: Option<&str>,
+  ) -> Result<Option<ProofPool>> {
+    Ok(
+      sqlx::query_as!(
+        ProofPool,
+        r#"
+      UPDATE proof_pools SET status = ?, error = COALESCE(?, error), updated_at = CURRENT_TIMESTAMP
+        WHERE pool_id = ? AND status IN ('generating', 'ready')
+      RETURNING pool_id, account_asset_id, asset_id as "asset_id: Uuid", receiver, auditors,
+        amount, size, generated, used, status, error, expires_at, created_at, updated_at
+      "#,
+        status,
+        error,
+        pool_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn reserve_pooled_proof(&self, pool_id: i64) -> Result<Option<PooledProof>> {
+    Ok(
+      sqlx::query_as!(
+        PooledProof,
+        r#"
+      UPDATE pool_proofs SET status = 'reserved', updated_at = CURRENT_TIMESTAMP
+        WHERE pool_proof_id = (
+          SELECT pool_proof_id FROM pool_proofs
+            WHERE pool_id = ? AND status = 'available'
+            ORDER BY seq LIMIT 1
+        )
+      RETURNING pool_proof_id, pool_id, seq, proof, enc_balance, enc_amount, status
+      "#,
+        pool_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn finish_pooled_proof(&self, pool_proof_id: i64, used: bool) -> Result<Option<ProofPool>> {
+    let status = if used { "used" } else { "available" };
+    let mut db_tx = self.pool.begin().await?;
+    let pool_id = match sqlx::query!(
+      r#"
+      UPDATE pool_proofs SET status = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE pool_proof_id = ? AND status = 'reserved'
+      RETURNING pool_id
+      "#,
+      status,
+      pool_proof_id,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?
+    {
+      Some(rec) => rec.pool_id,
+      None => return Ok(None),
+    };
+    let used = used as i64;
+    let pool = sqlx::query_as!(
+      ProofPool,
+      r#"
+      UPDATE proof_pools SET used = used + ?,
+        status = CASE WHEN status = 'ready' AND used + ? >= size THEN 'exhausted' ELSE status END,
+        updated_at = CURRENT_TIMESTAMP
+        WHERE pool_id = ?
+      RETURNING pool_id, account_asset_id, asset_id as "asset_id: Uuid", receiver, auditors,
+        amount, size, generated, used, status, error, expires_at, created_at, updated_at
+      "#,
+      used,
+      used,
+      pool_id,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    db_tx.commit().await?;
+    Ok(pool)
+  }
+
+  async fn release_pooled_proofs(&self, pool_id: i64) -> Result<Vec<PooledProof>> {
+    Ok(
+      sqlx::query_as!(
+        PooledProof,
+        r#"
+      UPDATE pool_proofs SET status = 'released', updated_at = CURRENT_TIMESTAMP
+        WHERE pool_id = ? AND status = 'available'
+      RETURNING pool_proof_id, pool_id, seq, proof, enc_balance, enc_amount, status
+      "#,
+        pool_id,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_audit_log(&self, entry: &AddAuditLogEntry) -> Result<()> {
+    let mut db_tx = self.pool.begin().await?;
+    // The insert takes the write lock, so no other entry can be chained before the commit.
+    let entry = sqlx::query_as!(
+      AuditLogEntry,
+      r#"
+      INSERT INTO audit_log (account_id, asset_id, action, amount, user)
+      VALUES (?, ?, ?, ?, ?)
+      RETURNING id, account_id, asset_id as "asset_id: Uuid", action, amount, user, created_at,
+        prev_hash, hash
+      "#,
+      entry.account_id,
+      entry.asset_id,
+      entry.action,
+      entry.amount,
+      entry.user,
+    )
+    .fetch_one(&mut *db_tx)
+    .await?;
+    let prev_hash = sqlx::query_scalar!(
+      r#"
+      SELECT hash as "hash!: Vec<u8>" FROM audit_log
+        WHERE id < ? AND hash IS NOT NULL
+        ORDER BY id DESC
+        LIMIT 1
+      "#,
+      entry.id,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?
+    .unwrap_or_else(|| GENESIS_HASH.to_vec());
+    let hash = entry_hash(&prev_hash, &entry);
+    let hash = hash.as_slice();
+    sqlx::query!(
+      r#"
+      UPDATE audit_log SET prev_hash = ?, hash = ? WHERE id = ?
+      "#,
+      prev_hash,
+      hash,
+      entry.id,
+    )
+    .execute(&mut *db_tx)
+    .await?;
+    db_tx.commit().await?;
+    Ok(())
+  }
+
+  async fn get_audit_log_page(&self, after_id: i64, limit: u32) -> Result<Vec<AuditLogEntry>> {
+    Ok(
+      sqlx::query_as!(
+        AuditLogEntry,
+        r#"
+        SELECT id, account_id, asset_id as "asset_id: Uuid", action, amount, user, created_at,
+          prev_hash, hash
+        FROM audit_log
+          WHERE id > ?
+          ORDER BY id
+          LIMIT ?
+        "#,
+        after_id,
+        limit,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_recent_sender_amounts(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+    limit: u32,
+  ) -> Result<Vec<i64>> {
+    Ok(
+      sqlx::query_scalar!(
+        r#"
+        SELECT amount as "amount!: i64" FROM audit_log
+          WHERE account_id = ? AND action = 'sender_proof' AND amount IS NOT NULL
+            AND asset_id IS ?
+          ORDER BY id DESC
+          LIMIT ?
+        "#,
+        account_id,
+        asset_id,
+        limit,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn count_recent_audit_log(&self, account_id: i64, action: &str, secs: u32) -> Result<i64> {
+    let since = format!("-{secs} seconds");
+    Ok(
+      sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!: i64" FROM audit_log
+          WHERE account_id = ? AND action = ? AND created_at >= datetime('now', ?)
+        "#,
+        account_id,
+        action,
+        since,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_anomaly_alerts(
+    &self,
+    rule: Option<&str>,
+    pub_key: Option<&str>,
+  ) -> Result<Vec<AnomalyAlert>> {
+    let account = pub_key.map(subject_to_hex).transpose()?;
+    Ok(
+      sqlx::query_as!(
+        AnomalyAlert,
+        r#"
+        SELECT alert_id, rule, confidential_account, asset_id as "asset_id: Uuid", user, details,
+          created_at
+        FROM anomaly_alerts
+          WHERE (? IS NULL OR rule = ?) AND (? IS NULL OR confidential_account = ?)
+          ORDER BY alert_id DESC
+        "#,
+        rule,
+        rule,
+        account,
+        account,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn has_recent_anomaly_alert(&self, rule: &str, pub_key: &str, secs: u32) -> Result<bool> {
+    let account = subject_to_hex(pub_key)?;
+    let since = format!("-{secs} seconds");
+    let found = sqlx::query_scalar!(
+      r#"
+      SELECT 1 as "found!: i64" FROM anomaly_alerts
+        WHERE rule = ? AND confidential_account = ? AND created_at >= datetime('now', ?)
+        LIMIT 1
+      "#,
+      rule,
+      account,
+      since,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    Ok(found.is_some())
+  }
+
+  async fn add_anomaly_alert(&self, alert: &AddAnomalyAlert) -> Result<AnomalyAlert> {
+    let account = subject_to_hex(&alert.confidential_account)?;
+    Ok(
+      sqlx::query_as!(
+        AnomalyAlert,
+        r#"
+      INSERT INTO anomaly_alerts (rule, confidential_account, asset_id, user, details)
+      VALUES (?, ?, ?, ?, ?)
+      RETURNING alert_id, rule, confidential_account, asset_id as "asset_id: Uuid", user, details,
+        created_at
+      "#,
+        alert.rule,
+        account,
+        alert.asset_id,
+        alert.user,
+        alert.details,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_proof(&self, proof: &AddProof) -> Result<i64> {
+    let proof_id = sqlx::query_scalar!(
+      r#"
+      INSERT INTO proofs (account_id, asset_id, proof_type, amount, receiver, proof)
+      VALUES (?, ?, ?, ?, ?, ?)
+      RETURNING proof_id
+      "#,
+      proof.account_id,
+      proof.asset_id,
+      proof.proof_type,
+      proof.amount,
+      proof.receiver,
+      proof.proof,
+    )
+    .fetch_one(&self.pool)
+    .await?;
+    Ok(proof_id)
+  }
+
+  async fn get_account_proofs(
+    &self,
+    pub_key: &str,
+    asset_id: Option<Uuid>,
+    proof_type: Option<&str>,
+  ) -> Result<Vec<ProofRecord>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        ProofRecord,
+        r#"
+          SELECT p.proof_id, p.account_id, acc.public_key as confidential_account,
+            p.asset_id as "asset_id: Uuid", p.proof_type, p.amount, p.receiver, p.proof,
+            p.created_at
+          FROM proofs as p
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND (? IS NULL OR p.asset_id = ?)
+            AND (? IS NULL OR p.proof_type = ?)
+          ORDER BY p.proof_id DESC
+        "#,
+        key,
+        asset_id,
+        asset_id,
+        proof_type,
+        proof_type,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_proof(&self, proof_id: i64) -> Result<Option<ProofRecord>> {
+    Ok(
+      sqlx::query_as!(
+        ProofRecord,
+        r#"
+          SELECT p.proof_id, p.account_id, acc.public_key as confidential_account,
+            p.asset_id as "asset_id: Uuid", p.proof_type, p.amount, p.receiver, p.proof,
+            p.created_at
+          FROM proofs as p
+          JOIN accounts as acc using(account_id)
+          WHERE p.proof_id = ?
+        "#,
+        proof_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_position_locks(&self, pub_key: &str) -> Result<Vec<PositionLock>> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    Ok(
+      sqlx::query_as!(
+        PositionLock,
+        r#"
+          SELECT l.lock_id, l.account_id, acc.public_key as confidential_account,
+            l.asset_id as "asset_id: Uuid", l.mode, l.reason, l.starts_at, l.ends_at, l.created_at
+          FROM position_locks as l
+          JOIN accounts as acc using(account_id)
+          WHERE acc.public_key = ? AND l.ends_at > CURRENT_TIMESTAMP
+          ORDER BY l.starts_at
+        "#,
+        key,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_active_position_lock(
+    &self,
+    account_id: i64,
+    asset_id: Option<Uuid>,
+  ) -> Result<Option<PositionLock>> {
+    Ok(
+      sqlx::query_as!(
+        PositionLock,
+        r#"
+          SELECT l.lock_id, l.account_id, acc.public_key as confidential_account,
+            l.asset_id as "asset_id: Uuid", l.mode, l.reason, l.starts_at, l.ends_at, l.created_at
+          FROM position_locks as l
+          JOIN accounts as acc using(account_id)
+          WHERE l.account_id = ? AND (l.asset_id IS NULL OR ? IS NULL OR l.asset_id = ?)
+            AND l.starts_at <= CURRENT_TIMESTAMP AND l.ends_at > CURRENT_TIMESTAMP
+          ORDER BY l.mode = 'reject' DESC, l.ends_at DESC
+          LIMIT 1
+        "#,
+        account_id,
+        asset_id,
+        asset_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_position_lock(
+    &self,
+    pub_key: &str,
+    lock: &CreatePositionLock,
+  ) -> Result<Option<PositionLock>> {
+    let account = match self.get_account(pub_key).await? {
+      Some(account) => account,
+      None => return Ok(None),
+    };
+    let mode = lock.mode.as_str();
+    let starts_at = lock.starts_at();
+    // `datetime` stores the window in the same format as `CURRENT_TIMESTAMP`.
+    let lock_id = sqlx::query_scalar!(
+      r#"
+      INSERT INTO position_locks (account_id, asset_id, mode, reason, starts_at, ends_at)
+      VALUES (?, ?, ?, ?, datetime(?), datetime(?))
+      RETURNING lock_id
+      "#,
+      account.account_id,
+      lock.asset_id,
+      mode,
+      lock.reason,
+      starts_at,
+      lock.ends_at,
+    )
+    .fetch_one(&self.pool)
+    .await?;
+    Ok(
+      sqlx::query_as!(
+        PositionLock,
+        r#"
+          SELECT l.lock_id, l.account_id, acc.public_key as confidential_account,
+            l.asset_id as "asset_id: Uuid", l.mode, l.reason, l.starts_at, l.ends_at, l.created_at
+          FROM position_locks as l
+          JOIN accounts as acc using(account_id)
+          WHERE l.lock_id = ?
+        "#,
+        lock_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn delete_position_lock(&self, pub_key: &str, lock_id: i64) -> Result<bool> {
+    let pub_key = PublicKey::from_str(pub_key)?;
+    let key = pub_key.0.as_slice();
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM position_locks
+        WHERE lock_id = ? AND account_id IN (SELECT account_id FROM accounts WHERE public_key = ?)
+      "#,
+      lock_id,
+      key,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  async fn get_balance_conflicts(&self, status: Option<&str>) -> Result<Vec<BalanceConflict>> {
+    Ok(
+      sqlx::query_as!(
+        BalanceConflict,
+        r#"
+          SELECT c.conflict_id, c.account_asset_id, acc.public_key as confidential_account,
+            aa.asset_id as "asset_id: Uuid", c.source, c.balance, c.enc_balance,
+            c.block_number as "block_number: u32", c.current_source, c.current_balance,
+            c.strategy, c.status, c.created_at, c.resolved_at
+          FROM balance_conflicts as c
+          JOIN account_assets as aa using(account_asset_id)
+          JOIN accounts as acc using(account_id)
+          WHERE ? IS NULL OR c.status = ?
+          ORDER BY c.conflict_id DESC
+        "#,
+        status,
+        status,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_balance_conflict(&self, conflict_id: i64) -> Result<Option<BalanceConflict>> {
+    Ok(
+      sqlx::query_as!(
+        BalanceConflict,
+        r#"
+          SELECT c.conflict_id, c.account_asset_id, acc.public_key as confidential_account,
+            aa.asset_id as "asset_id: Uuid", c.source, c.balance, c.enc_balance,
+            c.block_number as "block_number: u32", c.current_source, c.current_balance,
+            c.strategy, c.status, c.created_at, c.resolved_at
+          FROM balance_conflicts as c
+          JOIN account_assets as aa using(account_asset_id)
+          JOIN accounts as acc using(account_id)
+          WHERE c.conflict_id = ?
+        "#,
+        conflict_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn resolve_balance_conflict(
+    &self,
+    conflict_id: i64,
+    accept: bool,
+  ) -> Result<Option<BalanceConflict>> {
+    let status = if accept {
+      CONFLICT_ACCEPTED
+    } else {
+      CONFLICT_REJECTED
+    };
+    let mut db_tx = self.pool.begin().await?;
+    let conflict = sqlx::query!(
+      r#"
+      UPDATE balance_conflicts SET status = ?, resolved_at = CURRENT_TIMESTAMP
+        WHERE conflict_id = ? AND status = ?
+      RETURNING account_asset_id, source, balance, enc_balance, block_number
+      "#,
+      status,
+      conflict_id,
+      CONFLICT_PENDING,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    let conflict = match conflict {
+      Some(conflict) => conflict,
+      None => return Ok(None),
+    };
+    if accept {
+      let account_asset = sqlx::query!(
+        r#"
+        UPDATE account_assets SET balance = ?, enc_balance = ?, balance_source = ?,
+            updated_at = CURRENT_TIMESTAMP
+          WHERE account_asset_id = ?
+        RETURNING account_id, asset_id as "asset_id: Uuid"
+        "#,
+        conflict.balance,
+        conflict.enc_balance,
+        conflict.source,
+        conflict.account_asset_id,
+      )
+      .fetch_one(&mut *db_tx)
+      .await?;
+      let action = BalanceAction::ResolveConflict.as_str();
+      let source = format!("POST /admin/balance_conflicts/{conflict_id}/resolve");
+      sqlx::query!(
+        r#"
+        INSERT INTO balance_history
+          (account_asset_id, account_id, asset_id, balance, enc_balance, block_number,
+            action, source)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        conflict.account_asset_id,
+        account_asset.account_id,
+        account_asset.asset_id,
+        conflict.balance,
+        conflict.enc_balance,
+        conflict.block_number,
+        action,
+        source,
+      )
+      .execute(&mut *db_tx)
+      .await?;
+    }
+    db_tx.commit().await?;
+    self.get_balance_conflict(conflict_id).await
+  }
+
+  fn reload_config(&self, config: &EnvConfig) -> Result<()> {
+    let strategy = BalanceConflictStrategy::from_config(config)?;
+    *self
+      .conflict_strategy
+      .write()
+      .expect("Conflict strategy lock poisoned") = strategy;
+    Ok(())
+  }
+
+  async fn get_feature_flags(&self) -> Result<Vec<FeatureFlag>> {
+    Ok(
+      sqlx::query_as!(
+        FeatureFlag,
+        r#"
+          SELECT flag_id, endpoint, enabled, reason, created_at, updated_at
+          FROM feature_flags
+          ORDER BY flag_id
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_feature_flag(&self, flag: &SetFeatureFlag) -> Result<FeatureFlag> {
+    let endpoint = flag.endpoint.trim();
+    Ok(
+      sqlx::query_as!(
+        FeatureFlag,
+        r#"
+        INSERT INTO feature_flags (endpoint, enabled, reason)
+        VALUES (?, ?, ?)
+        ON CONFLICT(endpoint) DO UPDATE SET
+          enabled = excluded.enabled, reason = excluded.reason, updated_at = CURRENT_TIMESTAMP
+        RETURNING flag_id, endpoint, enabled, reason, created_at, updated_at
+        "#,
+        endpoint,
+        flag.enabled,
+        flag.reason,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn delete_feature_flag(&self, flag_id: i64) -> Result<bool> {
+    let res = sqlx::query!(r#"DELETE FROM feature_flags WHERE flag_id = ?"#, flag_id)
+      .execute(&self.pool)
+      .await?;
+    Ok(res.rows_affected() > 0)
+  }
+}
+
+/// Normalize a screening subject (confidential account or DID) to `0x` prefixed hex.
+fn subject_to_hex(subject: &str) -> Result<String> {
+  let subject = PublicKey::from_str(subject)?;
+  Ok(format!("0x{}", hex::encode(subject.0)))
+}
+
+impl SqliteConfidentialRepository {
+  async fn add_balance_history(
+    &self,
+    conn: &mut sqlx::SqliteConnection,
+    account_asset_id: i64,
+    account_asset: &UpdateAccountAsset,
+  ) -> Result<()> {
+    let balance = account_asset.balance as i64;
+    let enc_balance = account_asset.enc_balance();
+    let action = account_asset.action.as_str();
+    sqlx::query!(
+      r#"
+      INSERT INTO balance_history
+        (account_asset_id, account_id, asset_id, balance, enc_balance, block_number,
+          action, amount, source)
+      VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+      "#,
+      account_asset_id,
+      account_asset.account_id,
+      account_asset.asset_id,
+      balance,
+      enc_balance,
+      account_asset.block_number,
+      action,
+      account_asset.amount,
+      account_asset.history_source,
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+  }
+
+  /// Check a balance update against the stored balance and record it if they conflict.
+  /// Returns the account asset id if the update must not be applied.
+  async fn check_balance_conflict(
+    &self,
+    conn: &mut sqlx::SqliteConnection,
+    update: &UpdateAccountAsset,
+  ) -> Result<Option<i64>> {
+    let current = sqlx::query!(
+      r#"
+      SELECT account_asset_id, balance, enc_balance, balance_source
+        FROM account_assets
+        WHERE account_id = ? AND asset_id = ?
+      "#,
+      update.account_id,
+      update.asset_id,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let current = match current {
+      Some(current) => current,
+      None => return Ok(None),
+    };
+    let current_source = BalanceSource::from_str(&current.balance_source)?;
+    if !update.conflicts_with(current.balance, &current.enc_balance, current_source) {
+      return Ok(None);
+    }
+    let conflict_strategy = *self
+      .conflict_strategy
+      .read()
+      .expect("Conflict strategy lock poisoned");
+    let status = match conflict_strategy.resolve(update.source, current_source) {
+      Some(true) => CONFLICT_APPLIED,
+      Some(false) => CONFLICT_DISCARDED,
+      None => CONFLICT_PENDING,
+    };
+    let source = update.source.as_str();
+    let balance = update.balance as i64;
+    let enc_balance = update.enc_balance();
+    let strategy = conflict_strategy.as_str();
+    let conflict_id = sqlx::query_scalar!(
+      r#"
+      INSERT INTO balance_conflicts (account_asset_id, source, balance, enc_balance, block_number,
+        current_source, current_balance, strategy, status, resolved_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, CASE WHEN ? THEN NULL ELSE CURRENT_TIMESTAMP END)
+      RETURNING conflict_id
+      "#,
+      current.account_asset_id,
+      source,
+      balance,
+      enc_balance,
+      update.block_number,
+      current.balance_source,
+      current.balance,
+      strategy,
+      status,
+      status == CONFLICT_PENDING,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    log::warn!(
+      "Balance conflict {conflict_id} of account asset {}: {source} balance {balance}, \
+        stored {} balance {} ({status})",
+      current.account_asset_id,
+      current.balance_source,
+      current.balance,
+    );
+    if status == CONFLICT_APPLIED {
+      Ok(None)
+    } else {
+      Ok(Some(current.account_asset_id))
+    }
+  }
 }
@@ -0,0 +1,134 @@
+use actix_web::web::Data;
+use async_trait::async_trait;
+use codec::Encode;
+
+use confidential_assets::ElgamalPublicKey;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  ScreeningList, ScreeningSubject,
+};
+
+use crate::repo::Repository;
+
+pub type AppScreening = Data<Screening>;
+
+/// Screens receivers before sending to them.
+///
+/// Implement this to plug in a sanctions screening service.
+#[async_trait]
+pub trait ScreeningProvider: Send + Sync + 'static {
+  /// Returns the reason if sending to the subject isn't allowed.
+  async fn screen(&self, subject: &ScreeningSubject) -> Result<Option<String>>;
+}
+
+/// Static allow/deny lists from the `screening_list` table.
+///
+/// Denied accounts and identities are always blocked.  With `allow_list` only receivers
+/// with their account or identity on the allow list can be sent to.
+pub struct StaticListScreening {
+  repo: Repository,
+  allow_list: bool,
+}
+
+impl StaticListScreening {
+  pub fn new(repo: Repository, allow_list: bool) -> Self {
+    Self { repo, allow_list }
+  }
+}
+
+#[async_trait]
+impl ScreeningProvider for StaticListScreening {
+  async fn screen(&self, subject: &ScreeningSubject) -> Result<Option<String>> {
+    let mut entries = vec![self.repo.get_screening_entry(&subject.receiver).await?];
+    if let Some(did) = &subject.did {
+      entries.push(self.repo.get_screening_entry(did).await?);
+    }
+    let mut allowed = false;
+    for entry in entries.into_iter().flatten() {
+      match entry.list.parse()? {
+        ScreeningList::Deny => {
+          let reason = entry.reason.as_deref().unwrap_or("no reason given");
+          return Ok(Some(format!("{} is denied ({reason})", entry.subject)));
+        }
+        ScreeningList::Allow => allowed = true,
+      }
+    }
+    if self.allow_list && !allowed {
+      return Ok(Some(format!(
+        "{} isn't on the allow list",
+        subject.receiver
+      )));
+    }
+    Ok(None)
+  }
+}
+
+/// Receiver screening before generating sender proofs or creating settlements.
+pub struct Screening {
+  repo: Repository,
+  provider: Option<Box<dyn ScreeningProvider>>,
+}
+
+impl Screening {
+  /// Use the static lists as configured by `SCREENING`: `none` (default), `deny_list`
+  /// or `allow_list`.
+  pub fn from_env(repo: Repository) -> Result<AppScreening> {
+    let mode = std::env::var("SCREENING").unwrap_or_default();
+    let provider: Option<Box<dyn ScreeningProvider>> = match mode.as_str() {
+      "" | "none" => None,
+      "deny_list" => Some(Box::new(StaticListScreening::new(repo.clone(), false))),
+      "allow_list" => Some(Box::new(StaticListScreening::new(repo.clone(), true))),
+      _ => return Err(Error::Other(format!("Unknown SCREENING mode: {mode}"))),
+    };
+    if provider.is_some() {
+      log::info!("Screening receivers: {mode}");
+    }
+    Ok(Data::new(Self { repo, provider }))
+  }
+
+  /// Use a custom screening provider.
+  pub fn with_provider(repo: Repository, provider: impl ScreeningProvider) -> AppScreening {
+    Data::new(Self {
+      repo,
+      provider: Some(Box::new(provider)),
+    })
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.provider.is_some()
+  }
+
+  /// Screen a receiver.  Fails with `403 Forbidden` if sending to it isn't allowed.
+  ///
+  /// Without a `did`, the identity saved on the account is used if the receiver is one
+  /// of our accounts.
+  pub async fn screen_receiver(
+    &self,
+    receiver: &ElgamalPublicKey,
+    did: Option<String>,
+  ) -> Result<()> {
+    let provider = match &self.provider {
+      Some(provider) => provider,
+      None => return Ok(()),
+    };
+    let mut subject = ScreeningSubject {
+      receiver: format!("0x{}", hex::encode(receiver.encode())),
+      did,
+    };
+    if subject.did.is_none() {
+      subject.did = self
+        .repo
+        .get_account(&subject.receiver)
+        .await?
+        .and_then(|account| account.did);
+    }
+    if let Some(reason) = provider.screen(&subject).await? {
+      log::warn!("Receiver screening failed: {reason}");
+      return Err(Error::Forbidden(format!(
+        "Receiver screening failed: {reason}"
+      )));
+    }
+    Ok(())
+  }
+}
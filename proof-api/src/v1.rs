@@ -3,7 +3,29 @@ use actix_web::web;
 #[cfg(feature = "track_balances")]
 pub mod account_assets;
 pub mod accounts;
+pub mod anomalies;
+pub mod api_keys;
+pub mod approvals;
 pub mod assets;
+#[cfg(feature = "track_balances")]
+pub mod balance_conflicts;
+pub mod decrypt_tokens;
+pub mod decryption_proofs;
+pub mod escrow;
+pub mod feature_flags;
+pub mod fuzz;
+pub mod integrity;
+pub mod jobs;
+pub mod limits;
+pub mod position_locks;
+#[cfg(feature = "track_balances")]
+pub mod proof_pools;
+pub mod proofs;
+pub mod receipts;
+pub mod screening;
+pub mod selftest;
+pub mod stats;
+pub mod user_access;
 pub mod users;
 
 pub fn service(cfg: &mut web::ServiceConfig) {
@@ -11,6 +33,24 @@ pub fn service(cfg: &mut web::ServiceConfig) {
     web::scope("/v1")
       //.configure(users::service)
       .configure(assets::service)
-      .configure(accounts::service),
+      .configure(accounts::service)
+      .configure(anomalies::service)
+      .configure(api_keys::service)
+      .configure(approvals::service)
+      .configure(decrypt_tokens::service)
+      .configure(decryption_proofs::service)
+      .configure(escrow::service)
+      .configure(feature_flags::service)
+      .configure(fuzz::service)
+      .configure(integrity::service)
+      .configure(jobs::service)
+      .configure(limits::service)
+      .configure(position_locks::service)
+      .configure(proofs::service)
+      .configure(receipts::service)
+      .configure(screening::service)
+      .configure(selftest::service)
+      .configure(stats::service)
+      .configure(user_access::service),
   );
 }
@@ -1,60 +1,301 @@
-use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use std::time::Instant;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+use codec::Encode;
+use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
+use confidential_assets::CipherText;
+
 use polymesh_private_proof_shared::{
-  error::Error, AccountAssetWithProof, AccountDecryptRequest, BurnProofRequest, CreateAccountAsset,
-  ReceiverVerifyRequest, SenderProofRequest, UpdateAccountAssetBalanceRequest,
+  error::Error, spawn_proof, AccountAssetExport, AccountAssetExportRequest, AccountAssetWithProof,
+  AccountDecryptRequest, AddProof, ApprovalOperation, BurnProofRequest, CreateAccountAsset,
+  FormatQuery, FormattedBalanceHistory, ProofOperation, ProofStats, PublicKey,
+  ReceiverVerifyRequest, SenderProofRequest, SignedAccountAssetExport,
+  UpdateAccountAssetBalanceRequest, ValuationQuery, WARNING_AUDITORS_REORDERED,
+  WARNING_BALANCE_DRIFT,
 };
 
+use crate::anomalies::{request_user, AppAnomalies};
+use crate::approvals::AppApprovals;
+use crate::balance_overrides::AppBalanceOverrides;
+use crate::decrypt_jobs::AppDecryptJobs;
+use crate::formatting::amount_formatter;
+use crate::limits::{check_amount_limits, AmountLimitCheck};
+use crate::position_locks::check_position_locks;
+use crate::proof_pools::AppProofPools;
+use crate::receipts::AppReceiptSigner;
+use crate::replay::ReplayProtection;
 use crate::repo::Repository;
+use crate::screening::AppScreening;
+use crate::valuation::AppValuation;
+use crate::warnings::{add_warning, check_proof_size};
 
 pub fn service(cfg: &mut web::ServiceConfig) {
   cfg
     .service(get_all_account_assets)
     .service(get_account_asset)
+    .service(get_account_asset_balance_at)
+    .service(get_account_asset_history)
+    .service(export_account_asset)
     .service(create_account_asset)
     .service(request_sender_proof)
     .service(request_burn_proof)
     .service(receiver_verify_request)
     .service(decrypt_request)
-    .service(update_balance_request);
+    .service(update_balance_request)
+    .configure(super::proof_pools::service)
+    .configure(super::balance_conflicts::service);
 }
 
 /// Get all assets for an account.
+///
+/// With `currency` each balance includes its fiat-equivalent value.  With `locale` each
+/// balance includes its formatted balance.
 #[utoipa::path(
+  params(ValuationQuery, FormatQuery),
   responses(
-    (status = 200, body = [AccountAsset])
+    (status = 200, body = [ValuedAccountAsset])
   )
 )]
 #[get("/accounts/{confidential_account}/assets")]
 pub async fn get_all_account_assets(
   confidential_account: web::Path<String>,
+  query: web::Query<ValuationQuery>,
+  format: web::Query<FormatQuery>,
   repo: Repository,
+  valuation: AppValuation,
 ) -> Result<impl Responder> {
+  let formatter = amount_formatter(&repo, &format).await?;
   let account_assets = repo.get_account_assets(&confidential_account).await?;
+  let account_assets = valuation
+    .value_account_assets(
+      account_assets,
+      query.currency.as_deref(),
+      formatter.as_ref(),
+    )
+    .await?;
   Ok(HttpResponse::Ok().json(account_assets))
 }
 
 /// Get one asset for the account.
+///
+/// With `currency` the balance includes its fiat-equivalent value.  With `locale` it
+/// includes the formatted balance.
 #[utoipa::path(
+  params(ValuationQuery, FormatQuery),
   responses(
-    (status = 200, body = AccountAsset)
+    (status = 200, body = ValuedAccountAsset)
   )
 )]
 #[get("/accounts/{confidential_account}/assets/{asset_id}")]
 pub async fn get_account_asset(
   path: web::Path<(String, Uuid)>,
+  query: web::Query<ValuationQuery>,
+  format: web::Query<FormatQuery>,
   repo: Repository,
+  valuation: AppValuation,
 ) -> Result<impl Responder> {
   let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
   let account_asset = repo
     .get_account_asset(&confidential_account, asset_id)
     .await?
     .ok_or_else(|| Error::not_found("Account Asset"))?;
+  let formatter = amount_formatter(&repo, &format).await?;
+  let account_asset = valuation
+    .value_account_assets(
+      vec![account_asset],
+      query.currency.as_deref(),
+      formatter.as_ref(),
+    )
+    .await?
+    .pop();
   Ok(HttpResponse::Ok().json(account_asset))
 }
 
+/// Balance as of a block or time.  Only one of `block` or `timestamp` is allowed.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct BalanceAtQuery {
+  /// Block number.  Only balance changes from the chain are used.
+  pub block: Option<u32>,
+  /// Time (UTC), i.e. `2024-04-01T00:00:00`.
+  pub timestamp: Option<chrono::NaiveDateTime>,
+}
+
+/// Get the tracked balance of an account's asset as of a block or time.
+///
+/// With `locale` it includes the formatted balance.
+#[utoipa::path(
+  params(BalanceAtQuery, FormatQuery),
+  responses(
+    (status = 200, body = FormattedBalanceHistory)
+  )
+)]
+#[get("/accounts/{confidential_account}/assets/{asset_id}/balance_at")]
+pub async fn get_account_asset_balance_at(
+  path: web::Path<(String, Uuid)>,
+  query: web::Query<BalanceAtQuery>,
+  format: web::Query<FormatQuery>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
+  let balance = match (query.block, query.timestamp) {
+    (Some(block), None) => {
+      repo
+        .get_balance_at_block(&confidential_account, asset_id, block)
+        .await?
+    }
+    (None, Some(timestamp)) => {
+      repo
+        .get_balance_at_time(&confidential_account, asset_id, timestamp)
+        .await?
+    }
+    _ => Err(Error::other("Either `block` or `timestamp` is required"))?,
+  }
+  .ok_or_else(|| Error::not_found("Account Asset balance"))?;
+  let formatter = amount_formatter(&repo, &format).await?;
+  Ok(HttpResponse::Ok().json(FormattedBalanceHistory::new(balance, formatter.as_ref())))
+}
+
+/// Default number of balance history records per page.
+const DEFAULT_HISTORY_LIMIT: u32 = 100;
+/// Maximum number of balance history records per page.
+const MAX_HISTORY_LIMIT: u32 = 1000;
+
+/// Balance history page.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct BalanceHistoryQuery {
+  /// Number of records to return (default 100, max 1000).
+  pub limit: Option<u32>,
+  /// Number of records to skip.
+  pub offset: Option<u32>,
+}
+
+/// Get the balance changes of an account's asset, newest first.
+///
+/// Each change has its action (`mint`, `send`, `burn`, `apply_incoming`, `update_balance`,
+/// `deposit`, `withdrawal`, ...), amount, resulting balance and source (transaction hash or
+/// API call).  With `locale` each change includes its formatted balance.
+#[utoipa::path(
+  params(BalanceHistoryQuery, FormatQuery),
+  responses(
+    (status = 200, body = [FormattedBalanceHistory])
+  )
+)]
+#[get("/accounts/{confidential_account}/assets/{asset_id}/history")]
+pub async fn get_account_asset_history(
+  path: web::Path<(String, Uuid)>,
+  query: web::Query<BalanceHistoryQuery>,
+  format: web::Query<FormatQuery>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_HISTORY_LIMIT)
+    .clamp(1, MAX_HISTORY_LIMIT);
+  let history = repo
+    .get_balance_history(
+      &confidential_account,
+      asset_id,
+      limit,
+      query.offset.unwrap_or_default(),
+    )
+    .await?;
+  let formatter = amount_formatter(&repo, &format).await?;
+  let history = history
+    .into_iter()
+    .map(|history| FormattedBalanceHistory::new(history, formatter.as_ref()))
+    .collect::<Vec<_>>();
+  Ok(HttpResponse::Ok().json(history))
+}
+
+/// Export an account asset's evidence for an external auditor.
+///
+/// The export has the encrypted and decrypted balances after each change, the sender and
+/// burn proofs generated for the asset and the chain transactions (block number and
+/// transaction hash) of the balance changes in the date range.  The export is signed when
+/// receipt signing is enabled (see `/receipts/verify`).
+///
+/// Needs a second user's approval when `account_export` is in `APPROVAL_OPERATIONS`
+/// (see `/approvals`).
+#[utoipa::path(
+  responses(
+    (status = 200, body = SignedAccountAssetExport),
+    (status = 202, body = Approval)
+  )
+)]
+#[post(
+  "/accounts/{confidential_account}/assets/{asset_id}/export",
+  wrap = "ReplayProtection"
+)]
+pub async fn export_account_asset(
+  path: web::Path<(String, Uuid)>,
+  req: web::Json<AccountAssetExportRequest>,
+  http_req: HttpRequest,
+  repo: Repository,
+  approvals: AppApprovals,
+  receipts: AppReceiptSigner,
+) -> Result<impl Responder> {
+  let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
+  repo
+    .get_account_asset(&confidential_account, asset_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Account Asset"))?;
+  // Dual control.
+  if approvals.requires_approval(ApprovalOperation::AccountExport) {
+    let target = format!("{confidential_account}/{asset_id}");
+    let pending = approvals
+      .authorize(
+        &repo,
+        &http_req,
+        ApprovalOperation::AccountExport,
+        &target,
+        &*req,
+      )
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
+    }
+  }
+  let balances = repo
+    .get_balance_history_range(&confidential_account, asset_id, req.from_date, req.to_date)
+    .await?;
+  let proofs = repo
+    .get_account_proofs(&confidential_account, Some(asset_id), None)
+    .await?;
+  let export = AccountAssetExport::new(
+    format!(
+      "0x{}",
+      hex::encode(PublicKey::from_str(&confidential_account)?.0)
+    ),
+    asset_id,
+    &req,
+    balances,
+    proofs,
+  );
+  let receipt = receipts.sign(&http_req, &export, None)?;
+  Ok(HttpResponse::Ok().json(SignedAccountAssetExport { export, receipt }))
+}
+
 /// Add an asset to the account and initialize it's balance.
+///
+/// If the account has an account derived for the asset (see `asset_accounts`), the derived
+/// account's balance is initialized.  The `/accounts/{confidential_account}/assets/{asset_id}`
+/// endpoints also use the derived account for the asset.
 #[utoipa::path(
   responses(
     (status = 200, body = AccountAsset)
@@ -66,7 +307,10 @@ pub async fn create_account_asset(
   create_account_asset: web::Json<CreateAccountAsset>,
   repo: Repository,
 ) -> Result<impl Responder> {
-  // Get the account's secret key.
+  // Get the secret key of the account holding the asset.
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, create_account_asset.asset_id)
+    .await?;
   let account = repo
     .get_account_with_secret(&confidential_account)
     .await?
@@ -77,7 +321,9 @@ pub async fn create_account_asset(
     .ok_or_else(|| Error::not_found("Asset"))?;
 
   // Generate Account initialization proof.
-  let init = account.init_balance(asset.asset_id);
+  let init = account
+    .init_balance(asset.asset_id)
+    .with_source("POST /accounts/{confidential_account}/assets");
 
   // Save initialize account balance.
   let account_asset = repo.create_account_asset(&init).await?;
@@ -87,38 +333,203 @@ pub async fn create_account_asset(
 }
 
 /// Generate a sender proof.
+///
+/// The receiver is screened first (see `SCREENING`).  Amounts over the account's limits
+/// need a second user's approval of a `limit_override` (see `/approvals`).  An
+/// `encrypted_balance` overriding the tracked balance is subject to `BALANCE_OVERRIDES`.
+///
+/// If the account asset has an active proof pool, the next pooled proof is returned
+/// instead (see `proof_pools`).
+///
+/// With `update_balance: false` the tracked balance isn't changed, i.e. when the proof may
+/// not be submitted.  Apply the new balance with `update_balance` after the submission.
 #[utoipa::path(
   responses(
-    (status = 200, body = AccountAssetWithProof)
+    (status = 200, body = AccountAssetWithProof),
+    (status = 202, body = Approval)
   )
 )]
-#[post("/accounts/{confidential_account}/assets/{asset_id}/send")]
+#[post(
+  "/accounts/{confidential_account}/assets/{asset_id}/send",
+  wrap = "ReplayProtection"
+)]
 pub async fn request_sender_proof(
   path: web::Path<(String, Uuid)>,
   req: web::Json<SenderProofRequest>,
   repo: Repository,
+  approvals: AppApprovals,
+  balance_overrides: AppBalanceOverrides,
+  screening: AppScreening,
+  proof_pools: AppProofPools,
+  anomalies: AppAnomalies,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
   let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
   // Get the account asset with account secret key.
   let account_asset = repo
     .get_account_asset_with_secret(&confidential_account, asset_id)
     .await?
     .ok_or_else(|| Error::not_found("Account Asset"))?;
+  account_asset.account.ensure_unlocked()?;
+  check_position_locks(&repo, account_asset.account.account_id, Some(asset_id)).await?;
 
-  let enc_balance = req.encrypted_balance()?;
+  // Screen the receiver.
   let receiver = req.receiver()?;
+  screening.screen_receiver(&receiver, None).await?;
+
+  let account_id = account_asset.account.account_id;
+  let user = request_user(&repo, &http_req).await?;
+
+  let enc_balance = req.encrypted_balance()?;
+  if enc_balance.is_some() {
+    let pending = balance_overrides
+      .check(
+        &repo,
+        &approvals,
+        &http_req,
+        &account_asset.account,
+        asset_id,
+        &*req,
+      )
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
+    }
+  }
+  warn_balance_drift(&http_req, enc_balance.as_ref(), &account_asset.enc_balance);
+  if req.auditors_reordered()? {
+    add_warning(
+      &http_req,
+      WARNING_AUDITORS_REORDERED,
+      "The auditors were sorted, auditor ids are their index in the sorted list",
+    );
+  }
   let auditors = req.auditors()?;
   let amount = req.amount;
 
+  // Amount limits.
+  let reservation = match check_amount_limits(
+    &repo,
+    &approvals,
+    &http_req,
+    &account_asset.account,
+    Some(asset_id),
+    amount,
+    &*req,
+  )
+  .await?
+  {
+    AmountLimitCheck::Reserved(reservation) => reservation,
+    AmountLimitCheck::Pending(pending) => return Ok(pending),
+  };
+
+  // Use the next pooled proof, the pool already reserved the amount.
+  let account_asset_id = account_asset.account_asset_id;
+  let pooled = proof_pools
+    .take(
+      account_asset_id,
+      &receiver,
+      &auditors,
+      amount,
+      enc_balance.as_ref(),
+    )
+    .await;
+  let pooled = match pooled {
+    Ok(pooled) => pooled,
+    Err(err) => {
+      reservation.release(&repo).await;
+      return Err(err.into());
+    }
+  };
+  if let Some(pooled) = pooled {
+    proof_pools.finish(&pooled, true).await?;
+    repo
+      .add_proof(&AddProof::sender(
+        account_id,
+        Some(asset_id),
+        &receiver,
+        amount,
+        pooled.proof.clone(),
+      ))
+      .await?;
+    anomalies
+      .sender_proof(
+        user.as_deref(),
+        &account_asset.account,
+        Some(asset_id),
+        amount,
+      )
+      .await;
+    let account_asset = repo
+      .get_account_asset_by_id(account_asset_id)
+      .await?
+      .ok_or_else(|| Error::not_found("Account Asset"))?;
+    let balance_with_proof = AccountAssetWithProof {
+      account_asset,
+      proof: pooled.proof,
+    };
+    check_proof_size(&http_req, balance_with_proof.proof.len());
+    return Ok(receipts.json_response(&http_req, &*req, None, &balance_with_proof)?);
+  }
+
   // Generate sender proof.
-  let (update, proof) = account_asset.create_send_proof(enc_balance, receiver, auditors, amount)?;
+  let started = Instant::now();
+  let auditor_count = auditors.len();
+  let proving = account_asset.clone();
+  let res =
+    spawn_proof(move || proving.create_send_proof(enc_balance, receiver, auditors, amount)).await;
+  let (update, proof) = match res {
+    Ok(res) => res,
+    Err(err) => {
+      reservation.release(&repo).await;
+      return Err(err.into());
+    }
+  };
+  let update = update.with_source("POST /accounts/{confidential_account}/assets/{asset_id}/send");
+  let duration = started.elapsed();
+  anomalies
+    .sender_proof(
+      user.as_deref(),
+      &account_asset.account,
+      Some(asset_id),
+      amount,
+    )
+    .await;
 
-  // Update account balance.
-  let account_asset = repo.update_account_asset(&update).await?;
+  // Update account balance, unless the caller applies it after the submission.
+  let account_asset = if req.update_balance {
+    repo.update_account_asset(&update).await?
+  } else {
+    repo
+      .get_account_asset_by_id(account_asset_id)
+      .await?
+      .ok_or_else(|| Error::not_found("Account Asset"))?
+  };
 
   // Return account_asset with sender proof.
   let balance_with_proof = AccountAssetWithProof::new_send_proof(account_asset, proof);
-  Ok(HttpResponse::Ok().json(balance_with_proof))
+  repo
+    .add_proof(&AddProof::sender(
+      account_id,
+      Some(asset_id),
+      &receiver,
+      amount,
+      balance_with_proof.proof.clone(),
+    ))
+    .await?;
+  ProofStats::global().record(
+    ProofOperation::SenderProof,
+    Some(asset_id),
+    Some(auditor_count),
+    duration,
+    balance_with_proof.proof.len(),
+  );
+  check_proof_size(&http_req, balance_with_proof.proof.len());
+  Ok(receipts.json_response(&http_req, &*req, None, &balance_with_proof)?)
 }
 
 /// Verify a sender proof as the receiver.
@@ -127,81 +538,181 @@ pub async fn request_sender_proof(
     (status = 200, body = SenderProofVerifyResult)
   )
 )]
-#[post("/accounts/{confidential_account}/assets/{asset_id}/receiver_verify")]
+#[post(
+  "/accounts/{confidential_account}/assets/{asset_id}/receiver_verify",
+  wrap = "ReplayProtection"
+)]
 pub async fn receiver_verify_request(
   path: web::Path<(String, Uuid)>,
   req: web::Json<ReceiverVerifyRequest>,
   repo: Repository,
 ) -> Result<impl Responder> {
   let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
   // Get the account asset with account secret key.
   let account_asset = repo
     .get_account_asset_with_secret(&confidential_account, asset_id)
     .await?
     .ok_or_else(|| Error::not_found("Account Asset"))?;
+  account_asset.account.ensure_unlocked()?;
 
   // Verify the sender's proof.
-  let res = account_asset.receiver_verify_proof(&req)?;
+  let started = Instant::now();
+  let req = req.into_inner();
+  let proof_size = req.proof_size();
+  let res = spawn_proof(move || account_asset.receiver_verify_proof(&req)).await?;
+  ProofStats::global().record(
+    ProofOperation::ReceiverVerify,
+    Some(asset_id),
+    None,
+    started.elapsed(),
+    proof_size,
+  );
   Ok(HttpResponse::Ok().json(res))
 }
 
 /// Generate a burn proof.
+///
+/// Burns at or above `APPROVAL_BURN_THRESHOLD` need a second user's approval when
+/// `burn` is in `APPROVAL_OPERATIONS` (see `/approvals`).  An `encrypted_balance`
+/// overriding the tracked balance is subject to `BALANCE_OVERRIDES`.
 #[utoipa::path(
   responses(
-    (status = 200, body = AccountAssetWithProof)
+    (status = 200, body = AccountAssetWithProof),
+    (status = 202, body = Approval)
   )
 )]
-#[post("/accounts/{confidential_account}/assets/{asset_id}/burn")]
+#[post(
+  "/accounts/{confidential_account}/assets/{asset_id}/burn",
+  wrap = "ReplayProtection"
+)]
 pub async fn request_burn_proof(
   path: web::Path<(String, Uuid)>,
   req: web::Json<BurnProofRequest>,
   repo: Repository,
+  approvals: AppApprovals,
+  balance_overrides: AppBalanceOverrides,
+  proof_pools: AppProofPools,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
   let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
   // Get the account asset with account secret key.
   let account_asset = repo
     .get_account_asset_with_secret(&confidential_account, asset_id)
     .await?
     .ok_or_else(|| Error::not_found("Account Asset"))?;
+  account_asset.account.ensure_unlocked()?;
+  // The proof pool reserved part of the balance.
+  proof_pools
+    .ensure_no_pool(account_asset.account_asset_id)
+    .await?;
+
+  // Dual control.
+  if approvals.burn_requires_approval(req.amount) {
+    let target = format!("{confidential_account}/{asset_id}");
+    let pending = approvals
+      .authorize(&repo, &http_req, ApprovalOperation::Burn, &target, &*req)
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
+    }
+  }
 
   let enc_balance = req.encrypted_balance()?;
+  if enc_balance.is_some() {
+    let pending = balance_overrides
+      .check(
+        &repo,
+        &approvals,
+        &http_req,
+        &account_asset.account,
+        asset_id,
+        &*req,
+      )
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
+    }
+  }
+  warn_balance_drift(&http_req, enc_balance.as_ref(), &account_asset.enc_balance);
   let amount = req.amount;
 
   // Generate burn proof.
-  let (update, proof) = account_asset.create_burn_proof(enc_balance, amount)?;
+  let started = Instant::now();
+  let proving = account_asset.clone();
+  let (update, proof) = spawn_proof(move || proving.create_burn_proof(enc_balance, amount)).await?;
+  let update = update.with_source("POST /accounts/{confidential_account}/assets/{asset_id}/burn");
+  let duration = started.elapsed();
 
   // Update account balance.
   let account_asset = repo.update_account_asset(&update).await?;
 
   // Return account_asset with burn proof.
   let balance_with_proof = AccountAssetWithProof::new_burn_proof(account_asset, proof);
-  Ok(HttpResponse::Ok().json(balance_with_proof))
+  repo
+    .add_proof(&AddProof::burn(
+      balance_with_proof.account_asset.account_id,
+      Some(asset_id),
+      amount,
+      balance_with_proof.proof.clone(),
+    ))
+    .await?;
+  ProofStats::global().record(
+    ProofOperation::BurnProof,
+    Some(asset_id),
+    None,
+    duration,
+    balance_with_proof.proof.len(),
+  );
+  check_proof_size(&http_req, balance_with_proof.proof.len());
+  Ok(receipts.json_response(&http_req, &*req, None, &balance_with_proof)?)
 }
 
 /// Decrypt a `CipherText` value.
+///
+/// Large values are decrypted in a background job (see `/jobs/{job_id}`).
 #[utoipa::path(
   responses(
-    (status = 200, body = DecryptedResponse)
+    (status = 200, body = DecryptedResponse),
+    (status = 202, body = DecryptJob)
   )
 )]
-#[post("/accounts/{confidential_account}/assets/{asset_id}/decrypt")]
+#[post(
+  "/accounts/{confidential_account}/assets/{asset_id}/decrypt",
+  wrap = "ReplayProtection"
+)]
 pub async fn decrypt_request(
   path: web::Path<(String, Uuid)>,
   req: web::Json<AccountDecryptRequest>,
   repo: Repository,
+  jobs: AppDecryptJobs,
+  anomalies: AppAnomalies,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
   let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
   // Get the account asset with account secret key.
   let account_asset = repo
     .get_account_asset_with_secret(&confidential_account, asset_id)
     .await?
     .ok_or_else(|| Error::not_found("Account Asset"))?;
+  account_asset.account.ensure_unlocked()?;
+  let user = request_user(&repo, &http_req).await?;
+  anomalies
+    .decrypt(user.as_deref(), &account_asset.account, Some(asset_id))
+    .await;
 
-  // Decrypt the value.
-  let resp = account_asset.decrypt_request(&req)?;
-
-  // Return the decrypted value.
-  Ok(HttpResponse::Ok().json(resp))
+  // Decrypt the value or start a decryption job.
+  let enc_value = req.encrypted_value()?;
+  Ok(jobs.decrypt(&account_asset.account, enc_value)?)
 }
 
 /// Update an account's encrypted balance.
@@ -210,21 +721,30 @@ pub async fn decrypt_request(
     (status = 200, body = AccountAsset)
   )
 )]
-#[post("/accounts/{confidential_account}/assets/{asset_id}/update_balance")]
+#[post(
+  "/accounts/{confidential_account}/assets/{asset_id}/update_balance",
+  wrap = "ReplayProtection"
+)]
 pub async fn update_balance_request(
   path: web::Path<(String, Uuid)>,
   req: web::Json<UpdateAccountAssetBalanceRequest>,
   repo: Repository,
 ) -> Result<impl Responder> {
   let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
   // Get the account asset with account secret key.
   let account_asset = repo
     .get_account_asset_with_secret(&confidential_account, asset_id)
     .await?
     .ok_or_else(|| Error::not_found("Account Asset"))?;
+  account_asset.account.ensure_unlocked()?;
 
   // Prepare balance update.
-  let update = account_asset.update_balance(&req)?;
+  let update = account_asset
+    .update_balance(&req)?
+    .with_source("POST /accounts/{confidential_account}/assets/{asset_id}/update_balance");
 
   // Update account balance.
   let account_asset = repo.update_account_asset(&update).await?;
@@ -232,3 +752,20 @@ pub async fn update_balance_request(
   // Return account_asset.
   Ok(HttpResponse::Ok().json(account_asset))
 }
+
+/// Warn when the request's `encrypted_balance` isn't the tracked balance.
+fn warn_balance_drift(
+  http_req: &HttpRequest,
+  enc_balance: Option<&CipherText>,
+  tracked_enc_balance: &[u8],
+) {
+  if let Some(enc_balance) = enc_balance {
+    if enc_balance.encode() != tracked_enc_balance {
+      add_warning(
+        http_req,
+        WARNING_BALANCE_DRIFT,
+        "The encrypted balance doesn't match the tracked balance",
+      );
+    }
+  }
+}
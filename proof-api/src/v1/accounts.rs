@@ -1,18 +1,35 @@
-use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use std::time::Instant;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+use uuid::Uuid;
 
 use polymesh_private_proof_shared::{
-  error::Error, AccountDecryptRequest, AuditorVerifyRequest, BurnProof, BurnProofRequest,
-  CreateAccount, ReceiverVerifyRequest, SenderProof, SenderProofRequest,
+  error::Error, spawn_proof, AccountDecryptRequest, AddProof, ApprovalOperation,
+  AuditorVerifyRequest, BurnProof, BurnProofRequest, CreateAccount, ProofOperation, ProofStats,
+  ReceiverVerifyRequest, SenderProof, SenderProofRequest,
 };
 
+use crate::anomalies::{request_user, AppAnomalies};
+use crate::approvals::AppApprovals;
+use crate::decrypt_jobs::AppDecryptJobs;
+use crate::limits::{check_amount_limits, AmountLimitCheck};
+use crate::position_locks::check_position_locks;
+use crate::receipts::AppReceiptSigner;
+use crate::replay::ReplayProtection;
 use crate::repo::Repository;
+use crate::screening::AppScreening;
 
 pub fn service(cfg: &mut web::ServiceConfig) {
   let _cfg = cfg
     .service(get_all_accounts)
     .service(get_account)
     .service(create_account)
+    .service(lock_account)
+    .service(unlock_account)
+    .service(get_asset_accounts)
+    .service(create_asset_account)
     .service(decrypt_request)
+    .service(decrypt_with_proof_request)
     .service(request_sender_proof)
     .service(request_burn_proof)
     .service(receiver_verify_request)
@@ -67,35 +84,193 @@ pub async fn create_account(repo: Repository) -> Result<impl Responder> {
   Ok(HttpResponse::Ok().json(account))
 }
 
+/// Lock an account.  Blocks proof generation, burning and decryption with the account's
+/// secret key.  The on-chain account is not changed.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Account)
+  )
+)]
+#[post("/admin/accounts/{confidential_account}/lock")]
+pub async fn lock_account(
+  confidential_account: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let account = repo
+    .set_account_locked(&confidential_account, true)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  log::warn!("Account locked: {confidential_account}");
+  Ok(HttpResponse::Ok().json(account))
+}
+
+/// Unlock an account.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Account)
+  )
+)]
+#[post("/admin/accounts/{confidential_account}/unlock")]
+pub async fn unlock_account(
+  confidential_account: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let account = repo
+    .set_account_locked(&confidential_account, false)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  log::info!("Account unlocked: {confidential_account}");
+  Ok(HttpResponse::Ok().json(account))
+}
+
+/// Get the per-asset accounts derived from a master account.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [AssetAccount])
+  )
+)]
+#[get("/accounts/{confidential_account}/asset_accounts")]
+pub async fn get_asset_accounts(
+  confidential_account: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let accounts = repo.get_asset_accounts(&confidential_account).await?;
+  Ok(HttpResponse::Ok().json(accounts))
+}
+
+/// Derive the account of one asset from a master account.
+///
+/// The asset's Elgamal keys are derived from the master account's secret key (domain-separated
+/// by the asset id), so the same account is returned on every call.  Register the derived
+/// account on-chain; the master account's `/assets/{asset_id}` endpoints (balance, sender and
+/// receiver proofs, `/tx` transactions, ...) then use the derived account's keys.  A
+/// compromised asset account doesn't expose the master account or the accounts of other
+/// assets.
+///
+/// Fails with `409 Conflict` if the master account already holds the asset, the derived
+/// account would hide its balance.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AssetAccount),
+    (status = 409, description = "The account already holds the asset")
+  )
+)]
+#[post("/accounts/{confidential_account}/asset_accounts/{asset_id}")]
+pub async fn create_asset_account(
+  path: web::Path<(String, Uuid)>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let (confidential_account, asset_id) = path.into_inner();
+  if let Some(account) = repo
+    .get_asset_account(&confidential_account, asset_id)
+    .await?
+  {
+    return Ok(HttpResponse::Ok().json(account));
+  }
+  let master = repo
+    .get_account_with_secret(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  master.ensure_unlocked()?;
+  if repo
+    .get_account_asset(&confidential_account, asset_id)
+    .await?
+    .is_some()
+  {
+    Err(Error::conflict(
+      "The account already holds the asset, move its balance before deriving an asset account",
+    ))?
+  }
+  let derived = master.derive_asset_account(asset_id)?;
+  let account = repo
+    .create_asset_account(master.account_id, asset_id, &derived)
+    .await?;
+  Ok(HttpResponse::Ok().json(account))
+}
+
 /// Generate a sender proof.
+///
+/// The receiver is screened first (see `SCREENING`).  Amounts over the account's limits
+/// need a second user's approval of a `limit_override` (see `/approvals`).
 #[utoipa::path(
   responses(
-    (status = 200, body = SenderProof)
+    (status = 200, body = SenderProof),
+    (status = 202, body = Approval)
   )
 )]
-#[post("/accounts/{confidential_account}/send")]
+#[post("/accounts/{confidential_account}/send", wrap = "ReplayProtection")]
 pub async fn request_sender_proof(
   confidential_account: web::Path<String>,
   req: web::Json<SenderProofRequest>,
   repo: Repository,
+  approvals: AppApprovals,
+  screening: AppScreening,
+  anomalies: AppAnomalies,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
   // Get the account asset with account secret key.
   let account = repo
     .get_account_with_secret(&confidential_account)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account.ensure_unlocked()?;
+  check_position_locks(&repo, account.account_id, None).await?;
+  let user = request_user(&repo, &http_req).await?;
+
+  // Screen the receiver.
+  let receiver = req.receiver()?;
+  screening.screen_receiver(&receiver, None).await?;
 
   let enc_balance = req
     .encrypted_balance()?
     .ok_or_else(|| Error::other("Missing 'encrypted_balance'"))?;
-  let receiver = req.receiver()?;
   let auditors = req.auditors()?;
   let amount = req.amount;
 
+  // Amount limits.
+  let reservation =
+    match check_amount_limits(&repo, &approvals, &http_req, &account, None, amount, &*req).await? {
+      AmountLimitCheck::Reserved(reservation) => reservation,
+      AmountLimitCheck::Pending(pending) => return Ok(pending),
+    };
+
   // Generate sender proof.
-  let proof = account.create_send_proof(enc_balance, None, receiver, auditors, amount)?;
+  let started = Instant::now();
+  let auditor_count = auditors.len();
+  let proving = account.clone();
+  let res =
+    spawn_proof(move || proving.create_send_proof(enc_balance, None, receiver, auditors, amount))
+      .await;
+  let proof = match res {
+    Ok(res) => res,
+    Err(err) => {
+      reservation.release(&repo).await;
+      return Err(err.into());
+    }
+  };
+  let proof = SenderProof::new(proof);
+  ProofStats::global().record(
+    ProofOperation::SenderProof,
+    None,
+    Some(auditor_count),
+    started.elapsed(),
+    proof.0.len(),
+  );
+  repo
+    .add_proof(&AddProof::sender(
+      account.account_id,
+      None,
+      &receiver,
+      amount,
+      proof.0.clone(),
+    ))
+    .await?;
+  anomalies
+    .sender_proof(user.as_deref(), &account, None, amount)
+    .await;
 
-  Ok(HttpResponse::Ok().json(SenderProof::new(proof)))
+  Ok(receipts.json_response(&http_req, &*req, None, &proof)?)
 }
 
 /// Verify a sender proof as the receiver.
@@ -104,7 +279,10 @@ pub async fn request_sender_proof(
     (status = 200, body = SenderProofVerifyResult)
   )
 )]
-#[post("/accounts/{confidential_account}/receiver_verify")]
+#[post(
+  "/accounts/{confidential_account}/receiver_verify",
+  wrap = "ReplayProtection"
+)]
 pub async fn receiver_verify_request(
   confidential_account: web::Path<String>,
   req: web::Json<ReceiverVerifyRequest>,
@@ -115,29 +293,64 @@ pub async fn receiver_verify_request(
     .get_account_with_secret(&confidential_account)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account.ensure_unlocked()?;
 
   // Verify the sender's proof.
-  let res = account.receiver_verify_proof(&req)?;
+  let started = Instant::now();
+  let req = req.into_inner();
+  let proof_size = req.proof_size();
+  let res = spawn_proof(move || account.receiver_verify_proof(&req)).await?;
+  ProofStats::global().record(
+    ProofOperation::ReceiverVerify,
+    None,
+    None,
+    started.elapsed(),
+    proof_size,
+  );
   Ok(HttpResponse::Ok().json(res))
 }
 
 /// Generate a burn proof.
+///
+/// Burns at or above `APPROVAL_BURN_THRESHOLD` need a second user's approval when
+/// `burn` is in `APPROVAL_OPERATIONS` (see `/approvals`).
 #[utoipa::path(
   responses(
-    (status = 200, body = BurnProof)
+    (status = 200, body = BurnProof),
+    (status = 202, body = Approval)
   )
 )]
-#[post("/accounts/{confidential_account}/burn")]
+#[post("/accounts/{confidential_account}/burn", wrap = "ReplayProtection")]
 pub async fn request_burn_proof(
   confidential_account: web::Path<String>,
   req: web::Json<BurnProofRequest>,
   repo: Repository,
+  approvals: AppApprovals,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
   // Get the account asset with account secret key.
   let account = repo
     .get_account_with_secret(&confidential_account)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account.ensure_unlocked()?;
+
+  // Dual control.
+  if approvals.burn_requires_approval(req.amount) {
+    let pending = approvals
+      .authorize(
+        &repo,
+        &http_req,
+        ApprovalOperation::Burn,
+        &confidential_account,
+        &*req,
+      )
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
+    }
+  }
 
   let enc_balance = req
     .encrypted_balance()?
@@ -145,34 +358,94 @@ pub async fn request_burn_proof(
   let amount = req.amount;
 
   // Generate burn proof.
-  let proof = account.create_burn_proof(enc_balance, None, amount)?;
+  let started = Instant::now();
+  let proving = account.clone();
+  let proof = spawn_proof(move || proving.create_burn_proof(enc_balance, None, amount)).await?;
+  let proof = BurnProof::new(proof);
+  ProofStats::global().record(
+    ProofOperation::BurnProof,
+    None,
+    None,
+    started.elapsed(),
+    proof.0.len(),
+  );
+  repo
+    .add_proof(&AddProof::burn(
+      account.account_id,
+      None,
+      amount,
+      proof.0.clone(),
+    ))
+    .await?;
 
-  Ok(HttpResponse::Ok().json(BurnProof::new(proof)))
+  Ok(receipts.json_response(&http_req, &*req, None, &proof)?)
 }
 
 /// Decrypt a `CipherText` value.
+///
+/// Large values are decrypted in a background job (see `/jobs/{job_id}`).
 #[utoipa::path(
   responses(
-    (status = 200, body = DecryptedResponse)
+    (status = 200, body = DecryptedResponse),
+    (status = 202, body = DecryptJob)
   )
 )]
-#[post("/accounts/{confidential_account}/decrypt")]
+#[post("/accounts/{confidential_account}/decrypt", wrap = "ReplayProtection")]
 pub async fn decrypt_request(
   confidential_account: web::Path<String>,
   req: web::Json<AccountDecryptRequest>,
   repo: Repository,
+  jobs: AppDecryptJobs,
+  anomalies: AppAnomalies,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
   // Get the account asset with account secret key.
   let account = repo
     .get_account_with_secret(&confidential_account)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account.ensure_unlocked()?;
+  let user = request_user(&repo, &http_req).await?;
+  anomalies.decrypt(user.as_deref(), &account, None).await;
+
+  // Decrypt the value or start a decryption job.
+  let enc_value = req.encrypted_value()?;
+  Ok(jobs.decrypt(&account, enc_value)?)
+}
 
-  // Decrypt the value.
-  let resp = account.decrypt_request(&req)?;
+/// Decrypt a `CipherText` value with a proof of correct decryption.
+///
+/// The proof can be checked by anyone against the account's public key and the encrypted
+/// value (see `/decryption_proofs/verify`), so third parties can trust the reported value
+/// without the account's secret key.
+#[utoipa::path(
+  responses(
+    (status = 200, body = VerifiableDecryption)
+  )
+)]
+#[post(
+  "/accounts/{confidential_account}/decrypt_with_proof",
+  wrap = "ReplayProtection"
+)]
+pub async fn decrypt_with_proof_request(
+  confidential_account: web::Path<String>,
+  req: web::Json<AccountDecryptRequest>,
+  repo: Repository,
+  anomalies: AppAnomalies,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  // Get the account with account secret key.
+  let account = repo
+    .get_account_with_secret(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  account.ensure_unlocked()?;
+  let user = request_user(&repo, &http_req).await?;
+  anomalies.decrypt(user.as_deref(), &account, None).await;
 
-  // Return the decrypted value.
-  Ok(HttpResponse::Ok().json(resp))
+  let enc_value = req.encrypted_value()?;
+  let decryption = account.decrypt_with_proof(enc_value)?;
+  Ok(HttpResponse::Ok().json(decryption))
 }
 
 /// Verify a sender proof as an auditor.
@@ -181,7 +454,10 @@ pub async fn decrypt_request(
     (status = 200, body = SenderProofVerifyResult)
   )
 )]
-#[post("/accounts/{confidential_account}/auditor_verify")]
+#[post(
+  "/accounts/{confidential_account}/auditor_verify",
+  wrap = "ReplayProtection"
+)]
 pub async fn auditor_verify_request(
   confidential_account: web::Path<String>,
   req: web::Json<AuditorVerifyRequest>,
@@ -192,8 +468,19 @@ pub async fn auditor_verify_request(
     .get_account_with_secret(&confidential_account)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account.ensure_unlocked()?;
 
   // Verify the sender's proof.
-  let res = account.auditor_verify_proof(&req)?;
+  let started = Instant::now();
+  let req = req.into_inner();
+  let proof_size = req.proof_size();
+  let res = spawn_proof(move || account.auditor_verify_proof(&req)).await?;
+  ProofStats::global().record(
+    ProofOperation::AuditorVerify,
+    None,
+    None,
+    started.elapsed(),
+    proof_size,
+  );
   Ok(HttpResponse::Ok().json(res))
 }
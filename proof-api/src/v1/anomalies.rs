@@ -0,0 +1,53 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::audit_chain::verify_audit_chain;
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_anomaly_alerts).service(verify_audit_log);
+}
+
+/// Anomaly alert filter.  All filters must match.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct AnomalyAlertFilter {
+  /// Only alerts of this rule (`amount_zscore`, `proof_burst` or `off_hours_decrypt`).
+  pub rule: Option<String>,
+  /// Only alerts of this confidential account.
+  pub account: Option<String>,
+}
+
+/// Get the triggered anomaly alerts (see `ANOMALY_DETECTION`), newest first.
+#[utoipa::path(
+  params(AnomalyAlertFilter),
+  responses(
+    (status = 200, body = [AnomalyAlert])
+  )
+)]
+#[get("/admin/anomalies")]
+pub async fn get_anomaly_alerts(
+  filter: web::Query<AnomalyAlertFilter>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let alerts = repo
+    .get_anomaly_alerts(filter.rule.as_deref(), filter.account.as_deref())
+    .await?;
+  Ok(HttpResponse::Ok().json(alerts))
+}
+
+/// Verify the hash chain of the audit log.
+///
+/// Each audit log entry includes the previous entry's hash, so altered, deleted or
+/// reordered entries break the chain.  Record `head_hash` to also detect entries deleted
+/// from the end of the log.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AuditChainReport)
+  )
+)]
+#[get("/admin/audit_log/verify")]
+pub async fn verify_audit_log(repo: Repository) -> Result<impl Responder> {
+  let report = verify_audit_chain(&repo).await?;
+  Ok(HttpResponse::Ok().json(report))
+}
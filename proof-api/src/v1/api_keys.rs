@@ -0,0 +1,80 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{error::Error, CreateUserApiKey};
+
+use crate::api_keys::{create_api_key, AppApiKeyAdmins};
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_user_api_keys)
+    .service(create_user_api_key)
+    .service(revoke_user_api_key);
+}
+
+/// Get a user's API keys.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [UserApiKey])
+  )
+)]
+#[get("/admin/users/{user_name}/api_keys")]
+pub async fn get_user_api_keys(
+  user_name: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let keys = repo.get_user_api_keys(&user_name).await?;
+  Ok(HttpResponse::Ok().json(keys))
+}
+
+/// Create an API key for a user.
+///
+/// Users authenticate with the key in the `X-Api-Key` header to request and decide
+/// approvals.  The key is only returned once.
+///
+/// Only `API_KEY_ADMINS` (authenticated by their API key) or requests with the
+/// `X-Bootstrap-Secret` header can create keys.
+#[utoipa::path(
+  responses(
+    (status = 200, body = UserApiKeyCreated),
+    (status = 403, description = "Not an API key admin")
+  )
+)]
+#[post("/admin/users/{user_name}/api_keys")]
+pub async fn create_user_api_key(
+  user_name: web::Path<String>,
+  req: web::Json<CreateUserApiKey>,
+  repo: Repository,
+  admins: AppApiKeyAdmins,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  admins.authorize(&repo, &http_req).await?;
+  let created = create_api_key(&repo, &user_name, &req).await?;
+  Ok(HttpResponse::Ok().json(created))
+}
+
+/// Revoke a user's API key.
+///
+/// Needs the same authorization as creating a key.
+#[utoipa::path(
+  responses(
+    (status = 200, body = UserApiKey),
+    (status = 403, description = "Not an API key admin")
+  )
+)]
+#[delete("/admin/users/{user_name}/api_keys/{key_id}")]
+pub async fn revoke_user_api_key(
+  path: web::Path<(String, i64)>,
+  repo: Repository,
+  admins: AppApiKeyAdmins,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  admins.authorize(&repo, &http_req).await?;
+  let (user_name, key_id) = path.into_inner();
+  let key = repo
+    .revoke_user_api_key(&user_name, key_id)
+    .await?
+    .ok_or_else(|| Error::not_found("API key"))?;
+  log::info!("Revoked API key {key_id} of user {user_name}");
+  Ok(HttpResponse::Ok().json(key))
+}
@@ -0,0 +1,97 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_private_proof_shared::error::Error;
+
+use crate::approvals::AppApprovals;
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_approvals)
+    .service(get_approval)
+    .service(approve)
+    .service(reject);
+}
+
+/// Approvals filter.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct ApprovalsQuery {
+  /// Only return approvals with this status (`pending`, `approved`, `rejected` or `executed`).
+  pub status: Option<String>,
+}
+
+/// Get approvals of sensitive operations.
+#[utoipa::path(
+  params(ApprovalsQuery),
+  responses(
+    (status = 200, body = [Approval])
+  )
+)]
+#[get("/approvals")]
+pub async fn get_approvals(
+  query: web::Query<ApprovalsQuery>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let approvals = repo.get_approvals(query.status.as_deref()).await?;
+  Ok(HttpResponse::Ok().json(approvals))
+}
+
+/// Get one approval.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Approval)
+  )
+)]
+#[get("/approvals/{approval_id}")]
+pub async fn get_approval(approval_id: web::Path<i64>, repo: Repository) -> Result<impl Responder> {
+  let approval = repo
+    .get_approval(*approval_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Approval"))?;
+  Ok(HttpResponse::Ok().json(approval))
+}
+
+/// Approve a pending operation.
+///
+/// The approving user (`X-Api-Key` header) must be different from the requesting user.
+/// The requesting user can then resubmit the request with the `X-Approval-Id` header.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Approval)
+  )
+)]
+#[post("/approvals/{approval_id}/approve")]
+pub async fn approve(
+  approval_id: web::Path<i64>,
+  approvals: AppApprovals,
+  repo: Repository,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let approval = approvals
+    .decide(&repo, &http_req, *approval_id, true)
+    .await?;
+  Ok(HttpResponse::Ok().json(approval))
+}
+
+/// Reject a pending operation.
+///
+/// The rejecting user (`X-Api-Key` header) must be different from the requesting user.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Approval)
+  )
+)]
+#[post("/approvals/{approval_id}/reject")]
+pub async fn reject(
+  approval_id: web::Path<i64>,
+  approvals: AppApprovals,
+  repo: Repository,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let approval = approvals
+    .decide(&repo, &http_req, *approval_id, false)
+    .await?;
+  Ok(HttpResponse::Ok().json(approval))
+}
@@ -1,16 +1,29 @@
+#[cfg(feature = "track_balances")]
+use std::collections::HashSet;
+use std::time::Instant;
+
+#[cfg(feature = "track_balances")]
+use actix_web::HttpRequest;
 use actix_web::{get, post, web, HttpResponse, Responder, Result};
 use uuid::Uuid;
 
-use polymesh_private_proof_shared::{AddAsset, SenderProofVerifyRequest};
+use polymesh_private_proof_shared::{
+  AddAsset, ProofOperation, ProofStats, SenderProofVerifyRequest,
+};
 
+#[cfg(feature = "track_balances")]
+use crate::api_keys::authenticated_user;
 use crate::repo::Repository;
 
 pub fn service(cfg: &mut web::ServiceConfig) {
-  cfg
+  let _cfg = cfg
     .service(get_all_assets)
     .service(get_asset)
     .service(create_asset)
     .service(sender_proof_verify);
+
+  #[cfg(feature = "track_balances")]
+  _cfg.service(get_asset_holders);
 }
 
 /// Get all assets.
@@ -39,6 +52,41 @@ pub async fn get_asset(asset_id: web::Path<Uuid>, repo: Repository) -> Result<im
   })
 }
 
+/// Get the local accounts holding an asset and their balances.
+///
+/// The user is authenticated by their API key (`X-Api-Key` header) and only the accounts
+/// they were granted access to (`/admin/users/{user_name}/accounts`) are returned.
+///
+/// Only available when balances are tracked (`track_balances` feature).
+#[cfg(feature = "track_balances")]
+#[utoipa::path(
+  responses(
+    (status = 200, body = [AssetHolder]),
+    (status = 403, description = "Missing or invalid API key")
+  )
+)]
+#[get("/assets/{asset_id}/holders")]
+pub async fn get_asset_holders(
+  asset_id: web::Path<Uuid>,
+  repo: Repository,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let user = authenticated_user(&repo, &http_req).await?;
+  let allowed = repo
+    .get_user_account_access(&user.username)
+    .await?
+    .into_iter()
+    .map(|access| access.confidential_account)
+    .collect::<HashSet<_>>();
+  let holders = repo
+    .get_asset_holders(*asset_id)
+    .await?
+    .into_iter()
+    .filter(|holder| allowed.contains(&holder.confidential_account))
+    .collect::<Vec<_>>();
+  Ok(HttpResponse::Ok().json(holders))
+}
+
 /// Create an asset.
 #[utoipa::path(
   responses(
@@ -62,6 +110,14 @@ pub async fn sender_proof_verify(
   req: web::Json<SenderProofVerifyRequest>,
 ) -> Result<impl Responder> {
   // Verify the sender's proof.
+  let started = Instant::now();
   let res = req.verify_proof()?;
+  ProofStats::global().record(
+    ProofOperation::SenderVerify,
+    None,
+    Some(req.auditor_count()),
+    started.elapsed(),
+    req.proof_size(),
+  );
   Ok(HttpResponse::Ok().json(res))
 }
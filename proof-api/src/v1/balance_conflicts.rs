@@ -0,0 +1,89 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_private_proof_shared::{error::Error, ResolveBalanceConflict};
+
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_balance_conflicts)
+    .service(get_balance_conflict)
+    .service(resolve_balance_conflict);
+}
+
+/// Balance conflict filter.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct BalanceConflictFilter {
+  /// Only conflicts with this status (`applied`, `discarded`, `pending`, `accepted` or
+  /// `rejected`).
+  pub status: Option<String>,
+}
+
+/// Get the balance updates that disagreed with the stored balance, newest first.
+///
+/// `BALANCE_CONFLICT_STRATEGY` decides which balance is kept: `chain_wins` (default),
+/// `local_wins` or `review`.  With `review` the conflicting updates are `pending` until
+/// they are resolved.
+#[utoipa::path(
+  params(BalanceConflictFilter),
+  responses(
+    (status = 200, body = [BalanceConflict])
+  )
+)]
+#[get("/admin/balance_conflicts")]
+pub async fn get_balance_conflicts(
+  filter: web::Query<BalanceConflictFilter>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let conflicts = repo.get_balance_conflicts(filter.status.as_deref()).await?;
+  Ok(HttpResponse::Ok().json(conflicts))
+}
+
+/// Get a balance conflict.
+#[utoipa::path(
+  responses(
+    (status = 200, body = BalanceConflict)
+  )
+)]
+#[get("/admin/balance_conflicts/{conflict_id}")]
+pub async fn get_balance_conflict(
+  conflict_id: web::Path<i64>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let conflict = repo
+    .get_balance_conflict(*conflict_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Balance conflict"))?;
+  Ok(HttpResponse::Ok().json(conflict))
+}
+
+/// Resolve a pending balance conflict.
+///
+/// Accepting it replaces the stored balance with the held update, rejecting it keeps the
+/// stored balance.
+#[utoipa::path(
+  responses(
+    (status = 200, body = BalanceConflict)
+  )
+)]
+#[post("/admin/balance_conflicts/{conflict_id}/resolve")]
+pub async fn resolve_balance_conflict(
+  conflict_id: web::Path<i64>,
+  req: web::Json<ResolveBalanceConflict>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let conflict = repo
+    .get_balance_conflict(*conflict_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Balance conflict"))?;
+  if !conflict.is_pending() {
+    Err(Error::other("Balance conflict is not pending"))?;
+  }
+  let conflict = repo
+    .resolve_balance_conflict(*conflict_id, req.accept)
+    .await?
+    .ok_or_else(|| Error::other("Balance conflict is not pending"))?;
+  Ok(HttpResponse::Ok().json(conflict))
+}
@@ -0,0 +1,33 @@
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::CreateDecryptToken;
+
+use crate::decrypt_tokens::AppDecryptTokens;
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(create_decrypt_token);
+}
+
+/// Issue a short-lived token for decrypting an account's values.
+///
+/// The user is authenticated by the `X-Api-Key` header and must have been granted access to
+/// the account (`/admin/users/{user_name}/accounts`).  With `DECRYPT_TOKENS=true` the decrypt,
+/// incoming balance and balance at block endpoints require the token in the
+/// `X-Decrypt-Token` header.  A token for an asset only allows the account asset endpoints
+/// of that asset.
+#[utoipa::path(
+  responses(
+    (status = 200, body = DecryptToken)
+  )
+)]
+#[post("/decrypt_tokens")]
+pub async fn create_decrypt_token(
+  http_req: HttpRequest,
+  req: web::Json<CreateDecryptToken>,
+  repo: Repository,
+  tokens: AppDecryptTokens,
+) -> Result<impl Responder> {
+  let token = tokens.issue(&repo, &http_req, &req).await?;
+  Ok(HttpResponse::Ok().json(token))
+}
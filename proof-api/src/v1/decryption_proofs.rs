@@ -0,0 +1,24 @@
+use actix_web::{post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{DecryptionVerifyResult, VerifiableDecryption};
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(verify_decryption_proof);
+}
+
+/// Verify that an encrypted value decrypts to the reported value.
+///
+/// Checks the proof returned by `/accounts/{confidential_account}/decrypt_with_proof`, only
+/// the account's public key is needed.
+#[utoipa::path(
+  responses(
+    (status = 200, body = DecryptionVerifyResult)
+  )
+)]
+#[post("/decryption_proofs/verify")]
+pub async fn verify_decryption_proof(
+  req: web::Json<VerifiableDecryption>,
+) -> Result<impl Responder> {
+  let valid = req.verify()?;
+  Ok(HttpResponse::Ok().json(DecryptionVerifyResult { valid }))
+}
@@ -0,0 +1,122 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{
+  error::Error, AccountEscrow, EscrowAccountRequest, ReassembleAccountRequest,
+};
+
+use crate::replay::ReplayProtection;
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_account_escrow)
+    .service(escrow_account)
+    .service(reassemble_account)
+    .service(seal_account);
+}
+
+/// Get the escrow status of an account.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AccountEscrow)
+  )
+)]
+#[get("/admin/accounts/{confidential_account}/escrow")]
+pub async fn get_account_escrow(
+  confidential_account: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let escrow = repo
+    .get_account_escrow(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account escrow"))?;
+  Ok(HttpResponse::Ok().json(escrow))
+}
+
+/// Split the account's secret key into shares encrypted with each custodian's KEK.
+///
+/// The secret key is removed from the database.  Proofs and decryption with the account
+/// need the shares to be reassembled first.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AccountEscrow)
+  )
+)]
+#[post("/admin/accounts/{confidential_account}/escrow")]
+pub async fn escrow_account(
+  confidential_account: web::Path<String>,
+  req: web::Json<EscrowAccountRequest>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let account = repo
+    .get_account_with_secret(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  if account.escrowed {
+    return Err(Error::other("Account is already in escrow").into());
+  }
+  let shares = req.split(&account.confidential_account, &account.secret_key)?;
+  let escrow = repo
+    .escrow_account(&confidential_account, req.threshold, &shares)
+    .await?
+    .ok_or_else(|| Error::other("Account is already in escrow"))?;
+  log::warn!(
+    "Account secret key escrowed: {confidential_account}, threshold {} of {}",
+    escrow.threshold,
+    escrow.custodians.len()
+  );
+  Ok(HttpResponse::Ok().json(escrow))
+}
+
+/// Reassemble an escrowed account's secret key from a quorum of custodian shares.
+///
+/// The reassembled key is only held in memory, until it is sealed again or the
+/// server restarts.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AccountEscrow)
+  )
+)]
+#[post(
+  "/admin/accounts/{confidential_account}/escrow/reassemble",
+  wrap = "ReplayProtection"
+)]
+pub async fn reassemble_account(
+  confidential_account: web::Path<String>,
+  req: web::Json<ReassembleAccountRequest>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let escrow = repo
+    .get_account_escrow(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account escrow"))?;
+  let shares = repo
+    .get_account_escrow_shares(&confidential_account)
+    .await?;
+  let secret_key = req.reassemble(&escrow.confidential_account.0, escrow.threshold, &shares)?;
+  let escrow = repo
+    .set_reassembled_secret(&confidential_account, Some(secret_key.to_vec()))
+    .await?
+    .ok_or_else(|| Error::not_found("Account escrow"))?;
+  log::warn!("Account secret key reassembled: {confidential_account}");
+  Ok(HttpResponse::Ok().json(escrow))
+}
+
+/// Drop the reassembled secret key of an escrowed account from memory.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AccountEscrow)
+  )
+)]
+#[post("/admin/accounts/{confidential_account}/escrow/seal")]
+pub async fn seal_account(
+  confidential_account: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let escrow = repo
+    .set_reassembled_secret(&confidential_account, None)
+    .await?
+    .ok_or_else(|| Error::not_found("Account escrow"))?;
+  log::warn!("Account secret key sealed: {confidential_account}");
+  Ok(HttpResponse::Ok().json(escrow))
+}
@@ -0,0 +1,65 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{error::Error, SetFeatureFlag};
+
+use crate::feature_flags::AppFeatureFlags;
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_feature_flags)
+    .service(set_feature_flag)
+    .service(delete_feature_flag);
+}
+
+/// Get the runtime feature flags.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [FeatureFlag])
+  )
+)]
+#[get("/admin/feature_flags")]
+pub async fn get_feature_flags(repo: Repository) -> Result<impl Responder> {
+  let flags = repo.get_feature_flags().await?;
+  Ok(HttpResponse::Ok().json(flags))
+}
+
+/// Enable or disable the endpoints matching a pattern.
+///
+/// Requests to a disabled endpoint are rejected with `403 Forbidden` and the flag's reason.
+/// Other instances pick up the change within `FEATURE_FLAGS_REFRESH_SECS`.
+#[utoipa::path(
+  responses(
+    (status = 200, body = FeatureFlag)
+  )
+)]
+#[post("/admin/feature_flags")]
+pub async fn set_feature_flag(
+  req: web::Json<SetFeatureFlag>,
+  repo: Repository,
+  flags: AppFeatureFlags,
+) -> Result<impl Responder> {
+  req.validate()?;
+  let flag = repo.set_feature_flag(&req).await?;
+  flags.reload().await?;
+  Ok(HttpResponse::Ok().json(flag))
+}
+
+/// Delete a feature flag, the endpoints it disabled are enabled again.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/admin/feature_flags/{flag_id}")]
+pub async fn delete_feature_flag(
+  flag_id: web::Path<i64>,
+  repo: Repository,
+  flags: AppFeatureFlags,
+) -> Result<impl Responder> {
+  if !repo.delete_feature_flag(*flag_id).await? {
+    Err(Error::not_found("Feature flag"))?;
+  }
+  flags.reload().await?;
+  Ok(HttpResponse::Ok().finish())
+}
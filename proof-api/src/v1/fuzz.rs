@@ -0,0 +1,27 @@
+use actix_web::{post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{FuzzReport, FuzzRequest};
+
+use crate::fuzz::AppFuzzer;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(fuzz_routes);
+}
+
+/// Fuzz the documented routes with schema-derived boundary inputs (staging only).
+///
+/// Each route gets zero amounts, `u64::MAX` and oversized hex values in its path, query and
+/// body.  Reports the requests that returned a `5xx` status or failed, to catch validation
+/// gaps before production.  Needs `FUZZ_ENDPOINT=true`.  Only `GET` and proof verification
+/// routes are fuzzed, unless other routes (which can change state) or `/admin/` routes are
+/// listed in `allow_routes`.
+#[utoipa::path(
+  responses(
+    (status = 200, body = FuzzReport)
+  )
+)]
+#[post("/admin/fuzz")]
+pub async fn fuzz_routes(req: web::Json<FuzzRequest>, fuzzer: AppFuzzer) -> Result<impl Responder> {
+  let report = fuzzer.run(&req).await?;
+  Ok(HttpResponse::Ok().json(report))
+}
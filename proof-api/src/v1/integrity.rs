@@ -0,0 +1,39 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+
+use crate::integrity::AppSecretIntegrity;
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_secret_integrity)
+    .service(check_secret_integrity);
+}
+
+/// Get the result of the last check of the stored account secrets.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SecretIntegrityReport)
+  )
+)]
+#[get("/admin/accounts/integrity")]
+pub async fn get_secret_integrity(integrity: AppSecretIntegrity) -> Result<impl Responder> {
+  Ok(HttpResponse::Ok().json(integrity.last_report()))
+}
+
+/// Check the stored account secrets now.
+///
+/// Checks that each secret key decodes and belongs to the account's public key.
+/// With `SECRET_CHECK_CANARY=true` a random value is also encrypted and decrypted.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SecretIntegrityReport)
+  )
+)]
+#[post("/admin/accounts/integrity/check")]
+pub async fn check_secret_integrity(
+  integrity: AppSecretIntegrity,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let report = integrity.check(&repo).await?;
+  Ok(HttpResponse::Ok().json(report))
+}
@@ -0,0 +1,26 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::error::Error;
+
+use crate::decrypt_jobs::AppDecryptJobs;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_job);
+}
+
+/// Get a background decryption job.
+///
+/// Results are kept for an hour after the job finished.
+#[utoipa::path(
+  responses(
+    (status = 200, body = DecryptJob)
+  )
+)]
+#[get("/jobs/{job_id}")]
+pub async fn get_job(job_id: web::Path<Uuid>, jobs: AppDecryptJobs) -> Result<impl Responder> {
+  let job = jobs
+    .get_job(*job_id)
+    .ok_or_else(|| Error::not_found("Job"))?;
+  Ok(HttpResponse::Ok().json(job))
+}
@@ -0,0 +1,50 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{error::Error, SetAmountLimit};
+
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_amount_limits).service(set_amount_limit);
+}
+
+/// Get the account's amount limits.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [AmountLimit])
+  )
+)]
+#[get("/admin/accounts/{confidential_account}/limits")]
+pub async fn get_amount_limits(
+  confidential_account: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  repo
+    .get_account(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  let limits = repo.get_amount_limits(&confidential_account).await?;
+  Ok(HttpResponse::Ok().json(limits))
+}
+
+/// Set the account's amount limits for one asset, or all assets if `asset_id` isn't set.
+///
+/// Sender proofs over a limit need a second user's approval of a `limit_override`.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AmountLimit)
+  )
+)]
+#[post("/admin/accounts/{confidential_account}/limits")]
+pub async fn set_amount_limit(
+  confidential_account: web::Path<String>,
+  req: web::Json<SetAmountLimit>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let limit = repo
+    .set_amount_limit(&confidential_account, &req)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  log::info!("Amount limits of {confidential_account} set: {limit:?}");
+  Ok(HttpResponse::Ok().json(limit))
+}
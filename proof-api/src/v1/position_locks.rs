@@ -0,0 +1,77 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{error::Error, CreatePositionLock};
+
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_position_locks)
+    .service(create_position_lock)
+    .service(delete_position_lock);
+}
+
+/// Get the account's current and upcoming position locks.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [PositionLock])
+  )
+)]
+#[get("/admin/accounts/{confidential_account}/position_locks")]
+pub async fn get_position_locks(
+  confidential_account: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  repo
+    .get_account(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  let locks = repo.get_position_locks(&confidential_account).await?;
+  Ok(HttpResponse::Ok().json(locks))
+}
+
+/// Lock an account asset (or all of the account's assets) for a time window.
+///
+/// No sender proofs are issued during the window.  Requests either fail (`reject`) or
+/// wait until the window ends (`defer`).
+#[utoipa::path(
+  responses(
+    (status = 200, body = PositionLock)
+  )
+)]
+#[post("/admin/accounts/{confidential_account}/position_locks")]
+pub async fn create_position_lock(
+  confidential_account: web::Path<String>,
+  req: web::Json<CreatePositionLock>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  req.validate()?;
+  let lock = repo
+    .create_position_lock(&confidential_account, &req)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  log::info!("Position lock of {confidential_account} created: {lock:?}");
+  Ok(HttpResponse::Ok().json(lock))
+}
+
+/// Remove a position lock.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/admin/accounts/{confidential_account}/position_locks/{lock_id}")]
+pub async fn delete_position_lock(
+  path: web::Path<(String, i64)>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let (confidential_account, lock_id) = path.into_inner();
+  if !repo
+    .delete_position_lock(&confidential_account, lock_id)
+    .await?
+  {
+    return Err(Error::not_found("Position lock").into());
+  }
+  log::info!("Position lock {lock_id} of {confidential_account} removed");
+  Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,115 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{error::Error, CreateProofPool, ProofPoolStatus};
+
+use crate::position_locks::check_position_locks;
+use crate::proof_pools::AppProofPools;
+use crate::replay::ReplayProtection;
+use crate::repo::Repository;
+use crate::screening::AppScreening;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_proof_pools)
+    .service(get_proof_pool)
+    .service(create_proof_pool)
+    .service(release_proof_pool);
+}
+
+/// Get the sender proof pools of an account's asset.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [ProofPool])
+  )
+)]
+#[get("/accounts/{confidential_account}/assets/{asset_id}/proof_pools")]
+pub async fn get_proof_pools(
+  path: web::Path<(String, Uuid)>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
+  let pools = repo
+    .get_proof_pools(&confidential_account, asset_id)
+    .await?;
+  Ok(HttpResponse::Ok().json(pools))
+}
+
+/// Get a sender proof pool.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ProofPool)
+  )
+)]
+#[get("/proof_pools/{pool_id}")]
+pub async fn get_proof_pool(pool_id: web::Path<i64>, repo: Repository) -> Result<impl Responder> {
+  let pool = repo
+    .get_proof_pool(*pool_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Proof pool"))?;
+  Ok(HttpResponse::Ok().json(pool))
+}
+
+/// Pre-generate a pool of sender proofs for a fixed amount to a known receiver.
+///
+/// The proofs are generated in the background.  Sender proofs (and `sender_affirm_leg`)
+/// of the account asset for the same receiver, auditors and amount then use the pooled
+/// proofs in order, instead of generating a new proof.  Other sends and burns of the
+/// account asset are refused until the pool is used up, expires or is released.
+#[utoipa::path(
+  responses(
+    (status = 202, body = ProofPool)
+  )
+)]
+#[post(
+  "/accounts/{confidential_account}/assets/{asset_id}/proof_pools",
+  wrap = "ReplayProtection"
+)]
+pub async fn create_proof_pool(
+  path: web::Path<(String, Uuid)>,
+  req: web::Json<CreateProofPool>,
+  repo: Repository,
+  screening: AppScreening,
+  proof_pools: AppProofPools,
+) -> Result<impl Responder> {
+  let (confidential_account, asset_id) = path.into_inner();
+  let confidential_account = repo
+    .resolve_asset_account(&confidential_account, asset_id)
+    .await?;
+  // Get the account asset with account secret key.
+  let account_asset = repo
+    .get_account_asset_with_secret(&confidential_account, asset_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Account Asset"))?;
+  account_asset.account.ensure_unlocked()?;
+  check_position_locks(&repo, account_asset.account.account_id, Some(asset_id)).await?;
+
+  // Screen the receiver.
+  screening.screen_receiver(&req.receiver()?, None).await?;
+
+  let pool = proof_pools.create(account_asset, &req).await?;
+  Ok(HttpResponse::Accepted().json(pool))
+}
+
+/// Release a sender proof pool.
+///
+/// Unused proofs are dropped and their amounts added back to the tracked balance.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ProofPool)
+  )
+)]
+#[post("/proof_pools/{pool_id}/release")]
+pub async fn release_proof_pool(
+  pool_id: web::Path<i64>,
+  proof_pools: AppProofPools,
+) -> Result<impl Responder> {
+  let pool = proof_pools
+    .release_pool(*pool_id, ProofPoolStatus::Released, None)
+    .await?
+    .ok_or_else(|| Error::not_found("Active proof pool"))?;
+  Ok(HttpResponse::Ok().json(pool))
+}
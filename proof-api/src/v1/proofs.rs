@@ -0,0 +1,80 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{error::Error, FormatQuery, FormattedProofRecord};
+
+use crate::formatting::amount_formatter;
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_account_proofs).service(get_proof);
+}
+
+/// Proof history filter.  All filters must match.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct ProofFilter {
+  /// Only proofs for this asset.
+  pub asset_id: Option<Uuid>,
+  /// Only proofs of this type (`sender` or `burn`).
+  pub proof_type: Option<String>,
+}
+
+/// Get the sender and burn proofs generated by a confidential account, newest first.
+///
+/// With `locale` each proof includes its formatted amount.
+#[utoipa::path(
+  params(ProofFilter, FormatQuery),
+  responses(
+    (status = 200, body = [FormattedProofRecord])
+  )
+)]
+#[get("/accounts/{confidential_account}/proofs")]
+pub async fn get_account_proofs(
+  confidential_account: web::Path<String>,
+  filter: web::Query<ProofFilter>,
+  format: web::Query<FormatQuery>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  repo
+    .get_account(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  let proofs = repo
+    .get_account_proofs(
+      &confidential_account,
+      filter.asset_id,
+      filter.proof_type.as_deref(),
+    )
+    .await?;
+  let formatter = amount_formatter(&repo, &format).await?;
+  let proofs = proofs
+    .into_iter()
+    .map(|proof| FormattedProofRecord::new(proof, formatter.as_ref()))
+    .collect::<Vec<_>>();
+  Ok(HttpResponse::Ok().json(proofs))
+}
+
+/// Get a previously generated proof.
+///
+/// With `locale` it includes the formatted amount.
+#[utoipa::path(
+  params(FormatQuery),
+  responses(
+    (status = 200, body = FormattedProofRecord)
+  )
+)]
+#[get("/proofs/{proof_id}")]
+pub async fn get_proof(
+  proof_id: web::Path<i64>,
+  format: web::Query<FormatQuery>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let proof = repo
+    .get_proof(*proof_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Proof"))?;
+  let formatter = amount_formatter(&repo, &format).await?;
+  Ok(HttpResponse::Ok().json(FormattedProofRecord::new(proof, formatter.as_ref())))
+}
@@ -0,0 +1,38 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{error::Error, Receipt, ReceiptVerifyResult};
+
+use crate::receipts::AppReceiptSigner;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_receipt_public_key).service(verify_receipt);
+}
+
+/// Get the server's public key used to sign receipts.
+#[utoipa::path(
+  responses(
+    (status = 200, body = String)
+  )
+)]
+#[get("/receipts/public_key")]
+pub async fn get_receipt_public_key(receipts: AppReceiptSigner) -> Result<impl Responder> {
+  let public_key = receipts
+    .public_key()
+    .ok_or_else(|| Error::not_found("Receipt signing key"))?;
+  Ok(HttpResponse::Ok().json(public_key))
+}
+
+/// Verify a receipt signed by this server.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ReceiptVerifyResult)
+  )
+)]
+#[post("/receipts/verify")]
+pub async fn verify_receipt(
+  receipt: web::Json<Receipt>,
+  receipts: AppReceiptSigner,
+) -> Result<impl Responder> {
+  let valid = receipts.verify(&receipt);
+  Ok(HttpResponse::Ok().json(ReceiptVerifyResult { valid }))
+}
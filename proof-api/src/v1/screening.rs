@@ -0,0 +1,109 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{error::Error, CreateScreeningEntry, UpdateScreeningEntry};
+
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_all_screening_entries)
+    .service(get_screening_entry)
+    .service(create_screening_entry)
+    .service(update_screening_entry)
+    .service(delete_screening_entry);
+}
+
+/// Get the static screening lists (used with `SCREENING=deny_list` or `allow_list`).
+#[utoipa::path(
+  responses(
+    (status = 200, body = [ScreeningEntry])
+  )
+)]
+#[get("/screening/list")]
+pub async fn get_all_screening_entries(repo: Repository) -> Result<impl Responder> {
+  let entries = repo.get_screening_entries().await?;
+  Ok(HttpResponse::Ok().json(entries))
+}
+
+/// Get the screening list entry of a confidential account or identity.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ScreeningEntry)
+  )
+)]
+#[get("/screening/list/{subject}")]
+pub async fn get_screening_entry(
+  subject: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let entry = repo
+    .get_screening_entry(&subject)
+    .await?
+    .ok_or_else(|| Error::not_found("Screening entry"))?;
+  Ok(HttpResponse::Ok().json(entry))
+}
+
+/// Add a confidential account or identity to the allow or deny list.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ScreeningEntry)
+  )
+)]
+#[post("/screening/list")]
+pub async fn create_screening_entry(
+  req: web::Json<CreateScreeningEntry>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let entry = repo
+    .create_screening_entry(&req)
+    .await?
+    .ok_or_else(|| Error::other("The subject is already on a screening list"))?;
+  log::info!(
+    "Screening: {} added to the {} list",
+    entry.subject,
+    entry.list
+  );
+  Ok(HttpResponse::Ok().json(entry))
+}
+
+/// Update the screening list entry of a confidential account or identity.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ScreeningEntry)
+  )
+)]
+#[put("/screening/list/{subject}")]
+pub async fn update_screening_entry(
+  subject: web::Path<String>,
+  req: web::Json<UpdateScreeningEntry>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let entry = repo
+    .update_screening_entry(&subject, &req)
+    .await?
+    .ok_or_else(|| Error::not_found("Screening entry"))?;
+  log::info!(
+    "Screening: {} moved to the {} list",
+    entry.subject,
+    entry.list
+  );
+  Ok(HttpResponse::Ok().json(entry))
+}
+
+/// Remove a confidential account or identity from the screening lists.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/screening/list/{subject}")]
+pub async fn delete_screening_entry(
+  subject: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  if !repo.delete_screening_entry(&subject).await? {
+    return Err(Error::not_found("Screening entry").into());
+  }
+  log::info!("Screening: {subject} removed");
+  Ok(HttpResponse::Ok().finish())
+}
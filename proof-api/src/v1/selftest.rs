@@ -0,0 +1,29 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::SelfTestReport;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_selftest);
+}
+
+/// Self-test of the crypto stack.
+///
+/// Generates throwaway accounts, then creates and verifies a small sender proof and burn
+/// proof in-process.  Use it as a smoke check after deploys and for monitoring, it returns
+/// `503` if a step failed.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SelfTestReport),
+    (status = 503, body = SelfTestReport)
+  )
+)]
+#[get("/selftest")]
+pub async fn get_selftest() -> Result<impl Responder> {
+  let report = SelfTestReport::run();
+  if report.ok {
+    Ok(HttpResponse::Ok().json(report))
+  } else {
+    log::error!("Self-test failed: {:?}", report.steps.last());
+    Ok(HttpResponse::ServiceUnavailable().json(report))
+  }
+}
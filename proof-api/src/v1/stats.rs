@@ -0,0 +1,21 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::ProofStats;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_proof_stats);
+}
+
+/// Get proof generation and verification statistics.
+///
+/// Durations and serialized proof sizes since startup, grouped by operation, asset
+/// and number of auditors.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [ProofStatsEntry])
+  )
+)]
+#[get("/stats/proofs")]
+pub async fn get_proof_stats() -> Result<impl Responder> {
+  Ok(HttpResponse::Ok().json(ProofStats::global().entries()))
+}
@@ -0,0 +1,83 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::error::Error;
+
+use crate::repo::Repository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_user_account_access)
+    .service(grant_account_access)
+    .service(revoke_account_access);
+}
+
+/// Get the accounts a user may decrypt.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [UserAccountAccess])
+  )
+)]
+#[get("/admin/users/{user_name}/accounts")]
+pub async fn get_user_account_access(
+  user_name: web::Path<String>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let access = repo.get_user_account_access(&user_name).await?;
+  Ok(HttpResponse::Ok().json(access))
+}
+
+/// Allow a user to decrypt an account.
+///
+/// The user can then request decryption tokens for the account (`/decrypt_tokens`).
+#[utoipa::path(
+  responses(
+    (status = 200, body = [UserAccountAccess])
+  )
+)]
+#[post("/admin/users/{user_name}/accounts/{confidential_account}")]
+pub async fn grant_account_access(
+  path: web::Path<(String, String)>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let (user_name, confidential_account) = path.into_inner();
+  let account = repo
+    .get_account(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  if !repo
+    .grant_account_access(&user_name, account.account_id)
+    .await?
+  {
+    return Err(Error::not_found("User").into());
+  }
+  log::info!("User {user_name} may decrypt account {confidential_account}");
+  let access = repo.get_user_account_access(&user_name).await?;
+  Ok(HttpResponse::Ok().json(access))
+}
+
+/// Revoke a user's access to an account.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [UserAccountAccess])
+  )
+)]
+#[delete("/admin/users/{user_name}/accounts/{confidential_account}")]
+pub async fn revoke_account_access(
+  path: web::Path<(String, String)>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let (user_name, confidential_account) = path.into_inner();
+  let account = repo
+    .get_account(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  if !repo
+    .revoke_account_access(&user_name, account.account_id)
+    .await?
+  {
+    return Err(Error::not_found("Account access").into());
+  }
+  log::info!("Revoked access of user {user_name} to account {confidential_account}");
+  let access = repo.get_user_account_access(&user_name).await?;
+  Ok(HttpResponse::Ok().json(access))
+}
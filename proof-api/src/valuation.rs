@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode, Url};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  AccountAsset, AmountFormatter, AssetPrice, AssetValuation, PortfolioValuation,
+  ValuedAccountAsset,
+};
+
+pub type AppValuation = Data<Valuation>;
+
+/// Default seconds to cache prices from a price feed.
+const DEFAULT_FEED_CACHE_SECS: u64 = 60;
+
+/// Source of asset prices.
+///
+/// Implement this to plug in a pricing service.
+#[async_trait]
+pub trait PriceProvider: Send + Sync + 'static {
+  /// Price of one unit of the asset in `currency`.  `None` if the asset has no price.
+  async fn price(&self, asset_id: Uuid, currency: &str) -> Result<Option<Decimal>>;
+}
+
+/// Static price table.
+pub struct StaticPrices {
+  prices: BTreeMap<(Uuid, String), Decimal>,
+}
+
+impl StaticPrices {
+  pub fn new(prices: Vec<AssetPrice>) -> Self {
+    Self {
+      prices: prices
+        .into_iter()
+        .map(|p| ((p.asset_id, p.currency.to_uppercase()), p.price))
+        .collect(),
+    }
+  }
+
+  /// Load the prices from a JSON file with a list of `AssetPrice`.
+  pub fn from_file(path: &str) -> Result<Self> {
+    let prices = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(Self::new(prices))
+  }
+}
+
+#[async_trait]
+impl PriceProvider for StaticPrices {
+  async fn price(&self, asset_id: Uuid, currency: &str) -> Result<Option<Decimal>> {
+    let key = (asset_id, currency.to_uppercase());
+    Ok(self.prices.get(&key).copied())
+  }
+}
+
+/// External price feed.
+///
+/// Prices are requested with `GET {url}?asset_id={asset_id}&currency={currency}`, which
+/// must return an `AssetPrice`, or `404 Not Found` if the asset has no price.  Prices are
+/// cached for `cache_ttl`.
+pub struct PriceFeed {
+  client: Client,
+  url: Url,
+  cache_ttl: Duration,
+  cache: Mutex<BTreeMap<(Uuid, String), (Instant, Option<Decimal>)>>,
+}
+
+impl PriceFeed {
+  pub fn new(url: &str, cache_ttl: Duration) -> Result<Self> {
+    Ok(Self {
+      client: Client::new(),
+      url: Url::parse(url)?,
+      cache_ttl,
+      cache: Default::default(),
+    })
+  }
+
+  fn cached(&self, key: &(Uuid, String)) -> Option<Option<Decimal>> {
+    let cache = self.cache.lock().expect("Price cache poisoned");
+    cache
+      .get(key)
+      .filter(|(fetched, _)| fetched.elapsed() < self.cache_ttl)
+      .map(|(_, price)| *price)
+  }
+
+  async fn fetch(&self, asset_id: Uuid, currency: &str) -> Result<Option<Decimal>> {
+    let resp = self
+      .client
+      .get(self.url.clone())
+      .query(&[
+        ("asset_id", asset_id.to_string()),
+        ("currency", currency.to_string()),
+      ])
+      .send()
+      .await?;
+    if resp.status() == StatusCode::NOT_FOUND {
+      return Ok(None);
+    }
+    let price: AssetPrice = resp.error_for_status()?.json().await?;
+    Ok(Some(price.price))
+  }
+}
+
+#[async_trait]
+impl PriceProvider for PriceFeed {
+  async fn price(&self, asset_id: Uuid, currency: &str) -> Result<Option<Decimal>> {
+    let key = (asset_id, currency.to_uppercase());
+    if let Some(price) = self.cached(&key) {
+      return Ok(price);
+    }
+    let price = self.fetch(asset_id, &key.1).await?;
+    self
+      .cache
+      .lock()
+      .expect("Price cache poisoned")
+      .insert(key, (Instant::now(), price));
+    Ok(price)
+  }
+}
+
+/// Fiat-equivalent valuation of decrypted balances.
+pub struct Valuation {
+  provider: Option<Box<dyn PriceProvider>>,
+}
+
+impl Valuation {
+  /// Use the price source configured by `VALUATION`: `none` (default), `static` (prices
+  /// from the `VALUATION_PRICES_FILE` JSON file) or `feed` (prices from
+  /// `VALUATION_FEED_URL`, cached for `VALUATION_FEED_CACHE_SECS`).
+  pub fn from_env() -> Result<AppValuation> {
+    let mode = std::env::var("VALUATION").unwrap_or_default();
+    let provider: Option<Box<dyn PriceProvider>> = match mode.as_str() {
+      "" | "none" => None,
+      "static" => {
+        let path = std::env::var("VALUATION_PRICES_FILE")
+          .map_err(|_| Error::other("VALUATION_PRICES_FILE is required"))?;
+        Some(Box::new(StaticPrices::from_file(&path)?))
+      }
+      "feed" => {
+        let url = std::env::var("VALUATION_FEED_URL")
+          .map_err(|_| Error::other("VALUATION_FEED_URL is required"))?;
+        let cache_secs = std::env::var("VALUATION_FEED_CACHE_SECS")
+          .ok()
+          .and_then(|v| v.parse().ok())
+          .unwrap_or(DEFAULT_FEED_CACHE_SECS);
+        Some(Box::new(PriceFeed::new(
+          &url,
+          Duration::from_secs(cache_secs),
+        )?))
+      }
+      _ => return Err(Error::Other(format!("Unknown VALUATION mode: {mode}"))),
+    };
+    if provider.is_some() {
+      log::info!("Balance valuation: {mode}");
+    }
+    Ok(Data::new(Self { provider }))
+  }
+
+  /// Use a custom price provider.
+  pub fn with_provider(provider: impl PriceProvider) -> AppValuation {
+    Data::new(Self {
+      provider: Some(Box::new(provider)),
+    })
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.provider.is_some()
+  }
+
+  fn provider(&self) -> Result<&dyn PriceProvider> {
+    self
+      .provider
+      .as_deref()
+      .ok_or_else(|| Error::other("Balance valuation isn't enabled (see `VALUATION`)"))
+  }
+
+  /// Value the account assets' balances in `currency`, if given.
+  ///
+  /// The balances are also formatted with `formatter`, if given.
+  pub async fn value_account_assets(
+    &self,
+    account_assets: Vec<AccountAsset>,
+    currency: Option<&str>,
+    formatter: Option<&AmountFormatter>,
+  ) -> Result<Vec<ValuedAccountAsset>> {
+    let mut valued = Vec::with_capacity(account_assets.len());
+    for account_asset in account_assets {
+      let valuation = match currency {
+        Some(currency) => {
+          let balance = account_asset.balance as u64;
+          self
+            .provider()?
+            .price(account_asset.asset_id, currency)
+            .await?
+            .map(|price| AssetValuation::new(account_asset.asset_id, balance, currency, price))
+        }
+        None => None,
+      };
+      let formatted_balance = formatter.map(|formatter| {
+        formatter.format(Some(account_asset.asset_id), account_asset.balance as u64)
+      });
+      valued.push(ValuedAccountAsset {
+        account_asset,
+        valuation,
+        formatted_balance,
+      });
+    }
+    Ok(valued)
+  }
+
+  /// Aggregated value of the account assets' balances in `currency`.
+  pub async fn value_portfolio<'a>(
+    &self,
+    account_assets: impl IntoIterator<Item = &'a AccountAsset>,
+    currency: &str,
+  ) -> Result<PortfolioValuation> {
+    let provider = self.provider()?;
+    let mut valuation = PortfolioValuation::new(currency);
+    for account_asset in account_assets {
+      let price = provider.price(account_asset.asset_id, currency).await?;
+      valuation.add(account_asset.asset_id, account_asset.balance as u64, price);
+    }
+    Ok(valuation)
+  }
+}
@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+
+use actix_web::{
+  body::{to_bytes, BoxBody, EitherBody, MessageBody},
+  dev::{Service, ServiceRequest, ServiceResponse},
+  http::header,
+  HttpMessage, HttpRequest,
+};
+use futures_util::future::LocalBoxFuture;
+
+use polymesh_private_proof_shared::{ResponseEnvelope, ResponseWarning, WARNING_PROOF_SIZE};
+
+/// Request header to wrap the response in a `ResponseEnvelope` (`data`, `warnings`).
+pub const ENVELOPE_HEADER: &str = "x-response-envelope";
+
+/// Warnings of the current request.
+#[derive(Clone, Default)]
+struct RequestWarnings(Vec<ResponseWarning>);
+
+/// Add a non-fatal warning to the response.
+///
+/// The warning is always logged and only returned to clients that asked for the response
+/// envelope with the `X-Response-Envelope: true` header.
+pub fn add_warning(http_req: &HttpRequest, code: &str, message: impl Into<String>) {
+  let warning = ResponseWarning::new(code, message);
+  log::warn!(
+    "{} {}: {}",
+    http_req.method(),
+    http_req.path(),
+    warning.message
+  );
+  let mut extensions = http_req.extensions_mut();
+  match extensions.get_mut::<RequestWarnings>() {
+    Some(warnings) => warnings.0.push(warning),
+    None => {
+      extensions.insert(RequestWarnings(vec![warning]));
+    }
+  }
+}
+
+/// Warn about proofs at or above `PROOF_SIZE_WARNING` bytes (disabled by default).
+pub fn check_proof_size(http_req: &HttpRequest, size: usize) {
+  static LIMIT: OnceLock<Option<usize>> = OnceLock::new();
+  let limit = LIMIT.get_or_init(|| {
+    std::env::var("PROOF_SIZE_WARNING")
+      .ok()
+      .and_then(|v| v.parse().ok())
+  });
+  if let Some(limit) = limit.filter(|limit| size >= *limit) {
+    add_warning(
+      http_req,
+      WARNING_PROOF_SIZE,
+      format!("Proof size {size} bytes is at or above {limit} bytes"),
+    );
+  }
+}
+
+/// Wraps successful JSON responses in a `ResponseEnvelope`, if the client asked for it.
+pub struct ResponseEnvelopes;
+
+impl ResponseEnvelopes {
+  /// Middleware for `wrap_fn`.
+  pub fn middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+  ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, actix_web::Error>>
+  where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+  {
+    let envelope = req
+      .headers()
+      .get(ENVELOPE_HEADER)
+      .and_then(|val| val.to_str().ok())
+      .map(|val| val == "true" || val == "1")
+      .unwrap_or(false);
+    let fut = srv.call(req);
+    Box::pin(async move {
+      let res = fut.await?;
+      let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .map(|val| val.starts_with("application/json"))
+        .unwrap_or(false);
+      if !envelope || !is_json || !res.status().is_success() {
+        return Ok(res.map_into_left_body());
+      }
+      let warnings = res
+        .request()
+        .extensions()
+        .get::<RequestWarnings>()
+        .map(|warnings| warnings.0.clone())
+        .unwrap_or_default();
+      let (req, res) = res.into_parts();
+      let (res, body) = res.into_parts();
+      let body = to_bytes(body).await.map_err(|err| {
+        let err: Box<dyn std::error::Error> = err.into();
+        actix_web::error::ErrorInternalServerError(err.to_string())
+      })?;
+      let data: serde_json::Value = serde_json::from_slice(&body)?;
+      let body = serde_json::to_string(&ResponseEnvelope { data, warnings })?;
+      let res = res.set_body(BoxBody::new(body));
+      Ok(ServiceResponse::new(req, res).map_into_right_body())
+    })
+  }
+}
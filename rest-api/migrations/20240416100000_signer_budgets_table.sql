@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS signer_budgets
+(
+    public_key            TEXT PRIMARY KEY NOT NULL,
+
+    max_daily_extrinsics  INTEGER,
+    max_total_fees        INTEGER,
+    alert_percent         INTEGER DEFAULT 80 NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS signer_usage
+(
+    id             INTEGER PRIMARY KEY NOT NULL,
+
+    public_key     TEXT NOT NULL,
+    tx_hash        TEXT NOT NULL,
+    fee            INTEGER DEFAULT 0 NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS signer_usage_public_key_idx ON signer_usage(public_key, created_at);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS webhook_outbox
+(
+    id                INTEGER PRIMARY KEY NOT NULL,
+
+    event             TEXT NOT NULL,
+
+    -- pending, delivered, dead
+    status            TEXT DEFAULT 'pending' NOT NULL,
+    attempts          INTEGER DEFAULT 0 NOT NULL,
+    last_error        TEXT,
+    next_attempt_at   TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    created_at        TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at        TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS webhook_outbox_status_idx ON webhook_outbox(status, next_attempt_at);
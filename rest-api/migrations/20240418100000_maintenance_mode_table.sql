@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS maintenance_mode
+(
+    id                INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+
+    enabled           BOOLEAN DEFAULT FALSE NOT NULL,
+    -- Seconds clients should wait before retrying.
+    retry_after       INTEGER DEFAULT 60 NOT NULL,
+
+    updated_at        TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+INSERT OR IGNORE INTO maintenance_mode (id) VALUES (1);
@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS ledger_entries
+(
+    id                INTEGER PRIMARY KEY NOT NULL,
+
+    tx_hash           TEXT NOT NULL,
+    block_number      INTEGER NOT NULL,
+    -- Index of the balance update event in the transaction's processed events.
+    event_index       INTEGER NOT NULL,
+
+    -- Confidential account (hex) or a system account (issuance, burn, counterparty).
+    ledger_account    TEXT NOT NULL,
+    asset_id          BLOB NOT NULL,
+    debit             INTEGER DEFAULT 0 NOT NULL,
+    credit            INTEGER DEFAULT 0 NOT NULL,
+    -- mint, burn, settlement, apply_incoming
+    cause             TEXT NOT NULL,
+
+    created_at        TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    UNIQUE(tx_hash, event_index, ledger_account)
+);
+
+CREATE INDEX IF NOT EXISTS ledger_entries_account_idx ON ledger_entries(ledger_account, asset_id);
@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS balance_history
+(
+    id                INTEGER PRIMARY KEY NOT NULL,
+    account_asset_id  INTEGER NOT NULL,
+    account_id        INTEGER NOT NULL,
+    asset_id          BLOB NOT NULL,
+
+    balance           INTEGER NOT NULL,
+    enc_balance       BLOB NOT NULL,
+    -- Block the balance change was included in (NULL for local-only changes).
+    block_number      INTEGER,
+
+    created_at        TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_asset_id) REFERENCES account_assets(account_asset_id)
+);
+
+CREATE INDEX IF NOT EXISTS balance_history_account_asset_idx ON balance_history(account_asset_id, created_at);
+
+-- Start the history with the current balances.
+INSERT INTO balance_history (account_asset_id, account_id, asset_id, balance, enc_balance, created_at)
+  SELECT account_asset_id, account_id, asset_id, balance, enc_balance, updated_at FROM account_assets;
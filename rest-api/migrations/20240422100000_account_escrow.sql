@@ -0,0 +1,17 @@
+-- Escrowed accounts have their secret key split into encrypted shares.  The
+-- `secret_key` column is cleared once the shares are stored.
+ALTER TABLE accounts ADD COLUMN escrow_threshold INTEGER;
+
+CREATE TABLE IF NOT EXISTS account_escrow_shares
+(
+    share_id       INTEGER PRIMARY KEY NOT NULL,
+    account_id     INTEGER NOT NULL,
+
+    custodian      TEXT NOT NULL,
+    enc_share      BLOB NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_id) REFERENCES accounts(account_id),
+    UNIQUE (account_id, custodian)
+);
@@ -0,0 +1,2 @@
+-- Chain identity (DID) of the account, once resolved from the chain.
+ALTER TABLE accounts ADD COLUMN did TEXT;
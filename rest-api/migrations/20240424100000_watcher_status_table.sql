@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS watcher_status
+(
+    id                INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+
+    -- Latest block seen by the chain watcher.
+    head_block        INTEGER DEFAULT 0 NOT NULL,
+    -- Latest block published to the watcher's subscribers.
+    processed_block   INTEGER DEFAULT 0 NOT NULL,
+    catching_up       BOOLEAN DEFAULT FALSE NOT NULL,
+
+    updated_at        TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+INSERT OR IGNORE INTO watcher_status (id) VALUES (1);
@@ -0,0 +1,61 @@
+-- Confidential accounts and auditors are stored hex encoded (same as `ledger_entries`)
+-- and asset ids as text, so the existing JSON legs can be moved with SQLite's JSON functions.
+CREATE TABLE IF NOT EXISTS settlement_legs
+(
+    settlement_id     INTEGER NOT NULL,
+    leg_id            INTEGER NOT NULL,
+
+    sender            TEXT NOT NULL,
+    receiver          TEXT NOT NULL,
+    -- Mediator identities (JSON array).
+    mediators         TEXT DEFAULT '[]' NOT NULL,
+
+    PRIMARY KEY (settlement_id, leg_id)
+);
+
+CREATE INDEX IF NOT EXISTS settlement_legs_sender_idx ON settlement_legs(sender);
+CREATE INDEX IF NOT EXISTS settlement_legs_receiver_idx ON settlement_legs(receiver);
+
+CREATE TABLE IF NOT EXISTS leg_assets
+(
+    settlement_id     INTEGER NOT NULL,
+    leg_id            INTEGER NOT NULL,
+    asset_id          TEXT NOT NULL,
+
+    PRIMARY KEY (settlement_id, leg_id, asset_id)
+);
+
+CREATE INDEX IF NOT EXISTS leg_assets_asset_idx ON leg_assets(asset_id);
+
+CREATE TABLE IF NOT EXISTS leg_auditors
+(
+    settlement_id     INTEGER NOT NULL,
+    leg_id            INTEGER NOT NULL,
+    asset_id          TEXT NOT NULL,
+    auditor           TEXT NOT NULL,
+
+    PRIMARY KEY (settlement_id, leg_id, asset_id, auditor)
+);
+
+CREATE INDEX IF NOT EXISTS leg_auditors_auditor_idx ON leg_auditors(auditor);
+
+-- Move the legs of existing settlements.
+INSERT INTO settlement_legs (settlement_id, leg_id, sender, receiver, mediators)
+  SELECT s.settlement_id, leg.key,
+    lower(json_extract(leg.value, '$.sender')),
+    lower(json_extract(leg.value, '$.receiver')),
+    COALESCE(json_extract(leg.value, '$.mediators'), '[]')
+  FROM settlements as s, json_each(s.legs) as leg;
+
+INSERT INTO leg_assets (settlement_id, leg_id, asset_id)
+  SELECT s.settlement_id, leg.key, lower(asset.key)
+  FROM settlements as s, json_each(s.legs) as leg,
+    json_each(leg.value, '$.assets_and_auditors') as asset;
+
+INSERT INTO leg_auditors (settlement_id, leg_id, asset_id, auditor)
+  SELECT s.settlement_id, leg.key, lower(asset.key), lower(auditor.value)
+  FROM settlements as s, json_each(s.legs) as leg,
+    json_each(leg.value, '$.assets_and_auditors') as asset,
+    json_each(asset.value) as auditor;
+
+ALTER TABLE settlements DROP COLUMN legs;
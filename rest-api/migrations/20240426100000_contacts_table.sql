@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS contacts
+(
+    -- Confidential account (hex), same as the settlement leg tables.
+    confidential_account  TEXT PRIMARY KEY NOT NULL,
+
+    display_name          TEXT NOT NULL,
+    organization          TEXT,
+
+    created_at            TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at            TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
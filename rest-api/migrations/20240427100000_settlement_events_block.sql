@@ -0,0 +1,4 @@
+-- Block number of the settlement event.  NULL for events recorded before this column.
+ALTER TABLE settlement_events ADD COLUMN block_number INTEGER;
+
+CREATE INDEX IF NOT EXISTS settlement_events_block_idx ON settlement_events(block_number);
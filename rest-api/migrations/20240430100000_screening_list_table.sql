@@ -0,0 +1,12 @@
+-- Static allow/deny lists for screening receivers.
+CREATE TABLE IF NOT EXISTS screening_list
+(
+    -- Confidential account or identity (`0x` prefixed hex).
+    subject      TEXT PRIMARY KEY NOT NULL,
+    -- `allow` or `deny`.
+    list         TEXT NOT NULL,
+    reason       TEXT,
+
+    created_at   TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at   TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
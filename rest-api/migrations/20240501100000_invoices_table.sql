@@ -0,0 +1,24 @@
+-- Payment requests (invoices) of receivers.
+CREATE TABLE IF NOT EXISTS invoices
+(
+    invoice_id      INTEGER PRIMARY KEY NOT NULL,
+
+    -- Shareable reference, used as the settlement memo.
+    reference       TEXT NOT NULL UNIQUE,
+    -- Receiver's confidential account (`0x` prefixed hex).
+    receiver        TEXT NOT NULL,
+    asset_id        BLOB NOT NULL,
+    amount          INTEGER NOT NULL,
+    memo            TEXT,
+    -- open, settling, paid, cancelled
+    status          TEXT DEFAULT 'open' NOT NULL,
+    settlement_id   INTEGER,
+
+    expires_at      TIMESTAMP NOT NULL,
+    paid_at         TIMESTAMP,
+    created_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS invoices_receiver_idx ON invoices(receiver);
+CREATE INDEX IF NOT EXISTS invoices_settlement_idx ON invoices(settlement_id);
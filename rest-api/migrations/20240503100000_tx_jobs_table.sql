@@ -0,0 +1,21 @@
+-- Background jobs tracking submitted transactions.
+CREATE TABLE IF NOT EXISTS tx_jobs
+(
+    job_id          INTEGER PRIMARY KEY NOT NULL,
+
+    -- Endpoint that submitted the transaction.
+    operation       TEXT NOT NULL,
+    -- submitted, in_block, completed, failed
+    status          TEXT DEFAULT 'submitted' NOT NULL,
+    finalize        BOOLEAN NOT NULL,
+    tx_hash         TEXT NOT NULL,
+    block_hash      TEXT,
+    err_msg         TEXT,
+    -- Transaction results (JSON).
+    result          TEXT,
+
+    created_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS tx_jobs_status_idx ON tx_jobs(status);
@@ -0,0 +1,31 @@
+-- Deposit accounts.  Transfers to them are credited when the sender affirms.
+CREATE TABLE IF NOT EXISTS deposit_accounts
+(
+    -- Confidential account (`0x` prefixed hex).
+    confidential_account TEXT PRIMARY KEY NOT NULL,
+
+    created_at           TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+-- Transfers credited to deposit accounts.
+CREATE TABLE IF NOT EXISTS deposits
+(
+    deposit_id           INTEGER PRIMARY KEY NOT NULL,
+
+    settlement_id        INTEGER NOT NULL,
+    leg_id               INTEGER NOT NULL,
+    -- Deposit account (`0x` prefixed hex).
+    confidential_account TEXT NOT NULL,
+    asset_id             BLOB NOT NULL,
+    amount               INTEGER NOT NULL,
+    -- Internal reference (invoice or settlement memo).
+    reference            TEXT,
+    invoice_id           INTEGER,
+
+    created_at           TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    UNIQUE(settlement_id, leg_id, asset_id)
+);
+
+CREATE INDEX IF NOT EXISTS deposits_account_idx ON deposits(confidential_account);
+CREATE INDEX IF NOT EXISTS deposits_reference_idx ON deposits(reference);
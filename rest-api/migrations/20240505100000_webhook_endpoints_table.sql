@@ -0,0 +1,26 @@
+-- Registered webhook endpoints (in addition to `WEBHOOK_URL`).
+CREATE TABLE IF NOT EXISTS webhook_endpoints
+(
+    endpoint_id     INTEGER PRIMARY KEY NOT NULL,
+
+    url             TEXT NOT NULL,
+    -- backfilling, live
+    status          TEXT DEFAULT 'live' NOT NULL,
+    -- Stored chain events of blocks `backfill_from` to `live_from` are replayed, chain
+    -- events of newer blocks are sent live.
+    backfill_from   INTEGER,
+    live_from       INTEGER NOT NULL,
+    -- Last replayed block.
+    backfill_cursor INTEGER,
+
+    created_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+-- Outbox entries for registered endpoints.  `NULL` is `WEBHOOK_URL`.
+ALTER TABLE webhook_outbox ADD COLUMN endpoint_id INTEGER;
+-- Replayed by a backfill.
+ALTER TABLE webhook_outbox ADD COLUMN backfill INTEGER DEFAULT 0 NOT NULL;
+
+CREATE INDEX IF NOT EXISTS webhook_outbox_endpoint_idx ON webhook_outbox(endpoint_id, backfill, status);
+CREATE INDEX IF NOT EXISTS transactions_block_number_idx ON transactions(block_number);
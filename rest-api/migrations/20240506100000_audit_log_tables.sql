@@ -0,0 +1,36 @@
+-- Account operations (sender proofs, decryptions).
+CREATE TABLE IF NOT EXISTS audit_log
+(
+    id             INTEGER PRIMARY KEY NOT NULL,
+
+    account_id     INTEGER NOT NULL,
+    asset_id       BLOB,
+    -- sender_proof, decrypt
+    action         TEXT NOT NULL,
+    amount         INTEGER,
+    -- `X-User` header.
+    user           TEXT,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_id) REFERENCES accounts(account_id)
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_account_idx ON audit_log(account_id, action, created_at);
+
+-- Triggered anomaly detection rules.
+CREATE TABLE IF NOT EXISTS anomaly_alerts
+(
+    alert_id              INTEGER PRIMARY KEY NOT NULL,
+
+    rule                  TEXT NOT NULL,
+    -- `0x` prefixed hex.
+    confidential_account  TEXT NOT NULL,
+    asset_id              BLOB,
+    user                  TEXT,
+    details               TEXT NOT NULL,
+
+    created_at            TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS anomaly_alerts_account_idx ON anomaly_alerts(confidential_account, rule, created_at);
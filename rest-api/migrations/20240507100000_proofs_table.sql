@@ -0,0 +1,20 @@
+-- Generated sender and burn proofs.
+CREATE TABLE IF NOT EXISTS proofs
+(
+    proof_id       INTEGER PRIMARY KEY NOT NULL,
+
+    account_id     INTEGER NOT NULL,
+    asset_id       BLOB,
+    -- sender, burn
+    proof_type     TEXT NOT NULL,
+    amount         INTEGER NOT NULL,
+    -- `0x` prefixed hex.  NULL for burn proofs.
+    receiver       TEXT,
+    proof          BLOB NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(account_id) REFERENCES accounts(account_id)
+);
+
+CREATE INDEX IF NOT EXISTS proofs_account_idx ON proofs(account_id, created_at);
@@ -0,0 +1,16 @@
+-- Time-boxed session signers.  They sign with their parent signer's key, but can only
+-- submit the listed extrinsics.
+CREATE TABLE IF NOT EXISTS session_signers
+(
+    session_id     INTEGER PRIMARY KEY NOT NULL,
+    session_name   TEXT UNIQUE NOT NULL,
+
+    signer_name    TEXT NOT NULL,
+    -- JSON array of `confidential_asset` extrinsic names.
+    extrinsics     TEXT NOT NULL,
+    expires_at     TIMESTAMP NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS session_signers_signer_idx ON session_signers(signer_name);
@@ -0,0 +1,3 @@
+-- Hash of the latest block published to the watcher's subscribers.  Used to resume
+-- the chain watcher after a restart.
+ALTER TABLE watcher_status ADD COLUMN processed_hash TEXT;
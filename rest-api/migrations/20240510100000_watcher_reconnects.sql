@@ -0,0 +1,4 @@
+-- Chain watcher reconnects after a failed block subscription.
+ALTER TABLE watcher_status ADD COLUMN reconnects INTEGER DEFAULT 0 NOT NULL;
+ALTER TABLE watcher_status ADD COLUMN last_error TEXT;
+ALTER TABLE watcher_status ADD COLUMN last_reconnect_at TIMESTAMP;
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS venues
+(
+    -- On-chain confidential venue id.
+    venue_id      INTEGER PRIMARY KEY NOT NULL,
+
+    -- Signer that created (and owns) the venue.
+    signer        TEXT NOT NULL,
+    -- Local label.
+    label         TEXT,
+
+    created_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Transactions built for offline signers.
+CREATE TABLE IF NOT EXISTS unsigned_transactions
+(
+    build_id      INTEGER PRIMARY KEY NOT NULL,
+
+    -- Account (SS58 address) that signs the transaction.
+    account       TEXT NOT NULL,
+    -- Extrinsic and its arguments (JSON).
+    call          TEXT NOT NULL,
+    nonce         INTEGER NOT NULL,
+    -- SCALE encoded signing payload.
+    payload       BLOB NOT NULL,
+    tx_hash       TEXT,
+
+    created_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    submitted_at  TIMESTAMP
+);
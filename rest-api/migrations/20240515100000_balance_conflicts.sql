@@ -0,0 +1,31 @@
+-- Source of the last balance write: chain, local, manual.
+ALTER TABLE account_assets ADD COLUMN balance_source TEXT DEFAULT 'local' NOT NULL;
+
+-- Balance updates that disagreed with the stored balance.
+CREATE TABLE IF NOT EXISTS balance_conflicts
+(
+    conflict_id    INTEGER PRIMARY KEY NOT NULL,
+
+    account_asset_id  INTEGER NOT NULL,
+    -- chain, local, manual
+    source         TEXT NOT NULL,
+    balance        INTEGER NOT NULL,
+    enc_balance    BLOB NOT NULL,
+    block_number   INTEGER,
+
+    -- Stored balance when the update was made.
+    current_source   TEXT NOT NULL,
+    current_balance  INTEGER NOT NULL,
+
+    -- chain_wins, local_wins, review
+    strategy       TEXT NOT NULL,
+    -- applied, discarded, pending, accepted, rejected
+    status         TEXT NOT NULL,
+
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    resolved_at    TIMESTAMP,
+
+    FOREIGN KEY(account_asset_id) REFERENCES account_assets(account_asset_id)
+);
+
+CREATE INDEX IF NOT EXISTS balance_conflicts_status_idx ON balance_conflicts(status);
@@ -0,0 +1,26 @@
+-- Transactions that failed to submit, retried in the background.
+CREATE TABLE IF NOT EXISTS tx_outbox
+(
+    outbox_id       INTEGER PRIMARY KEY NOT NULL,
+
+    -- Endpoint that submitted the transaction.
+    operation       TEXT NOT NULL,
+    signer          TEXT NOT NULL,
+    extrinsic       TEXT NOT NULL,
+    -- SCALE encoded call.
+    call            BLOB NOT NULL,
+    finalize        BOOLEAN NOT NULL,
+    -- pending, submitted, completed, failed
+    status          TEXT DEFAULT 'pending' NOT NULL,
+    attempts        INTEGER DEFAULT 1 NOT NULL,
+    err_msg         TEXT,
+    tx_hash         TEXT,
+    -- Transaction results (JSON).
+    result          TEXT,
+
+    next_attempt_at TIMESTAMP NOT NULL,
+    created_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS tx_outbox_status_idx ON tx_outbox(status, next_attempt_at);
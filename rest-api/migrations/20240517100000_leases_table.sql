@@ -0,0 +1,11 @@
+-- Leases of the background tasks that only one instance runs.
+CREATE TABLE IF NOT EXISTS leases
+(
+    name        TEXT PRIMARY KEY NOT NULL,
+    -- Instance holding the lease.
+    holder      TEXT NOT NULL,
+
+    acquired_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    expires_at  TIMESTAMP NOT NULL,
+    updated_at  TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
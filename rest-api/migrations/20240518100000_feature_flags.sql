@@ -0,0 +1,14 @@
+-- Endpoints disabled (or re-enabled) at runtime.
+CREATE TABLE IF NOT EXISTS feature_flags
+(
+    flag_id     INTEGER PRIMARY KEY NOT NULL,
+
+    -- "METHOD /path" relative to `/v1`, i.e. "POST /proofs/*/sender_proof".
+    endpoint    TEXT NOT NULL UNIQUE,
+    enabled     BOOLEAN NOT NULL,
+    -- Explanation returned when the endpoint is disabled.
+    reason      TEXT,
+
+    created_at  TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at  TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Accounts with their incoming balances applied by the chain watcher.
+CREATE TABLE IF NOT EXISTS auto_apply_accounts
+(
+    -- Confidential account (`0x` prefixed hex).
+    confidential_account TEXT PRIMARY KEY NOT NULL,
+    -- Signer of the `apply_incoming_balance` transactions (the account's identity).
+    signer               TEXT NOT NULL,
+
+    created_at           TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at           TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Execute the venue's settlements with the venue signer once fully affirmed.
+ALTER TABLE venues ADD COLUMN auto_execute BOOLEAN DEFAULT FALSE NOT NULL;
@@ -0,0 +1,2 @@
+-- Number of decimals of the asset's amounts, for formatting.
+ALTER TABLE assets ADD COLUMN decimals INTEGER;
@@ -0,0 +1,17 @@
+-- Schedule of the periodic background jobs, run by the scheduler.
+CREATE TABLE IF NOT EXISTS scheduled_jobs
+(
+    name          TEXT PRIMARY KEY NOT NULL,
+    -- Seconds between successful runs.
+    interval_secs INTEGER NOT NULL,
+
+    next_run_at   TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    last_run_at   TIMESTAMP,
+    -- Error of the last run, if it failed.
+    last_error    TEXT,
+    -- Consecutive failed runs.
+    attempts      INTEGER DEFAULT 0 NOT NULL,
+
+    created_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Default signer of the transactions of our confidential accounts.
+CREATE TABLE IF NOT EXISTS account_signers
+(
+    -- Confidential account (`0x` prefixed hex).
+    confidential_account TEXT PRIMARY KEY NOT NULL,
+    -- Signer used when a transaction request omits `signer`.
+    signer               TEXT NOT NULL,
+
+    created_at           TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at           TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Status annotations of tracked settlements (disputes, investigations).
+CREATE TABLE IF NOT EXISTS settlement_annotations
+(
+    annotation_id   INTEGER PRIMARY KEY NOT NULL,
+    settlement_id   INTEGER NOT NULL,
+
+    -- disputed, under_investigation, resolved
+    status          TEXT NOT NULL,
+    notes           TEXT,
+    -- User that added the annotation (`X-User` header).
+    author          TEXT NOT NULL,
+
+    created_at      TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS settlement_annotations_settlement_idx
+  ON settlement_annotations(settlement_id, annotation_id);
@@ -0,0 +1,46 @@
+-- External wallets paired to receive signing requests.
+CREATE TABLE IF NOT EXISTS wallet_pairings
+(
+    pairing_id    INTEGER PRIMARY KEY NOT NULL,
+
+    name          TEXT NOT NULL,
+    -- Account (SS58 address) the wallet signs for.
+    account       TEXT NOT NULL,
+    -- Hash of the one-time pairing secret.
+    secret_hash   BLOB NOT NULL,
+    -- Hash of the wallet's session token, set when the wallet connects.
+    session_hash  BLOB UNIQUE,
+    -- Signing requests are posted to this url.
+    callback_url  TEXT,
+    -- pending, active, revoked
+    status        TEXT DEFAULT 'pending' NOT NULL,
+
+    connected_at  TIMESTAMP,
+    created_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+-- Unsigned transactions waiting for a paired wallet to approve or reject them.
+CREATE TABLE IF NOT EXISTS wallet_signing_requests
+(
+    request_id    INTEGER PRIMARY KEY NOT NULL,
+
+    pairing_id    INTEGER NOT NULL,
+    build_id      INTEGER NOT NULL,
+    -- Human-readable summary shown to the wallet user.
+    summary       TEXT NOT NULL,
+    -- pending, approved, rejected, failed, expired
+    status        TEXT DEFAULT 'pending' NOT NULL,
+    -- Rejection reason or submission error.
+    error         TEXT,
+
+    expires_at    TIMESTAMP NOT NULL,
+    decided_at    TIMESTAMP,
+    created_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at    TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(pairing_id) REFERENCES wallet_pairings(pairing_id),
+    FOREIGN KEY(build_id) REFERENCES unsigned_transactions(build_id)
+);
+
+CREATE INDEX IF NOT EXISTS wallet_signing_requests_pairing_idx ON wallet_signing_requests(pairing_id, status);
@@ -0,0 +1,3 @@
+-- Nonce of a transaction that may have reached the node.  Retries are signed with the same
+-- nonce, so only one of them can be executed.
+ALTER TABLE tx_outbox ADD COLUMN nonce INTEGER;
@@ -0,0 +1,17 @@
+-- API keys that authenticate users.  Only the hash of a key is stored.
+CREATE TABLE IF NOT EXISTS user_api_keys
+(
+    key_id         INTEGER PRIMARY KEY NOT NULL,
+
+    user_id        INTEGER NOT NULL,
+    name           TEXT NOT NULL,
+    -- Blake2-256 hash of the key.
+    key_hash       BLOB UNIQUE NOT NULL,
+
+    revoked_at     TIMESTAMP,
+    created_at     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+
+    FOREIGN KEY(user_id) REFERENCES users(user_id)
+);
+
+CREATE INDEX IF NOT EXISTS user_api_keys_user_idx ON user_api_keys(user_id);
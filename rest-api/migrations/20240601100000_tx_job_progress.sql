@@ -0,0 +1,3 @@
+-- Progress of a multi-step job (JSON), i.e. the key compromise workflow.  The job's
+-- `tx_hash` is the transaction of its current step.
+ALTER TABLE tx_jobs ADD COLUMN progress TEXT;
@@ -0,0 +1,7 @@
+-- Chain event of an outbox entry (`<tx hash>:<event index>`).  Blocks can be processed
+-- again after a restart, the unique index stops their events from being queued twice.
+ALTER TABLE webhook_outbox ADD COLUMN event_key TEXT;
+
+-- `NULL` endpoints (`WEBHOOK_URL`) aren't equal in a unique index.
+CREATE UNIQUE INDEX IF NOT EXISTS webhook_outbox_event_key_idx
+  ON webhook_outbox(event_key, COALESCE(endpoint_id, 0));
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use polymesh_api::client::{rpc_params, BlockHash};
+use polymesh_api::Api;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  scale_convert, AssetDetailsResult, ConfidentialAssetDetails,
+};
+
+pub type AppAssetDetailsCache = Data<AssetDetailsCache>;
+
+/// Default seconds asset details are cached.
+const DEFAULT_CACHE_SECS: u64 = 10;
+/// Number of assets queried concurrently.
+const FETCH_CONCURRENCY: usize = 8;
+
+/// Cache of the on-chain asset details (total supply, owner, mediators and auditors).
+///
+/// Details are cached for `ASSET_DETAILS_CACHE_SECS` (default: 10, `0` disables the cache),
+/// so the total supply can lag behind mints and burns for that long.  Unknown assets aren't
+/// cached.
+pub struct AssetDetailsCache {
+  ttl: Duration,
+  entries: Mutex<HashMap<Uuid, (Instant, ConfidentialAssetDetails)>>,
+}
+
+impl AssetDetailsCache {
+  /// Load the config from `ASSET_DETAILS_CACHE_SECS`.
+  pub fn from_env() -> AppAssetDetailsCache {
+    let secs = std::env::var("ASSET_DETAILS_CACHE_SECS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_CACHE_SECS);
+    Data::new(Self {
+      ttl: Duration::from_secs(secs),
+      entries: Default::default(),
+    })
+  }
+
+  fn cached(&self, asset_id: &Uuid) -> Option<ConfidentialAssetDetails> {
+    let mut entries = self.entries.lock().expect("Asset cache lock poisoned");
+    match entries.get(asset_id) {
+      Some((cached_at, details)) if cached_at.elapsed() < self.ttl => Some(details.clone()),
+      Some(_) => {
+        entries.remove(asset_id);
+        None
+      }
+      None => None,
+    }
+  }
+
+  /// Get the details of an asset.  Returns `None` if the asset doesn't exist.
+  pub async fn get(&self, api: &Api, asset_id: Uuid) -> Result<Option<ConfidentialAssetDetails>> {
+    if let Some(details) = self.cached(&asset_id) {
+      return Ok(Some(details));
+    }
+    let details = fetch_details(api, None, asset_id).await?;
+    if let Some(details) = &details {
+      self.insert(asset_id, details.clone());
+    }
+    Ok(details)
+  }
+
+  /// Get the details of many assets, in the order of `asset_ids`.
+  ///
+  /// Assets that aren't cached are read from the same block, a few at a time.
+  pub async fn get_many(&self, api: &Api, asset_ids: &[Uuid]) -> Result<Vec<AssetDetailsResult>> {
+    let mut found = HashMap::new();
+    let mut missing = Vec::new();
+    for asset_id in asset_ids {
+      match self.cached(asset_id) {
+        Some(details) => {
+          found.insert(*asset_id, details);
+        }
+        None if !missing.contains(asset_id) => missing.push(*asset_id),
+        None => (),
+      }
+    }
+    if !missing.is_empty() {
+      let block: BlockHash = api
+        .client()
+        .request("chain_getBlockHash", rpc_params!())
+        .await
+        .map_err(|err| Error::from(err))?;
+      let fetched = futures_util::stream::iter(missing)
+        .map(|asset_id| async move {
+          let details = fetch_details(api, Some(block), asset_id).await?;
+          Ok::<_, Error>((asset_id, details))
+        })
+        .buffer_unordered(FETCH_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+      for res in fetched {
+        if let (asset_id, Some(details)) = res? {
+          self.insert(asset_id, details.clone());
+          found.insert(asset_id, details);
+        }
+      }
+    }
+    Ok(
+      asset_ids
+        .iter()
+        .map(|asset_id| AssetDetailsResult {
+          asset_id: *asset_id,
+          details: found.get(asset_id).cloned(),
+        })
+        .collect(),
+    )
+  }
+
+  fn insert(&self, asset_id: Uuid, details: ConfidentialAssetDetails) {
+    if self.ttl.is_zero() {
+      return;
+    }
+    self
+      .entries
+      .lock()
+      .expect("Asset cache lock poisoned")
+      .insert(asset_id, (Instant::now(), details));
+  }
+}
+
+/// Read the asset's details and auditors from the chain (at `block` or the best block).
+async fn fetch_details(
+  api: &Api,
+  block: Option<BlockHash>,
+  asset_id: Uuid,
+) -> Result<Option<ConfidentialAssetDetails>> {
+  let query = match block {
+    Some(block) => api.query_at(block),
+    None => api.query(),
+  };
+  // Get confidential asset details.
+  let details = match query
+    .confidential_asset()
+    .details(*asset_id.as_bytes())
+    .await
+    .map_err(|err| Error::from(err))?
+  {
+    Some(details) => details,
+    None => return Ok(None),
+  };
+
+  // Get and convert asset auditors.
+  let asset_auditors = match query
+    .confidential_asset()
+    .asset_auditors(*asset_id.as_bytes())
+    .await
+    .map_err(|err| Error::from(err))?
+  {
+    Some(asset_auditors) => asset_auditors,
+    None => return Ok(None),
+  };
+  let mediators = asset_auditors.mediators.iter().map(|d| d.clone()).collect();
+  let auditors = asset_auditors
+    .auditors
+    .iter()
+    .map(|k| scale_convert(k))
+    .collect();
+
+  Ok(Some(ConfidentialAssetDetails {
+    total_supply: details.total_supply as u64,
+    owner: details.owner_did,
+    mediators,
+    auditors,
+  }))
+}
@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  scale_convert, AutoApplyAccount, BalanceUpdateAction, ProcessedEvent, TransactionResult,
+};
+
+use crate::budgets::AppSignerBudgets;
+use crate::nodes::AppNodes;
+use crate::outbox::{AppTxOutbox, TxSubmission};
+use crate::repo::TransactionRepository;
+use crate::signing::AppSigningManager;
+use crate::watcher::ProcessedEventHandler;
+
+/// Applies the incoming balances of the auto-apply accounts.
+///
+/// When the chain watcher sees an `AccountDepositIncoming` event for an account registered
+/// with `/auto_apply_accounts`, the account's signer submits `apply_incoming_balance` through
+/// the transaction outbox and the local balance is updated once the transaction is included.
+/// The results are waited for in the background, so the watcher's events aren't held up.
+/// Only our accounts (with their secret key stored here) can be auto-applied, locked
+/// accounts fail until unlocked.
+pub struct IncomingBalanceApplier {
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  nodes: AppNodes,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  outbox: AppTxOutbox,
+}
+
+impl IncomingBalanceApplier {
+  pub fn new(
+    repo: Repository,
+    tx_repo: TransactionRepository,
+    nodes: AppNodes,
+    signing: AppSigningManager,
+    budgets: AppSignerBudgets,
+    outbox: AppTxOutbox,
+  ) -> Self {
+    Self {
+      repo,
+      tx_repo,
+      nodes,
+      signing,
+      budgets,
+      outbox,
+    }
+  }
+
+  /// Apply the account's incoming balance of the asset, if it still has one.
+  async fn apply(&self, auto_apply: &AutoApplyAccount, asset_id: Uuid) -> Result<()> {
+    let public_key = &auto_apply.confidential_account;
+    let account_with_secret = match self.repo.get_account_with_secret(public_key).await? {
+      Some(account) => account,
+      None => {
+        log::warn!("Auto-apply account {public_key} isn't one of our accounts");
+        return Ok(());
+      }
+    };
+    account_with_secret.ensure_unlocked()?;
+    let account_asset = self
+      .repo
+      .get_account_asset_with_secret(public_key, asset_id)
+      .await?;
+
+    let api = self.nodes.api();
+    let account = account_with_secret.as_confidential_account()?;
+    // Already applied (i.e. by an earlier deposit of the same block).
+    let incoming_balance = match api
+      .query()
+      .confidential_asset()
+      .incoming_balance(account, *asset_id.as_bytes())
+      .await
+      .map_err(|err| Error::from(err))?
+    {
+      Some(incoming_balance) => incoming_balance,
+      None => return Ok(()),
+    };
+    // Convert from on-chain `CipherText`.
+    let enc_incoming = scale_convert(&incoming_balance);
+    let update = match account_asset {
+      Some(account_asset) => account_asset.apply_incoming(enc_incoming),
+      None => account_with_secret.apply_incoming(asset_id, enc_incoming),
+    }?;
+
+    let mut signer = self
+      .signing
+      .get_tx_signer(&auto_apply.signer, "apply_incoming_balance")
+      .await?
+      .ok_or_else(|| Error::not_found("Signer"))?;
+    let budget = self.budgets.check(&signer).await?;
+    let call = api
+      .call()
+      .confidential_asset()
+      .apply_incoming_balance(account, *asset_id.as_bytes())
+      .map_err(|err| Error::from(err))?;
+    let res = match self
+      .outbox
+      .submit(
+        "auto_apply",
+        &auto_apply.signer,
+        "apply_incoming_balance",
+        &mut signer,
+        call,
+        false,
+      )
+      .await?
+    {
+      TxSubmission::Submitted(res) => res,
+      TxSubmission::Queued(entry) => {
+        log::info!(
+          "Incoming balance of {public_key} asset {asset_id} queued in the outbox: {}",
+          entry.outbox_id
+        );
+        return Ok(());
+      }
+    };
+
+    // Wait for the results in the background.
+    let repo = self.repo.clone();
+    let budgets = self.budgets.clone();
+    let public_key = public_key.clone();
+    actix_web::rt::spawn(async move {
+      let res: Result<()> = async {
+        let res = TransactionResult::wait_for_results(res, false).await?;
+        budgets.record(budget, &res).await;
+        if !res.success {
+          return Err(Error::Other(format!(
+            "Failed to apply the incoming balance of {public_key}: {}",
+            res.err_msg.as_deref().unwrap_or("Transaction failed")
+          )));
+        }
+        // Update account balance.
+        let update = update.with_source(res.tx_hash.clone());
+        repo.update_account_asset(&update).await?;
+        log::info!(
+          "Applied incoming balance of {public_key} asset {asset_id}: {}",
+          res.tx_hash
+        );
+        Ok(())
+      }
+      .await;
+      if let Err(err) = res {
+        log::error!("Auto-apply failed: {err:?}");
+      }
+    });
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl ProcessedEventHandler for IncomingBalanceApplier {
+  fn name(&self) -> &'static str {
+    "auto-apply"
+  }
+
+  async fn handle_event(&self, _tx: &TransactionResult, event: &ProcessedEvent) -> Result<()> {
+    let balance_updated = match event {
+      ProcessedEvent::ConfidentialAccountBalanceUpdated(balance_updated) => balance_updated,
+      _ => return Ok(()),
+    };
+    if !matches!(balance_updated.action, BalanceUpdateAction::DepositIncoming) {
+      return Ok(());
+    }
+    let public_key = format!("0x{}", hex::encode(balance_updated.account.0));
+    match self.tx_repo.get_auto_apply_account(&public_key).await? {
+      Some(auto_apply) => self.apply(&auto_apply, balance_updated.asset_id).await,
+      None => Ok(()),
+    }
+  }
+}
@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  ProcessedEvent, SettlementLegFilter, TransactionAffirmed, TransactionResult,
+};
+
+use crate::budgets::AppSignerBudgets;
+use crate::nodes::AppNodes;
+use crate::outbox::{AppTxOutbox, TxSubmission};
+use crate::repo::TransactionRepository;
+use crate::signing::AppSigningManager;
+use crate::watcher::ProcessedEventHandler;
+
+/// Executes the settlements of the auto-execute venues.
+///
+/// When the chain watcher sees the last `TransactionAffirmed` event of a settlement
+/// (`pending_affirms` is zero) created in a venue with `auto_execute` enabled
+/// (`/tx/venues/{venue_id}/auto_execute`), the venue signer submits `execute_transaction`
+/// with the settlement's leg count through the transaction outbox.  The results are waited
+/// for in the background, so the watcher's events aren't held up.  The legs are taken from
+/// the stored settlement, so settlements created before the watcher's start block are
+/// skipped.
+pub struct SettlementExecutor {
+  tx_repo: TransactionRepository,
+  nodes: AppNodes,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  outbox: AppTxOutbox,
+}
+
+impl SettlementExecutor {
+  pub fn new(
+    tx_repo: TransactionRepository,
+    nodes: AppNodes,
+    signing: AppSigningManager,
+    budgets: AppSignerBudgets,
+    outbox: AppTxOutbox,
+  ) -> Self {
+    Self {
+      tx_repo,
+      nodes,
+      signing,
+      budgets,
+      outbox,
+    }
+  }
+
+  /// Execute the fully affirmed settlement, if its venue has auto-execution enabled.
+  async fn execute(&self, affirmed: &TransactionAffirmed) -> Result<()> {
+    let settlement_id = affirmed.transaction_id.0 as i64;
+    let settlement = match self.tx_repo.get_settlement(settlement_id).await? {
+      Some(settlement) => settlement,
+      None => return Ok(()),
+    };
+    let venue = match self.tx_repo.get_venue(settlement.venue_id as i64).await? {
+      Some(venue) if venue.auto_execute => venue,
+      _ => return Ok(()),
+    };
+    let filter = SettlementLegFilter {
+      settlement_id: Some(settlement.settlement_id),
+      ..Default::default()
+    };
+    let leg_count = self.tx_repo.get_settlement_legs(&filter).await?.len() as u32;
+    if leg_count == 0 {
+      log::warn!("Can't auto-execute settlement {settlement_id}, its legs aren't stored");
+      return Ok(());
+    }
+
+    let mut signer = self
+      .signing
+      .get_tx_signer(&venue.signer, "execute_transaction")
+      .await?
+      .ok_or_else(|| Error::not_found("Signer"))?;
+    let budget = self.budgets.check(&signer).await?;
+    let call = self
+      .nodes
+      .api()
+      .call()
+      .confidential_asset()
+      .execute_transaction(affirmed.transaction_id, leg_count)
+      .map_err(|err| Error::from(err))?;
+    let res = match self
+      .outbox
+      .submit(
+        "auto_execute",
+        &venue.signer,
+        "execute_transaction",
+        &mut signer,
+        call,
+        false,
+      )
+      .await?
+    {
+      TxSubmission::Submitted(res) => res,
+      TxSubmission::Queued(entry) => {
+        log::info!(
+          "Execution of settlement {settlement_id} queued in the outbox: {}",
+          entry.outbox_id
+        );
+        return Ok(());
+      }
+    };
+
+    // Wait for the results in the background.
+    let budgets = self.budgets.clone();
+    let venue_id = venue.venue_id;
+    actix_web::rt::spawn(async move {
+      let res: Result<()> = async {
+        let res = TransactionResult::wait_for_results(res, false).await?;
+        budgets.record(budget, &res).await;
+        if !res.success {
+          return Err(Error::Other(format!(
+            "Failed to execute settlement {settlement_id}: {}",
+            res.err_msg.as_deref().unwrap_or("Transaction failed")
+          )));
+        }
+        log::info!(
+          "Executed settlement {settlement_id} of venue {venue_id}: {}",
+          res.tx_hash
+        );
+        Ok(())
+      }
+      .await;
+      if let Err(err) = res {
+        log::error!("Auto-execute failed: {err:?}");
+      }
+    });
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl ProcessedEventHandler for SettlementExecutor {
+  fn name(&self) -> &'static str {
+    "auto-execute"
+  }
+
+  async fn handle_event(&self, _tx: &TransactionResult, event: &ProcessedEvent) -> Result<()> {
+    match event {
+      ProcessedEvent::ConfidentialTransactionAffirmed(affirmed)
+        if affirmed.pending_affirms == 0 =>
+      {
+        self.execute(affirmed).await
+      }
+      _ => Ok(()),
+    }
+  }
+}
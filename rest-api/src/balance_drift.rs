@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actix_web::{rt::pin, web::Data};
+use codec::Encode;
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use confidential_assets::CipherText;
+
+use polymesh_api::Api;
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  scale_convert, Account,
+};
+
+use crate::nodes::AppNodes;
+
+pub type AppBalanceDrift = Data<BalanceDrift>;
+
+/// Default seconds between checks.
+const DEFAULT_CHECK_INTERVAL: u64 = 600;
+/// Number of accounts checked concurrently.
+const CHECK_CONCURRENCY: usize = 8;
+
+/// Result of the last balance drift check.
+#[derive(Clone, Debug, Default)]
+pub struct BalanceDriftReport {
+  /// Number of accounts checked.
+  pub checked: usize,
+  /// Number of accounts with a local balance that doesn't match the chain.
+  pub drifted: usize,
+  pub completed_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Compares the local encrypted balances of the accounts with the chain in the background.
+///
+/// Unlike `/tx/accounts/refresh_balances` the local balances aren't updated, drifted
+/// accounts are only reported by the metrics.
+pub struct BalanceDrift {
+  interval: Option<Duration>,
+  report: Mutex<BalanceDriftReport>,
+}
+
+impl BalanceDrift {
+  /// Load the config from `BALANCE_DRIFT_CHECK_INTERVAL`.
+  pub fn from_env() -> AppBalanceDrift {
+    let interval = std::env::var("BALANCE_DRIFT_CHECK_INTERVAL")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_CHECK_INTERVAL);
+    Data::new(Self {
+      interval: (interval > 0).then(|| Duration::from_secs(interval)),
+      report: Default::default(),
+    })
+  }
+
+  /// Time between checks, `None` if the periodic checks are disabled.
+  pub fn interval(&self) -> Option<Duration> {
+    self.interval
+  }
+
+  /// Check the balances of all accounts now.
+  pub async fn check(&self, repo: &Repository, nodes: &AppNodes) -> Result<BalanceDriftReport> {
+    let api = nodes.api();
+    let accounts = repo.get_accounts().await?;
+    let drifted = futures_util::stream::iter(&accounts)
+      .map(|account| account_drifted(repo, &api, account))
+      .buffer_unordered(CHECK_CONCURRENCY)
+      .collect::<Vec<_>>()
+      .await
+      .into_iter()
+      .collect::<Result<Vec<_>>>()?;
+    let report = BalanceDriftReport {
+      checked: accounts.len(),
+      drifted: drifted.into_iter().filter(|drifted| *drifted).count(),
+      completed_at: Some(chrono::Utc::now().naive_utc()),
+    };
+    if report.drifted > 0 {
+      log::warn!(
+        "{} of {} accounts have a balance drift",
+        report.drifted,
+        report.checked
+      );
+    }
+    *self.report.lock().expect("Balance drift lock poisoned") = report.clone();
+    Ok(report)
+  }
+
+  /// Report of the last completed check.
+  pub fn last_report(&self) -> BalanceDriftReport {
+    self
+      .report
+      .lock()
+      .expect("Balance drift lock poisoned")
+      .clone()
+  }
+}
+
+/// Does any of the account's local balances not match the chain.
+async fn account_drifted(repo: &Repository, api: &Api, account: &Account) -> Result<bool> {
+  let public_key = hex::encode(&account.confidential_account);
+  let mut local_balances = repo
+    .get_account_assets(&public_key)
+    .await?
+    .into_iter()
+    .map(|account_asset| (account_asset.asset_id, account_asset.enc_balance))
+    .collect::<BTreeMap<_, _>>();
+
+  let balances = api
+    .paged_query()
+    .confidential_asset()
+    .account_balance(account.as_confidential_account()?)
+    .entries();
+  pin!(balances);
+  let mut drifted = false;
+  while let Some(balance) = balances.next().await {
+    match balance {
+      Ok((asset_id, Some(enc_balance))) => {
+        let enc_balance: CipherText = scale_convert(&enc_balance);
+        let local = local_balances.remove(&Uuid::from_bytes(asset_id));
+        if local != Some(enc_balance.encode()) {
+          drifted = true;
+        }
+      }
+      Ok((_, None)) => (),
+      Err(err) => {
+        Err(Error::from(err))?;
+      }
+    }
+  }
+  Ok(drifted)
+}
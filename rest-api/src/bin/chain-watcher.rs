@@ -1,35 +1,154 @@
-use sqlx::sqlite::SqlitePool;
+use std::sync::Arc;
 
-use polymesh_api::Api;
+use actix_web::web::Data;
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::SqlitePool;
 
 use polymesh_private_proof_api::repo::SqliteConfidentialRepository;
+use polymesh_private_proof_shared::{schema, DecryptionCache, DecryptionContext, EnvConfig};
 
+use polymesh_private_rest_api::auto_apply::IncomingBalanceApplier;
+use polymesh_private_rest_api::auto_execute::SettlementExecutor;
+use polymesh_private_rest_api::blobs::BlobStorage;
+use polymesh_private_rest_api::budgets::SignerBudgets;
+use polymesh_private_rest_api::event_sink::{EventPublisher, SinkFormat};
+use polymesh_private_rest_api::leases::Leases;
+use polymesh_private_rest_api::nodes::NodeManager;
+use polymesh_private_rest_api::outbox::TxOutbox;
 use polymesh_private_rest_api::repo::SqliteTransactionRepository;
+use polymesh_private_rest_api::signing::{
+  self, NonceManager, NonceSigningManager, SessionSigningManager, SigningManagerTrait,
+};
 use polymesh_private_rest_api::watcher::*;
+use polymesh_private_rest_api::webhooks::WebhookSender;
+
+static MIGRATOR: Migrator = sqlx::migrate!();
 
 async fn get_db_pool() -> anyhow::Result<SqlitePool> {
   let conn_str = std::env::var("DATABASE_URL")?;
   let pool = SqlitePool::connect(&conn_str).await?;
-  sqlx::migrate!().run(&pool).await?;
+  let applied = schema::prepare_schema(&pool, &MIGRATOR, schema::auto_migrate_from_env()).await?;
+  if !applied.is_empty() {
+    log::info!("Applied migrations: {applied:?}");
+  }
   Ok(pool)
 }
 
+/// Connect to the optional event sink.
+async fn connect_publisher() -> anyhow::Result<Option<EventPublisher>> {
+  match std::env::var("EVENT_SINK").ok() {
+    Some(kind) => {
+      let url = std::env::var("EVENT_SINK_URL")?;
+      let format = match std::env::var("EVENT_SINK_FORMAT").ok() {
+        Some(format) => format.parse()?,
+        None => SinkFormat::Json,
+      };
+      let prefix =
+        std::env::var("EVENT_SINK_TOPIC_PREFIX").unwrap_or("polymesh_private".to_string());
+      log::info!("Publishing events to {kind}");
+      Ok(Some(
+        EventPublisher::connect(&kind, &url, format, &prefix).await?,
+      ))
+    }
+    None => Ok(None),
+  }
+}
+
 async fn start_watcher() -> anyhow::Result<()> {
   // Open database.
   let pool = get_db_pool().await?;
   // Repositories.
-  let repo = SqliteConfidentialRepository::new_app_data(&pool);
-  let tx_repo = SqliteTransactionRepository::new_app_data(&pool);
+  let repo = SqliteConfidentialRepository::from_env(&pool).await?;
+  let blobs = BlobStorage::from_env().await?;
+  let tx_repo = Data::from(SqliteTransactionRepository::with_blobs(&pool, blobs));
   log::info!("Repositories initialized");
 
-  let polymesh_url =
-    std::env::var("POLYMESH_NODE_URL").unwrap_or("ws://localhost:9944/".to_string());
-  let api = Api::new(&polymesh_url).await?;
+  // Webhooks are only queued here, the REST API delivers them from the outbox.
+  let webhooks =
+    WebhookSender::new_app_data(std::env::var("WEBHOOK_URL").ok(), 0, tx_repo.clone())?;
+
+  // Decryption cache.
+  if let Some(size) = std::env::var("DECRYPTION_CACHE_SIZE")
+    .ok()
+    .and_then(|v| v.parse().ok())
+  {
+    DecryptionCache::set_size(size);
+  }
+  // Decryption baby-step table, built in the background.
+  DecryptionContext::from_env();
+
+  let options = WatcherOptions::from_env();
+
+  // Polymesh nodes.
+  let nodes = NodeManager::from_env().await?;
+  {
+    let nodes = nodes.clone();
+    actix_web::rt::spawn(async move {
+      nodes.run().await;
+    });
+  }
+
+  // Signers of the auto-applied incoming balances and auto-executed settlements (including
+  // session signers).  Nonces are tracked, as the REST API can submit with the same signers.
+  let signing = signing::signing_manager_from_env(&pool, &EnvConfig::from_env())?;
+  let signing: Arc<dyn SigningManagerTrait> = SessionSigningManager::new(signing, tx_repo.clone());
+  let signing: Arc<dyn SigningManagerTrait> =
+    NonceSigningManager::new(Data::from(signing), NonceManager::from_env(nodes.clone()));
+  let signing = Data::from(signing);
+  let budgets = SignerBudgets::new_app_data(tx_repo.clone(), webhooks.clone());
+  // Transactions the node can't accept are queued in the outbox, the REST API retries them.
+  let tx_outbox = TxOutbox::from_env(
+    nodes.clone(),
+    tx_repo.clone(),
+    signing.clone(),
+    budgets.clone(),
+  );
+
+  // Only one instance runs the chain watcher with distributed locks.
+  let leases = Leases::from_env(tx_repo.clone());
 
   // starting the server
   log::info!("🚀🚀🚀 Starting chain watcher");
 
-  start_chain_watcher(api, repo, tx_repo).await
+  leases
+    .run_exclusive("chain_watcher", || {
+      let nodes = nodes.clone();
+      let repo = repo.clone();
+      let tx_repo = tx_repo.clone();
+      let webhooks = webhooks.clone();
+      let signing = signing.clone();
+      let budgets = budgets.clone();
+      let tx_outbox = tx_outbox.clone();
+      let options = options.clone();
+      async move {
+        // The event sink is connected by the instance running the watcher.
+        let res = async {
+          let publisher = connect_publisher().await?;
+          let auto_apply = IncomingBalanceApplier::new(
+            repo.clone(),
+            tx_repo.clone(),
+            nodes.clone(),
+            signing.clone(),
+            budgets.clone(),
+            tx_outbox.clone(),
+          );
+          let auto_execute =
+            SettlementExecutor::new(tx_repo.clone(), nodes.clone(), signing, budgets, tx_outbox);
+          ChainWatcherBuilder::new(nodes, repo, tx_repo, webhooks)
+            .publisher(publisher)
+            .event_handler(auto_apply)
+            .event_handler(auto_execute)
+            .options(options)
+            .run()
+            .await
+        };
+        if let Err(err) = res.await {
+          log::error!("Chain watcher failed: {err:?}");
+        }
+      }
+    })
+    .await;
+  Err(anyhow::anyhow!("Chain watcher stopped"))
 }
 
 #[actix_web::main]
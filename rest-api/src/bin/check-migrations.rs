@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Migrations up to this version were written before the checks and aren't checked.
+const BASELINE_VERSION: i64 = 20240427100000;
+
+/// Comment to allow a non-additive migration, followed by the reason.
+const ALLOW_MARKER: &str = "-- non-additive:";
+
+/// Check that database migrations are additive.
+///
+/// The REST API and chain watcher can be upgraded one at a time, so the previous release
+/// must keep working against the migrated schema:
+///   - Only create tables and indexes (`IF NOT EXISTS`) and add columns.
+///   - New `NOT NULL` columns need a `DEFAULT`.
+///   - Don't drop or rename tables and columns, don't rewrite or delete existing rows.
+///
+/// Migrations that can't follow the rules must explain why with a
+/// `-- non-additive: <reason>` comment and be released with downtime.
+///
+/// Usage: `check-migrations [<migrations dir>...]` (default: `migrations`).
+fn main() -> ExitCode {
+  let mut dirs = std::env::args()
+    .skip(1)
+    .map(PathBuf::from)
+    .collect::<Vec<_>>();
+  if dirs.is_empty() {
+    dirs.push(PathBuf::from("migrations"));
+  }
+
+  let mut errors = 0;
+  for dir in &dirs {
+    match check_dir(dir) {
+      Ok(problems) => {
+        for problem in &problems {
+          eprintln!("{problem}");
+        }
+        errors += problems.len();
+      }
+      Err(err) => {
+        eprintln!("{}: {err}", dir.display());
+        errors += 1;
+      }
+    }
+  }
+  if errors > 0 {
+    eprintln!("Found {errors} migration problem(s)");
+    return ExitCode::FAILURE;
+  }
+  println!("Migrations OK");
+  ExitCode::SUCCESS
+}
+
+fn check_dir(dir: &Path) -> std::io::Result<Vec<String>> {
+  let mut problems = Vec::new();
+  let mut versions = BTreeMap::new();
+  let mut files = std::fs::read_dir(dir)?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<std::io::Result<Vec<_>>>()?;
+  files.sort();
+  for path in files {
+    if path.extension().and_then(|ext| ext.to_str()) != Some("sql") {
+      continue;
+    }
+    let name = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .unwrap_or_default()
+      .to_string();
+    let version = match parse_version(&name) {
+      Some(version) => version,
+      None => {
+        problems.push(format!(
+          "{}: file name must be `<YYYYMMDDHHMMSS>_<description>.sql`",
+          path.display()
+        ));
+        continue;
+      }
+    };
+    if let Some(other) = versions.insert(version, name.clone()) {
+      problems.push(format!(
+        "{}: version {version} is also used by {other}",
+        path.display()
+      ));
+    }
+    if version <= BASELINE_VERSION {
+      continue;
+    }
+    let sql = std::fs::read_to_string(&path)?;
+    if let Some(reason) = allowed_reason(&sql) {
+      println!(
+        "{}: non-additive migration allowed: {reason}",
+        path.display()
+      );
+      continue;
+    }
+    for (statement, problem) in check_sql(&sql) {
+      problems.push(format!("{}: {problem}: `{statement}`", path.display()));
+    }
+  }
+  Ok(problems)
+}
+
+/// The version of a migration file: `<version>_<description>.sql`.
+fn parse_version(name: &str) -> Option<i64> {
+  let (version, description) = name.strip_suffix(".sql")?.split_once('_')?;
+  if version.len() != 14 || description.is_empty() {
+    return None;
+  }
+  version.parse().ok()
+}
+
+/// The reason given for a non-additive migration.
+fn allowed_reason(sql: &str) -> Option<&str> {
+  sql.lines().find_map(|line| {
+    let reason = line.trim().strip_prefix(ALLOW_MARKER)?.trim();
+    (!reason.is_empty()).then_some(reason)
+  })
+}
+
+/// Returns the statements that break the rules and why.
+fn check_sql(sql: &str) -> Vec<(String, &'static str)> {
+  // Drop comments.
+  let sql = sql
+    .lines()
+    .map(|line| line.split("--").next().unwrap_or_default())
+    .collect::<Vec<_>>()
+    .join(" ");
+  sql
+    .split(';')
+    .map(|statement| statement.split_whitespace().collect::<Vec<_>>().join(" "))
+    .filter(|statement| !statement.is_empty())
+    .filter_map(|statement| {
+      let problem = check_statement(&statement.to_uppercase())?;
+      Some((statement, problem))
+    })
+    .collect()
+}
+
+fn check_statement(statement: &str) -> Option<&'static str> {
+  if statement.starts_with("DROP TABLE") || statement.starts_with("DROP VIEW") {
+    return Some("drops a table");
+  }
+  if statement.starts_with("ALTER TABLE") {
+    if statement.contains(" DROP ") {
+      return Some("drops a column");
+    }
+    if statement.contains(" RENAME ") {
+      return Some("renames a table or column");
+    }
+    if statement.contains(" NOT NULL") && !statement.contains(" DEFAULT ") {
+      return Some("adds a `NOT NULL` column without a `DEFAULT`");
+    }
+    return None;
+  }
+  if statement.starts_with("CREATE TABLE")
+    || statement.starts_with("CREATE INDEX")
+    || statement.starts_with("CREATE UNIQUE INDEX")
+  {
+    if !statement.contains(" IF NOT EXISTS ") {
+      return Some("creates a table or index without `IF NOT EXISTS`");
+    }
+    return None;
+  }
+  if statement.starts_with("UPDATE ") {
+    return Some("rewrites existing rows");
+  }
+  if statement.starts_with("DELETE ") {
+    return Some("deletes existing rows");
+  }
+  None
+}
@@ -0,0 +1,43 @@
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::SqlitePool;
+
+use polymesh_private_proof_shared::schema;
+use polymesh_private_rest_api::blobs::BlobStorage;
+use polymesh_private_rest_api::repo::SqliteTransactionRepository;
+
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Move the large payloads of existing rows to the configured blob store.
+async fn migrate_blobs() -> anyhow::Result<()> {
+  let blobs = BlobStorage::from_env()
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("BLOB_STORE is not set"))?;
+
+  let conn_str = std::env::var("DATABASE_URL")?;
+  let pool = SqlitePool::connect(&conn_str).await?;
+  let applied = schema::prepare_schema(&pool, &MIGRATOR, schema::auto_migrate_from_env()).await?;
+  if !applied.is_empty() {
+    log::info!("Applied migrations: {applied:?}");
+  }
+
+  log::info!("Moving large payloads to the blob store");
+  let (transactions, settlement_events) =
+    SqliteTransactionRepository::offload_existing_payloads(&pool, &blobs).await?;
+  log::info!(
+    "Moved {transactions} transaction events and {settlement_events} settlement events to the blob store"
+  );
+  Ok(())
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+  // env vars
+  dotenv::dotenv().ok();
+  env_logger::init();
+
+  if let Err(err) = migrate_blobs().await {
+    log::error!("Failed to migrate blobs: {err:?}");
+    return Err(std::io::Error::new(std::io::ErrorKind::Other, err));
+  }
+  Ok(())
+}
@@ -1,6 +1,8 @@
-use actix_cors::Cors;
+use std::sync::Arc;
+
 use actix_web::middleware::Logger;
 use actix_web::{web, App, HttpServer};
+use sqlx::migrate::Migrator;
 use sqlx::sqlite::SqlitePool;
 
 use utoipa::OpenApi;
@@ -8,12 +10,38 @@ use utoipa_rapidoc::RapiDoc;
 use utoipa_redoc::{Redoc, Servable};
 use utoipa_swagger_ui::SwaggerUi;
 
-use polymesh_api::{client::IdentityId, Api};
+use polymesh_api::client::IdentityId;
 
 use polymesh_private_proof_api as proof_api;
-use polymesh_private_proof_api::{repo::SqliteConfidentialRepository, v1::*};
+use polymesh_private_proof_api::{
+  decrypt_tokens::DecryptTokens, feature_flags::FeatureFlags, profile::DeploymentProfile,
+  replay::ReplayGuard, repo::SqliteConfidentialRepository, v1::*, warnings::ResponseEnvelopes,
+};
 use polymesh_private_proof_shared::*;
-use polymesh_private_rest_api::{repo::SqliteTransactionRepository, signing, v1::*};
+use polymesh_private_rest_api::{
+  asset_cache::AssetDetailsCache,
+  balance_drift::BalanceDrift,
+  blobs::BlobStorage,
+  budgets::SignerBudgets,
+  event_stream::EventStream,
+  key_compromise::KeyCompromiseJobs,
+  leases::Leases,
+  maintenance::Maintenance,
+  metrics,
+  nodes::NodeManager,
+  outbox::TxOutbox,
+  reload::{CorsOrigins, Reloader},
+  repo::SqliteTransactionRepository,
+  scheduler::Scheduler,
+  signing::{
+    self, NonceManager, NonceSigningManager, ReloadableSigningManager, SessionSigningManager,
+    SigningManagerTrait,
+  },
+  tx_jobs::TxJobs,
+  v1::*,
+  wallet_pairing::WalletPairings,
+  webhooks::WebhookSender,
+};
 
 pub fn v1_service(cfg: &mut web::ServiceConfig) {
   cfg.service(
@@ -21,32 +49,76 @@ pub fn v1_service(cfg: &mut web::ServiceConfig) {
       //.configure(users::service)
       .configure(assets::service)
       .configure(accounts::service)
+      .configure(anomalies::service)
+      .configure(api_keys::service)
+      .configure(user_access::service)
+      .configure(proofs::service)
+      .configure(escrow::service)
+      .configure(feature_flags::service)
+      .configure(fuzz::service)
+      .configure(integrity::service)
+      .configure(approvals::service)
+      .configure(decrypt_tokens::service)
+      .configure(decryption_proofs::service)
+      .configure(limits::service)
+      .configure(position_locks::service)
+      .configure(screening::service)
+      .configure(jobs::service)
+      .configure(receipts::service)
+      .configure(stats::service)
+      .configure(selftest::service)
+      .configure(audit_reports::service)
+      .configure(compromise::service)
+      .configure(config::service)
+      .configure(contacts::service)
+      .configure(deposits::service)
+      .configure(auto_apply::service)
+      .configure(account_signers::service)
+      .configure(events::service)
+      .configure(imports::service)
+      .configure(invoices::service)
+      .configure(leases::service)
+      .configure(ledger::service)
+      .configure(maintenance::service)
+      .configure(nodes::service)
+      .configure(payloads::service)
+      .configure(scheduler::service)
       .configure(signers::service)
-      .configure(tx::service),
+      .configure(tx::service)
+      .configure(wallet_pairings::service)
+      .configure(watcher::service)
+      .configure(webhooks::service)
+      .configure(ws::service),
   );
 }
 
+static MIGRATOR: Migrator = sqlx::migrate!();
+
 async fn get_db_pool() -> anyhow::Result<SqlitePool> {
   let conn_str = std::env::var("DATABASE_URL")?;
   let pool = SqlitePool::connect(&conn_str).await?;
-  sqlx::migrate!().run(&pool).await?;
+  let applied = schema::prepare_schema(&pool, &MIGRATOR, schema::auto_migrate_from_env()).await?;
+  if !applied.is_empty() {
+    log::info!("Applied migrations: {applied:?}");
+  }
   Ok(pool)
 }
 
-fn get_signing_manager(pool: &SqlitePool) -> anyhow::Result<signing::AppSigningManager> {
-  let manager = std::env::var("SIGNING_MANAGER").ok();
-  match manager.as_ref().map(|s| s.as_str()) {
-    Some("DB" | "LOCAL") | None => Ok(signing::SqliteSigningManager::new_app_data(pool)),
-    Some("VAULT") => {
-      let base = std::env::var("VAULT_TRANSIT_URL")?;
-      let token = std::env::var("VAULT_TOKEN")?;
-      Ok(signing::VaultSigningManager::new_app_data(base, token)?)
-    }
-    Some(manager) => Err(anyhow::anyhow!("Unknown Signing Manager: {manager:?}")),
+/// `migrate` subcommand: apply pending migrations and exit.
+async fn run_migrations() -> anyhow::Result<()> {
+  let conn_str = std::env::var("DATABASE_URL")?;
+  let pool = SqlitePool::connect(&conn_str).await?;
+  let applied = schema::migrate(&pool, &MIGRATOR).await?;
+  if applied.is_empty() {
+    log::info!("Database schema is up to date");
+  } else {
+    log::info!("Applied migrations: {applied:?}");
   }
+  Ok(())
 }
 
-async fn start_server() -> anyhow::Result<()> {
+async fn start_server(env: EnvConfig) -> anyhow::Result<()> {
+  let config = Reloader::load_config(&env)?;
   // building address
   let port = std::env::var("PORT").unwrap_or("8080".to_string());
   let bind_address = std::env::var("BIND_ADDRESS").unwrap_or("0.0.0.0".to_string());
@@ -55,29 +127,318 @@ async fn start_server() -> anyhow::Result<()> {
   // Open database.
   let pool = get_db_pool().await?;
   // Repositories.
-  let repo = SqliteConfidentialRepository::new_app_data(&pool);
-  let tx_repo = SqliteTransactionRepository::new_app_data(&pool);
+  let repo = SqliteConfidentialRepository::from_env(&pool).await?;
+  let blobs = BlobStorage::from_env().await?;
+  let tx_repo = web::Data::from(SqliteTransactionRepository::with_blobs(&pool, blobs));
+  // Leases of the background tasks.
+  let leases = Leases::from_env(tx_repo.clone());
+  // Periodic background jobs.
+  let scheduler = Scheduler::from_env(tx_repo.clone());
+  // Receipt signer.
+  let receipts = proof_api::receipts::ReceiptSigner::from_env()?;
+  // Replay protection.
+  let replay_guard = ReplayGuard::from_env()?;
+  // Background decryption jobs.
+  let decrypt_jobs = proof_api::decrypt_jobs::DecryptJobs::from_env();
+  // Dual control.
+  let approvals = proof_api::approvals::Approvals::from_env()?;
+  // Who may create API keys.
+  let api_key_admins = proof_api::api_keys::ApiKeyAdmins::from_env();
+  // Encrypted balance overrides.
+  let balance_overrides = proof_api::balance_overrides::BalanceOverrides::from_env()?;
+  // Deployment profile.
+  let profile = proof_api::profile::DeploymentProfile::from_env()?;
+  // Endpoints disabled at runtime.
+  let feature_flags = FeatureFlags::from_env(repo.clone()).await?;
+  {
+    let feature_flags = feature_flags.clone();
+    actix_web::rt::spawn(async move {
+      feature_flags.run().await;
+    });
+  }
+  // Decryption tokens.
+  let decrypt_tokens = proof_api::decrypt_tokens::DecryptTokens::from_env()?;
+  // Receiver screening.
+  let screening = proof_api::screening::Screening::from_env(repo.clone())?;
+  // Anomaly detection.
+  let anomalies = proof_api::anomalies::Anomalies::from_env(repo.clone())?;
+  // Balance valuation.
+  let valuation = proof_api::valuation::Valuation::from_env()?;
+  // Sender proof pools.
+  let proof_pools = proof_api::proof_pools::ProofPools::from_env(repo.clone());
+  {
+    let proof_pools = proof_pools.clone();
+    scheduler.register(
+      "proof_pools",
+      std::time::Duration::from_secs(proof_api::proof_pools::EXPIRY_CHECK_INTERVAL),
+      move || {
+        let proof_pools = proof_pools.clone();
+        async move { proof_pools.release_expired().await }
+      },
+    );
+  }
+  // Account secret integrity checks.
+  let secret_integrity = proof_api::integrity::SecretIntegrity::from_env();
+  if let Some(interval) = secret_integrity.interval() {
+    let secret_integrity = secret_integrity.clone();
+    let repo = repo.clone();
+    scheduler.register("secret_integrity", interval, move || {
+      let secret_integrity = secret_integrity.clone();
+      let repo = repo.clone();
+      async move { secret_integrity.check(&repo).await.map(|_| ()) }
+    });
+  }
   log::info!("Repositories initialized");
 
+  // Polymesh nodes.
+  let nodes = NodeManager::from_env().await?;
+  {
+    let nodes = nodes.clone();
+    actix_web::rt::spawn(async move {
+      nodes.run().await;
+    });
+  }
+  // Balance drift checks.
+  let balance_drift = BalanceDrift::from_env();
+  if let Some(interval) = balance_drift.interval() {
+    let balance_drift = balance_drift.clone();
+    let repo = repo.clone();
+    let nodes = nodes.clone();
+    scheduler.register("balance_drift", interval, move || {
+      let balance_drift = balance_drift.clone();
+      let repo = repo.clone();
+      let nodes = nodes.clone();
+      async move { balance_drift.check(&repo, &nodes).await.map(|_| ()) }
+    });
+  }
+  // Metric alert thresholds.
+  let metrics_config = metrics::MetricsConfig::from_env();
+  // On-chain asset details cache.
+  let asset_cache = AssetDetailsCache::from_env();
+
   // Signing manager.
-  let signing = get_signing_manager(&pool)?;
+  let reloadable_signing =
+    ReloadableSigningManager::new(signing::signing_manager_from_env(&pool, &config)?);
+  let signing: Arc<dyn SigningManagerTrait> = reloadable_signing.clone();
+  // Session signers.
+  let signing: Arc<dyn SigningManagerTrait> =
+    SessionSigningManager::new(web::Data::from(signing), tx_repo.clone());
+  // Nonces for concurrent submissions.
+  let nonces = NonceManager::from_env(nodes.clone());
+  let signing: Arc<dyn SigningManagerTrait> =
+    NonceSigningManager::new(web::Data::from(signing), nonces);
+  let signing = web::Data::from(signing);
+  // CORS.
+  let cors_origins = CorsOrigins::from_env(&config);
+  // Config reloading.
+  let reloader = Reloader::new_app_data(
+    &pool,
+    env,
+    reloadable_signing,
+    cors_origins.clone(),
+    repo.clone(),
+  );
+  #[cfg(unix)]
+  {
+    use actix_web::rt::signal::unix::{signal, SignalKind};
+    let reloader = reloader.clone();
+    let mut hangup = signal(SignalKind::hangup())?;
+    actix_web::rt::spawn(async move {
+      while hangup.recv().await.is_some() {
+        log::info!("Received SIGHUP, reloading config");
+        if let Err(err) = reloader.reload() {
+          log::error!("Failed to reload config: {err:?}");
+        }
+      }
+    });
+  }
 
-  let polymesh_url =
-    std::env::var("POLYMESH_NODE_URL").unwrap_or("ws://localhost:9944/".to_string());
-  let polymesh_api = web::Data::new(Api::new(&polymesh_url).await?);
+  // Webhooks.
+  let webhook_max_attempts = std::env::var("WEBHOOK_MAX_ATTEMPTS")
+    .ok()
+    .and_then(|v| v.parse().ok())
+    .unwrap_or(10);
+  let webhooks = WebhookSender::new_app_data(
+    std::env::var("WEBHOOK_URL").ok(),
+    webhook_max_attempts,
+    tx_repo.clone(),
+  )?;
+  {
+    let webhooks = webhooks.clone();
+    let leases = leases.clone();
+    actix_web::rt::spawn(async move {
+      leases
+        .run_exclusive("webhook_outbox", || {
+          let webhooks = webhooks.clone();
+          async move { webhooks.run_outbox().await }
+        })
+        .await;
+    });
+  }
+  // Signer budgets.
+  let budgets = SignerBudgets::new_app_data(tx_repo.clone(), webhooks.clone());
+  // Maintenance mode.
+  let maintenance = Maintenance::new_app_data(tx_repo.clone()).await?;
+  {
+    let maintenance = maintenance.clone();
+    actix_web::rt::spawn(async move { maintenance.run().await });
+  }
+  // Background transaction jobs.
+  let tx_jobs = TxJobs::new_app_data(tx_repo.clone());
+  // Chain event stream, fed from the transactions persisted by the chain watcher.
+  let event_stream = EventStream::new_app_data(tx_repo.clone());
+  {
+    let event_stream = event_stream.clone();
+    actix_web::rt::spawn(async move { event_stream.run().await });
+  }
+  {
+    let tx_jobs = tx_jobs.clone();
+    scheduler.register(
+      "tx_jobs",
+      std::time::Duration::from_secs(polymesh_private_rest_api::tx_jobs::RECOVERY_INTERVAL),
+      move || {
+        let tx_jobs = tx_jobs.clone();
+        async move { tx_jobs.recover_jobs().await }
+      },
+    );
+  }
+  // Wallet pairings for external signers.
+  let wallets = WalletPairings::from_env();
+  {
+    let wallets = wallets.clone();
+    let tx_repo = tx_repo.clone();
+    scheduler.register(
+      "wallet_requests",
+      polymesh_private_rest_api::wallet_pairing::EXPIRY_CHECK_INTERVAL,
+      move || {
+        let wallets = wallets.clone();
+        let tx_repo = tx_repo.clone();
+        async move { wallets.expire_requests(&tx_repo).await }
+      },
+    );
+  }
+  // Transaction outbox.
+  let tx_outbox = TxOutbox::from_env(
+    nodes.clone(),
+    tx_repo.clone(),
+    signing.clone(),
+    budgets.clone(),
+  );
+  {
+    let tx_outbox = tx_outbox.clone();
+    let leases = leases.clone();
+    actix_web::rt::spawn(async move {
+      leases
+        .run_exclusive("tx_outbox", || {
+          let tx_outbox = tx_outbox.clone();
+          async move { tx_outbox.run().await }
+        })
+        .await;
+    });
+  }
+  // Key compromise jobs.
+  let key_compromise_jobs = KeyCompromiseJobs::new_app_data(
+    repo.clone(),
+    tx_repo.clone(),
+    nodes.clone(),
+    signing.clone(),
+    budgets.clone(),
+    tx_outbox.clone(),
+  );
+  {
+    let key_compromise_jobs = key_compromise_jobs.clone();
+    scheduler.register(
+      "key_compromise_jobs",
+      std::time::Duration::from_secs(polymesh_private_rest_api::key_compromise::RESUME_INTERVAL),
+      move || {
+        let key_compromise_jobs = key_compromise_jobs.clone();
+        async move { KeyCompromiseJobs::resume_jobs(&key_compromise_jobs).await }
+      },
+    );
+  }
+  // Run the periodic jobs registered above.
+  {
+    let scheduler = scheduler.clone();
+    let leases = leases.clone();
+    actix_web::rt::spawn(async move {
+      leases
+        .run_exclusive("scheduler", || {
+          let scheduler = scheduler.clone();
+          async move { scheduler.run().await }
+        })
+        .await;
+    });
+  }
+
+  // Maximum time to wait for finalization.
+  let finalization_timeout = std::env::var("FINALIZATION_TIMEOUT")
+    .ok()
+    .and_then(|v| v.parse().ok())
+    .filter(|secs| *secs > 0)
+    .map(std::time::Duration::from_secs);
+  TransactionResult::set_finalization_timeout(finalization_timeout);
+  // Decryption cache.
+  if let Some(size) = std::env::var("DECRYPTION_CACHE_SIZE")
+    .ok()
+    .and_then(|v| v.parse().ok())
+  {
+    DecryptionCache::set_size(size);
+  }
+  // Decryption baby-step table, built in the background.
+  let decryption_context = DecryptionContext::from_env();
+  // Thread pool for generating and verifying proofs.
+  ProvingThreads::init_from_env();
 
   /*
   {
     use actix_web::rt;
-    use polymesh_private_rest_api::watcher;
+    use polymesh_private_rest_api::{
+      auto_apply::IncomingBalanceApplier, auto_execute::SettlementExecutor, watcher,
+    };
     let repo = repo.clone();
     let tx_repo = tx_repo.clone();
-    let api = (**polymesh_api).clone();
+    let webhooks = webhooks.clone();
+    let nodes = nodes.clone();
+    let signing = signing.clone();
+    let budgets = budgets.clone();
+    let tx_outbox = tx_outbox.clone();
+    let leases = leases.clone();
     log::info!("Starting chain watcher");
     rt::spawn(async move {
-      if let Err(err) = watcher::start_chain_watcher(api, repo, tx_repo).await {
-        log::error!("Chain watcher failed: {err:?}");
-      }
+      leases
+        .run_exclusive("chain_watcher", || {
+          let options = watcher::WatcherOptions::from_env();
+          let auto_apply = IncomingBalanceApplier::new(
+            repo.clone(),
+            tx_repo.clone(),
+            nodes.clone(),
+            signing.clone(),
+            budgets.clone(),
+            tx_outbox.clone(),
+          );
+          let auto_execute = SettlementExecutor::new(
+            tx_repo.clone(),
+            nodes.clone(),
+            signing.clone(),
+            budgets.clone(),
+            tx_outbox.clone(),
+          );
+          let builder = watcher::ChainWatcherBuilder::new(
+            nodes.clone(),
+            repo.clone(),
+            tx_repo.clone(),
+            webhooks.clone(),
+          )
+          .event_handler(auto_apply)
+          .event_handler(auto_execute)
+          .options(options);
+          async move {
+            if let Err(err) = builder.run().await {
+              log::error!("Chain watcher failed: {err:?}");
+            }
+          }
+        })
+        .await;
     });
   }// */
 
@@ -95,52 +456,238 @@ async fn start_server() -> anyhow::Result<()> {
         signers::create_signer,
         signers::get_signer_identity,
         signers::get_signer_venues,
+        signers::get_signer_budget,
+        signers::set_signer_budget,
+        signers::get_signer_usage,
+        signers::get_session_signers,
+        signers::create_session_signer,
+        signers::delete_session_signer,
+        webhooks::get_webhook_outbox,
+        webhooks::redeliver_webhook,
+        webhooks::get_webhook_endpoints,
+        webhooks::get_webhook_endpoint,
+        webhooks::create_webhook_endpoint,
+        webhooks::delete_webhook_endpoint,
+        imports::import_accounts,
+        config::reload_config,
+        contacts::get_all_contacts,
+        contacts::get_contact,
+        contacts::create_contact,
+        contacts::update_contact,
+        contacts::delete_contact,
+        deposits::get_deposit_accounts,
+        deposits::create_deposit_account,
+        deposits::delete_deposit_account,
+        deposits::get_deposits,
+        auto_apply::get_auto_apply_accounts,
+        auto_apply::create_auto_apply_account,
+        auto_apply::delete_auto_apply_account,
+        account_signers::get_account_signers,
+        account_signers::get_account_signer,
+        account_signers::create_account_signer,
+        account_signers::delete_account_signer,
+        events::stream_events,
+        ws::account_balances_ws,
+        invoices::get_invoices,
+        invoices::get_invoice,
+        invoices::create_invoice,
+        invoices::cancel_invoice,
+        tx::invoices::tx_pay_invoice,
+        payloads::get_account_payload,
+        payloads::get_invoice_payload,
+        payloads::decode_payload,
+        wallet_pairings::get_wallet_pairings,
+        wallet_pairings::get_wallet_pairing,
+        wallet_pairings::create_wallet_pairing,
+        wallet_pairings::revoke_wallet_pairing,
+        wallet_pairings::get_wallet_requests,
+        wallet_pairings::get_wallet_request,
+        wallet_pairings::wallet_connect,
+        wallet_pairings::wallet_get_requests,
+        wallet_pairings::wallet_get_request,
+        wallet_pairings::wallet_approve_request,
+        wallet_pairings::wallet_reject_request,
+        ledger::get_trial_balance,
+        ledger::get_ledger_account_entries,
+        maintenance::get_maintenance_mode,
+        maintenance::set_maintenance_mode,
+        nodes::get_nodes,
+        watcher::get_watcher_status,
+        leases::get_leases,
+        scheduler::get_scheduled_jobs,
         assets::get_all_assets,
         assets::get_asset,
         assets::create_asset,
+        assets::get_asset_holders,
         assets::sender_proof_verify,
         accounts::get_all_accounts,
         accounts::get_account,
         accounts::create_account,
+        accounts::lock_account,
+        accounts::unlock_account,
+        accounts::get_asset_accounts,
+        accounts::create_asset_account,
+        escrow::get_account_escrow,
+        escrow::escrow_account,
+        escrow::reassemble_account,
+        escrow::seal_account,
+        integrity::get_secret_integrity,
+        integrity::check_secret_integrity,
+        approvals::get_approvals,
+        approvals::get_approval,
+        approvals::approve,
+        approvals::reject,
+        api_keys::get_user_api_keys,
+        api_keys::create_user_api_key,
+        api_keys::revoke_user_api_key,
+        user_access::get_user_account_access,
+        user_access::grant_account_access,
+        user_access::revoke_account_access,
+        decrypt_tokens::create_decrypt_token,
+        limits::get_amount_limits,
+        limits::set_amount_limit,
+        position_locks::get_position_locks,
+        position_locks::create_position_lock,
+        position_locks::delete_position_lock,
+        screening::get_all_screening_entries,
+        screening::get_screening_entry,
+        screening::create_screening_entry,
+        screening::update_screening_entry,
+        screening::delete_screening_entry,
+        anomalies::get_anomaly_alerts,
+        anomalies::verify_audit_log,
+        proofs::get_account_proofs,
+        proofs::get_proof,
+        jobs::get_job,
+        compromise::key_compromised,
+        audit_reports::asset_audit_report,
         accounts::auditor_verify_request,
         accounts::request_sender_proof,
         accounts::request_burn_proof,
         accounts::receiver_verify_request,
         accounts::decrypt_request,
+        accounts::decrypt_with_proof_request,
+        decryption_proofs::verify_decryption_proof,
+        receipts::get_receipt_public_key,
+        receipts::verify_receipt,
+        stats::get_proof_stats,
+        selftest::get_selftest,
+        fuzz::fuzz_routes,
         account_assets::get_all_account_assets,
         account_assets::get_account_asset,
+        account_assets::get_account_asset_balance_at,
+        account_assets::get_account_asset_history,
+        account_assets::export_account_asset,
         account_assets::create_account_asset,
         account_assets::request_sender_proof,
         account_assets::request_burn_proof,
         account_assets::receiver_verify_request,
         account_assets::update_balance_request,
         account_assets::decrypt_request,
+        proof_pools::get_proof_pools,
+        proof_pools::get_proof_pool,
+        proof_pools::create_proof_pool,
+        proof_pools::release_proof_pool,
+        balance_conflicts::get_balance_conflicts,
+        balance_conflicts::get_balance_conflict,
+        balance_conflicts::resolve_balance_conflict,
+        feature_flags::get_feature_flags,
+        feature_flags::set_feature_flag,
+        feature_flags::delete_feature_flag,
         tx::assets::tx_create_asset,
         tx::assets::tx_create_venue,
+        tx::assets::get_venues,
+        tx::assets::set_venue_auto_execute,
         tx::assets::get_asset_details,
+        tx::assets::get_assets_details,
+        tx::transactions::get_transaction,
+        tx::transactions::get_transaction_leg,
+        tx::transactions::verify_transaction_leg,
+        tx::transactions::auditor_verify_transaction_leg,
+        tx::identities::get_identity_portfolio,
+        tx::settlements::get_settlements,
+        tx::settlements::assign_settlement_legs,
+        tx::settlements::get_settlement,
+        tx::settlements::get_settlement_annotations,
+        tx::settlements::add_settlement_annotation,
+        tx::settlements::get_settlement_legs,
         tx::assets::tx_allow_venues,
         tx::assets::tx_create_settlement,
         tx::assets::tx_execute_settlement,
         tx::accounts::tx_mediator_affirm_leg,
         tx::accounts::tx_affirm_transactions,
+        tx::accounts::tx_reject_transaction,
+        tx::accounts::tx_withdraw_affirmation,
         tx::accounts::tx_init_account,
         tx::accounts::tx_account_did,
         tx::accounts::tx_apply_incoming_balances,
+        tx::accounts::tx_refresh_balances,
         tx::accounts::get_incoming_balances,
+        tx::accounts::get_pending_transactions,
+        tx::jobs::get_tx_jobs,
+        tx::jobs::get_tx_job,
+        tx::outbox::get_tx_outbox_entries,
+        tx::outbox::get_tx_outbox_entry,
+        tx::offline::tx_build,
+        tx::offline::get_unsigned_transaction,
+        tx::offline::tx_submit_signed,
+        tx::wallet_requests::tx_wallet_request,
         tx::account_assets::tx_sender_affirm_leg,
         tx::account_assets::tx_receiver_affirm_leg,
         tx::account_assets::tx_apply_incoming,
         tx::account_assets::get_incoming_balance,
+        tx::account_assets::get_balance_at_block,
+        tx::account_assets::reconcile_balance,
+        tx::account_assets::repair_balance,
         tx::account_assets::tx_mint,
+        tx::account_assets::tx_burn,
       ),
       components(
         schemas(
           User, CreateUser,
           SignerInfo, CreateSigner,
+          SignerBudget, SetSignerBudget, SignerUsage,
+          SessionSigner, CreateSessionSigner,
+          WebhookOutboxRecord, WebhookEndpoint, CreateWebhookEndpoint,
+          ImportAccountsRequest, ImportedAccount, AccountAssetImportedBalance,
+          KeyCompromiseRequest, KeyCompromiseStep, KeyCompromiseReport,
+          MaintenanceStatus, SetMaintenanceMode,
+          WatcherStatus, NodeStatus, Lease, ScheduledJob,
+          LedgerEntry, TrialBalance,
           Asset, AddAsset,
-          Account,
-          AccountAsset, CreateAccountAsset,
+          Account, AssetAccount,
+          IdentityPortfolio, PortfolioAccount,
+          SettlementDetails, SettlementLeg,
+          SettlementAnnotation, SettlementAnnotationStatus, CreateSettlementAnnotation,
+          PendingSettlement, PendingSettlementLeg,
+          SettlementLegVerifyReport, LegAssetVerifyResult, LegAuditorVerifyResult,
+          AuditorVerifyLegRequest, AuditorVerifyLegResult,
+          Contact, CreateContact, UpdateContact,
+          Invoice, CreateInvoice, PayInvoice,
+          DepositAccount, CreateDepositAccount, Deposit,
+          AutoApplyAccount, CreateAutoApplyAccount,
+          AccountSigner, CreateAccountSigner,
+          WalletPayload, EncodedWalletPayload,
+          AuditReportRequest, AuditReport, AuditedProof, SignedAuditReport,
+          AccountEscrow, EscrowCustodian, EscrowAccountRequest, ReassembleAccountRequest,
+          SecretIntegrityReport, CorruptAccount, SecretProblem,
+          Approval, ApprovalOperation,
+          UserApiKey, CreateUserApiKey, UserApiKeyCreated, UserAccountAccess,
+          CreateDecryptToken, DecryptToken, DecryptTokenClaims,
+          AmountLimit, SetAmountLimit,
+          PositionLock, CreatePositionLock, PositionLockMode,
+          ScreeningEntry, CreateScreeningEntry, UpdateScreeningEntry, ScreeningList,
+          AnomalyAlert, AuditChainReport, AuditChainError, AuditChainProblem,
+          ProofRecord, FormattedProofRecord,
+          AccountAsset, CreateAccountAsset, AssetHolder, BalanceHistory, BalanceAction,
+          FormattedBalanceHistory,
+          AccountAssetExportRequest, AccountAssetExport, SignedAccountAssetExport,
+          ChainReference,
           AccountAssetWithProof,
+          ValuedAccountAsset, AssetValuation, AssetPrice, PortfolioValuation,
+          ProofPool, CreateProofPool,
+          BalanceConflict, ResolveBalanceConflict,
+          FeatureFlag, SetFeatureFlag,
           PublicKey, BurnProof, SenderProof, TransferProofs,
           AuditorVerifyRequest,
           ReceiverVerifyRequest,
@@ -150,7 +697,18 @@ async fn start_server() -> anyhow::Result<()> {
           SenderProofVerifyResult,
           AccountDecryptRequest,
           DecryptedResponse,
+          VerifiableDecryption, DecryptionVerifyResult,
+          DecryptJob, DecryptJobStatus,
+          Receipt, ReceiptVerifyResult,
+          ProofStatsEntry, ProofOperation,
+          SelfTestReport, SelfTestStep,
+          FuzzRequest, FuzzReport, FuzzFailure,
+          ResponseWarning,
           DecryptedIncomingBalance,
+          DecryptedBalanceAtBlock,
+          BlockTransactionRecord,
+          StorageReadProof,
+          ChainTransactionLeg,
           UpdateAccountAssetBalanceRequest,
 
           IdentityId,
@@ -160,23 +718,43 @@ async fn start_server() -> anyhow::Result<()> {
           TransactionParty,
           ProcessedEvent,
           ProcessedEvents,
+          ChainEvent,
           TransactionArgs,
           TransactionResult,
+          DryRunResult,
+          TxJob, TxJobStatus,
+          TxOutboxEntry, TxOutboxStatus,
+          OfflineCall,
+          BuildTransaction,
+          UnsignedTransaction,
+          SubmitSignedTransaction,
+          WalletPairing, CreateWalletPairing, WalletPairingCreated, ConnectWallet, WalletSession,
+          WalletSigningRequest, CreateWalletSigningRequest,
+          ApproveWalletSigningRequest, RejectWalletSigningRequest,
           CreateConfidentialAsset,
-          ConfidentialAssetDetails,
+          ConfidentialAssetDetails, AssetDetailsResult,
           ConfidentialSettlementLeg,
           CreateConfidentialSettlement,
+          SettlementTransfer, AssignSettlementLegsRequest, AssignedSettlementLegs,
           ExecuteConfidentialSettlement,
           AllowVenues,
+          CreateVenue,
+          Venue,
+          SetVenueAutoExecute,
           MintRequest,
+          BurnRequest,
           TransactionAssetAmount,
           AffirmTransactionLegRequest,
+          RejectTransactionRequest,
+          WithdrawAffirmationRequest,
           AffirmTransactionLeg,
           AffirmTransactionRequest,
           AffirmTransactionsRequest,
           BalanceUpdated,
           BalanceUpdateAction,
           AccountAssetIncomingBalance,
+          RefreshBalancesRequest, RefreshBalancesResult, RefreshedAccount, AssetBalanceDrift,
+          ReconciledBalance,
           AccountAssetBalanceUpdated,
           AccountAssetBalancesUpdated,
         ),
@@ -188,10 +766,16 @@ async fn start_server() -> anyhow::Result<()> {
   struct ApiDoc;
 
   let openapi = ApiDoc::openapi();
+  // Staging-only route fuzzer.
+  let fuzzer = proof_api::fuzz::Fuzzer::from_env(&openapi)?;
 
   HttpServer::new(move || {
     // CORS
-    let cors = Cors::permissive();
+    let cors = cors_origins.cors();
+    let maintenance = maintenance.clone();
+    let decrypt_tokens = decrypt_tokens.clone();
+    let profile = profile.clone();
+    let feature_flags = feature_flags.clone();
 
     App::new()
       .wrap(cors)
@@ -201,9 +785,42 @@ async fn start_server() -> anyhow::Result<()> {
           .app_data(repo.clone())
           .app_data(tx_repo.clone())
           .app_data(signing.clone())
-          .app_data(polymesh_api.clone())
+          .app_data(webhooks.clone())
+          .app_data(wallets.clone())
+          .app_data(budgets.clone())
+          .app_data(maintenance.clone())
+          .app_data(tx_jobs.clone())
+          .app_data(tx_outbox.clone())
+          .app_data(key_compromise_jobs.clone())
+          .app_data(event_stream.clone())
+          .app_data(reloader.clone())
+          .app_data(nodes.clone())
+          .app_data(receipts.clone())
+          .app_data(replay_guard.clone())
+          .app_data(decrypt_jobs.clone())
+          .app_data(decryption_context.clone())
+          .app_data(secret_integrity.clone())
+          .app_data(balance_drift.clone())
+          .app_data(asset_cache.clone())
+          .app_data(metrics_config.clone())
+          .app_data(approvals.clone())
+          .app_data(api_key_admins.clone())
+          .app_data(balance_overrides.clone())
+          .app_data(decrypt_tokens.clone())
+          .app_data(screening.clone())
+          .app_data(anomalies.clone())
+          .app_data(valuation.clone())
+          .app_data(proof_pools.clone())
+          .app_data(feature_flags.clone())
+          .app_data(fuzzer.clone())
           .configure(proof_api::health::service)
-          .configure(v1_service),
+          .configure(metrics::service)
+          .configure(v1_service)
+          .wrap_fn(ResponseEnvelopes::middleware)
+          .wrap_fn(move |req, srv| Maintenance::middleware(maintenance.clone(), req, srv))
+          .wrap_fn(move |req, srv| DecryptTokens::middleware(decrypt_tokens.clone(), req, srv))
+          .wrap_fn(move |req, srv| FeatureFlags::middleware(feature_flags.clone(), req, srv))
+          .wrap_fn(move |req, srv| DeploymentProfile::middleware(profile.clone(), req, srv)),
       )
       .service(Redoc::with_url("/redoc", openapi.clone()))
       .service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", openapi.clone()))
@@ -219,6 +836,7 @@ async fn start_server() -> anyhow::Result<()> {
   })?
   .run()
   .await?;
+  leases.release_all().await;
   Ok(())
 }
 
@@ -227,11 +845,18 @@ async fn main() -> std::io::Result<()> {
   if std::env::var_os("RUST_LOG").is_none() {
     std::env::set_var("RUST_LOG", "actix_web=info");
   }
+  // The environment without the `.env` file, the config is reloaded on top of it.
+  let env = EnvConfig::from_env();
   // env vars
   dotenv::dotenv().ok();
   env_logger::init();
 
-  if let Err(err) = start_server().await {
+  let res = match std::env::args().nth(1).as_deref() {
+    Some("migrate") => run_migrations().await,
+    Some(cmd) => Err(anyhow::anyhow!("Unknown subcommand: {cmd}")),
+    None => start_server(env).await,
+  };
+  if let Err(err) = res {
     log::error!("Failed to start server: {err:?}");
     return Err(std::io::Error::new(std::io::ErrorKind::Other, err));
   }
@@ -0,0 +1,224 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sp_core::hashing::blake2_256;
+
+use polymesh_private_proof_shared::error::*;
+
+/// Default minimum payload size (bytes) to move to the blob store.
+const DEFAULT_THRESHOLD: usize = 4096;
+/// Prefix of blob references stored in the database.
+const BLOB_REF_PREFIX: &str = "blob:";
+
+/// Storage for large binary payloads.
+#[async_trait]
+pub trait BlobStore: Send + Sync + 'static {
+  async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+
+  async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// Store blobs as files in a directory.
+pub struct FsBlobStore {
+  root: PathBuf,
+}
+
+impl FsBlobStore {
+  pub async fn new(root: impl Into<PathBuf>) -> Result<Self> {
+    let root = root.into();
+    tokio::fs::create_dir_all(&root).await?;
+    Ok(Self { root })
+  }
+
+  fn path(&self, key: &str) -> PathBuf {
+    // Spread the files over sub-directories.
+    self.root.join(&key[..2]).join(key)
+  }
+}
+
+#[async_trait]
+impl BlobStore for FsBlobStore {
+  async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+    let path = self.path(key);
+    if let Some(dir) = path.parent() {
+      tokio::fs::create_dir_all(dir).await?;
+    }
+    // Write to a temporary file first, so readers never see a partial blob.
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, data).await?;
+    tokio::fs::rename(&tmp, &path).await?;
+    Ok(())
+  }
+
+  async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+    match tokio::fs::read(self.path(key)).await {
+      Ok(data) => Ok(Some(data)),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+      Err(err) => Err(err.into()),
+    }
+  }
+}
+
+/// Store blobs in an S3 compatible bucket.
+///
+/// Credentials, region and endpoint are read from the standard `AWS_*` environment variables.
+#[cfg(feature = "s3")]
+pub struct S3BlobStore {
+  store: object_store::aws::AmazonS3,
+}
+
+#[cfg(feature = "s3")]
+impl S3BlobStore {
+  pub fn new(bucket: &str) -> Result<Self> {
+    let store = object_store::aws::AmazonS3Builder::from_env()
+      .with_bucket_name(bucket)
+      .build()
+      .map_err(|err| Error::Other(format!("S3 blob store error: {err:?}")))?;
+    Ok(Self { store })
+  }
+}
+
+#[cfg(feature = "s3")]
+#[async_trait]
+impl BlobStore for S3BlobStore {
+  async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+    use object_store::ObjectStore;
+    self
+      .store
+      .put(&object_store::path::Path::from(key), data.into())
+      .await
+      .map_err(|err| Error::Other(format!("S3 put error: {err:?}")))?;
+    Ok(())
+  }
+
+  async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+    use object_store::ObjectStore;
+    let res = match self.store.get(&object_store::path::Path::from(key)).await {
+      Ok(res) => res,
+      Err(object_store::Error::NotFound { .. }) => return Ok(None),
+      Err(err) => return Err(Error::Other(format!("S3 get error: {err:?}"))),
+    };
+    let data = res
+      .bytes()
+      .await
+      .map_err(|err| Error::Other(format!("S3 get error: {err:?}")))?;
+    Ok(Some(data.to_vec()))
+  }
+}
+
+/// Moves large payloads (raw events, settlement events with proofs) out of the database.
+///
+/// Payloads of at least `threshold` bytes are stored in the blob store, keyed by their
+/// hash, and the database keeps a reference.  The reference for a JSON encoded enum
+/// (`{"Variant":{...}}`) keeps the variant, i.e. `{"Variant":"blob:<hash>"}`, so that
+/// queries filtering on the variant still work.  Other payloads are replaced with
+/// `blob:<hash>`.
+pub struct BlobStorage {
+  store: Box<dyn BlobStore>,
+  threshold: usize,
+}
+
+impl BlobStorage {
+  pub fn new(store: Box<dyn BlobStore>, threshold: usize) -> Self {
+    Self { store, threshold }
+  }
+
+  /// Configure the blob store from the environment.
+  ///
+  /// `BLOB_STORE` is either `fs` (with `BLOB_STORE_PATH`) or `s3` (with `BLOB_STORE_BUCKET`).
+  /// Returns `None` if `BLOB_STORE` isn't set.
+  pub async fn from_env() -> Result<Option<Arc<Self>>> {
+    let kind = match std::env::var("BLOB_STORE").ok() {
+      Some(kind) => kind,
+      None => return Ok(None),
+    };
+    let threshold = std::env::var("BLOB_STORE_THRESHOLD")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_THRESHOLD);
+    let store: Box<dyn BlobStore> = match kind.as_str() {
+      "fs" => {
+        let path = std::env::var("BLOB_STORE_PATH")
+          .map_err(|_| Error::other("BLOB_STORE_PATH is required for the `fs` blob store"))?;
+        Box::new(FsBlobStore::new(path).await?)
+      }
+      #[cfg(feature = "s3")]
+      "s3" => {
+        let bucket = std::env::var("BLOB_STORE_BUCKET")
+          .map_err(|_| Error::other("BLOB_STORE_BUCKET is required for the `s3` blob store"))?;
+        Box::new(S3BlobStore::new(&bucket)?)
+      }
+      _ => {
+        return Err(Error::Other(format!(
+          "Unsupported blob store: {kind}.  Use `fs` or `s3` (needs the `s3` feature)."
+        )));
+      }
+    };
+    log::info!("Storing payloads of {threshold} bytes or more in the {kind} blob store");
+    Ok(Some(Arc::new(Self::new(store, threshold))))
+  }
+
+  /// Minimum payload size (bytes) to move to the blob store.
+  pub fn threshold(&self) -> usize {
+    self.threshold
+  }
+
+  /// Is the payload large enough to be moved to the blob store.
+  pub fn should_offload(&self, payload: &str) -> bool {
+    payload.len() >= self.threshold && blob_key(payload).is_none()
+  }
+
+  /// Move a large payload to the blob store.  Returns the value to store in the database.
+  pub async fn offload(&self, payload: String) -> Result<String> {
+    if !self.should_offload(&payload) {
+      return Ok(payload);
+    }
+    let key = hex::encode(blake2_256(payload.as_bytes()));
+    let reference = match enum_variant(&payload) {
+      Some(variant) => format!(r#"{{"{variant}":"{BLOB_REF_PREFIX}{key}"}}"#),
+      None => format!("{BLOB_REF_PREFIX}{key}"),
+    };
+    self.store.put(&key, payload.into_bytes()).await?;
+    Ok(reference)
+  }
+
+  /// Load the payload of a value from the database.
+  pub async fn load(&self, value: String) -> Result<String> {
+    let key = match blob_key(&value) {
+      Some(key) => key,
+      None => return Ok(value),
+    };
+    let data = self
+      .store
+      .get(&key)
+      .await?
+      .ok_or_else(|| Error::not_found("Blob"))?;
+    String::from_utf8(data).map_err(|_| Error::other("Invalid blob"))
+  }
+}
+
+/// The variant of a JSON encoded enum: `{"Variant":...}`.
+fn enum_variant(payload: &str) -> Option<&str> {
+  let rest = payload.strip_prefix("{\"")?;
+  let end = rest.find('"')?;
+  rest[end + 1..].starts_with(':').then(|| &rest[..end])
+}
+
+/// The blob key of a reference.
+fn blob_key(value: &str) -> Option<String> {
+  if let Some(key) = value.strip_prefix(BLOB_REF_PREFIX) {
+    return Some(key.to_string());
+  }
+  // References are short, don't parse payloads.
+  if value.len() > 256 {
+    return None;
+  }
+  let reference = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(value).ok()?;
+  match reference.values().next() {
+    Some(serde_json::Value::String(s)) if reference.len() == 1 => {
+      s.strip_prefix(BLOB_REF_PREFIX).map(|key| key.to_string())
+    }
+    _ => None,
+  }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+
+use polymesh_private_proof_shared::{error::Result, TransactionResult};
+
+use polymesh_api::client::Signer;
+
+use crate::repo::TransactionRepository;
+use crate::signing::TxSigner;
+use crate::webhooks::{AppWebhooks, WebhookEvent};
+
+pub type AppSignerBudgets = Data<SignerBudgets>;
+
+/// Time after which a reservation for a transaction that was never recorded is dropped.
+const RESERVATION_TTL: Duration = Duration::from_secs(600);
+
+/// Reserved extrinsics of each signer.
+type Reservations = Arc<Mutex<HashMap<String, Vec<(u64, Instant)>>>>;
+
+/// Enforces the optional per-signer budgets.
+///
+/// Each check of a signer with a daily extrinsic limit reserves one extrinsic until the
+/// transaction's usage is recorded, so concurrent requests can't go over the limit.  The
+/// reservation is released if the transaction isn't submitted (i.e. a dry run, a failed
+/// submission or a transaction queued in the outbox).  The fee budget is only known after
+/// the fact and can be overshot by the transactions in flight.
+pub struct SignerBudgets {
+  tx_repo: TransactionRepository,
+  webhooks: AppWebhooks,
+  reserved: Reservations,
+  next_id: AtomicU64,
+}
+
+/// An extrinsic reserved by `SignerBudgets::check`.
+///
+/// Dropping it releases the extrinsic, pass it to `SignerBudgets::record` once the
+/// transaction is included.
+#[must_use]
+pub struct BudgetReservation {
+  public_key: String,
+  reservation: Option<(u64, Reservations)>,
+}
+
+impl Drop for BudgetReservation {
+  fn drop(&mut self) {
+    if let Some((id, reserved)) = self.reservation.take() {
+      let mut reserved = reserved.lock().expect("Budget lock poisoned");
+      if let Some(reservations) = reserved.get_mut(&self.public_key) {
+        reservations.retain(|(reserved_id, _)| *reserved_id != id);
+      }
+    }
+  }
+}
+
+impl SignerBudgets {
+  pub fn new_app_data(tx_repo: TransactionRepository, webhooks: AppWebhooks) -> AppSignerBudgets {
+    Data::new(Self {
+      tx_repo,
+      webhooks,
+      reserved: Default::default(),
+      next_id: AtomicU64::new(0),
+    })
+  }
+
+  /// Check that the signer hasn't used up its budget and reserve an extrinsic, right
+  /// before submitting a transaction.
+  pub async fn check(&self, signer: &TxSigner) -> Result<BudgetReservation> {
+    let public_key = signer.account().to_string();
+    let mut reservation = None;
+    if let Some(budget) = self.tx_repo.get_signer_budget(&public_key).await? {
+      let mut usage = self.tx_repo.get_signer_usage(&public_key).await?;
+      let mut reserved = self.reserved.lock().expect("Budget lock poisoned");
+      let reservations = reserved.entry(public_key.clone()).or_default();
+      reservations.retain(|(_, reserved_at)| reserved_at.elapsed() < RESERVATION_TTL);
+      usage.daily_extrinsics += reservations.len() as i64;
+      budget.check_usage(&usage)?;
+      if budget.max_daily_extrinsics.is_some() {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        reservations.push((id, Instant::now()));
+        reservation = Some((id, self.reserved.clone()));
+      }
+    }
+    Ok(BudgetReservation {
+      public_key,
+      reservation,
+    })
+  }
+
+  /// Record the signer's usage, replacing the reservation, and send an alert if it is
+  /// getting close to a limit.
+  ///
+  /// The transaction was already submitted, so failures are only logged.
+  pub async fn record(&self, reservation: BudgetReservation, res: &TransactionResult) {
+    let public_key = reservation.public_key.clone();
+    if let Err(err) = self.try_record(&public_key, res).await {
+      log::error!(
+        "Failed to record usage of signer {public_key} for {}: {err:?}",
+        res.tx_hash
+      );
+    }
+    // The usage is recorded, release the reservation.
+    drop(reservation);
+  }
+
+  async fn try_record(&self, public_key: &str, res: &TransactionResult) -> Result<()> {
+    let public_key = public_key.to_string();
+    let fee = res.fee.unwrap_or_default();
+    self
+      .tx_repo
+      .add_signer_usage(&public_key, &res.tx_hash, fee)
+      .await?;
+
+    let budget = match self.tx_repo.get_signer_budget(&public_key).await? {
+      Some(budget) => budget,
+      None => return Ok(()),
+    };
+    let usage = self.tx_repo.get_signer_usage(&public_key).await?;
+    if let Some(max) = budget.max_daily_extrinsics {
+      let used = usage.daily_extrinsics;
+      if budget.crossed_alert(max, used - 1, used) {
+        self
+          .webhooks
+          .send(WebhookEvent::SignerBudgetAlert {
+            public_key: public_key.clone(),
+            limit: "max_daily_extrinsics".into(),
+            used,
+            max,
+          })
+          .await?;
+      }
+    }
+    if let Some(max) = budget.max_total_fees {
+      let used = usage.total_fees;
+      if budget.crossed_alert(max, used - fee as i64, used) {
+        self
+          .webhooks
+          .send(WebhookEvent::SignerBudgetAlert {
+            public_key,
+            limit: "max_total_fees".into(),
+            used,
+            max,
+          })
+          .await?;
+      }
+    }
+    Ok(())
+  }
+}
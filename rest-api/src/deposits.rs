@@ -0,0 +1,125 @@
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{
+  error::Result, AddDeposit, ProcessedEvent, SettlementLegFilter, TransactionAffirmed,
+  TransactionParty, TransactionResult,
+};
+
+use crate::repo::TransactionRepository;
+use crate::webhooks::{AppWebhooks, WebhookEvent};
+
+/// Credit transfers to the registered deposit accounts.
+///
+/// When a sender affirms a leg to a deposit account, the sender proofs are verified as the
+/// receiver and the decrypted amounts are credited to the internal reference of the
+/// settlement (the memo of the invoice it pays, or the settlement memo).  A `Deposit`
+/// webhook is sent for each credited amount.  Each leg's asset is only credited once.
+pub async fn credit_deposits(
+  repo: &Repository,
+  tx_repo: &TransactionRepository,
+  webhooks: &AppWebhooks,
+  tx: &TransactionResult,
+) -> Result<()> {
+  for ev in &tx.processed_events.0 {
+    if let ProcessedEvent::ConfidentialTransactionAffirmed(affirmed) = ev {
+      if matches!(affirmed.party, TransactionParty::Sender) {
+        credit_leg(repo, tx_repo, webhooks, affirmed).await?;
+      }
+    }
+  }
+  Ok(())
+}
+
+async fn credit_leg(
+  repo: &Repository,
+  tx_repo: &TransactionRepository,
+  webhooks: &AppWebhooks,
+  affirmed: &TransactionAffirmed,
+) -> Result<()> {
+  let proofs = match &affirmed.transfer_proofs {
+    Some(proofs) => proofs,
+    None => return Ok(()),
+  };
+  let settlement_id = affirmed.transaction_id.0 as i64;
+  let leg_id = affirmed.leg_id.0 as i64;
+  let filter = SettlementLegFilter {
+    settlement_id: Some(settlement_id as u32),
+    ..Default::default()
+  };
+  let leg = match tx_repo
+    .get_settlement_legs(&filter)
+    .await?
+    .into_iter()
+    .find(|leg| leg.leg_id as i64 == leg_id)
+  {
+    Some(leg) => leg,
+    None => return Ok(()),
+  };
+  let receiver = format!("0x{}", hex::encode(leg.leg.receiver.0));
+  if tx_repo.get_deposit_account(&receiver).await?.is_none() {
+    return Ok(());
+  }
+  let account = match repo
+    .get_account_with_secret(&hex::encode(leg.leg.receiver.0))
+    .await?
+  {
+    Some(account) => account,
+    None => {
+      log::warn!("Deposit account {receiver} isn't one of our accounts");
+      return Ok(());
+    }
+  };
+
+  // The internal reference is the invoice's memo, or the settlement memo.
+  let memo = tx_repo
+    .get_settlement(settlement_id)
+    .await?
+    .and_then(|settlement| settlement.memo);
+  let invoice = match &memo {
+    Some(memo) => tx_repo
+      .get_invoice(memo)
+      .await?
+      .filter(|invoice| invoice.settlement_id == Some(settlement_id)),
+    None => None,
+  };
+  let reference = invoice
+    .as_ref()
+    .and_then(|invoice| invoice.memo.clone())
+    .or(memo);
+
+  for (asset_id, proof) in &proofs.proofs {
+    let res = account.receiver_verify_sender_proof(proof, None)?;
+    let amount = match (res.is_valid(), res.amount()) {
+      (true, Some(amount)) => amount,
+      _ => {
+        log::warn!(
+          "Invalid sender proof for deposit to {receiver} in settlement {settlement_id} leg {leg_id}: {:?}",
+          res.err_msg()
+        );
+        continue;
+      }
+    };
+    let deposit = match tx_repo
+      .add_deposit(&AddDeposit {
+        settlement_id,
+        leg_id,
+        confidential_account: receiver.clone(),
+        asset_id: *asset_id,
+        amount: amount as i64,
+        reference: reference.clone(),
+        invoice_id: invoice.as_ref().map(|invoice| invoice.invoice_id),
+      })
+      .await?
+    {
+      Some(deposit) => deposit,
+      // Already credited.
+      None => continue,
+    };
+    log::info!(
+      "Deposit {} of {amount} credited to {receiver} (reference: {:?})",
+      deposit.deposit_id,
+      deposit.reference
+    );
+    webhooks.send(WebhookEvent::Deposit(deposit)).await?;
+  }
+  Ok(())
+}
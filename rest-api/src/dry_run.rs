@@ -0,0 +1,85 @@
+use codec::{Decode, Encode};
+use sp_core::Bytes;
+
+use polymesh_api::client::{rpc_params, Signer};
+use polymesh_api::types::runtime::RuntimeCall;
+use polymesh_api::{Api, WrappedCall};
+
+use polymesh_private_proof_shared::{error::*, DryRunResult};
+
+/// Approximate size of the signature, address and signed extensions of a signed extrinsic.
+const SIGNED_EXTRINSIC_OVERHEAD: u32 = 110;
+
+#[derive(Decode)]
+struct Weight {
+  #[codec(compact)]
+  ref_time: u64,
+  #[codec(compact)]
+  proof_size: u64,
+}
+
+#[derive(Decode)]
+enum DispatchClass {
+  Normal,
+  Operational,
+  Mandatory,
+}
+
+#[derive(Decode)]
+struct RuntimeDispatchInfo {
+  weight: Weight,
+  class: DispatchClass,
+  partial_fee: u128,
+}
+
+/// Estimate the fees of the call and check the signer can pay them, without submitting it.
+///
+/// The node can't return the events of a transaction without executing it in a block, so
+/// they aren't included.
+pub async fn dry_run(
+  api: &Api,
+  signer: &dyn Signer,
+  extrinsic: &str,
+  call: WrappedCall,
+) -> Result<DryRunResult> {
+  let call: RuntimeCall = call.into();
+  let encoded = call.encode();
+  let len = encoded.len() as u32 + SIGNED_EXTRINSIC_OVERHEAD;
+  let info: Bytes = api
+    .client()
+    .request(
+      "state_call",
+      rpc_params!(
+        "TransactionPaymentCallApi_query_call_info",
+        Bytes((call, len).encode())
+      ),
+    )
+    .await
+    .map_err(|err| Error::from(err))?;
+  let info = RuntimeDispatchInfo::decode(&mut &info[..])?;
+
+  let account = signer.account();
+  let free_balance = api
+    .query()
+    .system()
+    .account(account.clone())
+    .await
+    .map_err(|err| Error::from(err))?
+    .data
+    .free;
+  let class = match info.class {
+    DispatchClass::Normal => "normal",
+    DispatchClass::Operational => "operational",
+    DispatchClass::Mandatory => "mandatory",
+  };
+  Ok(DryRunResult {
+    extrinsic: extrinsic.to_string(),
+    account: account.to_string(),
+    weight: info.weight.ref_time,
+    proof_size: info.weight.proof_size,
+    class: class.to_string(),
+    partial_fee: info.partial_fee,
+    free_balance,
+    sufficient_balance: free_balance >= info.partial_fee,
+  })
+}
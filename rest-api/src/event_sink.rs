@@ -0,0 +1,165 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use codec::Encode;
+use serde::Serialize;
+
+use polymesh_private_proof_shared::{error::*, ProcessedEvent, TransactionResult};
+
+/// Serialization used for published events.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum SinkFormat {
+  #[default]
+  Json,
+  Scale,
+}
+
+impl FromStr for SinkFormat {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.to_lowercase().as_str() {
+      "json" => Ok(Self::Json),
+      "scale" => Ok(Self::Scale),
+      _ => Err(Error::other(&format!("Unknown event sink format: {s}"))),
+    }
+  }
+}
+
+impl SinkFormat {
+  fn encode<T: Serialize + Encode>(&self, value: &T) -> Result<Vec<u8>> {
+    Ok(match self {
+      Self::Json => serde_json::to_vec(value)?,
+      Self::Scale => value.encode(),
+    })
+  }
+}
+
+/// A message broker that events can be published to.
+#[async_trait]
+pub trait EventSink: Send + Sync + 'static {
+  async fn publish(&self, topic: &str, key: &str, payload: Vec<u8>) -> Result<()>;
+}
+
+/// Publish events to NATS subjects.
+#[cfg(feature = "nats")]
+pub struct NatsSink {
+  client: async_nats::Client,
+}
+
+#[cfg(feature = "nats")]
+impl NatsSink {
+  pub async fn connect(url: &str) -> Result<Self> {
+    let client = async_nats::connect(url)
+      .await
+      .map_err(|err| Error::Other(format!("NATS connect error: {err:?}")))?;
+    Ok(Self { client })
+  }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl EventSink for NatsSink {
+  async fn publish(&self, topic: &str, _key: &str, payload: Vec<u8>) -> Result<()> {
+    self
+      .client
+      .publish(topic.to_string(), payload.into())
+      .await
+      .map_err(|err| Error::Other(format!("NATS publish error: {err:?}")))?;
+    Ok(())
+  }
+}
+
+/// Publish events to Kafka topics.
+#[cfg(feature = "kafka")]
+pub struct KafkaSink {
+  producer: rdkafka::producer::FutureProducer,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSink {
+  pub fn connect(brokers: &str) -> Result<Self> {
+    let producer = rdkafka::ClientConfig::new()
+      .set("bootstrap.servers", brokers)
+      .create()
+      .map_err(|err| Error::Other(format!("Kafka producer error: {err:?}")))?;
+    Ok(Self { producer })
+  }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl EventSink for KafkaSink {
+  async fn publish(&self, topic: &str, key: &str, payload: Vec<u8>) -> Result<()> {
+    let record = rdkafka::producer::FutureRecord::to(topic)
+      .key(key)
+      .payload(&payload);
+    self
+      .producer
+      .send(record, std::time::Duration::from_secs(10))
+      .await
+      .map_err(|(err, _)| Error::Other(format!("Kafka publish error: {err:?}")))?;
+    Ok(())
+  }
+}
+
+/// Publishes the chain watcher's processed transactions and events to an `EventSink`.
+///
+/// Transactions are published to `<prefix>.transactions` and their events to
+/// `<prefix>.events`, keyed by the transaction hash.
+pub struct EventPublisher {
+  sink: Box<dyn EventSink>,
+  format: SinkFormat,
+  transactions_topic: String,
+  events_topic: String,
+}
+
+impl EventPublisher {
+  pub fn new(sink: Box<dyn EventSink>, format: SinkFormat, prefix: &str) -> Self {
+    Self {
+      sink,
+      format,
+      transactions_topic: format!("{prefix}.transactions"),
+      events_topic: format!("{prefix}.events"),
+    }
+  }
+
+  /// Connect to the sink.  `kind` is either `nats` or `kafka`, and
+  /// `url` is the NATS server url or the list of Kafka brokers.
+  pub async fn connect(kind: &str, url: &str, format: SinkFormat, prefix: &str) -> Result<Self> {
+    let sink: Box<dyn EventSink> = match kind {
+      #[cfg(feature = "nats")]
+      "nats" => Box::new(NatsSink::connect(url).await?),
+      #[cfg(feature = "kafka")]
+      "kafka" => Box::new(KafkaSink::connect(url)?),
+      _ => {
+        let _ = url;
+        return Err(Error::Other(format!(
+          "Unsupported event sink: {kind}.  Build with the `nats` or `kafka` feature."
+        )));
+      }
+    };
+    Ok(Self::new(sink, format, prefix))
+  }
+
+  /// Publish a processed transaction and it's events.
+  pub async fn publish(&self, tx: &TransactionResult) -> Result<()> {
+    let payload = self.format.encode(tx)?;
+    self
+      .sink
+      .publish(&self.transactions_topic, &tx.tx_hash, payload)
+      .await?;
+    for ev in &tx.processed_events.0 {
+      self.publish_event(&tx.tx_hash, ev).await?;
+    }
+    Ok(())
+  }
+
+  async fn publish_event(&self, tx_hash: &str, ev: &ProcessedEvent) -> Result<()> {
+    let payload = self.format.encode(ev)?;
+    self
+      .sink
+      .publish(&self.events_topic, tx_hash, payload)
+      .await
+  }
+}
@@ -0,0 +1,284 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::web::Data;
+use actix_web_lab::sse;
+use futures_util::stream::{self, Stream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::{error::Result, ChainEvent, PublicKey, SettlementLegFilter};
+
+use crate::repo::TransactionRepository;
+use crate::watcher::WatcherEvent;
+
+pub type AppEventStream = Data<EventStream>;
+
+/// Number of events buffered for slow clients.
+const EVENT_STREAM_CAPACITY: usize = 1024;
+/// How often to check for transactions persisted by the chain watcher.
+const EVENT_STREAM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum number of transactions read per query.
+const EVENT_STREAM_BATCH_SIZE: u32 = 100;
+
+/// Streams the events processed by the chain watcher to subscribed clients.
+///
+/// The chain watcher usually runs in another process, so `run` tails the transactions it
+/// persisted.  Affirmations, executions and rejections only reference the settlement, so
+/// their accounts and assets are looked up from the settlement legs saved by the chain
+/// watcher.
+pub struct EventStream {
+  tx_repo: TransactionRepository,
+  bus: broadcast::Sender<Arc<ChainEvent>>,
+}
+
+impl EventStream {
+  pub fn new_app_data(tx_repo: TransactionRepository) -> AppEventStream {
+    let (bus, _) = broadcast::channel(EVENT_STREAM_CAPACITY);
+    Data::new(Self { tx_repo, bus })
+  }
+
+  /// Publish the transactions persisted by the chain watcher, until the process exits.
+  ///
+  /// Starts after the last persisted transaction.  Each REST API instance streams to its
+  /// own clients, so this runs in every instance.
+  pub async fn run(&self) {
+    let mut last_id = None;
+    loop {
+      let res = match last_id {
+        Some(id) => self.publish_persisted(id).await,
+        None => self.tx_repo.get_last_block_transaction_id().await,
+      };
+      match res {
+        Ok(id) => last_id = Some(id),
+        Err(err) => log::error!("Failed to stream persisted chain events: {err:?}"),
+      }
+      actix_web::rt::time::sleep(EVENT_STREAM_POLL_INTERVAL).await;
+    }
+  }
+
+  /// Publish the transactions persisted after the row `last_id`.  Returns the row id of the
+  /// last published transaction.
+  ///
+  /// Only transactions of blocks the chain watcher has finished processing are published,
+  /// so their settlements and balances are saved by the time clients see the events.
+  pub async fn publish_persisted(&self, mut last_id: i64) -> Result<i64> {
+    let processed = self.tx_repo.get_watcher_status().await?.processed_block;
+    loop {
+      let txs = self
+        .tx_repo
+        .get_block_transactions_after(last_id, EVENT_STREAM_BATCH_SIZE)
+        .await?;
+      let count = txs.len();
+      for (id, rec) in txs {
+        if rec.block_number > processed {
+          return Ok(last_id);
+        }
+        match rec.to_tx_result() {
+          Ok(tx) => self.publish(Arc::new(tx)).await?,
+          Err(err) => log::error!("Failed to decode transaction {}: {err:?}", rec.tx_hash),
+        }
+        last_id = id;
+      }
+      if count < EVENT_STREAM_BATCH_SIZE as usize {
+        return Ok(last_id);
+      }
+    }
+  }
+
+  /// Publish the events of a block transaction to the subscribed clients.
+  pub async fn publish(&self, tx: WatcherEvent) -> Result<()> {
+    // Nothing to do without clients.
+    if self.bus.receiver_count() == 0 {
+      return Ok(());
+    }
+    for event in &tx.processed_events.0 {
+      let mut chain_event = ChainEvent::new(&tx, event);
+      if let Some((settlement_id, leg_id)) = chain_event.settlement_ref() {
+        let filter = SettlementLegFilter {
+          settlement_id: Some(settlement_id),
+          ..Default::default()
+        };
+        for leg in self.tx_repo.get_settlement_legs(&filter).await? {
+          if leg_id.map_or(true, |leg_id| leg.leg_id == leg_id) {
+            chain_event.add_leg(&leg.leg);
+          }
+        }
+      }
+      // Only fails when there are no clients.
+      let _ = self.bus.send(Arc::new(chain_event));
+    }
+    Ok(())
+  }
+
+  /// Receive all published events.
+  pub fn receiver(&self) -> broadcast::Receiver<Arc<ChainEvent>> {
+    self.bus.subscribe()
+  }
+
+  /// Subscribe to the events involving the account and asset.  `None` matches everything.
+  ///
+  /// Events are sent as `chain_event` messages.  If the client is too slow and events are
+  /// dropped, a `lagged` message with the number of dropped events is sent.
+  pub fn subscribe(
+    &self,
+    account: Option<PublicKey>,
+    asset_id: Option<Uuid>,
+  ) -> impl Stream<Item = Result<sse::Event, Infallible>> {
+    let rx = self.bus.subscribe();
+    stream::unfold((rx, account, asset_id), |state| async move {
+      let (mut rx, account, asset_id) = state;
+      loop {
+        let msg = match rx.recv().await {
+          Ok(event) => {
+            if !event.matches(account.as_ref(), asset_id) {
+              continue;
+            }
+            match sse::Data::new_json(&*event) {
+              Ok(data) => data.event("chain_event"),
+              Err(err) => {
+                log::error!("Failed to encode chain event: {err:?}");
+                continue;
+              }
+            }
+          }
+          Err(RecvError::Lagged(skipped)) => {
+            log::warn!("Event stream client lagged, skipped {skipped} events");
+            sse::Data::new(skipped.to_string()).event("lagged")
+          }
+          Err(RecvError::Closed) => return None,
+        };
+        return Some((Ok(msg.into()), (rx, account, asset_id)));
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use actix_web::body::MessageBody;
+  use actix_web::{test, App};
+  use futures_util::future::poll_fn;
+
+  use polymesh_private_proof_shared::{
+    BlockTransactionRecord, ProcessedEvent, ProcessedEvents, TransactionResult,
+  };
+
+  use super::*;
+  use crate::repo::SqliteTransactionRepository;
+
+  /// In-memory database with the migrations applied.
+  pub(crate) async fn test_pool() -> sqlx::SqlitePool {
+    // Each connection to `:memory:` is a separate database.
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect("sqlite::memory:")
+      .await
+      .expect("In-memory pool");
+    sqlx::migrate!().run(&pool).await.expect("Migrations");
+    pool
+  }
+
+  /// Persist a block transaction and mark its block as processed, like the chain watcher.
+  pub(crate) async fn persist_tx(
+    tx_repo: &TransactionRepository,
+    block_number: u32,
+    events: Vec<ProcessedEvent>,
+  ) {
+    let tx = TransactionResult {
+      block_hash: format!("0x{block_number:064x}"),
+      block_number,
+      tx_hash: format!("0x{:064x}", block_number + 1000),
+      success: true,
+      processed_events: ProcessedEvents(events),
+      ..Default::default()
+    };
+    let rec = BlockTransactionRecord::from_tx(&tx).expect("Transaction record");
+    tx_repo
+      .add_block_transaction(rec)
+      .await
+      .expect("Persist transaction");
+    tx_repo
+      .set_watcher_status(block_number, block_number, None, false)
+      .await
+      .expect("Watcher status");
+  }
+
+  #[actix_web::test]
+  async fn persisted_events_reach_sse_clients() {
+    let tx_repo = SqliteTransactionRepository::new_app_data(&test_pool().await);
+    let event_stream = EventStream::new_app_data(tx_repo.clone());
+    let app = test::init_service(
+      App::new()
+        .app_data(event_stream.clone())
+        .configure(crate::v1::events::service),
+    )
+    .await;
+    let last_id = tx_repo.get_last_block_transaction_id().await.unwrap();
+    let req = test::TestRequest::get().uri("/events/stream").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+
+    let asset_id = Uuid::new_v4();
+    persist_tx(
+      &tx_repo,
+      1,
+      vec![ProcessedEvent::ConfidentialAssetCreated { asset_id }],
+    )
+    .await;
+    let published = event_stream.publish_persisted(last_id).await.unwrap();
+    assert!(published > last_id);
+
+    let mut body = Box::pin(resp.into_body());
+    let received = actix_web::rt::time::timeout(Duration::from_secs(5), async {
+      let mut received = String::new();
+      while !received.contains("event: chain_event") {
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx))
+          .await
+          .expect("Event stream ended")
+          .expect("Event stream failed");
+        received.push_str(&String::from_utf8_lossy(&chunk));
+      }
+      received
+    })
+    .await
+    .expect("No chain event received");
+    assert!(received.contains(&asset_id.to_string()));
+  }
+
+  #[actix_web::test]
+  async fn unprocessed_blocks_are_held_back() {
+    let tx_repo = SqliteTransactionRepository::new_app_data(&test_pool().await);
+    let event_stream = EventStream::new_app_data(tx_repo.clone());
+    let mut rx = event_stream.receiver();
+    let last_id = tx_repo.get_last_block_transaction_id().await.unwrap();
+
+    let asset_id = Uuid::new_v4();
+    persist_tx(
+      &tx_repo,
+      2,
+      vec![ProcessedEvent::ConfidentialAssetCreated { asset_id }],
+    )
+    .await;
+    // The watcher hasn't finished the block yet.
+    tx_repo.set_watcher_status(2, 1, None, false).await.unwrap();
+    assert_eq!(
+      event_stream.publish_persisted(last_id).await.unwrap(),
+      last_id
+    );
+    assert!(rx.try_recv().is_err());
+
+    tx_repo.set_watcher_status(2, 2, None, false).await.unwrap();
+    let published = event_stream.publish_persisted(last_id).await.unwrap();
+    assert!(published > last_id);
+    let event = rx.try_recv().expect("Published event");
+    assert!(event.asset_ids.contains(&asset_id));
+    // Nothing is published twice.
+    assert_eq!(
+      event_stream.publish_persisted(published).await.unwrap(),
+      published
+    );
+    assert!(rx.try_recv().is_err());
+  }
+}
@@ -0,0 +1,724 @@
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::{rt::pin, web::Data};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use confidential_assets::CipherText;
+
+use polymesh_api::types::{
+  confidential_assets::transaction::ConfidentialTransferProof as SenderProof,
+  pallet_confidential_asset::{
+    AffirmLeg, AffirmParty, AffirmTransaction, AffirmTransactions, ConfidentialTransfers,
+    TransactionId, TransactionLegId,
+  },
+  polymesh_primitives::settlement::VenueId,
+};
+use polymesh_api::{Api, WrappedCall};
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{
+  auditor_account_to_key, confidential_account_to_key,
+  error::{Error, Result},
+  scale_convert, spawn_proof, AccountAssetImportedBalance, AccountWithSecret, BalanceAction,
+  BalanceSource, ConfidentialSettlementLeg, CreateAccount, CreateConfidentialSettlement,
+  KeyCompromiseReport, KeyCompromiseRequest, ProcessedEvent, PublicKey, TransactionResult, TxJob,
+  TxOutboxStatus, UpdateAccountAsset, MAX_ASSETS_PER_LEG, MAX_SETTLEMENT_LEGS,
+};
+
+use crate::budgets::AppSignerBudgets;
+use crate::nodes::AppNodes;
+use crate::outbox::{AppTxOutbox, TxSubmission};
+use crate::repo::TransactionRepository;
+use crate::signing::AppSigningManager;
+
+pub type AppKeyCompromiseJobs = Data<KeyCompromiseJobs>;
+
+/// Operation of the key compromise jobs.
+pub const KEY_COMPROMISE_OPERATION: &str = "key_compromised";
+/// Seconds between checks for jobs orphaned by a restart, run by the scheduler.
+pub const RESUME_INTERVAL: u64 = 30;
+/// Interval between checks for a step's transaction.
+const TX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Seconds before a step fails, if its transaction wasn't found on-chain.
+const TX_TIMEOUT: u64 = 3600;
+
+/// Saved progress of a key compromise job.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct JobState {
+  request: KeyCompromiseRequest,
+  report: KeyCompromiseReport,
+  /// Outbox entry of the current step's transaction, while it waits to be submitted.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  outbox_id: Option<i64>,
+}
+
+/// Background jobs that respond to compromised confidential accounts.
+///
+/// The workflow locks the account, creates a replacement account and moves the full
+/// balance of each asset to it with a self-settlement (one leg per `MAX_ASSETS_PER_LEG`
+/// assets, up to `MAX_SETTLEMENT_LEGS` legs), then updates the tracked balances of both
+/// accounts.  The transactions are submitted through the transaction outbox.
+///
+/// The job's progress is saved after each step (see `/tx/jobs/{job_id}`), and a job
+/// orphaned by a restart is resumed from its last step.  A step that already submitted its
+/// transaction waits for the transaction recorded by the chain watcher instead of
+/// submitting it again.
+pub struct KeyCompromiseJobs {
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  nodes: AppNodes,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  outbox: AppTxOutbox,
+  running: Mutex<HashSet<i64>>,
+  started_at: chrono::NaiveDateTime,
+}
+
+impl KeyCompromiseJobs {
+  pub fn new_app_data(
+    repo: Repository,
+    tx_repo: TransactionRepository,
+    nodes: AppNodes,
+    signing: AppSigningManager,
+    budgets: AppSignerBudgets,
+    outbox: AppTxOutbox,
+  ) -> AppKeyCompromiseJobs {
+    Data::new(Self {
+      repo,
+      tx_repo,
+      nodes,
+      signing,
+      budgets,
+      outbox,
+      running: Default::default(),
+      started_at: chrono::Utc::now().naive_utc(),
+    })
+  }
+
+  /// Start the workflow for the compromised account in the background.
+  pub async fn start(
+    jobs: &AppKeyCompromiseJobs,
+    compromised_account: PublicKey,
+    request: KeyCompromiseRequest,
+  ) -> Result<TxJob> {
+    let state = JobState {
+      request,
+      report: KeyCompromiseReport {
+        compromised_account,
+        ..Default::default()
+      },
+      outbox_id: None,
+    };
+    let job = jobs
+      .tx_repo
+      .create_progress_tx_job(
+        KEY_COMPROMISE_OPERATION,
+        state.request.finalize,
+        &serde_json::to_string(&state)?,
+      )
+      .await?;
+    Self::spawn(jobs, job.job_id, state);
+    TxJob::from_row(job)
+  }
+
+  /// Resume the jobs orphaned by a restart.
+  pub async fn resume_jobs(jobs: &AppKeyCompromiseJobs) -> Result<()> {
+    for job in jobs.tx_repo.get_unfinished_tx_jobs().await? {
+      // Jobs created since the start are still running.
+      if job.operation != KEY_COMPROMISE_OPERATION || job.created_at >= jobs.started_at {
+        continue;
+      }
+      if let Some(progress) = &job.progress {
+        log::info!("Resuming key compromise job {}", job.job_id);
+        Self::spawn(jobs, job.job_id, serde_json::from_str(progress)?);
+      }
+    }
+    Ok(())
+  }
+
+  fn spawn(jobs: &AppKeyCompromiseJobs, job_id: i64, state: JobState) {
+    if !jobs
+      .running
+      .lock()
+      .expect("Job lock poisoned")
+      .insert(job_id)
+    {
+      return;
+    }
+    let jobs = jobs.clone();
+    actix_web::rt::spawn(async move {
+      let mut workflow = Workflow {
+        jobs: &jobs,
+        job_id,
+        state,
+      };
+      let res = match workflow.run().await {
+        Ok(()) => {
+          workflow.state.report.completed = true;
+          workflow.save_status("completed").await
+        }
+        Err(err) => {
+          log::error!("Key compromise job {job_id} failed: {err:?}");
+          let msg = err.to_string();
+          if let Some(step) = workflow.state.report.steps.last_mut() {
+            step.message = Some(msg.clone());
+          }
+          match workflow.save().await {
+            Ok(()) => jobs.tx_repo.tx_job_failed(job_id, &msg).await,
+            Err(err) => Err(err),
+          }
+        }
+      };
+      if let Err(err) = res {
+        log::error!("Failed to update key compromise job {job_id}: {err:?}");
+      }
+      jobs
+        .running
+        .lock()
+        .expect("Job lock poisoned")
+        .remove(&job_id);
+    });
+  }
+}
+
+struct Workflow<'a> {
+  jobs: &'a KeyCompromiseJobs,
+  job_id: i64,
+  state: JobState,
+}
+
+impl<'a> Workflow<'a> {
+  async fn run(&mut self) -> Result<()> {
+    let api = self.jobs.nodes.api();
+    let public_key = hex::encode(self.state.report.compromised_account.0);
+
+    // Lock the account.
+    if self.start("lock_account").await? {
+      self
+        .jobs
+        .repo
+        .set_account_locked(&public_key, true)
+        .await?
+        .ok_or_else(|| Error::not_found("Account"))?;
+      self.done().await?;
+    }
+    // The workflow still needs the compromised key to move the balances.
+    let old = self
+      .jobs
+      .repo
+      .get_account_with_secret(&public_key)
+      .await?
+      .ok_or_else(|| Error::not_found("Account"))?;
+
+    // Create the replacement account.  Its public key is saved before the account is
+    // stored, so a resumed step reuses the account it already created.
+    if self.start("create_account").await? {
+      let created = match &self.state.report.replacement_account {
+        Some(replacement) => {
+          let replacement = hex::encode(replacement.0);
+          self.jobs.repo.get_account(&replacement).await?.is_some()
+        }
+        None => false,
+      };
+      if !created {
+        let account = CreateAccount::new();
+        let replacement = PublicKey::from_str(&hex::encode(&account.confidential_account))?;
+        self.state.report.replacement_account = Some(replacement);
+        self.save().await?;
+        self.jobs.repo.create_account(&account).await?;
+      }
+      self.done().await?;
+    }
+    let replacement = self
+      .state
+      .report
+      .replacement_account
+      .clone()
+      .ok_or_else(|| Error::other("Replacement account missing"))?;
+    let new = self
+      .jobs
+      .repo
+      .get_account_with_secret(&hex::encode(replacement.0))
+      .await?
+      .ok_or_else(|| Error::not_found("Account"))?;
+    let new_account = new.as_confidential_account()?;
+
+    if self.start("init_account").await? {
+      self
+        .run_tx("create_account", || async {
+          api
+            .call()
+            .confidential_asset()
+            .create_account(new_account)
+            .map_err(|err| Error::from(err))
+        })
+        .await?;
+      self.done().await?;
+    }
+
+    // Get the balances to move.
+    if self.start("get_balances").await? {
+      let balances = get_balances(&api, &old).await?;
+      self.state.report.balances = balances
+        .iter()
+        .map(|(asset_id, (_, balance))| AccountAssetImportedBalance {
+          asset_id: Uuid::from_bytes(*asset_id),
+          balance: *balance,
+        })
+        .collect();
+      self.done().await?;
+    }
+    if self.state.report.balances.is_empty() {
+      return Ok(());
+    }
+    let moved = self.state.report.balances.clone();
+    // One leg per `MAX_ASSETS_PER_LEG` assets.
+    let leg_assets = moved
+      .chunks(MAX_ASSETS_PER_LEG as usize)
+      .map(|assets| {
+        assets
+          .iter()
+          .map(|moved| moved.asset_id)
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+    if leg_assets.len() > MAX_SETTLEMENT_LEGS {
+      return Err(Error::Other(format!(
+        "The account holds {} assets, a settlement can only move {}",
+        moved.len(),
+        MAX_SETTLEMENT_LEGS * MAX_ASSETS_PER_LEG as usize
+      )));
+    }
+    let leg_ids = (0..leg_assets.len())
+      .map(|leg_id| TransactionLegId(leg_id as _))
+      .collect::<Vec<_>>();
+
+    // Create a self-settlement with all assets.
+    if self.start("create_settlement").await? {
+      let settlement = CreateConfidentialSettlement {
+        signer: Default::default(),
+        finalize: self.state.request.finalize,
+        dry_run: false,
+        legs: leg_assets
+          .into_iter()
+          .map(|assets| ConfidentialSettlementLeg {
+            assets,
+            sender: self.state.report.compromised_account.clone(),
+            receiver: replacement.clone(),
+            mediators: Default::default(),
+            auditors: Default::default(),
+          })
+          .collect(),
+        memo: Default::default(),
+      };
+      let venue_id = VenueId(self.state.request.venue_id);
+      let res = self
+        .run_tx("add_transaction", || async {
+          let legs = settlement.legs()?;
+          api
+            .call()
+            .confidential_asset()
+            .add_transaction(venue_id, legs, None)
+            .map_err(|err| Error::from(err))
+        })
+        .await?;
+      let transaction_id = res
+        .processed_events
+        .0
+        .iter()
+        .find_map(|ev| match ev {
+          ProcessedEvent::ConfidentialTransactionCreated(created) => Some(created.transaction_id),
+          _ => None,
+        })
+        .ok_or_else(|| Error::other("Settlement created event missing"))?;
+      self.state.report.settlement_id = Some(transaction_id.0);
+      self.done().await?;
+    }
+    let transaction_id = self
+      .state
+      .report
+      .settlement_id
+      .map(TransactionId)
+      .ok_or_else(|| Error::other("Settlement id missing"))?;
+
+    // Affirm as the sender with the compromised key.
+    if self.start("sender_affirm").await? {
+      self
+        .run_tx("affirm_transactions", || async {
+          // The proofs are for the balances the settlement was created for.
+          let balances = get_balances(&api, &old).await?;
+          let mut parties = Vec::new();
+          for leg_id in &leg_ids {
+            let leg = api
+              .query()
+              .confidential_asset()
+              .transaction_legs(transaction_id, *leg_id)
+              .await
+              .map_err(|err| Error::from(err))?
+              .ok_or_else(|| Error::not_found("Transaction Leg"))?;
+            let receiver = confidential_account_to_key(&leg.receiver);
+            let mut transfers = ConfidentialTransfers {
+              proofs: Default::default(),
+            };
+            for (asset_id, auditors) in leg.auditors {
+              let (enc_balance, balance) = *balances
+                .get(&asset_id)
+                .filter(|(_, balance)| {
+                  moved.iter().any(|moved| {
+                    moved.asset_id.as_bytes() == &asset_id && moved.balance == *balance
+                  })
+                })
+                .ok_or_else(|| Error::other("Balance changed since the workflow started"))?;
+              let auditors = auditors.iter().map(auditor_account_to_key).collect();
+              let proving = old.clone();
+              let proof = spawn_proof(move || {
+                proving.create_send_proof(enc_balance, Some(balance), receiver, auditors, balance)
+              })
+              .await?;
+              transfers
+                .proofs
+                .insert(asset_id, SenderProof(proof.as_bytes()));
+            }
+            parties.push((*leg_id, AffirmParty::Sender(transfers)));
+          }
+          affirm_call(&api, transaction_id, parties)
+        })
+        .await?;
+      self.done().await?;
+    }
+
+    // Affirm as the receiver.
+    if self.start("receiver_affirm").await? {
+      self
+        .run_tx("affirm_transactions", || async {
+          let parties = leg_ids
+            .iter()
+            .map(|leg_id| (*leg_id, AffirmParty::Receiver))
+            .collect();
+          affirm_call(&api, transaction_id, parties)
+        })
+        .await?;
+      self.done().await?;
+    }
+
+    // Execute the settlement.
+    if self.start("execute_settlement").await? {
+      self
+        .run_tx("execute_transaction", || async {
+          api
+            .call()
+            .confidential_asset()
+            .execute_transaction(transaction_id, leg_ids.len() as _)
+            .map_err(|err| Error::from(err))
+        })
+        .await?;
+      self.done().await?;
+    }
+
+    // The compromised account's balances were moved.
+    if self.start("update_compromised_balances").await? {
+      self.refresh_balances(&api, &old).await?;
+      self.done().await?;
+    }
+
+    // Apply the incoming balances to the replacement account.
+    if self.start("apply_incoming").await? {
+      self
+        .run_tx("apply_incoming_balance", || async {
+          let mut calls = Vec::new();
+          for asset in &moved {
+            calls.push(
+              api
+                .call()
+                .confidential_asset()
+                .apply_incoming_balance(new_account, *asset.asset_id.as_bytes())
+                .map_err(|err| Error::from(err))?
+                .into(),
+            );
+          }
+          api
+            .call()
+            .utility()
+            .batch_all(calls)
+            .map_err(|err| Error::from(err))
+        })
+        .await?;
+      self.done().await?;
+    }
+
+    if self.start("update_replacement_balances").await? {
+      self.refresh_balances(&api, &new).await?;
+      self.done().await?;
+    }
+    Ok(())
+  }
+
+  /// Start the step.  Returns `false` if the step already succeeded.
+  async fn start(&mut self, step: &str) -> Result<bool> {
+    if let Some(started) = self.state.report.steps.iter().find(|s| s.step == step) {
+      // Resumed.
+      return Ok(!started.success);
+    }
+    self.state.report.start(step);
+    self.save().await?;
+    Ok(true)
+  }
+
+  /// Mark the current step as successful.
+  async fn done(&mut self) -> Result<()> {
+    let tx_hash = self.tx_hash();
+    self.state.report.done(tx_hash);
+    self.save().await
+  }
+
+  /// Transaction of the current step.
+  fn tx_hash(&self) -> Option<String> {
+    self
+      .state
+      .report
+      .steps
+      .last()
+      .and_then(|step| step.tx_hash.clone())
+  }
+
+  async fn set_tx_hash(&mut self, tx_hash: String) -> Result<()> {
+    if let Some(step) = self.state.report.steps.last_mut() {
+      step.tx_hash = Some(tx_hash);
+    }
+    self.state.outbox_id = None;
+    self.save().await
+  }
+
+  async fn save(&self) -> Result<()> {
+    self.save_status("submitted").await
+  }
+
+  async fn save_status(&self, status: &str) -> Result<()> {
+    // The job's transaction is the latest one submitted.
+    let tx_hash = self
+      .state
+      .report
+      .steps
+      .iter()
+      .rev()
+      .find_map(|step| step.tx_hash.clone())
+      .unwrap_or_default();
+    self
+      .jobs
+      .tx_repo
+      .tx_job_progress(
+        self.job_id,
+        status,
+        &tx_hash,
+        &serde_json::to_string(&self.state)?,
+      )
+      .await
+  }
+
+  /// Submit the current step's transaction and wait for its results.  A resumed step
+  /// waits for the transaction it already submitted (or queued) instead.
+  async fn run_tx<F, Fut>(&mut self, extrinsic: &str, call: F) -> Result<TransactionResult>
+  where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<WrappedCall>>,
+  {
+    let res = match (self.tx_hash(), self.state.outbox_id) {
+      (Some(tx_hash), _) => self.wait_for_block_tx(&tx_hash).await?,
+      (None, Some(outbox_id)) => {
+        let tx_hash = self.wait_for_outbox(outbox_id).await?;
+        self.wait_for_block_tx(&tx_hash).await?
+      }
+      (None, None) => {
+        let call = call().await?;
+        self.submit(extrinsic, call).await?
+      }
+    };
+    if !res.success {
+      return Err(Error::other(
+        res.err_msg.as_deref().unwrap_or("Transaction failed"),
+      ));
+    }
+    Ok(res)
+  }
+
+  async fn submit(&mut self, extrinsic: &str, call: WrappedCall) -> Result<TransactionResult> {
+    let signer_name = self.state.request.signer.clone();
+    let finalize = self.state.request.finalize;
+    let mut signer = self
+      .jobs
+      .signing
+      .get_tx_signer(&signer_name, extrinsic)
+      .await?
+      .ok_or_else(|| Error::not_found("Signer"))?;
+    let budget = self.jobs.budgets.check(&signer).await?;
+    let res = match self
+      .jobs
+      .outbox
+      .submit(
+        KEY_COMPROMISE_OPERATION,
+        &signer_name,
+        extrinsic,
+        &mut signer,
+        call,
+        finalize,
+      )
+      .await?
+    {
+      TxSubmission::Submitted(res) => res,
+      TxSubmission::Queued(entry) => {
+        self.state.outbox_id = Some(entry.outbox_id);
+        self.save().await?;
+        let tx_hash = self.wait_for_outbox(entry.outbox_id).await?;
+        return self.wait_for_block_tx(&tx_hash).await;
+      }
+    };
+    let tx_hash = format!("{:#x}", res.hash());
+    self.set_tx_hash(tx_hash.clone()).await?;
+    match TransactionResult::wait_for_results(res, finalize).await {
+      Ok(res) if !res.pending => {
+        self.jobs.budgets.record(budget, &res).await;
+        Ok(res)
+      }
+      Ok(_) => self.wait_for_block_tx(&tx_hash).await,
+      Err(err) => {
+        log::warn!(
+          "Key compromise job {} lost track of {tx_hash}: {err:?}",
+          self.job_id
+        );
+        self.wait_for_block_tx(&tx_hash).await
+      }
+    }
+  }
+
+  /// Wait for the outbox to submit the current step's transaction.
+  async fn wait_for_outbox(&mut self, outbox_id: i64) -> Result<String> {
+    loop {
+      let entry = self
+        .jobs
+        .tx_repo
+        .get_tx_outbox_entry(outbox_id)
+        .await?
+        .ok_or_else(|| Error::not_found("Outbox transaction"))?;
+      if let Some(tx_hash) = entry.tx_hash {
+        self.set_tx_hash(tx_hash.clone()).await?;
+        return Ok(tx_hash);
+      }
+      if entry.status == TxOutboxStatus::Failed.as_str() {
+        return Err(Error::other(
+          entry
+            .err_msg
+            .as_deref()
+            .unwrap_or("Outbox transaction failed"),
+        ));
+      }
+      actix_web::rt::time::sleep(TX_POLL_INTERVAL).await;
+    }
+  }
+
+  /// Wait for the chain watcher to record the transaction.
+  async fn wait_for_block_tx(&self, tx_hash: &str) -> Result<TransactionResult> {
+    let started = Instant::now();
+    loop {
+      if let Some(rec) = self.jobs.tx_repo.get_block_transaction(tx_hash).await? {
+        return rec.to_tx_result();
+      }
+      if started.elapsed().as_secs() > TX_TIMEOUT {
+        return Err(Error::other("Transaction not found on-chain"));
+      }
+      actix_web::rt::time::sleep(TX_POLL_INTERVAL).await;
+    }
+  }
+
+  /// Update the tracked balances of the moved assets from the chain.
+  async fn refresh_balances(&self, api: &Api, account: &AccountWithSecret) -> Result<()> {
+    let confidential_account = account.as_confidential_account()?;
+    for moved in &self.state.report.balances {
+      let enc_balance: CipherText = match api
+        .query()
+        .confidential_asset()
+        .account_balance(confidential_account, *moved.asset_id.as_bytes())
+        .await
+        .map_err(|err| Error::from(err))?
+      {
+        Some(enc_balance) => scale_convert(&enc_balance),
+        None => continue,
+      };
+      let balance = account.decrypt(&enc_balance)?;
+      self
+        .jobs
+        .repo
+        .update_account_asset(&UpdateAccountAsset {
+          account_asset_id: None,
+          account_id: account.account_id,
+          asset_id: moved.asset_id,
+          balance,
+          enc_balance,
+          block_number: None,
+          source: BalanceSource::Chain,
+          base_enc_balance: None,
+          action: BalanceAction::Refresh,
+          amount: None,
+          history_source: Some(format!("key_compromised job {}", self.job_id)),
+        })
+        .await?;
+    }
+    Ok(())
+  }
+}
+
+/// Get the non-zero balances of the compromised account from the chain.
+async fn get_balances(
+  api: &Api,
+  old: &AccountWithSecret,
+) -> Result<BTreeMap<[u8; 16], (CipherText, u64)>> {
+  let entries = api
+    .paged_query()
+    .confidential_asset()
+    .account_balance(old.as_confidential_account()?)
+    .entries();
+  pin!(entries);
+  let mut balances = BTreeMap::new();
+  while let Some(entry) = entries.next().await {
+    match entry {
+      Ok((asset_id, Some(enc_balance))) => {
+        let enc_balance: CipherText = scale_convert(&enc_balance);
+        let balance = old.decrypt(&enc_balance)?;
+        if balance > 0 {
+          balances.insert(asset_id, (enc_balance, balance));
+        }
+      }
+      Ok((_, None)) => (),
+      Err(err) => {
+        Err(Error::from(err))?;
+      }
+    }
+  }
+  Ok(balances)
+}
+
+/// Affirm the settlement's legs.
+fn affirm_call(
+  api: &Api,
+  transaction_id: TransactionId,
+  parties: Vec<(TransactionLegId, AffirmParty)>,
+) -> Result<WrappedCall> {
+  let affirms = AffirmTransactions(
+    parties
+      .into_iter()
+      .map(|(leg_id, party)| AffirmTransaction {
+        id: transaction_id,
+        leg: AffirmLeg { leg_id, party },
+      })
+      .collect(),
+  );
+  api
+    .call()
+    .confidential_asset()
+    .affirm_transactions(affirms)
+    .map_err(|err| Error::from(err))
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+use futures_util::future::{self, Either};
+
+use crate::repo::TransactionRepository;
+
+pub type AppLeases = Data<Leases>;
+
+/// Default seconds a lease is held without being renewed.
+const DEFAULT_LEASE_TTL_SECS: u32 = 30;
+
+/// Database leases of the background tasks that only one instance should run (the chain
+/// watcher, webhook and transaction outboxes, job recovery and other schedulers).
+///
+/// Enabled with `DISTRIBUTED_LOCKS=true`, so multiple replicas can serve requests while
+/// the background tasks run on one of them (i.e. during a blue/green deploy).  Each task
+/// runs on the instance holding its lease.  The lease is renewed every third of
+/// `LEASE_TTL_SECS` (default 30) and taken over by another instance once it has expired,
+/// so the tasks of a stopped instance move within `LEASE_TTL_SECS`.  Leases are released
+/// when the server shuts down.
+///
+/// `INSTANCE_ID` names the lease holder (default: `$HOSTNAME` and the process id).
+pub struct Leases {
+  tx_repo: TransactionRepository,
+  enabled: bool,
+  holder: String,
+  ttl: u32,
+  held: Mutex<HashSet<&'static str>>,
+}
+
+impl Leases {
+  /// Load the config from `DISTRIBUTED_LOCKS`, `LEASE_TTL_SECS` and `INSTANCE_ID`.
+  pub fn from_env(tx_repo: TransactionRepository) -> AppLeases {
+    let enabled = std::env::var("DISTRIBUTED_LOCKS")
+      .map(|v| v == "true" || v == "1")
+      .unwrap_or(false);
+    let ttl = std::env::var("LEASE_TTL_SECS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_LEASE_TTL_SECS)
+      .max(3);
+    let holder = std::env::var("INSTANCE_ID").unwrap_or_else(|_| {
+      let host = std::env::var("HOSTNAME").unwrap_or("localhost".to_string());
+      format!("{host}-{}", std::process::id())
+    });
+    if enabled {
+      log::info!("Distributed locks enabled, instance id: {holder}");
+    }
+    Data::new(Self {
+      tx_repo,
+      enabled,
+      holder,
+      ttl,
+      held: Mutex::new(HashSet::new()),
+    })
+  }
+
+  /// Lease holder name of this instance.
+  pub fn holder(&self) -> &str {
+    &self.holder
+  }
+
+  fn renew_interval(&self) -> Duration {
+    Duration::from_secs((self.ttl / 3) as u64)
+  }
+
+  /// Run the task while holding the `name` lease.
+  ///
+  /// Waits until the lease is free.  If the lease is lost (i.e. it couldn't be renewed in
+  /// time) the task is stopped and restarted once the lease is acquired again.  Without
+  /// distributed locks the task is run right away.
+  pub async fn run_exclusive<F, Fut>(&self, name: &'static str, task: F)
+  where
+    F: Fn() -> Fut,
+    Fut: Future<Output = ()>,
+  {
+    if !self.enabled {
+      return task().await;
+    }
+    loop {
+      self.wait_for_lease(name).await;
+      log::info!("Acquired lease {name}, starting it");
+      match future::select(Box::pin(task()), Box::pin(self.keep_lease(name))).await {
+        Either::Left(_) => {
+          self.release(name).await;
+          return;
+        }
+        Either::Right(_) => {
+          log::warn!("Lost lease {name}, stopping it");
+          self.held.lock().expect("Lease lock poisoned").remove(name);
+        }
+      }
+    }
+  }
+
+  async fn wait_for_lease(&self, name: &'static str) {
+    loop {
+      match self
+        .tx_repo
+        .acquire_lease(name, &self.holder, self.ttl)
+        .await
+      {
+        Ok(true) => {
+          self.held.lock().expect("Lease lock poisoned").insert(name);
+          return;
+        }
+        Ok(false) => (),
+        Err(err) => log::error!("Failed to acquire lease {name}: {err:?}"),
+      }
+      actix_web::rt::time::sleep(self.renew_interval()).await;
+    }
+  }
+
+  /// Renew the lease until it is lost.
+  async fn keep_lease(&self, name: &'static str) {
+    let ttl = Duration::from_secs(self.ttl as u64);
+    let mut renewed = Instant::now();
+    loop {
+      actix_web::rt::time::sleep(self.renew_interval()).await;
+      match self
+        .tx_repo
+        .acquire_lease(name, &self.holder, self.ttl)
+        .await
+      {
+        Ok(true) => {
+          renewed = Instant::now();
+        }
+        Ok(false) => return,
+        Err(err) => {
+          log::error!("Failed to renew lease {name}: {err:?}");
+          // Another instance can take over once it expires.
+          if renewed.elapsed() >= ttl {
+            return;
+          }
+        }
+      }
+    }
+  }
+
+  async fn release(&self, name: &'static str) {
+    self.held.lock().expect("Lease lock poisoned").remove(name);
+    if let Err(err) = self.tx_repo.release_lease(name, &self.holder).await {
+      log::error!("Failed to release lease {name}: {err:?}");
+    }
+  }
+
+  /// Release the held leases, so other instances can take over right away.
+  pub async fn release_all(&self) {
+    let held = std::mem::take(&mut *self.held.lock().expect("Lease lock poisoned"));
+    for name in held {
+      log::info!("Releasing lease {name}");
+      if let Err(err) = self.tx_repo.release_lease(name, &self.holder).await {
+        log::error!("Failed to release lease {name}: {err:?}");
+      }
+    }
+  }
+}
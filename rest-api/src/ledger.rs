@@ -0,0 +1,100 @@
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{
+  error::Result, BalanceUpdateAction, LedgerEntry, ProcessedEvent, TransactionResult,
+};
+
+use crate::repo::TransactionRepository;
+
+/// System account for minted assets.
+pub const ISSUANCE_ACCOUNT: &str = "issuance";
+/// System account for burned assets.
+pub const BURN_ACCOUNT: &str = "burn";
+/// System account for the other side of settlements.
+pub const COUNTERPARTY_ACCOUNT: &str = "counterparty";
+
+/// Cause of a balance update.
+fn balance_update_cause(tx: &TransactionResult, action: BalanceUpdateAction) -> &'static str {
+  let mut minted = false;
+  let mut settlement = false;
+  for ev in &tx.processed_events.0 {
+    match ev {
+      ProcessedEvent::ConfidentialAssetMinted { .. } => minted = true,
+      ProcessedEvent::ConfidentialTransactionAffirmed(_)
+      | ProcessedEvent::ConfidentialTransactionExecuted { .. } => settlement = true,
+      _ => (),
+    }
+  }
+  match action {
+    BalanceUpdateAction::Deposit if minted => "mint",
+    BalanceUpdateAction::Deposit => "apply_incoming",
+    BalanceUpdateAction::DepositIncoming => "settlement",
+    BalanceUpdateAction::Withdraw if settlement => "settlement",
+    BalanceUpdateAction::Withdraw => "burn",
+  }
+}
+
+/// Record the balance updates of our confidential accounts as debit/credit ledger entries.
+///
+/// Incoming balances are tracked in a separate `<account>:incoming` ledger account until
+/// they are applied.
+pub async fn record_ledger_entries(
+  repo: &Repository,
+  tx_repo: &TransactionRepository,
+  tx: &TransactionResult,
+) -> Result<()> {
+  let entries = ledger_entries(repo, tx).await?;
+  if entries.len() > 0 {
+    tx_repo.add_ledger_entries(&entries).await?;
+  }
+  Ok(())
+}
+
+/// Build the ledger entries for the balance updates of our confidential accounts.
+pub async fn ledger_entries(repo: &Repository, tx: &TransactionResult) -> Result<Vec<LedgerEntry>> {
+  let mut entries = Vec::new();
+  for (idx, ev) in tx.processed_events.0.iter().enumerate() {
+    let balance_updated = match ev {
+      ProcessedEvent::ConfidentialAccountBalanceUpdated(balance_updated) => balance_updated,
+      _ => continue,
+    };
+    let pub_key = hex::encode(balance_updated.account.0);
+    let account = match repo.get_account_with_secret(&pub_key).await? {
+      Some(account) => account,
+      None => continue,
+    };
+    let update = match balance_updated.try_decrypt(&account) {
+      Some(update) => update,
+      None => continue,
+    };
+    let cause = balance_update_cause(tx, update.action);
+    let account = format!("0x{pub_key}");
+    let incoming = format!("{account}:incoming");
+    let (debit_account, credit_account) = match (update.action, cause) {
+      (BalanceUpdateAction::Deposit, "mint") => (account, ISSUANCE_ACCOUNT.to_string()),
+      (BalanceUpdateAction::Deposit, _) => (account, incoming),
+      (BalanceUpdateAction::DepositIncoming, _) => (incoming, COUNTERPARTY_ACCOUNT.to_string()),
+      (BalanceUpdateAction::Withdraw, "settlement") => (COUNTERPARTY_ACCOUNT.to_string(), account),
+      (BalanceUpdateAction::Withdraw, _) => (BURN_ACCOUNT.to_string(), account),
+    };
+    let amount = update.amount as i64;
+    let entry = LedgerEntry {
+      tx_hash: tx.tx_hash.clone(),
+      block_number: tx.block_number,
+      event_index: idx as u32,
+      asset_id: update.asset_id,
+      cause: cause.to_string(),
+      ..Default::default()
+    };
+    entries.push(LedgerEntry {
+      ledger_account: debit_account,
+      debit: amount,
+      ..entry.clone()
+    });
+    entries.push(LedgerEntry {
+      ledger_account: credit_account,
+      credit: amount,
+      ..entry
+    });
+  }
+  Ok(entries)
+}
@@ -1,4 +1,28 @@
+pub mod asset_cache;
+pub mod auto_apply;
+pub mod auto_execute;
+pub mod balance_drift;
+pub mod blobs;
+pub mod budgets;
+pub mod deposits;
+pub mod dry_run;
+pub mod event_sink;
+pub mod event_stream;
+pub mod key_compromise;
+pub mod leases;
+pub mod ledger;
+pub mod maintenance;
+pub mod metrics;
+pub mod nodes;
+pub mod offline;
+pub mod outbox;
+pub mod reload;
 pub mod repo;
+pub mod scheduler;
+pub mod screening;
 pub mod signing;
+pub mod tx_jobs;
 pub mod v1;
+pub mod wallet_pairing;
 pub mod watcher;
+pub mod webhooks;
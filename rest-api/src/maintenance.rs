@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use actix_web::{
+  body::EitherBody,
+  dev::{Service, ServiceRequest, ServiceResponse},
+  http::{header, Method},
+  web::Data,
+  HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+
+use polymesh_private_proof_shared::{error::Result, MaintenanceStatus};
+
+use crate::repo::TransactionRepository;
+
+pub type AppMaintenance = Data<Maintenance>;
+
+/// How often to check if in-flight requests have drained.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often to reload the maintenance mode from the database.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Maintenance mode.
+///
+/// While enabled, mutating requests (anything other than `GET`, `HEAD` and `OPTIONS`)
+/// are rejected with `503 Service Unavailable` and a `Retry-After` header.  Admin
+/// endpoints are still allowed, so maintenance mode can be disabled again.
+///
+/// The state is stored in the database, so the chain watcher can pause while it is enabled.
+/// It is reloaded every 5 seconds, so the other instances follow a change made on one.
+pub struct Maintenance {
+  enabled: AtomicBool,
+  retry_after: AtomicI64,
+  in_flight: AtomicUsize,
+  tx_repo: TransactionRepository,
+}
+
+impl Maintenance {
+  /// Load the current maintenance mode from the database.
+  pub async fn new_app_data(tx_repo: TransactionRepository) -> Result<AppMaintenance> {
+    let mode = tx_repo.get_maintenance_mode().await?;
+    Ok(Data::new(Self {
+      enabled: AtomicBool::new(mode.enabled),
+      retry_after: AtomicI64::new(mode.retry_after),
+      in_flight: AtomicUsize::new(0),
+      tx_repo,
+    }))
+  }
+
+  /// Reload the maintenance mode from the database.
+  pub async fn reload(&self) -> Result<()> {
+    let mode = self.tx_repo.get_maintenance_mode().await?;
+    self.retry_after.store(mode.retry_after, Ordering::SeqCst);
+    self.enabled.store(mode.enabled, Ordering::SeqCst);
+    Ok(())
+  }
+
+  /// Reload the maintenance mode periodically.
+  pub async fn run(&self) {
+    loop {
+      actix_web::rt::time::sleep(REFRESH_INTERVAL).await;
+      if let Err(err) = self.reload().await {
+        log::error!("Failed to reload the maintenance mode: {err:?}");
+      }
+    }
+  }
+
+  pub fn status(&self) -> MaintenanceStatus {
+    MaintenanceStatus {
+      enabled: self.enabled.load(Ordering::SeqCst),
+      retry_after: self.retry_after.load(Ordering::SeqCst),
+      in_flight: self.in_flight.load(Ordering::SeqCst),
+    }
+  }
+
+  /// Enable/disable maintenance mode.  When enabling, wait up to `drain_timeout` for
+  /// in-flight requests to finish.
+  pub async fn set(
+    &self,
+    enabled: bool,
+    retry_after: u32,
+    drain_timeout: Duration,
+  ) -> Result<MaintenanceStatus> {
+    let mode = self
+      .tx_repo
+      .set_maintenance_mode(enabled, retry_after)
+      .await?;
+    self.retry_after.store(mode.retry_after, Ordering::SeqCst);
+    self.enabled.store(mode.enabled, Ordering::SeqCst);
+    if enabled {
+      let start = Instant::now();
+      while self.in_flight.load(Ordering::SeqCst) > 0 && start.elapsed() < drain_timeout {
+        actix_web::rt::time::sleep(DRAIN_POLL_INTERVAL).await;
+      }
+    }
+    Ok(self.status())
+  }
+
+  fn is_blocked(&self, req: &ServiceRequest) -> bool {
+    if !self.enabled.load(Ordering::SeqCst) {
+      return false;
+    }
+    is_mutating(req) && !is_admin(req)
+  }
+
+  /// Middleware for `wrap_fn` that rejects mutating requests during maintenance
+  /// and tracks in-flight requests.
+  ///
+  /// Admin requests aren't tracked, so the request enabling maintenance mode doesn't wait
+  /// for itself to drain.
+  pub fn middleware<S, B>(
+    maintenance: AppMaintenance,
+    req: ServiceRequest,
+    srv: &S,
+  ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, actix_web::Error>>
+  where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+  {
+    if maintenance.is_blocked(&req) {
+      let retry_after = maintenance.retry_after.load(Ordering::SeqCst);
+      let res = HttpResponse::ServiceUnavailable()
+        .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+        .body("Service is in maintenance mode");
+      return Box::pin(async move { Ok(req.into_response(res).map_into_right_body()) });
+    }
+    if !is_mutating(&req) || is_admin(&req) {
+      let fut = srv.call(req);
+      return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+    }
+    let guard = InFlight::new(maintenance);
+    let fut = srv.call(req);
+    Box::pin(async move {
+      let res = fut.await;
+      drop(guard);
+      Ok(res?.map_into_left_body())
+    })
+  }
+}
+
+/// Tracks an in-flight request, even if it's future is dropped.
+struct InFlight(AppMaintenance);
+
+impl InFlight {
+  fn new(maintenance: AppMaintenance) -> Self {
+    maintenance.in_flight.fetch_add(1, Ordering::SeqCst);
+    Self(maintenance)
+  }
+}
+
+impl Drop for InFlight {
+  fn drop(&mut self) {
+    self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+  }
+}
+
+fn is_mutating(req: &ServiceRequest) -> bool {
+  !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn is_admin(req: &ServiceRequest) -> bool {
+  req.path().contains("/admin/")
+}
@@ -0,0 +1,242 @@
+use std::fmt::Write;
+
+use actix_web::{get, web, web::Data, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_api::integrity::AppSecretIntegrity;
+use polymesh_private_proof_shared::{
+  error::Error, AppDecryptionContext, DecryptionCache, ProvingThreads,
+};
+
+use crate::balance_drift::AppBalanceDrift;
+use crate::repo::TransactionRepository;
+
+pub type AppMetricsConfig = Data<MetricsConfig>;
+
+/// Default number of blocks before a pending settlement is stuck.
+const DEFAULT_STUCK_SETTLEMENT_BLOCKS: u32 = 600;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_metrics);
+}
+
+/// Alert thresholds of the metrics.
+pub struct MetricsConfig {
+  /// Settlements pending affirmation for more blocks than this are stuck.
+  stuck_settlement_blocks: u32,
+}
+
+impl MetricsConfig {
+  /// Load the thresholds from `STUCK_SETTLEMENT_BLOCKS`.
+  pub fn from_env() -> AppMetricsConfig {
+    let stuck_settlement_blocks = std::env::var("STUCK_SETTLEMENT_BLOCKS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_STUCK_SETTLEMENT_BLOCKS);
+    Data::new(Self {
+      stuck_settlement_blocks,
+    })
+  }
+}
+
+/// Prometheus text format metrics.
+#[derive(Default)]
+struct Metrics(String);
+
+impl Metrics {
+  fn metric(
+    &mut self,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl std::fmt::Display,
+  ) -> Result<(), Error> {
+    writeln!(self.0, "# HELP {name} {help}")
+      .and_then(|_| writeln!(self.0, "# TYPE {name} {kind}"))
+      .and_then(|_| writeln!(self.0, "{name} {value}"))
+      .map_err(|_| Error::other("Failed to format metrics"))
+  }
+
+  fn gauge(&mut self, name: &str, help: &str, value: impl std::fmt::Display) -> Result<(), Error> {
+    self.metric(name, "gauge", help, value)
+  }
+
+  fn counter(&mut self, name: &str, help: &str, value: u64) -> Result<(), Error> {
+    self.metric(name, "counter", help, value)
+  }
+}
+
+#[get("/metrics")]
+async fn get_metrics(
+  tx_repo: TransactionRepository,
+  config: AppMetricsConfig,
+  secret_integrity: AppSecretIntegrity,
+  balance_drift: AppBalanceDrift,
+  decryption_context: AppDecryptionContext,
+) -> Result<impl Responder> {
+  let mut metrics = Metrics::default();
+
+  // Chain watcher.
+  let status = tx_repo.get_watcher_status().await?;
+  metrics.gauge(
+    "watcher_head_block",
+    "Latest block seen by the chain watcher.",
+    status.head_block as u64,
+  )?;
+  metrics.gauge(
+    "watcher_processed_block",
+    "Latest block processed by the chain watcher.",
+    status.processed_block as u64,
+  )?;
+  metrics.gauge(
+    "watcher_blocks_behind",
+    "Number of blocks the chain watcher is behind the chain head.",
+    status.blocks_behind() as u64,
+  )?;
+  metrics.gauge(
+    "watcher_catching_up",
+    "Is the chain watcher catching up on missed blocks.",
+    status.catching_up as u64,
+  )?;
+  metrics.counter(
+    "watcher_reconnects_total",
+    "Number of times the chain watcher reconnected to the node.",
+    status.reconnects as u64,
+  )?;
+
+  // Settlements pending affirmation.
+  let stuck_before = status
+    .head_block
+    .saturating_sub(config.stuck_settlement_blocks);
+  let stuck = tx_repo
+    .count_pending_settlements_before(stuck_before)
+    .await?;
+  metrics.gauge(
+    "settlements_stuck",
+    "Settlements not executed or rejected, created more than `STUCK_SETTLEMENT_BLOCKS` ago.",
+    stuck,
+  )?;
+
+  // Webhook outbox.
+  let backlog = tx_repo.get_webhook_backlog().await?;
+  metrics.gauge(
+    "webhook_outbox_pending",
+    "Webhooks waiting for delivery.",
+    backlog.pending,
+  )?;
+  let now = chrono::Utc::now().naive_utc();
+  metrics.gauge(
+    "webhook_outbox_oldest_pending_seconds",
+    "Age of the oldest webhook waiting for delivery.",
+    backlog
+      .oldest_created_at
+      .map(|at| (now - at).num_seconds().max(0))
+      .unwrap_or_default(),
+  )?;
+
+  // Decryption cache of this process.
+  let cache = DecryptionCache::global().stats();
+  metrics.counter(
+    "decryption_cache_hits_total",
+    "Decryptions served from the decryption cache.",
+    cache.hits,
+  )?;
+  metrics.counter(
+    "decryption_cache_misses_total",
+    "Decryptions not found in the decryption cache.",
+    cache.misses,
+  )?;
+  metrics.gauge(
+    "decryption_cache_hit_ratio",
+    "Ratio of decryption cache hits to lookups.",
+    cache.hit_rate(),
+  )?;
+  metrics.gauge(
+    "decryption_cache_entries",
+    "Number of cached decrypted values.",
+    cache.entries,
+  )?;
+
+  // Decryption baby-step table of this process.
+  let context = decryption_context.stats();
+  metrics.gauge(
+    "decryption_table_ready",
+    "Is the decryption baby-step table built.",
+    context.ready as u64,
+  )?;
+  metrics.gauge(
+    "decryption_table_size",
+    "Number of baby steps in the decryption table.",
+    context.table_size,
+  )?;
+  metrics.counter(
+    "decryption_table_decryptions_total",
+    "Values decrypted with the decryption table.",
+    context.table_decryptions,
+  )?;
+  metrics.counter(
+    "decryption_fallback_decryptions_total",
+    "Values decrypted with a linear search, while the decryption table wasn't built.",
+    context.fallback_decryptions,
+  )?;
+
+  // Proving threads of this process.
+  let proving = ProvingThreads::global().stats();
+  metrics.gauge(
+    "proving_threads",
+    "Number of threads for generating and verifying proofs.",
+    proving.threads as u64,
+  )?;
+  metrics.gauge(
+    "proving_active",
+    "Proofs being generated or verified, or waiting for a proving thread.",
+    proving.active,
+  )?;
+
+  // Account secret integrity.
+  let report = secret_integrity.last_report();
+  metrics.gauge(
+    "secret_integrity_checked_accounts",
+    "Number of accounts checked by the last secret integrity check.",
+    report.checked,
+  )?;
+  metrics.gauge(
+    "secret_integrity_corrupt_accounts",
+    "Number of accounts with a corrupt secret found by the last secret integrity check.",
+    report.corrupt.len(),
+  )?;
+  metrics.gauge(
+    "secret_integrity_last_check_timestamp",
+    "Unix time of the last completed secret integrity check.",
+    report
+      .completed_at
+      .map(|at| at.timestamp())
+      .unwrap_or_default(),
+  )?;
+
+  // Local balances drifted from the chain.
+  let report = balance_drift.last_report();
+  metrics.gauge(
+    "balance_drift_checked_accounts",
+    "Number of accounts checked by the last balance drift check.",
+    report.checked,
+  )?;
+  metrics.gauge(
+    "balance_drift_accounts",
+    "Number of accounts with a local balance that doesn't match the chain.",
+    report.drifted,
+  )?;
+  metrics.gauge(
+    "balance_drift_last_check_timestamp",
+    "Unix time of the last completed balance drift check.",
+    report
+      .completed_at
+      .map(|at| at.timestamp())
+      .unwrap_or_default(),
+  )?;
+
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/plain; version=0.0.4")
+      .body(metrics.0),
+  )
+}
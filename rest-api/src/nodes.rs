@@ -0,0 +1,289 @@
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use actix_web::web::Data;
+use tokio::sync::watch;
+
+use polymesh_api::Api;
+
+use polymesh_private_proof_shared::{error::*, NodeStatus};
+
+pub type AppNodes = Data<NodeManager>;
+
+/// Node used when `POLYMESH_NODE_URL` isn't set.
+const DEFAULT_NODE_URL: &str = "ws://localhost:9944/";
+/// Default seconds between health checks.
+const DEFAULT_HEALTH_CHECK_SECS: u64 = 10;
+/// Default seconds before connecting to a node or a health check times out.
+const DEFAULT_NODE_TIMEOUT_SECS: u64 = 5;
+/// Default number of blocks a node can be behind the others before it is unhealthy.
+const DEFAULT_MAX_BLOCK_LAG: u32 = 10;
+
+struct Node {
+  /// Open connection, dropped when a health check fails.
+  api: Option<Api>,
+  status: NodeStatus,
+}
+
+/// Connections to the Polymesh nodes.
+///
+/// `POLYMESH_NODE_URL` is a comma separated list of nodes, in order of preference.  All
+/// nodes are health-checked every `NODE_HEALTH_CHECK_SECS` and requests use the first
+/// healthy node.  A node is unhealthy if its websocket dropped, it doesn't respond within
+/// `NODE_TIMEOUT_SECS` or its chain head is more than `NODE_MAX_BLOCK_LAG` blocks behind
+/// the other nodes.
+///
+/// When the current node is unhealthy, requests fail over to the next healthy node and
+/// move back once a preferred node is healthy again.  Block subscriptions (i.e. the chain
+/// watcher) are notified to resubscribe on the new node.
+pub struct NodeManager {
+  nodes: RwLock<Vec<Node>>,
+  current: RwLock<(usize, Api)>,
+  changed: watch::Sender<usize>,
+  check_interval: Duration,
+  timeout: Duration,
+  max_block_lag: u32,
+}
+
+impl NodeManager {
+  /// Load the config from `POLYMESH_NODE_URL`, `NODE_HEALTH_CHECK_SECS`,
+  /// `NODE_TIMEOUT_SECS` and `NODE_MAX_BLOCK_LAG`, then connect to the first reachable
+  /// node.
+  pub async fn from_env() -> Result<AppNodes> {
+    let urls = std::env::var("POLYMESH_NODE_URL").unwrap_or(DEFAULT_NODE_URL.to_string());
+    let env_u64 = |name: &str, default: u64| {
+      std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+    };
+    let check_interval = env_u64("NODE_HEALTH_CHECK_SECS", DEFAULT_HEALTH_CHECK_SECS).max(1);
+    let timeout = env_u64("NODE_TIMEOUT_SECS", DEFAULT_NODE_TIMEOUT_SECS).max(1);
+    let max_block_lag = env_u64("NODE_MAX_BLOCK_LAG", DEFAULT_MAX_BLOCK_LAG as u64) as u32;
+    Self::new(
+      parse_node_urls(&urls),
+      Duration::from_secs(check_interval),
+      Duration::from_secs(timeout),
+      max_block_lag,
+    )
+    .await
+  }
+
+  /// Connect to the first reachable node of `urls`.
+  pub async fn new(
+    urls: Vec<String>,
+    check_interval: Duration,
+    timeout: Duration,
+    max_block_lag: u32,
+  ) -> Result<AppNodes> {
+    let mut nodes = Vec::with_capacity(urls.len());
+    let mut current = None;
+    for url in urls {
+      let mut node = Node {
+        api: None,
+        status: NodeStatus {
+          url,
+          ..Default::default()
+        },
+      };
+      if current.is_none() {
+        match connect(&node.status.url, timeout).await {
+          Ok(api) => {
+            log::info!("Connected to Polymesh node {}", node.status.url);
+            node.api = Some(api.clone());
+            node.status.healthy = true;
+            current = Some((nodes.len(), api));
+          }
+          Err(err) => {
+            log::warn!(
+              "Failed to connect to Polymesh node {}: {err}",
+              node.status.url
+            );
+            node.status.last_error = Some(err.to_string());
+          }
+        }
+      }
+      nodes.push(node);
+    }
+    if nodes.is_empty() {
+      return Err(Error::other("No Polymesh node url"));
+    }
+    let current = current.ok_or_else(|| Error::other("Failed to connect to any Polymesh node"))?;
+    let (changed, _) = watch::channel(current.0);
+    Ok(Data::new(Self {
+      nodes: RwLock::new(nodes),
+      current: RwLock::new(current),
+      changed,
+      check_interval,
+      timeout,
+      max_block_lag,
+    }))
+  }
+
+  /// Connection to the current node.
+  pub fn api(&self) -> Api {
+    self.current.read().expect("Node lock poisoned").1.clone()
+  }
+
+  /// Url of the current node.
+  pub fn url(&self) -> String {
+    let idx = self.current_idx();
+    self.nodes.read().expect("Node lock poisoned")[idx]
+      .status
+      .url
+      .clone()
+  }
+
+  fn current_idx(&self) -> usize {
+    self.current.read().expect("Node lock poisoned").0
+  }
+
+  /// Get notified when requests move to another node.
+  pub fn subscribe(&self) -> watch::Receiver<usize> {
+    self.changed.subscribe()
+  }
+
+  /// Health of all nodes, in order of preference.
+  pub fn status(&self) -> Vec<NodeStatus> {
+    let current = self.current_idx();
+    self
+      .nodes
+      .read()
+      .expect("Node lock poisoned")
+      .iter()
+      .enumerate()
+      .map(|(idx, node)| NodeStatus {
+        current: idx == current,
+        ..node.status.clone()
+      })
+      .collect()
+  }
+
+  /// Health-check the nodes every `NODE_HEALTH_CHECK_SECS`.
+  pub async fn run(&self) {
+    loop {
+      actix_web::rt::time::sleep(self.check_interval).await;
+      self.check().await;
+    }
+  }
+
+  /// Health-check all nodes and switch to the first healthy node.
+  pub async fn check(&self) {
+    let count = self.nodes.read().expect("Node lock poisoned").len();
+    futures_util::future::join_all((0..count).map(|idx| self.check_node(idx))).await;
+
+    let mut nodes = self.nodes.write().expect("Node lock poisoned");
+    // Nodes that stopped following the chain.
+    let best_block = nodes
+      .iter()
+      .filter_map(|node| node.status.best_block.filter(|_| node.status.healthy))
+      .max()
+      .unwrap_or_default();
+    for node in nodes.iter_mut().filter(|node| node.status.healthy) {
+      let block = node.status.best_block.unwrap_or_default();
+      if best_block.saturating_sub(block) > self.max_block_lag {
+        log::warn!(
+          "Polymesh node {} is lagging: block {block}, best block {best_block}",
+          node.status.url
+        );
+        node.status.healthy = false;
+        node.status.last_error = Some(format!("Lagging at block {block}"));
+      }
+    }
+
+    let current = self.current_idx();
+    let next = nodes
+      .iter()
+      .enumerate()
+      .find_map(|(idx, node)| match &node.api {
+        Some(api) if node.status.healthy => Some((idx, api.clone())),
+        _ => None,
+      });
+    match next {
+      Some((idx, api)) if idx != current => {
+        let from = &nodes[current].status;
+        let to = &nodes[idx].status.url;
+        if from.healthy {
+          log::info!("Moving back to preferred Polymesh node {to}");
+        } else {
+          log::warn!("Failing over from Polymesh node {} to {to}", from.url);
+        }
+        *self.current.write().expect("Node lock poisoned") = (idx, api);
+        self.changed.send_replace(idx);
+      }
+      Some(_) => (),
+      None => {
+        log::error!(
+          "No healthy Polymesh node, still using {}",
+          nodes[current].status.url
+        );
+      }
+    }
+  }
+
+  /// Check that the node responds, connecting to it if needed.
+  async fn check_node(&self, idx: usize) {
+    let (url, api) = {
+      let nodes = self.nodes.read().expect("Node lock poisoned");
+      (nodes[idx].status.url.clone(), nodes[idx].api.clone())
+    };
+    let started = Instant::now();
+    let res = async {
+      let api = match api {
+        Some(api) => api,
+        None => connect(&url, self.timeout).await?,
+      };
+      let head =
+        match actix_web::rt::time::timeout(self.timeout, api.client().get_block_header(None)).await
+        {
+          Ok(head) => head.map_err(|err| Error::from(err))?,
+          Err(_) => Err(Error::other("Timed out getting the chain head"))?,
+        };
+      let head = head.ok_or_else(|| Error::other("Missing chain head"))?;
+      Ok::<_, Error>((api, head.number))
+    }
+    .await;
+
+    let mut nodes = self.nodes.write().expect("Node lock poisoned");
+    let node = &mut nodes[idx];
+    node.status.checked_at = Some(chrono::Utc::now().naive_utc());
+    match res {
+      Ok((api, best_block)) => {
+        if !node.status.healthy {
+          log::info!("Polymesh node {url} is healthy");
+        }
+        node.api = Some(api);
+        node.status.healthy = true;
+        node.status.best_block = Some(best_block);
+        node.status.latency_ms = Some(started.elapsed().as_millis() as u64);
+        node.status.last_error = None;
+      }
+      Err(err) => {
+        if node.status.healthy {
+          log::warn!("Polymesh node {url} is unhealthy: {err}");
+        }
+        node.api = None;
+        node.status.healthy = false;
+        node.status.latency_ms = None;
+        node.status.last_error = Some(err.to_string());
+      }
+    }
+  }
+}
+
+/// Split a comma separated list of node urls.
+pub fn parse_node_urls(urls: &str) -> Vec<String> {
+  urls
+    .split(',')
+    .map(|url| url.trim())
+    .filter(|url| !url.is_empty())
+    .map(|url| url.to_string())
+    .collect()
+}
+
+async fn connect(url: &str, timeout: Duration) -> Result<Api> {
+  match actix_web::rt::time::timeout(timeout, Api::new(url)).await {
+    Ok(api) => api.map_err(|err| Error::from(err)),
+    Err(_) => Err(Error::other("Timed out connecting to the node")),
+  }
+}
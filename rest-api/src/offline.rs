@@ -0,0 +1,158 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use codec::Decode;
+
+use polymesh_api::client::{AccountId, Error as ClientError, Signer};
+use polymesh_api::types::{
+  pallet_confidential_asset::TransactionId, polymesh_primitives::settlement::VenueId,
+};
+use polymesh_api::{Api, WrappedCall};
+use sp_runtime::MultiSignature;
+
+use polymesh_private_proof_shared::{error::*, join_auditors, OfflineCall};
+
+use crate::signing::next_nonce;
+
+/// Build the extrinsic call.
+pub fn build_call(api: &Api, call: &OfflineCall) -> Result<WrappedCall> {
+  let call_api = api.call();
+  let tx = call_api.confidential_asset();
+  let call = match call {
+    OfflineCall::CreateVenue => tx.create_venue(),
+    OfflineCall::CreateAsset {
+      mediators,
+      auditors,
+    } => tx.create_asset(vec![], join_auditors(mediators, auditors)?),
+    OfflineCall::AllowVenues { asset_id, venues } => {
+      let venues = venues.iter().map(|id| VenueId(*id)).collect();
+      tx.allow_venues(*asset_id.as_bytes(), venues)
+    }
+    OfflineCall::CreateAccount {
+      confidential_account,
+    } => tx.create_account(confidential_account.as_confidential_account()?),
+    OfflineCall::ApplyIncomingBalance {
+      confidential_account,
+      asset_id,
+    } => tx.apply_incoming_balance(
+      confidential_account.as_confidential_account()?,
+      *asset_id.as_bytes(),
+    ),
+    OfflineCall::Mint {
+      confidential_account,
+      asset_id,
+      amount,
+    } => tx.mint(
+      *asset_id.as_bytes(),
+      *amount as _,
+      confidential_account.as_confidential_account()?,
+    ),
+    OfflineCall::ExecuteTransaction {
+      transaction_id,
+      leg_count,
+    } => tx.execute_transaction(TransactionId(*transaction_id), *leg_count),
+  };
+  call.map_err(|err| Error::from(err))
+}
+
+/// Build the signing payload of the call, without submitting it.
+pub async fn build_payload(
+  api: &Api,
+  account: AccountId,
+  call: &OfflineCall,
+) -> Result<(u32, Vec<u8>)> {
+  let nonce = next_nonce(api, &account).await?;
+  let mut signer = PayloadSigner::new(account, nonce);
+  // The signer refuses to sign, so the transaction is never submitted.
+  let res = build_call(api, call)?.submit_and_watch(&mut signer).await;
+  match signer.payload.into_inner().ok().flatten() {
+    Some(payload) => Ok((nonce, payload)),
+    None => match res {
+      Err(err) => Err(Error::from(err)),
+      Ok(_) => Err(Error::other("Failed to build the signing payload")),
+    },
+  }
+}
+
+/// Signer that captures the signing payload and fails the submission.
+struct PayloadSigner {
+  account: AccountId,
+  nonce: u32,
+  payload: Mutex<Option<Vec<u8>>>,
+}
+
+impl PayloadSigner {
+  fn new(account: AccountId, nonce: u32) -> Self {
+    Self {
+      account,
+      nonce,
+      payload: Mutex::new(None),
+    }
+  }
+}
+
+#[async_trait]
+impl Signer for PayloadSigner {
+  fn account(&self) -> AccountId {
+    self.account.clone()
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    Some(self.nonce)
+  }
+
+  async fn set_nonce(&mut self, _nonce: u32) {}
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature, ClientError> {
+    if let Ok(mut payload) = self.payload.lock() {
+      *payload = Some(msg.to_vec());
+    }
+    Err(ClientError::SigningTransactionFailed(
+      "Offline transaction, not signing".into(),
+    ))
+  }
+}
+
+/// Signer with a signature produced by an offline signer.
+pub struct PresignedSigner {
+  account: AccountId,
+  nonce: u32,
+  payload: Vec<u8>,
+  signature: MultiSignature,
+}
+
+impl PresignedSigner {
+  pub fn new(account: AccountId, nonce: u32, payload: Vec<u8>, signature: &[u8]) -> Result<Self> {
+    let signature = MultiSignature::decode(&mut &signature[..])
+      .map_err(|_| Error::other("Invalid signature, expected a SCALE encoded MultiSignature"))?;
+    Ok(Self {
+      account,
+      nonce,
+      payload,
+      signature,
+    })
+  }
+}
+
+#[async_trait]
+impl Signer for PresignedSigner {
+  fn account(&self) -> AccountId {
+    self.account.clone()
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    Some(self.nonce)
+  }
+
+  async fn set_nonce(&mut self, _nonce: u32) {}
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature, ClientError> {
+    // The payload changes with the runtime version or genesis hash.
+    if msg != self.payload.as_slice() {
+      return Err(ClientError::SigningTransactionFailed(
+        "The signing payload changed, build the transaction again".into(),
+      ));
+    }
+    Ok(self.signature.clone())
+  }
+}
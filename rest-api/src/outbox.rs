@@ -0,0 +1,388 @@
+use std::time::Duration;
+
+use actix_web::web::Data;
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use sp_runtime::MultiSignature;
+
+use polymesh_api::client::{AccountId, Error as ClientError, Signer};
+use polymesh_api::types::runtime::RuntimeCall;
+use polymesh_api::{TransactionResults, WrappedCall};
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  TransactionResult, TxOutboxEntry, TxOutboxRow,
+};
+
+use crate::budgets::AppSignerBudgets;
+use crate::nodes::AppNodes;
+use crate::repo::TransactionRepository;
+use crate::signing::{next_nonce, AppSigningManager, TxSigner};
+
+pub type AppTxOutbox = Data<TxOutbox>;
+
+/// Seconds between checks for due retries.
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Seconds before a submitted transaction orphaned by a restart is failed, if it wasn't
+/// found on-chain.
+const RECOVERY_TIMEOUT: i64 = 3600;
+
+/// The transaction pool rejected the transaction for now.  It didn't reach the pool, so it
+/// is safe to sign it again with a new nonce.
+const REJECTED_ERRORS: &[&str] = &[
+  "Priority is too low",
+  "Immediately Dropped",
+  "temporarily banned",
+  "Transaction is outdated",
+];
+
+/// The node is unreachable.  The transaction may still have reached the pool, so the retries
+/// are signed with the same nonce.
+const TRANSPORT_ERRORS: &[&str] = &[
+  "connection",
+  "Connection",
+  "background task",
+  "restart",
+  "Networking",
+  "timed out",
+  "Timeout",
+];
+
+/// Result of submitting a transaction through the outbox.
+pub enum TxSubmission {
+  Submitted(TransactionResults),
+  Queued(TxOutboxEntry),
+}
+
+/// Outbox of transactions that failed to submit.
+///
+/// When the node can't accept a transaction (node down, priority too low), the encoded
+/// call is stored and the endpoint returns the outbox entry (`202 Accepted`).  The call is
+/// signed again by the same signer and resubmitted with exponential backoff, starting at
+/// `TX_OUTBOX_RETRY_SECS` (default 5) up to `TX_OUTBOX_MAX_RETRY_SECS` (default 600), for
+/// `TX_OUTBOX_MAX_ATTEMPTS` attempts (default 10, `0` disables the outbox).  Invalid
+/// transactions (i.e. a bad proof) fail right away.
+///
+/// A transaction rejected by the pool is signed with a new nonce.  After a transport error
+/// the transaction may have reached the node, so its nonce is kept and the retries are only
+/// submitted, with the same nonce, while the chain's next nonce (including the pool) hasn't
+/// passed it.  Otherwise the entry fails, so a call is never executed twice.
+///
+/// The endpoint's own processing of the results (i.e. updating tracked balances) is
+/// skipped for retried transactions, the chain watcher still records them.
+pub struct TxOutbox {
+  nodes: AppNodes,
+  tx_repo: TransactionRepository,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  max_attempts: u32,
+  retry_secs: u32,
+  max_retry_secs: u32,
+  started_at: chrono::NaiveDateTime,
+}
+
+impl TxOutbox {
+  /// Load the config from `TX_OUTBOX_MAX_ATTEMPTS`, `TX_OUTBOX_RETRY_SECS` and
+  /// `TX_OUTBOX_MAX_RETRY_SECS`.
+  pub fn from_env(
+    nodes: AppNodes,
+    tx_repo: TransactionRepository,
+    signing: AppSigningManager,
+    budgets: AppSignerBudgets,
+  ) -> AppTxOutbox {
+    let env_u32 = |name: &str, default: u32| {
+      std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+    };
+    Data::new(Self {
+      nodes,
+      tx_repo,
+      signing,
+      budgets,
+      max_attempts: env_u32("TX_OUTBOX_MAX_ATTEMPTS", 10),
+      retry_secs: env_u32("TX_OUTBOX_RETRY_SECS", 5).max(1),
+      max_retry_secs: env_u32("TX_OUTBOX_MAX_RETRY_SECS", 600),
+      started_at: chrono::Utc::now().naive_utc(),
+    })
+  }
+
+  /// Seconds to wait before the next attempt, after `attempts` failed attempts.
+  fn backoff(&self, attempts: u32) -> u32 {
+    let factor = 1u32 << attempts.saturating_sub(1).min(16);
+    self
+      .retry_secs
+      .saturating_mul(factor)
+      .min(self.max_retry_secs)
+  }
+
+  /// Submit the call, or store it in the outbox if the node couldn't accept it.
+  pub async fn submit(
+    &self,
+    operation: &str,
+    signer_name: &str,
+    extrinsic: &str,
+    signer: &mut TxSigner,
+    call: WrappedCall,
+    finalize: bool,
+  ) -> Result<TxSubmission> {
+    let call: RuntimeCall = call.into();
+    let encoded = call.encode();
+    let nonce = self.signer_nonce(signer).await?;
+    let err = match self
+      .nodes
+      .api()
+      .wrap_call(call)
+      .submit_and_watch(signer)
+      .await
+    {
+      Ok(res) => return Ok(TxSubmission::Submitted(res)),
+      Err(err) => Error::from(err),
+    };
+    if self.max_attempts == 0 {
+      return Err(err);
+    }
+    let nonce = match classify(&err) {
+      Some(SubmitError::Rejected) => None,
+      Some(SubmitError::Transport) => Some(nonce),
+      None => return Err(err),
+    };
+    let err_msg = err.to_string();
+    log::warn!("Failed to submit {operation}, added to the outbox: {err_msg}");
+    let row = self
+      .tx_repo
+      .add_tx_outbox_entry(
+        operation,
+        signer_name,
+        extrinsic,
+        &encoded,
+        finalize,
+        &err_msg,
+        nonce,
+        self.backoff(1),
+      )
+      .await?;
+    Ok(TxSubmission::Queued(TxOutboxEntry::from_row(row)?))
+  }
+
+  /// Retry the due transactions and recover the ones orphaned by a restart.
+  pub async fn run(&self) {
+    loop {
+      if let Err(err) = self.retry_due().await {
+        log::error!("Failed to process the transaction outbox: {err:?}");
+      }
+      if let Err(err) = self.recover_submitted().await {
+        log::error!("Failed to recover outbox transactions: {err:?}");
+      }
+      actix_web::rt::time::sleep(OUTBOX_POLL_INTERVAL).await;
+    }
+  }
+
+  async fn retry_due(&self) -> Result<()> {
+    for entry in self.tx_repo.get_due_tx_outbox_entries().await? {
+      if let Err(err) = self.retry(&entry).await {
+        let outbox_id = entry.outbox_id;
+        log::warn!("Outbox transaction {outbox_id} failed: {err:?}");
+        self
+          .tx_repo
+          .tx_outbox_failed(outbox_id, &err.to_string())
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// The nonce the signer will sign the next transaction with.
+  async fn signer_nonce(&self, signer: &mut TxSigner) -> Result<u32> {
+    if let Some(nonce) = signer.nonce().await {
+      return Ok(nonce);
+    }
+    let nonce = next_nonce(&self.nodes.api(), &signer.account()).await?;
+    signer.set_nonce(nonce).await;
+    Ok(nonce)
+  }
+
+  /// Sign and submit the call again.  Fails if the call can't be submitted or it was the
+  /// last attempt.
+  async fn retry(&self, entry: &TxOutboxRow) -> Result<()> {
+    let mut signer = self
+      .signing
+      .get_tx_signer(&entry.signer, &entry.extrinsic)
+      .await?
+      .ok_or_else(|| Error::not_found("Signer"))?;
+    let budget = self.budgets.check(&signer).await?;
+    let pinned = entry.nonce.map(|nonce| nonce as u32);
+    let nonce = match pinned {
+      Some(nonce) => {
+        // The transaction may be in the pool or on-chain.
+        let next = match next_nonce(&self.nodes.api(), &signer.account()).await {
+          Ok(next) => next,
+          Err(err) => return self.retry_later(entry, err, None).await,
+        };
+        if next > nonce {
+          return Err(Error::Other(format!(
+            "Nonce {nonce} was used, the transaction may have been submitted before. \
+             Check the chain before submitting it again"
+          )));
+        }
+        signer = Box::new(PinnedNonceSigner {
+          inner: signer,
+          nonce,
+        });
+        nonce
+      }
+      None => self.signer_nonce(&mut signer).await?,
+    };
+    let call = RuntimeCall::decode(&mut entry.call.as_slice())?;
+    let res = match self
+      .nodes
+      .api()
+      .wrap_call(call)
+      .submit_and_watch(&mut signer)
+      .await
+    {
+      Ok(res) => res,
+      Err(err) => {
+        let err = Error::from(err);
+        return match classify(&err) {
+          Some(SubmitError::Transport) => self.retry_later(entry, err, Some(nonce)).await,
+          // Nothing reached the pool, unless the pinned nonce is taken.
+          Some(SubmitError::Rejected) if pinned.is_none() => {
+            self.retry_later(entry, err, None).await
+          }
+          _ => Err(err),
+        };
+      }
+    };
+    let tx_hash = format!("{:#x}", res.hash());
+    log::info!(
+      "Outbox transaction {} submitted: {tx_hash}",
+      entry.outbox_id
+    );
+    self
+      .tx_repo
+      .tx_outbox_submitted(entry.outbox_id, &tx_hash)
+      .await?;
+
+    // Wait for the results in the background.
+    let tx_repo = self.tx_repo.clone();
+    let budgets = self.budgets.clone();
+    let outbox_id = entry.outbox_id;
+    let finalize = entry.finalize;
+    actix_web::rt::spawn(async move {
+      let res = match TransactionResult::wait_for_results(res, finalize).await {
+        Ok(res) => {
+          budgets.record(budget, &res).await;
+          tx_repo.tx_outbox_completed(outbox_id, &res).await
+        }
+        Err(err) => tx_repo.tx_outbox_failed(outbox_id, &err.to_string()).await,
+      };
+      if let Err(err) = res {
+        log::error!("Failed to update outbox transaction {outbox_id}: {err:?}");
+      }
+    });
+    Ok(())
+  }
+
+  /// Schedule the next attempt, pinned to `nonce` if set.  Fails if it was the last
+  /// attempt.
+  async fn retry_later(&self, entry: &TxOutboxRow, err: Error, nonce: Option<u32>) -> Result<()> {
+    let attempts = entry.attempts as u32 + 1;
+    if attempts >= self.max_attempts {
+      return Err(err);
+    }
+    self
+      .tx_repo
+      .tx_outbox_retry(
+        entry.outbox_id,
+        &err.to_string(),
+        nonce,
+        self.backoff(attempts),
+      )
+      .await
+  }
+
+  /// Complete the submitted transactions orphaned by a restart from the transactions
+  /// recorded by the chain watcher.
+  async fn recover_submitted(&self) -> Result<()> {
+    let now = chrono::Utc::now().naive_utc();
+    let entries = self
+      .tx_repo
+      .get_tx_outbox_entries(Some("submitted".to_string()))
+      .await?;
+    for entry in entries {
+      // Submitted since the start, still tracked.
+      if entry.updated_at >= self.started_at {
+        continue;
+      }
+      let tx_hash = entry.tx_hash.as_deref().unwrap_or_default();
+      match self.tx_repo.get_block_transaction(tx_hash).await? {
+        Some(rec) => {
+          log::info!("Recovered outbox transaction {}", entry.outbox_id);
+          let res = rec.to_tx_result()?;
+          self
+            .tx_repo
+            .tx_outbox_completed(entry.outbox_id, &res)
+            .await?;
+        }
+        None if (now - entry.updated_at).num_seconds() > RECOVERY_TIMEOUT => {
+          self
+            .tx_repo
+            .tx_outbox_failed(entry.outbox_id, "Transaction not found on-chain")
+            .await?;
+        }
+        None => (),
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Kind of a retryable submission error.
+enum SubmitError {
+  Rejected,
+  Transport,
+}
+
+fn classify(err: &Error) -> Option<SubmitError> {
+  let msg = format!("{err:?}");
+  if REJECTED_ERRORS
+    .iter()
+    .any(|rejected| msg.contains(rejected))
+  {
+    Some(SubmitError::Rejected)
+  } else if TRANSPORT_ERRORS
+    .iter()
+    .any(|transport| msg.contains(transport))
+  {
+    Some(SubmitError::Transport)
+  } else {
+    None
+  }
+}
+
+/// Signs a retry with the nonce of the earlier attempt.
+struct PinnedNonceSigner {
+  inner: TxSigner,
+  nonce: u32,
+}
+
+#[async_trait]
+impl Signer for PinnedNonceSigner {
+  fn account(&self) -> AccountId {
+    self.inner.account()
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    Some(self.nonce)
+  }
+
+  async fn set_nonce(&mut self, nonce: u32) {
+    self.inner.set_nonce(nonce).await
+  }
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature, ClientError> {
+    self.inner.sign(msg).await
+  }
+}
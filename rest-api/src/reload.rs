@@ -0,0 +1,113 @@
+use std::sync::{Arc, RwLock};
+
+use actix_cors::Cors;
+use actix_web::web::Data;
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::EnvConfig;
+
+use crate::signing::{self, ReloadableSigningManager};
+
+pub type AppReloader = Data<Reloader>;
+
+/// Allowed CORS origins, from the comma separated `CORS_ALLOWED_ORIGINS` config var.
+///
+/// All origins are allowed if it isn't set.
+#[derive(Default)]
+pub struct CorsOrigins {
+  origins: RwLock<Option<Vec<String>>>,
+}
+
+impl CorsOrigins {
+  pub fn from_env(config: &EnvConfig) -> Arc<Self> {
+    let cors = Arc::new(Self::default());
+    cors.reload(config);
+    cors
+  }
+
+  pub fn reload(&self, config: &EnvConfig) {
+    let origins = config.var("CORS_ALLOWED_ORIGINS").ok().map(|origins| {
+      origins
+        .split(',')
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect()
+    });
+    *self.origins.write().expect("CORS origins lock poisoned") = origins;
+  }
+
+  pub fn is_allowed(&self, origin: &[u8]) -> bool {
+    match &*self.origins.read().expect("CORS origins lock poisoned") {
+      Some(origins) => origins.iter().any(|allowed| allowed.as_bytes() == origin),
+      None => true,
+    }
+  }
+
+  /// Build a CORS policy that checks the current allowed origins.
+  pub fn cors(self: &Arc<Self>) -> Cors {
+    let origins = self.clone();
+    Cors::default()
+      .allowed_origin_fn(move |origin, _| origins.is_allowed(origin.as_bytes()))
+      .allow_any_method()
+      .allow_any_header()
+      .expose_any_header()
+      .supports_credentials()
+      .max_age(3600)
+  }
+}
+
+/// Reloads the config without restarting the server.
+///
+/// The config is the environment the server was started with, plus the values of the
+/// `.env` file that it doesn't set.  The `.env` file is read again, so values removed from
+/// it are unset.  The process environment isn't changed.
+///
+/// Rebuilds the signing manager (including the Vault client) and the CORS policy, and
+/// reloads the repository's balance conflict strategy.
+pub struct Reloader {
+  pool: sqlx::SqlitePool,
+  env: EnvConfig,
+  signing: Arc<ReloadableSigningManager>,
+  cors: Arc<CorsOrigins>,
+  repo: Repository,
+}
+
+impl Reloader {
+  /// `env` is the environment without the `.env` file.
+  pub fn new_app_data(
+    pool: &sqlx::SqlitePool,
+    env: EnvConfig,
+    signing: Arc<ReloadableSigningManager>,
+    cors: Arc<CorsOrigins>,
+    repo: Repository,
+  ) -> AppReloader {
+    Data::new(Self {
+      pool: pool.clone(),
+      env,
+      signing,
+      cors,
+      repo,
+    })
+  }
+
+  /// The environment with the values of the `.env` file that it doesn't set.
+  pub fn load_config(env: &EnvConfig) -> anyhow::Result<EnvConfig> {
+    let vars = match dotenv::dotenv_iter() {
+      Ok(vars) => vars.collect::<Result<Vec<_>, _>>()?,
+      // No `.env` file.
+      Err(_) => Vec::new(),
+    };
+    Ok(env.clone().with_defaults(vars))
+  }
+
+  pub fn reload(&self) -> anyhow::Result<()> {
+    let config = Self::load_config(&self.env)?;
+    // Only apply the config if it is valid.
+    let manager = signing::signing_manager_from_env(&self.pool, &config)?;
+    self.repo.reload_config(&config)?;
+    self.signing.replace(manager);
+    self.cors.reload(&config);
+    log::info!("Config reloaded");
+    Ok(())
+  }
+}
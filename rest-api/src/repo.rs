@@ -2,8 +2,16 @@ use actix_web::web::Data;
 
 use async_trait::async_trait;
 use polymesh_private_proof_shared::{
-  error::Result, BlockTransactionRecord, SettlementEventRecord, SettlementRecord,
+  error::Result, AccountSigner, AddDeposit, AuditReportRequest, AutoApplyAccount,
+  BlockTransactionRecord, Contact, CreateAccountSigner, CreateAutoApplyAccount, CreateContact,
+  CreateDepositAccount, CreateInvoice, CreateSessionSigner, CreateWalletPairing, Deposit,
+  DepositAccount, Invoice, Lease, LedgerEntry, MaintenanceMode, OfflineCall, ScheduledJob,
+  SessionSigner, SetSignerBudget, SettlementAnnotation, SettlementEventRecord, SettlementLeg,
+  SettlementLegFilter, SettlementRecord, SignerBudget, SignerUsage, TransactionResult,
+  TrialBalance, TxJobRow, TxOutboxRow, UnsignedTransaction, UpdateContact, Venue, WalletPairing,
+  WalletSigningRequest, WatcherStatus, WebhookBacklog, WebhookEndpoint, WebhookOutboxRecord,
 };
+use uuid::Uuid;
 
 mod sqlite;
 
@@ -16,15 +24,341 @@ pub type TransactionRepository = Data<dyn TransactionRepositoryTrait>;
 pub trait TransactionRepositoryTrait: Send + Sync + 'static {
   // Block transactions.
   async fn get_block_transactions(&self) -> Result<Vec<BlockTransactionRecord>>;
-  async fn get_block_transaction(&self, tx_hash: &[u8]) -> Result<Option<BlockTransactionRecord>>;
+  async fn get_block_transaction(&self, tx_hash: &str) -> Result<Option<BlockTransactionRecord>>;
+  /// Transactions of blocks `from` to `to` (inclusive), ordered by block.
+  async fn get_block_transactions_range(
+    &self,
+    from: u32,
+    to: u32,
+  ) -> Result<Vec<BlockTransactionRecord>>;
+  /// Up to `limit` transactions saved after the row `after_id`, ordered by row id.
+  /// Returns `(row id, transaction)`.
+  async fn get_block_transactions_after(
+    &self,
+    after_id: i64,
+    limit: u32,
+  ) -> Result<Vec<(i64, BlockTransactionRecord)>>;
+  /// Row id of the last saved transaction, `0` if there are none.
+  async fn get_last_block_transaction_id(&self) -> Result<i64>;
   async fn add_block_transaction(&self, rec: BlockTransactionRecord) -> Result<()>;
 
   // Settlements.
   async fn get_settlements(&self) -> Result<Vec<SettlementRecord>>;
   async fn get_settlement(&self, settlement_id: i64) -> Result<Option<SettlementRecord>>;
-  async fn add_settlement(&self, rec: SettlementRecord) -> Result<()>;
+  /// Settlements that haven't been executed or rejected.
+  async fn get_pending_settlements(&self) -> Result<Vec<SettlementRecord>>;
+  /// Number of settlements that haven't been executed or rejected, created before `block_number`.
+  async fn count_pending_settlements_before(&self, block_number: u32) -> Result<i64>;
+  async fn add_settlement(&self, rec: SettlementRecord, legs: &[SettlementLeg]) -> Result<()>;
+
+  // Settlement legs.
+  async fn get_settlement_legs(&self, filter: &SettlementLegFilter) -> Result<Vec<SettlementLeg>>;
 
   // Settlement Events.
   async fn get_settlement_events(&self, settlement_id: i64) -> Result<Vec<SettlementEventRecord>>;
   async fn add_settlement_event(&self, rec: SettlementEventRecord) -> Result<()>;
+  /// Leg affirmations of settlements with `asset_id`, in the report's range.
+  async fn get_asset_affirmations(
+    &self,
+    asset_id: Uuid,
+    range: &AuditReportRequest,
+  ) -> Result<Vec<SettlementEventRecord>>;
+
+  // Settlement annotations.
+  async fn get_settlement_annotations(
+    &self,
+    settlement_id: i64,
+  ) -> Result<Vec<SettlementAnnotation>>;
+  /// Latest annotation of each annotated settlement.
+  async fn get_latest_settlement_annotations(&self) -> Result<Vec<SettlementAnnotation>>;
+  async fn add_settlement_annotation(
+    &self,
+    settlement_id: i64,
+    status: &str,
+    notes: Option<&str>,
+    author: &str,
+  ) -> Result<SettlementAnnotation>;
+
+  // Contacts.
+  async fn get_contacts(&self) -> Result<Vec<Contact>>;
+  async fn get_contact(&self, confidential_account: &str) -> Result<Option<Contact>>;
+  /// Returns `None` if the account already has a contact.
+  async fn create_contact(&self, contact: &CreateContact) -> Result<Option<Contact>>;
+  async fn update_contact(
+    &self,
+    confidential_account: &str,
+    contact: &UpdateContact,
+  ) -> Result<Option<Contact>>;
+  async fn delete_contact(&self, confidential_account: &str) -> Result<bool>;
+
+  // Venues.
+  async fn get_venues(&self) -> Result<Vec<Venue>>;
+  async fn get_venue(&self, venue_id: i64) -> Result<Option<Venue>>;
+  async fn add_venue(&self, venue_id: i64, signer: &str, label: Option<&str>) -> Result<()>;
+  /// Returns `None` if the venue isn't in the local registry.
+  async fn set_venue_auto_execute(
+    &self,
+    venue_id: i64,
+    auto_execute: bool,
+  ) -> Result<Option<Venue>>;
+
+  // Unsigned transactions (offline signing).
+  async fn get_unsigned_transaction(&self, build_id: i64) -> Result<Option<UnsignedTransaction>>;
+  async fn add_unsigned_transaction(
+    &self,
+    account: &str,
+    call: &OfflineCall,
+    nonce: u32,
+    payload: &[u8],
+  ) -> Result<UnsignedTransaction>;
+  async fn set_unsigned_transaction_submitted(&self, build_id: i64, tx_hash: &str) -> Result<()>;
+
+  // Wallet pairings.
+  async fn get_wallet_pairings(&self) -> Result<Vec<WalletPairing>>;
+  async fn get_wallet_pairing(&self, pairing_id: i64) -> Result<Option<WalletPairing>>;
+  async fn create_wallet_pairing(
+    &self,
+    pairing: &CreateWalletPairing,
+    secret_hash: &[u8],
+  ) -> Result<WalletPairing>;
+  /// Activate a pending pairing if the secret matches.
+  async fn connect_wallet_pairing(
+    &self,
+    pairing_id: i64,
+    secret_hash: &[u8],
+    session_hash: &[u8],
+    callback_url: Option<&str>,
+  ) -> Result<Option<WalletPairing>>;
+  /// The active pairing of a wallet session.
+  async fn get_wallet_session(&self, session_hash: &[u8]) -> Result<Option<WalletPairing>>;
+  /// Revoke a pairing and reject its pending signing requests.
+  async fn revoke_wallet_pairing(&self, pairing_id: i64) -> Result<Option<WalletPairing>>;
+
+  // Wallet signing requests.
+  async fn get_wallet_signing_requests(
+    &self,
+    pairing_id: Option<i64>,
+    status: Option<&str>,
+  ) -> Result<Vec<WalletSigningRequest>>;
+  async fn get_wallet_signing_request(
+    &self,
+    request_id: i64,
+  ) -> Result<Option<WalletSigningRequest>>;
+  async fn create_wallet_signing_request(
+    &self,
+    pairing_id: i64,
+    build_id: i64,
+    summary: &str,
+    expires_in: u64,
+  ) -> Result<WalletSigningRequest>;
+  /// Approve or reject a pending, unexpired signing request of the pairing.
+  async fn decide_wallet_signing_request(
+    &self,
+    request_id: i64,
+    pairing_id: i64,
+    status: &str,
+    error: Option<&str>,
+  ) -> Result<Option<WalletSigningRequest>>;
+  /// Mark an approved signing request as failed (i.e. the submission failed).
+  async fn wallet_signing_request_failed(&self, request_id: i64, error: &str) -> Result<()>;
+  /// Expire pending signing requests.  Returns the number of expired requests.
+  async fn expire_wallet_signing_requests(&self) -> Result<u64>;
+
+  // Invoices.
+  async fn get_invoices(&self, receiver: Option<&str>) -> Result<Vec<Invoice>>;
+  async fn get_invoice(&self, reference: &str) -> Result<Option<Invoice>>;
+  async fn create_invoice(
+    &self,
+    reference: &str,
+    invoice: &CreateInvoice,
+    expires_in: u64,
+  ) -> Result<Invoice>;
+  /// Link an open invoice to the settlement paying it.
+  async fn set_invoice_settlement(
+    &self,
+    reference: &str,
+    settlement_id: i64,
+  ) -> Result<Option<Invoice>>;
+  /// Mark the invoice paid by the settlement as `paid` if executed, or `open` if rejected.
+  async fn settle_invoice(&self, settlement_id: i64, executed: bool) -> Result<Option<Invoice>>;
+  /// Cancel an open invoice.
+  async fn cancel_invoice(&self, reference: &str) -> Result<Option<Invoice>>;
+
+  // Deposits.
+  async fn get_deposit_accounts(&self) -> Result<Vec<DepositAccount>>;
+  async fn get_deposit_account(&self, confidential_account: &str)
+    -> Result<Option<DepositAccount>>;
+  async fn create_deposit_account(&self, req: &CreateDepositAccount) -> Result<DepositAccount>;
+  async fn delete_deposit_account(&self, confidential_account: &str) -> Result<bool>;
+  async fn get_deposits(
+    &self,
+    confidential_account: Option<&str>,
+    reference: Option<&str>,
+  ) -> Result<Vec<Deposit>>;
+  /// Add a deposit.  Returns `None` if the leg's asset was already credited.
+  async fn add_deposit(&self, deposit: &AddDeposit) -> Result<Option<Deposit>>;
+
+  // Auto-apply accounts.
+  async fn get_auto_apply_accounts(&self) -> Result<Vec<AutoApplyAccount>>;
+  async fn get_auto_apply_account(
+    &self,
+    confidential_account: &str,
+  ) -> Result<Option<AutoApplyAccount>>;
+  /// Add the account, or change its signer.
+  async fn set_auto_apply_account(&self, req: &CreateAutoApplyAccount) -> Result<AutoApplyAccount>;
+  async fn delete_auto_apply_account(&self, confidential_account: &str) -> Result<bool>;
+
+  // Account signers.
+  async fn get_account_signers(&self) -> Result<Vec<AccountSigner>>;
+  async fn get_account_signer(&self, confidential_account: &str) -> Result<Option<AccountSigner>>;
+  /// Set the account's default signer.
+  async fn set_account_signer(&self, req: &CreateAccountSigner) -> Result<AccountSigner>;
+  async fn delete_account_signer(&self, confidential_account: &str) -> Result<bool>;
+
+  // Signer budgets.
+  async fn get_signer_budget(&self, public_key: &str) -> Result<Option<SignerBudget>>;
+  async fn set_signer_budget(
+    &self,
+    public_key: &str,
+    budget: &SetSignerBudget,
+  ) -> Result<SignerBudget>;
+  async fn get_signer_usage(&self, public_key: &str) -> Result<SignerUsage>;
+  async fn add_signer_usage(&self, public_key: &str, tx_hash: &str, fee: u64) -> Result<()>;
+
+  // Session signers.
+  async fn get_session_signers(&self, signer: &str) -> Result<Vec<SessionSigner>>;
+  /// Get an unexpired session signer.
+  async fn get_session_signer(&self, name: &str) -> Result<Option<SessionSigner>>;
+  async fn create_session_signer(
+    &self,
+    name: &str,
+    signer: &str,
+    session: &CreateSessionSigner,
+  ) -> Result<SessionSigner>;
+  async fn delete_session_signer(&self, signer: &str, name: &str) -> Result<bool>;
+
+  // Webhook outbox.
+  async fn get_webhook_outbox(&self, status: Option<String>) -> Result<Vec<WebhookOutboxRecord>>;
+  async fn get_due_webhooks(&self, limit: u32) -> Result<Vec<WebhookOutboxRecord>>;
+  /// Queue a webhook.  Returns `None` if the chain event `event_key` is already queued for
+  /// the endpoint.
+  async fn add_webhook_outbox(
+    &self,
+    event: &str,
+    event_key: Option<&str>,
+    endpoint_id: Option<i64>,
+    backfill: bool,
+  ) -> Result<Option<i64>>;
+  async fn webhook_delivered(&self, id: i64) -> Result<()>;
+  async fn webhook_failed(&self, id: i64, err: &str, retry_secs: u64, dead: bool) -> Result<()>;
+  async fn redeliver_webhook(&self, id: i64) -> Result<Option<WebhookOutboxRecord>>;
+  async fn get_webhook_backlog(&self) -> Result<WebhookBacklog>;
+
+  // Webhook endpoints.
+  async fn get_webhook_endpoints(&self) -> Result<Vec<WebhookEndpoint>>;
+  async fn get_webhook_endpoint(&self, endpoint_id: i64) -> Result<Option<WebhookEndpoint>>;
+  /// Register an endpoint.  With `backfill_from` it starts `backfilling` the stored chain
+  /// events up to the last stored block.
+  async fn create_webhook_endpoint(
+    &self,
+    url: &str,
+    backfill_from: Option<u32>,
+  ) -> Result<WebhookEndpoint>;
+  /// Remove the endpoint.  Its pending webhooks are marked as `dead`.
+  async fn delete_webhook_endpoint(&self, endpoint_id: i64) -> Result<bool>;
+  async fn set_webhook_backfill_cursor(&self, endpoint_id: i64, block_number: u32) -> Result<()>;
+  /// Switch a `backfilling` endpoint to `live` once the backfill is queued and delivered.
+  async fn webhook_endpoint_live(&self, endpoint_id: i64) -> Result<bool>;
+
+  // Transaction jobs.
+  async fn get_tx_jobs(&self, status: Option<String>) -> Result<Vec<TxJobRow>>;
+  async fn get_tx_job(&self, job_id: i64) -> Result<Option<TxJobRow>>;
+  /// Jobs that are still `submitted` or `in_block`.
+  async fn get_unfinished_tx_jobs(&self) -> Result<Vec<TxJobRow>>;
+  async fn create_tx_job(&self, operation: &str, tx_hash: &str, finalize: bool)
+    -> Result<TxJobRow>;
+  async fn tx_job_in_block(&self, job_id: i64, block_hash: &str) -> Result<()>;
+  async fn tx_job_completed(&self, job_id: i64, res: &TransactionResult) -> Result<()>;
+  async fn tx_job_failed(&self, job_id: i64, err: &str) -> Result<()>;
+  /// Create a multi-step job.  It has no transaction until its first step submits one.
+  async fn create_progress_tx_job(
+    &self,
+    operation: &str,
+    finalize: bool,
+    progress: &str,
+  ) -> Result<TxJobRow>;
+  /// Save a multi-step job's status, progress and the transaction of its current step.
+  async fn tx_job_progress(
+    &self,
+    job_id: i64,
+    status: &str,
+    tx_hash: &str,
+    progress: &str,
+  ) -> Result<()>;
+
+  // Transaction outbox.
+  async fn get_tx_outbox_entries(&self, status: Option<String>) -> Result<Vec<TxOutboxRow>>;
+  async fn get_tx_outbox_entry(&self, outbox_id: i64) -> Result<Option<TxOutboxRow>>;
+  /// `pending` entries whose next attempt is due.
+  async fn get_due_tx_outbox_entries(&self) -> Result<Vec<TxOutboxRow>>;
+  /// Add a transaction that failed to submit.  It is retried in `retry_secs`, signed with
+  /// `nonce` if set.
+  async fn add_tx_outbox_entry(
+    &self,
+    operation: &str,
+    signer: &str,
+    extrinsic: &str,
+    call: &[u8],
+    finalize: bool,
+    err: &str,
+    nonce: Option<u32>,
+    retry_secs: u32,
+  ) -> Result<TxOutboxRow>;
+  /// Record a failed attempt and retry in `retry_secs`.  Pins the retries to `nonce` if set.
+  async fn tx_outbox_retry(
+    &self,
+    outbox_id: i64,
+    err: &str,
+    nonce: Option<u32>,
+    retry_secs: u32,
+  ) -> Result<()>;
+  async fn tx_outbox_submitted(&self, outbox_id: i64, tx_hash: &str) -> Result<()>;
+  async fn tx_outbox_completed(&self, outbox_id: i64, res: &TransactionResult) -> Result<()>;
+  async fn tx_outbox_failed(&self, outbox_id: i64, err: &str) -> Result<()>;
+
+  // Ledger.
+  async fn add_ledger_entries(&self, entries: &[LedgerEntry]) -> Result<()>;
+  async fn get_ledger_entries(&self, ledger_account: &str) -> Result<Vec<LedgerEntry>>;
+  async fn get_trial_balance(&self) -> Result<Vec<TrialBalance>>;
+
+  // Maintenance mode.
+  async fn get_maintenance_mode(&self) -> Result<MaintenanceMode>;
+  async fn set_maintenance_mode(&self, enabled: bool, retry_after: u32) -> Result<MaintenanceMode>;
+
+  // Chain watcher.
+  async fn get_watcher_status(&self) -> Result<WatcherStatus>;
+  async fn set_watcher_status(
+    &self,
+    head_block: u32,
+    processed_block: u32,
+    processed_hash: Option<&str>,
+    catching_up: bool,
+  ) -> Result<()>;
+  async fn add_watcher_reconnect(&self, error: &str) -> Result<()>;
+
+  // Leases.
+  async fn get_leases(&self) -> Result<Vec<Lease>>;
+  /// Acquire or renew the lease for `ttl_secs`.  Returns `false` if another holder has an
+  /// unexpired lease.
+  async fn acquire_lease(&self, name: &str, holder: &str, ttl_secs: u32) -> Result<bool>;
+  async fn release_lease(&self, name: &str, holder: &str) -> Result<()>;
+
+  // Scheduled jobs.
+  async fn get_scheduled_jobs(&self) -> Result<Vec<ScheduledJob>>;
+  /// Add the job (due now), or update its interval.  Returns the job's schedule.
+  async fn register_scheduled_job(&self, name: &str, interval_secs: u32) -> Result<ScheduledJob>;
+  /// Record a successful run, the next run is after the job's interval.
+  async fn scheduled_job_succeeded(&self, name: &str) -> Result<()>;
+  /// Record a failed run, the next run is after `retry_secs`.
+  async fn scheduled_job_failed(&self, name: &str, error: &str, retry_secs: u32) -> Result<()>;
 }
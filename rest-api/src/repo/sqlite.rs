@@ -1,59 +1,266 @@
 use std::sync::Arc;
 
+use uuid::Uuid;
+
 use actix_web::web::Data;
 
 use async_trait::async_trait;
 use polymesh_private_proof_shared::{
-  error::Result, BlockTransactionRecord, SettlementEventRecord, SettlementRecord,
+  error::{Error, Result},
+  AccountSigner, AddDeposit, AuditReportRequest, AutoApplyAccount, BlockTransactionRecord, Contact,
+  CreateAccountSigner, CreateAutoApplyAccount, CreateContact, CreateDepositAccount, CreateInvoice,
+  CreateSessionSigner, CreateWalletPairing, Deposit, DepositAccount, Invoice, Lease, LedgerEntry,
+  MaintenanceMode, OfflineCall, PublicKey, ScheduledJob, SessionSigner, SessionSignerRow,
+  SetSignerBudget, SettlementAnnotation, SettlementEventRecord, SettlementLeg, SettlementLegFilter,
+  SettlementLegRow, SettlementRecord, SignerBudget, SignerUsage, TransactionResult, TrialBalance,
+  TxJobRow, TxOutboxRow, UnsignedTransaction, UnsignedTransactionRow, UpdateContact, Venue,
+  WalletPairing, WalletSigningRequest, WalletSigningRequestRow, WatcherStatus, WebhookBacklog,
+  WebhookEndpoint, WebhookOutboxRecord,
 };
 
 use super::{TransactionRepository, TransactionRepositoryTrait};
+use crate::blobs::BlobStorage;
 
 pub struct SqliteTransactionRepository {
   pool: sqlx::SqlitePool,
+  /// Optional storage for large payloads.
+  blobs: Option<Arc<BlobStorage>>,
 }
 
 impl SqliteTransactionRepository {
   pub fn new(pool: &sqlx::SqlitePool) -> Arc<dyn TransactionRepositoryTrait> {
-    Arc::new(Self { pool: pool.clone() })
+    Self::with_blobs(pool, None)
   }
 
   pub fn new_app_data(pool: &sqlx::SqlitePool) -> TransactionRepository {
     Data::from(Self::new(pool))
   }
+
+  /// Store large payloads in `blobs`.
+  pub fn with_blobs(
+    pool: &sqlx::SqlitePool,
+    blobs: Option<Arc<BlobStorage>>,
+  ) -> Arc<dyn TransactionRepositoryTrait> {
+    Arc::new(Self {
+      pool: pool.clone(),
+      blobs,
+    })
+  }
+
+  async fn offload(&self, payload: String) -> Result<String> {
+    match &self.blobs {
+      Some(blobs) => blobs.offload(payload).await,
+      None => Ok(payload),
+    }
+  }
+
+  async fn load(&self, value: String) -> Result<String> {
+    match &self.blobs {
+      Some(blobs) => blobs.load(value).await,
+      None => Ok(value),
+    }
+  }
+
+  async fn load_transaction(
+    &self,
+    mut tx: BlockTransactionRecord,
+  ) -> Result<BlockTransactionRecord> {
+    if let Some(events) = tx.events.take() {
+      tx.events = Some(self.load(events).await?);
+    }
+    Ok(tx)
+  }
+
+  async fn load_events(
+    &self,
+    records: Vec<SettlementEventRecord>,
+  ) -> Result<Vec<SettlementEventRecord>> {
+    let mut loaded = Vec::with_capacity(records.len());
+    for mut rec in records {
+      rec.event = self.load(rec.event).await?;
+      loaded.push(rec);
+    }
+    Ok(loaded)
+  }
+
+  /// Move the large payloads of existing rows to the blob store.
+  ///
+  /// Returns the number of moved transaction events and settlement events.
+  pub async fn offload_existing_payloads(
+    pool: &sqlx::SqlitePool,
+    blobs: &BlobStorage,
+  ) -> Result<(u64, u64)> {
+    let threshold = blobs.threshold() as i64;
+    let mut transactions = 0;
+    let mut last_id = 0;
+    loop {
+      let rows = sqlx::query!(
+        r#"
+        SELECT id, events as "events!"
+        FROM transactions
+        WHERE id > ? AND events IS NOT NULL AND length(events) >= ?
+        ORDER BY id LIMIT 100
+        "#,
+        last_id,
+        threshold,
+      )
+      .fetch_all(pool)
+      .await?;
+      if rows.is_empty() {
+        break;
+      }
+      for row in rows {
+        last_id = row.id;
+        if !blobs.should_offload(&row.events) {
+          continue;
+        }
+        let events = blobs.offload(row.events).await?;
+        sqlx::query!(
+          r#"UPDATE transactions SET events = ? WHERE id = ?"#,
+          events,
+          row.id,
+        )
+        .execute(pool)
+        .await?;
+        transactions += 1;
+      }
+    }
+
+    let mut settlement_events = 0;
+    let mut last_id = 0;
+    loop {
+      let rows = sqlx::query!(
+        r#"
+        SELECT id, event
+        FROM settlement_events
+        WHERE id > ? AND length(event) >= ?
+        ORDER BY id LIMIT 100
+        "#,
+        last_id,
+        threshold,
+      )
+      .fetch_all(pool)
+      .await?;
+      if rows.is_empty() {
+        break;
+      }
+      for row in rows {
+        last_id = row.id;
+        if !blobs.should_offload(&row.event) {
+          continue;
+        }
+        let event = blobs.offload(row.event).await?;
+        sqlx::query!(
+          r#"UPDATE settlement_events SET event = ? WHERE id = ?"#,
+          event,
+          row.id,
+        )
+        .execute(pool)
+        .await?;
+        settlement_events += 1;
+      }
+    }
+    Ok((transactions, settlement_events))
+  }
 }
 
 #[async_trait]
 impl TransactionRepositoryTrait for SqliteTransactionRepository {
   // Block transactions.
   async fn get_block_transactions(&self) -> Result<Vec<BlockTransactionRecord>> {
-    Ok(
-      sqlx::query_as!(BlockTransactionRecord, r#"
+    let records = sqlx::query_as!(BlockTransactionRecord, r#"
         SELECT block_hash, block_number as "block_number: u32", tx_hash, success as "success: bool", error, events, created_at
         FROM transactions
         "#,)
-        .fetch_all(&self.pool)
-        .await?,
-    )
+      .fetch_all(&self.pool)
+      .await?;
+    let mut loaded = Vec::with_capacity(records.len());
+    for tx in records {
+      loaded.push(self.load_transaction(tx).await?);
+    }
+    Ok(loaded)
   }
 
-  async fn get_block_transaction(&self, tx_hash: &[u8]) -> Result<Option<BlockTransactionRecord>> {
-    Ok(
-      sqlx::query_as!(BlockTransactionRecord, r#"
+  async fn get_block_transactions_range(
+    &self,
+    from: u32,
+    to: u32,
+  ) -> Result<Vec<BlockTransactionRecord>> {
+    let records = sqlx::query_as!(BlockTransactionRecord, r#"
+        SELECT block_hash, block_number as "block_number: u32", tx_hash, success as "success: bool", error, events, created_at
+        FROM transactions
+        WHERE block_number >= ? AND block_number <= ?
+        ORDER BY block_number, id
+        "#, from, to)
+      .fetch_all(&self.pool)
+      .await?;
+    let mut loaded = Vec::with_capacity(records.len());
+    for tx in records {
+      loaded.push(self.load_transaction(tx).await?);
+    }
+    Ok(loaded)
+  }
+
+  async fn get_block_transaction(&self, tx_hash: &str) -> Result<Option<BlockTransactionRecord>> {
+    let record = sqlx::query_as!(BlockTransactionRecord, r#"
         SELECT block_hash, block_number as "block_number: u32", tx_hash, success as "success: bool", error, events, created_at
         FROM transactions
         WHERE tx_hash = ?
         "#, tx_hash)
-        .fetch_optional(&self.pool)
+      .fetch_optional(&self.pool)
+      .await?;
+    match record {
+      Some(tx) => Ok(Some(self.load_transaction(tx).await?)),
+      None => Ok(None),
+    }
+  }
+
+  async fn get_block_transactions_after(
+    &self,
+    after_id: i64,
+    limit: u32,
+  ) -> Result<Vec<(i64, BlockTransactionRecord)>> {
+    let rows = sqlx::query!(r#"
+        SELECT id, block_hash, block_number as "block_number: u32", tx_hash, success as "success: bool", error, events, created_at
+        FROM transactions
+        WHERE id > ?
+        ORDER BY id LIMIT ?
+        "#, after_id, limit)
+      .fetch_all(&self.pool)
+      .await?;
+    let mut loaded = Vec::with_capacity(rows.len());
+    for row in rows {
+      let tx = BlockTransactionRecord {
+        block_hash: row.block_hash,
+        block_number: row.block_number,
+        tx_hash: row.tx_hash,
+        success: row.success,
+        error: row.error,
+        events: row.events,
+        created_at: row.created_at,
+      };
+      loaded.push((row.id, self.load_transaction(tx).await?));
+    }
+    Ok(loaded)
+  }
+
+  async fn get_last_block_transaction_id(&self) -> Result<i64> {
+    Ok(
+      sqlx::query_scalar!(r#"SELECT COALESCE(MAX(id), 0) as "id!: i64" FROM transactions"#)
+        .fetch_one(&self.pool)
         .await?,
     )
   }
 
-  async fn add_block_transaction(&self, tx: BlockTransactionRecord) -> Result<()> {
+  async fn add_block_transaction(&self, mut tx: BlockTransactionRecord) -> Result<()> {
+    if let Some(events) = tx.events.take() {
+      tx.events = Some(self.offload(events).await?);
+    }
     sqlx::query!(
       r#"
       INSERT INTO transactions (block_hash, block_number, tx_hash, success, error, events)
       VALUES (?, ?, ?, ?, ?, ?)
+      ON CONFLICT DO NOTHING
       "#,
       tx.block_hash,
       tx.block_number,
@@ -70,52 +277,295 @@ impl TransactionRepositoryTrait for SqliteTransactionRepository {
   // Settlements.
   async fn get_settlements(&self) -> Result<Vec<SettlementRecord>> {
     Ok(
-      sqlx::query_as!(SettlementRecord, r#"
-        SELECT settlement_id as "settlement_id: u32", venue_id as "venue_id: u32", legs, memo, created_at
+      sqlx::query_as!(
+        SettlementRecord,
+        r#"
+        SELECT settlement_id as "settlement_id: u32", venue_id as "venue_id: u32", memo, created_at
         FROM settlements
-        "#,)
-        .fetch_all(&self.pool)
-        .await?,
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_pending_settlements(&self) -> Result<Vec<SettlementRecord>> {
+    Ok(
+      sqlx::query_as!(
+        SettlementRecord,
+        r#"
+        SELECT settlement_id as "settlement_id: u32", venue_id as "venue_id: u32", memo, created_at
+        FROM settlements as s
+        WHERE NOT EXISTS (
+          SELECT 1 FROM settlement_events as se
+          WHERE se.settlement_id = s.settlement_id
+            AND (se.event LIKE '{"ConfidentialTransactionExecuted"%'
+              OR se.event LIKE '{"ConfidentialTransactionRejected"%')
+        )
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn count_pending_settlements_before(&self, block_number: u32) -> Result<i64> {
+    Ok(
+      sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!: i64"
+        FROM settlements as s
+        WHERE EXISTS (
+          SELECT 1 FROM settlement_events as se
+          WHERE se.settlement_id = s.settlement_id AND se.block_number < ?
+            AND se.event LIKE '{"ConfidentialTransactionCreated"%'
+        )
+        AND NOT EXISTS (
+          SELECT 1 FROM settlement_events as se
+          WHERE se.settlement_id = s.settlement_id
+            AND (se.event LIKE '{"ConfidentialTransactionExecuted"%'
+              OR se.event LIKE '{"ConfidentialTransactionRejected"%')
+        )
+        "#,
+        block_number,
+      )
+      .fetch_one(&self.pool)
+      .await?,
     )
   }
 
   async fn get_settlement(&self, settlement_id: i64) -> Result<Option<SettlementRecord>> {
     Ok(
-      sqlx::query_as!(SettlementRecord, r#"
-        SELECT settlement_id as "settlement_id: u32", venue_id as "venue_id: u32", legs, memo, created_at
+      sqlx::query_as!(
+        SettlementRecord,
+        r#"
+        SELECT settlement_id as "settlement_id: u32", venue_id as "venue_id: u32", memo, created_at
         FROM settlements
         WHERE settlement_id = ?
-        "#, settlement_id)
-        .fetch_optional(&self.pool)
-        .await?,
+        "#,
+        settlement_id
+      )
+      .fetch_optional(&self.pool)
+      .await?,
     )
   }
 
-  async fn add_settlement(&self, rec: SettlementRecord) -> Result<()> {
+  async fn add_settlement(&self, rec: SettlementRecord, legs: &[SettlementLeg]) -> Result<()> {
+    let mut db_tx = self.pool.begin().await?;
     sqlx::query!(
       r#"
-      INSERT INTO settlements (settlement_id, venue_id, legs, memo)
-      VALUES (?, ?, ?, ?)
+      INSERT INTO settlements (settlement_id, venue_id, memo)
+      VALUES (?, ?, ?)
+      ON CONFLICT DO NOTHING
       "#,
       rec.settlement_id,
       rec.venue_id,
-      rec.legs,
       rec.memo,
     )
-    .execute(&self.pool)
+    .execute(&mut *db_tx)
     .await?;
+    for leg in legs {
+      let sender = key_to_hex(&leg.leg.sender);
+      let receiver = key_to_hex(&leg.leg.receiver);
+      let mediators = serde_json::to_string(&leg.leg.mediators)?;
+      sqlx::query!(
+        r#"
+        INSERT INTO settlement_legs (settlement_id, leg_id, sender, receiver, mediators)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT DO NOTHING
+        "#,
+        leg.settlement_id,
+        leg.leg_id,
+        sender,
+        receiver,
+        mediators,
+      )
+      .execute(&mut *db_tx)
+      .await?;
+      for (asset_id, auditors) in &leg.leg.assets_and_auditors {
+        let asset_id = asset_id.to_string();
+        sqlx::query!(
+          r#"
+          INSERT INTO leg_assets (settlement_id, leg_id, asset_id)
+          VALUES (?, ?, ?)
+          ON CONFLICT DO NOTHING
+          "#,
+          leg.settlement_id,
+          leg.leg_id,
+          asset_id,
+        )
+        .execute(&mut *db_tx)
+        .await?;
+        for auditor in auditors {
+          let auditor = key_to_hex(auditor);
+          sqlx::query!(
+            r#"
+            INSERT INTO leg_auditors (settlement_id, leg_id, asset_id, auditor)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT DO NOTHING
+            "#,
+            leg.settlement_id,
+            leg.leg_id,
+            asset_id,
+            auditor,
+          )
+          .execute(&mut *db_tx)
+          .await?;
+        }
+      }
+    }
+    db_tx.commit().await?;
     Ok(())
   }
 
+  // Settlement legs.
+  async fn get_settlement_legs(&self, filter: &SettlementLegFilter) -> Result<Vec<SettlementLeg>> {
+    // Only add the used filters, so the indexes can be used.
+    let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+      r#"
+      SELECT l.settlement_id, l.leg_id, l.sender, l.receiver, l.mediators, la.asset_id, a.auditor
+      FROM settlement_legs as l
+      LEFT JOIN leg_assets as la
+        ON la.settlement_id = l.settlement_id AND la.leg_id = l.leg_id
+      LEFT JOIN leg_auditors as a
+        ON a.settlement_id = la.settlement_id AND a.leg_id = la.leg_id AND a.asset_id = la.asset_id
+      WHERE 1 = 1
+      "#,
+    );
+    if let Some(settlement_id) = filter.settlement_id {
+      query
+        .push(" AND l.settlement_id = ")
+        .push_bind(settlement_id);
+    }
+    if let Some(sender) = &filter.sender {
+      query
+        .push(" AND l.sender = ")
+        .push_bind(str_key_to_hex(sender)?);
+    }
+    if let Some(receiver) = &filter.receiver {
+      query
+        .push(" AND l.receiver = ")
+        .push_bind(str_key_to_hex(receiver)?);
+    }
+    if let Some(account) = &filter.account {
+      let account = str_key_to_hex(account)?;
+      query
+        .push(" AND (l.sender = ")
+        .push_bind(account.clone())
+        .push(" OR l.receiver = ")
+        .push_bind(account)
+        .push(")");
+    }
+    if let Some(auditor) = &filter.auditor {
+      query
+        .push(
+          " AND EXISTS (SELECT 1 FROM leg_auditors as f WHERE f.settlement_id = l.settlement_id \
+          AND f.leg_id = l.leg_id AND f.auditor = ",
+        )
+        .push_bind(str_key_to_hex(auditor)?)
+        .push(")");
+    }
+    if let Some(asset_id) = &filter.asset_id {
+      query
+        .push(
+          " AND EXISTS (SELECT 1 FROM leg_assets as f WHERE f.settlement_id = l.settlement_id \
+          AND f.leg_id = l.leg_id AND f.asset_id = ",
+        )
+        .push_bind(asset_id.to_string())
+        .push(")");
+    }
+    query.push(" ORDER BY l.settlement_id, l.leg_id");
+    let rows: Vec<SettlementLegRow> = query.build_query_as().fetch_all(&self.pool).await?;
+    SettlementLeg::from_rows(rows)
+  }
+
   // Settlement Events.
   async fn get_settlement_events(&self, settlement_id: i64) -> Result<Vec<SettlementEventRecord>> {
+    let records = sqlx::query_as!(
+      SettlementEventRecord,
+      r#"
+        SELECT settlement_id as "settlement_id: u32", event,
+          block_number as "block_number: u32", created_at
+        FROM settlement_events
+        WHERE settlement_id = ?
+        "#,
+      settlement_id
+    )
+    .fetch_all(&self.pool)
+    .await?;
+    self.load_events(records).await
+  }
+
+  async fn add_settlement_event(&self, mut rec: SettlementEventRecord) -> Result<()> {
+    rec.event = self.offload(rec.event).await?;
+    // Skip events already recorded when a block is processed again.
+    sqlx::query!(
+      r#"
+      INSERT INTO settlement_events (settlement_id, event, block_number)
+      SELECT ?, ?, ?
+      WHERE NOT EXISTS (
+        SELECT 1 FROM settlement_events
+        WHERE settlement_id = ? AND event = ? AND block_number IS ?
+      )
+      "#,
+      rec.settlement_id,
+      rec.event,
+      rec.block_number,
+      rec.settlement_id,
+      rec.event,
+      rec.block_number,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn get_asset_affirmations(
+    &self,
+    asset_id: Uuid,
+    range: &AuditReportRequest,
+  ) -> Result<Vec<SettlementEventRecord>> {
+    let mut query = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+      r#"
+      SELECT se.settlement_id, se.event, se.block_number, se.created_at
+      FROM settlement_events as se
+      WHERE se.event LIKE '{"ConfidentialTransactionAffirmed"%'
+        AND EXISTS (
+          SELECT 1 FROM leg_assets as la
+          WHERE la.settlement_id = se.settlement_id AND la.asset_id =
+      "#,
+    );
+    query.push_bind(asset_id.to_string()).push(")");
+    if let Some(from_block) = range.from_block {
+      query.push(" AND se.block_number >= ").push_bind(from_block);
+    }
+    if let Some(to_block) = range.to_block {
+      query.push(" AND se.block_number <= ").push_bind(to_block);
+    }
+    if let Some(from_date) = range.from_date {
+      query.push(" AND se.created_at >= ").push_bind(from_date);
+    }
+    if let Some(to_date) = range.to_date {
+      query.push(" AND se.created_at < ").push_bind(to_date);
+    }
+    query.push(" ORDER BY se.id");
+    let records = query.build_query_as().fetch_all(&self.pool).await?;
+    self.load_events(records).await
+  }
+
+  // Settlement annotations.
+  async fn get_settlement_annotations(
+    &self,
+    settlement_id: i64,
+  ) -> Result<Vec<SettlementAnnotation>> {
     Ok(
       sqlx::query_as!(
-        SettlementEventRecord,
+        SettlementAnnotation,
         r#"
-        SELECT settlement_id as "settlement_id: u32", event, created_at
-        FROM settlement_events
+        SELECT annotation_id, settlement_id, status, notes, author, created_at
+        FROM settlement_annotations
         WHERE settlement_id = ?
+        ORDER BY annotation_id
         "#,
         settlement_id
       )
@@ -124,17 +574,1915 @@ impl TransactionRepositoryTrait for SqliteTransactionRepository {
     )
   }
 
-  async fn add_settlement_event(&self, rec: SettlementEventRecord) -> Result<()> {
+  async fn get_latest_settlement_annotations(&self) -> Result<Vec<SettlementAnnotation>> {
+    Ok(
+      sqlx::query_as!(
+        SettlementAnnotation,
+        r#"
+        SELECT annotation_id, settlement_id, status, notes, author, created_at
+        FROM settlement_annotations
+        WHERE annotation_id IN (
+          SELECT MAX(annotation_id) FROM settlement_annotations GROUP BY settlement_id
+        )
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_settlement_annotation(
+    &self,
+    settlement_id: i64,
+    status: &str,
+    notes: Option<&str>,
+    author: &str,
+  ) -> Result<SettlementAnnotation> {
+    Ok(
+      sqlx::query_as!(
+        SettlementAnnotation,
+        r#"
+      INSERT INTO settlement_annotations (settlement_id, status, notes, author)
+      VALUES (?, ?, ?, ?)
+      RETURNING annotation_id, settlement_id, status, notes, author, created_at
+      "#,
+        settlement_id,
+        status,
+        notes,
+        author,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  // Contacts.
+  async fn get_contacts(&self) -> Result<Vec<Contact>> {
+    Ok(
+      sqlx::query_as!(
+        Contact,
+        r#"
+        SELECT confidential_account, display_name, organization, created_at, updated_at
+        FROM contacts
+        ORDER BY display_name
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_contact(&self, confidential_account: &str) -> Result<Option<Contact>> {
+    let key = str_key_to_hex(confidential_account)?;
+    Ok(
+      sqlx::query_as!(
+        Contact,
+        r#"
+        SELECT confidential_account, display_name, organization, created_at, updated_at
+        FROM contacts
+        WHERE confidential_account = ?
+        "#,
+        key
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_contact(&self, contact: &CreateContact) -> Result<Option<Contact>> {
+    let key = key_to_hex(&contact.confidential_account);
+    Ok(
+      sqlx::query_as!(
+        Contact,
+        r#"
+      INSERT INTO contacts (confidential_account, display_name, organization)
+      VALUES (?, ?, ?)
+      ON CONFLICT(confidential_account) DO NOTHING
+      RETURNING confidential_account, display_name, organization, created_at, updated_at
+      "#,
+        key,
+        contact.display_name,
+        contact.organization,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn update_contact(
+    &self,
+    confidential_account: &str,
+    contact: &UpdateContact,
+  ) -> Result<Option<Contact>> {
+    let key = str_key_to_hex(confidential_account)?;
+    Ok(
+      sqlx::query_as!(
+        Contact,
+        r#"
+      UPDATE contacts SET display_name = ?, organization = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE confidential_account = ?
+      RETURNING confidential_account, display_name, organization, created_at, updated_at
+      "#,
+        contact.display_name,
+        contact.organization,
+        key,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn delete_contact(&self, confidential_account: &str) -> Result<bool> {
+    let key = str_key_to_hex(confidential_account)?;
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM contacts WHERE confidential_account = ?
+      "#,
+      key,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  // Venues.
+  async fn get_venues(&self) -> Result<Vec<Venue>> {
+    Ok(
+      sqlx::query_as!(
+        Venue,
+        r#"
+        SELECT venue_id, signer, label, auto_execute as "auto_execute: bool", created_at
+        FROM venues
+        ORDER BY venue_id
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_venue(&self, venue_id: i64) -> Result<Option<Venue>> {
+    Ok(
+      sqlx::query_as!(
+        Venue,
+        r#"
+        SELECT venue_id, signer, label, auto_execute as "auto_execute: bool", created_at
+        FROM venues
+        WHERE venue_id = ?
+        "#,
+        venue_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_venue(&self, venue_id: i64, signer: &str, label: Option<&str>) -> Result<()> {
     sqlx::query!(
       r#"
-      INSERT INTO settlement_events (settlement_id, event)
-      VALUES (?, ?)
+      INSERT INTO venues (venue_id, signer, label)
+      VALUES (?, ?, ?)
+      ON CONFLICT(venue_id) DO NOTHING
       "#,
-      rec.settlement_id,
-      rec.event,
+      venue_id,
+      signer,
+      label,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn set_venue_auto_execute(
+    &self,
+    venue_id: i64,
+    auto_execute: bool,
+  ) -> Result<Option<Venue>> {
+    Ok(
+      sqlx::query_as!(
+        Venue,
+        r#"
+      UPDATE venues SET auto_execute = ?
+      WHERE venue_id = ?
+      RETURNING venue_id, signer, label, auto_execute as "auto_execute: bool", created_at
+      "#,
+        auto_execute,
+        venue_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  // Unsigned transactions (offline signing).
+  async fn get_unsigned_transaction(&self, build_id: i64) -> Result<Option<UnsignedTransaction>> {
+    let row = sqlx::query_as!(
+      UnsignedTransactionRow,
+      r#"
+      SELECT build_id, account, call, nonce, payload, tx_hash, created_at, submitted_at
+      FROM unsigned_transactions
+      WHERE build_id = ?
+      "#,
+      build_id
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    row.map(UnsignedTransaction::from_row).transpose()
+  }
+
+  async fn add_unsigned_transaction(
+    &self,
+    account: &str,
+    call: &OfflineCall,
+    nonce: u32,
+    payload: &[u8],
+  ) -> Result<UnsignedTransaction> {
+    let call = serde_json::to_string(call)?;
+    let nonce = nonce as i64;
+    let row = sqlx::query_as!(
+      UnsignedTransactionRow,
+      r#"
+      INSERT INTO unsigned_transactions (account, call, nonce, payload)
+      VALUES (?, ?, ?, ?)
+      RETURNING build_id, account, call, nonce, payload, tx_hash, created_at, submitted_at
+      "#,
+      account,
+      call,
+      nonce,
+      payload,
+    )
+    .fetch_one(&self.pool)
+    .await?;
+    UnsignedTransaction::from_row(row)
+  }
+
+  async fn set_unsigned_transaction_submitted(&self, build_id: i64, tx_hash: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE unsigned_transactions SET tx_hash = ?, submitted_at = CURRENT_TIMESTAMP
+      WHERE build_id = ?
+      "#,
+      tx_hash,
+      build_id,
     )
     .execute(&self.pool)
     .await?;
     Ok(())
   }
+
+  // Wallet pairings.
+  async fn get_wallet_pairings(&self) -> Result<Vec<WalletPairing>> {
+    Ok(
+      sqlx::query_as!(
+        WalletPairing,
+        r#"
+        SELECT pairing_id, name, account, callback_url, status, connected_at, created_at,
+          updated_at
+        FROM wallet_pairings
+        ORDER BY pairing_id
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_wallet_pairing(&self, pairing_id: i64) -> Result<Option<WalletPairing>> {
+    Ok(
+      sqlx::query_as!(
+        WalletPairing,
+        r#"
+        SELECT pairing_id, name, account, callback_url, status, connected_at, created_at,
+          updated_at
+        FROM wallet_pairings
+        WHERE pairing_id = ?
+        "#,
+        pairing_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_wallet_pairing(
+    &self,
+    pairing: &CreateWalletPairing,
+    secret_hash: &[u8],
+  ) -> Result<WalletPairing> {
+    Ok(
+      sqlx::query_as!(
+        WalletPairing,
+        r#"
+      INSERT INTO wallet_pairings (name, account, secret_hash)
+      VALUES (?, ?, ?)
+      RETURNING pairing_id, name, account, callback_url, status, connected_at, created_at,
+        updated_at
+      "#,
+        pairing.name,
+        pairing.account,
+        secret_hash,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn connect_wallet_pairing(
+    &self,
+    pairing_id: i64,
+    secret_hash: &[u8],
+    session_hash: &[u8],
+    callback_url: Option<&str>,
+  ) -> Result<Option<WalletPairing>> {
+    Ok(
+      sqlx::query_as!(
+        WalletPairing,
+        r#"
+      UPDATE wallet_pairings SET status = 'active', session_hash = ?, callback_url = ?,
+        connected_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE pairing_id = ? AND secret_hash = ? AND status = 'pending'
+      RETURNING pairing_id, name, account, callback_url, status, connected_at, created_at,
+        updated_at
+      "#,
+        session_hash,
+        callback_url,
+        pairing_id,
+        secret_hash,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_wallet_session(&self, session_hash: &[u8]) -> Result<Option<WalletPairing>> {
+    Ok(
+      sqlx::query_as!(
+        WalletPairing,
+        r#"
+        SELECT pairing_id, name, account, callback_url, status, connected_at, created_at,
+          updated_at
+        FROM wallet_pairings
+        WHERE session_hash = ? AND status = 'active'
+        "#,
+        session_hash,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn revoke_wallet_pairing(&self, pairing_id: i64) -> Result<Option<WalletPairing>> {
+    let mut db_tx = self.pool.begin().await?;
+    let pairing = sqlx::query_as!(
+      WalletPairing,
+      r#"
+      UPDATE wallet_pairings SET status = 'revoked', updated_at = CURRENT_TIMESTAMP
+        WHERE pairing_id = ? AND status != 'revoked'
+      RETURNING pairing_id, name, account, callback_url, status, connected_at, created_at,
+        updated_at
+      "#,
+      pairing_id,
+    )
+    .fetch_optional(&mut *db_tx)
+    .await?;
+    if pairing.is_some() {
+      sqlx::query!(
+        r#"
+        UPDATE wallet_signing_requests SET status = 'rejected', error = 'Pairing revoked',
+          decided_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+          WHERE pairing_id = ? AND status = 'pending'
+        "#,
+        pairing_id,
+      )
+      .execute(&mut *db_tx)
+      .await?;
+    }
+    db_tx.commit().await?;
+    Ok(pairing)
+  }
+
+  // Wallet signing requests.
+  async fn get_wallet_signing_requests(
+    &self,
+    pairing_id: Option<i64>,
+    status: Option<&str>,
+  ) -> Result<Vec<WalletSigningRequest>> {
+    let rows = sqlx::query_as!(
+      WalletSigningRequestRow,
+      r#"
+      SELECT r.request_id, r.pairing_id, r.build_id, u.account, u.call, r.summary, u.nonce,
+        u.payload, r.status, r.error, u.tx_hash, r.expires_at, r.decided_at, r.created_at,
+        r.updated_at
+      FROM wallet_signing_requests as r
+      JOIN unsigned_transactions as u ON u.build_id = r.build_id
+      WHERE (? IS NULL OR r.pairing_id = ?) AND (? IS NULL OR r.status = ?)
+      ORDER BY r.request_id
+      "#,
+      pairing_id,
+      pairing_id,
+      status,
+      status,
+    )
+    .fetch_all(&self.pool)
+    .await?;
+    rows
+      .into_iter()
+      .map(WalletSigningRequest::from_row)
+      .collect()
+  }
+
+  async fn get_wallet_signing_request(
+    &self,
+    request_id: i64,
+  ) -> Result<Option<WalletSigningRequest>> {
+    let row = sqlx::query_as!(
+      WalletSigningRequestRow,
+      r#"
+      SELECT r.request_id, r.pairing_id, r.build_id, u.account, u.call, r.summary, u.nonce,
+        u.payload, r.status, r.error, u.tx_hash, r.expires_at, r.decided_at, r.created_at,
+        r.updated_at
+      FROM wallet_signing_requests as r
+      JOIN unsigned_transactions as u ON u.build_id = r.build_id
+      WHERE r.request_id = ?
+      "#,
+      request_id,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    row.map(WalletSigningRequest::from_row).transpose()
+  }
+
+  async fn create_wallet_signing_request(
+    &self,
+    pairing_id: i64,
+    build_id: i64,
+    summary: &str,
+    expires_in: u64,
+  ) -> Result<WalletSigningRequest> {
+    let expires_in = format!("+{expires_in} seconds");
+    let request_id = sqlx::query!(
+      r#"
+      INSERT INTO wallet_signing_requests (pairing_id, build_id, summary, expires_at)
+      VALUES (?, ?, ?, datetime('now', ?))
+      RETURNING request_id
+      "#,
+      pairing_id,
+      build_id,
+      summary,
+      expires_in,
+    )
+    .fetch_one(&self.pool)
+    .await?
+    .request_id;
+    self
+      .get_wallet_signing_request(request_id)
+      .await?
+      .ok_or_else(|| Error::not_found("Wallet signing request"))
+  }
+
+  async fn decide_wallet_signing_request(
+    &self,
+    request_id: i64,
+    pairing_id: i64,
+    status: &str,
+    error: Option<&str>,
+  ) -> Result<Option<WalletSigningRequest>> {
+    let res = sqlx::query!(
+      r#"
+      UPDATE wallet_signing_requests SET status = ?, error = ?,
+        decided_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE request_id = ? AND pairing_id = ? AND status = 'pending'
+          AND expires_at > CURRENT_TIMESTAMP
+      "#,
+      status,
+      error,
+      request_id,
+      pairing_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    if res.rows_affected() == 0 {
+      return Ok(None);
+    }
+    self.get_wallet_signing_request(request_id).await
+  }
+
+  async fn wallet_signing_request_failed(&self, request_id: i64, error: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE wallet_signing_requests SET status = 'failed', error = ?,
+        updated_at = CURRENT_TIMESTAMP
+        WHERE request_id = ?
+      "#,
+      error,
+      request_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn expire_wallet_signing_requests(&self) -> Result<u64> {
+    let res = sqlx::query!(
+      r#"
+      UPDATE wallet_signing_requests SET status = 'expired', updated_at = CURRENT_TIMESTAMP
+        WHERE status = 'pending' AND expires_at <= CURRENT_TIMESTAMP
+      "#,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected())
+  }
+
+  // Invoices.
+  async fn get_invoices(&self, receiver: Option<&str>) -> Result<Vec<Invoice>> {
+    let receiver = receiver.map(str_key_to_hex).transpose()?;
+    Ok(
+      sqlx::query_as!(
+        Invoice,
+        r#"
+        SELECT invoice_id, reference, receiver, asset_id as "asset_id: Uuid", amount, memo,
+          status, settlement_id, expires_at, paid_at, created_at, updated_at
+        FROM invoices
+        WHERE ? IS NULL OR receiver = ?
+        ORDER BY invoice_id
+        "#,
+        receiver,
+        receiver,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_invoice(&self, reference: &str) -> Result<Option<Invoice>> {
+    Ok(
+      sqlx::query_as!(
+        Invoice,
+        r#"
+        SELECT invoice_id, reference, receiver, asset_id as "asset_id: Uuid", amount, memo,
+          status, settlement_id, expires_at, paid_at, created_at, updated_at
+        FROM invoices
+        WHERE reference = ?
+        "#,
+        reference,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_invoice(
+    &self,
+    reference: &str,
+    invoice: &CreateInvoice,
+    expires_in: u64,
+  ) -> Result<Invoice> {
+    let receiver = key_to_hex(&invoice.receiver);
+    let amount = invoice.amount as i64;
+    let expires_in = format!("+{expires_in} seconds");
+    Ok(
+      sqlx::query_as!(
+        Invoice,
+        r#"
+      INSERT INTO invoices (reference, receiver, asset_id, amount, memo, expires_at)
+      VALUES (?, ?, ?, ?, ?, datetime('now', ?))
+      RETURNING invoice_id, reference, receiver, asset_id as "asset_id: Uuid", amount, memo,
+        status, settlement_id, expires_at, paid_at, created_at, updated_at
+      "#,
+        reference,
+        receiver,
+        invoice.asset_id,
+        amount,
+        invoice.memo,
+        expires_in,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_invoice_settlement(
+    &self,
+    reference: &str,
+    settlement_id: i64,
+  ) -> Result<Option<Invoice>> {
+    Ok(
+      sqlx::query_as!(
+        Invoice,
+        r#"
+      UPDATE invoices SET status = 'settling', settlement_id = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE reference = ? AND status = 'open'
+      RETURNING invoice_id, reference, receiver, asset_id as "asset_id: Uuid", amount, memo,
+        status, settlement_id, expires_at, paid_at, created_at, updated_at
+      "#,
+        settlement_id,
+        reference,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn settle_invoice(&self, settlement_id: i64, executed: bool) -> Result<Option<Invoice>> {
+    Ok(
+      sqlx::query_as!(
+        Invoice,
+        r#"
+      UPDATE invoices SET
+          status = CASE WHEN ? THEN 'paid' ELSE 'open' END,
+          settlement_id = CASE WHEN ? THEN settlement_id ELSE NULL END,
+          paid_at = CASE WHEN ? THEN CURRENT_TIMESTAMP ELSE NULL END,
+          updated_at = CURRENT_TIMESTAMP
+        WHERE settlement_id = ? AND status = 'settling'
+      RETURNING invoice_id, reference, receiver, asset_id as "asset_id: Uuid", amount, memo,
+        status, settlement_id, expires_at, paid_at, created_at, updated_at
+      "#,
+        executed,
+        executed,
+        executed,
+        settlement_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn cancel_invoice(&self, reference: &str) -> Result<Option<Invoice>> {
+    Ok(
+      sqlx::query_as!(
+        Invoice,
+        r#"
+      UPDATE invoices SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+        WHERE reference = ? AND status = 'open'
+      RETURNING invoice_id, reference, receiver, asset_id as "asset_id: Uuid", amount, memo,
+        status, settlement_id, expires_at, paid_at, created_at, updated_at
+      "#,
+        reference,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  // Deposits.
+  async fn get_deposit_accounts(&self) -> Result<Vec<DepositAccount>> {
+    Ok(
+      sqlx::query_as!(
+        DepositAccount,
+        r#"
+        SELECT confidential_account, created_at
+        FROM deposit_accounts
+        ORDER BY created_at
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_deposit_account(
+    &self,
+    confidential_account: &str,
+  ) -> Result<Option<DepositAccount>> {
+    let key = str_key_to_hex(confidential_account)?;
+    Ok(
+      sqlx::query_as!(
+        DepositAccount,
+        r#"
+        SELECT confidential_account, created_at
+        FROM deposit_accounts
+        WHERE confidential_account = ?
+        "#,
+        key,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_deposit_account(&self, req: &CreateDepositAccount) -> Result<DepositAccount> {
+    let key = key_to_hex(&req.confidential_account);
+    sqlx::query!(
+      r#"
+      INSERT INTO deposit_accounts (confidential_account) VALUES (?)
+        ON CONFLICT(confidential_account) DO NOTHING
+      "#,
+      key,
+    )
+    .execute(&self.pool)
+    .await?;
+    self
+      .get_deposit_account(&key)
+      .await?
+      .ok_or_else(|| Error::not_found("Deposit account"))
+  }
+
+  async fn delete_deposit_account(&self, confidential_account: &str) -> Result<bool> {
+    let key = str_key_to_hex(confidential_account)?;
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM deposit_accounts WHERE confidential_account = ?
+      "#,
+      key,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  async fn get_deposits(
+    &self,
+    confidential_account: Option<&str>,
+    reference: Option<&str>,
+  ) -> Result<Vec<Deposit>> {
+    let key = confidential_account.map(str_key_to_hex).transpose()?;
+    Ok(
+      sqlx::query_as!(
+        Deposit,
+        r#"
+        SELECT deposit_id, settlement_id, leg_id, confidential_account,
+          asset_id as "asset_id: Uuid", amount, reference, invoice_id, created_at
+        FROM deposits
+        WHERE (? IS NULL OR confidential_account = ?) AND (? IS NULL OR reference = ?)
+        ORDER BY deposit_id
+        "#,
+        key,
+        key,
+        reference,
+        reference,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_deposit(&self, deposit: &AddDeposit) -> Result<Option<Deposit>> {
+    Ok(
+      sqlx::query_as!(
+        Deposit,
+        r#"
+      INSERT INTO deposits (settlement_id, leg_id, confidential_account, asset_id, amount,
+        reference, invoice_id)
+      VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(settlement_id, leg_id, asset_id) DO NOTHING
+      RETURNING deposit_id, settlement_id, leg_id, confidential_account,
+        asset_id as "asset_id: Uuid", amount, reference, invoice_id, created_at
+      "#,
+        deposit.settlement_id,
+        deposit.leg_id,
+        deposit.confidential_account,
+        deposit.asset_id,
+        deposit.amount,
+        deposit.reference,
+        deposit.invoice_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+  async fn get_auto_apply_accounts(&self) -> Result<Vec<AutoApplyAccount>> {
+    Ok(
+      sqlx::query_as!(
+        AutoApplyAccount,
+        r#"
+        SELECT confidential_account, signer, created_at, updated_at
+        FROM auto_apply_accounts
+        ORDER BY created_at
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_auto_apply_account(
+    &self,
+    confidential_account: &str,
+  ) -> Result<Option<AutoApplyAccount>> {
+    let key = str_key_to_hex(confidential_account)?;
+    Ok(
+      sqlx::query_as!(
+        AutoApplyAccount,
+        r#"
+        SELECT confidential_account, signer, created_at, updated_at
+        FROM auto_apply_accounts
+        WHERE confidential_account = ?
+        "#,
+        key,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_auto_apply_account(&self, req: &CreateAutoApplyAccount) -> Result<AutoApplyAccount> {
+    let key = key_to_hex(&req.confidential_account);
+    Ok(
+      sqlx::query_as!(
+        AutoApplyAccount,
+        r#"
+      INSERT INTO auto_apply_accounts (confidential_account, signer) VALUES (?, ?)
+        ON CONFLICT(confidential_account) DO UPDATE SET
+          signer = excluded.signer, updated_at = CURRENT_TIMESTAMP
+      RETURNING confidential_account, signer, created_at, updated_at
+      "#,
+        key,
+        req.signer,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn delete_auto_apply_account(&self, confidential_account: &str) -> Result<bool> {
+    let key = str_key_to_hex(confidential_account)?;
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM auto_apply_accounts WHERE confidential_account = ?
+      "#,
+      key,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  // Account signers.
+  async fn get_account_signers(&self) -> Result<Vec<AccountSigner>> {
+    Ok(
+      sqlx::query_as!(
+        AccountSigner,
+        r#"
+        SELECT confidential_account, signer, created_at, updated_at
+        FROM account_signers
+        ORDER BY created_at
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_account_signer(&self, confidential_account: &str) -> Result<Option<AccountSigner>> {
+    let key = str_key_to_hex(confidential_account)?;
+    Ok(
+      sqlx::query_as!(
+        AccountSigner,
+        r#"
+        SELECT confidential_account, signer, created_at, updated_at
+        FROM account_signers
+        WHERE confidential_account = ?
+        "#,
+        key,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_account_signer(&self, req: &CreateAccountSigner) -> Result<AccountSigner> {
+    let key = key_to_hex(&req.confidential_account);
+    Ok(
+      sqlx::query_as!(
+        AccountSigner,
+        r#"
+      INSERT INTO account_signers (confidential_account, signer) VALUES (?, ?)
+        ON CONFLICT(confidential_account) DO UPDATE SET
+          signer = excluded.signer, updated_at = CURRENT_TIMESTAMP
+      RETURNING confidential_account, signer, created_at, updated_at
+      "#,
+        key,
+        req.signer,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn delete_account_signer(&self, confidential_account: &str) -> Result<bool> {
+    let key = str_key_to_hex(confidential_account)?;
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM account_signers WHERE confidential_account = ?
+      "#,
+      key,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  // Signer budgets.
+  async fn get_signer_budget(&self, public_key: &str) -> Result<Option<SignerBudget>> {
+    Ok(
+      sqlx::query_as!(
+        SignerBudget,
+        r#"
+        SELECT public_key, max_daily_extrinsics, max_total_fees, alert_percent, created_at, updated_at
+        FROM signer_budgets
+        WHERE public_key = ?
+        "#,
+        public_key
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_signer_budget(
+    &self,
+    public_key: &str,
+    budget: &SetSignerBudget,
+  ) -> Result<SignerBudget> {
+    let max_fees = budget.max_total_fees.map(|fees| fees as i64);
+    Ok(
+      sqlx::query_as!(
+        SignerBudget,
+        r#"
+      INSERT INTO signer_budgets (public_key, max_daily_extrinsics, max_total_fees, alert_percent)
+      VALUES (?, ?, ?, ?)
+      ON CONFLICT(public_key)
+        DO UPDATE SET max_daily_extrinsics = excluded.max_daily_extrinsics,
+          max_total_fees = excluded.max_total_fees, alert_percent = excluded.alert_percent,
+          updated_at = CURRENT_TIMESTAMP
+      RETURNING public_key, max_daily_extrinsics, max_total_fees, alert_percent, created_at, updated_at
+      "#,
+        public_key,
+        budget.max_daily_extrinsics,
+        max_fees,
+        budget.alert_percent,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_signer_usage(&self, public_key: &str) -> Result<SignerUsage> {
+    Ok(
+      sqlx::query_as!(
+        SignerUsage,
+        r#"
+        SELECT
+          (SELECT COUNT(*) FROM signer_usage
+            WHERE public_key = ? AND created_at >= date('now')) as "daily_extrinsics!: i64",
+          (SELECT COALESCE(SUM(fee), 0) FROM signer_usage
+            WHERE public_key = ?) as "total_fees!: i64"
+        "#,
+        public_key,
+        public_key,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_signer_usage(&self, public_key: &str, tx_hash: &str, fee: u64) -> Result<()> {
+    let fee = fee as i64;
+    sqlx::query!(
+      r#"
+      INSERT INTO signer_usage (public_key, tx_hash, fee)
+      VALUES (?, ?, ?)
+      "#,
+      public_key,
+      tx_hash,
+      fee,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  // Session signers.
+  async fn get_session_signers(&self, signer: &str) -> Result<Vec<SessionSigner>> {
+    let rows = sqlx::query_as!(
+      SessionSignerRow,
+      r#"
+      SELECT session_name as name, signer_name as signer, extrinsics, expires_at, created_at
+      FROM session_signers
+      WHERE signer_name = ?
+      ORDER BY session_id DESC
+      "#,
+      signer
+    )
+    .fetch_all(&self.pool)
+    .await?;
+    rows.into_iter().map(SessionSigner::from_row).collect()
+  }
+
+  async fn get_session_signer(&self, name: &str) -> Result<Option<SessionSigner>> {
+    let row = sqlx::query_as!(
+      SessionSignerRow,
+      r#"
+      SELECT session_name as name, signer_name as signer, extrinsics, expires_at, created_at
+      FROM session_signers
+      WHERE session_name = ? AND expires_at > CURRENT_TIMESTAMP
+      "#,
+      name
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    row.map(SessionSigner::from_row).transpose()
+  }
+
+  async fn create_session_signer(
+    &self,
+    name: &str,
+    signer: &str,
+    session: &CreateSessionSigner,
+  ) -> Result<SessionSigner> {
+    let extrinsics = serde_json::to_string(&session.extrinsics)?;
+    let expires = format!("+{} minutes", session.minutes);
+    let row = sqlx::query_as!(
+      SessionSignerRow,
+      r#"
+      INSERT INTO session_signers (session_name, signer_name, extrinsics, expires_at)
+      VALUES (?, ?, ?, datetime('now', ?))
+      RETURNING session_name as name, signer_name as signer, extrinsics, expires_at, created_at
+      "#,
+      name,
+      signer,
+      extrinsics,
+      expires,
+    )
+    .fetch_one(&self.pool)
+    .await?;
+    SessionSigner::from_row(row)
+  }
+
+  async fn delete_session_signer(&self, signer: &str, name: &str) -> Result<bool> {
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM session_signers WHERE signer_name = ? AND session_name = ?
+      "#,
+      signer,
+      name,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  // Webhook outbox.
+  async fn get_webhook_outbox(&self, status: Option<String>) -> Result<Vec<WebhookOutboxRecord>> {
+    Ok(
+      sqlx::query_as!(
+        WebhookOutboxRecord,
+        r#"
+        SELECT id, endpoint_id, backfill as "backfill: bool", event, status, attempts, last_error,
+          next_attempt_at, created_at, updated_at
+        FROM webhook_outbox
+        WHERE ? IS NULL OR status = ?
+        ORDER BY id
+        "#,
+        status,
+        status,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_due_webhooks(&self, limit: u32) -> Result<Vec<WebhookOutboxRecord>> {
+    Ok(
+      sqlx::query_as!(
+        WebhookOutboxRecord,
+        r#"
+        SELECT id, endpoint_id, backfill as "backfill: bool", event, status, attempts, last_error,
+          next_attempt_at, created_at, updated_at
+        FROM webhook_outbox as o
+        WHERE status = 'pending' AND next_attempt_at <= CURRENT_TIMESTAMP
+          -- Hold live webhooks until the endpoint's backfill is delivered.
+          AND (o.endpoint_id IS NULL OR o.backfill = 1 OR NOT EXISTS (
+            SELECT 1 FROM webhook_endpoints as e
+            WHERE e.endpoint_id = o.endpoint_id AND e.status = 'backfilling'
+          ))
+        ORDER BY id
+        LIMIT ?
+        "#,
+        limit,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_webhook_outbox(
+    &self,
+    event: &str,
+    event_key: Option<&str>,
+    endpoint_id: Option<i64>,
+    backfill: bool,
+  ) -> Result<Option<i64>> {
+    let rec = sqlx::query!(
+      r#"
+      INSERT INTO webhook_outbox (event, event_key, endpoint_id, backfill)
+      VALUES (?, ?, ?, ?)
+      ON CONFLICT DO NOTHING
+      RETURNING id
+      "#,
+      event,
+      event_key,
+      endpoint_id,
+      backfill,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+    Ok(rec.map(|rec| rec.id))
+  }
+
+  async fn webhook_delivered(&self, id: i64) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE webhook_outbox SET status = 'delivered', last_error = NULL, updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?
+      "#,
+      id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn webhook_failed(&self, id: i64, err: &str, retry_secs: u64, dead: bool) -> Result<()> {
+    let status = if dead { "dead" } else { "pending" };
+    let retry = format!("+{retry_secs} seconds");
+    sqlx::query!(
+      r#"
+      UPDATE webhook_outbox SET status = ?, attempts = attempts + 1, last_error = ?,
+        next_attempt_at = datetime('now', ?), updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?
+      "#,
+      status,
+      err,
+      retry,
+      id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn redeliver_webhook(&self, id: i64) -> Result<Option<WebhookOutboxRecord>> {
+    Ok(
+      sqlx::query_as!(
+        WebhookOutboxRecord,
+        r#"
+      UPDATE webhook_outbox SET status = 'pending', attempts = 0,
+        next_attempt_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?
+      RETURNING id, endpoint_id, backfill as "backfill: bool", event, status, attempts, last_error,
+        next_attempt_at, created_at, updated_at
+      "#,
+        id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_webhook_backlog(&self) -> Result<WebhookBacklog> {
+    Ok(
+      sqlx::query_as!(
+        WebhookBacklog,
+        r#"
+        SELECT COUNT(*) as "pending!: i64",
+          MIN(created_at) as "oldest_created_at?: chrono::NaiveDateTime"
+        FROM webhook_outbox
+        WHERE status = 'pending'
+        "#,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  // Webhook endpoints.
+  async fn get_webhook_endpoints(&self) -> Result<Vec<WebhookEndpoint>> {
+    Ok(
+      sqlx::query_as!(
+        WebhookEndpoint,
+        r#"
+        SELECT endpoint_id, url, status, backfill_from, live_from, backfill_cursor,
+          created_at, updated_at
+        FROM webhook_endpoints
+        ORDER BY endpoint_id
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_webhook_endpoint(&self, endpoint_id: i64) -> Result<Option<WebhookEndpoint>> {
+    Ok(
+      sqlx::query_as!(
+        WebhookEndpoint,
+        r#"
+        SELECT endpoint_id, url, status, backfill_from, live_from, backfill_cursor,
+          created_at, updated_at
+        FROM webhook_endpoints
+        WHERE endpoint_id = ?
+        "#,
+        endpoint_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_webhook_endpoint(
+    &self,
+    url: &str,
+    backfill_from: Option<u32>,
+  ) -> Result<WebhookEndpoint> {
+    let status = if backfill_from.is_some() {
+      "backfilling"
+    } else {
+      "live"
+    };
+    Ok(
+      sqlx::query_as!(
+        WebhookEndpoint,
+        r#"
+      INSERT INTO webhook_endpoints (url, status, backfill_from, live_from)
+      VALUES (?, ?, ?, (SELECT COALESCE(MAX(block_number), 0) FROM transactions))
+      RETURNING endpoint_id, url, status, backfill_from, live_from, backfill_cursor,
+        created_at, updated_at
+      "#,
+        url,
+        status,
+        backfill_from,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn delete_webhook_endpoint(&self, endpoint_id: i64) -> Result<bool> {
+    let mut db_tx = self.pool.begin().await?;
+    sqlx::query!(
+      r#"
+      UPDATE webhook_outbox SET status = 'dead', last_error = 'Endpoint removed',
+        updated_at = CURRENT_TIMESTAMP
+        WHERE endpoint_id = ? AND status = 'pending'
+      "#,
+      endpoint_id,
+    )
+    .execute(&mut *db_tx)
+    .await?;
+    let res = sqlx::query!(
+      r#"
+      DELETE FROM webhook_endpoints WHERE endpoint_id = ?
+      "#,
+      endpoint_id,
+    )
+    .execute(&mut *db_tx)
+    .await?;
+    db_tx.commit().await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  async fn set_webhook_backfill_cursor(&self, endpoint_id: i64, block_number: u32) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE webhook_endpoints SET backfill_cursor = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE endpoint_id = ?
+      "#,
+      block_number,
+      endpoint_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn webhook_endpoint_live(&self, endpoint_id: i64) -> Result<bool> {
+    let res = sqlx::query!(
+      r#"
+      UPDATE webhook_endpoints SET status = 'live', updated_at = CURRENT_TIMESTAMP
+        WHERE endpoint_id = ? AND status = 'backfilling'
+          AND COALESCE(backfill_cursor, backfill_from - 1) >= live_from
+          AND NOT EXISTS (
+            SELECT 1 FROM webhook_outbox
+            WHERE endpoint_id = ? AND backfill = 1 AND status = 'pending'
+          )
+      "#,
+      endpoint_id,
+      endpoint_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  // Transaction jobs.
+  async fn get_tx_jobs(&self, status: Option<String>) -> Result<Vec<TxJobRow>> {
+    Ok(
+      sqlx::query_as!(
+        TxJobRow,
+        r#"
+        SELECT job_id, operation, status, finalize as "finalize: bool", tx_hash, block_hash,
+          err_msg, result, progress, created_at, updated_at
+        FROM tx_jobs
+        WHERE ? IS NULL OR status = ?
+        ORDER BY job_id
+        "#,
+        status,
+        status,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_tx_job(&self, job_id: i64) -> Result<Option<TxJobRow>> {
+    Ok(
+      sqlx::query_as!(
+        TxJobRow,
+        r#"
+        SELECT job_id, operation, status, finalize as "finalize: bool", tx_hash, block_hash,
+          err_msg, result, progress, created_at, updated_at
+        FROM tx_jobs
+        WHERE job_id = ?
+        "#,
+        job_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_unfinished_tx_jobs(&self) -> Result<Vec<TxJobRow>> {
+    Ok(
+      sqlx::query_as!(
+        TxJobRow,
+        r#"
+        SELECT job_id, operation, status, finalize as "finalize: bool", tx_hash, block_hash,
+          err_msg, result, progress, created_at, updated_at
+        FROM tx_jobs
+        WHERE status IN ('submitted', 'in_block')
+        ORDER BY job_id
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn create_tx_job(
+    &self,
+    operation: &str,
+    tx_hash: &str,
+    finalize: bool,
+  ) -> Result<TxJobRow> {
+    Ok(
+      sqlx::query_as!(
+        TxJobRow,
+        r#"
+      INSERT INTO tx_jobs (operation, tx_hash, finalize)
+      VALUES (?, ?, ?)
+      RETURNING job_id, operation, status, finalize as "finalize: bool", tx_hash, block_hash,
+        err_msg, result, progress, created_at, updated_at
+      "#,
+        operation,
+        tx_hash,
+        finalize,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn tx_job_in_block(&self, job_id: i64, block_hash: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE tx_jobs SET status = 'in_block', block_hash = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE job_id = ? AND status = 'submitted'
+      "#,
+      block_hash,
+      job_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn tx_job_completed(&self, job_id: i64, res: &TransactionResult) -> Result<()> {
+    let result = serde_json::to_string(res)?;
+    sqlx::query!(
+      r#"
+      UPDATE tx_jobs SET status = 'completed', block_hash = ?, err_msg = ?, result = ?,
+        updated_at = CURRENT_TIMESTAMP
+        WHERE job_id = ?
+      "#,
+      res.block_hash,
+      res.err_msg,
+      result,
+      job_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn tx_job_failed(&self, job_id: i64, err: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE tx_jobs SET status = 'failed', err_msg = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE job_id = ?
+      "#,
+      err,
+      job_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn create_progress_tx_job(
+    &self,
+    operation: &str,
+    finalize: bool,
+    progress: &str,
+  ) -> Result<TxJobRow> {
+    Ok(
+      sqlx::query_as!(
+        TxJobRow,
+        r#"
+      INSERT INTO tx_jobs (operation, tx_hash, finalize, progress)
+      VALUES (?, '', ?, ?)
+      RETURNING job_id, operation, status, finalize as "finalize: bool", tx_hash, block_hash,
+        err_msg, result, progress, created_at, updated_at
+      "#,
+        operation,
+        finalize,
+        progress,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn tx_job_progress(
+    &self,
+    job_id: i64,
+    status: &str,
+    tx_hash: &str,
+    progress: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE tx_jobs SET status = ?, tx_hash = ?, progress = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE job_id = ?
+      "#,
+      status,
+      tx_hash,
+      progress,
+      job_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  // Transaction outbox.
+  async fn get_tx_outbox_entries(&self, status: Option<String>) -> Result<Vec<TxOutboxRow>> {
+    Ok(
+      sqlx::query_as!(
+        TxOutboxRow,
+        r#"
+        SELECT outbox_id, operation, signer, extrinsic, call, finalize as "finalize: bool",
+          status, attempts, err_msg, nonce, tx_hash, result, next_attempt_at, created_at,
+          updated_at
+        FROM tx_outbox
+        WHERE ? IS NULL OR status = ?
+        ORDER BY outbox_id
+        "#,
+        status,
+        status,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_tx_outbox_entry(&self, outbox_id: i64) -> Result<Option<TxOutboxRow>> {
+    Ok(
+      sqlx::query_as!(
+        TxOutboxRow,
+        r#"
+        SELECT outbox_id, operation, signer, extrinsic, call, finalize as "finalize: bool",
+          status, attempts, err_msg, nonce, tx_hash, result, next_attempt_at, created_at,
+          updated_at
+        FROM tx_outbox
+        WHERE outbox_id = ?
+        "#,
+        outbox_id,
+      )
+      .fetch_optional(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_due_tx_outbox_entries(&self) -> Result<Vec<TxOutboxRow>> {
+    Ok(
+      sqlx::query_as!(
+        TxOutboxRow,
+        r#"
+        SELECT outbox_id, operation, signer, extrinsic, call, finalize as "finalize: bool",
+          status, attempts, err_msg, nonce, tx_hash, result, next_attempt_at, created_at,
+          updated_at
+        FROM tx_outbox
+        WHERE status = 'pending' AND next_attempt_at <= CURRENT_TIMESTAMP
+        ORDER BY outbox_id
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn add_tx_outbox_entry(
+    &self,
+    operation: &str,
+    signer: &str,
+    extrinsic: &str,
+    call: &[u8],
+    finalize: bool,
+    err: &str,
+    nonce: Option<u32>,
+    retry_secs: u32,
+  ) -> Result<TxOutboxRow> {
+    Ok(
+      sqlx::query_as!(
+        TxOutboxRow,
+        r#"
+      INSERT INTO tx_outbox (operation, signer, extrinsic, call, finalize, err_msg, nonce,
+        next_attempt_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?, datetime(CURRENT_TIMESTAMP, printf('+%d seconds', ?)))
+      RETURNING outbox_id, operation, signer, extrinsic, call, finalize as "finalize: bool",
+        status, attempts, err_msg, nonce, tx_hash, result, next_attempt_at, created_at,
+        updated_at
+      "#,
+        operation,
+        signer,
+        extrinsic,
+        call,
+        finalize,
+        err,
+        nonce,
+        retry_secs,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn tx_outbox_retry(
+    &self,
+    outbox_id: i64,
+    err: &str,
+    nonce: Option<u32>,
+    retry_secs: u32,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE tx_outbox SET attempts = attempts + 1, err_msg = ?, nonce = COALESCE(?, nonce),
+        next_attempt_at = datetime(CURRENT_TIMESTAMP, printf('+%d seconds', ?)),
+        updated_at = CURRENT_TIMESTAMP
+        WHERE outbox_id = ?
+      "#,
+      err,
+      nonce,
+      retry_secs,
+      outbox_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn tx_outbox_submitted(&self, outbox_id: i64, tx_hash: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE tx_outbox SET status = 'submitted', tx_hash = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE outbox_id = ?
+      "#,
+      tx_hash,
+      outbox_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn tx_outbox_completed(&self, outbox_id: i64, res: &TransactionResult) -> Result<()> {
+    let result = serde_json::to_string(res)?;
+    sqlx::query!(
+      r#"
+      UPDATE tx_outbox SET status = 'completed', err_msg = ?, result = ?,
+        updated_at = CURRENT_TIMESTAMP
+        WHERE outbox_id = ?
+      "#,
+      res.err_msg,
+      result,
+      outbox_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn tx_outbox_failed(&self, outbox_id: i64, err: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE tx_outbox SET status = 'failed', err_msg = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE outbox_id = ?
+      "#,
+      err,
+      outbox_id,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  // Ledger.
+  async fn add_ledger_entries(&self, entries: &[LedgerEntry]) -> Result<()> {
+    let mut db_tx = self.pool.begin().await?;
+    for entry in entries {
+      sqlx::query!(
+        r#"
+        INSERT INTO ledger_entries
+          (tx_hash, block_number, event_index, ledger_account, asset_id, debit, credit, cause)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(tx_hash, event_index, ledger_account) DO NOTHING
+        "#,
+        entry.tx_hash,
+        entry.block_number,
+        entry.event_index,
+        entry.ledger_account,
+        entry.asset_id,
+        entry.debit,
+        entry.credit,
+        entry.cause,
+      )
+      .execute(&mut *db_tx)
+      .await?;
+    }
+    db_tx.commit().await?;
+    Ok(())
+  }
+
+  async fn get_ledger_entries(&self, ledger_account: &str) -> Result<Vec<LedgerEntry>> {
+    Ok(
+      sqlx::query_as!(
+        LedgerEntry,
+        r#"
+        SELECT id, tx_hash, block_number as "block_number: u32", event_index as "event_index: u32",
+          ledger_account, asset_id as "asset_id: Uuid", debit, credit, cause, created_at
+        FROM ledger_entries
+        WHERE ledger_account = ?
+        ORDER BY id
+        "#,
+        ledger_account,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_trial_balance(&self) -> Result<Vec<TrialBalance>> {
+    Ok(
+      sqlx::query_as!(
+        TrialBalance,
+        r#"
+        SELECT ledger_account, asset_id as "asset_id: Uuid",
+          SUM(debit) as "debits!: i64", SUM(credit) as "credits!: i64",
+          SUM(debit) - SUM(credit) as "balance!: i64"
+        FROM ledger_entries
+        GROUP BY ledger_account, asset_id
+        ORDER BY ledger_account, asset_id
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  // Maintenance mode.
+  async fn get_maintenance_mode(&self) -> Result<MaintenanceMode> {
+    Ok(
+      sqlx::query_as!(
+        MaintenanceMode,
+        r#"
+        SELECT enabled, retry_after, updated_at
+        FROM maintenance_mode
+        WHERE id = 1
+        "#,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_maintenance_mode(&self, enabled: bool, retry_after: u32) -> Result<MaintenanceMode> {
+    Ok(
+      sqlx::query_as!(
+        MaintenanceMode,
+        r#"
+      UPDATE maintenance_mode SET enabled = ?, retry_after = ?, updated_at = CURRENT_TIMESTAMP
+        WHERE id = 1
+      RETURNING enabled, retry_after, updated_at
+      "#,
+        enabled,
+        retry_after,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn get_watcher_status(&self) -> Result<WatcherStatus> {
+    Ok(
+      sqlx::query_as!(
+        WatcherStatus,
+        r#"
+        SELECT head_block as "head_block: u32", processed_block as "processed_block: u32",
+          processed_hash, catching_up as "catching_up: bool", reconnects as "reconnects: u32",
+          last_error, last_reconnect_at, updated_at
+        FROM watcher_status
+        WHERE id = 1
+        "#,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn set_watcher_status(
+    &self,
+    head_block: u32,
+    processed_block: u32,
+    processed_hash: Option<&str>,
+    catching_up: bool,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE watcher_status
+        SET head_block = ?, processed_block = ?, processed_hash = ?, catching_up = ?,
+          updated_at = CURRENT_TIMESTAMP
+        WHERE id = 1
+      "#,
+      head_block,
+      processed_block,
+      processed_hash,
+      catching_up,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn add_watcher_reconnect(&self, error: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE watcher_status
+        SET reconnects = reconnects + 1, last_error = ?, last_reconnect_at = CURRENT_TIMESTAMP,
+          updated_at = CURRENT_TIMESTAMP
+        WHERE id = 1
+      "#,
+      error,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn get_leases(&self) -> Result<Vec<Lease>> {
+    Ok(
+      sqlx::query_as!(
+        Lease,
+        r#"
+        SELECT name, holder, acquired_at, expires_at, updated_at
+        FROM leases
+        ORDER BY name
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn acquire_lease(&self, name: &str, holder: &str, ttl_secs: u32) -> Result<bool> {
+    let res = sqlx::query!(
+      r#"
+      INSERT INTO leases (name, holder, expires_at)
+        VALUES (?, ?, datetime(CURRENT_TIMESTAMP, printf('+%d seconds', ?)))
+      ON CONFLICT(name) DO UPDATE SET holder = excluded.holder,
+        acquired_at = CASE WHEN leases.holder = excluded.holder
+          THEN leases.acquired_at ELSE CURRENT_TIMESTAMP END,
+        expires_at = excluded.expires_at, updated_at = CURRENT_TIMESTAMP
+        WHERE leases.holder = excluded.holder OR leases.expires_at < CURRENT_TIMESTAMP
+      "#,
+      name,
+      holder,
+      ttl_secs,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(res.rows_affected() > 0)
+  }
+
+  async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      DELETE FROM leases WHERE name = ? AND holder = ?
+      "#,
+      name,
+      holder,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn get_scheduled_jobs(&self) -> Result<Vec<ScheduledJob>> {
+    Ok(
+      sqlx::query_as!(
+        ScheduledJob,
+        r#"
+        SELECT name, interval_secs, next_run_at, last_run_at, last_error, attempts,
+          created_at, updated_at
+        FROM scheduled_jobs
+        ORDER BY name
+        "#,
+      )
+      .fetch_all(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn register_scheduled_job(&self, name: &str, interval_secs: u32) -> Result<ScheduledJob> {
+    Ok(
+      sqlx::query_as!(
+        ScheduledJob,
+        r#"
+        INSERT INTO scheduled_jobs (name, interval_secs) VALUES (?, ?)
+        ON CONFLICT(name) DO UPDATE SET interval_secs = excluded.interval_secs,
+          updated_at = CURRENT_TIMESTAMP
+        RETURNING name, interval_secs, next_run_at, last_run_at, last_error, attempts,
+          created_at, updated_at
+        "#,
+        name,
+        interval_secs,
+      )
+      .fetch_one(&self.pool)
+      .await?,
+    )
+  }
+
+  async fn scheduled_job_succeeded(&self, name: &str) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE scheduled_jobs SET last_run_at = CURRENT_TIMESTAMP, last_error = NULL, attempts = 0,
+        next_run_at = datetime(CURRENT_TIMESTAMP, printf('+%d seconds', interval_secs)),
+        updated_at = CURRENT_TIMESTAMP
+      WHERE name = ?
+      "#,
+      name,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+
+  async fn scheduled_job_failed(&self, name: &str, error: &str, retry_secs: u32) -> Result<()> {
+    sqlx::query!(
+      r#"
+      UPDATE scheduled_jobs SET last_run_at = CURRENT_TIMESTAMP, last_error = ?,
+        attempts = attempts + 1,
+        next_run_at = datetime(CURRENT_TIMESTAMP, printf('+%d seconds', ?)),
+        updated_at = CURRENT_TIMESTAMP
+      WHERE name = ?
+      "#,
+      error,
+      retry_secs,
+      name,
+    )
+    .execute(&self.pool)
+    .await?;
+    Ok(())
+  }
+}
+
+/// Confidential accounts and auditors are stored hex encoded in the settlement leg and
+/// contacts tables.
+fn key_to_hex(key: &PublicKey) -> String {
+  format!("0x{}", hex::encode(key.0))
+}
+
+fn str_key_to_hex(key: &str) -> Result<String> {
+  Ok(key_to_hex(&PublicKey::from_str(key)?))
 }
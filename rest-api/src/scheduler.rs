@@ -0,0 +1,180 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::web::Data;
+use async_trait::async_trait;
+use futures_util::future;
+
+use polymesh_private_proof_shared::{error::Result, ScheduledJob};
+
+use crate::repo::TransactionRepository;
+
+pub type AppScheduler = Data<Scheduler>;
+
+/// Default seconds before retrying a failed run.
+const DEFAULT_RETRY_BASE_SECS: u32 = 10;
+/// Default consecutive failed runs that are retried before the job's interval.
+const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 5;
+/// Seconds before retrying when the job's schedule can't be loaded.
+const REPO_RETRY_SECS: u64 = 10;
+
+/// Periodic task run by the [`Scheduler`].
+#[async_trait(?Send)]
+pub trait ScheduledTask: Send + Sync {
+  async fn run(&self) -> Result<()>;
+}
+
+#[async_trait(?Send)]
+impl<F, Fut> ScheduledTask for F
+where
+  F: Fn() -> Fut + Send + Sync,
+  Fut: Future<Output = Result<()>>,
+{
+  async fn run(&self) -> Result<()> {
+    self().await
+  }
+}
+
+/// Retry policy of failed runs.
+///
+/// Up to `max_attempts` consecutive failed runs are retried with an exponential backoff
+/// (`base_secs`, `2 * base_secs`, ...), capped at the job's interval.  After that the job
+/// runs at its regular interval until it succeeds again.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+  pub base_secs: u32,
+  pub max_attempts: u32,
+}
+
+impl RetryPolicy {
+  /// Seconds before the next run after `attempts` consecutive failures.
+  pub fn retry_secs(&self, attempts: u32, interval_secs: u32) -> u32 {
+    if attempts > self.max_attempts {
+      return interval_secs;
+    }
+    let backoff = self
+      .base_secs
+      .saturating_mul(1 << attempts.saturating_sub(1).min(16));
+    backoff.min(interval_secs)
+  }
+}
+
+struct Job {
+  name: &'static str,
+  interval_secs: u32,
+  task: Box<dyn ScheduledTask>,
+}
+
+/// Persistent scheduler of the periodic background jobs (proof pool expiry, secret checks,
+/// transaction job recovery, ...).
+///
+/// Jobs are registered with a name and an interval before the scheduler is started.  Their
+/// schedule (next run, last error and consecutive failures) is stored in the
+/// `scheduled_jobs` table, so restarts and fail-overs don't reset the intervals.  The
+/// scheduler runs under the `scheduler` lease (see `Leases`), so with distributed locks only
+/// one instance runs the jobs.
+///
+/// Failed runs are retried with the [`RetryPolicy`] loaded from `SCHEDULER_RETRY_BASE_SECS`
+/// (default 10) and `SCHEDULER_RETRY_MAX_ATTEMPTS` (default 5).
+pub struct Scheduler {
+  tx_repo: TransactionRepository,
+  retry: RetryPolicy,
+  jobs: Mutex<Vec<Arc<Job>>>,
+}
+
+impl Scheduler {
+  /// Load the retry policy from `SCHEDULER_RETRY_BASE_SECS` and
+  /// `SCHEDULER_RETRY_MAX_ATTEMPTS`.
+  pub fn from_env(tx_repo: TransactionRepository) -> AppScheduler {
+    let base_secs = std::env::var("SCHEDULER_RETRY_BASE_SECS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_RETRY_BASE_SECS)
+      .max(1);
+    let max_attempts = std::env::var("SCHEDULER_RETRY_MAX_ATTEMPTS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_RETRY_MAX_ATTEMPTS);
+    Data::new(Self {
+      tx_repo,
+      retry: RetryPolicy {
+        base_secs,
+        max_attempts,
+      },
+      jobs: Default::default(),
+    })
+  }
+
+  /// Register a job to run every `interval`.  Jobs registered after the scheduler was
+  /// started only run once it's restarted (i.e. the lease moved).
+  pub fn register(
+    &self,
+    name: &'static str,
+    interval: Duration,
+    task: impl ScheduledTask + 'static,
+  ) {
+    let job = Job {
+      name,
+      interval_secs: interval.as_secs().clamp(1, u32::MAX as u64) as u32,
+      task: Box::new(task),
+    };
+    self
+      .jobs
+      .lock()
+      .expect("Scheduler lock poisoned")
+      .push(Arc::new(job));
+  }
+
+  /// Run the registered jobs.
+  pub async fn run(&self) {
+    let jobs = self.jobs.lock().expect("Scheduler lock poisoned").clone();
+    log::info!("Scheduler started with {} jobs", jobs.len());
+    future::join_all(jobs.iter().map(|job| self.run_job(job))).await;
+  }
+
+  async fn run_job(&self, job: &Job) {
+    loop {
+      let schedule = self.load_schedule(job).await;
+      let now = chrono::Utc::now().naive_utc();
+      if let Ok(wait) = (schedule.next_run_at - now).to_std() {
+        actix_web::rt::time::sleep(wait).await;
+      }
+      let res = match job.task.run().await {
+        Ok(()) => self.tx_repo.scheduled_job_succeeded(job.name).await,
+        Err(err) => {
+          let attempts = schedule.attempts as u32 + 1;
+          let retry_secs = self.retry.retry_secs(attempts, job.interval_secs);
+          log::error!(
+            "Scheduled job {} failed ({attempts} attempts), next run in {retry_secs}s: {err:?}",
+            job.name
+          );
+          self
+            .tx_repo
+            .scheduled_job_failed(job.name, &err.to_string(), retry_secs)
+            .await
+        }
+      };
+      if let Err(err) = res {
+        log::error!("Failed to update scheduled job {}: {err:?}", job.name);
+        // Don't rerun the job right away.
+        actix_web::rt::time::sleep(Duration::from_secs(REPO_RETRY_SECS)).await;
+      }
+    }
+  }
+
+  /// Load the job's schedule, adding the job if it's new.
+  async fn load_schedule(&self, job: &Job) -> ScheduledJob {
+    loop {
+      match self
+        .tx_repo
+        .register_scheduled_job(job.name, job.interval_secs)
+        .await
+      {
+        Ok(schedule) => return schedule,
+        Err(err) => log::error!("Failed to load scheduled job {}: {err:?}", job.name),
+      }
+      actix_web::rt::time::sleep(Duration::from_secs(REPO_RETRY_SECS)).await;
+    }
+  }
+}
@@ -0,0 +1,31 @@
+use polymesh_api::types::pallet_confidential_asset::ConfidentialAccount;
+use polymesh_api::Api;
+
+use polymesh_private_proof_api::screening::Screening;
+use polymesh_private_proof_shared::{
+  confidential_account_to_key, did_to_hex,
+  error::{Error, Result},
+};
+
+/// Screen a settlement receiver, with its identity from the chain.
+pub async fn screen_chain_receiver(
+  screening: &Screening,
+  api: &Api,
+  receiver: ConfidentialAccount,
+) -> Result<()> {
+  if !screening.is_enabled() {
+    return Ok(());
+  }
+  let did = api
+    .query()
+    .confidential_asset()
+    .account_did(receiver)
+    .await
+    .map_err(|err| Error::from(err))?;
+  screening
+    .screen_receiver(
+      &confidential_account_to_key(&receiver),
+      did.as_ref().map(did_to_hex),
+    )
+    .await
+}
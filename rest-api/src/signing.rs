@@ -1,16 +1,41 @@
 use actix_web::web::Data;
 
 use async_trait::async_trait;
-use polymesh_private_proof_shared::{error::Result, CreateSigner, SignerInfo};
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  CreateSigner, EnvConfig, SignerInfo,
+};
 
 use polymesh_api::client::Signer;
 
+use crate::repo::TransactionRepository;
+
 mod db;
 pub use db::SqliteSigningManager;
 
 mod vault;
 pub use vault::VaultSigningManager;
 
+mod vault_auth;
+pub use vault_auth::{VaultAuth, VaultAuthMethod};
+
+mod cloud_token;
+
+mod gcp_kms;
+pub use gcp_kms::GcpKmsSigningManager;
+
+mod azure_kv;
+pub use azure_kv::AzureKvSigningManager;
+
+mod reloadable;
+pub use reloadable::ReloadableSigningManager;
+
+mod sessions;
+pub use sessions::SessionSigningManager;
+
+mod nonces;
+pub use nonces::{next_nonce, ManagedNonceSigner, NonceManager, NonceSigningManager};
+
 pub type AppSigningManager = Data<dyn SigningManagerTrait>;
 pub type TxSigner = Box<dyn Signer>;
 
@@ -21,5 +46,51 @@ pub trait SigningManagerTrait: Send + Sync + 'static {
   async fn get_signers(&self) -> Result<Vec<SignerInfo>>;
   async fn get_signer_info(&self, signer: &str) -> Result<Option<SignerInfo>>;
   async fn get_signer(&self, signer: &str) -> Result<Option<TxSigner>>;
+  /// Get a signer to submit the `extrinsic` (`confidential_asset` call name).  Session
+  /// signers can only submit their extrinsics.
+  async fn get_tx_signer(&self, signer: &str, _extrinsic: &str) -> Result<Option<TxSigner>> {
+    self.get_signer(signer).await
+  }
   async fn create_signer(&self, signer: &CreateSigner) -> Result<SignerInfo>;
 }
+
+/// Create the signing manager selected by the `SIGNING_MANAGER` config var: `DB` (default),
+/// `VAULT`, `GCP_KMS` or `AZURE_KV`.
+pub fn signing_manager_from_env(
+  pool: &sqlx::SqlitePool,
+  config: &EnvConfig,
+) -> anyhow::Result<AppSigningManager> {
+  let manager = config.var("SIGNING_MANAGER").ok();
+  match manager.as_ref().map(|s| s.as_str()) {
+    Some("DB" | "LOCAL") | None => Ok(SqliteSigningManager::new_app_data(pool)),
+    Some("VAULT") => {
+      let mount = VaultSigningManager::mount_from_env(config)?;
+      let auth = VaultAuth::from_env(config, &mount)?;
+      Ok(VaultSigningManager::new_app_data(mount, auth)?)
+    }
+    Some("GCP_KMS") => GcpKmsSigningManager::from_env(config),
+    Some("AZURE_KV") => AzureKvSigningManager::from_env(config),
+    Some(manager) => Err(anyhow::anyhow!("Unknown Signing Manager: {manager:?}")),
+  }
+}
+
+/// The request's `signer`, or the account's default signer if it was omitted (see
+/// `/account_signers`).
+///
+/// The signer is then checked like one given in the request (i.e. session signers can only
+/// submit their extrinsics).
+pub async fn resolve_signer(
+  tx_repo: &TransactionRepository,
+  confidential_account: &str,
+  signer: &str,
+) -> Result<String> {
+  if !signer.is_empty() {
+    return Ok(signer.to_string());
+  }
+  match tx_repo.get_account_signer(confidential_account).await? {
+    Some(account_signer) => Ok(account_signer.signer),
+    None => Err(Error::other(
+      "Missing `signer`, the account has no default signer (see `/account_signers`)",
+    )),
+  }
+}
@@ -0,0 +1,519 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use actix_web::web::Data;
+
+use reqwest::{Client, RequestBuilder, StatusCode, Url};
+
+use dashmap::DashMap;
+
+use async_trait::async_trait;
+use polymesh_private_proof_shared::{error::*, CreateSigner, EnvConfig, SignerInfo};
+
+use polymesh_api::client::{AccountId, Error as ClientError, Signer};
+use sp_core::{ecdsa, hashing::blake2_256};
+use sp_runtime::MultiSignature;
+
+use super::cloud_token::{CloudToken, TokenSource};
+use super::{AppSigningManager, SigningManagerTrait, TxSigner};
+
+/// Key Vault REST API version.
+const API_VERSION: &str = "7.4";
+/// Scope of Key Vault access tokens.
+const VAULT_RESOURCE: &str = "https://vault.azure.net";
+/// Access token of the managed identity (instance metadata service).
+const IMDS_TOKEN_URL: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+/// Order of the secp256k1 curve.
+const SECP256K1_N: [u8; 32] = [
+  0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+  0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+/// Half the order of the secp256k1 curve.
+const SECP256K1_HALF_N: [u8; 32] = [
+  0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+  0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorResponse {
+  error: Option<ErrorDetails>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorDetails {
+  #[serde(default)]
+  message: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListResponse {
+  #[serde(default)]
+  value: Vec<KeyItem>,
+  next_link: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct KeyItem {
+  kid: String,
+  #[serde(default)]
+  attributes: KeyAttributes,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct KeyAttributes {
+  #[serde(default)]
+  enabled: bool,
+  /// Unix timestamp.
+  #[serde(default)]
+  created: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct KeyBundle {
+  key: JsonWebKey,
+  #[serde(default)]
+  attributes: KeyAttributes,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct JsonWebKey {
+  kid: String,
+  #[serde(default)]
+  crv: String,
+  #[serde(default)]
+  x: String,
+  #[serde(default)]
+  y: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SignResponse {
+  value: String,
+}
+
+impl JsonWebKey {
+  /// Compressed secp256k1 public key.
+  fn public(&self) -> Result<ecdsa::Public> {
+    if self.crv != "P-256K" {
+      return Err(Error::other("Key Vault key isn't a secp256k1 (P-256K) key"));
+    }
+    let invalid = || Error::other("Invalid public key from Key Vault");
+    let x = URL_SAFE_NO_PAD.decode(&self.x).map_err(|_| invalid())?;
+    let y = URL_SAFE_NO_PAD.decode(&self.y).map_err(|_| invalid())?;
+    if x.len() != 32 || y.len() != 32 {
+      return Err(invalid());
+    }
+    let mut public = [0u8; 33];
+    public[0] = 0x02 | (y[31] & 1);
+    public[1..].copy_from_slice(&x);
+    Ok(ecdsa::Public::from_raw(public))
+  }
+}
+
+/// Name and version of a Key Vault key.  Signers are named `{name}-{version}`, the key's
+/// current version is used if the version is omitted.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct KeyVersion {
+  name: String,
+  version: Option<String>,
+}
+
+impl KeyVersion {
+  fn parse(s: &str) -> Self {
+    match s.rsplit_once('-') {
+      Some((name, version))
+        if version.len() == 32 && version.chars().all(|c| c.is_ascii_hexdigit()) =>
+      {
+        Self {
+          name: name.to_string(),
+          version: Some(version.to_string()),
+        }
+      }
+      _ => Self {
+        name: s.to_string(),
+        version: None,
+      },
+    }
+  }
+
+  /// From a key id `{vault}/keys/{name}/{version}`.
+  fn from_kid(kid: &str) -> Option<Self> {
+    let (_, path) = kid.split_once("/keys/")?;
+    let (name, version) = path.split_once('/')?;
+    Some(Self {
+      name: name.to_string(),
+      version: Some(version.to_string()),
+    })
+  }
+
+  fn signer_name(&self) -> String {
+    match &self.version {
+      Some(version) => format!("{}-{version}", self.name),
+      None => self.name.clone(),
+    }
+  }
+}
+
+/// Substrate account of a secp256k1 key.
+fn ecdsa_account(public: &ecdsa::Public) -> AccountId {
+  AccountId::from(blake2_256(public.as_ref()))
+}
+
+/// Replace `s` with `n - s` if it is in the upper half of the curve order.
+fn normalize_s(s: &mut [u8]) {
+  if s[..] <= SECP256K1_HALF_N[..] {
+    return;
+  }
+  let mut borrow = 0i16;
+  for (s, n) in s.iter_mut().zip(SECP256K1_N.iter()).rev() {
+    let diff = *n as i16 - *s as i16 - borrow;
+    borrow = (diff < 0) as i16;
+    *s = diff.rem_euclid(256) as u8;
+  }
+}
+
+/// Key Vault client.
+struct KeyVaultClient {
+  client: Client,
+  token: CloudToken,
+}
+
+impl KeyVaultClient {
+  async fn request<T: DeserializeOwned>(
+    &self,
+    req: impl Fn(&Client) -> RequestBuilder,
+  ) -> Result<Option<T>> {
+    let mut resp = req(&self.client)
+      .query(&[("api-version", API_VERSION)])
+      .bearer_auth(self.token.token().await?)
+      .send()
+      .await?;
+    if resp.status() == StatusCode::UNAUTHORIZED {
+      // The token might have been revoked, request a new one.
+      self.token.clear().await;
+      resp = req(&self.client)
+        .query(&[("api-version", API_VERSION)])
+        .bearer_auth(self.token.token().await?)
+        .send()
+        .await?;
+    }
+    match resp.status() {
+      StatusCode::NOT_FOUND => Ok(None),
+      status if status.is_success() => Ok(Some(resp.json().await?)),
+      status => {
+        let err: ErrorResponse = resp.json().await.unwrap_or_default();
+        let message = err.error.map(|err| err.message).unwrap_or_default();
+        Err(Error::Other(format!(
+          "Key Vault error ({status}): {message}"
+        )))
+      }
+    }
+  }
+}
+
+pub struct AzureKvSigner {
+  kv: Arc<KeyVaultClient>,
+  url: Url,
+  public: ecdsa::Public,
+  account: AccountId,
+}
+
+impl AzureKvSigner {
+  async fn sign_data(&self, msg: &[u8]) -> Result<MultiSignature> {
+    // Substrate ECDSA signatures are over the blake2 hash of the message.
+    let digest = blake2_256(msg);
+    let body = json!({ "alg": "ES256K", "value": URL_SAFE_NO_PAD.encode(digest) });
+    let signed: SignResponse = self
+      .kv
+      .request(|client| client.post(self.url.clone()).json(&body))
+      .await?
+      .ok_or_else(|| Error::other("No signature from Key Vault"))?;
+    let rs = URL_SAFE_NO_PAD
+      .decode(signed.value)
+      .ok()
+      .filter(|rs| rs.len() == 64)
+      .ok_or_else(|| Error::other("Invalid signature from Key Vault."))?;
+    // Key Vault returns `r || s`, Substrate needs a low `s` and the recovery id.
+    let mut sig = [0u8; 65];
+    sig[..64].copy_from_slice(&rs);
+    normalize_s(&mut sig[32..64]);
+    for recovery_id in 0..2 {
+      sig[64] = recovery_id;
+      let candidate = ecdsa::Signature::from_raw(sig);
+      if candidate.recover_prehashed(&digest) == Some(self.public) {
+        return Ok(candidate.into());
+      }
+    }
+    Err(Error::other(
+      "Key Vault signature doesn't match the public key.",
+    ))
+  }
+}
+
+#[async_trait]
+impl Signer for AzureKvSigner {
+  fn account(&self) -> AccountId {
+    self.account.clone()
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    None
+  }
+
+  async fn set_nonce(&mut self, _nonce: u32) {}
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature, ClientError> {
+    Ok(
+      self
+        .sign_data(msg)
+        .await
+        .map_err(|e| ClientError::SigningTransactionFailed(format!("{e:?}")))?,
+    )
+  }
+}
+
+#[derive(Clone)]
+struct CachedKey {
+  info: SignerInfo,
+  sign_url: Url,
+  public: ecdsa::Public,
+}
+
+/// Signing manager using secp256k1 (`P-256K`) keys of an Azure Key Vault.
+///
+/// Key Vault doesn't support Ed25519 or Sr25519 keys, the signers are ECDSA accounts
+/// (the blake2 hash of the compressed public key).
+pub struct AzureKvSigningManager {
+  kv: Arc<KeyVaultClient>,
+  /// `https://{vault}.vault.azure.net`.
+  vault_url: Url,
+  keys: DashMap<KeyVersion, CachedKey>,
+  cache: DashMap<AccountId, KeyVersion>,
+}
+
+impl AzureKvSigningManager {
+  pub fn new(vault_url: Url, token: TokenSource) -> Arc<dyn SigningManagerTrait> {
+    Arc::new(Self {
+      kv: Arc::new(KeyVaultClient {
+        client: Client::new(),
+        token: CloudToken::new(token),
+      }),
+      vault_url,
+      keys: DashMap::new(),
+      cache: DashMap::new(),
+    })
+  }
+
+  /// Load the config from the env: the vault `AZURE_KEY_VAULT_URL` and the access token
+  /// `AZURE_ACCESS_TOKEN`.  Without a token the service principal `AZURE_TENANT_ID`,
+  /// `AZURE_CLIENT_ID` and `AZURE_CLIENT_SECRET` logs in, or the managed identity is used
+  /// (`AZURE_CLIENT_ID` selects a user-assigned identity).
+  pub fn from_env(config: &EnvConfig) -> anyhow::Result<AppSigningManager> {
+    let vault_url = Url::parse(&config.var("AZURE_KEY_VAULT_URL")?)?;
+    let client_id = config.var("AZURE_CLIENT_ID").ok();
+    let token = match (
+      config.var("AZURE_ACCESS_TOKEN"),
+      config.var("AZURE_TENANT_ID"),
+      config.var("AZURE_CLIENT_SECRET"),
+    ) {
+      (Ok(token), _, _) => TokenSource::Static(token),
+      (_, Ok(tenant), Ok(secret)) => {
+        let client_id = client_id.ok_or_else(|| anyhow::anyhow!("AZURE_CLIENT_ID is required"))?;
+        let url = format!("https://login.microsoftonline.com/{tenant}/oauth2/v2.0/token");
+        let scope = format!("{VAULT_RESOURCE}/.default");
+        TokenSource::Endpoint(Box::new(move |client: &Client| {
+          client.post(&url).form(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id.as_str()),
+            ("client_secret", secret.as_str()),
+            ("scope", scope.as_str()),
+          ])
+        }))
+      }
+      _ => TokenSource::Endpoint(Box::new(move |client: &Client| {
+        let mut query = vec![("api-version", "2018-02-01"), ("resource", VAULT_RESOURCE)];
+        if let Some(client_id) = &client_id {
+          query.push(("client_id", client_id.as_str()));
+        }
+        client
+          .get(IMDS_TOKEN_URL)
+          .query(&query)
+          .header("Metadata", "true")
+      })),
+    };
+    Ok(Data::from(Self::new(vault_url, token)))
+  }
+
+  fn vault_path(&self, path: &[&str]) -> Result<Url> {
+    let mut url = self.vault_url.clone();
+    url
+      .path_segments_mut()
+      .map_err(|_| Error::other("Invalid AZURE_KEY_VAULT_URL"))?
+      .clear()
+      .extend(path);
+    Ok(url)
+  }
+
+  /// All pages of a key list.
+  async fn fetch_list(&self, url: Url) -> Result<Vec<KeyItem>> {
+    let mut items = Vec::new();
+    let mut next = Some(url);
+    while let Some(url) = next {
+      let list: ListResponse = self
+        .kv
+        .request(|client| client.get(url.clone()))
+        .await?
+        .unwrap_or_default();
+      items.extend(list.value);
+      next = match list.next_link {
+        Some(link) => Some(Url::parse(&link)?),
+        None => None,
+      };
+    }
+    Ok(items)
+  }
+
+  /// Get the key version's public key and cache the signer.
+  async fn cache_key_version(&self, key_version: &KeyVersion) -> Result<Option<CachedKey>> {
+    if key_version.version.is_some() {
+      if let Some(cached) = self.keys.get(key_version) {
+        return Ok(Some(cached.clone()));
+      }
+    }
+    let mut path = vec!["keys", key_version.name.as_str()];
+    if let Some(version) = &key_version.version {
+      path.push(version.as_str());
+    }
+    let url = self.vault_path(&path)?;
+    let bundle: KeyBundle = match self.kv.request(|client| client.get(url.clone())).await? {
+      Some(bundle) => bundle,
+      None => return Ok(None),
+    };
+    self.cache_key_bundle(bundle).map(Some)
+  }
+
+  fn cache_key_bundle(&self, bundle: KeyBundle) -> Result<CachedKey> {
+    let key_version = KeyVersion::from_kid(&bundle.key.kid)
+      .ok_or_else(|| Error::other("Invalid key id from Key Vault"))?;
+    let public = bundle.key.public()?;
+    let account = ecdsa_account(&public);
+    let version = key_version.version.as_deref().unwrap_or_default();
+    let cached = CachedKey {
+      info: SignerInfo {
+        name: key_version.signer_name(),
+        public_key: account.to_string(),
+        created_at: chrono::NaiveDateTime::from_timestamp_opt(bundle.attributes.created, 0)
+          .unwrap_or_default(),
+      },
+      sign_url: self.vault_path(&["keys", &key_version.name, version, "sign"])?,
+      public,
+    };
+    self.keys.insert(key_version.clone(), cached.clone());
+    self.cache.insert(account, key_version);
+    Ok(cached)
+  }
+
+  async fn load_keys(
+    &self,
+    mut signers: Option<&mut Vec<SignerInfo>>,
+    find: Option<AccountId>,
+  ) -> Result<Option<CachedKey>> {
+    for key in self.fetch_list(self.vault_path(&["keys"])?).await? {
+      // Key ids of the list are `{vault}/keys/{name}`.
+      let name = match key.kid.rsplit('/').next() {
+        Some(name) => name.to_string(),
+        None => continue,
+      };
+      let versions = self
+        .fetch_list(self.vault_path(&["keys", &name, "versions"])?)
+        .await?;
+      for version in versions {
+        if !version.attributes.enabled {
+          continue;
+        }
+        let key_version = match KeyVersion::from_kid(&version.kid) {
+          Some(key_version) => key_version,
+          None => continue,
+        };
+        // Skip keys that can't sign transactions.
+        let cached = match self.cache_key_version(&key_version).await {
+          Ok(Some(cached)) => cached,
+          Ok(None) => continue,
+          Err(err) => {
+            log::debug!(
+              "Skipping Key Vault key {}: {err:?}",
+              key_version.signer_name()
+            );
+            continue;
+          }
+        };
+        if Some(cached.info.account_id()?) == find {
+          return Ok(Some(cached));
+        }
+        if let Some(signers) = &mut signers {
+          signers.push(cached.info);
+        }
+      }
+    }
+    Ok(None)
+  }
+
+  async fn find_signer(&self, name: &str) -> Result<Option<CachedKey>> {
+    match AccountId::from_str(name).ok() {
+      Some(account_id) => {
+        let key_version = self.cache.get(&account_id).as_deref().cloned();
+        match key_version {
+          Some(key_version) => self.cache_key_version(&key_version).await,
+          None => self.load_keys(None, Some(account_id)).await,
+        }
+      }
+      None => self.cache_key_version(&KeyVersion::parse(name)).await,
+    }
+  }
+}
+
+#[async_trait]
+impl SigningManagerTrait for AzureKvSigningManager {
+  async fn get_signers(&self) -> Result<Vec<SignerInfo>> {
+    let mut signers = vec![];
+    self.load_keys(Some(&mut signers), None).await?;
+    Ok(signers)
+  }
+
+  async fn get_signer_info(&self, name: &str) -> Result<Option<SignerInfo>> {
+    Ok(self.find_signer(name).await?.map(|cached| cached.info))
+  }
+
+  async fn get_signer(&self, name: &str) -> Result<Option<TxSigner>> {
+    Ok(match self.find_signer(name).await? {
+      Some(cached) => Some(Box::new(AzureKvSigner {
+        kv: self.kv.clone(),
+        account: cached.info.account_id()?,
+        url: cached.sign_url,
+        public: cached.public,
+      })),
+      None => None,
+    })
+  }
+
+  async fn create_signer(&self, signer: &CreateSigner) -> Result<SignerInfo> {
+    if signer.secret_uri.is_some() {
+      return Err(Error::other(
+        "AZURE_KV signing manager doesn't support `secret_uri`.",
+      ));
+    }
+    let url = self.vault_path(&["keys", &signer.name, "create"])?;
+    let body = json!({ "kty": "EC", "crv": "P-256K", "key_ops": ["sign", "verify"] });
+    let bundle: KeyBundle = self
+      .kv
+      .request(|client| client.post(url.clone()).json(&body))
+      .await?
+      .ok_or_else(|| Error::other("Failed to create key"))?;
+    Ok(self.cache_key_bundle(bundle)?.info)
+  }
+}
@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use reqwest::{Client, RequestBuilder};
+
+use polymesh_private_proof_shared::error::*;
+
+/// Seconds before expiry a token is refreshed.
+const REFRESH_MARGIN_SECS: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+  access_token: String,
+  /// Seconds, some endpoints return it as a string.
+  #[serde(default)]
+  expires_in: serde_json::Value,
+}
+
+impl TokenResponse {
+  fn expires_in(&self) -> Duration {
+    let secs = match &self.expires_in {
+      serde_json::Value::Number(secs) => secs.as_u64(),
+      serde_json::Value::String(secs) => secs.parse().ok(),
+      _ => None,
+    };
+    Duration::from_secs(secs.unwrap_or(0).saturating_sub(REFRESH_MARGIN_SECS))
+  }
+}
+
+/// Where the OAuth2 access token of a cloud signing manager comes from.
+pub enum TokenSource {
+  /// Static token, it isn't refreshed.
+  Static(String),
+  /// Token endpoint (e.g. the instance metadata server or an OAuth2 client credentials
+  /// login), requested again before the token expires.
+  Endpoint(Box<dyn Fn(&Client) -> RequestBuilder + Send + Sync>),
+}
+
+/// Cached OAuth2 access token.
+pub struct CloudToken {
+  client: Client,
+  source: TokenSource,
+  token: RwLock<Option<(String, Instant)>>,
+}
+
+impl CloudToken {
+  pub fn new(source: TokenSource) -> Self {
+    Self {
+      client: Client::new(),
+      source,
+      token: RwLock::new(None),
+    }
+  }
+
+  /// The current token.  Requests a new token if it expired.
+  pub async fn token(&self) -> Result<String> {
+    let req = match &self.source {
+      TokenSource::Static(token) => return Ok(token.clone()),
+      TokenSource::Endpoint(req) => req,
+    };
+    if let Some((token, expires)) = self.token.read().await.as_ref() {
+      if Instant::now() < *expires {
+        return Ok(token.clone());
+      }
+    }
+    let mut cached = self.token.write().await;
+    // Another request might have refreshed the token.
+    if let Some((token, expires)) = cached.as_ref() {
+      if Instant::now() < *expires {
+        return Ok(token.clone());
+      }
+    }
+    let resp = req(&self.client).send().await?;
+    if !resp.status().is_success() {
+      let status = resp.status();
+      let body = resp.text().await.unwrap_or_default();
+      return Err(Error::Other(format!(
+        "Failed to get access token ({status}): {body}"
+      )));
+    }
+    let resp: TokenResponse = resp.json().await?;
+    let expires = Instant::now() + resp.expires_in();
+    *cached = Some((resp.access_token.clone(), expires));
+    Ok(resp.access_token)
+  }
+
+  /// Clear the cached token (e.g. it was rejected).
+  pub async fn clear(&self) {
+    *self.token.write().await = None;
+  }
+}
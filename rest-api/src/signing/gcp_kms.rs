@@ -0,0 +1,429 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use actix_web::web::Data;
+
+use reqwest::{Client, RequestBuilder, StatusCode, Url};
+
+use dashmap::DashMap;
+
+use async_trait::async_trait;
+use polymesh_private_proof_shared::{error::*, CreateSigner, EnvConfig, SignerInfo};
+
+use polymesh_api::client::{AccountId, Error as ClientError, Signer};
+use sp_core::ed25519::Signature;
+use sp_runtime::MultiSignature;
+
+use super::cloud_token::{CloudToken, TokenSource};
+use super::vault::NameVersion;
+use super::{AppSigningManager, SigningManagerTrait, TxSigner};
+
+/// Cloud KMS API.
+const KMS_API: &str = "https://cloudkms.googleapis.com/v1/";
+/// Access token of the instance's service account (GCE/GKE metadata server).
+const METADATA_TOKEN_URL: &str =
+  "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+  0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+/// Times to check if a new key version was generated.
+const GENERATE_RETRIES: usize = 10;
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorResponse {
+  error: Option<ErrorDetails>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorDetails {
+  #[serde(default)]
+  message: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListKeys {
+  #[serde(default)]
+  crypto_keys: Vec<CryptoKey>,
+  next_page_token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CryptoKey {
+  name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListKeyVersions {
+  #[serde(default)]
+  crypto_key_versions: Vec<CryptoKeyVersion>,
+  next_page_token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CryptoKeyVersion {
+  name: String,
+  state: String,
+  algorithm: String,
+  create_time: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PublicKeyResponse {
+  pem: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SignResponse {
+  signature: String,
+}
+
+/// Ed25519 public key from a PEM encoded `SubjectPublicKeyInfo`.
+fn ed25519_from_pem(pem: &str) -> Result<[u8; 32]> {
+  let body: String = pem
+    .lines()
+    .filter(|line| !line.starts_with("-----"))
+    .collect();
+  let der = STANDARD
+    .decode(body.trim())
+    .map_err(|_| Error::other("Invalid public key PEM from Cloud KMS"))?;
+  der
+    .strip_prefix(&ED25519_SPKI_PREFIX[..])
+    .and_then(|key| key.try_into().ok())
+    .ok_or_else(|| Error::other("Cloud KMS key isn't an Ed25519 key"))
+}
+
+/// Cloud KMS client.
+struct KmsClient {
+  client: Client,
+  token: CloudToken,
+}
+
+impl KmsClient {
+  async fn request<T: DeserializeOwned>(
+    &self,
+    req: impl Fn(&Client) -> RequestBuilder,
+  ) -> Result<Option<T>> {
+    let mut resp = req(&self.client)
+      .bearer_auth(self.token.token().await?)
+      .send()
+      .await?;
+    if resp.status() == StatusCode::UNAUTHORIZED {
+      // The token might have been revoked, request a new one.
+      self.token.clear().await;
+      resp = req(&self.client)
+        .bearer_auth(self.token.token().await?)
+        .send()
+        .await?;
+    }
+    match resp.status() {
+      StatusCode::NOT_FOUND => Ok(None),
+      status if status.is_success() => Ok(Some(resp.json().await?)),
+      status => {
+        let err: ErrorResponse = resp.json().await.unwrap_or_default();
+        let message = err.error.map(|err| err.message).unwrap_or_default();
+        Err(Error::Other(format!(
+          "Cloud KMS error ({status}): {message}"
+        )))
+      }
+    }
+  }
+}
+
+pub struct GcpKmsSigner {
+  kms: Arc<KmsClient>,
+  url: Url,
+  account: AccountId,
+}
+
+impl GcpKmsSigner {
+  async fn sign_data(&self, msg: &[u8]) -> Result<MultiSignature> {
+    // Ed25519 keys sign the message, not a digest.
+    let body = json!({ "data": STANDARD.encode(msg) });
+    let signed: SignResponse = self
+      .kms
+      .request(|client| client.post(self.url.clone()).json(&body))
+      .await?
+      .ok_or_else(|| Error::other("No signature from Cloud KMS"))?;
+    let sig = STANDARD
+      .decode(signed.signature)
+      .ok()
+      .and_then(|data| Signature::from_slice(data.as_slice()))
+      .ok_or_else(|| Error::other("Invalid signature from Cloud KMS."))?;
+    Ok(sig.into())
+  }
+}
+
+#[async_trait]
+impl Signer for GcpKmsSigner {
+  fn account(&self) -> AccountId {
+    self.account.clone()
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    None
+  }
+
+  async fn set_nonce(&mut self, _nonce: u32) {}
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature, ClientError> {
+    Ok(
+      self
+        .sign_data(msg)
+        .await
+        .map_err(|e| ClientError::SigningTransactionFailed(format!("{e:?}")))?,
+    )
+  }
+}
+
+/// Signing manager using Ed25519 keys of a Google Cloud KMS key ring.
+///
+/// Signers are named `{key}-{version}` like the Vault signing manager.
+pub struct GcpKmsSigningManager {
+  kms: Arc<KmsClient>,
+  /// `projects/{project}/locations/{location}/keyRings/{key_ring}`.
+  key_ring: String,
+  protection_level: String,
+  keys: DashMap<NameVersion, (SignerInfo, Url)>,
+  cache: DashMap<AccountId, NameVersion>,
+}
+
+impl GcpKmsSigningManager {
+  pub fn new(
+    key_ring: String,
+    protection_level: String,
+    token: TokenSource,
+  ) -> Arc<dyn SigningManagerTrait> {
+    Arc::new(Self {
+      kms: Arc::new(KmsClient {
+        client: Client::new(),
+        token: CloudToken::new(token),
+      }),
+      key_ring: key_ring.trim_matches('/').to_string(),
+      protection_level,
+      keys: DashMap::new(),
+      cache: DashMap::new(),
+    })
+  }
+
+  /// Load the config from the env: the key ring `GCP_KMS_KEY_RING`, the protection level of
+  /// new keys `GCP_KMS_PROTECTION_LEVEL` (default: `SOFTWARE`) and the access token
+  /// `GCP_ACCESS_TOKEN` (default: the token of the instance's service account).
+  pub fn from_env(config: &EnvConfig) -> anyhow::Result<AppSigningManager> {
+    let key_ring = config.var("GCP_KMS_KEY_RING")?;
+    let protection_level = config
+      .var("GCP_KMS_PROTECTION_LEVEL")
+      .unwrap_or_else(|_| "SOFTWARE".to_string());
+    let token = match config.var("GCP_ACCESS_TOKEN") {
+      Ok(token) => TokenSource::Static(token),
+      Err(_) => TokenSource::Endpoint(Box::new(|client: &Client| {
+        client
+          .get(METADATA_TOKEN_URL)
+          .header("Metadata-Flavor", "Google")
+      })),
+    };
+    Ok(Data::from(Self::new(key_ring, protection_level, token)))
+  }
+
+  fn api_url(&self, path: &str) -> Result<Url> {
+    Ok(Url::parse(KMS_API)?.join(path)?)
+  }
+
+  fn key_path(&self, name: &str) -> String {
+    format!("{}/cryptoKeys/{name}", self.key_ring)
+  }
+
+  async fn fetch_keys(&self) -> Result<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut page_token = None;
+    loop {
+      let mut url = self.api_url(&format!("{}/cryptoKeys", self.key_ring))?;
+      url
+        .query_pairs_mut()
+        .append_pair("filter", "purpose=ASYMMETRIC_SIGN");
+      if let Some(page_token) = &page_token {
+        url.query_pairs_mut().append_pair("pageToken", page_token);
+      }
+      let list: ListKeys = self
+        .kms
+        .request(|client| client.get(url.clone()))
+        .await?
+        .unwrap_or_default();
+      keys.extend(list.crypto_keys.into_iter().filter_map(|key| {
+        key
+          .name
+          .rsplit_once("/cryptoKeys/")
+          .map(|(_, name)| name.to_string())
+      }));
+      match list.next_page_token.filter(|token| !token.is_empty()) {
+        Some(token) => page_token = Some(token),
+        None => break,
+      }
+    }
+    Ok(keys)
+  }
+
+  async fn fetch_key_versions(&self, name: &str) -> Result<Vec<CryptoKeyVersion>> {
+    let mut versions = Vec::new();
+    let mut page_token = None;
+    loop {
+      let mut url = self.api_url(&format!("{}/cryptoKeyVersions", self.key_path(name)))?;
+      if let Some(page_token) = &page_token {
+        url.query_pairs_mut().append_pair("pageToken", page_token);
+      }
+      let list: ListKeyVersions = self
+        .kms
+        .request(|client| client.get(url.clone()))
+        .await?
+        .unwrap_or_default();
+      versions.extend(
+        list
+          .crypto_key_versions
+          .into_iter()
+          .filter(|version| version.state == "ENABLED" && version.algorithm == "EC_SIGN_ED25519"),
+      );
+      match list.next_page_token.filter(|token| !token.is_empty()) {
+        Some(token) => page_token = Some(token),
+        None => break,
+      }
+    }
+    Ok(versions)
+  }
+
+  /// Get the version's public key and cache the signer.
+  async fn cache_key_version(&self, name: &str, version: &CryptoKeyVersion) -> Result<SignerInfo> {
+    let number = version
+      .name
+      .rsplit('/')
+      .next()
+      .and_then(|number| number.parse().ok())
+      .ok_or_else(|| Error::other("Invalid Cloud KMS key version"))?;
+    let name_version = NameVersion::new(name.to_string(), number);
+    if let Some(cached) = self.keys.get(&name_version) {
+      return Ok(cached.0.clone());
+    }
+    let url = self.api_url(&format!("{}/publicKey", version.name))?;
+    let public: PublicKeyResponse = self
+      .kms
+      .request(|client| client.get(url.clone()))
+      .await?
+      .ok_or_else(|| Error::other("Cloud KMS key version not found"))?;
+    let account = AccountId::from(ed25519_from_pem(&public.pem)?);
+    let signer = SignerInfo {
+      name: name_version.to_string(),
+      public_key: account.to_string(),
+      created_at: version.create_time.naive_utc(),
+    };
+    let sign_url = self.api_url(&format!("{}:asymmetricSign", version.name))?;
+    self
+      .keys
+      .insert(name_version.clone(), (signer.clone(), sign_url));
+    self.cache.insert(account, name_version);
+    Ok(signer)
+  }
+
+  async fn load_keys(
+    &self,
+    mut signers: Option<&mut Vec<SignerInfo>>,
+    find: Option<AccountId>,
+  ) -> Result<Option<SignerInfo>> {
+    for name in self.fetch_keys().await? {
+      for version in self.fetch_key_versions(&name).await? {
+        let signer = self.cache_key_version(&name, &version).await?;
+        if Some(signer.account_id()?) == find {
+          return Ok(Some(signer));
+        }
+        if let Some(signers) = &mut signers {
+          signers.push(signer);
+        }
+      }
+    }
+    Ok(None)
+  }
+
+  async fn find_signer(&self, name: &str) -> Result<Option<(SignerInfo, Url)>> {
+    let name_version: NameVersion = match AccountId::from_str(name).ok() {
+      Some(account_id) => match self.cache.get(&account_id) {
+        Some(name_version) => name_version.clone(),
+        None => match self.load_keys(None, Some(account_id)).await? {
+          Some(signer) => signer.name.parse().expect("Doesn't fail"),
+          None => return Ok(None),
+        },
+      },
+      None => name.parse().expect("Doesn't fail"),
+    };
+    if let Some(cached) = self.keys.get(&name_version) {
+      return Ok(Some(cached.clone()));
+    }
+    for version in self.fetch_key_versions(&name_version.name).await? {
+      self.cache_key_version(&name_version.name, &version).await?;
+    }
+    Ok(self.keys.get(&name_version).as_deref().cloned())
+  }
+
+  async fn create_key(&self, name: &str) -> Result<SignerInfo> {
+    let mut url = self.api_url(&format!("{}/cryptoKeys", self.key_ring))?;
+    url.query_pairs_mut().append_pair("cryptoKeyId", name);
+    let body = json!({
+      "purpose": "ASYMMETRIC_SIGN",
+      "versionTemplate": {
+        "algorithm": "EC_SIGN_ED25519",
+        "protectionLevel": self.protection_level,
+      },
+    });
+    self
+      .kms
+      .request::<serde_json::Value>(|client| client.post(url.clone()).json(&body))
+      .await?;
+    // The first key version is generated in the background.
+    for _ in 0..GENERATE_RETRIES {
+      if let Some(version) = self.fetch_key_versions(name).await?.first() {
+        return self.cache_key_version(name, version).await;
+      }
+      actix_web::rt::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    Err(Error::other(
+      "Cloud KMS key was created, but isn't enabled yet",
+    ))
+  }
+}
+
+#[async_trait]
+impl SigningManagerTrait for GcpKmsSigningManager {
+  async fn get_signers(&self) -> Result<Vec<SignerInfo>> {
+    let mut signers = vec![];
+    self.load_keys(Some(&mut signers), None).await?;
+    Ok(signers)
+  }
+
+  async fn get_signer_info(&self, name: &str) -> Result<Option<SignerInfo>> {
+    Ok(self.find_signer(name).await?.map(|(info, _)| info))
+  }
+
+  async fn get_signer(&self, name: &str) -> Result<Option<TxSigner>> {
+    Ok(match self.find_signer(name).await? {
+      Some((info, url)) => Some(Box::new(GcpKmsSigner {
+        kms: self.kms.clone(),
+        url,
+        account: info.account_id()?,
+      })),
+      None => None,
+    })
+  }
+
+  async fn create_signer(&self, signer: &CreateSigner) -> Result<SignerInfo> {
+    if signer.secret_uri.is_some() {
+      return Err(Error::other(
+        "GCP_KMS signing manager doesn't support `secret_uri`.",
+      ));
+    }
+    self.create_key(&signer.name).await
+  }
+}
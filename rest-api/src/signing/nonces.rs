@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use polymesh_private_proof_shared::{error::*, CreateSigner, SignerInfo};
+
+use polymesh_api::client::{rpc_params, AccountId, Error as ClientError, Signer};
+use polymesh_api::Api;
+use sp_runtime::MultiSignature;
+
+use super::{AppSigningManager, SigningManagerTrait, TxSigner};
+use crate::nodes::AppNodes;
+
+/// Default seconds the chain's nonce can stall behind the allocated nonces, before they are
+/// resynced with the chain.
+const DEFAULT_NONCE_RESYNC_SECS: u64 = 30;
+
+/// Get the account's next nonce from the chain, including transactions in the pool.
+pub async fn next_nonce(api: &Api, account: &AccountId) -> Result<u32> {
+  Ok(
+    api
+      .client()
+      .request("system_accountNextIndex", rpc_params!(account.to_string()))
+      .await
+      .map_err(|err| Error::from(err))?,
+  )
+}
+
+struct NextNonce {
+  nonce: u32,
+  /// The chain's next nonce at the last allocation.
+  chain: u32,
+  /// When the chain's next nonce last advanced.
+  chain_updated: Instant,
+}
+
+/// Allocates nonces for concurrent transactions from the same account.
+///
+/// Each allocation takes the larger of the chain's next nonce and the last allocated nonce
+/// plus one, so parallel requests don't sign with the same nonce.  A nonce that wasn't
+/// used (i.e. signing failed) is given back.  If a signed transaction never reaches the
+/// pool, the chain's next nonce stops advancing towards the allocated nonces.  Once it
+/// hasn't advanced for `NONCE_RESYNC_SECS`, the nonce is resynced with the chain, also
+/// while new nonces are being allocated.
+pub struct NonceManager {
+  nodes: AppNodes,
+  resync: Duration,
+  nonces: Mutex<HashMap<AccountId, NextNonce>>,
+}
+
+impl NonceManager {
+  /// Load the config from `NONCE_RESYNC_SECS`.
+  pub fn from_env(nodes: AppNodes) -> Arc<Self> {
+    let resync = std::env::var("NONCE_RESYNC_SECS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_NONCE_RESYNC_SECS);
+    Arc::new(Self {
+      nodes,
+      resync: Duration::from_secs(resync),
+      nonces: Mutex::new(HashMap::new()),
+    })
+  }
+
+  /// Allocate the account's next nonce.
+  pub async fn allocate(&self, account: &AccountId) -> Result<u32> {
+    let chain = next_nonce(&self.nodes.api(), account).await?;
+    let mut nonces = self.nonces.lock().expect("Nonce lock poisoned");
+    let now = Instant::now();
+    let (nonce, chain_updated) = match nonces.get(account) {
+      // The chain is still catching up with the allocated nonces.
+      Some(next) if next.nonce > chain && chain > next.chain => (next.nonce, now),
+      Some(next) if next.nonce > chain && next.chain_updated.elapsed() < self.resync => {
+        (next.nonce, next.chain_updated)
+      }
+      Some(next) if next.nonce > chain => {
+        log::warn!(
+          "Resync nonce of {account}: chain {chain}, allocated {}",
+          next.nonce
+        );
+        (chain, now)
+      }
+      _ => (chain, now),
+    };
+    nonces.insert(
+      account.clone(),
+      NextNonce {
+        nonce: nonce + 1,
+        chain,
+        chain_updated,
+      },
+    );
+    Ok(nonce)
+  }
+
+  /// Give back an unused nonce.
+  fn release(&self, account: &AccountId, nonce: u32) {
+    let mut nonces = self.nonces.lock().expect("Nonce lock poisoned");
+    match nonces.get_mut(account) {
+      // Nothing was allocated after it.
+      Some(next) if next.nonce == nonce + 1 => {
+        next.nonce = nonce;
+      }
+      // Later nonces are in use, resync with the chain on the next allocation.
+      Some(_) => {
+        nonces.remove(account);
+      }
+      None => (),
+    }
+  }
+}
+
+/// Signer that gets its nonce from the `NonceManager`.
+pub struct ManagedNonceSigner {
+  inner: TxSigner,
+  nonces: Arc<NonceManager>,
+  /// Allocated nonce, until the transaction is signed with it.
+  nonce: Mutex<Option<u32>>,
+}
+
+impl ManagedNonceSigner {
+  pub fn new(inner: TxSigner, nonces: Arc<NonceManager>) -> Self {
+    Self {
+      inner,
+      nonces,
+      nonce: Mutex::new(None),
+    }
+  }
+}
+
+#[async_trait]
+impl Signer for ManagedNonceSigner {
+  fn account(&self) -> AccountId {
+    self.inner.account()
+  }
+
+  async fn nonce(&self) -> Option<u32> {
+    if let Some(nonce) = *self.nonce.lock().expect("Nonce lock poisoned") {
+      return Some(nonce);
+    }
+    let account = self.account();
+    match self.nonces.allocate(&account).await {
+      Ok(nonce) => {
+        *self.nonce.lock().expect("Nonce lock poisoned") = Some(nonce);
+        Some(nonce)
+      }
+      Err(err) => {
+        // Let the client get the nonce.
+        log::error!("Failed to allocate nonce for {account}: {err:?}");
+        None
+      }
+    }
+  }
+
+  async fn set_nonce(&mut self, nonce: u32) {
+    *self.nonce.lock().expect("Nonce lock poisoned") = None;
+    self.inner.set_nonce(nonce).await
+  }
+
+  async fn sign(&self, msg: &[u8]) -> Result<MultiSignature, ClientError> {
+    self.inner.sign(msg).await
+  }
+}
+
+impl Drop for ManagedNonceSigner {
+  fn drop(&mut self) {
+    if let Ok(Some(nonce)) = self.nonce.lock().map(|nonce| *nonce) {
+      self.nonces.release(&self.inner.account(), nonce);
+    }
+  }
+}
+
+/// Signing manager that wraps the transaction signers with a `ManagedNonceSigner`, so
+/// concurrent requests can use the same signer.
+pub struct NonceSigningManager {
+  inner: AppSigningManager,
+  nonces: Arc<NonceManager>,
+}
+
+impl NonceSigningManager {
+  pub fn new(inner: AppSigningManager, nonces: Arc<NonceManager>) -> Arc<Self> {
+    Arc::new(Self { inner, nonces })
+  }
+
+  fn wrap(&self, signer: Option<TxSigner>) -> Option<TxSigner> {
+    signer.map(|signer| Box::new(ManagedNonceSigner::new(signer, self.nonces.clone())) as TxSigner)
+  }
+}
+
+#[async_trait]
+impl SigningManagerTrait for NonceSigningManager {
+  async fn get_signers(&self) -> Result<Vec<SignerInfo>> {
+    self.inner.get_signers().await
+  }
+
+  async fn get_signer_info(&self, signer: &str) -> Result<Option<SignerInfo>> {
+    self.inner.get_signer_info(signer).await
+  }
+
+  async fn get_signer(&self, signer: &str) -> Result<Option<TxSigner>> {
+    Ok(self.wrap(self.inner.get_signer(signer).await?))
+  }
+
+  async fn get_tx_signer(&self, signer: &str, extrinsic: &str) -> Result<Option<TxSigner>> {
+    Ok(self.wrap(self.inner.get_tx_signer(signer, extrinsic).await?))
+  }
+
+  async fn create_signer(&self, signer: &CreateSigner) -> Result<SignerInfo> {
+    self.inner.create_signer(signer).await
+  }
+}
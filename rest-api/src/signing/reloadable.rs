@@ -0,0 +1,55 @@
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use polymesh_private_proof_shared::{error::Result, CreateSigner, SignerInfo};
+
+use super::{AppSigningManager, SigningManagerTrait, TxSigner};
+
+/// Signing manager that can be replaced at runtime (i.e. when the config is reloaded).
+pub struct ReloadableSigningManager {
+  inner: RwLock<AppSigningManager>,
+}
+
+impl ReloadableSigningManager {
+  pub fn new(inner: AppSigningManager) -> Arc<Self> {
+    Arc::new(Self {
+      inner: RwLock::new(inner),
+    })
+  }
+
+  /// Replace the signing manager.  Requests already using the old manager will finish with it.
+  pub fn replace(&self, inner: AppSigningManager) {
+    *self.inner.write().expect("Signing manager lock poisoned") = inner;
+  }
+
+  fn current(&self) -> AppSigningManager {
+    self
+      .inner
+      .read()
+      .expect("Signing manager lock poisoned")
+      .clone()
+  }
+}
+
+#[async_trait]
+impl SigningManagerTrait for ReloadableSigningManager {
+  async fn get_signers(&self) -> Result<Vec<SignerInfo>> {
+    self.current().get_signers().await
+  }
+
+  async fn get_signer_info(&self, signer: &str) -> Result<Option<SignerInfo>> {
+    self.current().get_signer_info(signer).await
+  }
+
+  async fn get_signer(&self, signer: &str) -> Result<Option<TxSigner>> {
+    self.current().get_signer(signer).await
+  }
+
+  async fn get_tx_signer(&self, signer: &str, extrinsic: &str) -> Result<Option<TxSigner>> {
+    self.current().get_tx_signer(signer, extrinsic).await
+  }
+
+  async fn create_signer(&self, signer: &CreateSigner) -> Result<SignerInfo> {
+    self.current().create_signer(signer).await
+  }
+}
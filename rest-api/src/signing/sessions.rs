@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  CreateSigner, SignerInfo,
+};
+
+use super::{AppSigningManager, SigningManagerTrait, TxSigner};
+use crate::repo::TransactionRepository;
+
+/// Adds time-boxed session signers (see `/signers/{signer}/sessions`) to a signing manager.
+///
+/// A session signer signs with its parent signer's key, but can only submit its extrinsics.
+/// Endpoints that don't say which extrinsic they submit (`get_signer`) refuse session signers.
+pub struct SessionSigningManager {
+  inner: AppSigningManager,
+  tx_repo: TransactionRepository,
+}
+
+impl SessionSigningManager {
+  pub fn new(inner: AppSigningManager, tx_repo: TransactionRepository) -> Arc<Self> {
+    Arc::new(Self { inner, tx_repo })
+  }
+}
+
+#[async_trait]
+impl SigningManagerTrait for SessionSigningManager {
+  async fn get_signers(&self) -> Result<Vec<SignerInfo>> {
+    self.inner.get_signers().await
+  }
+
+  async fn get_signer_info(&self, signer: &str) -> Result<Option<SignerInfo>> {
+    if let Some(info) = self.inner.get_signer_info(signer).await? {
+      return Ok(Some(info));
+    }
+    // A session signer has the parent signer's key.
+    match self.tx_repo.get_session_signer(signer).await? {
+      Some(session) => Ok(
+        self
+          .inner
+          .get_signer_info(&session.signer)
+          .await?
+          .map(|parent| SignerInfo {
+            name: session.name,
+            public_key: parent.public_key,
+            created_at: session.created_at,
+          }),
+      ),
+      None => Ok(None),
+    }
+  }
+
+  async fn get_signer(&self, signer: &str) -> Result<Option<TxSigner>> {
+    if let Some(tx_signer) = self.inner.get_signer(signer).await? {
+      return Ok(Some(tx_signer));
+    }
+    if self.tx_repo.get_session_signer(signer).await?.is_some() {
+      return Err(Error::forbidden("Session signers can't be used here"));
+    }
+    Ok(None)
+  }
+
+  async fn get_tx_signer(&self, signer: &str, extrinsic: &str) -> Result<Option<TxSigner>> {
+    if let Some(tx_signer) = self.inner.get_signer(signer).await? {
+      return Ok(Some(tx_signer));
+    }
+    let session = match self.tx_repo.get_session_signer(signer).await? {
+      Some(session) => session,
+      None => return Ok(None),
+    };
+    if !session.allows(extrinsic) {
+      log::warn!("Session signer {signer} isn't allowed to submit {extrinsic}");
+      return Err(Error::forbidden(&format!(
+        "Session signer isn't allowed to submit {extrinsic}"
+      )));
+    }
+    self.inner.get_signer(&session.signer).await
+  }
+
+  async fn create_signer(&self, signer: &CreateSigner) -> Result<SignerInfo> {
+    self.inner.create_signer(signer).await
+  }
+}
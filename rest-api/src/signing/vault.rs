@@ -8,17 +8,18 @@ use serde_with::{base64::Base64, serde_as};
 
 use actix_web::web::Data;
 
-use reqwest::{header, Client, Method, Url};
+use reqwest::{Client, Method, Url};
 
 use dashmap::DashMap;
 
 use async_trait::async_trait;
-use polymesh_private_proof_shared::{error::*, CreateSigner, SignerInfo};
+use polymesh_private_proof_shared::{error::*, CreateSigner, EnvConfig, SignerInfo};
 
 use polymesh_api::client::{AccountId, Error as ClientError, Signer};
 use sp_core::ed25519::Signature;
 use sp_runtime::MultiSignature;
 
+use super::vault_auth::VaultAuth;
 use super::{AppSigningManager, SigningManagerTrait, TxSigner};
 
 #[derive(Debug, Deserialize)]
@@ -178,6 +179,7 @@ impl ToString for NameVersion {
 
 pub struct VaultSigner {
   pub client: Client,
+  pub auth: Arc<VaultAuth>,
   pub url: Url,
   pub key_version: u64,
   pub account: AccountId,
@@ -189,7 +191,10 @@ impl VaultSigner {
       key_version: self.key_version,
       input: msg.into(),
     };
-    let resp = self.client.post(self.url.clone()).json(&req).send().await?;
+    let resp = self
+      .auth
+      .send(|| self.client.post(self.url.clone()).json(&req))
+      .await?;
     let signed = VaultResponse::<SignResponse>::from_response(resp)
       .await?
       .ok_or_else(|| Error::other("No signature from vault"))?;
@@ -221,47 +226,78 @@ impl Signer for VaultSigner {
 
 pub struct VaultSigningManager {
   client: Client,
-  list_url: Url,
+  auth: Arc<VaultAuth>,
+  /// Transit secrets engine mount, e.g. `http://127.0.0.1:8200/v1/transit`.
+  mount: Url,
   list: Method,
-  keys_base: Url,
-  sign_base: Url,
   keys: DashMap<NameVersion, SignerInfo>,
   cache: DashMap<AccountId, NameVersion>,
 }
 
 impl VaultSigningManager {
-  pub fn new(base: String, token: String) -> Result<Arc<dyn SigningManagerTrait>> {
-    let base = Url::parse(&base)?;
-    let mut headers = header::HeaderMap::new();
-    headers.insert("X-Vault-Token", header::HeaderValue::from_str(&token)?);
-    let client = Client::builder().default_headers(headers).build()?;
+  /// `mount` is the URL of the transit secrets engine, the mount path can have any number
+  /// of segments (e.g. `/v1/polymesh/transit`).
+  pub fn new(mount: Url, auth: Arc<VaultAuth>) -> Result<Arc<dyn SigningManagerTrait>> {
+    if mount.cannot_be_a_base() {
+      return Err(Error::Other(format!("Invalid Vault transit URL: {mount}")));
+    }
     Ok(Arc::new(Self {
-      client,
-      list_url: base.join("./keys")?,
+      client: Client::new(),
+      auth,
+      mount,
       list: Method::from_bytes(b"LIST")?,
-      keys_base: base.join("./keys/")?,
-      sign_base: base.join("./sign/")?,
       keys: DashMap::new(),
       cache: DashMap::new(),
     }))
   }
 
-  pub fn new_app_data(base: String, token: String) -> Result<AppSigningManager> {
-    Ok(Data::from(Self::new(base, token)?))
+  pub fn new_app_data(mount: Url, auth: Arc<VaultAuth>) -> Result<AppSigningManager> {
+    Ok(Data::from(Self::new(mount, auth)?))
+  }
+
+  /// The transit mount URL from `VAULT_TRANSIT_URL`, or `VAULT_TRANSIT_MOUNT` (default:
+  /// `transit`) of the `VAULT_ADDR` server.
+  pub fn mount_from_env(config: &EnvConfig) -> anyhow::Result<Url> {
+    if let Ok(url) = config.var("VAULT_TRANSIT_URL") {
+      return Ok(Url::parse(&url)?);
+    }
+    let mut url = Url::parse(&config.var("VAULT_ADDR")?)?;
+    let mount = config
+      .var("VAULT_TRANSIT_MOUNT")
+      .unwrap_or_else(|_| "transit".to_string());
+    url
+      .path_segments_mut()
+      .map_err(|_| anyhow::anyhow!("Invalid VAULT_ADDR"))?
+      .pop_if_empty()
+      .push("v1")
+      .extend(mount.split('/').filter(|s| !s.is_empty()));
+    Ok(url)
+  }
+
+  /// Url of `path` under the transit mount.
+  fn mount_url(&self, path: &[&str]) -> Result<Url> {
+    let mut url = self.mount.clone();
+    url
+      .path_segments_mut()
+      .map_err(|_| Error::other("Invalid Vault transit URL"))?
+      .pop_if_empty()
+      .extend(path);
+    Ok(url)
   }
 
   pub fn get_key_url(&self, key: &str) -> Result<Url> {
-    Ok(self.keys_base.join(key)?)
+    self.mount_url(&["keys", key])
   }
 
   pub fn get_sign_url(&self, key: &str) -> Result<Url> {
-    Ok(self.sign_base.join(key)?)
+    self.mount_url(&["sign", key])
   }
 
   fn info_to_vault_signer(&self, info: SignerInfo) -> Result<VaultSigner> {
     let name_version: NameVersion = info.name.parse().expect("Doesn't fail");
     Ok(VaultSigner {
       client: self.client.clone(),
+      auth: self.auth.clone(),
       url: self.get_sign_url(&name_version.name)?,
       key_version: name_version.version,
       account: info.account_id()?,
@@ -272,13 +308,16 @@ impl VaultSigningManager {
   where
     T: std::fmt::Debug + std::default::Default + de::DeserializeOwned,
   {
-    let resp = self.client.request(method, url).send().await?;
+    let resp = self
+      .auth
+      .send(|| self.client.request(method.clone(), url.clone()))
+      .await?;
     Ok(VaultResponse::from_response(resp).await?)
   }
 
   pub async fn fetch_keys(&self) -> Result<Vec<String>> {
     let data = self
-      .vault_request::<ListKeys>(self.list.clone(), self.list_url.clone())
+      .vault_request::<ListKeys>(self.list.clone(), self.mount_url(&["keys"])?)
       .await?;
     Ok(data.unwrap_or_default().keys)
   }
@@ -294,7 +333,10 @@ impl VaultSigningManager {
       ..Default::default()
     };
     let url = self.get_key_url(key)?;
-    let resp = self.client.post(url).json(&req).send().await?;
+    let resp = self
+      .auth
+      .send(|| self.client.post(url.clone()).json(&req))
+      .await?;
     Ok(VaultResponse::<ReadKey>::from_response(resp).await?)
   }
 
@@ -0,0 +1,329 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+
+use polymesh_private_proof_shared::{error::*, EnvConfig};
+
+/// Header with the Vault token.
+pub const VAULT_TOKEN_HEADER: &str = "X-Vault-Token";
+/// Header with the Vault Enterprise namespace.
+pub const VAULT_NAMESPACE_HEADER: &str = "X-Vault-Namespace";
+
+/// Minimum seconds between token renewals.
+const MIN_RENEW_SECS: u64 = 5;
+/// Seconds before retrying a failed renewal/login.
+const RETRY_SECS: u64 = 10;
+
+/// How the Vault signing manager authenticates.
+#[derive(Clone, Debug)]
+pub enum VaultAuthMethod {
+  /// Static token.
+  Token(String),
+  /// AppRole login.
+  AppRole { role_id: String, secret_id: String },
+  /// Kubernetes service account JWT login.
+  Kubernetes { role: String, jwt_path: String },
+}
+
+impl VaultAuthMethod {
+  /// Load the auth method from `VAULT_AUTH_METHOD`: `token` (default, `VAULT_TOKEN`),
+  /// `approle` (`VAULT_ROLE_ID` and `VAULT_SECRET_ID`) or `kubernetes` (`VAULT_K8S_ROLE`
+  /// and the service account JWT at `VAULT_K8S_JWT_PATH`).
+  pub fn from_env(config: &EnvConfig) -> anyhow::Result<Self> {
+    let method = config
+      .var("VAULT_AUTH_METHOD")
+      .unwrap_or_else(|_| "token".to_string());
+    match method.to_lowercase().as_str() {
+      "token" => Ok(Self::Token(config.var("VAULT_TOKEN")?)),
+      "approle" => Ok(Self::AppRole {
+        role_id: config.var("VAULT_ROLE_ID")?,
+        secret_id: config.var("VAULT_SECRET_ID")?,
+      }),
+      "kubernetes" => Ok(Self::Kubernetes {
+        role: config.var("VAULT_K8S_ROLE")?,
+        jwt_path: config
+          .var("VAULT_K8S_JWT_PATH")
+          .unwrap_or_else(|_| "/var/run/secrets/kubernetes.io/serviceaccount/token".to_string()),
+      }),
+      method => Err(anyhow::anyhow!("Unknown Vault auth method: {method:?}")),
+    }
+  }
+
+  fn default_mount(&self) -> Option<&'static str> {
+    match self {
+      Self::Token(_) => None,
+      Self::AppRole { .. } => Some("approle"),
+      Self::Kubernetes { .. } => Some("kubernetes"),
+    }
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthResponse {
+  #[serde(default)]
+  auth: Option<AuthInfo>,
+  #[serde(default)]
+  errors: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthInfo {
+  client_token: String,
+  #[serde(default)]
+  lease_duration: u64,
+  #[serde(default)]
+  renewable: bool,
+}
+
+/// Vault token of the signing manager.
+///
+/// With AppRole or Kubernetes auth the token is requested at the first Vault request.  The
+/// token is renewed in the background (at 2/3 of its lease), a token that can't be renewed
+/// is replaced by logging in again.  Static tokens (`VAULT_TOKEN`) are renewed until Vault
+/// refuses to renew them.
+///
+/// Requests rejected with `403 Forbidden` (e.g. the token expired) are retried once after
+/// logging in again.
+///
+/// All requests (including login and renewal) are sent to the Vault Enterprise namespace,
+/// if one is set.
+pub struct VaultAuth {
+  client: Client,
+  method: VaultAuthMethod,
+  namespace: Option<String>,
+  login_url: Option<Url>,
+  renew_url: Url,
+  token: RwLock<Option<AuthInfo>>,
+}
+
+impl VaultAuth {
+  /// `addr` is the Vault server address, the auth method is mounted at `mount` (default:
+  /// `approle` or `kubernetes`) of the `namespace`.
+  pub fn new(
+    addr: &Url,
+    mount: Option<String>,
+    namespace: Option<String>,
+    method: VaultAuthMethod,
+  ) -> Result<Arc<Self>> {
+    let login_url = match mount.as_deref().or(method.default_mount()) {
+      Some(mount) => Some(addr.join(&format!("/v1/auth/{}/login", mount.trim_matches('/')))?),
+      None => None,
+    };
+    let token = match &method {
+      VaultAuthMethod::Token(token) => Some(AuthInfo {
+        client_token: token.clone(),
+        lease_duration: 0,
+        // Unknown until the first renewal.
+        renewable: true,
+      }),
+      _ => None,
+    };
+    let auth = Arc::new(Self {
+      client: Client::new(),
+      login_url,
+      renew_url: addr.join("/v1/auth/token/renew-self")?,
+      method,
+      namespace,
+      token: RwLock::new(token),
+    });
+    Self::spawn_renewal(Arc::downgrade(&auth));
+    Ok(auth)
+  }
+
+  /// Load the auth method from the env (see `VaultAuthMethod::from_env`).  The Vault server
+  /// address is `VAULT_ADDR` (default: the origin of `transit_url`), the auth method's mount
+  /// path is `VAULT_AUTH_MOUNT` and the namespace is `VAULT_NAMESPACE`.
+  pub fn from_env(config: &EnvConfig, transit_url: &Url) -> anyhow::Result<Arc<Self>> {
+    let addr = match config.var("VAULT_ADDR") {
+      Ok(addr) => Url::parse(&addr)?,
+      Err(_) => Url::parse(&transit_url.origin().ascii_serialization())?,
+    };
+    let mount = config.var("VAULT_AUTH_MOUNT").ok();
+    let namespace = config
+      .var("VAULT_NAMESPACE")
+      .ok()
+      .filter(|ns| !ns.is_empty());
+    Ok(Self::new(
+      &addr,
+      mount,
+      namespace,
+      VaultAuthMethod::from_env(config)?,
+    )?)
+  }
+
+  /// Add the namespace header, if a namespace is set.
+  fn with_namespace(&self, req: RequestBuilder) -> RequestBuilder {
+    match &self.namespace {
+      Some(namespace) => req.header(VAULT_NAMESPACE_HEADER, namespace),
+      None => req,
+    }
+  }
+
+  /// The current token.  Logs in if there is no token yet.
+  pub async fn token(&self) -> Result<String> {
+    if let Some(info) = self.token.read().await.as_ref() {
+      return Ok(info.client_token.clone());
+    }
+    self.login().await?;
+    self
+      .token
+      .read()
+      .await
+      .as_ref()
+      .map(|info| info.client_token.clone())
+      .ok_or_else(|| Error::other("No Vault token"))
+  }
+
+  /// Send a Vault request with the token.  If the token is rejected, authenticate again
+  /// and retry once.
+  pub async fn send(&self, req: impl Fn() -> RequestBuilder) -> Result<Response> {
+    let token = self.token().await?;
+    let resp = self
+      .with_namespace(req())
+      .header(VAULT_TOKEN_HEADER, &token)
+      .send()
+      .await?;
+    if resp.status() != StatusCode::FORBIDDEN {
+      return Ok(resp);
+    }
+    log::warn!("Vault rejected the token, authenticating again");
+    self.reauthenticate(&token).await?;
+    let token = self.token().await?;
+    Ok(
+      self
+        .with_namespace(req())
+        .header(VAULT_TOKEN_HEADER, &token)
+        .send()
+        .await?,
+    )
+  }
+
+  /// Replace the `rejected` token, unless another request already replaced it.
+  async fn reauthenticate(&self, rejected: &str) -> Result<()> {
+    let current = self
+      .token
+      .read()
+      .await
+      .as_ref()
+      .map(|info| info.client_token.clone());
+    if current.as_deref() != Some(rejected) {
+      return Ok(());
+    }
+    if self.login_url.is_none() {
+      return Err(Error::other(
+        "Vault rejected `VAULT_TOKEN`, it might have expired",
+      ));
+    }
+    self.login().await?;
+    Ok(())
+  }
+
+  fn login_body(&self) -> Result<serde_json::Value> {
+    Ok(match &self.method {
+      VaultAuthMethod::Token(_) => Err(Error::other("Token auth doesn't login"))?,
+      VaultAuthMethod::AppRole { role_id, secret_id } => {
+        json!({ "role_id": role_id, "secret_id": secret_id })
+      }
+      VaultAuthMethod::Kubernetes { role, jwt_path } => {
+        let jwt = std::fs::read_to_string(jwt_path)
+          .map_err(|err| Error::Other(format!("Failed to read {jwt_path}: {err:?}")))?;
+        json!({ "role": role, "jwt": jwt.trim() })
+      }
+    })
+  }
+
+  async fn auth_request(&self, req: RequestBuilder) -> Result<AuthInfo> {
+    let res: AuthResponse = self.with_namespace(req).send().await?.json().await?;
+    match res {
+      AuthResponse {
+        errors: Some(errors),
+        ..
+      } => Err(Error::Other(format!("Vault auth error: {errors:?}"))),
+      AuthResponse {
+        auth: Some(auth), ..
+      } => Ok(auth),
+      AuthResponse { auth: None, .. } => Err(Error::other("Empty Vault auth response")),
+    }
+  }
+
+  /// Login and replace the token.  Returns the token's lease duration.
+  pub async fn login(&self) -> Result<Duration> {
+    let url = self
+      .login_url
+      .clone()
+      .ok_or_else(|| Error::other("Token auth doesn't login"))?;
+    let body = self.login_body()?;
+    let info = self.auth_request(self.client.post(url).json(&body)).await?;
+    log::info!("Logged in to Vault (lease: {}s)", info.lease_duration);
+    let lease = Duration::from_secs(info.lease_duration);
+    *self.token.write().await = Some(info);
+    Ok(lease)
+  }
+
+  /// Renew the token, or login again if it can't be renewed.  Returns the token's lease
+  /// duration, or `None` if the token doesn't need to (or can't) be renewed.
+  async fn renew_or_login(&self) -> Result<Option<Duration>> {
+    let token = self
+      .token
+      .read()
+      .await
+      .as_ref()
+      .filter(|info| info.renewable)
+      .map(|info| info.client_token.clone());
+    if let Some(token) = token {
+      let req = self
+        .client
+        .post(self.renew_url.clone())
+        .header(VAULT_TOKEN_HEADER, token);
+      match self.auth_request(req).await {
+        Ok(info) => {
+          log::debug!("Renewed Vault token (lease: {}s)", info.lease_duration);
+          let lease = Duration::from_secs(info.lease_duration);
+          *self.token.write().await = Some(info);
+          // Tokens without a TTL don't expire.
+          return Ok(Some(lease).filter(|lease| !lease.is_zero()));
+        }
+        Err(err) if self.login_url.is_none() => {
+          log::warn!("Vault token can't be renewed: {err:?}");
+          if let Some(info) = self.token.write().await.as_mut() {
+            info.renewable = false;
+          }
+          return Ok(None);
+        }
+        Err(err) => {
+          log::warn!("Failed to renew Vault token, logging in again: {err:?}");
+        }
+      }
+    }
+    if self.login_url.is_none() {
+      return Ok(None);
+    }
+    Ok(Some(self.login().await?))
+  }
+
+  fn spawn_renewal(auth: Weak<Self>) {
+    actix_web::rt::spawn(async move {
+      loop {
+        // Stop when the signing manager is dropped (e.g. replaced by a config reload).
+        let lease = match auth.upgrade() {
+          Some(auth) => auth.renew_or_login().await,
+          None => break,
+        };
+        let wait = match lease {
+          Ok(Some(lease)) => Duration::from_secs((lease.as_secs() * 2 / 3).max(MIN_RENEW_SECS)),
+          Ok(None) => break,
+          Err(err) => {
+            log::error!("Failed to login to Vault: {err:?}");
+            Duration::from_secs(RETRY_SECS)
+          }
+        };
+        actix_web::rt::time::sleep(wait).await;
+      }
+    });
+  }
+}
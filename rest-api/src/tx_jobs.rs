@@ -0,0 +1,188 @@
+use std::future::Future;
+
+use actix_web::web::Data;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_api::TransactionResults;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  TransactionResult, TxJob, TxJobRow,
+};
+
+use crate::repo::TransactionRepository;
+
+pub type AppTxJobs = Data<TxJobs>;
+
+/// Seconds between checks for jobs orphaned by a restart, run by the scheduler.
+pub const RECOVERY_INTERVAL: u64 = 30;
+/// Seconds before an orphaned job is failed, if its transaction wasn't found on-chain.
+const RECOVERY_TIMEOUT: i64 = 3600;
+
+/// Query parameters of the transaction endpoints.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct TxJobQuery {
+  /// Don't wait for the results.  Return a job (`202 Accepted`) to track the transaction
+  /// with `/tx/jobs/{job_id}` instead.  A job is also returned if the transaction isn't
+  /// finalized within `FINALIZATION_TIMEOUT`.
+  #[serde(default, rename = "async")]
+  pub run_async: bool,
+}
+
+/// Results of a transaction, or the job tracking it.
+pub enum TxJobOutcome {
+  Done(TransactionResult),
+  Queued(TxJob),
+}
+
+/// Background jobs for long-running transactions.
+///
+/// Waiting for a transaction (especially for finalization) can hold the HTTP request for
+/// a long time.  With `async=true` the transaction endpoints return a job as soon as the
+/// transaction is submitted, and the job tracks its inclusion, finalization and results.
+/// A transaction that isn't finalized within `FINALIZATION_TIMEOUT` is also handed over
+/// to a job, so its results are still processed once it is finalized.
+///
+/// Jobs are stored in the database.  Jobs orphaned by a restart are completed from the
+/// transactions recorded by the chain watcher, but the endpoint's own processing of the
+/// results (i.e. updating tracked balances) is skipped.
+pub struct TxJobs {
+  tx_repo: TransactionRepository,
+  started_at: chrono::NaiveDateTime,
+}
+
+impl TxJobs {
+  pub fn new_app_data(tx_repo: TransactionRepository) -> AppTxJobs {
+    Data::new(Self {
+      tx_repo,
+      started_at: chrono::Utc::now().naive_utc(),
+    })
+  }
+
+  /// Wait for the transaction's results and `process` them, or if `query` asks for it
+  /// return a job that does that in the background.  Also returns a job if the transaction
+  /// isn't finalized within the finalization timeout.
+  ///
+  /// `process` is always called, also if waiting for the results failed, so it can release
+  /// anything reserved for the transaction.
+  pub async fn wait_for_results<F, Fut>(
+    &self,
+    query: &TxJobQuery,
+    operation: &str,
+    mut tx_res: TransactionResults,
+    finalize: bool,
+    process: F,
+  ) -> Result<TxJobOutcome>
+  where
+    F: FnOnce(Result<TransactionResult>) -> Fut + 'static,
+    Fut: Future<Output = Result<TransactionResult>> + 'static,
+  {
+    if !query.run_async {
+      let res = match TransactionResult::finalization_timeout().filter(|_| finalize) {
+        Some(timeout) => {
+          let finalized = actix_web::rt::time::timeout(timeout, tx_res.wait_finalized()).await;
+          match finalized {
+            Ok(Ok(block_hash)) => TransactionResult::from_tx_results(tx_res, block_hash).await,
+            Ok(Err(err)) => Err(Error::from(err)),
+            // Don't hold the connection, the job processes the results on finalization.
+            Err(_) => return self.start_job(operation, tx_res, finalize, process).await,
+          }
+        }
+        None => TransactionResult::wait_for_results(tx_res, finalize).await,
+      };
+      return Ok(TxJobOutcome::Done(process(res).await?));
+    }
+    self.start_job(operation, tx_res, finalize, process).await
+  }
+
+  /// Track the transaction and `process` its results in the background.
+  async fn start_job<F, Fut>(
+    &self,
+    operation: &str,
+    tx_res: TransactionResults,
+    finalize: bool,
+    process: F,
+  ) -> Result<TxJobOutcome>
+  where
+    F: FnOnce(Result<TransactionResult>) -> Fut + 'static,
+    Fut: Future<Output = Result<TransactionResult>> + 'static,
+  {
+    let tx_hash = format!("{:#x}", tx_res.hash());
+    let job = match self
+      .tx_repo
+      .create_tx_job(operation, &tx_hash, finalize)
+      .await
+    {
+      Ok(job) => job,
+      Err(err) => return process(Err(err)).await.map(TxJobOutcome::Done),
+    };
+    let tx_repo = self.tx_repo.clone();
+    let job_id = job.job_id;
+    actix_web::rt::spawn(async move {
+      let res = track_tx(&tx_repo, job_id, tx_res, finalize).await;
+      let res = match process(res).await {
+        Ok(res) => tx_repo.tx_job_completed(job_id, &res).await,
+        Err(err) => {
+          log::error!("Transaction job {job_id} failed: {err:?}");
+          tx_repo.tx_job_failed(job_id, &err.to_string()).await
+        }
+      };
+      if let Err(err) = res {
+        log::error!("Failed to update transaction job {job_id}: {err:?}");
+      }
+    });
+    Ok(TxJobOutcome::Queued(TxJob::from_row(job)?))
+  }
+
+  /// Recover the jobs orphaned by a restart now.
+  pub async fn recover_jobs(&self) -> Result<()> {
+    let now = chrono::Utc::now().naive_utc();
+    for job in self.tx_repo.get_unfinished_tx_jobs().await? {
+      // Jobs created since the start are still tracked.  Multi-step jobs are resumed by
+      // their workflow.
+      if job.created_at >= self.started_at || job.progress.is_some() {
+        continue;
+      }
+      self.recover_job(&job, now).await?;
+    }
+    Ok(())
+  }
+
+  async fn recover_job(&self, job: &TxJobRow, now: chrono::NaiveDateTime) -> Result<()> {
+    match self.tx_repo.get_block_transaction(&job.tx_hash).await? {
+      Some(rec) => {
+        log::info!("Recovered transaction job {}", job.job_id);
+        let res = rec.to_tx_result()?;
+        self.tx_repo.tx_job_completed(job.job_id, &res).await?;
+      }
+      None if (now - job.created_at).num_seconds() > RECOVERY_TIMEOUT => {
+        log::warn!("Transaction job {} timed out", job.job_id);
+        self
+          .tx_repo
+          .tx_job_failed(job.job_id, "Transaction not found on-chain")
+          .await?;
+      }
+      None => (),
+    }
+    Ok(())
+  }
+}
+
+async fn track_tx(
+  tx_repo: &TransactionRepository,
+  job_id: i64,
+  mut tx_res: TransactionResults,
+  finalize: bool,
+) -> Result<TransactionResult> {
+  let mut block_hash = tx_res.wait_in_block().await?;
+  if let Some(block_hash) = &block_hash {
+    tx_repo
+      .tx_job_in_block(job_id, &format!("{block_hash:#x}"))
+      .await?;
+  }
+  if finalize {
+    block_hash = tx_res.wait_finalized().await?;
+  }
+  TransactionResult::from_tx_results(tx_res, block_hash).await
+}
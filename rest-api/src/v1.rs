@@ -1,12 +1,49 @@
 use actix_web::web;
 
+pub mod account_signers;
+pub mod audit_reports;
+pub mod auto_apply;
+pub mod compromise;
+pub mod config;
+pub mod contacts;
+pub mod deposits;
+pub mod events;
+pub mod imports;
+pub mod invoices;
+pub mod leases;
+pub mod ledger;
+pub mod maintenance;
+pub mod nodes;
+pub mod payloads;
+pub mod scheduler;
 pub mod signers;
 pub mod tx;
+pub mod wallet_pairings;
+pub mod watcher;
+pub mod webhooks;
+pub mod ws;
 
 pub fn service(cfg: &mut web::ServiceConfig) {
   cfg.service(
     web::scope("/v1")
+      .configure(account_signers::service)
+      .configure(audit_reports::service)
+      .configure(auto_apply::service)
+      .configure(compromise::service)
+      .configure(config::service)
+      .configure(contacts::service)
+      .configure(deposits::service)
+      .configure(events::service)
+      .configure(imports::service)
+      .configure(invoices::service)
+      .configure(ledger::service)
+      .configure(maintenance::service)
+      .configure(payloads::service)
       .configure(signers::service)
-      .configure(tx::service),
+      .configure(tx::service)
+      .configure(wallet_pairings::service)
+      .configure(watcher::service)
+      .configure(webhooks::service)
+      .configure(ws::service),
   );
 }
@@ -0,0 +1,105 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{error::Error, CreateAccountSigner};
+
+use crate::nodes::AppNodes;
+use crate::repo::TransactionRepository;
+use crate::signing::AppSigningManager;
+use crate::v1::signers::get_signer_did;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_account_signers)
+    .service(get_account_signer)
+    .service(create_account_signer)
+    .service(delete_account_signer);
+}
+
+/// Get the default signers of our accounts.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [AccountSigner])
+  )
+)]
+#[get("/account_signers")]
+pub async fn get_account_signers(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let signers = tx_repo.get_account_signers().await?;
+  Ok(HttpResponse::Ok().json(signers))
+}
+
+/// Get the default signer of one of our accounts.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AccountSigner)
+  )
+)]
+#[get("/account_signers/{confidential_account}")]
+pub async fn get_account_signer(
+  confidential_account: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let signer = tx_repo
+    .get_account_signer(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Account signer"))?;
+  Ok(HttpResponse::Ok().json(signer))
+}
+
+/// Set the default signer of one of our accounts, or change it.
+///
+/// The account's transaction requests (`/tx/accounts/{public_key}/...`) can then omit
+/// `signer`.  The signer is checked like one given in the request, so session signers can
+/// still only submit their extrinsics.  Once the account is on-chain, the signer must be a
+/// key of the account's identity.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AccountSigner)
+  )
+)]
+#[post("/account_signers")]
+pub async fn create_account_signer(
+  req: web::Json<CreateAccountSigner>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  signing: AppSigningManager,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  repo
+    .get_account(&hex::encode(req.confidential_account.0))
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  // Also checks that the signer exists.
+  let api = nodes.api();
+  let signer_did = get_signer_did(&req.signer, signing, &api).await?;
+  let account_did = api
+    .query()
+    .confidential_asset()
+    .account_did(req.confidential_account.as_confidential_account()?)
+    .await
+    .map_err(|err| Error::from(err))?;
+  if let Some(account_did) = account_did {
+    if signer_did != Some(account_did) {
+      return Err(Error::forbidden("The signer isn't a key of the account's identity").into());
+    }
+  }
+  let signer = tx_repo.set_account_signer(&req).await?;
+  Ok(HttpResponse::Ok().json(signer))
+}
+
+/// Remove the default signer of one of our accounts.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/account_signers/{confidential_account}")]
+pub async fn delete_account_signer(
+  confidential_account: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  if !tx_repo.delete_account_signer(&confidential_account).await? {
+    return Err(Error::not_found("Account signer").into());
+  }
+  Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder, Result};
+use uuid::Uuid;
+
+use polymesh_private_proof_api::{
+  formatting::amount_formatter, receipts::AppReceiptSigner, repo::Repository,
+};
+use polymesh_private_proof_shared::{
+  error::Error, AuditReport, AuditReportRequest, FormatQuery, ProcessedEvent, SettlementLegFilter,
+  SignedAuditReport,
+};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(asset_audit_report);
+}
+
+/// Verify all settlement sender proofs of an asset with the issuer's auditor account.
+///
+/// The sender proofs are taken from the settlement events recorded by the chain watcher.
+/// The report is signed when receipt signing is enabled (see `/receipts/verify`).  With
+/// `locale` the report includes formatted amounts.
+#[utoipa::path(
+  params(FormatQuery),
+  responses(
+    (status = 200, body = SignedAuditReport)
+  )
+)]
+#[post("/assets/{asset_id}/audit_report")]
+pub async fn asset_audit_report(
+  asset_id: web::Path<Uuid>,
+  req: web::Json<AuditReportRequest>,
+  format: web::Query<FormatQuery>,
+  http_req: HttpRequest,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  receipts: AppReceiptSigner,
+) -> Result<impl Responder> {
+  let asset_id = asset_id.into_inner();
+  let auditor = repo
+    .get_account_with_secret(&hex::encode(req.auditor.0))
+    .await?
+    .ok_or_else(|| Error::not_found("Auditor account"))?;
+  auditor.ensure_unlocked()?;
+
+  let mut report = AuditReport::new(asset_id, &req);
+  let mut settlement_legs = BTreeMap::new();
+  for rec in tx_repo.get_asset_affirmations(asset_id, &req).await? {
+    let affirmed = match serde_json::from_str(&rec.event)? {
+      ProcessedEvent::ConfidentialTransactionAffirmed(affirmed) => affirmed,
+      _ => continue,
+    };
+    let proofs = match affirmed.transfer_proofs {
+      Some(proofs) => proofs,
+      None => continue,
+    };
+    let leg_id = affirmed.leg_id.0 as u32;
+    for (proof_asset_id, proof) in &proofs.proofs {
+      if *proof_asset_id != asset_id {
+        continue;
+      }
+      if !settlement_legs.contains_key(&rec.settlement_id) {
+        let filter = SettlementLegFilter {
+          settlement_id: Some(rec.settlement_id),
+          ..Default::default()
+        };
+        let legs = tx_repo.get_settlement_legs(&filter).await?;
+        settlement_legs.insert(rec.settlement_id, legs);
+      }
+      // The auditor id is the auditor's index in the leg's (sorted) auditors of the asset.
+      let auditor_id = settlement_legs[&rec.settlement_id]
+        .iter()
+        .find(|leg| leg.leg_id == leg_id)
+        .and_then(|leg| leg.leg.assets_and_auditors.get(&asset_id))
+        .and_then(|auditors| auditors.iter().position(|key| key == &req.auditor));
+      match auditor_id {
+        Some(auditor_id) => {
+          let res = auditor.auditor_verify_sender_proof(proof, auditor_id as u32, None)?;
+          report.add_proof(rec.settlement_id, leg_id, rec.block_number, res);
+        }
+        None => report.skipped_count += 1,
+      }
+    }
+  }
+
+  if let Some(formatter) = amount_formatter(&repo, &format).await? {
+    report.format_amounts(&formatter);
+  }
+
+  let receipt = receipts.sign(&http_req, &report, None)?;
+  Ok(HttpResponse::Ok().json(SignedAuditReport { report, receipt }))
+}
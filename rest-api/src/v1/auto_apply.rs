@@ -0,0 +1,75 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{error::Error, CreateAutoApplyAccount};
+
+use crate::repo::TransactionRepository;
+use crate::signing::AppSigningManager;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_auto_apply_accounts)
+    .service(create_auto_apply_account)
+    .service(delete_auto_apply_account);
+}
+
+/// Get the accounts with their incoming balances applied automatically.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [AutoApplyAccount])
+  )
+)]
+#[get("/auto_apply_accounts")]
+pub async fn get_auto_apply_accounts(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let accounts = tx_repo.get_auto_apply_accounts().await?;
+  Ok(HttpResponse::Ok().json(accounts))
+}
+
+/// Apply one of our account's incoming balances automatically, or change its signer.
+///
+/// When the chain watcher sees an incoming deposit to the account, the signer submits
+/// `apply_incoming_balance` and the account's local balance is updated once the transaction
+/// is included.  No need to poll `incoming_balance` and apply it manually.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AutoApplyAccount)
+  )
+)]
+#[post("/auto_apply_accounts")]
+pub async fn create_auto_apply_account(
+  req: web::Json<CreateAutoApplyAccount>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  signing: AppSigningManager,
+) -> Result<impl Responder> {
+  repo
+    .get_account(&hex::encode(req.confidential_account.0))
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  signing
+    .get_signer_info(&req.signer)
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let account = tx_repo.set_auto_apply_account(&req).await?;
+  Ok(HttpResponse::Ok().json(account))
+}
+
+/// Stop applying an account's incoming balances automatically.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/auto_apply_accounts/{confidential_account}")]
+pub async fn delete_auto_apply_account(
+  confidential_account: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  if !tx_repo
+    .delete_auto_apply_account(&confidential_account)
+    .await?
+  {
+    return Err(Error::not_found("Auto-apply account").into());
+  }
+  Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,67 @@
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_api::{approvals::AppApprovals, repo::Repository};
+use polymesh_private_proof_shared::{
+  error::Error, ApprovalOperation, KeyCompromiseRequest, PublicKey,
+};
+
+use crate::key_compromise::{AppKeyCompromiseJobs, KeyCompromiseJobs};
+use crate::signing::AppSigningManager;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(key_compromised);
+}
+
+/// Respond to a compromised confidential account.
+///
+/// Starts a job (`202 Accepted`) that locks the account, creates a replacement account and
+/// moves the full balance of each asset to it with a self-settlement.  The job's `progress`
+/// (see `/tx/jobs/{job_id}`) shows how far the workflow got, so it can be finished by hand
+/// if a step fails.
+///
+/// Needs a second user's approval when `key_rotation` is in `APPROVAL_OPERATIONS`
+/// (see `/approvals`).
+#[utoipa::path(
+  responses(
+    (status = 202, description = "Key compromise job, or approval", body = TxJob)
+  )
+)]
+#[post("/admin/accounts/{public_key}/compromised")]
+pub async fn key_compromised(
+  path: web::Path<String>,
+  req: web::Json<KeyCompromiseRequest>,
+  repo: Repository,
+  signing: AppSigningManager,
+  jobs: AppKeyCompromiseJobs,
+  approvals: AppApprovals,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let public_key = path.into_inner();
+  let compromised_account = PublicKey::from_str(&public_key)?;
+  // Dual control.
+  if approvals.requires_approval(ApprovalOperation::KeyRotation) {
+    let pending = approvals
+      .authorize(
+        &repo,
+        &http_req,
+        ApprovalOperation::KeyRotation,
+        &public_key,
+        &*req,
+      )
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
+    }
+  }
+  repo
+    .get_account(&public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  signing
+    .get_signer_info(&req.signer)
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+
+  let job = KeyCompromiseJobs::start(&jobs, compromised_account, req.into_inner()).await?;
+  Ok(HttpResponse::Accepted().json(job))
+}
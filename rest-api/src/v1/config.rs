@@ -0,0 +1,26 @@
+use actix_web::{post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::error::Error;
+
+use crate::reload::AppReloader;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(reload_config);
+}
+
+/// Reload the config and rebuild the signing manager, CORS policy and balance conflict
+/// strategy.
+///
+/// The same as sending `SIGHUP` to the server.
+#[utoipa::path(
+  responses(
+    (status = 200, description = "Config reloaded")
+  )
+)]
+#[post("/admin/config/reload")]
+pub async fn reload_config(reloader: AppReloader) -> Result<impl Responder> {
+  reloader
+    .reload()
+    .map_err(|err| Error::Other(format!("Failed to reload config: {err:?}")))?;
+  Ok(HttpResponse::Ok().finish())
+}
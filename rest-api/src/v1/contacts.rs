@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::{
+  error::Error, Contact, CreateContact, PublicKey, SettlementLeg, UpdateContact,
+};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_all_contacts)
+    .service(get_contact)
+    .service(create_contact)
+    .service(update_contact)
+    .service(delete_contact);
+}
+
+/// Get all counterparty contacts.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [Contact])
+  )
+)]
+#[get("/contacts")]
+pub async fn get_all_contacts(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let contacts = tx_repo.get_contacts().await?;
+  Ok(HttpResponse::Ok().json(contacts))
+}
+
+/// Get the contact of a counterparty confidential account.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Contact)
+  )
+)]
+#[get("/contacts/{confidential_account}")]
+pub async fn get_contact(
+  confidential_account: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let contact = tx_repo
+    .get_contact(&confidential_account)
+    .await?
+    .ok_or_else(|| Error::not_found("Contact"))?;
+  Ok(HttpResponse::Ok().json(contact))
+}
+
+/// Add a counterparty contact.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Contact)
+  )
+)]
+#[post("/contacts")]
+pub async fn create_contact(
+  req: web::Json<CreateContact>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let contact = tx_repo
+    .create_contact(&req)
+    .await?
+    .ok_or_else(|| Error::other("The account already has a contact"))?;
+  Ok(HttpResponse::Ok().json(contact))
+}
+
+/// Update the contact of a counterparty confidential account.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Contact)
+  )
+)]
+#[put("/contacts/{confidential_account}")]
+pub async fn update_contact(
+  confidential_account: web::Path<String>,
+  req: web::Json<UpdateContact>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let contact = tx_repo
+    .update_contact(&confidential_account, &req)
+    .await?
+    .ok_or_else(|| Error::not_found("Contact"))?;
+  Ok(HttpResponse::Ok().json(contact))
+}
+
+/// Remove the contact of a counterparty confidential account.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/contacts/{confidential_account}")]
+pub async fn delete_contact(
+  confidential_account: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  if !tx_repo.delete_contact(&confidential_account).await? {
+    return Err(Error::not_found("Contact").into());
+  }
+  Ok(HttpResponse::Ok().finish())
+}
+
+/// Add the sender and receiver contacts to settlement legs.
+pub async fn add_leg_contacts(
+  tx_repo: &TransactionRepository,
+  legs: &mut [SettlementLeg],
+) -> Result<()> {
+  let mut contacts = BTreeMap::new();
+  for leg in legs {
+    leg.sender_contact = lookup_contact(tx_repo, &mut contacts, &leg.leg.sender).await?;
+    leg.receiver_contact = lookup_contact(tx_repo, &mut contacts, &leg.leg.receiver).await?;
+  }
+  Ok(())
+}
+
+async fn lookup_contact(
+  tx_repo: &TransactionRepository,
+  contacts: &mut BTreeMap<PublicKey, Option<Contact>>,
+  key: &PublicKey,
+) -> Result<Option<Contact>> {
+  if let Some(contact) = contacts.get(key) {
+    return Ok(contact.clone());
+  }
+  let contact = tx_repo.get_contact(&hex::encode(key.0)).await?;
+  contacts.insert(key.clone(), contact.clone());
+  Ok(contact)
+}
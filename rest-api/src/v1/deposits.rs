@@ -0,0 +1,100 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{error::Error, CreateDepositAccount};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_deposit_accounts)
+    .service(create_deposit_account)
+    .service(delete_deposit_account)
+    .service(get_deposits);
+}
+
+/// Get the deposit accounts.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [DepositAccount])
+  )
+)]
+#[get("/deposit_accounts")]
+pub async fn get_deposit_accounts(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let accounts = tx_repo.get_deposit_accounts().await?;
+  Ok(HttpResponse::Ok().json(accounts))
+}
+
+/// Register one of our accounts as a deposit account.
+///
+/// When a sender affirms a leg to a deposit account, the chain watcher verifies the sender
+/// proof as the receiver and credits the decrypted amount to the settlement's reference (the
+/// memo of the invoice it pays, or the settlement memo).  A `Deposit` webhook is sent for
+/// each credited amount.
+#[utoipa::path(
+  responses(
+    (status = 200, body = DepositAccount)
+  )
+)]
+#[post("/deposit_accounts")]
+pub async fn create_deposit_account(
+  req: web::Json<CreateDepositAccount>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  repo
+    .get_account(&hex::encode(req.confidential_account.0))
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  let account = tx_repo.create_deposit_account(&req).await?;
+  Ok(HttpResponse::Ok().json(account))
+}
+
+/// Stop crediting transfers to a deposit account.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/deposit_accounts/{confidential_account}")]
+pub async fn delete_deposit_account(
+  confidential_account: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  if !tx_repo
+    .delete_deposit_account(&confidential_account)
+    .await?
+  {
+    return Err(Error::not_found("Deposit account").into());
+  }
+  Ok(HttpResponse::Ok().finish())
+}
+
+/// Deposit filter.  All filters must match.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct DepositFilter {
+  /// Only deposits to this deposit account.
+  pub account: Option<String>,
+  /// Only deposits credited to this reference.
+  pub reference: Option<String>,
+}
+
+/// Get the credited deposits.
+#[utoipa::path(
+  params(DepositFilter),
+  responses(
+    (status = 200, body = [Deposit])
+  )
+)]
+#[get("/deposits")]
+pub async fn get_deposits(
+  filter: web::Query<DepositFilter>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let deposits = tx_repo
+    .get_deposits(filter.account.as_deref(), filter.reference.as_deref())
+    .await?;
+  Ok(HttpResponse::Ok().json(deposits))
+}
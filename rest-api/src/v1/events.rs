@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use actix_web::{get, web, Responder, Result};
+use actix_web_lab::sse::Sse;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use polymesh_private_proof_shared::PublicKey;
+
+use crate::event_stream::AppEventStream;
+
+/// Seconds between keep-alive messages.
+const KEEP_ALIVE: u64 = 15;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(stream_events);
+}
+
+/// Chain event stream filter.  All filters must match.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct EventStreamFilter {
+  /// Only events involving this confidential account (as sender, receiver or auditor).
+  pub account: Option<String>,
+  /// Only events involving this asset.
+  pub asset_id: Option<Uuid>,
+}
+
+/// Stream the events processed by the chain watcher (Server-Sent Events).
+///
+/// Each event is sent as a `chain_event` message with a `ChainEvent`.  A `lagged` message
+/// with the number of dropped events is sent if the client can't keep up.
+#[utoipa::path(
+  params(EventStreamFilter),
+  responses(
+    (status = 200, description = "`text/event-stream` of `ChainEvent`s")
+  )
+)]
+#[get("/events/stream")]
+pub async fn stream_events(
+  filter: web::Query<EventStreamFilter>,
+  event_stream: AppEventStream,
+) -> Result<impl Responder> {
+  let filter = filter.into_inner();
+  let account = filter
+    .account
+    .as_deref()
+    .map(PublicKey::from_str)
+    .transpose()?;
+  let events = event_stream.subscribe(account, filter.asset_id);
+  Ok(Sse::from_stream(events).with_keep_alive(Duration::from_secs(KEEP_ALIVE)))
+}
@@ -0,0 +1,157 @@
+use actix_web::{post, rt::pin, web, HttpResponse, Responder, Result};
+use futures_util::StreamExt;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use polymesh_api::Api;
+
+use polymesh_private_proof_api::{
+  key_store::{AccountKeyStore, VaultKvStore},
+  repo::Repository,
+};
+use polymesh_private_proof_shared::{
+  error::Error, scale_convert, AccountAssetImportedBalance, AccountAssetIncomingBalance,
+  AccountWithSecret, AddAsset, BalanceAction, BalanceSource, CreateAccount, ImportAccountsRequest,
+  ImportedAccount, PublicKey, UpdateAccountAsset,
+};
+
+use crate::nodes::AppNodes;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(import_accounts);
+}
+
+/// Import confidential accounts from their secret keys (from the request and/or a Vault KV
+/// path) and populate their balances from the chain.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [ImportedAccount])
+  )
+)]
+#[post("/admin/import/accounts")]
+pub async fn import_accounts(
+  req: web::Json<ImportAccountsRequest>,
+  repo: Repository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let mut secrets = Vec::with_capacity(req.secrets.len());
+  for secret in &req.secrets {
+    let secret = secret.strip_prefix("0x").unwrap_or(secret);
+    secrets.push(Zeroizing::new(hex::decode(secret).map_err(Error::from)?));
+  }
+  if let Some(path) = &req.vault_path {
+    let store = VaultKvStore::from_env()?;
+    for key_ref in store.list_secrets(path).await? {
+      secrets.push(store.get_secret(&key_ref).await?);
+    }
+  }
+
+  let api = nodes.api();
+  let mut imported = Vec::with_capacity(secrets.len());
+  for secret in &secrets {
+    let create = CreateAccount::from_secret(secret)?;
+    let public_key = hex::encode(&create.confidential_account);
+    // Add the account if it doesn't exist.
+    let existing = repo.get_account_with_secret(&public_key).await?.is_some();
+    if !existing {
+      repo.create_account(&create).await?;
+    }
+    let account = repo
+      .get_account_with_secret(&public_key)
+      .await?
+      .ok_or_else(|| Error::not_found("Account"))?;
+    let confidential_account = PublicKey::from_str(&public_key)?;
+    imported.push(import_account(&repo, &api, account, confidential_account, existing).await?);
+  }
+
+  Ok(HttpResponse::Ok().json(imported))
+}
+
+async fn import_account(
+  repo: &Repository,
+  api: &Api,
+  account_with_secret: AccountWithSecret,
+  confidential_account: PublicKey,
+  existing: bool,
+) -> Result<ImportedAccount> {
+  let account = account_with_secret.as_confidential_account()?;
+
+  // Get all asset balances for this account.
+  let balances = api
+    .paged_query()
+    .confidential_asset()
+    .account_balance(account)
+    .entries();
+  pin!(balances);
+  let mut imported_balances = Vec::new();
+  while let Some(balance) = balances.next().await {
+    match balance {
+      Ok((asset_id, Some(enc_balance))) => {
+        let asset_id = Uuid::from_bytes(asset_id);
+        let enc_balance = scale_convert(&enc_balance);
+        let balance = account_with_secret.decrypt(&enc_balance)?;
+        // Make sure the asset exists.
+        if repo.get_asset(asset_id).await?.is_none() {
+          repo
+            .create_asset(&AddAsset {
+              asset_id,
+              ..Default::default()
+            })
+            .await?;
+        }
+        repo
+          .update_account_asset(&UpdateAccountAsset {
+            account_asset_id: None,
+            account_id: account_with_secret.account_id,
+            asset_id,
+            balance,
+            enc_balance,
+            block_number: None,
+            source: BalanceSource::Chain,
+            base_enc_balance: None,
+            action: BalanceAction::Refresh,
+            amount: None,
+            history_source: Some("POST /admin/import/accounts".into()),
+          })
+          .await?;
+        imported_balances.push(AccountAssetImportedBalance { asset_id, balance });
+      }
+      Ok((_, None)) => (),
+      Err(err) => {
+        Err(Error::from(err))?;
+      }
+    }
+  }
+
+  // Get all incoming balances for this account.
+  let incoming = api
+    .paged_query()
+    .confidential_asset()
+    .incoming_balance(account)
+    .entries();
+  pin!(incoming);
+  let mut incoming_balances = Vec::new();
+  while let Some(incoming) = incoming.next().await {
+    match incoming {
+      Ok((asset_id, Some(amount))) => {
+        let enc_amount = scale_convert(&amount);
+        let amount = account_with_secret.decrypt(&enc_amount)?;
+        incoming_balances.push(AccountAssetIncomingBalance {
+          asset_id: Uuid::from_bytes(asset_id),
+          incoming_amount: amount,
+        });
+      }
+      Ok((_, None)) => (),
+      Err(err) => {
+        Err(Error::from(err))?;
+      }
+    }
+  }
+
+  Ok(ImportedAccount {
+    confidential_account,
+    existing,
+    balances: imported_balances,
+    incoming_balances,
+  })
+}
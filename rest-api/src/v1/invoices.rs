@@ -0,0 +1,112 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{error::Error, CreateInvoice};
+
+use crate::repo::TransactionRepository;
+
+/// Default seconds until an invoice expires.
+const DEFAULT_INVOICE_TTL: u64 = 86400;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_invoices)
+    .service(get_invoice)
+    .service(create_invoice)
+    .service(cancel_invoice);
+}
+
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct InvoiceFilter {
+  /// Only invoices of this receiver.
+  pub receiver: Option<String>,
+}
+
+/// Get payment requests (invoices).
+#[utoipa::path(
+  params(InvoiceFilter),
+  responses(
+    (status = 200, body = [Invoice])
+  )
+)]
+#[get("/invoices")]
+pub async fn get_invoices(
+  filter: web::Query<InvoiceFilter>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let invoices = tx_repo.get_invoices(filter.receiver.as_deref()).await?;
+  Ok(HttpResponse::Ok().json(invoices))
+}
+
+/// Get a payment request (invoice) by its reference.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Invoice)
+  )
+)]
+#[get("/invoices/{reference}")]
+pub async fn get_invoice(
+  reference: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let invoice = tx_repo
+    .get_invoice(&reference)
+    .await?
+    .ok_or_else(|| Error::not_found("Invoice"))?;
+  Ok(HttpResponse::Ok().json(invoice))
+}
+
+/// Create a payment request (invoice) for one of our accounts.
+///
+/// Share the returned `reference` with the sender.  The sender pays the invoice with
+/// `/tx/accounts/{public_key}/invoices/{reference}/pay`, the chain watcher marks it paid
+/// when the settlement is executed.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Invoice)
+  )
+)]
+#[post("/invoices")]
+pub async fn create_invoice(
+  req: web::Json<CreateInvoice>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  if req.amount == 0 {
+    return Err(Error::other("The invoice amount must be more than zero").into());
+  }
+  repo
+    .get_account(&hex::encode(req.receiver.0))
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  repo
+    .get_asset(req.asset_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Asset"))?;
+
+  // The reference is used as the settlement memo (32 bytes).
+  let reference = format!("0x{}", hex::encode(rand::random::<[u8; 32]>()));
+  let expires_in = req.expires_in.unwrap_or(DEFAULT_INVOICE_TTL);
+  let invoice = tx_repo.create_invoice(&reference, &req, expires_in).await?;
+  Ok(HttpResponse::Ok().json(invoice))
+}
+
+/// Cancel an open payment request (invoice).
+#[utoipa::path(
+  responses(
+    (status = 200, body = Invoice)
+  )
+)]
+#[post("/invoices/{reference}/cancel")]
+pub async fn cancel_invoice(
+  reference: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let invoice = tx_repo
+    .cancel_invoice(&reference)
+    .await?
+    .ok_or_else(|| Error::not_found("Open invoice"))?;
+  Ok(HttpResponse::Ok().json(invoice))
+}
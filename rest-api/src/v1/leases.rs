@@ -0,0 +1,22 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_leases);
+}
+
+/// Get the leases of the background tasks, with the instance running each task.
+///
+/// Only used with `DISTRIBUTED_LOCKS=true`.  An expired lease is taken over by the next
+/// instance that tries to acquire it.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [Lease])
+  )
+)]
+#[get("/admin/leases")]
+pub async fn get_leases(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let leases = tx_repo.get_leases().await?;
+  Ok(HttpResponse::Ok().json(leases))
+}
@@ -0,0 +1,39 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_trial_balance)
+    .service(get_ledger_account_entries);
+}
+
+/// Get the trial balance of all ledger accounts.
+///
+/// The total debits and credits over all ledger accounts are always equal.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [TrialBalance])
+  )
+)]
+#[get("/ledger/trial_balance")]
+pub async fn get_trial_balance(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let balances = tx_repo.get_trial_balance().await?;
+  Ok(HttpResponse::Ok().json(balances))
+}
+
+/// Get the ledger entries of a confidential account (hex) or system account
+/// (`issuance`, `burn`, `counterparty`).
+#[utoipa::path(
+  responses(
+    (status = 200, body = [LedgerEntry])
+  )
+)]
+#[get("/ledger/accounts/{ledger_account}")]
+pub async fn get_ledger_account_entries(
+  path: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let entries = tx_repo.get_ledger_entries(&path).await?;
+  Ok(HttpResponse::Ok().json(entries))
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_shared::SetMaintenanceMode;
+
+use crate::maintenance::AppMaintenance;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_maintenance_mode)
+    .service(set_maintenance_mode);
+}
+
+/// Get the maintenance mode status.
+#[utoipa::path(
+  responses(
+    (status = 200, body = MaintenanceStatus)
+  )
+)]
+#[get("/admin/maintenance")]
+pub async fn get_maintenance_mode(maintenance: AppMaintenance) -> Result<impl Responder> {
+  Ok(HttpResponse::Ok().json(maintenance.status()))
+}
+
+/// Enable or disable maintenance mode.
+///
+/// When enabling, the response is returned after in-flight requests have finished
+/// (or `drain_timeout` has passed).  Check `in_flight` in the response.
+#[utoipa::path(
+  responses(
+    (status = 200, body = MaintenanceStatus)
+  )
+)]
+#[post("/admin/maintenance")]
+pub async fn set_maintenance_mode(
+  req: web::Json<SetMaintenanceMode>,
+  maintenance: AppMaintenance,
+) -> Result<impl Responder> {
+  let status = maintenance
+    .set(
+      req.enabled,
+      req.retry_after,
+      Duration::from_secs(req.drain_timeout as u64),
+    )
+    .await?;
+  Ok(HttpResponse::Ok().json(status))
+}
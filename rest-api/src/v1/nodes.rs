@@ -0,0 +1,20 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use crate::nodes::AppNodes;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_nodes);
+}
+
+/// Get the health of the Polymesh nodes, in order of preference.
+///
+/// Requests and the chain watcher use the `current` node, the first healthy node.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [NodeStatus])
+  )
+)]
+#[get("/admin/nodes")]
+pub async fn get_nodes(nodes: AppNodes) -> Result<impl Responder> {
+  Ok(HttpResponse::Ok().json(nodes.status()))
+}
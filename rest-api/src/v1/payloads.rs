@@ -0,0 +1,125 @@
+use std::io::Cursor;
+
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use image::{DynamicImage, ImageOutputFormat, Luma};
+use qrcode::{render::svg, QrCode};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{error::Error, EncodedWalletPayload, PublicKey, WalletPayload};
+
+use crate::repo::TransactionRepository;
+
+/// Minimum size of QR code images (pixels).
+const QR_MIN_SIZE: u32 = 256;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_account_payload)
+    .service(get_invoice_payload)
+    .service(decode_payload);
+}
+
+/// Wallet payload format.
+#[derive(Clone, Copy, Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+  /// JSON with the payload string.
+  #[default]
+  Text,
+  /// QR code PNG image.
+  Png,
+  /// QR code SVG image.
+  Svg,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct PayloadQuery {
+  /// Response format (default: `text`).
+  #[serde(default)]
+  #[param(inline)]
+  pub format: PayloadFormat,
+}
+
+fn payload_response(payload: &WalletPayload, format: PayloadFormat) -> Result<HttpResponse> {
+  let payload = payload.to_payload();
+  let qr = |payload: &str| {
+    QrCode::new(payload.as_bytes())
+      .map_err(|err| Error::Other(format!("Failed to generate QR code: {err:?}")))
+  };
+  Ok(match format {
+    PayloadFormat::Text => HttpResponse::Ok().json(EncodedWalletPayload { payload }),
+    PayloadFormat::Svg => {
+      let svg = qr(&payload)?
+        .render::<svg::Color>()
+        .min_dimensions(QR_MIN_SIZE, QR_MIN_SIZE)
+        .build();
+      HttpResponse::Ok().content_type("image/svg+xml").body(svg)
+    }
+    PayloadFormat::Png => {
+      let image = qr(&payload)?
+        .render::<Luma<u8>>()
+        .min_dimensions(QR_MIN_SIZE, QR_MIN_SIZE)
+        .build();
+      let mut png = Vec::new();
+      DynamicImage::ImageLuma8(image)
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|err| Error::Other(format!("Failed to encode QR code: {err:?}")))?;
+      HttpResponse::Ok().content_type("image/png").body(png)
+    }
+  })
+}
+
+/// Get the wallet payload (or QR code) of one of our accounts, for receiving assets.
+#[utoipa::path(
+  params(PayloadQuery),
+  responses(
+    (status = 200, body = EncodedWalletPayload)
+  )
+)]
+#[get("/payloads/accounts/{public_key}")]
+pub async fn get_account_payload(
+  public_key: web::Path<PublicKey>,
+  query: web::Query<PayloadQuery>,
+  repo: Repository,
+) -> Result<impl Responder> {
+  let public_key = public_key.into_inner();
+  repo
+    .get_account(&hex::encode(public_key.0))
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  payload_response(&WalletPayload::account(public_key), query.format)
+}
+
+/// Get the wallet payload (or QR code) of a payment request (invoice).
+#[utoipa::path(
+  params(PayloadQuery),
+  responses(
+    (status = 200, body = EncodedWalletPayload)
+  )
+)]
+#[get("/payloads/invoices/{reference}")]
+pub async fn get_invoice_payload(
+  reference: web::Path<String>,
+  query: web::Query<PayloadQuery>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let invoice = tx_repo
+    .get_invoice(&reference)
+    .await?
+    .ok_or_else(|| Error::not_found("Invoice"))?;
+  payload_response(&WalletPayload::invoice(&invoice)?, query.format)
+}
+
+/// Decode a wallet payload.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletPayload)
+  )
+)]
+#[post("/payloads/decode")]
+pub async fn decode_payload(req: web::Json<EncodedWalletPayload>) -> Result<impl Responder> {
+  let payload = WalletPayload::from_payload(&req.payload)?;
+  Ok(HttpResponse::Ok().json(payload))
+}
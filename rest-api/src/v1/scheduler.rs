@@ -0,0 +1,21 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_scheduled_jobs);
+}
+
+/// Get the periodic background jobs, with their next run and last error.
+///
+/// Failed runs are retried with a backoff, `attempts` counts the consecutive failures.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [ScheduledJob])
+  )
+)]
+#[get("/admin/scheduled_jobs")]
+pub async fn get_scheduled_jobs(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let jobs = tx_repo.get_scheduled_jobs().await?;
+  Ok(HttpResponse::Ok().json(jobs))
+}
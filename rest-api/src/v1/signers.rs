@@ -1,13 +1,19 @@
-use actix_web::{get, post, rt::pin, web, HttpResponse, Responder, Result};
+use actix_web::{delete, get, post, rt::pin, web, HttpRequest, HttpResponse, Responder, Result};
 use futures_util::StreamExt;
 
-use polymesh_private_proof_shared::{error::Error, CreateSigner};
+use polymesh_private_proof_api::{approvals::AppApprovals, repo::Repository};
+use polymesh_private_proof_shared::{
+  error::Error, ApprovalOperation, CreateSessionSigner, CreateSigner, SetSignerBudget,
+};
+use uuid::Uuid;
 
 use polymesh_api::Api;
 use polymesh_api::{
   client::basic_types::IdentityId, types::polymesh_primitives::secondary_key::KeyRecord,
 };
 
+use crate::nodes::AppNodes;
+use crate::repo::TransactionRepository;
 use crate::signing::AppSigningManager;
 
 pub fn service(cfg: &mut web::ServiceConfig) {
@@ -16,7 +22,13 @@ pub fn service(cfg: &mut web::ServiceConfig) {
     .service(get_signer)
     .service(create_signer)
     .service(get_signer_identity)
-    .service(get_signer_venues);
+    .service(get_signer_venues)
+    .service(get_signer_budget)
+    .service(set_signer_budget)
+    .service(get_signer_usage)
+    .service(get_session_signers)
+    .service(create_session_signer)
+    .service(delete_session_signer);
 }
 
 /// Get all signers.
@@ -82,8 +94,9 @@ pub async fn get_signer_did(
 pub async fn get_signer_identity(
   signer: web::Path<String>,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  nodes: AppNodes,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let did = get_signer_did(&signer, signing, &api)
     .await?
     .map(|did| format!("{did:?}"));
@@ -100,8 +113,9 @@ pub async fn get_signer_identity(
 pub async fn get_signer_venues(
   signer: web::Path<String>,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  nodes: AppNodes,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let did = get_signer_did(&signer, signing, &api).await?;
   let venues = match did {
     Some(did) => {
@@ -126,16 +140,173 @@ pub async fn get_signer_venues(
 }
 
 /// Create a new signer.
+///
+/// Needs a second user's approval when `create_signer` is in `APPROVAL_OPERATIONS`
+/// (see `/approvals`).
 #[utoipa::path(
   responses(
-    (status = 200, body = SignerInfo)
+    (status = 200, body = SignerInfo),
+    (status = 202, body = Approval)
   )
 )]
 #[post("/signers")]
 pub async fn create_signer(
   signer: web::Json<CreateSigner>,
   signing: AppSigningManager,
+  repo: Repository,
+  approvals: AppApprovals,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  // Dual control.
+  if approvals.requires_approval(ApprovalOperation::CreateSigner) {
+    let pending = approvals
+      .authorize(
+        &repo,
+        &http_req,
+        ApprovalOperation::CreateSigner,
+        &signer.name,
+        &*signer,
+      )
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
+    }
+  }
+
   let signer = signing.create_signer(&signer).await?;
   Ok(HttpResponse::Ok().json(signer))
 }
+
+/// Get the signer's spending budget.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SignerBudget)
+  )
+)]
+#[get("/signers/{signer}/budget")]
+pub async fn get_signer_budget(
+  signer: web::Path<String>,
+  signing: AppSigningManager,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let signer = signing
+    .get_signer_info(&signer)
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = tx_repo
+    .get_signer_budget(&signer.public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Signer budget"))?;
+  Ok(HttpResponse::Ok().json(budget))
+}
+
+/// Set the signer's spending budget.
+///
+/// Transactions submitted by the signer are rejected with `403 Forbidden` once a limit is reached.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SignerBudget)
+  )
+)]
+#[post("/signers/{signer}/budget")]
+pub async fn set_signer_budget(
+  signer: web::Path<String>,
+  req: web::Json<SetSignerBudget>,
+  signing: AppSigningManager,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let signer = signing
+    .get_signer_info(&signer)
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = tx_repo.set_signer_budget(&signer.public_key, &req).await?;
+  Ok(HttpResponse::Ok().json(budget))
+}
+
+/// Get the signer's usage (extrinsics submitted today and total fees paid).
+#[utoipa::path(
+  responses(
+    (status = 200, body = SignerUsage)
+  )
+)]
+#[get("/signers/{signer}/usage")]
+pub async fn get_signer_usage(
+  signer: web::Path<String>,
+  signing: AppSigningManager,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let signer = signing
+    .get_signer_info(&signer)
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let usage = tx_repo.get_signer_usage(&signer.public_key).await?;
+  Ok(HttpResponse::Ok().json(usage))
+}
+
+/// Get the signer's session signers.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [SessionSigner])
+  )
+)]
+#[get("/signers/{signer}/sessions")]
+pub async fn get_session_signers(
+  signer: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let sessions = tx_repo.get_session_signers(&signer).await?;
+  Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// Create a time-boxed session signer.
+///
+/// The session signer signs with the signer's key, but can only submit the listed extrinsics
+/// until it expires.  Use its name as the `signer` of transaction requests.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SessionSigner)
+  )
+)]
+#[post("/signers/{signer}/sessions")]
+pub async fn create_session_signer(
+  signer: web::Path<String>,
+  req: web::Json<CreateSessionSigner>,
+  signing: AppSigningManager,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  req.validate()?;
+  // Session signers can't create more session signers.
+  if tx_repo.get_session_signer(&signer).await?.is_some() {
+    return Err(Error::forbidden("Session signers can't create session signers").into());
+  }
+  signing
+    .get_signer_info(&signer)
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let name = format!("session-{}", Uuid::new_v4().simple());
+  let session = tx_repo.create_session_signer(&name, &signer, &req).await?;
+  log::info!(
+    "Session signer {name} for {signer} can submit {:?} until {}",
+    session.extrinsics,
+    session.expires_at
+  );
+  Ok(HttpResponse::Ok().json(session))
+}
+
+/// Revoke a session signer.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/signers/{signer}/sessions/{session}")]
+pub async fn delete_session_signer(
+  path: web::Path<(String, String)>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let (signer, session) = path.into_inner();
+  if !tx_repo.delete_session_signer(&signer, &session).await? {
+    return Err(Error::not_found("Session signer").into());
+  }
+  Ok(HttpResponse::Ok().finish())
+}
@@ -3,7 +3,25 @@ use actix_web::web;
 pub mod account_assets;
 pub mod accounts;
 pub mod assets;
+pub mod identities;
+pub mod invoices;
+pub mod jobs;
+pub mod offline;
+pub mod outbox;
+pub mod settlements;
+pub mod transactions;
+pub mod wallet_requests;
 
 pub fn service(cfg: &mut web::ServiceConfig) {
-  cfg.configure(assets::service).configure(accounts::service);
+  cfg
+    .configure(assets::service)
+    .configure(accounts::service)
+    .configure(identities::service)
+    .configure(invoices::service)
+    .configure(jobs::service)
+    .configure(offline::service)
+    .configure(outbox::service)
+    .configure(settlements::service)
+    .configure(transactions::service)
+    .configure(wallet_requests::service);
 }
@@ -1,7 +1,15 @@
-use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use std::collections::BTreeSet;
+use std::time::Instant;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+use codec::Encode;
+use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
+use polymesh_api::client::{rpc_params, BlockHash};
 use polymesh_api::types::{
+  confidential_assets::burn::ConfidentialBurnProof as BurnProof,
   confidential_assets::transaction::ConfidentialTransferProof as SenderProof,
   pallet_confidential_asset::{
     AffirmLeg, AffirmParty, AffirmTransaction, AffirmTransactions, ConfidentialTransfers,
@@ -9,14 +17,35 @@ use polymesh_api::types::{
 };
 use polymesh_api::Api;
 
-use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_api::{
+  anomalies::{request_user, AppAnomalies},
+  approvals::AppApprovals,
+  limits::{check_amount_limits, AmountLimitCheck, AmountReservation},
+  position_locks::check_position_locks,
+  proof_pools::AppProofPools,
+  receipts::AppReceiptSigner,
+  replay::ReplayProtection,
+  repo::Repository,
+  screening::AppScreening,
+  warnings::add_warning,
+};
 use polymesh_private_proof_shared::{
-  auditor_account_to_key, confidential_account_to_key, error::Error, scale_convert,
-  AffirmTransactionLegRequest, DecryptedIncomingBalance, MintRequest, TransactionArgs,
-  TransactionResult,
+  account_balance_key, auditor_account_to_key, confidential_account_to_key, error::Error,
+  incoming_balance_key, scale_convert, spawn_proof, AddAsset, AddProof,
+  AffirmTransactionLegRequest, ApprovalOperation, BalanceAction, BalanceSource, BurnRequest,
+  DecryptedBalanceAtBlock, DecryptedIncomingBalance, MintRequest, ProofOperation, ProofStats,
+  PublicKey, ReconciledBalance, StorageReadProof, TransactionArgs, TransactionAssetAmount,
+  UpdateAccountAsset, WARNING_BALANCE_DRIFT,
 };
 
-use crate::signing::AppSigningManager;
+use crate::budgets::AppSignerBudgets;
+use crate::dry_run::dry_run;
+use crate::nodes::AppNodes;
+use crate::outbox::{AppTxOutbox, TxSubmission};
+use crate::repo::TransactionRepository;
+use crate::screening::screen_chain_receiver;
+use crate::signing::{resolve_signer, AppSigningManager};
+use crate::tx_jobs::{AppTxJobs, TxJobOutcome, TxJobQuery};
 
 pub fn service(cfg: &mut web::ServiceConfig) {
   cfg
@@ -24,28 +53,47 @@ pub fn service(cfg: &mut web::ServiceConfig) {
     .service(tx_receiver_affirm_leg)
     .service(tx_apply_incoming)
     .service(get_incoming_balance)
-    .service(tx_mint);
+    .service(get_balance_at_block)
+    .service(reconcile_balance)
+    .service(repair_balance)
+    .service(tx_mint)
+    .service(tx_burn);
 }
 
 /// Affirm confidential asset settlement leg as the receiver.
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
-#[post("/tx/accounts/{public_key}/assets/{asset_id}/receiver_affirm_leg")]
+#[post(
+  "/tx/accounts/{public_key}/assets/{asset_id}/receiver_affirm_leg",
+  wrap = "ReplayProtection"
+)]
 pub async fn tx_receiver_affirm_leg(
   path: web::Path<(String, Uuid)>,
   req: web::Json<AffirmTransactionLegRequest>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let (public_key, _asset_id) = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "affirm_transactions")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
   // Get the account.
   let _account = repo
     .get_account(&public_key)
@@ -63,23 +111,77 @@ pub async fn tx_receiver_affirm_leg(
       party: AffirmParty::Receiver,
     },
   }]);
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .affirm_transactions(affirms)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "receiver_affirm_leg",
+      &signer_name,
+      "affirm_transactions",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "receiver_affirm_leg",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Include a storage proof of the on-chain value.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct StorageProofQuery {
+  /// Include a storage proof.  The proof can be verified against the block hash.
+  #[serde(default)]
+  pub proof: bool,
+}
 
-  Ok(HttpResponse::Ok().json(res))
+impl StorageProofQuery {
+  /// The latest finalized block if a proof was requested.  The proof must be for the same
+  /// block as the value.
+  pub async fn block(&self, api: &Api) -> Result<Option<BlockHash>, Error> {
+    if !self.proof {
+      return Ok(None);
+    }
+    let block: BlockHash = api
+      .client()
+      .request("chain_getFinalizedHead", rpc_params!())
+      .await
+      .map_err(|err| Error::from(err))?;
+    Ok(Some(block))
+  }
 }
 
 /// Query chain for an account's incoming balance.
+///
+/// With `proof=true` the balance is read at the latest finalized block.
 #[utoipa::path(
+  params(StorageProofQuery),
   responses(
     (status = 200, body = DecryptedIncomingBalance)
   )
@@ -87,20 +189,28 @@ pub async fn tx_receiver_affirm_leg(
 #[get("/tx/accounts/{public_key}/assets/{asset_id}/incoming_balance")]
 pub async fn get_incoming_balance(
   path: web::Path<(String, Uuid)>,
+  query: web::Query<StorageProofQuery>,
   repo: Repository,
-  api: web::Data<Api>,
+  nodes: AppNodes,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let (public_key, asset_id) = path.into_inner();
+  let public_key = repo.resolve_asset_account(&public_key, asset_id).await?;
   // Get the account.
   let account_with_secret = repo
     .get_account_with_secret(&public_key)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
 
   let account = account_with_secret.as_confidential_account()?;
+  let block = query.block(&api).await?;
+  let chain_query = match block {
+    Some(block) => api.query_at(block),
+    None => api.query(),
+  };
   // Get incoming balance.
-  let enc_incoming = api
-    .query()
+  let enc_incoming = chain_query
     .confidential_asset()
     .incoming_balance(account, *asset_id.as_bytes())
     .await
@@ -114,33 +224,260 @@ pub async fn get_incoming_balance(
     None
   };
 
-  Ok(HttpResponse::Ok().json(DecryptedIncomingBalance { incoming_balance }))
+  let proof = match block {
+    Some(block) => {
+      let key = PublicKey::from_str(&public_key)?;
+      let key = incoming_balance_key(&key.0, asset_id.as_bytes());
+      Some(StorageReadProof::fetch(&api, block, key).await?)
+    }
+    None => None,
+  };
+
+  Ok(HttpResponse::Ok().json(DecryptedIncomingBalance {
+    incoming_balance,
+    proof,
+  }))
+}
+
+/// Query chain for an account's balance at a historical block and decrypt it.
+///
+/// Can be used to cross-check the locally tracked balance history against the chain.
+#[utoipa::path(
+  params(StorageProofQuery),
+  responses(
+    (status = 200, body = DecryptedBalanceAtBlock)
+  )
+)]
+#[get("/tx/accounts/{public_key}/assets/{asset_id}/balance_at/{block_hash}")]
+pub async fn get_balance_at_block(
+  path: web::Path<(String, Uuid, String)>,
+  query: web::Query<StorageProofQuery>,
+  repo: Repository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let (public_key, asset_id, block_hash) = path.into_inner();
+  let public_key = repo.resolve_asset_account(&public_key, asset_id).await?;
+  let hash =
+    hex::decode(block_hash.strip_prefix("0x").unwrap_or(&block_hash)).map_err(Error::from)?;
+  if hash.len() != 32 {
+    Err(Error::other("Invalid block hash"))?;
+  }
+  let block = BlockHash::from_slice(&hash);
+  // Get the account.
+  let account_with_secret = repo
+    .get_account_with_secret(&public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
+
+  let account = account_with_secret.as_confidential_account()?;
+  // Get the encrypted balance at the block.
+  let enc_balance = api
+    .query_at(block)
+    .confidential_asset()
+    .account_balance(account, *asset_id.as_bytes())
+    .await
+    .map_err(|err| Error::from(err))?
+    .map(|enc| scale_convert(&enc));
+
+  // Decrypt balance.
+  let balance = if let Some(enc_balance) = enc_balance {
+    Some(account_with_secret.decrypt(&enc_balance)?)
+  } else {
+    None
+  };
+
+  let proof = if query.proof {
+    let key = PublicKey::from_str(&public_key)?;
+    let key = account_balance_key(&key.0, asset_id.as_bytes());
+    Some(StorageReadProof::fetch(&api, block, key).await?)
+  } else {
+    None
+  };
+
+  Ok(HttpResponse::Ok().json(DecryptedBalanceAtBlock {
+    block_hash: format!("{block:#x}"),
+    balance,
+    proof,
+  }))
+}
+
+/// Compare an account's locally tracked balance with its on-chain balance.
+///
+/// The on-chain encrypted balance is decrypted with the account's key.  Nothing is changed,
+/// use `POST` to repair a local balance that doesn't match.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ReconciledBalance)
+  )
+)]
+#[get("/tx/accounts/{public_key}/assets/{asset_id}/reconcile")]
+pub async fn reconcile_balance(
+  path: web::Path<(String, Uuid)>,
+  repo: Repository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let (public_key, asset_id) = path.into_inner();
+  let reconciled = reconcile(&repo, &nodes, &public_key, asset_id, false).await?;
+  Ok(HttpResponse::Ok().json(reconciled))
+}
+
+/// Compare an account's locally tracked balance with its on-chain balance, and rewrite the
+/// local balance if it doesn't match (subject to the balance conflict strategy).
+///
+/// The on-chain encrypted balance is decrypted with the account's key.
+#[utoipa::path(
+  responses(
+    (status = 200, body = ReconciledBalance)
+  )
+)]
+#[post(
+  "/tx/accounts/{public_key}/assets/{asset_id}/reconcile",
+  wrap = "ReplayProtection"
+)]
+pub async fn repair_balance(
+  path: web::Path<(String, Uuid)>,
+  repo: Repository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let (public_key, asset_id) = path.into_inner();
+  let reconciled = reconcile(&repo, &nodes, &public_key, asset_id, true).await?;
+  Ok(HttpResponse::Ok().json(reconciled))
+}
+
+async fn reconcile(
+  repo: &Repository,
+  nodes: &AppNodes,
+  public_key: &str,
+  asset_id: Uuid,
+  repair: bool,
+) -> Result<ReconciledBalance> {
+  let api = nodes.api();
+  let public_key = repo.resolve_asset_account(public_key, asset_id).await?;
+  // Get the account.
+  let account_with_secret = repo
+    .get_account_with_secret(&public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
+
+  let account = account_with_secret.as_confidential_account()?;
+  // Get the on-chain encrypted balance.
+  let enc_balance = api
+    .query()
+    .confidential_asset()
+    .account_balance(account, *asset_id.as_bytes())
+    .await
+    .map_err(|err| Error::from(err))?
+    .map(|enc| scale_convert(&enc));
+  let chain_balance = match &enc_balance {
+    Some(enc_balance) => Some(account_with_secret.decrypt(enc_balance)?),
+    None => None,
+  };
+
+  // Compare with the local balance.
+  let local = repo.get_account_asset(&public_key, asset_id).await?;
+  let local_balance = local.as_ref().map(|local| local.balance as u64);
+  let diff = chain_balance.unwrap_or_default() as i64 - local_balance.unwrap_or_default() as i64;
+  let matches = match (&local, &enc_balance) {
+    (Some(local), Some(enc_balance)) => local.enc_balance == enc_balance.encode(),
+    (None, None) => true,
+    // An untracked asset or a zero balance that isn't on-chain yet.
+    (local, None) => local
+      .as_ref()
+      .map(|local| local.balance == 0)
+      .unwrap_or(true),
+    (None, Some(_)) => false,
+  };
+
+  let mut repaired = false;
+  if let (true, false, Some(enc_balance), Some(chain_balance)) =
+    (repair, matches, enc_balance, chain_balance)
+  {
+    // Make sure the asset exists.
+    if repo.get_asset(asset_id).await?.is_none() {
+      repo
+        .create_asset(&AddAsset {
+          asset_id,
+          ..Default::default()
+        })
+        .await?;
+    }
+    let enc = enc_balance.encode();
+    let updated = repo
+      .update_account_asset(&UpdateAccountAsset {
+        account_asset_id: local.as_ref().map(|local| local.account_asset_id),
+        account_id: account_with_secret.account_id,
+        asset_id,
+        balance: chain_balance,
+        enc_balance,
+        block_number: None,
+        source: BalanceSource::Chain,
+        base_enc_balance: None,
+        action: BalanceAction::Refresh,
+        amount: Some(diff),
+        history_source: Some("POST /tx/accounts/{public_key}/assets/{asset_id}/reconcile".into()),
+      })
+      .await?;
+    // A held balance conflict leaves the local balance unchanged.
+    repaired = updated.enc_balance == enc;
+  }
+
+  Ok(ReconciledBalance {
+    asset_id,
+    local_balance,
+    chain_balance,
+    diff,
+    matches,
+    repaired,
+  })
 }
 
 /// Apply any incoming balance to the confidential account and update the local database.
+///
+/// With `dry_run` the transaction isn't submitted, the estimated fees are returned instead
+/// (`DryRunResult`).
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
-#[post("/tx/accounts/{public_key}/assets/{asset_id}/apply_incoming")]
+#[post(
+  "/tx/accounts/{public_key}/assets/{asset_id}/apply_incoming",
+  wrap = "ReplayProtection"
+)]
 pub async fn tx_apply_incoming(
   path: web::Path<(String, Uuid)>,
   req: web::Json<TransactionArgs>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let (public_key, asset_id) = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
+  let public_key = repo.resolve_asset_account(&public_key, asset_id).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "apply_incoming_balance")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
   // Get the account.
   let account_with_secret = repo
     .get_account_with_secret(&public_key)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
   // Get the account asset with account secret key.
   let account_asset = repo
     .get_account_asset_with_secret(&public_key, asset_id)
@@ -162,54 +499,111 @@ pub async fn tx_apply_incoming(
     None => account_with_secret.apply_incoming(asset_id, enc_incoming),
   }?;
 
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .apply_incoming_balance(account, *asset_id.as_bytes())
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  if req.dry_run {
+    let res = dry_run(&api, &*signer, "apply_incoming_balance", call).await?;
+    return Ok(HttpResponse::Ok().json(res));
+  }
+  let res = match outbox
+    .submit(
+      "apply_incoming",
+      &signer_name,
+      "apply_incoming_balance",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "apply_incoming",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
 
-  // Update account balance.
-  if res.success {
-    repo.update_account_asset(&update).await?;
-  }
+        // Update account balance.
+        if res.success {
+          let update = update.with_source(res.tx_hash.clone());
+          repo.update_account_asset(&update).await?;
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
 
 /// Affirm confidential asset settlement leg as the sender.
+///
+/// The receiver is screened first (see `SCREENING`).  Amounts over the account's limits
+/// need a second user's approval of a `limit_override` (see `/approvals`).
+///
+/// Legs can transfer multiple assets, `amounts` needs one amount for each asset of the leg
+/// (the deprecated `amount` is only for single asset legs).
+///
+/// If an account asset has an active proof pool, the next pooled proof is used instead of
+/// generating a new proof (see `proof_pools`).
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, description = "Approval, or transaction job with `async=true`", body = Approval)
   )
 )]
-#[post("/tx/accounts/{public_key}/assets/{asset_id}/sender_affirm_leg")]
+#[post(
+  "/tx/accounts/{public_key}/assets/{asset_id}/sender_affirm_leg",
+  wrap = "ReplayProtection"
+)]
 pub async fn tx_sender_affirm_leg(
   path: web::Path<(String, Uuid)>,
   req: web::Json<AffirmTransactionLegRequest>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  approvals: AppApprovals,
+  screening: AppScreening,
+  proof_pools: AppProofPools,
+  anomalies: AppAnomalies,
+  nodes: AppNodes,
+  outbox: AppTxOutbox,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let (public_key, asset_id) = path.into_inner();
+  let user = request_user(&repo, &http_req).await?;
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
+  let public_key = repo.resolve_asset_account(&public_key, asset_id).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "affirm_transactions")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
-  // Get the account asset with account secret key.
-  let account_asset = repo
-    .get_account_asset_with_secret(&public_key, asset_id)
-    .await?
-    .ok_or_else(|| Error::not_found("Account Asset"))?;
+  let budget = budgets.check(&signer).await?;
 
   let transaction_id = req.transaction_id;
   let leg_id = req.leg_id;
-  let amount = req.amount;
+  let amounts = req.asset_amounts(asset_id)?;
 
   // Query the chain for Transaction Leg to get the receiver and auditors.
   let leg = api
@@ -220,111 +614,508 @@ pub async fn tx_sender_affirm_leg(
     .map_err(|err| Error::from(err))?
     .ok_or_else(|| Error::not_found("Transaction Leg"))?;
 
-  let receiver = confidential_account_to_key(&leg.receiver);
+  // Check the asset amounts against the leg before generating any proofs.
+  TransactionAssetAmount::validate_leg(
+    &amounts,
+    leg.auditors.keys().map(|id| Uuid::from_bytes(*id)),
+  )?;
+  if !amounts.iter().any(|amount| amount.asset_id == asset_id) {
+    Err(Error::Other(format!(
+      "The leg doesn't transfer asset {asset_id}"
+    )))?
+  }
+
+  // Get the account assets with account secret key.
+  let mut account_assets = Vec::new();
+  for amount in &amounts {
+    let account_asset = repo
+      .get_account_asset_with_secret(&public_key, amount.asset_id)
+      .await?
+      .ok_or_else(|| Error::not_found("Account Asset"))?;
+    account_asset.account.ensure_unlocked()?;
+    check_position_locks(
+      &repo,
+      account_asset.account.account_id,
+      Some(amount.asset_id),
+    )
+    .await?;
+    account_assets.push((account_asset, amount.amount));
+  }
+
+  // Amount limits, the reserved amounts are released unless the affirmation succeeds.
+  let mut reservations = Vec::new();
+  for (account_asset, amount) in &account_assets {
+    let limits = check_amount_limits(
+      &repo,
+      &approvals,
+      &http_req,
+      &account_asset.account,
+      Some(account_asset.asset_id),
+      *amount,
+      &*req,
+    )
+    .await;
+    match limits {
+      Ok(AmountLimitCheck::Reserved(reservation)) => reservations.push(reservation),
+      Ok(AmountLimitCheck::Pending(pending)) => {
+        AmountReservation::release_all(reservations, &repo).await;
+        return Ok(pending);
+      }
+      Err(err) => {
+        AmountReservation::release_all(reservations, &repo).await;
+        return Err(err.into());
+      }
+    }
+  }
 
   let mut updates = Vec::new();
-  let mut transfers = ConfidentialTransfers {
-    proofs: Default::default(),
-  };
+  let mut pooled = Vec::new();
+  let res: Result<_, Error> = async {
+    // Screen the receiver.
+    screen_chain_receiver(&screening, &api, leg.receiver).await?;
+    let receiver = confidential_account_to_key(&leg.receiver);
 
-  for (asset_id, auditors) in leg.auditors {
-    let auditors = auditors.iter().map(auditor_account_to_key).collect();
+    let mut transfers = ConfidentialTransfers {
+      proofs: Default::default(),
+    };
+
+    for (account_asset, amount) in &account_assets {
+      let amount = *amount;
+      let asset_id = *account_asset.asset_id.as_bytes();
+      let auditors: BTreeSet<_> = leg
+        .auditors
+        .get(&asset_id)
+        .map(|auditors| auditors.iter().map(auditor_account_to_key).collect())
+        .unwrap_or_default();
+
+      // Query the chain for the sender's current balance.
+      let enc_balance = api
+        .query()
+        .confidential_asset()
+        .account_balance(leg.sender, asset_id)
+        .await
+        .map_err(|err| Error::from(err))?
+        .ok_or_else(|| Error::not_found("Sender account balance"))?;
+      if enc_balance.encode() != account_asset.enc_balance {
+        add_warning(
+          &http_req,
+          WARNING_BALANCE_DRIFT,
+          format!(
+            "The local balance of asset {} doesn't match the chain balance",
+            account_asset.asset_id
+          ),
+        );
+      }
+      // Convert from on-chain `CipherText`.
+      let enc_balance = Some(scale_convert(&enc_balance));
 
-    // Query the chain for the sender's current balance.
-    let enc_balance = api
-      .query()
+      // Use the next pooled proof, the pool already reserved the amount.
+      let proof = proof_pools
+        .take(
+          account_asset.account_asset_id,
+          &receiver,
+          &auditors,
+          amount,
+          enc_balance.as_ref(),
+        )
+        .await?;
+      if let Some(proof) = proof {
+        repo
+          .add_proof(&AddProof::sender(
+            account_asset.account.account_id,
+            Some(account_asset.asset_id),
+            &receiver,
+            amount,
+            proof.proof.clone(),
+          ))
+          .await?;
+        transfers
+          .proofs
+          .insert(asset_id, SenderProof(proof.proof.clone()));
+        pooled.push(proof);
+        continue;
+      }
+
+      // Generate sender proof.
+      let started = Instant::now();
+      let auditor_count = auditors.len();
+      let proving = account_asset.clone();
+      let (update, proof) =
+        spawn_proof(move || proving.create_send_proof(enc_balance, receiver, auditors, amount))
+          .await?;
+      let proof = proof.as_bytes();
+      ProofStats::global().record(
+        ProofOperation::SenderProof,
+        Some(account_asset.asset_id),
+        Some(auditor_count),
+        started.elapsed(),
+        proof.len(),
+      );
+      repo
+        .add_proof(&AddProof::sender(
+          account_asset.account.account_id,
+          Some(account_asset.asset_id),
+          &receiver,
+          amount,
+          proof.clone(),
+        ))
+        .await?;
+      transfers.proofs.insert(asset_id, SenderProof(proof));
+      updates.push(update);
+    }
+
+    let affirms = AffirmTransactions(vec![AffirmTransaction {
+      id: transaction_id,
+      leg: AffirmLeg {
+        leg_id: leg_id,
+        party: AffirmParty::Sender(transfers),
+      },
+    }]);
+    let call = api
+      .call()
       .confidential_asset()
-      .account_balance(leg.sender, asset_id)
+      .affirm_transactions(affirms)
+      .map_err(|err| Error::from(err))?;
+    outbox
+      .submit(
+        "sender_affirm_leg",
+        &signer_name,
+        "affirm_transactions",
+        &mut signer,
+        call,
+        req.finalize,
+      )
       .await
-      .map_err(|err| Error::from(err))?
-      .ok_or_else(|| Error::not_found("Sender account balance"))?;
-    // Convert from on-chain `CipherText`.
-    let enc_balance = Some(scale_convert(&enc_balance));
-
-    // Generate sender proof.
-    let (update, proof) =
-      account_asset.create_send_proof(enc_balance, receiver, auditors, amount)?;
-    transfers
-      .proofs
-      .insert(asset_id, SenderProof(proof.as_bytes()));
-    updates.push(update);
   }
-
-  let affirms = AffirmTransactions(vec![AffirmTransaction {
-    id: transaction_id,
-    leg: AffirmLeg {
-      leg_id: leg_id,
-      party: AffirmParty::Sender(transfers),
-    },
-  }]);
-  let res = api
-    .call()
-    .confidential_asset()
-    .affirm_transactions(affirms)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
-    .map_err(|err| Error::from(err))?;
+  .await;
+  let res = match res {
+    Ok(TxSubmission::Submitted(res)) => res,
+    Ok(TxSubmission::Queued(entry)) => {
+      // The queued call carries the proofs, the pooled proofs and the reserved amounts
+      // stay used.
+      for pooled in &pooled {
+        proof_pools.finish(pooled, true).await?;
+      }
+      return Ok(HttpResponse::Accepted().json(entry));
+    }
+    Err(err) => {
+      // The pooled proofs and the reserved amounts are available again.
+      for pooled in &pooled {
+        proof_pools.finish(pooled, false).await?;
+      }
+      AmountReservation::release_all(reservations, &repo).await;
+      return Err(err.into());
+    }
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "sender_affirm_leg",
+      res,
+      req.finalize,
+      move |res| async move {
+        // The pooled proofs and the reserved amounts are available again if the
+        // affirmation failed.
+        let used = matches!(&res, Ok(res) if res.success);
+        for pooled in &pooled {
+          proof_pools.finish(pooled, used).await?;
+        }
+        if !used {
+          AmountReservation::release_all(reservations, &repo).await;
+        }
+        let res = res?;
+        budgets.record(budget, &res).await;
 
-  // Update account balance.
-  if res.success {
-    for update in updates {
-      repo.update_account_asset(&update).await?;
-    }
-  }
+        // Update account balance.
+        if res.success {
+          for update in updates {
+            let update = update.with_source(res.tx_hash.clone());
+            repo.update_account_asset(&update).await?;
+          }
+          for (account_asset, amount) in &account_assets {
+            let asset_id = Some(account_asset.asset_id);
+            anomalies
+              .sender_proof(user.as_deref(), &account_asset.account, asset_id, *amount)
+              .await;
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
 
 /// Mint confidential assets on-chain.
+///
+/// With `dry_run` the transaction isn't submitted, the estimated fees are returned instead
+/// (`DryRunResult`).
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
-#[post("/tx/accounts/{public_key}/assets/{asset_id}/mint")]
+#[post(
+  "/tx/accounts/{public_key}/assets/{asset_id}/mint",
+  wrap = "ReplayProtection"
+)]
 pub async fn tx_mint(
   path: web::Path<(String, Uuid)>,
   req: web::Json<MintRequest>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let (public_key, asset_id) = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
+  let public_key = repo.resolve_asset_account(&public_key, asset_id).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "mint")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
   // Get the account.
   let account_with_secret = repo
     .get_account_with_secret(&public_key)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
 
   let account = account_with_secret.as_confidential_account()?;
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .mint(*asset_id.as_bytes(), req.amount as _, account)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  if req.dry_run {
+    let res = dry_run(&api, &*signer, "mint", call).await?;
+    return Ok(HttpResponse::Ok().json(res));
+  }
+  let res = match outbox
+    .submit(
+      "mint",
+      &signer_name,
+      "mint",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let mut res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "mint",
+      res,
+      req.finalize,
+      move |res| async move {
+        let mut res = res?;
+        budgets.record(budget, &res).await;
 
-  // Update account balance.
-  if res.success {
-    if let Some(updates) = res.decrypt_balance_updates(&account_with_secret) {
-      for (_asset_id, update) in updates {
-        repo.update_account_asset(&update).await?;
-      }
+        // Update account balance.
+        if res.success {
+          if let Some(updates) = res.decrypt_balance_updates(&account_with_secret) {
+            for (_asset_id, update) in updates {
+              repo.update_account_asset(&update).await?;
+            }
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Burn confidential assets on-chain.
+///
+/// The burn proof is generated from the account's on-chain balance and the local balance
+/// is updated once the burn succeeds.  Burns at or above `APPROVAL_BURN_THRESHOLD` need a
+/// second user's approval when `burn` is in `APPROVAL_OPERATIONS` (see `/approvals`).
+///
+/// With `dry_run` the transaction isn't submitted, the estimated fees are returned instead
+/// (`DryRunResult`).
+#[utoipa::path(
+  params(TxJobQuery),
+  responses(
+    (status = 200, body = TransactionResult),
+    (status = 202, description = "Approval, or transaction job with `async=true`", body = Approval)
+  )
+)]
+#[post(
+  "/tx/accounts/{public_key}/assets/{asset_id}/burn",
+  wrap = "ReplayProtection"
+)]
+pub async fn tx_burn(
+  path: web::Path<(String, Uuid)>,
+  req: web::Json<BurnRequest>,
+  job_query: web::Query<TxJobQuery>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  approvals: AppApprovals,
+  proof_pools: AppProofPools,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let (public_key, asset_id) = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
+  let public_key = repo.resolve_asset_account(&public_key, asset_id).await?;
+  let mut signer = signing
+    .get_tx_signer(&signer_name, "burn")
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
+  // Get the account asset with account secret key.
+  let account_asset = repo
+    .get_account_asset_with_secret(&public_key, asset_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Account Asset"))?;
+  account_asset.account.ensure_unlocked()?;
+  check_position_locks(&repo, account_asset.account.account_id, Some(asset_id)).await?;
+  // The proof pool reserved part of the balance.
+  proof_pools
+    .ensure_no_pool(account_asset.account_asset_id)
+    .await?;
+
+  // Dual control.
+  if approvals.burn_requires_approval(req.amount) {
+    let target = format!("{public_key}/{asset_id}");
+    let pending = approvals
+      .authorize(&repo, &http_req, ApprovalOperation::Burn, &target, &*req)
+      .await?;
+    if let Some(pending) = pending {
+      return Ok(pending);
     }
   }
 
-  Ok(HttpResponse::Ok().json(res))
+  let account = account_asset.account.as_confidential_account()?;
+  let amount = req.amount;
+
+  // Query the chain for the account's current balance.
+  let enc_balance = api
+    .query()
+    .confidential_asset()
+    .account_balance(account, *asset_id.as_bytes())
+    .await
+    .map_err(|err| Error::from(err))?
+    .ok_or_else(|| Error::not_found("Account balance"))?;
+  if enc_balance.encode() != account_asset.enc_balance {
+    add_warning(
+      &http_req,
+      WARNING_BALANCE_DRIFT,
+      "The local balance doesn't match the chain balance",
+    );
+  }
+  // Convert from on-chain `CipherText`.
+  let enc_balance = Some(scale_convert(&enc_balance));
+
+  // Generate burn proof.
+  let started = Instant::now();
+  let proving = account_asset.clone();
+  let (update, proof) = spawn_proof(move || proving.create_burn_proof(enc_balance, amount)).await?;
+  let proof = proof.as_bytes();
+  ProofStats::global().record(
+    ProofOperation::BurnProof,
+    Some(asset_id),
+    None,
+    started.elapsed(),
+    proof.len(),
+  );
+
+  let call = api
+    .call()
+    .confidential_asset()
+    .burn(
+      *asset_id.as_bytes(),
+      amount as _,
+      account,
+      BurnProof(proof.clone()),
+    )
+    .map_err(|err| Error::from(err))?;
+  if req.dry_run {
+    let res = dry_run(&api, &*signer, "burn", call).await?;
+    return Ok(HttpResponse::Ok().json(res));
+  }
+  repo
+    .add_proof(&AddProof::burn(
+      account_asset.account.account_id,
+      Some(asset_id),
+      amount,
+      proof,
+    ))
+    .await?;
+  let res = match outbox
+    .submit(
+      "burn",
+      &signer_name,
+      "burn",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
+
+  // Wait for transaction results.
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "burn",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+
+        // Update account balance.
+        if res.success {
+          let update = update.with_source(res.tx_hash.clone());
+          repo.update_account_asset(&update).await?;
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
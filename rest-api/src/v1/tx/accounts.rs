@@ -1,55 +1,100 @@
-use actix_web::{get, post, rt::pin, web, HttpResponse, Responder, Result};
+use std::collections::BTreeMap;
+
+use actix_web::{get, post, rt::pin, web, HttpRequest, HttpResponse, Responder, Result};
+use codec::Encode;
 use futures_util::StreamExt;
 use uuid::Uuid;
 
+use confidential_assets::CipherText;
+
 use polymesh_api::types::{
   confidential_assets::transaction::ConfidentialTransferProof as SenderProof,
   pallet_confidential_asset::{
-    AffirmLeg, AffirmParty, AffirmTransaction, AffirmTransactions, ConfidentialTransfers,
+    AffirmLeg, AffirmParty, AffirmTransaction, AffirmTransactions, ConfidentialTransfers, LegParty,
+    UnaffirmLeg, UnaffirmTransaction, UnaffirmTransactions,
   },
 };
 use polymesh_api::Api;
 
-use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_api::{
+  anomalies::{request_user, AppAnomalies},
+  approvals::AppApprovals,
+  limits::{check_amount_limits, AmountLimitCheck, AmountReservation},
+  position_locks::check_position_locks,
+  receipts::AppReceiptSigner,
+  replay::ReplayProtection,
+  repo::Repository,
+  screening::AppScreening,
+};
 use polymesh_private_proof_shared::{
-  auditor_account_to_key, confidential_account_to_key, error::Error, scale_convert,
-  AccountAssetIncomingBalance, AffirmTransactionLegRequest, AffirmTransactionsRequest, PublicKey,
-  TransactionArgs, TransactionParty, TransactionResult,
+  auditor_account_to_key, confidential_account_to_key, did_to_hex, error::Error, memo_to_string,
+  scale_convert, spawn_proof, AccountAssetIncomingBalance, AddAsset, AddProof,
+  AffirmTransactionLegRequest, AffirmTransactionsRequest, AssetBalanceDrift, BalanceAction,
+  BalanceSource, PendingSettlement, PendingSettlementLeg, ProcessedEvent, PublicKey,
+  RefreshBalancesRequest, RefreshBalancesResult, RefreshedAccount, RejectTransactionRequest,
+  SettlementEventRecord, TransactionAffirmed, TransactionArgs, TransactionAssetAmount,
+  TransactionLegDetails, TransactionParty, UpdateAccountAsset, WithdrawAffirmationRequest,
 };
 
 use super::account_assets;
-use crate::signing::AppSigningManager;
+use crate::budgets::AppSignerBudgets;
+use crate::dry_run::dry_run;
+use crate::nodes::AppNodes;
+use crate::outbox::{AppTxOutbox, TxSubmission};
+use crate::repo::TransactionRepository;
+use crate::screening::screen_chain_receiver;
+use crate::signing::{resolve_signer, AppSigningManager};
+use crate::tx_jobs::{AppTxJobs, TxJobOutcome, TxJobQuery};
 
 pub fn service(cfg: &mut web::ServiceConfig) {
   cfg
     .service(tx_init_account)
     .service(tx_account_did)
     .service(tx_apply_incoming_balances)
+    .service(tx_refresh_balances)
     .service(get_incoming_balances)
+    .service(get_pending_transactions)
     .service(tx_affirm_transactions)
     .service(tx_mediator_affirm_leg)
+    .service(tx_reject_transaction)
+    .service(tx_withdraw_affirmation)
     .configure(account_assets::service);
 }
 
 /// Add the account on-chain.
+///
+/// With `dry_run` the transaction isn't submitted, the estimated fees are returned instead
+/// (`DryRunResult`).
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
 #[post("/tx/accounts/{public_key}/init_account")]
 pub async fn tx_init_account(
   path: web::Path<String>,
   req: web::Json<TransactionArgs>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let public_key = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "create_account")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
   // Get the account.
   let account = repo
     .get_account_with_secret(&public_key)
@@ -57,21 +102,59 @@ pub async fn tx_init_account(
     .ok_or_else(|| Error::not_found("Account"))?;
   let confidential_account = account.as_confidential_account()?;
 
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .create_account(confidential_account)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  if req.dry_run {
+    let res = dry_run(&api, &*signer, "create_account", call).await?;
+    return Ok(HttpResponse::Ok().json(res));
+  }
+  let res = match outbox
+    .submit(
+      "init_account",
+      &signer_name,
+      "create_account",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
-  Ok(HttpResponse::Ok().json(res))
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "init_account",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+
+        // Save the account's identity.
+        for ev in &res.processed_events.0 {
+          if let ProcessedEvent::ConfidentialAccountCreated { did, .. } = ev {
+            repo.set_account_did(&public_key, &did_to_hex(did)).await?;
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
 
-/// Get the account's on-chain identity.
+/// Get the account's on-chain identity.  Saved on the account, if it is one of ours.
 #[utoipa::path(
   responses(
     (status = 200, body = TransactionResult)
@@ -80,8 +163,10 @@ pub async fn tx_init_account(
 #[post("/tx/accounts/{public_key}/identity")]
 pub async fn tx_account_did(
   path: web::Path<PublicKey>,
-  api: web::Data<Api>,
+  repo: Repository,
+  nodes: AppNodes,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let public_key = path.into_inner();
   let confidential_account = public_key.as_confidential_account()?;
 
@@ -92,6 +177,9 @@ pub async fn tx_account_did(
     .await
     .map_err(|err| Error::from(err))?
     .ok_or_else(|| Error::not_found("Confidential account doesn't exist"))?;
+  repo
+    .set_account_did(&hex::encode(public_key.0), &did_to_hex(&account_did))
+    .await?;
 
   Ok(HttpResponse::Ok().json(account_did))
 }
@@ -106,14 +194,16 @@ pub async fn tx_account_did(
 pub async fn get_incoming_balances(
   path: web::Path<String>,
   repo: Repository,
-  api: web::Data<Api>,
+  nodes: AppNodes,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let public_key = path.into_inner();
   // Get the account.
   let account_with_secret = repo
     .get_account_with_secret(&public_key)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
 
   let account = account_with_secret.as_confidential_account()?;
 
@@ -146,29 +236,48 @@ pub async fn get_incoming_balances(
 }
 
 /// Apply any incoming balances to the confidential account and update the local database.
+///
+/// With `dry_run` the transaction isn't submitted, the estimated fees are returned instead
+/// (`DryRunResult`).
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
-#[post("/tx/accounts/{public_key}/apply_incoming_balances")]
+#[post(
+  "/tx/accounts/{public_key}/apply_incoming_balances",
+  wrap = "ReplayProtection"
+)]
 pub async fn tx_apply_incoming_balances(
   path: web::Path<String>,
   req: web::Json<TransactionArgs>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let public_key = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "apply_incoming_balance")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
   // Get the account.
   let account_with_secret = repo
     .get_account_with_secret(&public_key)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
 
   let account = account_with_secret.as_confidential_account()?;
 
@@ -198,173 +307,472 @@ pub async fn tx_apply_incoming_balances(
     Err(Error::other("No incoming balances to apply"))?;
   }
 
-  let res = api
+  let call = api
     .call()
     .utility()
     .batch_all(calls)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  if req.dry_run {
+    let res = dry_run(&api, &*signer, "batch_all", call).await?;
+    return Ok(HttpResponse::Ok().json(res));
+  }
+  let res = match outbox
+    .submit(
+      "apply_incoming_balances",
+      &signer_name,
+      "apply_incoming_balance",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let mut res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "apply_incoming_balances",
+      res,
+      req.finalize,
+      move |res| async move {
+        let mut res = res?;
+        budgets.record(budget, &res).await;
+
+        // Update account balance.
+        if res.success {
+          if let Some(updates) = res.decrypt_balance_updates(&account_with_secret) {
+            for (_asset_id, update) in updates {
+              repo.update_account_asset(&update).await?;
+            }
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Number of accounts to refresh in parallel.
+const REFRESH_CONCURRENCY: usize = 8;
 
-  // Update account balance.
-  if res.success {
-    if let Some(updates) = res.decrypt_balance_updates(&account_with_secret) {
-      for (_asset_id, update) in updates {
-        repo.update_account_asset(&update).await?;
+/// Refresh the local balances of all (or the selected) accounts from the chain.
+///
+/// The on-chain balances are queried in parallel and decrypted, local balances that
+/// don't match the chain are updated.  Reports the drift of each balance, which can be
+/// used to recover after chain watcher downtime.
+#[utoipa::path(
+  responses(
+    (status = 200, body = RefreshBalancesResult)
+  )
+)]
+#[post("/tx/accounts/refresh_balances", wrap = "ReplayProtection")]
+pub async fn tx_refresh_balances(
+  req: web::Json<RefreshBalancesRequest>,
+  repo: Repository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let accounts = if req.accounts.is_empty() {
+    repo
+      .get_accounts()
+      .await?
+      .into_iter()
+      .map(|account| PublicKey::from_str(&hex::encode(&account.confidential_account)))
+      .collect::<Result<Vec<_>, _>>()?
+  } else {
+    req.accounts.clone()
+  };
+
+  let refreshed = futures_util::stream::iter(accounts)
+    .map(|public_key| refresh_account(&repo, &api, public_key))
+    .buffered(REFRESH_CONCURRENCY)
+    .collect::<Vec<_>>()
+    .await;
+
+  Ok(HttpResponse::Ok().json(RefreshBalancesResult::new(refreshed)))
+}
+
+async fn refresh_account(repo: &Repository, api: &Api, public_key: PublicKey) -> RefreshedAccount {
+  let mut refreshed = RefreshedAccount {
+    confidential_account: public_key,
+    balances: Vec::new(),
+    err_msg: None,
+  };
+  if let Err(err) = refresh_account_balances(repo, api, &mut refreshed).await {
+    log::warn!(
+      "Failed to refresh balances of account 0x{}: {err:?}",
+      hex::encode(refreshed.confidential_account.0)
+    );
+    refreshed.err_msg = Some(err.to_string());
+  }
+  refreshed
+}
+
+async fn refresh_account_balances(
+  repo: &Repository,
+  api: &Api,
+  refreshed: &mut RefreshedAccount,
+) -> Result<(), Error> {
+  let public_key = hex::encode(refreshed.confidential_account.0);
+  // Get the account.
+  let account_with_secret = repo
+    .get_account_with_secret(&public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
+
+  let account = account_with_secret.as_confidential_account()?;
+
+  // Get all asset balances for this account.
+  let balances = api
+    .paged_query()
+    .confidential_asset()
+    .account_balance(account)
+    .entries();
+  pin!(balances);
+  let mut chain_balances = Vec::new();
+  while let Some(balance) = balances.next().await {
+    match balance {
+      Ok((asset_id, Some(enc_balance))) => {
+        let enc_balance: CipherText = scale_convert(&enc_balance);
+        chain_balances.push((Uuid::from_bytes(asset_id), enc_balance));
+      }
+      Ok((_, None)) => (),
+      Err(err) => {
+        Err(Error::from(err))?;
       }
     }
   }
 
-  Ok(HttpResponse::Ok().json(res))
+  // Compare with the local balances.
+  let local_balances = repo
+    .get_account_assets(&public_key)
+    .await?
+    .into_iter()
+    .map(|account_asset| (account_asset.asset_id, account_asset))
+    .collect::<BTreeMap<_, _>>();
+  for (asset_id, enc_balance) in chain_balances {
+    let chain_balance = account_with_secret.decrypt(&enc_balance)?;
+    let local = local_balances.get(&asset_id);
+    let local_balance = local.map(|account_asset| account_asset.balance as u64);
+    let updated = local
+      .map(|account_asset| account_asset.enc_balance != enc_balance.encode())
+      .unwrap_or(true);
+    if updated {
+      // Make sure the asset exists.
+      if repo.get_asset(asset_id).await?.is_none() {
+        repo
+          .create_asset(&AddAsset {
+            asset_id,
+            ..Default::default()
+          })
+          .await?;
+      }
+      repo
+        .update_account_asset(&UpdateAccountAsset {
+          account_asset_id: local.map(|account_asset| account_asset.account_asset_id),
+          account_id: account_with_secret.account_id,
+          asset_id,
+          balance: chain_balance,
+          enc_balance,
+          block_number: None,
+          source: BalanceSource::Chain,
+          base_enc_balance: None,
+          action: BalanceAction::Refresh,
+          amount: Some(chain_balance as i64 - local_balance.unwrap_or_default() as i64),
+          history_source: Some("POST /tx/accounts/refresh_balances".into()),
+        })
+        .await?;
+    }
+    refreshed.balances.push(AssetBalanceDrift {
+      asset_id,
+      local_balance,
+      chain_balance,
+      drift: chain_balance as i64 - local_balance.unwrap_or_default() as i64,
+      updated,
+    });
+  }
+  Ok(())
 }
 
 /// Affirm confidential asset settlements as the sender/receiver/mediator.
+///
+/// Receivers are screened before sending (see `SCREENING`).  Sender amounts over the
+/// account's limits need a second user's approval of a `limit_override` (see `/approvals`).
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, description = "Approval, or transaction job with `async=true`", body = Approval)
   )
 )]
-#[post("/tx/accounts/{public_key}/affirm_transactions")]
+#[post(
+  "/tx/accounts/{public_key}/affirm_transactions",
+  wrap = "ReplayProtection"
+)]
 pub async fn tx_affirm_transactions(
   path: web::Path<String>,
   req: web::Json<AffirmTransactionsRequest>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  approvals: AppApprovals,
+  screening: AppScreening,
+  anomalies: AppAnomalies,
+  nodes: AppNodes,
+  outbox: AppTxOutbox,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let public_key = path.into_inner();
+  let user = request_user(&repo, &http_req).await?;
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "affirm_transactions")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
   let account_with_secret = repo
     .get_account_with_secret(&public_key)
     .await?
     .ok_or_else(|| Error::not_found("Account"))?;
+  account_with_secret.ensure_unlocked()?;
 
-  let mut affirms = Vec::new();
-
-  for tx in &req.transactions {
-    let transaction_id = tx.transaction_id;
-    for leg in &tx.legs {
-      let leg_id = leg.leg_id;
-      let affirm_party = match (&leg.party, &leg.amounts) {
-        (TransactionParty::Sender, None) => Err(Error::other("Missing asset amounts."))?,
-        (TransactionParty::Sender, Some(amounts)) => {
-          // Query the chain for Transaction Leg to get the receiver and auditors.
-          let leg_details = api
-            .query()
-            .confidential_asset()
-            .transaction_legs(transaction_id, leg_id)
-            .await
-            .map_err(|err| Error::from(err))?
-            .ok_or_else(|| Error::not_found("Transaction Leg"))?;
-
-          let receiver = confidential_account_to_key(&leg_details.receiver);
-          let sender = leg_details.sender;
-
-          let mut transfers = ConfidentialTransfers {
-            proofs: Default::default(),
-          };
-
-          if leg_details.auditors.len() != amounts.len() {
-            Err(Error::other("Wrong number of asset amounts."))?
-          }
+  let mut sent = Vec::new();
+  // The amounts reserved against the limits, released unless the affirmation succeeds.
+  let mut reservations = Vec::new();
+  let res: Result<_, Error> = async {
+    let mut affirms = Vec::new();
 
-          for amount in amounts {
-            let asset_id = amount.asset_id;
-            let amount = amount.amount;
-            let auditors = leg_details
-              .auditors
-              .get(asset_id.as_bytes())
-              .ok_or_else(|| Error::other(&format!("Invalid asset in leg: {asset_id:?}")))?;
-            // Get the account asset with account secret key.
-            let account_asset = repo
-              .get_account_asset_with_secret(&public_key, asset_id)
-              .await?
-              .ok_or_else(|| Error::not_found("Account Asset"))?;
-            let auditors = auditors.iter().map(auditor_account_to_key).collect();
-
-            // Query the chain for the sender's current balance.
-            let enc_balance = api
+    for tx in &req.transactions {
+      let transaction_id = tx.transaction_id;
+      for leg in &tx.legs {
+        let leg_id = leg.leg_id;
+        let affirm_party = match (&leg.party, &leg.amounts) {
+          (TransactionParty::Sender, None) => Err(Error::other("Missing asset amounts."))?,
+          (TransactionParty::Sender, Some(amounts)) => {
+            // Query the chain for Transaction Leg to get the receiver and auditors.
+            let leg_details = api
               .query()
               .confidential_asset()
-              .account_balance(sender, *asset_id.as_bytes())
+              .transaction_legs(transaction_id, leg_id)
               .await
               .map_err(|err| Error::from(err))?
-              .ok_or_else(|| Error::not_found("Sender account balance"))?;
-            // Convert from on-chain `CipherText`.
-            let enc_balance = Some(scale_convert(&enc_balance));
-
-            // Generate sender proof.
-            let (_update, proof) =
-              account_asset.create_send_proof(enc_balance, receiver, auditors, amount)?;
-            transfers
-              .proofs
-              .insert(*asset_id.as_bytes(), SenderProof(proof.as_bytes()));
-          }
-          AffirmParty::Sender(transfers)
-        }
-        (TransactionParty::Receiver, _amounts) => AffirmParty::Receiver,
-        (TransactionParty::Mediator, _amounts) => AffirmParty::Mediator,
-      };
-      affirms.push(AffirmTransaction {
-        id: transaction_id,
-        leg: AffirmLeg {
-          leg_id: leg_id,
-          party: affirm_party,
-        },
-      });
-    }
-  }
+              .ok_or_else(|| Error::not_found("Transaction Leg"))?;
 
-  let res = api
-    .call()
-    .confidential_asset()
-    .affirm_transactions(AffirmTransactions(affirms))
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
-    .map_err(|err| Error::from(err))?;
+            // Screen the receiver.
+            screen_chain_receiver(&screening, &api, leg_details.receiver).await?;
+            let receiver = confidential_account_to_key(&leg_details.receiver);
+            let sender = leg_details.sender;
 
-  // Wait for transaction results.
-  let mut res = TransactionResult::wait_for_results(res, req.finalize).await?;
+            let mut transfers = ConfidentialTransfers {
+              proofs: Default::default(),
+            };
+
+            // Check the asset amounts against the leg before generating any proofs.
+            TransactionAssetAmount::validate_leg(
+              amounts,
+              leg_details.auditors.keys().map(|id| Uuid::from_bytes(*id)),
+            )?;
+
+            for amount in amounts {
+              let asset_id = amount.asset_id;
+              let amount = amount.amount;
+              let auditors = leg_details
+                .auditors
+                .get(asset_id.as_bytes())
+                .ok_or_else(|| Error::other(&format!("Invalid asset in leg: {asset_id:?}")))?;
+              // Get the account asset with account secret key.
+              let account_asset = repo
+                .get_account_asset_with_secret(&public_key, asset_id)
+                .await?
+                .ok_or_else(|| Error::not_found("Account Asset"))?;
+              let auditors = auditors.iter().map(auditor_account_to_key).collect();
+              check_position_locks(&repo, account_with_secret.account_id, Some(asset_id)).await?;
 
-  // Update account balance.
-  if res.success {
-    if let Some(updates) = res.decrypt_balance_updates(&account_with_secret) {
-      for (_asset_id, update) in updates {
-        repo.update_account_asset(&update).await?;
+              // Amount limits.
+              let limits = check_amount_limits(
+                &repo,
+                &approvals,
+                &http_req,
+                &account_with_secret,
+                Some(asset_id),
+                amount,
+                &*req,
+              )
+              .await?;
+              match limits {
+                AmountLimitCheck::Reserved(reservation) => reservations.push(reservation),
+                AmountLimitCheck::Pending(pending) => return Ok(Err(pending)),
+              }
+              sent.push((asset_id, amount));
+
+              // Query the chain for the sender's current balance.
+              let enc_balance = api
+                .query()
+                .confidential_asset()
+                .account_balance(sender, *asset_id.as_bytes())
+                .await
+                .map_err(|err| Error::from(err))?
+                .ok_or_else(|| Error::not_found("Sender account balance"))?;
+              // Convert from on-chain `CipherText`.
+              let enc_balance = Some(scale_convert(&enc_balance));
+
+              // Generate sender proof.
+              let (_update, proof) = spawn_proof(move || {
+                account_asset.create_send_proof(enc_balance, receiver, auditors, amount)
+              })
+              .await?;
+              let proof = proof.as_bytes();
+              repo
+                .add_proof(&AddProof::sender(
+                  account_with_secret.account_id,
+                  Some(asset_id),
+                  &receiver,
+                  amount,
+                  proof.clone(),
+                ))
+                .await?;
+              transfers
+                .proofs
+                .insert(*asset_id.as_bytes(), SenderProof(proof));
+            }
+            AffirmParty::Sender(transfers)
+          }
+          (TransactionParty::Receiver, _amounts) => AffirmParty::Receiver,
+          (TransactionParty::Mediator, _amounts) => AffirmParty::Mediator,
+        };
+        affirms.push(AffirmTransaction {
+          id: transaction_id,
+          leg: AffirmLeg {
+            leg_id: leg_id,
+            party: affirm_party,
+          },
+        });
       }
     }
+
+    let call = api
+      .call()
+      .confidential_asset()
+      .affirm_transactions(AffirmTransactions(affirms))
+      .map_err(|err| Error::from(err))?;
+    let submission = outbox
+      .submit(
+        "affirm_transactions",
+        &signer_name,
+        "affirm_transactions",
+        &mut signer,
+        call,
+        req.finalize,
+      )
+      .await?;
+    Ok(Ok(submission))
   }
+  .await;
+  let res = match res {
+    Ok(Ok(TxSubmission::Submitted(res))) => res,
+    // The queued call carries the sender proofs, the reserved amounts stay used.
+    Ok(Ok(TxSubmission::Queued(entry))) => return Ok(HttpResponse::Accepted().json(entry)),
+    Ok(Err(pending)) => {
+      AmountReservation::release_all(reservations, &repo).await;
+      return Ok(pending);
+    }
+    Err(err) => {
+      AmountReservation::release_all(reservations, &repo).await;
+      return Err(err.into());
+    }
+  };
+
+  // Wait for transaction results.
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "affirm_transactions",
+      res,
+      req.finalize,
+      move |res| async move {
+        if !matches!(&res, Ok(res) if res.success) {
+          AmountReservation::release_all(reservations, &repo).await;
+        }
+        let mut res = res?;
+        budgets.record(budget, &res).await;
+
+        // Update account balance.
+        if res.success {
+          if let Some(updates) = res.decrypt_balance_updates(&account_with_secret) {
+            for (_asset_id, update) in updates {
+              repo.update_account_asset(&update).await?;
+            }
+          }
+          for (asset_id, amount) in sent {
+            anomalies
+              .sender_proof(
+                user.as_deref(),
+                &account_with_secret,
+                Some(asset_id),
+                amount,
+              )
+              .await;
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
 
 /// Affirm confidential asset settlement as a mediator.
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
 #[post("/tx/accounts/{public_key}/mediator_affirm_leg")]
 pub async fn tx_mediator_affirm_leg(
   path: web::Path<String>,
   req: web::Json<AffirmTransactionLegRequest>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let public_key = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&signer_name, "affirm_transactions")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
   let _account = repo
     .get_account(&public_key)
     .await?
@@ -378,17 +786,424 @@ pub async fn tx_mediator_affirm_leg(
       party: AffirmParty::Mediator,
     },
   }]);
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .affirm_transactions(affirms)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "mediator_affirm_leg",
+      &signer_name,
+      "affirm_transactions",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
+
+  // Wait for transaction results.
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "mediator_affirm_leg",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Reject a confidential asset settlement.
+///
+/// Any party of the settlement can reject it.  The senders get back the affirmed amounts,
+/// the account's local balance is updated and the rejection is recorded with the
+/// settlement (invoices paid by the settlement are open again).
+#[utoipa::path(
+  params(TxJobQuery),
+  responses(
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
+  )
+)]
+#[post(
+  "/tx/accounts/{public_key}/reject_transaction",
+  wrap = "ReplayProtection"
+)]
+pub async fn tx_reject_transaction(
+  path: web::Path<String>,
+  req: web::Json<RejectTransactionRequest>,
+  job_query: web::Query<TxJobQuery>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let public_key = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
+  let mut signer = signing
+    .get_tx_signer(&signer_name, "reject_transaction")
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
+  repo
+    .get_account(&public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  // Mediator accounts don't have a balance.
+  let account_with_secret = repo.get_account_with_secret(&public_key).await?;
+
+  let call = api
+    .call()
+    .confidential_asset()
+    .reject_transaction(req.transaction_id, req.leg_count)
+    .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "reject_transaction",
+      &signer_name,
+      "reject_transaction",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
+
+  // Wait for transaction results.
+  let settlement_id = req.transaction_id.0 as i64;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "reject_transaction",
+      res,
+      req.finalize,
+      move |res| async move {
+        let mut res = res?;
+        budgets.record(budget, &res).await;
+
+        if res.success {
+          // Update account balance.
+          if let Some(account_with_secret) = &account_with_secret {
+            if let Some(updates) = res.decrypt_balance_updates(account_with_secret) {
+              for (_asset_id, update) in updates {
+                repo.update_account_asset(&update).await?;
+              }
+            }
+          }
+          // Record the rejection, unless the chain watcher already did.
+          let rejected = tx_repo
+            .get_settlement_events(settlement_id)
+            .await?
+            .iter()
+            .any(|rec| {
+              rec
+                .event
+                .starts_with(r#"{"ConfidentialTransactionRejected""#)
+            });
+          if !rejected && tx_repo.get_settlement(settlement_id).await?.is_some() {
+            let recs = SettlementEventRecord::from_events(res.block_number, &res.processed_events)?;
+            for rec in recs {
+              tx_repo.add_settlement_event(rec).await?;
+            }
+          }
+          if let Some(invoice) = tx_repo.settle_invoice(settlement_id, false).await? {
+            log::info!(
+              "Invoice {} is open again, its settlement was rejected",
+              invoice.reference
+            );
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Withdraw the affirmation of a confidential asset settlement leg.
+///
+/// The settlement can't be executed until the party affirms again.  Withdrawing a sender
+/// affirmation gives back the affirmed amounts, the account's local balance is updated.
+#[utoipa::path(
+  params(TxJobQuery),
+  responses(
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
+  )
+)]
+#[post(
+  "/tx/accounts/{public_key}/withdraw_affirmation",
+  wrap = "ReplayProtection"
+)]
+pub async fn tx_withdraw_affirmation(
+  path: web::Path<String>,
+  req: web::Json<WithdrawAffirmationRequest>,
+  job_query: web::Query<TxJobQuery>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let public_key = path.into_inner();
+  let signer_name = resolve_signer(&tx_repo, &public_key, &req.signer).await?;
+  let mut signer = signing
+    .get_tx_signer(&signer_name, "unaffirm_transactions")
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
+  repo
+    .get_account(&public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  // Mediator accounts don't have a balance.
+  let account_with_secret = repo.get_account_with_secret(&public_key).await?;
+
+  let party = match req.party {
+    TransactionParty::Sender => LegParty::Sender,
+    TransactionParty::Receiver => LegParty::Receiver,
+    TransactionParty::Mediator => LegParty::Mediator,
+  };
+  let unaffirms = UnaffirmTransactions(vec![UnaffirmTransaction {
+    id: req.transaction_id,
+    leg: UnaffirmLeg {
+      leg_id: req.leg_id,
+      party,
+    },
+  }]);
+  let call = api
+    .call()
+    .confidential_asset()
+    .unaffirm_transactions(unaffirms)
+    .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "withdraw_affirmation",
+      &signer_name,
+      "unaffirm_transactions",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "withdraw_affirmation",
+      res,
+      req.finalize,
+      move |res| async move {
+        let mut res = res?;
+        budgets.record(budget, &res).await;
+
+        // Update account balance.
+        if res.success {
+          if let Some(account_with_secret) = &account_with_secret {
+            if let Some(updates) = res.decrypt_balance_updates(account_with_secret) {
+              for (_asset_id, update) in updates {
+                repo.update_account_asset(&update).await?;
+              }
+            }
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Query chain for the pending settlements involving an account.
+///
+/// Returns the account's legs of each pending settlement with its role and affirmation
+/// status.  When the account's secret key is stored here, the leg amounts are decrypted
+/// from the sender proofs of the settlement events recorded by the chain watcher.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Vec<PendingSettlement>)
+  )
+)]
+#[get("/tx/accounts/{public_key}/pending_transactions")]
+pub async fn get_pending_transactions(
+  path: web::Path<String>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let public_key = path.into_inner();
+  let account = PublicKey::from_str(&public_key)?.as_confidential_account()?;
+  // Only decrypt the amounts with an unlocked account.
+  let account_with_secret = repo
+    .get_account_with_secret(&public_key)
+    .await?
+    .filter(|account| account.ensure_unlocked().is_ok());
+
+  // Get the account's affirmations, grouped by settlement.
+  let affirmations = api
+    .paged_query()
+    .confidential_asset()
+    .user_affirmations(account)
+    .entries();
+  pin!(affirmations);
+  let mut pending = BTreeMap::new();
+  while let Some(affirmation) = affirmations.next().await {
+    match affirmation {
+      Ok(((transaction_id, leg_id, party), Some(affirmed))) => {
+        let role = match party {
+          LegParty::Sender => TransactionParty::Sender,
+          LegParty::Receiver => TransactionParty::Receiver,
+          LegParty::Mediator => TransactionParty::Mediator,
+        };
+        pending
+          .entry(transaction_id.0)
+          .or_insert_with(Vec::new)
+          .push((transaction_id, leg_id, role, affirmed));
+      }
+      Ok((_, None)) => (),
+      Err(err) => {
+        Err(Error::from(err))?;
+      }
+    }
+  }
+
+  let mut settlements = Vec::new();
+  for (settlement_id, legs) in pending {
+    let transaction_id = legs[0].0;
+    let details = match api
+      .query()
+      .confidential_asset()
+      .transactions(transaction_id)
+      .await
+      .map_err(|err| Error::from(err))?
+    {
+      Some(details) => details,
+      // Executed or rejected since the affirmations were read.
+      None => continue,
+    };
+    let pending_affirms = api
+      .query()
+      .confidential_asset()
+      .pending_affirms(transaction_id)
+      .await
+      .map_err(|err| Error::from(err))?
+      .unwrap_or_default();
+    let memo = memo_to_string(&details.memo);
+
+    // The sender proofs of the affirmed legs.
+    let mut proofs = BTreeMap::new();
+    if account_with_secret.is_some() {
+      for rec in tx_repo.get_settlement_events(settlement_id as i64).await? {
+        if let ProcessedEvent::ConfidentialTransactionAffirmed(TransactionAffirmed {
+          leg_id,
+          transfer_proofs: Some(transfer_proofs),
+          ..
+        }) = serde_json::from_str::<ProcessedEvent>(&rec.event)?
+        {
+          proofs.insert(leg_id.0, transfer_proofs.proofs);
+        }
+      }
+    }
+
+    let mut pending_legs = Vec::new();
+    for (transaction_id, leg_id, role, affirmed) in legs {
+      let leg = api
+        .query()
+        .confidential_asset()
+        .transaction_legs(transaction_id, leg_id)
+        .await
+        .map_err(|err| Error::from(err))?
+        .ok_or_else(|| Error::not_found("Transaction Leg"))?;
+      let amounts = match (&account_with_secret, proofs.get(&leg_id.0)) {
+        (Some(account_with_secret), Some(leg_proofs)) => {
+          let mut amounts = Vec::new();
+          for (asset_id, proof) in leg_proofs {
+            let amount = match role {
+              TransactionParty::Sender => {
+                Some(account_with_secret.sender_decrypt_sender_proof(proof)?)
+              }
+              TransactionParty::Receiver => {
+                let receiver = account_with_secret.clone();
+                let proof = proof.clone();
+                spawn_proof(move || receiver.receiver_verify_sender_proof(&proof, None))
+                  .await?
+                  .amount()
+              }
+              TransactionParty::Mediator => None,
+            };
+            if let Some(amount) = amount {
+              amounts.push(TransactionAssetAmount {
+                asset_id: *asset_id,
+                amount,
+              });
+            }
+          }
+          Some(amounts).filter(|amounts| amounts.len() == leg_proofs.len())
+        }
+        _ => None,
+      };
+      pending_legs.push(PendingSettlementLeg {
+        leg_id: leg_id.0 as _,
+        role,
+        affirmed,
+        leg: TransactionLegDetails::from_leg(&leg),
+        amounts,
+      });
+    }
+
+    settlements.push(PendingSettlement {
+      settlement_id: settlement_id as _,
+      venue_id: details.venue_id.0 as _,
+      memo: Some(memo).filter(|memo| memo.len() > 0),
+      pending_affirms,
+      legs: pending_legs,
+    });
+  }
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(HttpResponse::Ok().json(settlements))
 }
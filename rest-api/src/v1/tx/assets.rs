@@ -1,30 +1,84 @@
-use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
 use polymesh_api::types::{
   pallet_confidential_asset::TransactionId, polymesh_primitives::settlement::VenueId,
 };
-use polymesh_api::Api;
 
-use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_api::{
+  receipts::AppReceiptSigner, repo::Repository, screening::AppScreening,
+};
 use polymesh_private_proof_shared::{
-  error::Error, scale_convert, AddAsset, AllowVenues, ConfidentialAssetDetails,
-  CreateConfidentialAsset, CreateConfidentialSettlement, ExecuteConfidentialSettlement,
-  ProcessedEvent, TransactionArgs, TransactionResult,
+  error::Error, AllowVenues, AssetDetailsResult, ConfidentialAssetDetails, CreateConfidentialAsset,
+  CreateConfidentialSettlement, CreateVenue, ExecuteConfidentialSettlement, ProcessedEvent,
+  SetVenueAutoExecute, Venue,
 };
 
+use crate::asset_cache::AppAssetDetailsCache;
+use crate::budgets::AppSignerBudgets;
+use crate::dry_run::dry_run;
+use crate::nodes::AppNodes;
+use crate::outbox::{AppTxOutbox, TxSubmission};
+use crate::repo::TransactionRepository;
+use crate::screening::screen_chain_receiver;
 use crate::signing::AppSigningManager;
+use crate::tx_jobs::{AppTxJobs, TxJobOutcome, TxJobQuery};
 
 pub fn service(cfg: &mut web::ServiceConfig) {
   cfg
     .service(tx_create_asset)
     .service(tx_create_venue)
+    .service(get_venues)
+    .service(set_venue_auto_execute)
     .service(tx_allow_venues)
+    .service(get_assets_details)
     .service(get_asset_details)
     .service(tx_create_settlement)
     .service(tx_execute_settlement);
 }
 
+/// Maximum number of assets of a bulk asset details request.
+const MAX_BULK_ASSETS: usize = 100;
+
+/// Assets to get the details of.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct AssetIdsQuery {
+  /// Comma separated asset ids (max 100).
+  pub ids: String,
+}
+
+/// Get the details of many assets, e.g. for a dashboard.
+///
+/// Assets that aren't cached are read from the same block.  Unknown assets have no
+/// details.
+#[utoipa::path(
+  params(AssetIdsQuery),
+  responses(
+    (status = 200, body = [AssetDetailsResult])
+  )
+)]
+#[get("/tx/assets")]
+pub async fn get_assets_details(
+  query: web::Query<AssetIdsQuery>,
+  asset_cache: AppAssetDetailsCache,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let asset_ids = query
+    .ids
+    .split(',')
+    .map(str::trim)
+    .filter(|id| !id.is_empty())
+    .map(|id| Uuid::parse_str(id).map_err(|_| Error::Other(format!("Invalid asset id: {id}"))))
+    .collect::<Result<Vec<_>, _>>()?;
+  if asset_ids.len() > MAX_BULK_ASSETS {
+    return Err(Error::Other(format!("At most {MAX_BULK_ASSETS} assets per request")).into());
+  }
+  let details = asset_cache.get_many(&nodes.api(), &asset_ids).await?;
+  Ok(HttpResponse::Ok().json(details))
+}
+
 /// Get asset details.
 #[utoipa::path(
   responses(
@@ -34,228 +88,459 @@ pub fn service(cfg: &mut web::ServiceConfig) {
 #[get("/tx/assets/{asset_id}")]
 pub async fn get_asset_details(
   asset_id: web::Path<Uuid>,
-  _repo: Repository,
-  api: web::Data<Api>,
+  asset_cache: AppAssetDetailsCache,
+  nodes: AppNodes,
 ) -> Result<impl Responder> {
-  // Get confidential asset details.
-  let details = api
-    .query()
-    .confidential_asset()
-    .details(*asset_id.as_bytes())
-    .await
-    .map_err(|err| Error::from(err))?
-    .ok_or_else(|| Error::not_found("Confidential asset doesn't exist"))?;
-
-  // Get and convert asset auditors.
-  let asset_auditors = api
-    .query()
-    .confidential_asset()
-    .asset_auditors(*asset_id.as_bytes())
-    .await
-    .map_err(|err| Error::from(err))?
+  let details = asset_cache
+    .get(&nodes.api(), asset_id.into_inner())
+    .await?
     .ok_or_else(|| Error::not_found("Confidential asset doesn't exist"))?;
-  let mediators = asset_auditors.mediators.iter().map(|d| d.clone()).collect();
-  let auditors = asset_auditors
-    .auditors
-    .iter()
-    .map(|k| scale_convert(k))
-    .collect();
-
-  let details = ConfidentialAssetDetails {
-    total_supply: details.total_supply as u64,
-    owner: details.owner_did,
-    mediators,
-    auditors,
-  };
   Ok(HttpResponse::Ok().json(details))
 }
 
 /// Allow Venues.
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
 #[post("/tx/assets/{asset_id}/allow_venues")]
 pub async fn tx_allow_venues(
   asset_id: web::Path<Uuid>,
   req: web::Json<AllowVenues>,
+  job_query: web::Query<TxJobQuery>,
   _repo: Repository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&req.signer, "allow_venues")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
 
   let venues = req.venues();
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .allow_venues(*asset_id.as_bytes(), venues)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "allow_venues",
+      &req.signer,
+      "allow_venues",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "allow_venues",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
 
 /// Create confidential asset on-chain.
+///
+/// The created asset is added to the local database with its `name` and `ticker`, its id is
+/// returned in `created_asset_id`.  Fails before submitting the transaction if a local asset
+/// already uses the ticker, so a retried request doesn't create a second asset.
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
 #[post("/tx/assets/create_asset")]
 pub async fn tx_create_asset(
   req: web::Json<CreateConfidentialAsset>,
+  job_query: web::Query<TxJobQuery>,
   repo: Repository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&req.signer, "create_asset")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
 
+  if let Some(ticker) = &req.ticker {
+    if let Some(asset) = repo.get_asset_by_ticker(ticker).await? {
+      Err(Error::Other(format!(
+        "Ticker {ticker} is already used by asset {}",
+        asset.asset_id
+      )))?;
+    }
+  }
   let auditors = req.auditors()?;
 
   // TODO: Check if the mediators exist on-chain.
 
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .create_asset(vec![], auditors)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "create_asset",
+      &req.signer,
+      "create_asset",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
-
-  for event in &res.processed_events.0 {
-    match event {
-      ProcessedEvent::ConfidentialAssetCreated { asset_id, .. } => {
-        // Check if the asset exists.
-        if repo.get_asset(*asset_id).await?.is_none() {
-          repo
-            .create_asset(&AddAsset {
-              asset_id: *asset_id,
-            })
-            .await?;
+  let create = (*req).clone();
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "create_asset",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+
+        for event in &res.processed_events.0 {
+          match event {
+            ProcessedEvent::ConfidentialAssetCreated { asset_id, .. } => {
+              // Link the local asset to the on-chain asset.
+              repo.link_asset(&create.add_asset(*asset_id)).await?;
+            }
+            _ => (),
+          }
         }
-      }
-      _ => (),
-    }
-  }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
 
 /// Create confidential asset settlement.
+///
+/// The leg receivers are screened first (see `SCREENING`).
+///
+/// With `dry_run` the transaction isn't submitted, the estimated fees are returned instead
+/// (`DryRunResult`).
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
 #[post("/tx/venues/{venue_id}/settlement/create")]
 pub async fn tx_create_settlement(
   venue_id: web::Path<u64>,
   req: web::Json<CreateConfidentialSettlement>,
+  job_query: web::Query<TxJobQuery>,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  screening: AppScreening,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&req.signer, "add_transaction")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
 
   let venue_id = VenueId(*venue_id);
   let memo = req.memo()?;
   let legs = req.legs()?;
-  let res = api
+  // Screen the receivers.
+  for leg in &legs {
+    screen_chain_receiver(&screening, &api, leg.receiver).await?;
+  }
+  let call = api
     .call()
     .confidential_asset()
     .add_transaction(venue_id, legs, memo)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  if req.dry_run {
+    let res = dry_run(&api, &*signer, "add_transaction", call).await?;
+    return Ok(HttpResponse::Ok().json(res));
+  }
+  let res = match outbox
+    .submit(
+      "create_settlement",
+      &req.signer,
+      "add_transaction",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "create_settlement",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
 
 /// Execute confidential asset settlement.
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
 #[post("/tx/settlements/{settlement_id}/execute")]
 pub async fn tx_execute_settlement(
   transaction_id: web::Path<u64>,
   req: web::Json<ExecuteConfidentialSettlement>,
+  job_query: web::Query<TxJobQuery>,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&req.signer, "execute_transaction")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
 
   let transaction_id = TransactionId(*transaction_id);
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .execute_transaction(transaction_id, req.leg_count)
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "execute_settlement",
+      &req.signer,
+      "execute_transaction",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "execute_settlement",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Get the venues created by our signers.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [Venue])
+  )
+)]
+#[get("/tx/venues")]
+pub async fn get_venues(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let venues = tx_repo.get_venues().await?;
+  Ok(HttpResponse::Ok().json(venues))
+}
+
+/// Enable/disable auto-execution of the venue's settlements.
+///
+/// With auto-execution the chain watcher executes the venue's settlements with the venue
+/// signer as soon as all parties have affirmed.
+#[utoipa::path(
+  responses(
+    (status = 200, body = Venue)
+  )
+)]
+#[post("/tx/venues/{venue_id}/auto_execute")]
+pub async fn set_venue_auto_execute(
+  venue_id: web::Path<i64>,
+  req: web::Json<SetVenueAutoExecute>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let venue = tx_repo
+    .set_venue_auto_execute(*venue_id, req.auto_execute)
+    .await?
+    .ok_or_else(|| Error::not_found("Venue"))?;
+  Ok(HttpResponse::Ok().json(venue))
 }
 
 /// Create Venue.
+///
+/// The created venue is added to the local venue registry (`/tx/venues`) with the
+/// signer and label.
 #[utoipa::path(
+  params(TxJobQuery),
   responses(
-    (status = 200, body = TransactionResult)
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
   )
 )]
 #[post("/tx/assets/create_venue")]
 pub async fn tx_create_venue(
-  req: web::Json<TransactionArgs>,
+  req: web::Json<CreateVenue>,
+  job_query: web::Query<TxJobQuery>,
+  tx_repo: TransactionRepository,
   signing: AppSigningManager,
-  api: web::Data<Api>,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
 ) -> Result<impl Responder> {
+  let api = nodes.api();
   let mut signer = signing
-    .get_signer(&req.signer)
+    .get_tx_signer(&req.signer, "create_venue")
     .await?
     .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
 
-  let res = api
+  let call = api
     .call()
     .confidential_asset()
     .create_venue()
-    .map_err(|err| Error::from(err))?
-    .submit_and_watch(&mut signer)
-    .await
     .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "create_venue",
+      &req.signer,
+      "create_venue",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
 
   // Wait for transaction results.
-  let res = TransactionResult::wait_for_results(res, req.finalize).await?;
+  let signer_name = req.signer.clone();
+  let label = req.label.clone();
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "create_venue",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+
+        for event in &res.processed_events.0 {
+          match event {
+            ProcessedEvent::ConfidentialVenueCreated { venue_id } => {
+              tx_repo
+                .add_venue(venue_id.0 as i64, &signer_name, label.as_deref())
+                .await?;
+            }
+            _ => (),
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
 
-  Ok(HttpResponse::Ok().json(res))
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
 }
@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_api::{repo::Repository, valuation::AppValuation};
+use polymesh_private_proof_shared::{
+  error::Error, IdentityPortfolio, PortfolioAccount, SettlementDetails, SettlementLegFilter,
+  ValuationQuery,
+};
+
+use crate::repo::TransactionRepository;
+use crate::v1::contacts::add_leg_contacts;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_identity_portfolio);
+}
+
+/// Get all local confidential accounts of an identity, with their balances and pending
+/// settlements.
+///
+/// Only includes accounts with a known DID (see `/tx/accounts/{public_key}/identity`).
+/// With `currency` the portfolio includes the aggregated value of all balances.
+#[utoipa::path(
+  params(ValuationQuery),
+  responses(
+    (status = 200, body = IdentityPortfolio)
+  )
+)]
+#[get("/tx/identities/{did}/portfolio")]
+pub async fn get_identity_portfolio(
+  did: web::Path<String>,
+  query: web::Query<ValuationQuery>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  valuation: AppValuation,
+) -> Result<impl Responder> {
+  let did = did.into_inner().to_lowercase();
+  let did = if did.starts_with("0x") {
+    did
+  } else {
+    format!("0x{did}")
+  };
+
+  let mut settlement_ids = BTreeSet::new();
+  let mut accounts = Vec::new();
+  for account in repo.get_accounts_by_did(&did).await? {
+    let public_key = hex::encode(&account.confidential_account);
+    let filter = SettlementLegFilter {
+      account: Some(public_key.clone()),
+      ..Default::default()
+    };
+    for leg in tx_repo.get_settlement_legs(&filter).await? {
+      settlement_ids.insert(leg.settlement_id);
+    }
+    let balances = repo.get_account_assets(&public_key).await?;
+    accounts.push(PortfolioAccount { account, balances });
+  }
+  if accounts.is_empty() {
+    return Err(Error::not_found("Identity accounts").into());
+  }
+
+  let mut pending_settlements = Vec::new();
+  for rec in tx_repo.get_pending_settlements().await? {
+    if !settlement_ids.contains(&rec.settlement_id) {
+      continue;
+    }
+    let filter = SettlementLegFilter {
+      settlement_id: Some(rec.settlement_id),
+      ..Default::default()
+    };
+    let mut legs = tx_repo.get_settlement_legs(&filter).await?;
+    add_leg_contacts(&tx_repo, &mut legs).await?;
+    pending_settlements.push(SettlementDetails::from_record(&rec, legs));
+  }
+
+  let valuation = match &query.currency {
+    Some(currency) => {
+      let balances = accounts.iter().flat_map(|account| &account.balances);
+      Some(valuation.value_portfolio(balances, currency).await?)
+    }
+    None => None,
+  };
+
+  Ok(HttpResponse::Ok().json(IdentityPortfolio {
+    did,
+    accounts,
+    pending_settlements,
+    valuation,
+  }))
+}
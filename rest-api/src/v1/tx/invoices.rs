@@ -0,0 +1,129 @@
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder, Result};
+
+use polymesh_api::types::polymesh_primitives::settlement::VenueId;
+
+use polymesh_private_proof_api::{
+  receipts::AppReceiptSigner, replay::ReplayProtection, screening::AppScreening,
+};
+use polymesh_private_proof_shared::{error::Error, PayInvoice, ProcessedEvent, PublicKey};
+
+use crate::budgets::AppSignerBudgets;
+use crate::nodes::AppNodes;
+use crate::outbox::{AppTxOutbox, TxSubmission};
+use crate::repo::TransactionRepository;
+use crate::screening::screen_chain_receiver;
+use crate::signing::AppSigningManager;
+use crate::tx_jobs::{AppTxJobs, TxJobOutcome, TxJobQuery};
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(tx_pay_invoice);
+}
+
+/// Pay a payment request (invoice).
+///
+/// Creates a settlement with one leg from the sender to the invoice's receiver, with the
+/// invoice reference as memo.  The sender then affirms the leg with the invoice `amount`
+/// (see `sender_affirm_leg`).  The chain watcher marks the invoice paid when the
+/// settlement is executed.
+#[utoipa::path(
+  params(TxJobQuery),
+  responses(
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
+  )
+)]
+#[post(
+  "/tx/accounts/{public_key}/invoices/{reference}/pay",
+  wrap = "ReplayProtection"
+)]
+pub async fn tx_pay_invoice(
+  path: web::Path<(PublicKey, String)>,
+  req: web::Json<PayInvoice>,
+  job_query: web::Query<TxJobQuery>,
+  tx_repo: TransactionRepository,
+  signing: AppSigningManager,
+  budgets: AppSignerBudgets,
+  tx_jobs: AppTxJobs,
+  outbox: AppTxOutbox,
+  screening: AppScreening,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let (sender, reference) = path.into_inner();
+  let invoice = tx_repo
+    .get_invoice(&reference)
+    .await?
+    .ok_or_else(|| Error::not_found("Invoice"))?;
+  if invoice.status != "open" {
+    return Err(Error::other(&format!("The invoice is {}", invoice.status)).into());
+  }
+  if invoice.is_expired() {
+    return Err(Error::other("The invoice has expired").into());
+  }
+
+  let mut signer = signing
+    .get_tx_signer(&req.signer, "add_transaction")
+    .await?
+    .ok_or_else(|| Error::not_found("Signer"))?;
+  let budget = budgets.check(&signer).await?;
+
+  let settlement = req.settlement(sender, &invoice)?;
+  let venue_id = VenueId(req.venue_id);
+  let memo = settlement.memo()?;
+  let legs = settlement.legs()?;
+  // Screen the receiver.
+  for leg in &legs {
+    screen_chain_receiver(&screening, &api, leg.receiver).await?;
+  }
+  let call = api
+    .call()
+    .confidential_asset()
+    .add_transaction(venue_id, legs, memo)
+    .map_err(|err| Error::from(err))?;
+  let res = match outbox
+    .submit(
+      "pay_invoice",
+      &req.signer,
+      "add_transaction",
+      &mut signer,
+      call,
+      req.finalize,
+    )
+    .await?
+  {
+    TxSubmission::Submitted(res) => res,
+    TxSubmission::Queued(entry) => return Ok(HttpResponse::Accepted().json(entry)),
+  };
+
+  // Wait for transaction results.
+  let outcome = tx_jobs
+    .wait_for_results(
+      &job_query,
+      "pay_invoice",
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        budgets.record(budget, &res).await;
+
+        // Link the invoice to the settlement, without waiting for the chain watcher.
+        for ev in &res.processed_events.0 {
+          if let ProcessedEvent::ConfidentialTransactionCreated(created) = ev {
+            tx_repo
+              .set_invoice_settlement(&reference, created.transaction_id.0 as i64)
+              .await?;
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
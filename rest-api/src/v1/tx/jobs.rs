@@ -0,0 +1,57 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_private_proof_shared::{error::Error, TxJob};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_tx_jobs).service(get_tx_job);
+}
+
+/// Transaction job filter.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct TxJobsQuery {
+  /// Only return jobs with this status (`submitted`, `in_block`, `completed` or `failed`).
+  pub status: Option<String>,
+}
+
+/// Get background transaction jobs.
+#[utoipa::path(
+  params(TxJobsQuery),
+  responses(
+    (status = 200, body = [TxJob])
+  )
+)]
+#[get("/tx/jobs")]
+pub async fn get_tx_jobs(
+  query: web::Query<TxJobsQuery>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let jobs = tx_repo
+    .get_tx_jobs(query.into_inner().status)
+    .await?
+    .into_iter()
+    .map(TxJob::from_row)
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(HttpResponse::Ok().json(jobs))
+}
+
+/// Get a background transaction job.
+#[utoipa::path(
+  responses(
+    (status = 200, body = TxJob)
+  )
+)]
+#[get("/tx/jobs/{job_id}")]
+pub async fn get_tx_job(
+  job_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let job = tx_repo
+    .get_tx_job(*job_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Transaction job"))?;
+  Ok(HttpResponse::Ok().json(TxJob::from_row(job)?))
+}
@@ -0,0 +1,167 @@
+use std::str::FromStr;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+
+use polymesh_api::client::AccountId;
+
+use polymesh_private_proof_api::{receipts::AppReceiptSigner, repo::Repository};
+use polymesh_private_proof_shared::{
+  error::Error, AddAsset, BuildTransaction, ProcessedEvent, SubmitSignedTransaction,
+  UnsignedTransaction,
+};
+
+use crate::nodes::AppNodes;
+use crate::offline::{build_call, build_payload, PresignedSigner};
+use crate::repo::TransactionRepository;
+use crate::tx_jobs::{AppTxJobs, TxJobOutcome, TxJobQuery};
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(tx_build)
+    .service(get_unsigned_transaction)
+    .service(tx_submit_signed);
+}
+
+/// Build an unsigned transaction for an offline signer.
+///
+/// Returns the SCALE encoded signing payload and nonce without submitting the transaction.
+/// Sign the payload with the account's key and submit the signature with
+/// `/tx/submit_signed`.  The payload is only valid for the nonce, build again if the
+/// account submits another transaction first.
+#[utoipa::path(
+  responses(
+    (status = 200, body = UnsignedTransaction)
+  )
+)]
+#[post("/tx/build")]
+pub async fn tx_build(
+  req: web::Json<BuildTransaction>,
+  tx_repo: TransactionRepository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let account =
+    AccountId::from_str(&req.account).map_err(|_| Error::other("Invalid account address"))?;
+  let (nonce, payload) = build_payload(&api, account, &req.call).await?;
+  let unsigned = tx_repo
+    .add_unsigned_transaction(&req.account, &req.call, nonce, &payload)
+    .await?;
+  Ok(HttpResponse::Ok().json(unsigned))
+}
+
+/// Get an unsigned transaction.
+#[utoipa::path(
+  responses(
+    (status = 200, body = UnsignedTransaction)
+  )
+)]
+#[get("/tx/build/{build_id}")]
+pub async fn get_unsigned_transaction(
+  build_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let unsigned = tx_repo
+    .get_unsigned_transaction(*build_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Unsigned transaction"))?;
+  Ok(HttpResponse::Ok().json(unsigned))
+}
+
+/// Submit a transaction signed by an offline signer.
+#[utoipa::path(
+  params(TxJobQuery),
+  responses(
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
+  )
+)]
+#[post("/tx/submit_signed")]
+pub async fn tx_submit_signed(
+  req: web::Json<SubmitSignedTransaction>,
+  job_query: web::Query<TxJobQuery>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  tx_jobs: AppTxJobs,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let outcome = submit_signed(&req, &job_query, repo, tx_repo, &tx_jobs, &nodes).await?;
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Submit an unsigned transaction with the offline signer's signature.
+pub async fn submit_signed(
+  req: &SubmitSignedTransaction,
+  job_query: &TxJobQuery,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  tx_jobs: &AppTxJobs,
+  nodes: &AppNodes,
+) -> Result<TxJobOutcome, Error> {
+  let api = nodes.api();
+  let unsigned = tx_repo
+    .get_unsigned_transaction(req.build_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Unsigned transaction"))?;
+  if unsigned.submitted_at.is_some() {
+    Err(Error::other("Transaction already submitted"))?;
+  }
+  let account =
+    AccountId::from_str(&unsigned.account).map_err(|_| Error::other("Invalid account address"))?;
+  let mut signer = PresignedSigner::new(
+    account,
+    unsigned.nonce,
+    unsigned.payload.clone(),
+    &req.signature,
+  )?;
+
+  let res = build_call(&api, &unsigned.call)?
+    .submit_and_watch(&mut signer)
+    .await
+    .map_err(|err| Error::from(err))?;
+
+  // Wait for transaction results.
+  let build_id = unsigned.build_id;
+  let account = unsigned.account.clone();
+  tx_jobs
+    .wait_for_results(
+      job_query,
+      unsigned.call.extrinsic(),
+      res,
+      req.finalize,
+      move |res| async move {
+        let res = res?;
+        tx_repo
+          .set_unsigned_transaction_submitted(build_id, &res.tx_hash)
+          .await?;
+
+        for event in &res.processed_events.0 {
+          match event {
+            ProcessedEvent::ConfidentialAssetCreated { asset_id, .. } => {
+              // Check if the asset exists.
+              if repo.get_asset(*asset_id).await?.is_none() {
+                repo
+                  .create_asset(&AddAsset {
+                    asset_id: *asset_id,
+                    ..Default::default()
+                  })
+                  .await?;
+              }
+            }
+            ProcessedEvent::ConfidentialVenueCreated { venue_id } => {
+              tx_repo.add_venue(venue_id.0 as i64, &account, None).await?;
+            }
+            _ => (),
+          }
+        }
+        Ok(res)
+      },
+    )
+    .await
+}
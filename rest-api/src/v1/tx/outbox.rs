@@ -0,0 +1,63 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_private_proof_shared::{error::Error, TxOutboxEntry};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_tx_outbox_entries)
+    .service(get_tx_outbox_entry);
+}
+
+/// Transaction outbox filter.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct TxOutboxQuery {
+  /// Only return entries with this status (`pending`, `submitted`, `completed` or
+  /// `failed`).
+  pub status: Option<String>,
+}
+
+/// Get the transactions that failed to submit and are retried in the background.
+///
+/// When the node can't accept a transaction (node down, priority too low), the transaction
+/// endpoints return its outbox entry (`202 Accepted`) instead of an error.
+#[utoipa::path(
+  params(TxOutboxQuery),
+  responses(
+    (status = 200, body = [TxOutboxEntry])
+  )
+)]
+#[get("/tx/outbox")]
+pub async fn get_tx_outbox_entries(
+  query: web::Query<TxOutboxQuery>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let entries = tx_repo
+    .get_tx_outbox_entries(query.into_inner().status)
+    .await?
+    .into_iter()
+    .map(TxOutboxEntry::from_row)
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(HttpResponse::Ok().json(entries))
+}
+
+/// Get the status of a transaction in the outbox.
+#[utoipa::path(
+  responses(
+    (status = 200, body = TxOutboxEntry)
+  )
+)]
+#[get("/tx/outbox/{outbox_id}")]
+pub async fn get_tx_outbox_entry(
+  outbox_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let entry = tx_repo
+    .get_tx_outbox_entry(*outbox_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Outbox transaction"))?;
+  Ok(HttpResponse::Ok().json(TxOutboxEntry::from_row(entry)?))
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+
+use polymesh_private_proof_api::{api_keys::authenticated_user, repo::Repository};
+use polymesh_private_proof_shared::{
+  error::Error, AssignSettlementLegsRequest, AssignedSettlementLegs, CreateSettlementAnnotation,
+  SettlementAnnotation, SettlementDetails, SettlementFilter, SettlementLegFilter,
+};
+
+use crate::repo::TransactionRepository;
+use crate::v1::contacts::add_leg_contacts;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_settlements)
+    .service(assign_settlement_legs)
+    .service(get_settlement)
+    .service(get_settlement_annotations)
+    .service(add_settlement_annotation)
+    .service(get_settlement_legs);
+}
+
+/// Get all settlements processed by the chain watcher.
+///
+/// Legs include the sender and receiver from the contacts directory.  Settlements can be
+/// filtered by the status of their latest annotation.
+#[utoipa::path(
+  params(SettlementFilter),
+  responses(
+    (status = 200, body = [SettlementDetails])
+  )
+)]
+#[get("/tx/settlements")]
+pub async fn get_settlements(
+  filter: web::Query<SettlementFilter>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let mut annotations = tx_repo
+    .get_latest_settlement_annotations()
+    .await?
+    .into_iter()
+    .map(|annotation| (annotation.settlement_id, annotation))
+    .collect::<BTreeMap<_, _>>();
+  let mut all_legs = tx_repo.get_settlement_legs(&Default::default()).await?;
+  add_leg_contacts(&tx_repo, &mut all_legs).await?;
+  let mut legs = BTreeMap::<_, Vec<_>>::new();
+  for leg in all_legs {
+    legs.entry(leg.settlement_id).or_default().push(leg);
+  }
+  let settlements = tx_repo
+    .get_settlements()
+    .await?
+    .into_iter()
+    .map(|rec| {
+      let legs = legs.remove(&rec.settlement_id).unwrap_or_default();
+      let mut details = SettlementDetails::from_record(&rec, legs);
+      details.annotation = annotations.remove(&(rec.settlement_id as i64));
+      details
+    })
+    .filter(|details| match &filter.status {
+      Some(status) => details
+        .annotation
+        .as_ref()
+        .map(|annotation| annotation.status == status.as_str())
+        .unwrap_or(false),
+      None => true,
+    })
+    .collect::<Vec<_>>();
+  Ok(HttpResponse::Ok().json(settlements))
+}
+
+/// Group transfers into settlement legs.
+///
+/// Returns the body for `/tx/venues/{venue_id}/settlement/create` and the asset amounts
+/// of each leg for the sender affirmations.  Nothing is submitted.
+#[utoipa::path(
+  responses(
+    (status = 200, body = AssignedSettlementLegs)
+  )
+)]
+#[post("/tx/settlements/assign_legs")]
+pub async fn assign_settlement_legs(
+  req: web::Json<AssignSettlementLegsRequest>,
+) -> Result<impl Responder> {
+  Ok(HttpResponse::Ok().json(req.assign()?))
+}
+
+/// Get a settlement processed by the chain watcher.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SettlementDetails)
+  )
+)]
+#[get("/tx/settlements/{settlement_id}")]
+pub async fn get_settlement(
+  settlement_id: web::Path<u32>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let settlement_id = settlement_id.into_inner();
+  let rec = tx_repo
+    .get_settlement(settlement_id as i64)
+    .await?
+    .ok_or_else(|| Error::not_found("Settlement"))?;
+  let filter = SettlementLegFilter {
+    settlement_id: Some(settlement_id),
+    ..Default::default()
+  };
+  let mut legs = tx_repo.get_settlement_legs(&filter).await?;
+  add_leg_contacts(&tx_repo, &mut legs).await?;
+  let mut details = SettlementDetails::from_record(&rec, legs);
+  details.annotation = tx_repo
+    .get_settlement_annotations(settlement_id as i64)
+    .await?
+    .pop();
+  Ok(HttpResponse::Ok().json(details))
+}
+
+/// Get the status annotations of a settlement, oldest first.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [SettlementAnnotation])
+  )
+)]
+#[get("/tx/settlements/{settlement_id}/annotations")]
+pub async fn get_settlement_annotations(
+  settlement_id: web::Path<u32>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let annotations = tx_repo
+    .get_settlement_annotations(settlement_id.into_inner() as i64)
+    .await?;
+  Ok(HttpResponse::Ok().json(annotations))
+}
+
+/// Annotate a settlement, e.g. mark it as disputed.
+///
+/// The author is the user authenticated by the `X-Api-Key` header.  Annotations are only
+/// stored locally.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SettlementAnnotation)
+  )
+)]
+#[post("/tx/settlements/{settlement_id}/annotations")]
+pub async fn add_settlement_annotation(
+  settlement_id: web::Path<u32>,
+  req: web::Json<CreateSettlementAnnotation>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let settlement_id = settlement_id.into_inner() as i64;
+  let author = authenticated_user(&repo, &http_req).await?.username;
+  tx_repo
+    .get_settlement(settlement_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Settlement"))?;
+  let annotation = tx_repo
+    .add_settlement_annotation(
+      settlement_id,
+      req.status.as_str(),
+      req.notes.as_deref(),
+      &author,
+    )
+    .await?;
+  Ok(HttpResponse::Ok().json(annotation))
+}
+
+/// Search settlement legs, e.g. all legs where an account is the receiver.
+#[utoipa::path(
+  params(SettlementLegFilter),
+  responses(
+    (status = 200, body = [SettlementLeg])
+  )
+)]
+#[get("/tx/settlement_legs")]
+pub async fn get_settlement_legs(
+  filter: web::Query<SettlementLegFilter>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let mut legs = tx_repo.get_settlement_legs(&filter).await?;
+  add_leg_contacts(&tx_repo, &mut legs).await?;
+  Ok(HttpResponse::Ok().json(legs))
+}
@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use uuid::Uuid;
+
+use polymesh_api::types::pallet_confidential_asset::{TransactionId, TransactionLegId};
+
+use polymesh_private_proof_api::{replay::ReplayProtection, repo::Repository};
+use polymesh_private_proof_shared::{
+  confidential_account_to_key, error::Error, scale_convert, transaction_leg_key,
+  AuditorVerifyLegRequest, AuditorVerifyLegResult, BlockTransactionRecord, ChainTransactionLeg,
+  LegAssetVerifyResult, LegAuditorVerifyResult, ProcessedEvent, PublicKey, SenderProof,
+  SettlementLegFilter, SettlementLegVerifyReport, StorageReadProof, TransactionLegDetails,
+};
+
+use super::account_assets::StorageProofQuery;
+use crate::nodes::AppNodes;
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_transaction)
+    .service(get_transaction_leg)
+    .service(verify_transaction_leg)
+    .service(auditor_verify_transaction_leg);
+}
+
+/// Look up a transaction processed by the chain watcher, i.e. one that was still
+/// `pending` when the finalization timeout was reached.
+#[utoipa::path(
+  responses(
+    (status = 200, body = BlockTransactionRecord)
+  )
+)]
+#[get("/tx/transactions/{tx_hash}")]
+pub async fn get_transaction(
+  tx_hash: web::Path<String>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let tx_hash = tx_hash.into_inner().to_lowercase();
+  let tx_hash = if tx_hash.starts_with("0x") {
+    tx_hash
+  } else {
+    format!("0x{tx_hash}")
+  };
+  let tx = tx_repo
+    .get_block_transaction(&tx_hash)
+    .await?
+    .ok_or_else(|| Error::not_found("Transaction"))?;
+  Ok(HttpResponse::Ok().json(tx))
+}
+
+/// Query the chain for a confidential transaction leg.
+///
+/// With `proof=true` the leg is read at the latest finalized block.
+#[utoipa::path(
+  params(StorageProofQuery),
+  responses(
+    (status = 200, body = ChainTransactionLeg)
+  )
+)]
+#[get("/tx/transactions/{transaction_id}/legs/{leg_id}")]
+pub async fn get_transaction_leg(
+  path: web::Path<(u32, u32)>,
+  query: web::Query<StorageProofQuery>,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let (settlement_id, leg_id) = path.into_inner();
+  let transaction_id = TransactionId(settlement_id as _);
+  let tx_leg_id = TransactionLegId(leg_id as _);
+
+  let block = query.block(&api).await?;
+  let chain_query = match block {
+    Some(block) => api.query_at(block),
+    None => api.query(),
+  };
+  let leg = chain_query
+    .confidential_asset()
+    .transaction_legs(transaction_id, tx_leg_id)
+    .await
+    .map_err(|err| Error::from(err))?
+    .ok_or_else(|| Error::not_found("Transaction Leg"))?;
+
+  let proof = match block {
+    Some(block) => {
+      let key = transaction_leg_key(settlement_id as u64, leg_id);
+      Some(StorageReadProof::fetch(&api, block, key).await?)
+    }
+    None => None,
+  };
+
+  Ok(HttpResponse::Ok().json(ChainTransactionLeg {
+    settlement_id,
+    leg_id,
+    block_hash: block.map(|block| format!("{block:#x}")),
+    leg: TransactionLegDetails::from_leg(&leg),
+    proof,
+  }))
+}
+
+/// Get the sender proofs of the leg's last sender affirmation recorded by the chain watcher.
+async fn leg_sender_proofs(
+  tx_repo: &TransactionRepository,
+  settlement_id: u32,
+  leg_id: u32,
+) -> Result<Vec<(Uuid, SenderProof)>, Error> {
+  let mut proofs = None;
+  for rec in tx_repo.get_settlement_events(settlement_id as i64).await? {
+    match serde_json::from_str(&rec.event)? {
+      ProcessedEvent::ConfidentialTransactionAffirmed(affirmed)
+        if affirmed.leg_id.0 as u32 == leg_id =>
+      {
+        if let Some(transfer_proofs) = affirmed.transfer_proofs {
+          proofs = Some(transfer_proofs.proofs);
+        }
+      }
+      _ => (),
+    }
+  }
+  proofs.ok_or_else(|| Error::not_found("Sender affirmation"))
+}
+
+/// Verify the sender proofs of a confidential transaction leg against chain state.
+///
+/// The leg and the sender's balance at affirmation are queried from chain, the sender
+/// proofs are taken from the leg's last sender affirmation recorded by the chain watcher.
+/// The proofs are also verified as the receiver and auditors whose secret keys are stored
+/// here.
+#[utoipa::path(
+  responses(
+    (status = 200, body = SettlementLegVerifyReport)
+  )
+)]
+#[post(
+  "/tx/transactions/{transaction_id}/legs/{leg_id}/verify",
+  wrap = "ReplayProtection"
+)]
+pub async fn verify_transaction_leg(
+  path: web::Path<(u32, u32)>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let api = nodes.api();
+  let (settlement_id, leg_id) = path.into_inner();
+  let transaction_id = TransactionId(settlement_id as _);
+  let leg_id = TransactionLegId(leg_id as _);
+
+  // Query the chain for Transaction Leg to get the sender, receiver and auditors.
+  let leg = api
+    .query()
+    .confidential_asset()
+    .transaction_legs(transaction_id, leg_id)
+    .await
+    .map_err(|err| Error::from(err))?
+    .ok_or_else(|| Error::not_found("Transaction Leg"))?;
+  let sender = confidential_account_to_key(&leg.sender);
+  let receiver = confidential_account_to_key(&leg.receiver);
+
+  let proofs = leg_sender_proofs(&tx_repo, settlement_id, leg_id.0 as _).await?;
+  if let Some(asset_id) = leg
+    .auditors
+    .keys()
+    .map(|id| Uuid::from_bytes(*id))
+    .find(|id| !proofs.iter().any(|(asset_id, _)| asset_id == id))
+  {
+    Err(Error::Other(format!(
+      "Missing sender proof for asset: {asset_id}"
+    )))?;
+  }
+
+  // Local receiver account.
+  let receiver_key: PublicKey = scale_convert(&leg.receiver);
+  let receiver_account = repo
+    .get_account_with_secret(&hex::encode(receiver_key.0))
+    .await?
+    .filter(|account| account.ensure_unlocked().is_ok());
+
+  let mut assets = Vec::new();
+  for (asset_id, proof) in &proofs {
+    let auditor_keys: BTreeSet<PublicKey> = leg
+      .auditors
+      .get(asset_id.as_bytes())
+      .ok_or_else(|| Error::Other(format!("Invalid asset in leg: {asset_id}")))?
+      .iter()
+      .map(|auditor| scale_convert(auditor))
+      .collect();
+
+    // Query the chain for the sender's balance when the leg was affirmed.
+    let sender_balance = api
+      .query()
+      .confidential_asset()
+      .tx_leg_sender_balance((transaction_id, leg_id), *asset_id.as_bytes())
+      .await
+      .map_err(|err| Error::from(err))?
+      .ok_or_else(|| Error::other("The sender hasn't affirmed the leg"))?;
+    let sender_balance = scale_convert(&sender_balance);
+    let auditor_set = auditor_keys
+      .iter()
+      .map(|key| key.decode())
+      .collect::<Result<_, _>>()?;
+    let sender_res = proof.verify(&sender, &sender_balance, &receiver, &auditor_set)?;
+
+    let receiver_res = match &receiver_account {
+      Some(account) => Some(account.receiver_verify_sender_proof(proof, None)?),
+      None => None,
+    };
+
+    // The auditor id is the auditor's index in the leg's (sorted) auditors of the asset.
+    let mut auditors = Vec::new();
+    for (auditor_id, auditor) in auditor_keys.into_iter().enumerate() {
+      let account = repo
+        .get_account_with_secret(&hex::encode(auditor.0))
+        .await?
+        .filter(|account| account.ensure_unlocked().is_ok());
+      if let Some(account) = account {
+        let result = account.auditor_verify_sender_proof(proof, auditor_id as u32, None)?;
+        auditors.push(LegAuditorVerifyResult {
+          auditor,
+          auditor_id: auditor_id as u32,
+          result,
+        });
+      }
+    }
+
+    assets.push(LegAssetVerifyResult {
+      asset_id: *asset_id,
+      sender: sender_res,
+      receiver: receiver_res,
+      auditors,
+    });
+  }
+  Ok(HttpResponse::Ok().json(SettlementLegVerifyReport::new(
+    settlement_id,
+    leg_id.0 as _,
+    assets,
+  )))
+}
+
+/// Verify the sender proofs of a confidential transaction leg as an auditor.
+///
+/// The sender proofs are taken from the leg's last sender affirmation recorded by the chain
+/// watcher.  The auditor id of each asset is looked up in the leg's auditors (from the
+/// stored settlement, or from chain if it isn't stored).  Assets the auditor doesn't audit
+/// are skipped.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [AuditorVerifyLegResult])
+  )
+)]
+#[post(
+  "/tx/transactions/{transaction_id}/legs/{leg_id}/auditor_verify",
+  wrap = "ReplayProtection"
+)]
+pub async fn auditor_verify_transaction_leg(
+  path: web::Path<(u32, u32)>,
+  req: web::Json<AuditorVerifyLegRequest>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let (settlement_id, leg_id) = path.into_inner();
+  let auditor = repo
+    .get_account_with_secret(&hex::encode(req.auditor.0))
+    .await?
+    .ok_or_else(|| Error::not_found("Auditor account"))?;
+  auditor.ensure_unlocked()?;
+
+  // Get the leg's auditors.
+  let filter = SettlementLegFilter {
+    settlement_id: Some(settlement_id),
+    ..Default::default()
+  };
+  let stored_leg = tx_repo
+    .get_settlement_legs(&filter)
+    .await?
+    .into_iter()
+    .find(|leg| leg.leg_id == leg_id);
+  let leg_auditors: BTreeMap<Uuid, BTreeSet<PublicKey>> = match stored_leg {
+    Some(leg) => leg.leg.assets_and_auditors,
+    None => {
+      let leg = nodes
+        .api()
+        .query()
+        .confidential_asset()
+        .transaction_legs(
+          TransactionId(settlement_id as _),
+          TransactionLegId(leg_id as _),
+        )
+        .await
+        .map_err(|err| Error::from(err))?
+        .ok_or_else(|| Error::not_found("Transaction Leg"))?;
+      leg
+        .auditors
+        .iter()
+        .map(|(id, keys)| {
+          (
+            Uuid::from_bytes(*id),
+            keys.iter().map(|k| scale_convert(k)).collect(),
+          )
+        })
+        .collect()
+    }
+  };
+
+  let proofs = leg_sender_proofs(&tx_repo, settlement_id, leg_id).await?;
+  let mut results = Vec::new();
+  for (asset_id, proof) in &proofs {
+    // The auditor id is the auditor's index in the leg's (sorted) auditors of the asset.
+    let auditor_id = leg_auditors
+      .get(asset_id)
+      .and_then(|auditors| auditors.iter().position(|key| key == &req.auditor));
+    let auditor_id = match auditor_id {
+      Some(auditor_id) => auditor_id as u32,
+      None => continue,
+    };
+    let amount = match &req.amounts {
+      Some(amounts) => Some(
+        amounts
+          .iter()
+          .find(|amount| amount.asset_id == *asset_id)
+          .ok_or_else(|| Error::Other(format!("Missing amount for leg asset: {asset_id}")))?
+          .amount,
+      ),
+      None => None,
+    };
+    let result = auditor.auditor_verify_sender_proof(proof, auditor_id, amount)?;
+    results.push(AuditorVerifyLegResult {
+      asset_id: *asset_id,
+      auditor_id,
+      result,
+    });
+  }
+  if results.is_empty() {
+    Err(Error::other("The account isn't an auditor of the leg"))?;
+  }
+
+  Ok(HttpResponse::Ok().json(results))
+}
@@ -0,0 +1,58 @@
+use std::str::FromStr;
+
+use actix_web::{post, web, HttpResponse, Responder, Result};
+
+use polymesh_api::client::AccountId;
+
+use polymesh_private_proof_shared::{error::Error, CreateWalletSigningRequest};
+
+use crate::nodes::AppNodes;
+use crate::offline::build_payload;
+use crate::repo::TransactionRepository;
+use crate::wallet_pairing::AppWalletPairings;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(tx_wallet_request);
+}
+
+/// Build an unsigned transaction and request a paired wallet to sign it.
+///
+/// The request (signing payload and a human-readable summary) is posted to the wallet's
+/// callback url.  The wallet approves it with its signature, which submits the
+/// transaction, or rejects it.  Track the request with `/wallet_requests/{request_id}`.
+/// The payload is only valid for the nonce, the submission fails if the account submits
+/// another transaction before the wallet approves the request.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletSigningRequest)
+  )
+)]
+#[post("/tx/wallet_requests")]
+pub async fn tx_wallet_request(
+  req: web::Json<CreateWalletSigningRequest>,
+  tx_repo: TransactionRepository,
+  wallets: AppWalletPairings,
+  nodes: AppNodes,
+) -> Result<impl Responder> {
+  let pairing = tx_repo
+    .get_wallet_pairing(req.pairing_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Wallet pairing"))?;
+  if pairing.status != "active" {
+    return Err(Error::other("The wallet pairing isn't active").into());
+  }
+  let api = nodes.api();
+  let account =
+    AccountId::from_str(&pairing.account).map_err(|_| Error::other("Invalid account address"))?;
+  let (nonce, payload) = build_payload(&api, account, &req.call).await?;
+  let unsigned = tx_repo
+    .add_unsigned_transaction(&pairing.account, &req.call, nonce, &payload)
+    .await?;
+  let summary = req.summary.clone().unwrap_or_else(|| req.call.summary());
+  let expires_in = req.expires_in.unwrap_or(wallets.request_ttl());
+  let request = tx_repo
+    .create_wallet_signing_request(pairing.pairing_id, unsigned.build_id, &summary, expires_in)
+    .await?;
+  wallets.notify(&pairing, &request);
+  Ok(HttpResponse::Ok().json(request))
+}
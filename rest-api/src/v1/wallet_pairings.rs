@@ -0,0 +1,302 @@
+use std::str::FromStr;
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_api::client::AccountId;
+
+use polymesh_private_proof_api::{receipts::AppReceiptSigner, repo::Repository};
+use polymesh_private_proof_shared::{
+  error::Error, ApproveWalletSigningRequest, ConnectWallet, CreateWalletPairing,
+  RejectWalletSigningRequest, SubmitSignedTransaction,
+};
+
+use crate::nodes::AppNodes;
+use crate::repo::TransactionRepository;
+use crate::tx_jobs::{AppTxJobs, TxJobOutcome, TxJobQuery};
+use crate::v1::tx::offline::submit_signed;
+use crate::wallet_pairing::AppWalletPairings;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_wallet_pairings)
+    .service(get_wallet_pairing)
+    .service(create_wallet_pairing)
+    .service(revoke_wallet_pairing)
+    .service(get_wallet_requests)
+    .service(get_wallet_request)
+    .service(wallet_connect)
+    .service(wallet_get_requests)
+    .service(wallet_get_request)
+    .service(wallet_approve_request)
+    .service(wallet_reject_request);
+}
+
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct WalletRequestFilter {
+  /// Only requests of this pairing.
+  pub pairing_id: Option<i64>,
+  /// Only requests with this status.
+  pub status: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct WalletRequestStatus {
+  /// Only requests with this status (default: `pending`).
+  pub status: Option<String>,
+}
+
+/// Get wallet pairings.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [WalletPairing])
+  )
+)]
+#[get("/wallet_pairings")]
+pub async fn get_wallet_pairings(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let pairings = tx_repo.get_wallet_pairings().await?;
+  Ok(HttpResponse::Ok().json(pairings))
+}
+
+/// Get a wallet pairing.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletPairing)
+  )
+)]
+#[get("/wallet_pairings/{pairing_id}")]
+pub async fn get_wallet_pairing(
+  pairing_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let pairing = tx_repo
+    .get_wallet_pairing(*pairing_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Wallet pairing"))?;
+  Ok(HttpResponse::Ok().json(pairing))
+}
+
+/// Pair an external wallet to approve signing requests for an account.
+///
+/// Share the returned `payload` (e.g. as a QR code) with the wallet, it connects with
+/// `/wallet/connect`.  The secret is only returned once.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletPairingCreated)
+  )
+)]
+#[post("/wallet_pairings")]
+pub async fn create_wallet_pairing(
+  req: web::Json<CreateWalletPairing>,
+  tx_repo: TransactionRepository,
+  wallets: AppWalletPairings,
+) -> Result<impl Responder> {
+  AccountId::from_str(&req.account).map_err(|_| Error::other("Invalid account address"))?;
+  let created = wallets.create_pairing(&tx_repo, &req).await?;
+  Ok(HttpResponse::Ok().json(created))
+}
+
+/// Revoke a wallet pairing.  Its pending signing requests are rejected.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletPairing)
+  )
+)]
+#[post("/wallet_pairings/{pairing_id}/revoke")]
+pub async fn revoke_wallet_pairing(
+  pairing_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let pairing = tx_repo
+    .revoke_wallet_pairing(*pairing_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Wallet pairing"))?;
+  log::info!("Revoked wallet pairing {}", pairing.pairing_id);
+  Ok(HttpResponse::Ok().json(pairing))
+}
+
+/// Get wallet signing requests.
+#[utoipa::path(
+  params(WalletRequestFilter),
+  responses(
+    (status = 200, body = [WalletSigningRequest])
+  )
+)]
+#[get("/wallet_requests")]
+pub async fn get_wallet_requests(
+  filter: web::Query<WalletRequestFilter>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let requests = tx_repo
+    .get_wallet_signing_requests(filter.pairing_id, filter.status.as_deref())
+    .await?;
+  Ok(HttpResponse::Ok().json(requests))
+}
+
+/// Get a wallet signing request.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletSigningRequest)
+  )
+)]
+#[get("/wallet_requests/{request_id}")]
+pub async fn get_wallet_request(
+  request_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let request = tx_repo
+    .get_wallet_signing_request(*request_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Wallet signing request"))?;
+  Ok(HttpResponse::Ok().json(request))
+}
+
+/// Connect a wallet to a pending pairing.
+///
+/// Returns the session token, send it in the `X-Wallet-Session` header of the other
+/// `/wallet` endpoints.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletSession)
+  )
+)]
+#[post("/wallet/connect")]
+pub async fn wallet_connect(
+  req: web::Json<ConnectWallet>,
+  tx_repo: TransactionRepository,
+  wallets: AppWalletPairings,
+) -> Result<impl Responder> {
+  let session = wallets.connect(&tx_repo, &req).await?;
+  Ok(HttpResponse::Ok().json(session))
+}
+
+/// Get the signing requests of the wallet's pairing.
+#[utoipa::path(
+  params(WalletRequestStatus),
+  responses(
+    (status = 200, body = [WalletSigningRequest])
+  )
+)]
+#[get("/wallet/requests")]
+pub async fn wallet_get_requests(
+  filter: web::Query<WalletRequestStatus>,
+  tx_repo: TransactionRepository,
+  wallets: AppWalletPairings,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let pairing = wallets.session(&tx_repo, &http_req).await?;
+  let status = filter.status.as_deref().unwrap_or("pending");
+  let requests = tx_repo
+    .get_wallet_signing_requests(Some(pairing.pairing_id), Some(status))
+    .await?;
+  Ok(HttpResponse::Ok().json(requests))
+}
+
+/// Get a signing request of the wallet's pairing.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletSigningRequest)
+  )
+)]
+#[get("/wallet/requests/{request_id}")]
+pub async fn wallet_get_request(
+  request_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+  wallets: AppWalletPairings,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let pairing = wallets.session(&tx_repo, &http_req).await?;
+  let request = tx_repo
+    .get_wallet_signing_request(*request_id)
+    .await?
+    .filter(|request| request.pairing_id == pairing.pairing_id)
+    .ok_or_else(|| Error::not_found("Wallet signing request"))?;
+  Ok(HttpResponse::Ok().json(request))
+}
+
+/// Approve a signing request with the wallet's signature and submit the transaction.
+#[utoipa::path(
+  params(TxJobQuery),
+  responses(
+    (status = 200, body = TransactionResult),
+    (status = 202, body = TxJob)
+  )
+)]
+#[post("/wallet/requests/{request_id}/approve")]
+pub async fn wallet_approve_request(
+  request_id: web::Path<i64>,
+  req: web::Json<ApproveWalletSigningRequest>,
+  job_query: web::Query<TxJobQuery>,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  wallets: AppWalletPairings,
+  tx_jobs: AppTxJobs,
+  nodes: AppNodes,
+  receipts: AppReceiptSigner,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let pairing = wallets.session(&tx_repo, &http_req).await?;
+  let request = tx_repo
+    .decide_wallet_signing_request(*request_id, pairing.pairing_id, "approved", None)
+    .await?
+    .ok_or_else(|| Error::not_found("Pending wallet signing request"))?;
+  let submit = SubmitSignedTransaction {
+    build_id: request.build_id,
+    signature: req.signature.clone(),
+    finalize: req.finalize,
+  };
+  let outcome =
+    match submit_signed(&submit, &job_query, repo, tx_repo.clone(), &tx_jobs, &nodes).await {
+      Ok(outcome) => outcome,
+      Err(err) => {
+        tx_repo
+          .wallet_signing_request_failed(request.request_id, &err.to_string())
+          .await?;
+        return Err(err.into());
+      }
+    };
+  log::info!(
+    "Wallet pairing {} approved signing request {}",
+    pairing.pairing_id,
+    request.request_id
+  );
+  let res = match outcome {
+    TxJobOutcome::Done(res) => res,
+    TxJobOutcome::Queued(job) => return Ok(HttpResponse::Accepted().json(job)),
+  };
+
+  Ok(receipts.json_response(&http_req, &*req, Some(&res.tx_hash), &res)?)
+}
+
+/// Reject a signing request.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WalletSigningRequest)
+  )
+)]
+#[post("/wallet/requests/{request_id}/reject")]
+pub async fn wallet_reject_request(
+  request_id: web::Path<i64>,
+  req: web::Json<RejectWalletSigningRequest>,
+  tx_repo: TransactionRepository,
+  wallets: AppWalletPairings,
+  http_req: HttpRequest,
+) -> Result<impl Responder> {
+  let pairing = wallets.session(&tx_repo, &http_req).await?;
+  let request = tx_repo
+    .decide_wallet_signing_request(
+      *request_id,
+      pairing.pairing_id,
+      "rejected",
+      req.reason.as_deref(),
+    )
+    .await?
+    .ok_or_else(|| Error::not_found("Pending wallet signing request"))?;
+  log::info!(
+    "Wallet pairing {} rejected signing request {}",
+    pairing.pairing_id,
+    request.request_id
+  );
+  Ok(HttpResponse::Ok().json(request))
+}
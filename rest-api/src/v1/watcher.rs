@@ -0,0 +1,21 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+
+use crate::repo::TransactionRepository;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(get_watcher_status);
+}
+
+/// Get the chain watcher's progress.
+///
+/// `catching_up` is set while the watcher is processing missed blocks.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WatcherStatus)
+  )
+)]
+#[get("/watcher/status")]
+pub async fn get_watcher_status(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let status = tx_repo.get_watcher_status().await?;
+  Ok(HttpResponse::Ok().json(status))
+}
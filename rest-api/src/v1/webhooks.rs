@@ -0,0 +1,126 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use polymesh_private_proof_shared::{error::Error, CreateWebhookEndpoint};
+
+use crate::repo::TransactionRepository;
+use crate::webhooks::AppWebhooks;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(get_webhook_outbox)
+    .service(redeliver_webhook)
+    .service(get_webhook_endpoints)
+    .service(get_webhook_endpoint)
+    .service(create_webhook_endpoint)
+    .service(delete_webhook_endpoint);
+}
+
+/// Webhook outbox filter.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct WebhookOutboxQuery {
+  /// Only return webhooks with this status (`pending`, `delivered` or `dead`).
+  pub status: Option<String>,
+}
+
+/// Get webhooks in the outbox.
+#[utoipa::path(
+  params(WebhookOutboxQuery),
+  responses(
+    (status = 200, body = [WebhookOutboxRecord])
+  )
+)]
+#[get("/admin/webhooks/outbox")]
+pub async fn get_webhook_outbox(
+  query: web::Query<WebhookOutboxQuery>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let outbox = tx_repo
+    .get_webhook_outbox(query.into_inner().status)
+    .await?;
+  Ok(HttpResponse::Ok().json(outbox))
+}
+
+/// Queue a webhook for redelivery (e.g. a `dead` webhook after the receiver has been fixed).
+#[utoipa::path(
+  responses(
+    (status = 200, body = WebhookOutboxRecord)
+  )
+)]
+#[post("/admin/webhooks/outbox/{id}/redeliver")]
+pub async fn redeliver_webhook(
+  id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let rec = tx_repo
+    .redeliver_webhook(*id)
+    .await?
+    .ok_or_else(|| Error::not_found("Webhook"))?;
+  Ok(HttpResponse::Ok().json(rec))
+}
+
+/// Get the registered webhook endpoints.
+#[utoipa::path(
+  responses(
+    (status = 200, body = [WebhookEndpoint])
+  )
+)]
+#[get("/admin/webhooks/endpoints")]
+pub async fn get_webhook_endpoints(tx_repo: TransactionRepository) -> Result<impl Responder> {
+  let endpoints = tx_repo.get_webhook_endpoints().await?;
+  Ok(HttpResponse::Ok().json(endpoints))
+}
+
+/// Get a registered webhook endpoint.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WebhookEndpoint)
+  )
+)]
+#[get("/admin/webhooks/endpoints/{endpoint_id}")]
+pub async fn get_webhook_endpoint(
+  endpoint_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  let endpoint = tx_repo
+    .get_webhook_endpoint(*endpoint_id)
+    .await?
+    .ok_or_else(|| Error::not_found("Webhook endpoint"))?;
+  Ok(HttpResponse::Ok().json(endpoint))
+}
+
+/// Register a webhook endpoint.
+///
+/// With `from_block` or `from_settlement` the stored chain events from that block are
+/// replayed to the endpoint before it receives live events.
+#[utoipa::path(
+  responses(
+    (status = 200, body = WebhookEndpoint)
+  )
+)]
+#[post("/admin/webhooks/endpoints")]
+pub async fn create_webhook_endpoint(
+  req: web::Json<CreateWebhookEndpoint>,
+  webhooks: AppWebhooks,
+) -> Result<impl Responder> {
+  let endpoint = webhooks.create_endpoint(&req).await?;
+  Ok(HttpResponse::Ok().json(endpoint))
+}
+
+/// Remove a webhook endpoint.  Its pending webhooks are marked as `dead`.
+#[utoipa::path(
+  responses(
+    (status = 200)
+  )
+)]
+#[delete("/admin/webhooks/endpoints/{endpoint_id}")]
+pub async fn delete_webhook_endpoint(
+  endpoint_id: web::Path<i64>,
+  tx_repo: TransactionRepository,
+) -> Result<impl Responder> {
+  if !tx_repo.delete_webhook_endpoint(*endpoint_id).await? {
+    return Err(Error::not_found("Webhook endpoint").into());
+  }
+  Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use actix_web::{get, rt, web, HttpRequest, Responder, Result};
+use actix_ws::{Message, MessageStream, Session};
+use futures_util::{future, StreamExt};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use polymesh_private_proof_api::repo::Repository;
+use polymesh_private_proof_shared::{error::Error, AccountWithSecret, ChainEvent, ProcessedEvent};
+
+use crate::event_stream::AppEventStream;
+
+pub fn service(cfg: &mut web::ServiceConfig) {
+  cfg.service(account_balances_ws);
+}
+
+/// Stream the decrypted balance updates of one of our confidential accounts (WebSocket).
+///
+/// Each balance update persisted by the chain watcher for the account is decrypted and sent
+/// as an `AccountAssetBalanceUpdated` JSON text message.  Updates come from the same source
+/// as `/events/stream`, see `EventStream::run`.  A `{"lagged": <count>}` message is
+/// sent if the client can't keep up and updates were dropped.
+#[utoipa::path(
+  responses(
+    (status = 101, description = "WebSocket of `AccountAssetBalanceUpdated`s")
+  )
+)]
+#[get("/ws/accounts/{public_key}")]
+pub async fn account_balances_ws(
+  public_key: web::Path<String>,
+  repo: Repository,
+  event_stream: AppEventStream,
+  req: HttpRequest,
+  body: web::Payload,
+) -> Result<impl Responder> {
+  // Get the account with account secret key.
+  let account = repo
+    .get_account_with_secret(&public_key)
+    .await?
+    .ok_or_else(|| Error::not_found("Account"))?;
+  account.ensure_unlocked()?;
+  let account = Arc::new(account);
+  let events = event_stream.receiver();
+
+  let (response, session, msg_stream) = actix_ws::handle(&req, body)?;
+  rt::spawn(async move {
+    let updates = send_balance_updates(session.clone(), events, account);
+    let messages = handle_messages(session, msg_stream);
+    future::select(Box::pin(updates), Box::pin(messages)).await;
+  });
+  Ok(response)
+}
+
+/// Decrypt and send the account's balance updates, until the client disconnects.
+async fn send_balance_updates(
+  mut session: Session,
+  mut events: Receiver<Arc<ChainEvent>>,
+  account: Arc<AccountWithSecret>,
+) {
+  loop {
+    let msg = match events.recv().await {
+      Ok(event) => {
+        let updated = match &event.event {
+          ProcessedEvent::ConfidentialAccountBalanceUpdated(updated)
+            if account.match_confidential_account(&updated.account) =>
+          {
+            updated.clone()
+          }
+          _ => continue,
+        };
+        let account = account.clone();
+        let update = match rt::task::spawn_blocking(move || updated.try_decrypt(&account)).await {
+          Ok(Some(update)) => update,
+          Ok(None) => continue,
+          Err(err) => {
+            log::error!("Failed to decrypt balance update: {err:?}");
+            continue;
+          }
+        };
+        match serde_json::to_string(&update) {
+          Ok(msg) => msg,
+          Err(err) => {
+            log::error!("Failed to encode balance update: {err:?}");
+            continue;
+          }
+        }
+      }
+      Err(RecvError::Lagged(skipped)) => {
+        log::warn!("Balance update client lagged, skipped {skipped} events");
+        serde_json::json!({ "lagged": skipped }).to_string()
+      }
+      Err(RecvError::Closed) => return,
+    };
+    if session.text(msg).await.is_err() {
+      // Client disconnected.
+      return;
+    }
+  }
+}
+
+/// Answer pings, until the client closes the connection.
+async fn handle_messages(mut session: Session, mut msg_stream: MessageStream) {
+  while let Some(Ok(msg)) = msg_stream.next().await {
+    match msg {
+      Message::Ping(bytes) => {
+        if session.pong(&bytes).await.is_err() {
+          return;
+        }
+      }
+      Message::Close(reason) => {
+        let _ = session.close(reason).await;
+        return;
+      }
+      _ => (),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::body::MessageBody;
+  use actix_web::dev::Payload;
+  use actix_web::error::PayloadError;
+  use actix_web::web::Bytes;
+  use actix_web::{http::StatusCode, test, App};
+  use codec::{Decode, Encode};
+  use confidential_assets::{ElgamalPublicKey, Scalar};
+  use futures_util::future::poll_fn;
+  use futures_util::stream;
+  use uuid::Uuid;
+
+  use polymesh_private_proof_api::repo::SqliteConfidentialRepository;
+  use polymesh_private_proof_shared::{
+    BalanceUpdateAction, BalanceUpdated, CreateAccount, PublicKey,
+  };
+
+  use super::*;
+  use crate::event_stream::tests::{persist_tx, test_pool};
+  use crate::event_stream::EventStream;
+  use crate::repo::SqliteTransactionRepository;
+
+  #[actix_web::test]
+  async fn persisted_balance_updates_reach_sockets() {
+    let pool = test_pool().await;
+    let repo = SqliteConfidentialRepository::new_app_data(&pool);
+    let tx_repo = SqliteTransactionRepository::new_app_data(&pool);
+    let event_stream = EventStream::new_app_data(tx_repo.clone());
+    let app = test::init_service(
+      App::new()
+        .app_data(repo.clone())
+        .app_data(event_stream.clone())
+        .configure(service),
+    )
+    .await;
+
+    let account = CreateAccount::new();
+    repo.create_account(&account).await.unwrap();
+    let public_key = hex::encode(&account.confidential_account);
+    let req = test::TestRequest::get()
+      .uri(&format!("/ws/accounts/{public_key}"))
+      .insert_header(("upgrade", "websocket"))
+      .insert_header(("connection", "upgrade"))
+      .insert_header(("sec-websocket-version", "13"))
+      .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+      .to_request();
+    // Keep the client connected.
+    let client = stream::pending::<std::result::Result<Bytes, PayloadError>>();
+    let (req, _) = req.replace_payload(Payload::Stream {
+      payload: Box::pin(client),
+    });
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+
+    // Deposit 5 into the account.
+    let mut rng = rand::thread_rng();
+    let public = ElgamalPublicKey::decode(&mut account.confidential_account.as_slice()).unwrap();
+    let (_, amount) = public.encrypt_value(Scalar::from(5u64), &mut rng);
+    let (_, balance) = public.encrypt_value(Scalar::from(5u64), &mut rng);
+    let asset_id = Uuid::new_v4();
+    let last_id = tx_repo.get_last_block_transaction_id().await.unwrap();
+    persist_tx(
+      &tx_repo,
+      1,
+      vec![ProcessedEvent::ConfidentialAccountBalanceUpdated(
+        BalanceUpdated {
+          account: PublicKey::from_str(&public_key).unwrap(),
+          asset_id,
+          action: BalanceUpdateAction::Deposit,
+          amount: amount.encode().try_into().unwrap(),
+          balance: balance.encode().try_into().unwrap(),
+        },
+      )],
+    )
+    .await;
+    event_stream.publish_persisted(last_id).await.unwrap();
+
+    let mut body = Box::pin(resp.into_body());
+    let received = rt::time::timeout(std::time::Duration::from_secs(10), async {
+      let mut received = String::new();
+      while !received.contains(&asset_id.to_string()) {
+        let frame = poll_fn(|cx| body.as_mut().poll_next(cx))
+          .await
+          .expect("Socket closed")
+          .expect("Socket failed");
+        received.push_str(&String::from_utf8_lossy(&frame));
+      }
+      received
+    })
+    .await
+    .expect("No balance update received");
+    assert!(received.contains(r#""amount":5"#));
+    assert!(received.contains(r#""balance":5"#));
+  }
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use actix_web::{web::Data, HttpRequest};
+use reqwest::{Client, Url};
+use sp_core::hashing::blake2_256;
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  ConnectWallet, CreateWalletPairing, WalletPairing, WalletPairingCreated, WalletPayload,
+  WalletSession, WalletSigningRequest,
+};
+
+use crate::repo::TransactionRepository;
+
+pub type AppWalletPairings = Data<WalletPairings>;
+
+/// Header with the session token of a connected wallet.
+pub const WALLET_SESSION_HEADER: &str = "X-Wallet-Session";
+/// Default seconds until a signing request expires.
+const DEFAULT_REQUEST_TTL: u64 = 3600;
+/// How often pending signing requests are checked for expiry.
+pub const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Timeout of a callback request.
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pairs external wallets to approve signing requests.
+///
+/// A pairing is created for an account and shared with the wallet as a wallet payload
+/// (QR code or deep-link) holding the pairing id and a one-time secret.  The wallet
+/// connects with the secret and gets a session token, optionally registering a callback
+/// url.  Signing requests for the account are posted to the callback url (and listed by
+/// `/wallet/requests`), the wallet approves them with its signature or rejects them.
+///
+/// Callbacks aren't authenticated, wallets should load the request with their session
+/// before signing it.
+pub struct WalletPairings {
+  client: Client,
+  request_ttl: u64,
+  api_url: Option<String>,
+}
+
+impl WalletPairings {
+  /// Load the config from `WALLET_REQUEST_TTL_SECS` and `WALLET_PAIRING_API_URL`.
+  pub fn from_env() -> AppWalletPairings {
+    let request_ttl = std::env::var("WALLET_REQUEST_TTL_SECS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_REQUEST_TTL);
+    Data::new(Self {
+      client: Client::new(),
+      request_ttl,
+      api_url: std::env::var("WALLET_PAIRING_API_URL").ok(),
+    })
+  }
+
+  /// Default seconds until a signing request expires.
+  pub fn request_ttl(&self) -> u64 {
+    self.request_ttl
+  }
+
+  /// Create a pairing and the wallet payload to share with the wallet.
+  pub async fn create_pairing(
+    &self,
+    tx_repo: &TransactionRepository,
+    req: &CreateWalletPairing,
+  ) -> Result<WalletPairingCreated> {
+    let secret = rand::random::<[u8; 32]>();
+    let pairing = tx_repo
+      .create_wallet_pairing(req, &blake2_256(&secret))
+      .await?;
+    let payload = WalletPayload::pairing(pairing.pairing_id, secret, self.api_url.clone());
+    log::info!(
+      "Created wallet pairing {} for account {}",
+      pairing.pairing_id,
+      pairing.account
+    );
+    Ok(WalletPairingCreated {
+      pairing,
+      secret: format!("0x{}", hex::encode(secret)),
+      payload: payload.to_payload(),
+    })
+  }
+
+  /// Connect a wallet with the pairing secret.
+  pub async fn connect(
+    &self,
+    tx_repo: &TransactionRepository,
+    req: &ConnectWallet,
+  ) -> Result<WalletSession> {
+    if let Some(url) = &req.callback_url {
+      Url::parse(url)?;
+    }
+    let secret = decode_token(&req.secret)?;
+    let session_token = rand::random::<[u8; 32]>();
+    let pairing = tx_repo
+      .connect_wallet_pairing(
+        req.pairing_id,
+        &blake2_256(&secret),
+        &blake2_256(&session_token),
+        req.callback_url.as_deref(),
+      )
+      .await?
+      .ok_or_else(|| Error::forbidden("Invalid pairing secret or the pairing isn't pending"))?;
+    log::info!("Wallet connected to pairing {}", pairing.pairing_id);
+    Ok(WalletSession {
+      pairing,
+      session_token: format!("0x{}", hex::encode(session_token)),
+    })
+  }
+
+  /// The active pairing of the wallet session in the request.
+  pub async fn session(
+    &self,
+    tx_repo: &TransactionRepository,
+    req: &HttpRequest,
+  ) -> Result<WalletPairing> {
+    let token = req
+      .headers()
+      .get(WALLET_SESSION_HEADER)
+      .and_then(|v| v.to_str().ok())
+      .ok_or_else(|| Error::forbidden("Missing wallet session"))?;
+    let token = decode_token(token)?;
+    tx_repo
+      .get_wallet_session(&blake2_256(&token))
+      .await?
+      .ok_or_else(|| Error::forbidden("Invalid wallet session"))
+  }
+
+  /// Post a new signing request to the wallet's callback url in the background.
+  pub fn notify(&self, pairing: &WalletPairing, request: &WalletSigningRequest) {
+    let url = match &pairing.callback_url {
+      Some(url) => url.clone(),
+      None => return,
+    };
+    let client = self.client.clone();
+    let request = request.clone();
+    actix_web::rt::spawn(async move {
+      let request_id = request.request_id;
+      let res = client
+        .post(&url)
+        .header("X-Wallet-Request-Id", request_id)
+        .timeout(CALLBACK_TIMEOUT)
+        .json(&request)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status());
+      if let Err(err) = res {
+        log::warn!("Failed to notify wallet of signing request {request_id}: {err:?}");
+      }
+    });
+  }
+
+  /// Expire pending signing requests.
+  pub async fn expire_requests(&self, tx_repo: &TransactionRepository) -> Result<()> {
+    let expired = tx_repo.expire_wallet_signing_requests().await?;
+    if expired > 0 {
+      log::info!("Expired {expired} wallet signing requests");
+    }
+    Ok(())
+  }
+}
+
+/// Decode a `0x` prefixed 32 byte secret or session token.
+fn decode_token(token: &str) -> Result<[u8; 32]> {
+  let mut bytes = [0u8; 32];
+  hex::decode_to_slice(token.trim().trim_start_matches("0x"), &mut bytes)
+    .map_err(|_| Error::forbidden("Invalid token"))?;
+  Ok(bytes)
+}
@@ -1,54 +1,813 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures_util::future::{self, Either, LocalBoxFuture};
+use futures_util::{FutureExt, StreamExt};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
 use polymesh_api::*;
 
 use polymesh_private_proof_api::repo::Repository;
 use polymesh_private_proof_shared::*;
 
+use crate::deposits::credit_deposits;
+use crate::event_sink::EventPublisher;
+use crate::ledger::{ledger_entries, record_ledger_entries};
+use crate::nodes::AppNodes;
 use crate::repo::TransactionRepository;
+use crate::webhooks::{AppWebhooks, WebhookEvent};
+
+/// Number of block transactions buffered for slow subscribers.
+const EVENT_BUS_CAPACITY: usize = 1024;
+/// Publishing waits while this many transactions haven't been received by all subscribers.
+const EVENT_BUS_HIGH_WATER: usize = EVENT_BUS_CAPACITY / 2;
+/// How often to check if the subscribers caught up.
+const EVENT_BUS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Maximum time to wait for slow subscribers before publishing anyway.
+const EVENT_BUS_MAX_WAIT: Duration = Duration::from_secs(30);
+/// How often to check if maintenance mode has been disabled.
+const MAINTENANCE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Default number of blocks fetched in parallel while catching up.
+const DEFAULT_CATCH_UP_CONCURRENCY: usize = 8;
+/// How often to save the watcher status while catching up.
+const CATCH_UP_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+/// Delay before the first reconnect attempt.  Doubled after each failed attempt.
+const MIN_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+/// Default maximum delay between reconnect attempts.
+const DEFAULT_MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+/// Attempts to persist a block transaction before the watcher fails.
+const PERSIST_ATTEMPTS: u32 = 3;
+
+/// A processed block transaction published by the chain watcher.
+pub type WatcherEvent = Arc<TransactionResult>;
+
+/// Writes a block transaction to the database.  See `ChainWatcher::persist_with`.
+pub type PersistFn = Box<dyn Fn(WatcherEvent) -> LocalBoxFuture<'static, Result<()>>>;
+
+/// Chain watcher options.
+#[derive(Clone, Debug)]
+pub struct WatcherOptions {
+  /// Log what would be written, without changing the database or sending events.
+  pub dry_run: bool,
+  /// First block to process.  Blocks between it and the chain head are caught up
+  /// before following new blocks.  Without a start block the watcher resumes after the
+  /// last processed block, or starts at the chain head on the first run.
+  pub start_block: Option<u32>,
+  /// Maximum number of blocks fetched and decoded in parallel while catching up.
+  pub concurrency: usize,
+  /// Maximum delay between reconnect attempts.
+  pub max_backoff: Duration,
+}
+
+impl Default for WatcherOptions {
+  fn default() -> Self {
+    Self {
+      dry_run: false,
+      start_block: None,
+      concurrency: DEFAULT_CATCH_UP_CONCURRENCY,
+      max_backoff: DEFAULT_MAX_RECONNECT_BACKOFF,
+    }
+  }
+}
+
+impl WatcherOptions {
+  /// Read the options from `WATCHER_DRY_RUN`, `WATCHER_START_BLOCK`,
+  /// `WATCHER_CONCURRENCY` and `WATCHER_MAX_BACKOFF` (seconds).
+  pub fn from_env() -> Self {
+    Self {
+      dry_run: std::env::var("WATCHER_DRY_RUN")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false),
+      start_block: std::env::var("WATCHER_START_BLOCK")
+        .ok()
+        .and_then(|v| v.parse().ok()),
+      concurrency: std::env::var("WATCHER_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_CATCH_UP_CONCURRENCY),
+      max_backoff: std::env::var("WATCHER_MAX_BACKOFF")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_MAX_RECONNECT_BACKOFF)
+        .max(MIN_RECONNECT_BACKOFF),
+    }
+  }
+}
+
+/// Watches the chain for new blocks, persists their processed transactions and publishes
+/// them to all subscribers.
+///
+/// Missed blocks are fetched and decoded in parallel, but always persisted and published
+/// in block order.  A block's transactions are persisted before the block is published and
+/// saved as the last processed block, so blocks missed while the service was down (or
+/// that failed to persist) are processed again on restart.  Processing is paused while
+/// maintenance mode is enabled.
+///
+/// When the block subscription fails the watcher health-checks the nodes (failing over to
+/// another node if needed), reconnects with exponential backoff and catches up on the
+/// blocks missed in between.  It also resubscribes when `NodeManager` moves to another
+/// node.
+pub struct ChainWatcher {
+  nodes: AppNodes,
+  tx_repo: TransactionRepository,
+  bus: broadcast::Sender<WatcherEvent>,
+  persist: Option<PersistFn>,
+  start_block: Option<u32>,
+  concurrency: usize,
+  max_backoff: Duration,
+  dry_run: bool,
+}
+
+impl ChainWatcher {
+  pub fn new(nodes: AppNodes, tx_repo: TransactionRepository, options: &WatcherOptions) -> Self {
+    let (bus, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+    Self {
+      nodes,
+      tx_repo,
+      bus,
+      persist: None,
+      start_block: options.start_block,
+      concurrency: options.concurrency.max(1),
+      max_backoff: options.max_backoff,
+      dry_run: options.dry_run,
+    }
+  }
+
+  /// Current node connection.
+  fn api(&self) -> Api {
+    self.nodes.api()
+  }
+
+  /// Subscribe to the processed block transactions.
+  ///
+  /// Subscribers must be created before `run` is called to receive all transactions.
+  /// Delivery is best-effort: a subscriber that falls more than `EVENT_BUS_CAPACITY`
+  /// transactions behind skips transactions.  Use `persist_with` for writes that must
+  /// not be lost.
+  pub fn subscribe(&self) -> broadcast::Receiver<WatcherEvent> {
+    self.bus.subscribe()
+  }
+
+  /// Persist each block transaction with `persist` before it is published.
+  ///
+  /// A failed transaction is retried, if it keeps failing the watcher reconnects and
+  /// resumes from the last processed block, so `persist` must be idempotent.
+  pub fn persist_with(&mut self, persist: PersistFn) {
+    self.persist = Some(persist);
+  }
+
+  /// Catch up from the start block (or the block after the last processed block), then
+  /// process new blocks.
+  ///
+  /// Reconnects when the block subscription fails or ends, so this only returns if the
+  /// watcher status can't be saved.
+  pub async fn run(&self) -> anyhow::Result<()> {
+    let mut start_block = self.start_block;
+    let mut backoff = MIN_RECONNECT_BACKOFF;
+    loop {
+      let started = Instant::now();
+      let err = match self.follow_blocks(start_block.take()).await {
+        // Moved to another node, resubscribe right away.
+        Ok(()) => continue,
+        Err(err) => format!("{err:?}"),
+      };
+      // Only back off further if the last connection failed quickly.
+      if started.elapsed() > self.max_backoff {
+        backoff = MIN_RECONNECT_BACKOFF;
+      }
+      log::error!(
+        "Chain watcher failed, reconnecting in {} seconds: {err}",
+        backoff.as_secs()
+      );
+      if !self.dry_run {
+        self.tx_repo.add_watcher_reconnect(&err).await?;
+      }
+      // Reconnect or fail over before resubscribing.
+      self.nodes.check().await;
+      actix_web::rt::time::sleep(backoff).await;
+      backoff = (backoff * 2).min(self.max_backoff);
+    }
+  }
+
+  /// Catch up from `start_block` (or the block after the last processed block), then
+  /// process new blocks until the block subscription ends (an error) or the current node
+  /// changes.
+  async fn follow_blocks(&self, start_block: Option<u32>) -> anyhow::Result<()> {
+    let mut node_changed = self.nodes.subscribe();
+    let api = self.api();
+    let client = api.client();
+
+    let mut next_block = match start_block {
+      Some(start_block) => Some(start_block),
+      None => self.resume_block().await?,
+    };
+    if let Some(start_block) = next_block {
+      let head = client
+        .get_block_header(None)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Missing chain head"))?;
+      if start_block <= head.number {
+        self.catch_up(start_block, head.number + 1).await?;
+        next_block = Some(head.number + 1);
+      }
+    }
+
+    let mut sub_blocks = client.subscribe_blocks().await?;
+
+    loop {
+      let header = match future::select(sub_blocks.next(), Box::pin(node_changed.changed())).await {
+        Either::Left((Some(header), _)) => header?,
+        Either::Left((None, _)) => Err(anyhow::anyhow!("Block subscription ended"))?,
+        Either::Right(_) => {
+          log::info!("Chain watcher moving to Polymesh node {}", self.nodes.url());
+          return Ok(());
+        }
+      };
+      self.wait_for_maintenance().await?;
+      let number = header.number;
+      // Fill any gap since the last processed block.
+      if let Some(next) = next_block.filter(|next| *next < number) {
+        log::warn!(
+          "Chain watcher missed blocks {next} to {}",
+          number.saturating_sub(1)
+        );
+        self.catch_up(next, number).await?;
+      }
+      let hash = format!("{:#x}", header.hash());
+      let transactions = TransactionResult::get_block_transactions(&api, header).await?;
+      self.process_block(transactions).await?;
+      next_block = Some(number + 1);
+      self.set_status(number, number, Some(&hash), false).await?;
+    }
+  }
+
+  /// Block to resume from after a restart.
+  ///
+  /// This is the block after the last processed block.  If the last processed block is no
+  /// longer part of the chain (reorg), it is processed again.
+  async fn resume_block(&self) -> anyhow::Result<Option<u32>> {
+    let status = self.tx_repo.get_watcher_status().await?;
+    let processed = status.processed_block;
+    if processed == 0 {
+      // Never ran before.  Start from the chain head.
+      return Ok(None);
+    }
+    let saved_hash = match status.processed_hash {
+      Some(hash) => hash,
+      None => {
+        log::info!("Chain watcher resuming after block {processed}");
+        return Ok(Some(processed + 1));
+      }
+    };
+    let hash = self
+      .api()
+      .client()
+      .get_block_hash(processed)
+      .await?
+      .map(|hash| format!("{hash:#x}"));
+    if hash.as_deref() == Some(saved_hash.as_str()) {
+      log::info!("Chain watcher resuming after block {processed} ({saved_hash})");
+      Ok(Some(processed + 1))
+    } else {
+      log::warn!(
+        "Chain watcher's last processed block {processed} ({saved_hash}) is no longer part of the chain, processing it again"
+      );
+      Ok(Some(processed))
+    }
+  }
+
+  /// Process the blocks `from..to`.
+  ///
+  /// Up to `concurrency` blocks are fetched and decoded at the same time, but each block
+  /// is persisted before the next one and the processed block is only saved once its
+  /// transactions are persisted.  Publishing waits for slow subscribers, so a long catch-up
+  /// doesn't overrun the event bus.
+  async fn catch_up(&self, from: u32, to: u32) -> anyhow::Result<()> {
+    let head = to.saturating_sub(1);
+    log::info!("Chain watcher catching up on blocks {from} to {head}");
+    self
+      .set_status(head, from.saturating_sub(1), None, true)
+      .await?;
+
+    let mut blocks = futures_util::stream::iter(from..to)
+      .map(|number| self.fetch_block(number))
+      .buffered(self.concurrency);
+    let mut last_status = Instant::now();
+    let mut processed = from.saturating_sub(1);
+    let mut processed_hash = None;
+    while let Some((number, hash, transactions)) = blocks.next().await.transpose()? {
+      self.process_block(transactions).await?;
+      processed = number;
+      processed_hash = Some(hash);
+      if last_status.elapsed() >= CATCH_UP_STATUS_INTERVAL {
+        self
+          .set_status(head, processed, processed_hash.as_deref(), true)
+          .await?;
+        self.wait_for_maintenance().await?;
+        last_status = Instant::now();
+      }
+    }
+
+    self
+      .set_status(head, processed, processed_hash.as_deref(), false)
+      .await?;
+    log::info!("Chain watcher caught up to block {processed}");
+    Ok(())
+  }
+
+  /// Fetch and decode the transactions of a block.  Returns the block's number and hash.
+  async fn fetch_block(
+    &self,
+    number: u32,
+  ) -> anyhow::Result<(u32, String, Vec<TransactionResult>)> {
+    let api = self.api();
+    let client = api.client();
+    let hash = client
+      .get_block_hash(number)
+      .await?
+      .ok_or_else(|| anyhow::anyhow!("Missing block hash for block {number}"))?;
+    let header = client
+      .get_block_header(Some(hash))
+      .await?
+      .ok_or_else(|| anyhow::anyhow!("Missing header for block {number}"))?;
+    let transactions = TransactionResult::get_block_transactions(&api, header).await?;
+    Ok((number, format!("{hash:#x}"), transactions))
+  }
+
+  /// Persist a block's transactions in order, then publish them to the subscribers.
+  ///
+  /// The processed block is saved after this returns, so a block can be processed again
+  /// after a crash or failure.  Persisting is idempotent, replayed rows are skipped.
+  async fn process_block(&self, transactions: Vec<TransactionResult>) -> anyhow::Result<()> {
+    // Skip blocks with only the timestamp inherent.
+    if transactions.len() <= 1 {
+      return Ok(());
+    }
+    for tx in transactions {
+      let tx = Arc::new(tx);
+      self.persist(&tx).await?;
+      self.wait_for_subscribers().await;
+      // Only fails when there are no subscribers.
+      let _ = self.bus.send(tx);
+    }
+    Ok(())
+  }
+
+  /// Wait until the subscribers have received most of the published transactions.
+  ///
+  /// Gives up after `EVENT_BUS_MAX_WAIT`, so a stuck subscriber can't stop the watcher.
+  async fn wait_for_subscribers(&self) {
+    let started = Instant::now();
+    while self.bus.len() >= EVENT_BUS_HIGH_WATER {
+      if started.elapsed() >= EVENT_BUS_MAX_WAIT {
+        log::warn!("Chain watcher subscribers are falling behind, some may skip transactions");
+        return;
+      }
+      actix_web::rt::time::sleep(EVENT_BUS_POLL_INTERVAL).await;
+    }
+  }
+
+  /// Persist a block transaction, retrying failures.
+  async fn persist(&self, tx: &WatcherEvent) -> anyhow::Result<()> {
+    let persist = match &self.persist {
+      Some(persist) => persist,
+      None => return Ok(()),
+    };
+    let mut attempt = 1;
+    loop {
+      match persist(tx.clone()).await {
+        Ok(()) => return Ok(()),
+        Err(err) if attempt < PERSIST_ATTEMPTS => {
+          log::warn!(
+            "Failed to persist transaction {} (attempt {attempt}), retrying: {err:?}",
+            tx.tx_hash
+          );
+          actix_web::rt::time::sleep(MIN_RECONNECT_BACKOFF * attempt).await;
+          attempt += 1;
+        }
+        Err(err) => {
+          return Err(anyhow::anyhow!(
+            "Failed to persist transaction {} of block {}: {err:?}",
+            tx.tx_hash,
+            tx.block_number
+          ));
+        }
+      }
+    }
+  }
+
+  /// Save the watcher status, unless in dry-run mode.
+  ///
+  /// The processed block and its hash are where the watcher resumes after a restart.
+  async fn set_status(
+    &self,
+    head: u32,
+    processed: u32,
+    processed_hash: Option<&str>,
+    catching_up: bool,
+  ) -> Result<()> {
+    if self.dry_run {
+      return Ok(());
+    }
+    self
+      .tx_repo
+      .set_watcher_status(head, processed, processed_hash, catching_up)
+      .await
+  }
+
+  /// Wait until maintenance mode is disabled.
+  async fn wait_for_maintenance(&self) -> anyhow::Result<()> {
+    if !self.tx_repo.get_maintenance_mode().await?.enabled {
+      return Ok(());
+    }
+    log::info!("Maintenance mode enabled, pausing chain watcher");
+    while self.tx_repo.get_maintenance_mode().await?.enabled {
+      actix_web::rt::time::sleep(MAINTENANCE_POLL_INTERVAL).await;
+    }
+    log::info!("Maintenance mode disabled, resuming chain watcher");
+    Ok(())
+  }
+}
+
+/// Custom business logic run on each processed event, e.g. posting to an internal OMS.
+///
+/// Register handlers with `ChainWatcherBuilder::event_handler`.  Each handler runs in
+/// its own subscriber task, so a slow or failing handler doesn't hold up the others.
+/// Like the other subscribers handlers are best-effort, failed events aren't retried.
+/// Handlers are not run in dry-run mode.
+#[async_trait]
+pub trait ProcessedEventHandler: Send + Sync + 'static {
+  /// Handler name, used in logs.
+  fn name(&self) -> &'static str;
+
+  /// Handle one event of a block transaction.
+  async fn handle_event(&self, tx: &TransactionResult, event: &ProcessedEvent) -> Result<()>;
+
+  /// Handle all events of a block transaction.  Stops at the first failed event.
+  async fn handle_transaction(&self, tx: &TransactionResult) -> Result<()> {
+    for event in &tx.processed_events.0 {
+      self.handle_event(tx, event).await?;
+    }
+    Ok(())
+  }
+}
+
+/// Build a chain watcher with the default subscribers and any custom event handlers.
+pub struct ChainWatcherBuilder {
+  nodes: AppNodes,
+  repo: Repository,
+  tx_repo: TransactionRepository,
+  webhooks: AppWebhooks,
+  publisher: Option<EventPublisher>,
+  options: WatcherOptions,
+  handlers: Vec<Arc<dyn ProcessedEventHandler>>,
+}
+
+impl ChainWatcherBuilder {
+  pub fn new(
+    nodes: AppNodes,
+    repo: Repository,
+    tx_repo: TransactionRepository,
+    webhooks: AppWebhooks,
+  ) -> Self {
+    Self {
+      nodes,
+      repo,
+      tx_repo,
+      webhooks,
+      publisher: None,
+      options: Default::default(),
+      handlers: Vec::new(),
+    }
+  }
+
+  /// Publish transactions and events to a message broker.
+  pub fn publisher(mut self, publisher: Option<EventPublisher>) -> Self {
+    self.publisher = publisher;
+    self
+  }
+
+  pub fn options(mut self, options: WatcherOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Register a custom event handler.
+  pub fn event_handler<H: ProcessedEventHandler>(mut self, handler: H) -> Self {
+    self.handlers.push(Arc::new(handler));
+    self
+  }
+
+  /// Start the subscribers and run the chain watcher.
+  ///
+  /// In dry-run mode nothing is written to the database and no events are sent or
+  /// published.  Instead each processed transaction is logged as a `DryRunRecord`.  Use
+  /// `ChainWatcher::subscribe` with `DryRunRecord::from_tx` to handle them some other way.
+  pub async fn run(self) -> anyhow::Result<()> {
+    let Self {
+      nodes,
+      repo,
+      tx_repo,
+      webhooks,
+      publisher,
+      options,
+      handlers,
+    } = self;
+    let mut watcher = ChainWatcher::new(nodes, tx_repo.clone(), &options);
+
+    if options.dry_run {
+      log::warn!("Chain watcher running in dry-run mode, nothing will be written");
+      spawn_subscriber("dry-run", watcher.subscribe(), move |tx| {
+        log_dry_run(repo.clone(), tx)
+      });
+      return watcher.run().await;
+    }
+
+    // The database writes (including the webhook outbox) run in-line, in block order.
+    watcher.persist_with(Box::new(move |tx: WatcherEvent| {
+      let repo = repo.clone();
+      let tx_repo = tx_repo.clone();
+      let webhooks = webhooks.clone();
+      async move {
+        persist_transaction(&repo, &tx_repo, &tx).await?;
+        credit_deposits(&repo, &tx_repo, &webhooks, &tx).await?;
+        record_ledger_entries(&repo, &tx_repo, &tx).await?;
+        webhooks.send_chain_events(&tx).await?;
+        update_balances(&repo, &tx).await
+      }
+      .boxed_local()
+    }));
+    if let Some(publisher) = publisher {
+      let publisher = Arc::new(publisher);
+      spawn_subscriber("publisher", watcher.subscribe(), move |tx| {
+        let publisher = publisher.clone();
+        async move { publisher.publish(&tx).await }
+      });
+    }
+    for handler in handlers {
+      log::info!("Registered chain watcher event handler: {}", handler.name());
+      spawn_subscriber(handler.name(), watcher.subscribe(), move |tx| {
+        let handler = handler.clone();
+        async move { handler.handle_transaction(&tx).await }
+      });
+    }
 
+    watcher.run().await
+  }
+}
+
+/// Start the chain watcher with the default subscribers.
+///
+/// See `ChainWatcherBuilder` to register custom event handlers.
 pub async fn start_chain_watcher(
-  api: Api,
+  nodes: AppNodes,
   repo: Repository,
   tx_repo: TransactionRepository,
+  webhooks: AppWebhooks,
+  publisher: Option<EventPublisher>,
+  options: WatcherOptions,
 ) -> anyhow::Result<()> {
-  let client = api.client();
+  ChainWatcherBuilder::new(nodes, repo, tx_repo, webhooks)
+    .publisher(publisher)
+    .options(options)
+    .run()
+    .await
+}
 
-  let mut sub_blocks = client.subscribe_blocks().await?;
+/// Spawn a task that passes each published transaction to `handler`.
+///
+/// Delivery is best-effort, failed and skipped (lagged) transactions are only logged.
+pub fn spawn_subscriber<F, Fut>(
+  name: &'static str,
+  mut rx: broadcast::Receiver<WatcherEvent>,
+  handler: F,
+) where
+  F: Fn(WatcherEvent) -> Fut + 'static,
+  Fut: std::future::Future<Output = Result<()>> + 'static,
+{
+  actix_web::rt::spawn(async move {
+    loop {
+      match rx.recv().await {
+        Ok(tx) => {
+          if let Err(err) = handler(tx).await {
+            log::error!("Chain watcher subscriber {name} failed: {err:?}");
+          }
+        }
+        Err(RecvError::Lagged(skipped)) => {
+          log::error!("Chain watcher subscriber {name} lagged, skipped {skipped} transactions");
+        }
+        Err(RecvError::Closed) => break,
+      }
+    }
+  });
+}
 
-  while let Some(header) = sub_blocks.next().await.transpose()? {
-    let transactions = TransactionResult::get_block_transactions(&api, header).await?;
-    if transactions.len() > 1 {
-      for tx in transactions {
-        let rec = BlockTransactionRecord::from_tx(&tx)?;
-        // Add block transaction record.
-        tx_repo.add_block_transaction(rec).await?;
-        // process events.
-        for ev in &tx.processed_events.0 {
-          match ev {
-            ProcessedEvent::ConfidentialTransactionCreated(created) => {
-              let rec = SettlementRecord::from_tx(created)?;
-              tx_repo.add_settlement(rec).await?;
-            }
-            ProcessedEvent::ConfidentialAssetCreated { asset_id } => {
-              // Check if the asset exists.
-              if repo.get_asset(*asset_id).await?.is_none() {
-                repo
-                  .create_asset(&AddAsset {
-                    asset_id: *asset_id,
-                  })
-                  .await?;
-              }
-            }
-            _ => (),
+/// Save block transactions, settlements, assets, account DIDs and paid invoices to the database.
+async fn persist_transaction(
+  repo: &Repository,
+  tx_repo: &TransactionRepository,
+  tx: &TransactionResult,
+) -> Result<()> {
+  let rec = BlockTransactionRecord::from_tx(tx)?;
+  // Add block transaction record.
+  tx_repo.add_block_transaction(rec).await?;
+  // process events.
+  for ev in &tx.processed_events.0 {
+    match ev {
+      ProcessedEvent::ConfidentialTransactionCreated(created) => {
+        let rec = SettlementRecord::from_tx(created)?;
+        tx_repo
+          .add_settlement(rec, &SettlementLeg::from_tx(created))
+          .await?;
+        // Settlements paying an invoice have the invoice reference as memo.
+        if !created.memo.is_empty() {
+          let settlement_id = created.transaction_id.0 as i64;
+          if let Some(invoice) = tx_repo
+            .set_invoice_settlement(&created.memo, settlement_id)
+            .await?
+          {
+            log::info!(
+              "Invoice {} is paid by settlement {settlement_id}",
+              invoice.reference
+            );
           }
         }
-        // Settlement events.
-        let recs = SettlementEventRecord::from_events(&tx.processed_events)?;
-        for rec in recs {
-          tx_repo.add_settlement_event(rec).await?;
+      }
+      ProcessedEvent::ConfidentialTransactionExecuted { transaction_id } => {
+        if let Some(invoice) = tx_repo
+          .settle_invoice(transaction_id.0 as i64, true)
+          .await?
+        {
+          log::info!("Invoice {} paid", invoice.reference);
+        }
+      }
+      ProcessedEvent::ConfidentialTransactionRejected { transaction_id } => {
+        if let Some(invoice) = tx_repo
+          .settle_invoice(transaction_id.0 as i64, false)
+          .await?
+        {
+          log::info!(
+            "Invoice {} is open again, its settlement was rejected",
+            invoice.reference
+          );
         }
       }
+      ProcessedEvent::ConfidentialAssetCreated { asset_id } => {
+        ensure_asset(repo, *asset_id).await?;
+      }
+      ProcessedEvent::ConfidentialAccountCreated { did, account } => {
+        // Only updates our own accounts.
+        repo
+          .set_account_did(&hex::encode(account.0), &did_to_hex(did))
+          .await?;
+      }
+      _ => (),
     }
   }
+  // Settlement events.
+  let recs = SettlementEventRecord::from_events(tx.block_number, &tx.processed_events)?;
+  for rec in recs {
+    tx_repo.add_settlement_event(rec).await?;
+  }
+  Ok(())
+}
+
+/// Update the balances of our confidential accounts.
+async fn update_balances(repo: &Repository, tx: &TransactionResult) -> Result<()> {
+  for update in balance_updates(repo, tx).await? {
+    ensure_asset(repo, update.asset_id).await?;
+    repo.update_account_asset(&update).await?;
+  }
+  Ok(())
+}
+
+/// Decrypt the balance updates of our confidential accounts.
+async fn balance_updates(
+  repo: &Repository,
+  tx: &TransactionResult,
+) -> Result<Vec<UpdateAccountAsset>> {
+  let mut updates = Vec::new();
+  for ev in &tx.processed_events.0 {
+    let balance_updated = match ev {
+      ProcessedEvent::ConfidentialAccountBalanceUpdated(balance_updated) => balance_updated,
+      _ => continue,
+    };
+    // Incoming balances are not part of the account's balance until they are applied.
+    if let BalanceUpdateAction::DepositIncoming = balance_updated.action {
+      continue;
+    }
+    let pub_key = hex::encode(balance_updated.account.0);
+    let account = match repo.get_account_with_secret(&pub_key).await? {
+      Some(account) => account,
+      None => continue,
+    };
+    if let Some(update) = balance_updated.try_decrypt(&account) {
+      let (action, amount) = update.history_change();
+      updates.push(UpdateAccountAsset {
+        account_asset_id: None,
+        account_id: account.account_id,
+        asset_id: update.asset_id,
+        balance: update.balance,
+        enc_balance: balance_updated.balance()?,
+        block_number: Some(tx.block_number),
+        source: BalanceSource::Chain,
+        base_enc_balance: None,
+        action,
+        amount: Some(amount),
+        history_source: Some(tx.tx_hash.clone()),
+      });
+    }
+  }
+  Ok(updates)
+}
 
+/// Balance update of a local confidential account, as reported in dry-run mode.
+#[derive(Clone, Debug, Serialize)]
+pub struct DryRunBalanceUpdate {
+  pub account_id: i64,
+  pub asset_id: uuid::Uuid,
+  pub balance: u64,
+}
+
+/// Everything the watcher would write for one block transaction.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DryRunRecord {
+  pub transaction: BlockTransactionRecord,
+  pub settlements: Vec<SettlementRecord>,
+  pub settlement_legs: Vec<SettlementLeg>,
+  pub settlement_events: Vec<SettlementEventRecord>,
+  /// New assets.
+  pub assets: Vec<uuid::Uuid>,
+  /// `(confidential account, did)` of our accounts.
+  pub account_dids: Vec<(String, String)>,
+  pub balance_updates: Vec<DryRunBalanceUpdate>,
+  pub ledger_entries: Vec<LedgerEntry>,
+  pub webhooks: Vec<WebhookEvent>,
+}
+
+impl DryRunRecord {
+  /// Build the record without writing anything.  Only reads from the database.
+  pub async fn from_tx(repo: &Repository, tx: &TransactionResult) -> Result<Self> {
+    let mut rec = Self {
+      transaction: BlockTransactionRecord::from_tx(tx)?,
+      settlement_events: SettlementEventRecord::from_events(tx.block_number, &tx.processed_events)?,
+      balance_updates: balance_updates(repo, tx)
+        .await?
+        .into_iter()
+        .map(|update| DryRunBalanceUpdate {
+          account_id: update.account_id,
+          asset_id: update.asset_id,
+          balance: update.balance,
+        })
+        .collect(),
+      ledger_entries: ledger_entries(repo, tx).await?,
+      webhooks: WebhookEvent::chain_events(tx),
+      ..Default::default()
+    };
+    for ev in &tx.processed_events.0 {
+      match ev {
+        ProcessedEvent::ConfidentialTransactionCreated(created) => {
+          rec.settlements.push(SettlementRecord::from_tx(created)?);
+          rec.settlement_legs.extend(SettlementLeg::from_tx(created));
+        }
+        ProcessedEvent::ConfidentialAssetCreated { asset_id } => {
+          if repo.get_asset(*asset_id).await?.is_none() {
+            rec.assets.push(*asset_id);
+          }
+        }
+        ProcessedEvent::ConfidentialAccountCreated { did, account } => {
+          let pub_key = hex::encode(account.0);
+          if repo.get_account(&pub_key).await?.is_some() {
+            rec.account_dids.push((pub_key, did_to_hex(did)));
+          }
+        }
+        _ => (),
+      }
+    }
+    Ok(rec)
+  }
+}
+
+/// Log what the watcher would write.
+async fn log_dry_run(repo: Repository, tx: WatcherEvent) -> Result<()> {
+  let rec = DryRunRecord::from_tx(&repo, &tx).await?;
+  log::info!("Dry-run: {}", serde_json::to_string(&rec)?);
+  Ok(())
+}
+
+async fn ensure_asset(repo: &Repository, asset_id: uuid::Uuid) -> Result<()> {
+  // Check if the asset exists.
+  if repo.get_asset(asset_id).await?.is_none() {
+    repo
+      .create_asset(&AddAsset {
+        asset_id,
+        ..Default::default()
+      })
+      .await?;
+  }
   Ok(())
 }
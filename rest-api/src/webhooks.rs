@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use actix_web::web::Data;
+
+use reqwest::{header, Client, Url};
+
+use polymesh_private_proof_shared::{
+  error::{Error, Result},
+  CreateWebhookEndpoint, Deposit, ProcessedEvent, TransactionResult, WebhookEndpoint,
+  WebhookOutboxRecord,
+};
+
+use crate::repo::TransactionRepository;
+
+pub type AppWebhooks = Data<WebhookSender>;
+
+/// How often the outbox is checked for webhooks to deliver.
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Maximum number of webhooks to deliver per poll.
+const OUTBOX_BATCH_SIZE: u32 = 100;
+/// Maximum delay between retries.
+const MAX_RETRY_SECS: u64 = 60 * 60;
+/// Number of blocks replayed per poll when backfilling an endpoint.
+const BACKFILL_BLOCKS: u32 = 1000;
+/// Timeout of a delivery.  A receiver that doesn't respond in time counts as a failed attempt.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Webhook events.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum WebhookEvent {
+  /// A signer has used `used` of `max` for one of its budget limits.
+  SignerBudgetAlert {
+    public_key: String,
+    limit: String,
+    used: i64,
+    max: i64,
+  },
+  /// An event processed by the chain watcher.
+  ChainEvent {
+    block_number: u32,
+    tx_hash: String,
+    event: ProcessedEvent,
+  },
+  /// A transfer was credited to a deposit account.
+  Deposit(Deposit),
+}
+
+impl WebhookEvent {
+  /// Chain events of a block transaction.
+  pub fn chain_events(tx: &TransactionResult) -> Vec<Self> {
+    tx.processed_events
+      .0
+      .iter()
+      .map(|ev| Self::ChainEvent {
+        block_number: tx.block_number,
+        tx_hash: tx.tx_hash.clone(),
+        event: ev.clone(),
+      })
+      .collect()
+  }
+
+  /// Identifies the chain event with index `index` of the transaction `tx_hash`.
+  fn chain_event_key(tx_hash: &str, index: usize) -> String {
+    format!("{tx_hash}:{index}")
+  }
+
+  fn block_number(&self) -> Option<u32> {
+    match self {
+      Self::ChainEvent { block_number, .. } => Some(*block_number),
+      _ => None,
+    }
+  }
+}
+
+/// Delivers webhook events to the configured url and the registered endpoints.
+///
+/// Events are first stored in the `webhook_outbox` table and then delivered (at-least-once)
+/// by `run_outbox`, so they survive restarts.  The chain watcher queues chain events while
+/// persisting their block, before the block is saved as processed.  The outbox id is sent
+/// in the `X-Webhook-Id` header to allow receivers to de-duplicate events.
+///
+/// An endpoint registered with a backfill first gets the stored chain events from the
+/// requested block up to the last block stored when it was registered.  Its live webhooks
+/// are held in the outbox until the backfill has been delivered.
+pub struct WebhookSender {
+  client: Client,
+  url: Option<Url>,
+  max_attempts: i64,
+  tx_repo: TransactionRepository,
+}
+
+impl WebhookSender {
+  pub fn new(
+    url: Option<String>,
+    max_attempts: i64,
+    tx_repo: TransactionRepository,
+  ) -> Result<Self> {
+    let url = match url {
+      Some(url) => Some(Url::parse(&url)?),
+      None => None,
+    };
+    Ok(Self {
+      client: Client::builder().timeout(DELIVERY_TIMEOUT).build()?,
+      url,
+      max_attempts,
+      tx_repo,
+    })
+  }
+
+  pub fn new_app_data(
+    url: Option<String>,
+    max_attempts: i64,
+    tx_repo: TransactionRepository,
+  ) -> Result<AppWebhooks> {
+    Ok(Data::new(Self::new(url, max_attempts, tx_repo)?))
+  }
+
+  /// Queue a webhook event for delivery to the configured url and the registered endpoints.
+  pub async fn send(&self, event: WebhookEvent) -> Result<()> {
+    self.queue(&event, None).await
+  }
+
+  /// Queue the chain events of a block transaction.
+  ///
+  /// Events that are already queued are skipped, so the transaction's block can be processed
+  /// again.
+  pub async fn send_chain_events(&self, tx: &TransactionResult) -> Result<()> {
+    for (index, event) in WebhookEvent::chain_events(tx).iter().enumerate() {
+      let key = WebhookEvent::chain_event_key(&tx.tx_hash, index);
+      self.queue(event, Some(&key)).await?;
+    }
+    Ok(())
+  }
+
+  /// Queue a webhook event, once per endpoint if it has an `event_key`.
+  ///
+  /// Chain events of blocks covered by an endpoint's backfill aren't queued for it again.
+  async fn queue(&self, event: &WebhookEvent, event_key: Option<&str>) -> Result<()> {
+    let endpoints = self.tx_repo.get_webhook_endpoints().await?;
+    if self.url.is_none() && endpoints.is_empty() {
+      return Ok(());
+    }
+    let block_number = event.block_number();
+    let event = serde_json::to_string(event)?;
+    if self.url.is_some() {
+      self
+        .tx_repo
+        .add_webhook_outbox(&event, event_key, None, false)
+        .await?;
+    }
+    for endpoint in endpoints {
+      if block_number.map_or(true, |block| block as i64 > endpoint.live_from) {
+        self
+          .tx_repo
+          .add_webhook_outbox(&event, event_key, Some(endpoint.endpoint_id), false)
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Register a webhook endpoint.
+  pub async fn create_endpoint(&self, req: &CreateWebhookEndpoint) -> Result<WebhookEndpoint> {
+    Url::parse(&req.url)?;
+    let backfill_from = match (req.from_block, req.from_settlement) {
+      (Some(_), Some(_)) => {
+        return Err(Error::other(
+          "Only one of `from_block` and `from_settlement` can be given",
+        ));
+      }
+      (Some(block), None) => Some(block),
+      (None, Some(settlement_id)) => Some(
+        self
+          .tx_repo
+          .get_settlement_events(settlement_id as i64)
+          .await?
+          .iter()
+          .map(|ev| ev.block_number)
+          .min()
+          .ok_or_else(|| Error::not_found("Settlement"))?,
+      ),
+      (None, None) => None,
+    };
+    let endpoint = self
+      .tx_repo
+      .create_webhook_endpoint(&req.url, backfill_from)
+      .await?;
+    log::info!(
+      "Registered webhook endpoint {}: {} (backfill from: {backfill_from:?})",
+      endpoint.endpoint_id,
+      endpoint.url
+    );
+    Ok(endpoint)
+  }
+
+  /// Deliver queued webhooks until the process exits.
+  pub async fn run_outbox(&self) {
+    loop {
+      if let Err(err) = self.backfill().await {
+        log::error!("Failed to backfill webhook endpoints: {err:?}");
+      }
+      if let Err(err) = self.deliver_pending().await {
+        log::error!("Failed to process webhook outbox: {err:?}");
+      }
+      actix_web::rt::time::sleep(OUTBOX_POLL_INTERVAL).await;
+    }
+  }
+
+  /// Queue the next window of stored chain events for the `backfilling` endpoints and switch
+  /// them to `live` once the backfill has been delivered.
+  async fn backfill(&self) -> Result<()> {
+    let endpoints = self.tx_repo.get_webhook_endpoints().await?;
+    for endpoint in endpoints {
+      if endpoint.status != "backfilling" {
+        continue;
+      }
+      let from = match (endpoint.backfill_cursor, endpoint.backfill_from) {
+        (Some(cursor), _) => cursor + 1,
+        (None, Some(from)) => from,
+        (None, None) => endpoint.live_from + 1,
+      };
+      if from > endpoint.live_from {
+        if self
+          .tx_repo
+          .webhook_endpoint_live(endpoint.endpoint_id)
+          .await?
+        {
+          log::info!(
+            "Webhook endpoint {} backfilled, switched to live",
+            endpoint.endpoint_id
+          );
+        }
+        continue;
+      }
+      let from = from as u32;
+      let to = from
+        .saturating_add(BACKFILL_BLOCKS - 1)
+        .min(endpoint.live_from as u32);
+      let txs = self.tx_repo.get_block_transactions_range(from, to).await?;
+      for tx in txs {
+        let events = WebhookEvent::chain_events(&tx.to_tx_result()?);
+        for (index, event) in events.iter().enumerate() {
+          let key = WebhookEvent::chain_event_key(&tx.tx_hash, index);
+          let event = serde_json::to_string(event)?;
+          self
+            .tx_repo
+            .add_webhook_outbox(&event, Some(&key), Some(endpoint.endpoint_id), true)
+            .await?;
+        }
+      }
+      self
+        .tx_repo
+        .set_webhook_backfill_cursor(endpoint.endpoint_id, to)
+        .await?;
+      log::debug!(
+        "Webhook endpoint {}: replayed blocks {from} to {to}",
+        endpoint.endpoint_id
+      );
+    }
+    Ok(())
+  }
+
+  async fn deliver_pending(&self) -> Result<()> {
+    let pending = self.tx_repo.get_due_webhooks(OUTBOX_BATCH_SIZE).await?;
+    if pending.is_empty() {
+      return Ok(());
+    }
+    let endpoints = self
+      .tx_repo
+      .get_webhook_endpoints()
+      .await?
+      .into_iter()
+      .map(|endpoint| Ok((endpoint.endpoint_id, Url::parse(&endpoint.url)?)))
+      .collect::<Result<BTreeMap<_, _>>>()?;
+    for rec in pending {
+      let url = match rec.endpoint_id {
+        Some(endpoint_id) => endpoints.get(&endpoint_id),
+        None => self.url.as_ref(),
+      };
+      let res = match url {
+        Some(url) => self.deliver(url, &rec).await,
+        None => Err(Error::other("Webhook url isn't configured")),
+      };
+      match res {
+        Ok(_) => {
+          self.tx_repo.webhook_delivered(rec.id).await?;
+        }
+        Err(err) => {
+          let attempts = rec.attempts + 1;
+          // No point retrying without a url.
+          let dead = attempts >= self.max_attempts || url.is_none();
+          if dead {
+            log::error!(
+              "Webhook {} failed {attempts} times, giving up: {err:?}",
+              rec.id
+            );
+          }
+          // Exponential backoff.
+          let retry_secs = 2u64.saturating_pow(attempts as u32).min(MAX_RETRY_SECS);
+          self
+            .tx_repo
+            .webhook_failed(rec.id, &format!("{err:?}"), retry_secs, dead)
+            .await?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  async fn deliver(&self, url: &Url, rec: &WebhookOutboxRecord) -> Result<()> {
+    self
+      .client
+      .post(url.clone())
+      .header(header::CONTENT_TYPE, "application/json")
+      .header("X-Webhook-Id", rec.id)
+      .body(rec.event.clone())
+      .send()
+      .await?
+      .error_for_status()?;
+    Ok(())
+  }
+}
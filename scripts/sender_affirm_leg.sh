@@ -17,6 +17,6 @@ curl -s -X 'POST' \
 	\"finalize\": false,
   \"transaction_id\": $TX_ID,
   \"leg_id\": $LEG_ID,
-  \"amount\": $AMOUNT
+  \"amounts\": [{ \"asset_id\": \"$TICKER\", \"amount\": $AMOUNT }]
 }" | json_pp
 
@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{BalanceHistory, ProofRecord, Receipt};
+
+/// Export an account asset's balances and proofs for an auditor.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AccountAssetExportRequest {
+  /// Only records created at or after this time (UTC).
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub from_date: Option<chrono::NaiveDateTime>,
+  /// Only records created before this time (UTC).
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub to_date: Option<chrono::NaiveDateTime>,
+}
+
+impl AccountAssetExportRequest {
+  /// Was a record created at `created_at` in the export's range.
+  pub fn contains(&self, created_at: &chrono::NaiveDateTime) -> bool {
+    self.from_date.map_or(true, |from| *created_at >= from)
+      && self.to_date.map_or(true, |to| *created_at < to)
+  }
+}
+
+/// Chain transaction that changed the balance.
+#[derive(
+  Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, ToSchema,
+)]
+pub struct ChainReference {
+  /// Block number.
+  #[schema(example = 1)]
+  pub block_number: u32,
+  /// Transaction hash.
+  #[schema(example = "0xb6f8a9a8a0e5a7c8c1d1e0f2a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6")]
+  pub tx_hash: Option<String>,
+}
+
+/// Evidence package of an account asset.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AccountAssetExport {
+  /// Confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: String,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Start time of the export (UTC).
+  pub from_date: Option<chrono::NaiveDateTime>,
+  /// End time of the export (UTC).
+  pub to_date: Option<chrono::NaiveDateTime>,
+  /// Encrypted and decrypted balances after each change, oldest first.
+  pub balances: Vec<BalanceHistory>,
+  /// Sender and burn proofs generated for the asset, oldest first.
+  pub proofs: Vec<ProofRecord>,
+  /// Chain transactions of the balance changes.
+  pub chain_references: Vec<ChainReference>,
+
+  pub generated_at: chrono::NaiveDateTime,
+}
+
+impl AccountAssetExport {
+  pub fn new(
+    confidential_account: String,
+    asset_id: Uuid,
+    req: &AccountAssetExportRequest,
+    balances: Vec<BalanceHistory>,
+    mut proofs: Vec<ProofRecord>,
+  ) -> Self {
+    proofs.retain(|proof| req.contains(&proof.created_at));
+    proofs.sort_by_key(|proof| proof.proof_id);
+    let chain_references = balances
+      .iter()
+      .filter_map(|history| {
+        let block_number = history.block_number?;
+        // Chain changes have the transaction hash as their source.
+        let tx_hash = history
+          .source
+          .as_ref()
+          .filter(|source| source.starts_with("0x"))
+          .cloned();
+        Some(ChainReference {
+          block_number,
+          tx_hash,
+        })
+      })
+      .collect::<BTreeSet<_>>()
+      .into_iter()
+      .collect();
+    Self {
+      confidential_account,
+      asset_id,
+      from_date: req.from_date,
+      to_date: req.to_date,
+      balances,
+      proofs,
+      chain_references,
+      generated_at: chrono::Utc::now().naive_utc(),
+    }
+  }
+}
+
+/// Account asset export with a signed receipt.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SignedAccountAssetExport {
+  pub export: AccountAssetExport,
+  /// Receipt with `request_hash` set to the Blake2-256 hash of the export's JSON.
+  /// Only available when receipt signing is enabled.
+  pub receipt: Option<Receipt>,
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Audit log entry of an account operation.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AuditLogEntry {
+  /// Entry id.
+  #[schema(example = 1)]
+  pub id: i64,
+  /// Account id.
+  #[serde(skip)]
+  pub account_id: i64,
+  /// Asset id, if the operation was for one asset.
+  #[schema(example = json!(null))]
+  pub asset_id: Option<Uuid>,
+  /// Operation: `sender_proof` or `decrypt`.
+  #[schema(example = "sender_proof")]
+  pub action: String,
+  /// Amount sent.
+  #[schema(example = 1000)]
+  pub amount: Option<i64>,
+  /// User making the request, authenticated by their API key.  `None` without an API key.
+  #[schema(example = "Alice")]
+  pub user: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+
+  /// Hash of the previous chained entry.  `None` for entries from before the hash chain.
+  #[serde(skip)]
+  pub prev_hash: Option<Vec<u8>>,
+  /// Hash of this entry (including `prev_hash`).
+  #[serde(skip)]
+  pub hash: Option<Vec<u8>>,
+}
+
+/// Add an audit log entry.
+#[derive(Clone, Debug, Default)]
+pub struct AddAuditLogEntry {
+  pub account_id: i64,
+  pub asset_id: Option<Uuid>,
+  pub action: String,
+  pub amount: Option<i64>,
+  pub user: Option<String>,
+}
+
+/// Problem with an audit log entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditChainProblem {
+  /// The entry doesn't match its hash, it was altered.
+  HashMismatch,
+  /// The entry doesn't link to the previous entry's hash, entries before it were deleted,
+  /// altered or reordered.
+  BrokenLink,
+  /// The entry was added to the chain without a hash (or its hash was removed).
+  MissingHash,
+}
+
+/// Audit log entry that failed verification.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuditChainError {
+  /// Entry id.
+  #[schema(example = 42)]
+  pub id: i64,
+  /// What is wrong with the entry.
+  pub problem: AuditChainProblem,
+}
+
+/// Result of verifying the audit log hash chain.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct AuditChainReport {
+  /// Is the chain intact.
+  #[schema(example = true)]
+  pub valid: bool,
+  /// Number of checked entries.
+  #[schema(example = 1000)]
+  pub checked: u64,
+  /// Number of entries from before the hash chain, they can't be verified.
+  #[schema(example = 0)]
+  pub unchained: u64,
+  /// Entries that failed verification.
+  pub errors: Vec<AuditChainError>,
+  /// Id of the last chained entry.
+  #[schema(example = 1000)]
+  pub head_id: Option<i64>,
+  /// Hash of the last chained entry.  Record it elsewhere to detect deleted entries at the
+  /// end of the log.
+  #[schema(example = "0x0000000000000000000000000000000000000000000000000000000000000000")]
+  pub head_hash: Option<String>,
+
+  pub completed_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Triggered anomaly detection rule.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AnomalyAlert {
+  /// Alert id.
+  #[schema(example = 1)]
+  pub alert_id: i64,
+  /// Rule: `amount_zscore`, `proof_burst` or `off_hours_decrypt`.
+  #[schema(example = "amount_zscore")]
+  pub rule: String,
+  /// Confidential account (`0x` prefixed hex).
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: String,
+  /// Asset id, if the operation was for one asset.
+  #[schema(example = json!(null))]
+  pub asset_id: Option<Uuid>,
+  /// User making the request, authenticated by their API key.  `None` without an API key.
+  #[schema(example = "Alice")]
+  pub user: Option<String>,
+  /// Why the rule triggered.
+  #[schema(example = "amount 5000000 is 4.2 standard deviations above the mean 1000 (50 samples)")]
+  pub details: String,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Add an anomaly alert.
+#[derive(Clone, Debug, Default)]
+pub struct AddAnomalyAlert {
+  pub rule: String,
+  pub confidential_account: String,
+  pub asset_id: Option<Uuid>,
+  pub user: Option<String>,
+  pub details: String,
+}
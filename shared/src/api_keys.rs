@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+
+/// API key that authenticates a user.  Only the hash of the key is stored.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct UserApiKey {
+  /// Key id.
+  #[schema(example = 1)]
+  pub key_id: i64,
+  /// User the key authenticates.
+  #[schema(example = "Alice")]
+  pub username: String,
+  /// Name of the key.
+  #[schema(example = "Treasury desk")]
+  pub name: String,
+
+  pub revoked_at: Option<chrono::NaiveDateTime>,
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Create an API key for a user.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateUserApiKey {
+  /// Name of the key.
+  #[schema(example = "Treasury desk")]
+  pub name: String,
+}
+
+/// New API key.  The key is only returned once.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct UserApiKeyCreated {
+  pub api_key: UserApiKey,
+  /// The key, sent in the `X-Api-Key` header.
+  #[schema(example = "0x5ba1d3b3a0a5f6f5c1f0e4f7b0c0d1a2b3c4d5e6f708192a3b4c5d6e7f809102")]
+  pub key: String,
+}
+
+/// Account a user may decrypt.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct UserAccountAccess {
+  /// User name.
+  #[schema(example = "Alice")]
+  pub username: String,
+  /// Confidential account (Elgamal public key).
+  #[schema(example = "0xdeadbeef00000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub confidential_account: Vec<u8>,
+
+  pub created_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+
+use crate::error::*;
+
+/// Operation that can require a second user's approval.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalOperation {
+  /// Generate a burn proof at or above the burn threshold.
+  Burn,
+  /// Create a signer.
+  CreateSigner,
+  /// Send more than an account's amount limits allow.
+  LimitOverride,
+  /// Generate a proof with an `encrypted_balance` instead of the tracked balance.
+  BalanceOverride,
+  /// Export an account asset's evidence.
+  AccountExport,
+  /// Replace a compromised account's key.
+  KeyRotation,
+}
+
+impl ApprovalOperation {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Burn => "burn",
+      Self::CreateSigner => "create_signer",
+      Self::LimitOverride => "limit_override",
+      Self::BalanceOverride => "balance_override",
+      Self::AccountExport => "account_export",
+      Self::KeyRotation => "key_rotation",
+    }
+  }
+}
+
+impl FromStr for ApprovalOperation {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "burn" => Ok(Self::Burn),
+      "create_signer" => Ok(Self::CreateSigner),
+      "limit_override" => Ok(Self::LimitOverride),
+      "balance_override" => Ok(Self::BalanceOverride),
+      "account_export" => Ok(Self::AccountExport),
+      "key_rotation" => Ok(Self::KeyRotation),
+      _ => Err(Error::Other(format!("Unknown approval operation: {s}"))),
+    }
+  }
+}
+
+/// Approval of a sensitive operation.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct Approval {
+  /// Approval id.
+  #[schema(example = 1)]
+  pub approval_id: i64,
+  /// Operation: `burn`, `create_signer`, `limit_override`, `balance_override`,
+  /// `account_export` or `key_rotation`.
+  #[schema(example = "burn")]
+  pub operation: String,
+  /// What the operation is for (i.e. the account or signer name).
+  #[schema(example = "0xdeadbeef00000000000000000000000000000000000000000000000000000000")]
+  pub target: String,
+  /// Blake2-256 hash of the request.  The approved request must be resubmitted unchanged.
+  #[schema(example = "0xdeadbeef00000000000000000000000000000000000000000000000000000000")]
+  pub request_hash: String,
+  /// Status: `pending`, `approved`, `rejected` or `executed`.
+  #[schema(example = "pending")]
+  pub status: String,
+  /// User that requested the operation.
+  #[schema(example = "Alice")]
+  pub requested_by: String,
+  /// User that approved or rejected the operation.
+  #[schema(example = json!(null))]
+  pub decided_by: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Create an approval.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CreateApproval {
+  pub operation: String,
+  pub target: String,
+  pub request_hash: String,
+  pub requested_by: String,
+}
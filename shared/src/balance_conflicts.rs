@@ -0,0 +1,182 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::*;
+use crate::EnvConfig;
+
+/// Where a balance update came from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSource {
+  /// Read from the chain (chain watcher, balance refresh or import).
+  Chain,
+  /// Computed locally (proof generation, mint, applying incoming balances).
+  #[default]
+  Local,
+  /// Set with the `update_balance` endpoint.
+  Manual,
+}
+
+impl BalanceSource {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Chain => "chain",
+      Self::Local => "local",
+      Self::Manual => "manual",
+    }
+  }
+}
+
+impl FromStr for BalanceSource {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "chain" => Ok(Self::Chain),
+      "local" => Ok(Self::Local),
+      "manual" => Ok(Self::Manual),
+      _ => Err(Error::Other(format!("Unknown balance source: {s}"))),
+    }
+  }
+}
+
+/// How a balance update that disagrees with the stored balance is resolved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceConflictStrategy {
+  /// Chain balances replace local ones, local updates of a chain balance are discarded.
+  #[default]
+  ChainWins,
+  /// Local balances are kept, chain updates of a local balance are discarded.
+  LocalWins,
+  /// Conflicting updates are held for an operator to accept or reject.
+  Review,
+}
+
+impl BalanceConflictStrategy {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::ChainWins => "chain_wins",
+      Self::LocalWins => "local_wins",
+      Self::Review => "review",
+    }
+  }
+
+  /// Load the strategy from `BALANCE_CONFLICT_STRATEGY` (default: `chain_wins`).
+  pub fn from_env() -> Result<Self> {
+    Self::from_config(&EnvConfig::from_env())
+  }
+
+  /// Load the strategy from the config's `BALANCE_CONFLICT_STRATEGY`.
+  pub fn from_config(config: &EnvConfig) -> Result<Self> {
+    match config.var("BALANCE_CONFLICT_STRATEGY") {
+      Ok(strategy) if !strategy.is_empty() => Self::from_str(&strategy),
+      _ => Ok(Self::default()),
+    }
+  }
+
+  /// Should an update from `source` replace the stored balance written by `current`?
+  /// `None` holds the update for review.
+  ///
+  /// The strategies only decide between chain and local balances, between local and
+  /// manual updates the last write wins.
+  pub fn resolve(&self, source: BalanceSource, current: BalanceSource) -> Option<bool> {
+    let from_chain = source == BalanceSource::Chain;
+    let chain_stored = current == BalanceSource::Chain;
+    match self {
+      Self::ChainWins => Some(from_chain || !chain_stored),
+      Self::LocalWins => Some(!from_chain || chain_stored),
+      Self::Review => None,
+    }
+  }
+}
+
+impl FromStr for BalanceConflictStrategy {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "chain_wins" => Ok(Self::ChainWins),
+      "local_wins" => Ok(Self::LocalWins),
+      "review" => Ok(Self::Review),
+      _ => Err(Error::Other(format!(
+        "Unknown balance conflict strategy: {s}"
+      ))),
+    }
+  }
+}
+
+/// Balance conflict status.
+pub const CONFLICT_APPLIED: &str = "applied";
+pub const CONFLICT_DISCARDED: &str = "discarded";
+pub const CONFLICT_PENDING: &str = "pending";
+pub const CONFLICT_ACCEPTED: &str = "accepted";
+pub const CONFLICT_REJECTED: &str = "rejected";
+
+/// Balance update that disagreed with the stored balance.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct BalanceConflict {
+  /// Conflict id.
+  #[schema(example = 1)]
+  pub conflict_id: i64,
+  /// Account asset id.
+  #[serde(skip)]
+  pub account_asset_id: i64,
+  /// Confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub confidential_account: Vec<u8>,
+  /// Asset id.
+  pub asset_id: Uuid,
+
+  /// Source of the update: `chain`, `local` or `manual`.
+  #[schema(example = "chain")]
+  pub source: String,
+  /// Balance of the update.
+  #[schema(example = 1000)]
+  pub balance: i64,
+  /// Balance of the update encryted.
+  #[schema(value_type = String, format = Binary, example = "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub enc_balance: Vec<u8>,
+  /// Block of a chain update.
+  #[schema(example = json!(null))]
+  pub block_number: Option<u32>,
+
+  /// Source of the stored balance.
+  #[schema(example = "local")]
+  pub current_source: String,
+  /// Stored balance when the update was made.
+  #[schema(example = 900)]
+  pub current_balance: i64,
+
+  /// Strategy that handled the conflict.
+  #[schema(example = "review")]
+  pub strategy: String,
+  /// Status: `applied`, `discarded`, `pending`, `accepted` or `rejected`.
+  #[schema(example = "pending")]
+  pub status: String,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub resolved_at: Option<chrono::NaiveDateTime>,
+}
+
+impl BalanceConflict {
+  pub fn is_pending(&self) -> bool {
+    self.status == CONFLICT_PENDING
+  }
+}
+
+/// Resolve a pending balance conflict.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ResolveBalanceConflict {
+  /// Apply the held update (`true`) or keep the stored balance (`false`).
+  #[schema(example = true)]
+  pub accept: bool,
+}
@@ -0,0 +1,152 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use codec::Encode;
+use confidential_assets::{
+  elgamal::CipherText, transaction::MAX_TOTAL_SUPPLY, Balance, ElgamalKeys,
+};
+use lru::LruCache;
+
+use crate::decryption_context::DecryptionContext;
+
+/// Default number of cached decryptions.
+const DEFAULT_CACHE_SIZE: usize = 10_000;
+
+/// `(account public key, ciphertext)`.
+type CacheKey = (Vec<u8>, Vec<u8>);
+
+static DECRYPTION_CACHE: OnceLock<DecryptionCache> = OnceLock::new();
+
+/// LRU cache of decrypted values.
+///
+/// Decrypting a balance is a discrete log search, so the same ciphertexts (balances
+/// used in reconciliation, reports and history) are only decrypted once.  Entries are
+/// keyed by the account's public key and the ciphertext, and an account's entries are
+/// dropped when its secret key changes or stops being available.
+pub struct DecryptionCache {
+  cache: Mutex<Option<LruCache<CacheKey, Balance>>>,
+  hits: AtomicU64,
+  misses: AtomicU64,
+}
+
+/// Decryption cache statistics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecryptionCacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  pub entries: usize,
+  pub capacity: usize,
+}
+
+impl DecryptionCacheStats {
+  /// Ratio of cache hits to lookups.
+  pub fn hit_rate(&self) -> f64 {
+    match self.hits + self.misses {
+      0 => 0.0,
+      total => self.hits as f64 / total as f64,
+    }
+  }
+}
+
+impl DecryptionCache {
+  fn new(size: usize) -> Self {
+    Self {
+      cache: Mutex::new(NonZeroUsize::new(size).map(LruCache::new)),
+      hits: AtomicU64::new(0),
+      misses: AtomicU64::new(0),
+    }
+  }
+
+  /// The process wide cache.
+  pub fn global() -> &'static Self {
+    DECRYPTION_CACHE.get_or_init(|| Self::new(DEFAULT_CACHE_SIZE))
+  }
+
+  /// Resize the cache.  A size of zero disables the cache.
+  pub fn set_size(size: usize) {
+    let mut cache = Self::global()
+      .cache
+      .lock()
+      .expect("Decryption cache poisoned");
+    match (cache.as_mut(), NonZeroUsize::new(size)) {
+      (Some(lru), Some(size)) => lru.resize(size),
+      (_, size) => *cache = size.map(LruCache::new),
+    }
+  }
+
+  /// Decrypt `enc_value` with `keys`, using the cached value if available.
+  pub fn decrypt(&self, keys: &ElgamalKeys, enc_value: &CipherText) -> Option<Balance> {
+    self.decrypt_in_range(keys, enc_value, MAX_TOTAL_SUPPLY)
+  }
+
+  /// Decrypt `enc_value` with `keys`, only searching for values up to `max`.
+  ///
+  /// Cached values are returned even when they are above `max`.
+  pub fn decrypt_in_range(
+    &self,
+    keys: &ElgamalKeys,
+    enc_value: &CipherText,
+    max: Balance,
+  ) -> Option<Balance> {
+    let key = (keys.public.encode(), enc_value.encode());
+    if let Some(value) = self.get(&key) {
+      self.hits.fetch_add(1, Ordering::Relaxed);
+      return Some(value);
+    }
+    self.misses.fetch_add(1, Ordering::Relaxed);
+    // Don't hold the lock while searching.
+    let value = DecryptionContext::global().decrypt(keys, enc_value, max)?;
+    if let Some(cache) = self
+      .cache
+      .lock()
+      .expect("Decryption cache poisoned")
+      .as_mut()
+    {
+      cache.put(key, value);
+    }
+    Some(value)
+  }
+
+  fn get(&self, key: &CacheKey) -> Option<Balance> {
+    self
+      .cache
+      .lock()
+      .expect("Decryption cache poisoned")
+      .as_mut()?
+      .get(key)
+      .copied()
+  }
+
+  /// Drop all cached values of an account, i.e. after key rotation.
+  pub fn invalidate_account(&self, public_key: &[u8]) {
+    if let Some(cache) = self
+      .cache
+      .lock()
+      .expect("Decryption cache poisoned")
+      .as_mut()
+    {
+      let keys = cache
+        .iter()
+        .filter(|((account, _), _)| account.as_slice() == public_key)
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+      for key in keys {
+        cache.pop(&key);
+      }
+    }
+  }
+
+  pub fn stats(&self) -> DecryptionCacheStats {
+    let cache = self.cache.lock().expect("Decryption cache poisoned");
+    DecryptionCacheStats {
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      entries: cache.as_ref().map(|cache| cache.len()).unwrap_or_default(),
+      capacity: cache
+        .as_ref()
+        .map(|cache| cache.cap().get())
+        .unwrap_or_default(),
+    }
+  }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::proofs::PublicKey;
+
+/// Request a short-lived decryption token.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateDecryptToken {
+  /// Confidential account the token can decrypt with.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// Only decrypt values of this asset.  Without an asset the token can decrypt any value
+  /// of the account.
+  #[serde(default)]
+  pub asset_id: Option<Uuid>,
+  /// Minutes until the token expires.
+  #[schema(example = 5)]
+  #[serde(default)]
+  pub minutes: Option<u32>,
+}
+
+/// What a decryption token grants.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct DecryptTokenClaims {
+  /// Confidential account the token can decrypt with.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// Only values of this asset, if set.
+  pub asset_id: Option<Uuid>,
+  /// User the token was issued to.
+  #[schema(example = "Alice")]
+  pub user: String,
+  /// Unix timestamp (seconds) when the token expires.
+  #[schema(example = 1700000000)]
+  pub expires_at: i64,
+}
+
+impl DecryptTokenClaims {
+  /// Does the token grant decryption for the account and asset (`None` for any asset).
+  pub fn allows(&self, account: &PublicKey, asset_id: Option<Uuid>) -> bool {
+    if &self.confidential_account != account {
+      return false;
+    }
+    match self.asset_id {
+      Some(token_asset) => asset_id == Some(token_asset),
+      None => true,
+    }
+  }
+}
+
+/// Short-lived decryption token.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct DecryptToken {
+  /// Token for the `X-Decrypt-Token` header.
+  pub token: String,
+  #[serde(flatten)]
+  pub claims: DecryptTokenClaims,
+}
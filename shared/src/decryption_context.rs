@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use actix_web::web::Data;
+use confidential_assets::{elgamal::CipherText, Balance, ElgamalKeys, ElgamalSecretKey, Scalar};
+use curve25519_dalek::{
+  constants::RISTRETTO_BASEPOINT_POINT, ristretto::RistrettoPoint, traits::Identity,
+};
+
+/// Default table size (`2^20` baby steps, about 20 MB).
+const DEFAULT_TABLE_BITS: u32 = 20;
+/// Largest table size.
+const MAX_TABLE_BITS: u32 = 28;
+
+pub type AppDecryptionContext = Data<&'static DecryptionContext>;
+
+static DECRYPTION_CONTEXT: DecryptionContext = DecryptionContext {
+  table: OnceLock::new(),
+  table_decryptions: AtomicU64::new(0),
+  fallback_decryptions: AtomicU64::new(0),
+};
+
+/// Truncated compressed point, as key of the baby-step table.
+fn point_key(point: &RistrettoPoint) -> u64 {
+  let bytes = point.compress().to_bytes();
+  u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"))
+}
+
+/// Precomputed baby steps `j * G` for the first `2^bits` values.
+pub struct BabyStepTable {
+  steps: HashMap<u64, u32>,
+  count: u64,
+  /// `-count * G`.
+  giant_step: RistrettoPoint,
+}
+
+impl BabyStepTable {
+  pub fn new(bits: u32) -> Self {
+    let count = 1u64 << bits;
+    let mut steps = HashMap::with_capacity(count as usize);
+    let mut point = RistrettoPoint::identity();
+    for step in 0..count {
+      steps.entry(point_key(&point)).or_insert(step as u32);
+      point += RISTRETTO_BASEPOINT_POINT;
+    }
+    Self {
+      steps,
+      count,
+      giant_step: -point,
+    }
+  }
+
+  /// Number of baby steps.
+  pub fn size(&self) -> u64 {
+    self.count
+  }
+
+  /// Decrypt `enc_value` with baby-step giant-step, only searching for values up to `max`.
+  pub fn decrypt(
+    &self,
+    keys: &ElgamalKeys,
+    enc_value: &CipherText,
+    max: Balance,
+  ) -> Option<Balance> {
+    // value * G = Y - X / secret_key
+    let mut point = enc_value.y - keys.secret.secret.invert() * enc_value.x;
+    for giant in 0..=(max / self.count) {
+      if let Some(step) = self.steps.get(&point_key(&point)) {
+        let value = giant * self.count + *step as u64;
+        // The keys are truncated, check the match with the secret key.
+        if value <= max && keys.secret.decrypt_with_hint(enc_value, value, value + 1) == Some(value)
+        {
+          return Some(value);
+        }
+      }
+      point += self.giant_step;
+    }
+    None
+  }
+
+  /// Check the table against the brute-force decryption with throwaway keys.
+  fn check(&self) -> bool {
+    let mut rng = rand::thread_rng();
+    let secret = ElgamalSecretKey::new(Scalar::random(&mut rng));
+    let keys = ElgamalKeys {
+      public: secret.get_public_key(),
+      secret,
+    };
+    // Needs a giant step.
+    let value = self.count + 7;
+    let (_, enc_value) = keys.public.encrypt_value(Scalar::from(value), &mut rng);
+    self.decrypt(&keys, &enc_value, value) == Some(value)
+  }
+}
+
+/// Decryption context statistics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecryptionContextStats {
+  /// Is the baby-step table built.
+  pub ready: bool,
+  /// Number of baby steps.
+  pub table_size: u64,
+  /// Values decrypted with the table.
+  pub table_decryptions: u64,
+  /// Values decrypted with a linear search, while the table wasn't available.
+  pub fallback_decryptions: u64,
+}
+
+/// Process-wide baby-step table for decrypting balances.
+///
+/// Decrypting a value is a discrete log search over `0..=MAX_TOTAL_SUPPLY`.  The table is
+/// built once in the background and shared by all decryptions (through the
+/// `DecryptionCache`), which then only need `MAX_TOTAL_SUPPLY / 2^bits` giant steps.  Until
+/// it's built, values are decrypted with the linear `decrypt_with_hint` search.
+pub struct DecryptionContext {
+  table: OnceLock<BabyStepTable>,
+  table_decryptions: AtomicU64,
+  fallback_decryptions: AtomicU64,
+}
+
+impl DecryptionContext {
+  /// The process wide context.
+  pub fn global() -> &'static Self {
+    &DECRYPTION_CONTEXT
+  }
+
+  /// Build the process wide table with `DECRYPTION_TABLE_BITS` bits (default 20, 0 disables
+  /// the table) in the background.
+  pub fn from_env() -> AppDecryptionContext {
+    let bits = std::env::var("DECRYPTION_TABLE_BITS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(DEFAULT_TABLE_BITS)
+      .min(MAX_TABLE_BITS);
+    if bits > 0 {
+      Self::global().build(bits);
+    }
+    Data::new(Self::global())
+  }
+
+  /// Build the table with `2^bits` baby steps in a background thread.
+  pub fn build(&'static self, bits: u32) {
+    if self.table.get().is_some() {
+      return;
+    }
+    std::thread::spawn(move || {
+      let started = Instant::now();
+      let table = BabyStepTable::new(bits);
+      if !table.check() {
+        log::error!("Decryption table check failed, using the linear search");
+        return;
+      }
+      log::info!(
+        "Decryption table with {} baby steps built in {:?}",
+        table.size(),
+        started.elapsed()
+      );
+      let _ = self.table.set(table);
+    });
+  }
+
+  /// The baby-step table, if it's built.
+  pub fn table(&self) -> Option<&BabyStepTable> {
+    self.table.get()
+  }
+
+  /// Decrypt `enc_value` with `keys`, only searching for values up to `max`.
+  pub fn decrypt(
+    &self,
+    keys: &ElgamalKeys,
+    enc_value: &CipherText,
+    max: Balance,
+  ) -> Option<Balance> {
+    match self.table() {
+      Some(table) => {
+        self.table_decryptions.fetch_add(1, Ordering::Relaxed);
+        table.decrypt(keys, enc_value, max)
+      }
+      None => {
+        self.fallback_decryptions.fetch_add(1, Ordering::Relaxed);
+        keys.secret.decrypt_with_hint(enc_value, 0, max)
+      }
+    }
+  }
+
+  pub fn stats(&self) -> DecryptionContextStats {
+    DecryptionContextStats {
+      ready: self.table().is_some(),
+      table_size: self.table().map(|table| table.size()).unwrap_or_default(),
+      table_decryptions: self.table_decryptions.load(Ordering::Relaxed),
+      fallback_decryptions: self.fallback_decryptions.load(Ordering::Relaxed),
+    }
+  }
+}
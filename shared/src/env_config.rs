@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+use std::env::VarError;
+
+/// Config values, read like environment variables.
+///
+/// A snapshot of the environment, so the config can be rebuilt (i.e. from an updated
+/// `.env` file) without changing the process environment.  `std::env::set_var` isn't safe
+/// while other threads read the environment.
+#[derive(Clone, Debug, Default)]
+pub struct EnvConfig {
+  vars: HashMap<String, String>,
+}
+
+impl EnvConfig {
+  /// Snapshot of the process environment.  Values that aren't valid unicode are skipped.
+  pub fn from_env() -> Self {
+    Self {
+      vars: std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect(),
+    }
+  }
+
+  /// Add the values that aren't set yet.  Like `dotenv`, the existing values win.
+  pub fn with_defaults(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+    for (key, value) in vars {
+      self.vars.entry(key).or_insert(value);
+    }
+    self
+  }
+
+  /// Get a value, the same as `std::env::var`.
+  pub fn var(&self, key: &str) -> Result<String, VarError> {
+    self.vars.get(key).cloned().ok_or(VarError::NotPresent)
+  }
+}
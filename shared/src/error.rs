@@ -26,6 +26,10 @@ pub enum Error {
   #[error("Database error: {0}")]
   Database(#[from] sqlx::Error),
 
+  #[error("Database migration error: {0}")]
+  #[cfg(feature = "backend")]
+  Migrate(#[from] sqlx::migrate::MigrateError),
+
   #[error("Reqwest client error: {0}")]
   Reqwest(#[from] reqwest::Error),
 
@@ -41,6 +45,9 @@ pub enum Error {
   #[error("Json error: {0}")]
   Json(#[from] serde_json::Error),
 
+  #[error("IO error: {0}")]
+  Io(#[from] std::io::Error),
+
   #[error("hex error: {0}")]
   Hex(#[from] hex::FromHexError),
 
@@ -48,7 +55,7 @@ pub enum Error {
   Base64Decode(#[from] base64::DecodeError),
 
   #[error("parity-scale-codec error: {0}")]
-  #[cfg(feature = "backend")]
+  #[cfg(any(feature = "backend", feature = "storage_proof"))]
   ParityScaleCodec(#[from] codec::Error),
 
   #[error("sp-core crypto secret error: {0}")]
@@ -59,6 +66,12 @@ pub enum Error {
 
   #[error("{0} not found")]
   NotFound(String),
+
+  #[error("Forbidden: {0}")]
+  Forbidden(String),
+
+  #[error("Conflict: {0}")]
+  Conflict(String),
 }
 
 impl Error {
@@ -69,6 +82,14 @@ impl Error {
   pub fn not_found(msg: &str) -> Self {
     Self::NotFound(msg.to_string())
   }
+
+  pub fn forbidden(msg: &str) -> Self {
+    Self::Forbidden(msg.to_string())
+  }
+
+  pub fn conflict(msg: &str) -> Self {
+    Self::Conflict(msg.to_string())
+  }
 }
 
 #[cfg(feature = "tx_backend")]
@@ -96,6 +117,8 @@ impl ResponseError for Error {
   fn status_code(&self) -> StatusCode {
     match self {
       Self::NotFound(_) => StatusCode::NOT_FOUND,
+      Self::Forbidden(_) => StatusCode::FORBIDDEN,
+      Self::Conflict(_) => StatusCode::CONFLICT,
       _ => StatusCode::INTERNAL_SERVER_ERROR,
     }
   }
@@ -0,0 +1,453 @@
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+
+#[cfg(feature = "backend")]
+use chacha20poly1305::{
+  aead::{Aead, KeyInit, Payload},
+  ChaCha20Poly1305, Key, Nonce,
+};
+#[cfg(feature = "backend")]
+use confidential_assets::ElgamalSecretKey;
+#[cfg(feature = "backend")]
+use rand::RngCore;
+#[cfg(feature = "backend")]
+use zeroize::Zeroizing;
+
+#[cfg(feature = "backend")]
+use codec::{Decode, Encode};
+
+#[cfg(feature = "backend")]
+use crate::error::{Error, Result};
+use crate::PublicKey;
+
+/// Custodian of one escrow share and the key encryption key (KEK) protecting it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct EscrowCustodian {
+  /// Custodian name.  Also used as the share's label (i.e. a Vault path).
+  #[schema(example = "custodian-1")]
+  pub name: String,
+  /// Key encryption key (hex encoded 32 bytes).  Never stored.
+  #[schema(example = "0x0000000000000000000000000000000000000000000000000000000000000000")]
+  pub kek: String,
+}
+
+/// Split an account's secret key into escrow shares.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct EscrowAccountRequest {
+  /// Number of shares needed to reassemble the secret key.
+  #[schema(example = 2)]
+  pub threshold: u8,
+  /// One share is created for each custodian.
+  pub custodians: Vec<EscrowCustodian>,
+}
+
+/// Reassemble an escrowed account's secret key from a quorum of shares.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ReassembleAccountRequest {
+  /// Custodians providing their KEKs.
+  pub custodians: Vec<EscrowCustodian>,
+}
+
+/// Escrow status of an account.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AccountEscrow {
+  /// Confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// Number of shares needed to reassemble the secret key.
+  #[schema(example = 2)]
+  pub threshold: u8,
+  /// Custodians holding a share.
+  #[schema(example = json!(["custodian-1", "custodian-2", "custodian-3"]))]
+  pub custodians: Vec<String>,
+  /// Has the secret key been reassembled (held in memory only).
+  #[schema(example = false)]
+  pub reassembled: bool,
+}
+
+/// Encrypted escrow share.  Not allowed to be serialized.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default)]
+#[cfg(feature = "backend")]
+pub struct EscrowShare {
+  pub custodian: String,
+  pub enc_share: Vec<u8>,
+}
+
+#[cfg(feature = "backend")]
+impl EscrowShare {
+  /// Encrypt `share` with the custodian's KEK.
+  ///
+  /// The share is bound to the account and custodian, so it can't be moved to
+  /// another account or custodian.
+  pub fn seal(account: &[u8], custodian: &EscrowCustodian, share: &[u8]) -> Result<Self> {
+    let cipher = custodian.cipher()?;
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let aad = share_aad(account, &custodian.name);
+    let mut enc_share = nonce.to_vec();
+    enc_share.extend(
+      cipher
+        .encrypt(
+          Nonce::from_slice(&nonce),
+          Payload {
+            msg: share,
+            aad: &aad,
+          },
+        )
+        .map_err(|_| Error::other("Failed to encrypt escrow share"))?,
+    );
+    Ok(Self {
+      custodian: custodian.name.clone(),
+      enc_share,
+    })
+  }
+
+  /// Decrypt the share with the custodian's KEK.
+  pub fn open(&self, account: &[u8], custodian: &EscrowCustodian) -> Result<Zeroizing<Vec<u8>>> {
+    if self.enc_share.len() < 12 {
+      return Err(Error::other("Invalid escrow share"));
+    }
+    let cipher = custodian.cipher()?;
+    let (nonce, enc_share) = self.enc_share.split_at(12);
+    let aad = share_aad(account, &self.custodian);
+    let share = cipher
+      .decrypt(
+        Nonce::from_slice(nonce),
+        Payload {
+          msg: enc_share,
+          aad: &aad,
+        },
+      )
+      .map_err(|_| {
+        Error::forbidden(&format!(
+          "Invalid KEK for escrow custodian: {}",
+          self.custodian
+        ))
+      })?;
+    Ok(Zeroizing::new(share))
+  }
+}
+
+#[cfg(feature = "backend")]
+fn share_aad(account: &[u8], custodian: &str) -> Vec<u8> {
+  let mut aad = account.to_vec();
+  aad.extend_from_slice(custodian.as_bytes());
+  aad
+}
+
+#[cfg(feature = "backend")]
+impl EscrowCustodian {
+  fn cipher(&self) -> Result<ChaCha20Poly1305> {
+    let kek = Zeroizing::new(hex::decode(
+      self.kek.strip_prefix("0x").unwrap_or(&self.kek),
+    )?);
+    if kek.len() != 32 {
+      return Err(Error::other("The KEK must be 32 bytes"));
+    }
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&kek)))
+  }
+}
+
+#[cfg(feature = "backend")]
+impl EscrowAccountRequest {
+  /// Split the account's secret key into encrypted shares, one for each custodian.
+  pub fn split(&self, account: &[u8], secret_key: &[u8]) -> Result<Vec<EscrowShare>> {
+    let count = self.custodians.len();
+    if self.threshold == 0 || self.threshold as usize > count {
+      return Err(Error::other(
+        "The threshold must be between 1 and the number of custodians",
+      ));
+    }
+    if count > 255 {
+      return Err(Error::other("Too many custodians"));
+    }
+    for (idx, custodian) in self.custodians.iter().enumerate() {
+      if self.custodians[..idx]
+        .iter()
+        .any(|other| other.name == custodian.name)
+      {
+        return Err(Error::other(&format!(
+          "Duplicate escrow custodian: {}",
+          custodian.name
+        )));
+      }
+    }
+    shamir_split(secret_key, self.threshold, count as u8)
+      .iter()
+      .zip(&self.custodians)
+      .map(|(share, custodian)| EscrowShare::seal(account, custodian, share))
+      .collect()
+  }
+}
+
+#[cfg(feature = "backend")]
+impl ReassembleAccountRequest {
+  /// Decrypt the custodians' shares and reassemble the account's secret key.
+  ///
+  /// The reassembled key is checked against the account's public key.
+  pub fn reassemble(
+    &self,
+    account: &[u8],
+    threshold: u8,
+    shares: &[EscrowShare],
+  ) -> Result<Zeroizing<Vec<u8>>> {
+    let mut opened = Vec::new();
+    for custodian in &self.custodians {
+      let share = shares
+        .iter()
+        .find(|share| share.custodian == custodian.name)
+        .ok_or_else(|| {
+          Error::not_found(&format!("Escrow share for custodian: {}", custodian.name))
+        })?;
+      if opened.iter().any(|(name, _)| name == &custodian.name) {
+        continue;
+      }
+      opened.push((custodian.name.clone(), share.open(account, custodian)?));
+    }
+    if opened.len() < threshold as usize {
+      return Err(Error::forbidden(&format!(
+        "Need {threshold} escrow shares to reassemble the secret key, got {}",
+        opened.len()
+      )));
+    }
+    let shares = opened
+      .iter()
+      .map(|(_, share)| share.as_slice())
+      .collect::<Vec<_>>();
+    let secret_key = Zeroizing::new(shamir_combine(&shares)?);
+    // Check that the shares reassembled the right key.
+    let secret = ElgamalSecretKey::decode(&mut secret_key.as_slice())
+      .map_err(|_| Error::other("Escrow shares don't match"))?;
+    if secret.get_public_key().encode() != account {
+      return Err(Error::other("Escrow shares don't match"));
+    }
+    Ok(secret_key)
+  }
+}
+
+/// Multiply in GF(2^8) (AES polynomial).
+#[cfg(feature = "backend")]
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+  let mut p = 0u8;
+  for _ in 0..8 {
+    // Branch-free: add `a` if the low bit of `b` is set.
+    p ^= a & 0u8.wrapping_sub(b & 1);
+    let carry = 0u8.wrapping_sub(a >> 7);
+    a = (a << 1) ^ (0x1b & carry);
+    b >>= 1;
+  }
+  p
+}
+
+/// Inverse in GF(2^8): `a^254`.
+#[cfg(feature = "backend")]
+fn gf_inv(a: u8) -> u8 {
+  let mut res = 1u8;
+  let mut base = a;
+  let mut exp = 254u8;
+  while exp > 0 {
+    if exp & 1 == 1 {
+      res = gf_mul(res, base);
+    }
+    base = gf_mul(base, base);
+    exp >>= 1;
+  }
+  res
+}
+
+/// Shamir secret sharing over GF(2^8).  Each share is `[x, y_0, y_1, ...]`.
+#[cfg(feature = "backend")]
+fn shamir_split(secret: &[u8], threshold: u8, count: u8) -> Vec<Zeroizing<Vec<u8>>> {
+  let mut shares: Vec<Zeroizing<Vec<u8>>> = (1..=count)
+    .map(|x| {
+      let mut share = Vec::with_capacity(secret.len() + 1);
+      share.push(x);
+      Zeroizing::new(share)
+    })
+    .collect();
+  let mut coeffs = Zeroizing::new(vec![0u8; threshold as usize]);
+  for byte in secret {
+    coeffs[0] = *byte;
+    rand::thread_rng().fill_bytes(&mut coeffs[1..]);
+    for share in shares.iter_mut() {
+      let x = share[0];
+      // Horner's method.
+      let y = coeffs
+        .iter()
+        .rev()
+        .fold(0u8, |acc, coeff| gf_mul(acc, x) ^ coeff);
+      share.push(y);
+    }
+  }
+  shares
+}
+
+/// Reassemble a secret from Shamir shares (Lagrange interpolation at `x = 0`).
+#[cfg(feature = "backend")]
+fn shamir_combine(shares: &[&[u8]]) -> Result<Vec<u8>> {
+  let len = shares
+    .first()
+    .map(|share| share.len())
+    .ok_or_else(|| Error::other("No escrow shares"))?;
+  if len < 2 || shares.iter().any(|share| share.len() != len) {
+    return Err(Error::other("Invalid escrow share"));
+  }
+  for (idx, share) in shares.iter().enumerate() {
+    if share[0] == 0 || shares[..idx].iter().any(|other| other[0] == share[0]) {
+      return Err(Error::other("Invalid escrow share"));
+    }
+  }
+  let mut secret = vec![0u8; len - 1];
+  for (i, share) in shares.iter().enumerate() {
+    let xi = share[0];
+    let mut basis = 1u8;
+    for (j, other) in shares.iter().enumerate() {
+      if i != j {
+        let xj = other[0];
+        basis = gf_mul(basis, gf_mul(xj, gf_inv(xj ^ xi)));
+      }
+    }
+    for (byte, y) in secret.iter_mut().zip(&share[1..]) {
+      *byte ^= gf_mul(basis, *y);
+    }
+  }
+  Ok(secret)
+}
+
+#[cfg(all(test, feature = "backend"))]
+mod tests {
+  use confidential_assets::Scalar;
+
+  use super::*;
+
+  fn custodian(name: &str, kek: u8) -> EscrowCustodian {
+    EscrowCustodian {
+      name: name.to_string(),
+      kek: hex::encode([kek; 32]),
+    }
+  }
+
+  fn custodians() -> Vec<EscrowCustodian> {
+    vec![
+      custodian("custodian-1", 1),
+      custodian("custodian-2", 2),
+      custodian("custodian-3", 3),
+    ]
+  }
+
+  /// `(account, secret key)` of a new confidential account.
+  fn new_account() -> (Vec<u8>, Vec<u8>) {
+    let secret = ElgamalSecretKey::new(Scalar::random(&mut rand::thread_rng()));
+    (secret.get_public_key().encode(), secret.encode())
+  }
+
+  fn random_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+  }
+
+  #[test]
+  fn gf_identities() {
+    // FIPS-197 example.
+    assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+    for a in 0..=255u8 {
+      assert_eq!(gf_mul(a, 0), 0);
+      assert_eq!(gf_mul(a, 1), a);
+      if a != 0 {
+        assert_eq!(gf_mul(a, gf_inv(a)), 1, "a = {a}");
+      }
+    }
+  }
+
+  #[test]
+  fn shamir_round_trip_with_every_threshold_subset() {
+    let secret = random_secret();
+    let (threshold, count) = (3, 5);
+    let shares = shamir_split(&secret, threshold, count);
+    for subset in 0u32..(1 << count) {
+      if subset.count_ones() != threshold as u32 {
+        continue;
+      }
+      let picked = shares
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| subset & (1 << idx) != 0)
+        .map(|(_, share)| share.as_slice())
+        .collect::<Vec<_>>();
+      assert_eq!(
+        shamir_combine(&picked).unwrap(),
+        secret,
+        "shares {subset:#b}"
+      );
+    }
+  }
+
+  #[test]
+  fn shamir_below_threshold_mismatches() {
+    let secret = random_secret();
+    let shares = shamir_split(&secret, 3, 5);
+    let picked = [shares[0].as_slice(), shares[3].as_slice()];
+    assert_ne!(shamir_combine(&picked).unwrap(), secret);
+    assert!(shamir_combine(&[]).is_err());
+    // Duplicate shares.
+    assert!(shamir_combine(&[shares[0].as_slice(), shares[0].as_slice()]).is_err());
+  }
+
+  #[test]
+  fn open_requires_the_right_kek() {
+    let (account, _) = new_account();
+    let share = EscrowShare::seal(&account, &custodian("custodian-1", 1), b"share").unwrap();
+    assert_eq!(
+      share
+        .open(&account, &custodian("custodian-1", 1))
+        .unwrap()
+        .as_slice(),
+      b"share"
+    );
+    assert!(share.open(&account, &custodian("custodian-1", 9)).is_err());
+  }
+
+  #[test]
+  fn open_is_bound_to_account_and_custodian() {
+    let (account, _) = new_account();
+    let (other_account, _) = new_account();
+    let share = EscrowShare::seal(&account, &custodian("custodian-1", 1), b"share").unwrap();
+    assert!(share
+      .open(&other_account, &custodian("custodian-1", 1))
+      .is_err());
+    // The same share presented as another custodian's (with the same KEK).
+    let moved = EscrowShare {
+      custodian: "custodian-2".to_string(),
+      enc_share: share.enc_share.clone(),
+    };
+    assert!(moved.open(&account, &custodian("custodian-2", 1)).is_err());
+  }
+
+  #[test]
+  fn split_and_reassemble() {
+    let (account, secret_key) = new_account();
+    let req = EscrowAccountRequest {
+      threshold: 2,
+      custodians: custodians(),
+    };
+    let shares = req.split(&account, &secret_key).unwrap();
+    assert_eq!(shares.len(), 3);
+
+    let quorum = ReassembleAccountRequest {
+      custodians: custodians()[1..].to_vec(),
+    };
+    let reassembled = quorum.reassemble(&account, 2, &shares).unwrap();
+    assert_eq!(reassembled.as_slice(), secret_key.as_slice());
+
+    let single = ReassembleAccountRequest {
+      custodians: custodians()[..1].to_vec(),
+    };
+    assert!(single.reassemble(&account, 2, &shares).is_err());
+    // Shares of another account don't reassemble its key.
+    let (other_account, _) = new_account();
+    assert!(quorum.reassemble(&other_account, 2, &shares).is_err());
+  }
+}
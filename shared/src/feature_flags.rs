@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+
+use crate::error::*;
+
+/// Runtime switch of the endpoints matching a pattern.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct FeatureFlag {
+  /// Flag id.
+  #[schema(example = 1)]
+  pub flag_id: i64,
+  /// Endpoint pattern: `METHOD /path`, relative to `/v1`.
+  #[schema(example = "POST /accounts/*/export")]
+  pub endpoint: String,
+  /// Is the endpoint enabled.
+  #[schema(example = false)]
+  pub enabled: bool,
+  /// Explanation returned when the endpoint is disabled.
+  #[schema(example = "Account exports are disabled during the audit")]
+  pub reason: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+impl FeatureFlag {
+  /// Does the flag's pattern match the request.  `path` is the part after `/v1`.
+  pub fn matches(&self, method: &str, path: &[&str]) -> bool {
+    match parse_endpoint(&self.endpoint) {
+      Some((flag_method, pattern)) => {
+        (flag_method == "*" || flag_method.eq_ignore_ascii_case(method))
+          && path_matches(&pattern, path)
+      }
+      None => false,
+    }
+  }
+}
+
+/// Split an endpoint pattern into its method and path segments.
+fn parse_endpoint(endpoint: &str) -> Option<(&str, Vec<&str>)> {
+  let (method, path) = endpoint.trim().split_once(' ')?;
+  let path = path
+    .trim()
+    .trim_start_matches("/v1")
+    .split('/')
+    .filter(|s| !s.is_empty())
+    .collect();
+  Some((method, path))
+}
+
+/// `*` matches one segment and a trailing `**` matches the rest of the path.
+fn path_matches(pattern: &[&str], path: &[&str]) -> bool {
+  match (pattern, path) {
+    (["**"], _) => true,
+    ([], []) => true,
+    ([p, pattern @ ..], [s, path @ ..]) if *p == "*" || p == s => path_matches(pattern, path),
+    _ => false,
+  }
+}
+
+/// Enable or disable the endpoints matching a pattern.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SetFeatureFlag {
+  /// Endpoint pattern: `METHOD /path`, relative to `/v1`.  `*` matches one path segment, a
+  /// trailing `**` matches the rest of the path and the method `*` matches any method.
+  #[schema(example = "POST /accounts/*/export")]
+  pub endpoint: String,
+  /// Enable (`true`) or disable the endpoint.
+  #[schema(example = false)]
+  pub enabled: bool,
+  /// Explanation returned when the endpoint is disabled.
+  #[schema(example = "Account exports are disabled during the audit")]
+  #[serde(default)]
+  pub reason: Option<String>,
+}
+
+impl SetFeatureFlag {
+  pub fn validate(&self) -> Result<()> {
+    match parse_endpoint(&self.endpoint) {
+      Some((method, path)) if !method.is_empty() => {
+        if let Some(idx) = path.iter().position(|s| *s == "**") {
+          if idx + 1 != path.len() {
+            return Err(Error::other("`**` must be the last path segment"));
+          }
+        }
+        Ok(())
+      }
+      _ => Err(Error::Other(format!(
+        "Endpoint must be `METHOD /path`: {}",
+        self.endpoint
+      ))),
+    }
+  }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::{Asset, BalanceHistory, ProofRecord};
+
+/// Optionally include formatted amounts alongside the raw amounts.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct FormatQuery {
+  /// Locale to format amounts in, i.e. `en-US`, `de-DE` or `fr-FR`.  Amounts are scaled by
+  /// the asset's `decimals` (0 if not set).
+  pub locale: Option<String>,
+}
+
+/// Number separators of a locale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AmountLocale {
+  /// Digit group separator.
+  pub group: char,
+  /// Decimal separator.
+  pub decimal: char,
+}
+
+impl AmountLocale {
+  /// Parse a locale (`language[-REGION]`, `_` is also accepted as separator).
+  pub fn parse(locale: &str) -> Result<Self> {
+    let lower = locale.trim().replace('_', "-").to_ascii_lowercase();
+    let mut parts = lower.split('-');
+    let lang = parts.next().unwrap_or_default();
+    let region = parts.last();
+    let (group, decimal) = match (lang, region) {
+      ("de" | "it", Some("ch" | "li")) => ('\u{2019}', '.'),
+      ("en" | "ja" | "zh" | "ko" | "he" | "th", _) => (',', '.'),
+      ("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el", _) => ('.', ','),
+      ("fr", _) => ('\u{202f}', ','),
+      ("ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg", _) => {
+        ('\u{a0}', ',')
+      }
+      _ => Err(Error::Other(format!("Unsupported locale: {locale}")))?,
+    };
+    Ok(Self { group, decimal })
+  }
+}
+
+/// Format a raw amount with `decimals` decimal places.
+pub fn format_amount(amount: u64, decimals: u8, locale: AmountLocale) -> String {
+  let decimals = decimals as usize;
+  let digits = format!("{amount:0>width$}", width = decimals + 1);
+  let (int, frac) = digits.split_at(digits.len() - decimals);
+  let mut formatted = String::with_capacity(digits.len() + int.len() / 3 + 1);
+  for (idx, digit) in int.chars().enumerate() {
+    if idx > 0 && (int.len() - idx) % 3 == 0 {
+      formatted.push(locale.group);
+    }
+    formatted.push(digit);
+  }
+  if !frac.is_empty() {
+    formatted.push(locale.decimal);
+    formatted.push_str(frac);
+  }
+  formatted
+}
+
+/// Formats amounts of assets in a locale, using the assets' decimals.
+#[derive(Clone, Debug)]
+pub struct AmountFormatter {
+  locale: AmountLocale,
+  decimals: BTreeMap<Uuid, u8>,
+}
+
+impl AmountFormatter {
+  pub fn new(locale: &str, assets: &[Asset]) -> Result<Self> {
+    Ok(Self {
+      locale: AmountLocale::parse(locale)?,
+      decimals: assets
+        .iter()
+        .filter_map(|asset| asset.decimals.map(|decimals| (asset.asset_id, decimals)))
+        .collect(),
+    })
+  }
+
+  /// Format an amount of the asset.  Unknown assets have no decimals.
+  pub fn format(&self, asset_id: Option<Uuid>, amount: u64) -> String {
+    let decimals = asset_id
+      .and_then(|asset_id| self.decimals.get(&asset_id))
+      .copied()
+      .unwrap_or_default();
+    format_amount(amount, decimals, self.locale)
+  }
+}
+
+/// Balance history with its formatted balance.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct FormattedBalanceHistory {
+  #[serde(flatten)]
+  pub history: BalanceHistory,
+  /// Formatted balance, if requested.
+  #[schema(example = "1,000.000000")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub formatted_balance: Option<String>,
+}
+
+impl FormattedBalanceHistory {
+  pub fn new(history: BalanceHistory, formatter: Option<&AmountFormatter>) -> Self {
+    let formatted_balance =
+      formatter.map(|formatter| formatter.format(Some(history.asset_id), history.balance as u64));
+    Self {
+      history,
+      formatted_balance,
+    }
+  }
+}
+
+/// Proof history record with its formatted amount.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct FormattedProofRecord {
+  #[serde(flatten)]
+  pub proof: ProofRecord,
+  /// Formatted amount, if requested.
+  #[schema(example = "1,000.000000")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub formatted_amount: Option<String>,
+}
+
+impl FormattedProofRecord {
+  pub fn new(proof: ProofRecord, formatter: Option<&AmountFormatter>) -> Self {
+    let formatted_amount =
+      formatter.map(|formatter| formatter.format(proof.asset_id, proof.amount as u64));
+    Self {
+      proof,
+      formatted_amount,
+    }
+  }
+}
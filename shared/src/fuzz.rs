@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Fuzz the registered routes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct FuzzRequest {
+  /// Only fuzz routes starting with this path, i.e. `/accounts`.  All routes by default.
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub path_prefix: Option<String>,
+  /// Routes, as documented in the OpenAPI spec, that may also be fuzzed with mutating
+  /// methods or are under `/admin/`.  By default only `GET` routes and the proof
+  /// verification routes are fuzzed.
+  #[schema(example = json!(["/accounts/{confidential_account}/send"]))]
+  #[serde(default)]
+  pub allow_routes: Vec<String>,
+}
+
+/// Fuzz request that returned a `5xx` status or failed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct FuzzFailure {
+  /// HTTP method.
+  #[schema(example = "POST")]
+  pub method: String,
+  /// Route, as documented in the OpenAPI spec.
+  #[schema(example = "/accounts/{confidential_account}/send")]
+  pub path: String,
+  /// Boundary input: `zero`, `max_u64` or `oversized_hex`.
+  #[schema(example = "max_u64")]
+  pub input: String,
+  /// Response status, or `null` if the request failed (i.e. the connection was dropped).
+  #[schema(example = 500)]
+  pub status: Option<u16>,
+  /// Why the request failed.
+  #[schema(example = json!(null))]
+  pub error: Option<String>,
+  /// Start of the response body.
+  #[schema(example = "Internal server error")]
+  pub body: String,
+}
+
+/// Fuzz report.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct FuzzReport {
+  /// Number of fuzzed routes.
+  #[schema(example = 100)]
+  pub routes: u32,
+  /// Number of requests sent.
+  #[schema(example = 300)]
+  pub requests: u32,
+  /// Number of routes skipped because they weren't in `allow_routes`.
+  #[schema(example = 40)]
+  pub skipped: u32,
+  /// Total duration in milliseconds.
+  #[schema(example = 1520.5)]
+  pub duration_ms: f64,
+  /// Requests that returned a `5xx` status or failed.
+  pub failures: Vec<FuzzFailure>,
+}
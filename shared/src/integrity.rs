@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+
+#[cfg(feature = "backend")]
+use codec::{Decode, Encode};
+#[cfg(feature = "backend")]
+use confidential_assets::{ElgamalPublicKey, ElgamalSecretKey, Scalar};
+#[cfg(feature = "backend")]
+use rand::Rng;
+
+#[cfg(feature = "backend")]
+use crate::AccountWithSecret;
+
+/// Largest canary value, small enough to decrypt quickly.
+#[cfg(feature = "backend")]
+const MAX_CANARY_VALUE: u64 = 1000;
+
+/// Problem with a stored account secret.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretProblem {
+  /// The stored public key doesn't decode.
+  InvalidPublicKey,
+  /// The stored secret key doesn't decode.
+  InvalidSecretKey,
+  /// The secret key doesn't belong to the public key.
+  KeyMismatch,
+  /// A value encrypted with the public key didn't decrypt with the secret key.
+  CanaryFailed,
+  /// The secret key can't be loaded (i.e. it doesn't decrypt or is missing from the key
+  /// store).
+  Unreadable,
+}
+
+/// Account with a corrupt secret.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CorruptAccount {
+  /// Confidential account (Elgamal public key).
+  #[schema(example = "0xdeadbeef00000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub confidential_account: Vec<u8>,
+  /// What is wrong with the secret.
+  pub problem: SecretProblem,
+}
+
+/// Result of checking the stored account secrets.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct SecretIntegrityReport {
+  /// Number of checked accounts.
+  #[schema(example = 10)]
+  pub checked: u64,
+  /// Number of skipped accounts, their secret key is in escrow.
+  #[schema(example = 0)]
+  pub escrowed: u64,
+  /// Accounts with a corrupt secret.
+  pub corrupt: Vec<CorruptAccount>,
+  /// Were canary values encrypted and decrypted.
+  #[schema(example = false)]
+  pub canary: bool,
+
+  pub started_at: Option<chrono::NaiveDateTime>,
+  pub completed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[cfg(feature = "backend")]
+impl AccountWithSecret {
+  /// Check that the secret key decodes and belongs to the public key.
+  ///
+  /// With `canary` also check that a random value encrypted with the public key
+  /// decrypts with the secret key.
+  pub fn check_integrity(&self, canary: bool) -> Option<SecretProblem> {
+    let public = match ElgamalPublicKey::decode(&mut self.confidential_account.as_slice()) {
+      Ok(public) => public,
+      Err(_) => return Some(SecretProblem::InvalidPublicKey),
+    };
+    let secret = match ElgamalSecretKey::decode(&mut self.secret_key.as_slice()) {
+      Ok(secret) => secret,
+      Err(_) => return Some(SecretProblem::InvalidSecretKey),
+    };
+    if secret.get_public_key().encode() != self.confidential_account {
+      return Some(SecretProblem::KeyMismatch);
+    }
+    if canary {
+      let mut rng = rand::thread_rng();
+      let value = rng.gen_range(0..=MAX_CANARY_VALUE);
+      let (_, enc_value) = public.encrypt_value(Scalar::from(value), &mut rng);
+      // Don't use the decryption cache, the value must be decrypted with the stored key.
+      if secret.decrypt_with_hint(&enc_value, 0, MAX_CANARY_VALUE) != Some(value) {
+        return Some(SecretProblem::CanaryFailed);
+      }
+    }
+    None
+  }
+}
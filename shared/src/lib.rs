@@ -19,9 +19,118 @@ mod tx;
 #[cfg(feature = "tx_api")]
 pub use tx::*;
 
+#[cfg(feature = "tx_api")]
+mod raw_events;
+#[cfg(feature = "tx_api")]
+pub use raw_events::*;
+
+#[cfg(feature = "tx_api")]
+mod wallet_payload;
+#[cfg(feature = "tx_api")]
+pub use wallet_payload::*;
+
+#[cfg(feature = "tx_api")]
+mod offline_tx;
+#[cfg(feature = "tx_api")]
+pub use offline_tx::*;
+
+#[cfg(feature = "tx_api")]
+mod wallet_pairing;
+#[cfg(feature = "tx_api")]
+pub use wallet_pairing::*;
+
 mod proofs;
 pub use proofs::*;
 
+mod escrow;
+pub use escrow::*;
+
+mod proof_stats;
+pub use proof_stats::*;
+
+mod integrity;
+pub use integrity::*;
+
+mod approvals;
+pub use approvals::*;
+
+mod api_keys;
+pub use api_keys::*;
+
+mod decrypt_tokens;
+pub use decrypt_tokens::*;
+
+mod limits;
+pub use limits::*;
+
+mod screening;
+pub use screening::*;
+
+mod proof_pool;
+pub use proof_pool::*;
+
+mod valuation;
+pub use valuation::*;
+
+mod anomalies;
+pub use anomalies::*;
+
+mod proof_history;
+pub use proof_history::*;
+
+mod account_export;
+pub use account_export::*;
+
+mod position_locks;
+pub use position_locks::*;
+
+mod balance_conflicts;
+pub use balance_conflicts::*;
+
+mod env_config;
+pub use env_config::*;
+
+mod feature_flags;
+pub use feature_flags::*;
+
+mod verifiable_decryption;
+pub use verifiable_decryption::*;
+
+mod selftest;
+pub use selftest::*;
+
+mod warnings;
+pub use warnings::*;
+
+mod formatting;
+pub use formatting::*;
+
+mod fuzz;
+pub use fuzz::*;
+
+#[cfg(feature = "backend")]
+mod decrypt_cache;
+#[cfg(feature = "backend")]
+pub use decrypt_cache::*;
+
+#[cfg(feature = "backend")]
+mod decryption_context;
+#[cfg(feature = "backend")]
+pub use decryption_context::*;
+
+#[cfg(feature = "backend")]
+mod proving;
+#[cfg(feature = "backend")]
+pub use proving::*;
+
+#[cfg(feature = "backend")]
+pub mod schema;
+
+#[cfg(feature = "storage_proof")]
+mod storage_proof;
+#[cfg(feature = "storage_proof")]
+pub use storage_proof::*;
+
 #[cfg(feature = "tx_backend")]
 use polymesh_api::client::basic_types::AccountId;
 
@@ -59,6 +168,183 @@ impl SignerWithSecret {
   }
 }
 
+/// Signer spending budget.
+#[cfg_attr(feature = "tx_backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SignerBudget {
+  /// Signer's public key.
+  #[schema(example = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")]
+  pub public_key: String,
+  /// Maximum number of extrinsics the signer can submit per day (UTC).
+  #[schema(example = 100)]
+  pub max_daily_extrinsics: Option<i64>,
+  /// Maximum cumulative fees the signer can pay.
+  #[schema(example = 10000000)]
+  pub max_total_fees: Option<i64>,
+  /// Send an alert webhook when this percentage of a limit has been used.
+  #[schema(example = 80)]
+  pub alert_percent: i64,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+#[cfg(feature = "tx_backend")]
+impl SignerBudget {
+  /// Returns an error if the signer has used up any of its limits.
+  pub fn check_usage(&self, usage: &SignerUsage) -> Result<()> {
+    if let Some(max) = self.max_daily_extrinsics {
+      if usage.daily_extrinsics >= max {
+        return Err(Error::forbidden("Signer's daily extrinsic limit reached"));
+      }
+    }
+    if let Some(max) = self.max_total_fees {
+      if usage.total_fees >= max {
+        return Err(Error::forbidden("Signer's fee budget used up"));
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns true if `used` just crossed the alert threshold of `max`.
+  pub fn crossed_alert(&self, max: i64, prev_used: i64, used: i64) -> bool {
+    // In `i128` so large fee budgets don't overflow.
+    let threshold = i128::from(max) * i128::from(self.alert_percent) / 100;
+    let threshold = i64::try_from(threshold).unwrap_or(i64::MAX);
+    prev_used < threshold && used >= threshold
+  }
+}
+
+/// Set a signer's spending budget.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SetSignerBudget {
+  /// Maximum number of extrinsics the signer can submit per day (UTC).
+  #[schema(example = 100)]
+  #[serde(default)]
+  pub max_daily_extrinsics: Option<u32>,
+  /// Maximum cumulative fees the signer can pay.
+  #[schema(example = 10000000)]
+  #[serde(default)]
+  pub max_total_fees: Option<u64>,
+  /// Send an alert webhook when this percentage of a limit has been used.
+  #[schema(example = 80)]
+  #[serde(default = "default_alert_percent")]
+  pub alert_percent: u32,
+}
+
+fn default_alert_percent() -> u32 {
+  80
+}
+
+/// Signer usage.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SignerUsage {
+  /// Number of extrinsics submitted today (UTC).
+  #[schema(example = 10)]
+  pub daily_extrinsics: i64,
+  /// Cumulative fees paid.
+  #[schema(example = 100000)]
+  pub total_fees: i64,
+}
+
+/// Extrinsics (`confidential_asset` calls) a session signer can be allowed to submit.
+pub const SESSION_SIGNER_EXTRINSICS: &[&str] = &[
+  "create_account",
+  "apply_incoming_balance",
+  "affirm_transactions",
+  "unaffirm_transactions",
+  "add_transaction",
+  "execute_transaction",
+  "reject_transaction",
+  "create_venue",
+  "create_asset",
+  "allow_venues",
+  "mint",
+  "burn",
+];
+
+/// Maximum minutes a session signer can be valid for (7 days).
+pub const MAX_SESSION_SIGNER_MINUTES: u32 = 7 * 24 * 60;
+
+/// Time-boxed session signer.  It signs with its parent signer's key, but can only submit
+/// its extrinsics.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SessionSigner {
+  /// Session signer name.  Use it as the `signer` of transaction requests.
+  #[schema(example = "session-9b2a6c1e5f0d4e7a8b3c2d1e0f9a8b7c")]
+  pub name: String,
+  /// Parent signer.
+  #[schema(example = "Alice")]
+  pub signer: String,
+  /// Extrinsics the session signer can submit.
+  #[schema(example = json!(["affirm_transactions"]))]
+  pub extrinsics: Vec<String>,
+
+  pub expires_at: chrono::NaiveDateTime,
+  pub created_at: chrono::NaiveDateTime,
+}
+
+#[cfg(feature = "tx_backend")]
+impl SessionSigner {
+  pub fn from_row(row: SessionSignerRow) -> Result<Self> {
+    Ok(Self {
+      name: row.name,
+      signer: row.signer,
+      extrinsics: serde_json::from_str(&row.extrinsics)?,
+      expires_at: row.expires_at,
+      created_at: row.created_at,
+    })
+  }
+
+  /// Can the session signer submit the extrinsic.
+  pub fn allows(&self, extrinsic: &str) -> bool {
+    self.extrinsics.iter().any(|allowed| allowed == extrinsic)
+  }
+}
+
+/// Session signer row, with the extrinsics as a JSON array.
+#[cfg(feature = "tx_backend")]
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct SessionSignerRow {
+  pub name: String,
+  pub signer: String,
+  pub extrinsics: String,
+  pub expires_at: chrono::NaiveDateTime,
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Create a session signer.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateSessionSigner {
+  /// Extrinsics the session signer can submit: `create_account`, `apply_incoming_balance`,
+  /// `affirm_transactions`, `unaffirm_transactions`, `add_transaction`, `execute_transaction`,
+  /// `reject_transaction`, `create_venue`, `create_asset`, `allow_venues`, `mint` or `burn`.
+  #[schema(example = json!(["affirm_transactions"]))]
+  pub extrinsics: Vec<String>,
+  /// Minutes until the session signer expires.
+  #[schema(example = 60)]
+  pub minutes: u32,
+}
+
+impl CreateSessionSigner {
+  pub fn validate(&self) -> Result<()> {
+    if self.extrinsics.is_empty() {
+      return Err(Error::other("Session signer needs at least one extrinsic"));
+    }
+    for extrinsic in &self.extrinsics {
+      if !SESSION_SIGNER_EXTRINSICS.contains(&extrinsic.as_str()) {
+        return Err(Error::Other(format!("Unknown extrinsic: {extrinsic}")));
+      }
+    }
+    if self.minutes == 0 || self.minutes > MAX_SESSION_SIGNER_MINUTES {
+      return Err(Error::Other(format!(
+        "Session signer minutes must be between 1 and {MAX_SESSION_SIGNER_MINUTES}"
+      )));
+    }
+    Ok(())
+  }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema, Zeroize, ZeroizeOnDrop)]
 #[cfg(feature = "tx_api")]
 pub struct CreateSigner {
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Amount limits of a confidential account.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AmountLimit {
+  /// Account id.
+  #[serde(skip)]
+  pub account_id: i64,
+  /// Asset id.  The limits apply to all assets of the account if not set.
+  #[schema(example = json!(null))]
+  pub asset_id: Option<Uuid>,
+  /// Maximum amount of a single transfer.
+  #[schema(example = 1000000)]
+  pub max_transfer: Option<i64>,
+  /// Maximum cumulative amount sent per day (UTC).
+  #[schema(example = 10000000)]
+  pub max_daily: Option<i64>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+impl AmountLimit {
+  /// Returns the reason if sending `amount` breaks a limit, when `daily_used` has already
+  /// been sent today.
+  pub fn check(&self, amount: u64, daily_used: u64) -> Option<String> {
+    if let Some(max) = self.max_transfer {
+      if amount > max as u64 {
+        return Some(format!("amount {amount} is over the transfer limit {max}"));
+      }
+    }
+    if let Some(max) = self.max_daily {
+      if daily_used.saturating_add(amount) > max as u64 {
+        return Some(format!(
+          "amount {amount} is over the daily limit {max} ({daily_used} already sent today)"
+        ));
+      }
+    }
+    None
+  }
+}
+
+/// Set the amount limits of a confidential account.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SetAmountLimit {
+  /// Asset id.  The limits apply to all assets of the account if not set.
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub asset_id: Option<Uuid>,
+  /// Maximum amount of a single transfer.
+  #[schema(example = 1000000)]
+  #[serde(default)]
+  pub max_transfer: Option<u64>,
+  /// Maximum cumulative amount sent per day (UTC).
+  #[schema(example = 10000000)]
+  #[serde(default)]
+  pub max_daily: Option<u64>,
+}
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use polymesh_api::client::basic_types::IdentityId;
+
+use crate::error::Result;
+use crate::proofs::PublicKey;
+
+/// Extrinsic to build for an offline signer.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "extrinsic", rename_all = "snake_case")]
+pub enum OfflineCall {
+  /// Create a confidential venue.
+  CreateVenue,
+  /// Create a confidential asset.
+  CreateAsset {
+    /// List of mediators identities.
+    #[schema(example = json!([]))]
+    #[serde(default)]
+    mediators: Vec<IdentityId>,
+    /// List of auditor Elgamal public key.
+    #[schema(example = json!(["0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114"]))]
+    #[serde(default)]
+    auditors: Vec<PublicKey>,
+  },
+  /// Allow venues to create settlements for an asset.
+  AllowVenues {
+    /// Asset id.
+    asset_id: Uuid,
+    /// Venues to allow.
+    #[schema(example = json!([1]))]
+    venues: Vec<u64>,
+  },
+  /// Initialize a confidential account on-chain.
+  CreateAccount {
+    /// Confidential account.
+    #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+    confidential_account: PublicKey,
+  },
+  /// Apply an incoming balance to a confidential account.
+  ApplyIncomingBalance {
+    /// Confidential account.
+    #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+    confidential_account: PublicKey,
+    /// Asset id.
+    asset_id: Uuid,
+  },
+  /// Mint assets to a confidential account.
+  Mint {
+    /// Confidential account receiving the minted assets.
+    #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+    confidential_account: PublicKey,
+    /// Asset id.
+    asset_id: Uuid,
+    /// Amount to mint.
+    #[schema(example = 1000)]
+    amount: u64,
+  },
+  /// Execute a confidential settlement.
+  ExecuteTransaction {
+    /// Settlement id.
+    #[schema(example = 1)]
+    transaction_id: u64,
+    /// Number of legs in the settlement.
+    #[schema(example = 1)]
+    leg_count: u32,
+  },
+}
+
+impl OfflineCall {
+  /// Extrinsic name.
+  pub fn extrinsic(&self) -> &'static str {
+    match self {
+      Self::CreateVenue => "create_venue",
+      Self::CreateAsset { .. } => "create_asset",
+      Self::AllowVenues { .. } => "allow_venues",
+      Self::CreateAccount { .. } => "create_account",
+      Self::ApplyIncomingBalance { .. } => "apply_incoming_balance",
+      Self::Mint { .. } => "mint",
+      Self::ExecuteTransaction { .. } => "execute_transaction",
+    }
+  }
+
+  /// Human-readable summary, shown to the signer before approving the transaction.
+  pub fn summary(&self) -> String {
+    let key = |key: &PublicKey| format!("0x{}", hex::encode(key.0));
+    match self {
+      Self::CreateVenue => "Create a confidential venue".to_string(),
+      Self::CreateAsset {
+        mediators,
+        auditors,
+      } => format!(
+        "Create a confidential asset with {} mediator(s) and {} auditor(s)",
+        mediators.len(),
+        auditors.len()
+      ),
+      Self::AllowVenues { asset_id, venues } => {
+        format!("Allow venues {venues:?} to create settlements for asset {asset_id}")
+      }
+      Self::CreateAccount {
+        confidential_account,
+      } => format!(
+        "Initialize confidential account {}",
+        key(confidential_account)
+      ),
+      Self::ApplyIncomingBalance {
+        confidential_account,
+        asset_id,
+      } => format!(
+        "Apply the incoming balance of asset {asset_id} to confidential account {}",
+        key(confidential_account)
+      ),
+      Self::Mint {
+        confidential_account,
+        asset_id,
+        amount,
+      } => format!(
+        "Mint {amount} of asset {asset_id} to confidential account {}",
+        key(confidential_account)
+      ),
+      Self::ExecuteTransaction {
+        transaction_id,
+        leg_count,
+      } => format!("Execute settlement {transaction_id} ({leg_count} leg(s))"),
+    }
+  }
+}
+
+/// Build an unsigned transaction for an offline signer.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct BuildTransaction {
+  /// Account (SS58 address) that will sign the transaction.
+  #[schema(example = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")]
+  pub account: String,
+  /// Extrinsic and its arguments.
+  #[serde(flatten)]
+  pub call: OfflineCall,
+}
+
+/// Unsigned transaction waiting for a signature from an offline signer.
+///
+/// Sign `payload` with the account's key and submit the signature with
+/// `/tx/submit_signed`.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct UnsignedTransaction {
+  /// Build id.
+  #[schema(example = 1)]
+  pub build_id: i64,
+  /// Account (SS58 address) that needs to sign the transaction.
+  #[schema(example = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")]
+  pub account: String,
+  /// Extrinsic and its arguments.
+  #[serde(flatten)]
+  pub call: OfflineCall,
+  /// Account nonce the transaction was built with.
+  #[schema(example = 0)]
+  pub nonce: u32,
+  /// SCALE encoded signing payload.
+  #[schema(value_type = String, format = Binary, example = "0x2f0000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub payload: Vec<u8>,
+  /// Transaction hash, once submitted.
+  #[schema(example = json!(null))]
+  pub tx_hash: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub submitted_at: Option<chrono::NaiveDateTime>,
+}
+
+impl UnsignedTransaction {
+  pub fn from_row(row: UnsignedTransactionRow) -> Result<Self> {
+    Ok(Self {
+      build_id: row.build_id,
+      account: row.account,
+      call: serde_json::from_str(&row.call)?,
+      nonce: row.nonce as u32,
+      payload: row.payload,
+      tx_hash: row.tx_hash,
+      created_at: row.created_at,
+      submitted_at: row.submitted_at,
+    })
+  }
+}
+
+/// Unsigned transaction row, with the call as JSON.
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct UnsignedTransactionRow {
+  pub build_id: i64,
+  pub account: String,
+  pub call: String,
+  pub nonce: i64,
+  pub payload: Vec<u8>,
+  pub tx_hash: Option<String>,
+  pub created_at: chrono::NaiveDateTime,
+  pub submitted_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Submit a transaction signed by an offline signer.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SubmitSignedTransaction {
+  /// Build id from `/tx/build`.
+  #[schema(example = 1)]
+  pub build_id: i64,
+  /// SCALE encoded `MultiSignature` of the signing payload (i.e. `0x01` followed by the
+  /// 64 byte sr25519 signature).
+  #[schema(value_type = String, format = Binary, example = "0x01d4e2a5")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub signature: Vec<u8>,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+}
@@ -0,0 +1,120 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::*;
+
+/// What happens to sender proof requests during a position lock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionLockMode {
+  /// Fail right away.
+  #[default]
+  Reject,
+  /// Wait until the lock window ends, then generate the proof.
+  Defer,
+}
+
+impl PositionLockMode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Reject => "reject",
+      Self::Defer => "defer",
+    }
+  }
+}
+
+impl FromStr for PositionLockMode {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "reject" => Ok(Self::Reject),
+      "defer" => Ok(Self::Defer),
+      _ => Err(Error::Other(format!("Unknown position lock mode: {s}"))),
+    }
+  }
+}
+
+/// Time window during which no sender proofs are issued for an account asset
+/// (e.g. during a NAV strike).
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PositionLock {
+  /// Position lock id.
+  #[schema(example = 1)]
+  pub lock_id: i64,
+  /// Account id.
+  #[serde(skip)]
+  pub account_id: i64,
+  /// Locked confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub confidential_account: Vec<u8>,
+  /// Locked asset.  All of the account's assets if not set.
+  #[schema(example = json!(null))]
+  pub asset_id: Option<Uuid>,
+  /// Mode: `reject` or `defer`.
+  #[schema(example = "reject")]
+  pub mode: String,
+  /// Why the position is locked.
+  #[schema(example = "NAV strike")]
+  pub reason: Option<String>,
+
+  /// Start of the lock window (UTC).
+  pub starts_at: chrono::NaiveDateTime,
+  /// End of the lock window (UTC).
+  pub ends_at: chrono::NaiveDateTime,
+  pub created_at: chrono::NaiveDateTime,
+}
+
+impl PositionLock {
+  pub fn mode(&self) -> Result<PositionLockMode> {
+    PositionLockMode::from_str(&self.mode)
+  }
+}
+
+/// Lock an account asset (or all of the account's assets) for a time window.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreatePositionLock {
+  /// Asset to lock.  All of the account's assets if not set.
+  #[serde(default)]
+  pub asset_id: Option<Uuid>,
+  /// Start of the lock window (UTC).  Defaults to now.
+  #[schema(example = "2024-05-10T16:00:00")]
+  #[serde(default)]
+  pub starts_at: Option<chrono::NaiveDateTime>,
+  /// End of the lock window (UTC).
+  #[schema(example = "2024-05-10T17:00:00")]
+  pub ends_at: chrono::NaiveDateTime,
+  /// What happens to sender proof requests during the window (default: `reject`).
+  #[serde(default)]
+  pub mode: PositionLockMode,
+  /// Why the position is locked.
+  #[schema(example = "NAV strike")]
+  #[serde(default)]
+  pub reason: Option<String>,
+}
+
+impl CreatePositionLock {
+  /// Start of the lock window, now if not set.
+  pub fn starts_at(&self) -> chrono::NaiveDateTime {
+    self
+      .starts_at
+      .unwrap_or_else(|| chrono::Utc::now().naive_utc())
+  }
+
+  pub fn validate(&self) -> Result<()> {
+    if self.ends_at <= self.starts_at() {
+      return Err(Error::other("The lock window must end after it starts"));
+    }
+    if self.ends_at <= chrono::Utc::now().naive_utc() {
+      return Err(Error::other("The lock window has already ended"));
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[cfg(feature = "backend")]
+use codec::Encode;
+
+#[cfg(feature = "backend")]
+use confidential_assets::{Balance, ElgamalPublicKey};
+
+/// Proof types.
+pub const PROOF_TYPE_SENDER: &str = "sender";
+pub const PROOF_TYPE_BURN: &str = "burn";
+
+/// Previously generated sender or burn proof.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ProofRecord {
+  /// Proof id.
+  #[schema(example = 1)]
+  pub proof_id: i64,
+  /// Account id.
+  #[serde(skip)]
+  pub account_id: i64,
+  /// Confidential account that generated the proof.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub confidential_account: Vec<u8>,
+  /// Asset id, if the proof was generated for a tracked account asset.
+  #[schema(example = json!(null))]
+  pub asset_id: Option<Uuid>,
+  /// Proof type: `sender` or `burn`.
+  #[schema(example = "sender")]
+  pub proof_type: String,
+  /// Amount sent or burned.
+  #[schema(example = 1000)]
+  pub amount: i64,
+  /// Receiver's confidential account (`0x` prefixed hex).  Only for sender proofs.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub receiver: Option<String>,
+  /// Sender/burn proof.
+  #[schema(example = "<Hex encoded proof>")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub proof: Vec<u8>,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Add a generated proof to the proof history.
+#[derive(Clone, Debug, Default)]
+pub struct AddProof {
+  pub account_id: i64,
+  pub asset_id: Option<Uuid>,
+  pub proof_type: String,
+  pub amount: i64,
+  pub receiver: Option<String>,
+  pub proof: Vec<u8>,
+}
+
+#[cfg(feature = "backend")]
+impl AddProof {
+  pub fn sender(
+    account_id: i64,
+    asset_id: Option<Uuid>,
+    receiver: &ElgamalPublicKey,
+    amount: Balance,
+    proof: Vec<u8>,
+  ) -> Self {
+    Self {
+      account_id,
+      asset_id,
+      proof_type: PROOF_TYPE_SENDER.to_string(),
+      amount: amount as i64,
+      receiver: Some(format!("0x{}", hex::encode(receiver.encode()))),
+      proof,
+    }
+  }
+
+  pub fn burn(account_id: i64, asset_id: Option<Uuid>, amount: Balance, proof: Vec<u8>) -> Self {
+    Self {
+      account_id,
+      asset_id,
+      proof_type: PROOF_TYPE_BURN.to_string(),
+      amount: amount as i64,
+      receiver: None,
+      proof,
+    }
+  }
+}
@@ -0,0 +1,252 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[cfg(feature = "backend")]
+use std::collections::BTreeSet;
+
+#[cfg(feature = "backend")]
+use codec::{Decode, Encode};
+
+#[cfg(feature = "backend")]
+use confidential_assets::{elgamal::CipherText, Balance, ElgamalPublicKey};
+
+use crate::error::*;
+use crate::PublicKey;
+#[cfg(feature = "backend")]
+use crate::{AccountAsset, BalanceAction, BalanceSource, UpdateAccountAsset};
+
+/// Status of a sender proof pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofPoolStatus {
+  /// Proofs are being generated.  Generated proofs can already be used.
+  #[default]
+  Generating,
+  /// All proofs have been generated.
+  Ready,
+  /// All proofs have been used.
+  Exhausted,
+  /// Released by an operator.  Unused proofs were dropped.
+  Released,
+  /// Expired.  Unused proofs were dropped.
+  Expired,
+  /// Generating the proofs failed.  Unused proofs were dropped.
+  Failed,
+}
+
+impl ProofPoolStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Generating => "generating",
+      Self::Ready => "ready",
+      Self::Exhausted => "exhausted",
+      Self::Released => "released",
+      Self::Expired => "expired",
+      Self::Failed => "failed",
+    }
+  }
+
+  /// Active pools reserve the balance of their unused proofs.
+  pub fn is_active(&self) -> bool {
+    matches!(self, Self::Generating | Self::Ready)
+  }
+}
+
+impl FromStr for ProofPoolStatus {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "generating" => Ok(Self::Generating),
+      "ready" => Ok(Self::Ready),
+      "exhausted" => Ok(Self::Exhausted),
+      "released" => Ok(Self::Released),
+      "expired" => Ok(Self::Expired),
+      "failed" => Ok(Self::Failed),
+      _ => Err(Error::Other(format!("Unknown proof pool status: {s}"))),
+    }
+  }
+}
+
+/// Pool of pre-generated sender proofs of one account asset.
+///
+/// All proofs in the pool are for the same receiver, auditors and amount.  Each proof is
+/// generated from the balance left by the previous one, so they must be used in order.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ProofPool {
+  /// Proof pool id.
+  #[schema(example = 1)]
+  pub pool_id: i64,
+  /// Account asset id.
+  #[serde(skip)]
+  pub account_asset_id: i64,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Receiver's confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub receiver: String,
+  /// Auditors (comma separated).
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub auditors: String,
+  /// Amount (denomination) of each proof.
+  #[schema(example = 1000)]
+  pub amount: i64,
+  /// Number of proofs requested.
+  #[schema(example = 10)]
+  pub size: i64,
+  /// Number of proofs generated.
+  #[schema(example = 10)]
+  pub generated: i64,
+  /// Number of proofs used.
+  #[schema(example = 2)]
+  pub used: i64,
+  /// Status: `generating`, `ready`, `exhausted`, `released`, `expired` or `failed`.
+  #[schema(example = "ready")]
+  pub status: String,
+  /// Why generating the proofs failed.
+  #[schema(example = json!(null))]
+  pub error: Option<String>,
+
+  pub expires_at: chrono::NaiveDateTime,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+#[cfg(feature = "backend")]
+impl ProofPool {
+  pub fn status(&self) -> Result<ProofPoolStatus> {
+    ProofPoolStatus::from_str(&self.status)
+  }
+
+  pub fn is_active(&self) -> bool {
+    self
+      .status()
+      .map(|status| status.is_active())
+      .unwrap_or(false)
+  }
+
+  /// Returns true if the pool's proofs can be used for this transfer.
+  pub fn matches(
+    &self,
+    receiver: &ElgamalPublicKey,
+    auditors: &BTreeSet<ElgamalPublicKey>,
+    amount: Balance,
+  ) -> bool {
+    self.amount as Balance == amount
+      && self.receiver == key_to_hex(receiver)
+      && self.auditors == auditors_to_hex(auditors.iter())
+  }
+
+  /// Add the amounts of unused `proofs` back to the account asset's balance.
+  pub fn release_update(
+    &self,
+    account_asset: &AccountAsset,
+    proofs: &[PooledProof],
+  ) -> Result<UpdateAccountAsset> {
+    let mut enc_balance = account_asset.enc_balance()?;
+    for proof in proofs {
+      enc_balance = enc_balance + proof.enc_amount()?;
+    }
+    Ok(UpdateAccountAsset {
+      account_asset_id: Some(account_asset.account_asset_id),
+      account_id: account_asset.account_id,
+      asset_id: account_asset.asset_id,
+      balance: account_asset.balance as u64 + self.amount as u64 * proofs.len() as u64,
+      enc_balance,
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: Some(account_asset.enc_balance.clone()),
+      action: BalanceAction::ProofPoolRelease,
+      amount: Some(self.amount as i64 * proofs.len() as i64),
+      history_source: None,
+    })
+  }
+}
+
+/// Pre-generated sender proof.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default)]
+pub struct PooledProof {
+  pub pool_proof_id: i64,
+  pub pool_id: i64,
+  /// Order the proofs must be used in.
+  pub seq: i64,
+  pub proof: Vec<u8>,
+  /// Encrypted balance the proof was generated from.
+  pub enc_balance: Vec<u8>,
+  /// Encrypted amount the proof subtracts from the sender's balance.
+  pub enc_amount: Vec<u8>,
+  /// Status: `available`, `reserved`, `used` or `released`.
+  pub status: String,
+}
+
+#[cfg(feature = "backend")]
+impl PooledProof {
+  pub fn enc_amount(&self) -> Result<CipherText> {
+    Ok(CipherText::decode(&mut self.enc_amount.as_slice())?)
+  }
+}
+
+/// Pre-generate a pool of sender proofs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateProofPool {
+  /// Receiver's confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub receiver: PublicKey,
+  /// List of auditors.
+  #[schema(example = json!(["0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114"]))]
+  #[serde(default)]
+  pub auditors: Vec<PublicKey>,
+  /// Amount (denomination) of each proof.
+  #[schema(example = 1000)]
+  pub amount: u64,
+  /// Number of proofs to generate.
+  #[schema(example = 10)]
+  pub size: u32,
+  /// Seconds until unused proofs expire (default: 3600).
+  #[schema(example = 3600)]
+  #[serde(default)]
+  pub expires_in: Option<u64>,
+}
+
+#[cfg(feature = "backend")]
+impl CreateProofPool {
+  pub fn receiver(&self) -> Result<ElgamalPublicKey> {
+    self.receiver.decode()
+  }
+
+  pub fn auditors(&self) -> Result<BTreeSet<ElgamalPublicKey>> {
+    let mut auditors = BTreeSet::new();
+    for k in &self.auditors {
+      auditors.insert(k.decode()?);
+    }
+    Ok(auditors)
+  }
+
+  /// The receiver as stored (`0x` prefixed hex).
+  pub fn receiver_hex(&self) -> String {
+    format!("0x{}", hex::encode(self.receiver.0))
+  }
+
+  /// The auditors as stored (sorted, comma separated).
+  pub fn auditors_hex(&self) -> Result<String> {
+    Ok(auditors_to_hex(self.auditors()?.iter()))
+  }
+}
+
+/// `0x` prefixed hex of an Elgamal public key.
+#[cfg(feature = "backend")]
+fn key_to_hex(key: &ElgamalPublicKey) -> String {
+  format!("0x{}", hex::encode(key.encode()))
+}
+
+/// Sorted, comma separated auditor keys.
+#[cfg(feature = "backend")]
+fn auditors_to_hex<'a>(auditors: impl Iterator<Item = &'a ElgamalPublicKey>) -> String {
+  auditors.map(key_to_hex).collect::<Vec<_>>().join(",")
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+static PROOF_STATS: OnceLock<ProofStats> = OnceLock::new();
+
+/// Proof operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofOperation {
+  /// Generate a sender proof.
+  SenderProof,
+  /// Generate a burn proof.
+  BurnProof,
+  /// Verify a sender proof.
+  SenderVerify,
+  /// Verify a sender proof as the receiver.
+  ReceiverVerify,
+  /// Verify a sender proof as an auditor.
+  AuditorVerify,
+}
+
+/// `(operation, asset, auditor count)`.
+type StatsKey = (ProofOperation, Option<Uuid>, Option<u32>);
+
+#[derive(Clone, Copy, Default)]
+struct Samples {
+  count: u64,
+  total_secs: f64,
+  min_secs: f64,
+  max_secs: f64,
+  total_size: u64,
+  min_size: u64,
+  max_size: u64,
+}
+
+/// Aggregated proof statistics for an operation, asset and number of auditors.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProofStatsEntry {
+  /// Proof operation.
+  pub operation: ProofOperation,
+  /// Asset id, if known.
+  #[schema(example = json!(null))]
+  pub asset_id: Option<Uuid>,
+  /// Number of auditors, if known.
+  #[schema(example = 1)]
+  pub auditors: Option<u32>,
+  /// Number of proofs.
+  #[schema(example = 10)]
+  pub count: u64,
+  /// Average duration in milliseconds.
+  #[schema(example = 25.5)]
+  pub avg_ms: f64,
+  /// Minimum duration in milliseconds.
+  #[schema(example = 20.1)]
+  pub min_ms: f64,
+  /// Maximum duration in milliseconds.
+  #[schema(example = 40.7)]
+  pub max_ms: f64,
+  /// Average serialized proof size in bytes.
+  #[schema(example = 1200)]
+  pub avg_size: u64,
+  /// Minimum serialized proof size in bytes.
+  #[schema(example = 1200)]
+  pub min_size: u64,
+  /// Maximum serialized proof size in bytes.
+  #[schema(example = 1200)]
+  pub max_size: u64,
+}
+
+/// Durations and sizes of generated and verified proofs since startup.
+///
+/// Used for capacity planning and to spot regressions after library upgrades.
+#[derive(Default)]
+pub struct ProofStats {
+  samples: Mutex<BTreeMap<StatsKey, Samples>>,
+}
+
+impl ProofStats {
+  /// The process wide statistics.
+  pub fn global() -> &'static Self {
+    PROOF_STATS.get_or_init(Default::default)
+  }
+
+  /// Record the duration and serialized proof size of a proof operation.
+  pub fn record(
+    &self,
+    operation: ProofOperation,
+    asset_id: Option<Uuid>,
+    auditors: Option<usize>,
+    duration: Duration,
+    size: usize,
+  ) {
+    let secs = duration.as_secs_f64();
+    let size = size as u64;
+    let key = (operation, asset_id, auditors.map(|n| n as u32));
+    let mut samples = self.samples.lock().expect("Proof stats poisoned");
+    let s = samples.entry(key).or_default();
+    if s.count == 0 {
+      s.min_secs = secs;
+      s.min_size = size;
+    }
+    s.count += 1;
+    s.total_secs += secs;
+    s.min_secs = s.min_secs.min(secs);
+    s.max_secs = s.max_secs.max(secs);
+    s.total_size += size;
+    s.min_size = s.min_size.min(size);
+    s.max_size = s.max_size.max(size);
+  }
+
+  /// Aggregated statistics.
+  pub fn entries(&self) -> Vec<ProofStatsEntry> {
+    let samples = self.samples.lock().expect("Proof stats poisoned");
+    samples
+      .iter()
+      .map(|(&(operation, asset_id, auditors), s)| ProofStatsEntry {
+        operation,
+        asset_id,
+        auditors,
+        count: s.count,
+        avg_ms: s.total_secs * 1000.0 / s.count as f64,
+        min_ms: s.min_secs * 1000.0,
+        max_ms: s.max_secs * 1000.0,
+        avg_size: s.total_size / s.count,
+        min_size: s.min_size,
+        max_size: s.max_size,
+      })
+      .collect()
+  }
+}
@@ -17,17 +17,26 @@ use polymesh_api::types::pallet_confidential_asset::{AuditorAccount, Confidentia
 
 #[cfg(feature = "backend")]
 use confidential_assets::{
-  burn::ConfidentialBurnProof,
-  elgamal::CipherText,
-  transaction::{ConfidentialTransferProof, MAX_TOTAL_SUPPLY},
+  burn::ConfidentialBurnProof, elgamal::CipherText, transaction::ConfidentialTransferProof,
   Balance, ElgamalKeys, ElgamalPublicKey, ElgamalSecretKey, Scalar,
 };
 
+#[cfg(feature = "backend")]
+use merlin::Transcript;
+
+#[cfg(feature = "backend")]
+use crate::balance_conflicts::BalanceSource;
+#[cfg(feature = "backend")]
+use crate::decrypt_cache::DecryptionCache;
 use crate::error::*;
 
 #[cfg(not(feature = "backend"))]
 pub type Balance = u64;
 
+/// Transcript label of per-asset key derivation.
+#[cfg(feature = "backend")]
+pub const ASSET_KEY_LABEL: &[u8] = b"PolymeshPrivateAssetKey";
+
 /// User for account access control.
 #[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
@@ -57,6 +66,15 @@ pub struct CreateUser {
 pub struct Asset {
   /// Asset id.
   pub asset_id: Uuid,
+  /// Asset name.
+  #[schema(example = "Private Fund A")]
+  pub name: Option<String>,
+  /// Asset ticker.
+  #[schema(example = "PFA")]
+  pub ticker: Option<String>,
+  /// Number of decimals of the asset's amounts.
+  #[schema(example = 6)]
+  pub decimals: Option<u8>,
 
   pub created_at: chrono::NaiveDateTime,
   pub updated_at: chrono::NaiveDateTime,
@@ -67,6 +85,19 @@ pub struct Asset {
 pub struct AddAsset {
   /// Asset id.
   pub asset_id: Uuid,
+  /// Asset name.
+  #[schema(example = "Private Fund A")]
+  #[serde(default)]
+  pub name: Option<String>,
+  /// Asset ticker.  Must be unique.
+  #[schema(example = "PFA")]
+  #[serde(default)]
+  pub ticker: Option<String>,
+  /// Number of decimals of the asset's amounts.  Only used to format amounts, the API
+  /// always uses the raw amounts.
+  #[schema(example = 6)]
+  #[serde(default)]
+  pub decimals: Option<u8>,
 }
 
 /// Confidential account.
@@ -81,6 +112,14 @@ pub struct Account {
   #[serde(with = "SerHexSeq::<StrictPfx>")]
   pub confidential_account: Vec<u8>,
 
+  /// Locked accounts can't be used to generate proofs or decrypt values.
+  #[schema(example = false)]
+  pub locked: bool,
+
+  /// Chain identity (DID) of the account, once resolved from the chain.
+  #[schema(example = "0x0600000000000000000000000000000000000000000000000000000000000000")]
+  pub did: Option<String>,
+
   pub created_at: chrono::NaiveDateTime,
   pub updated_at: chrono::NaiveDateTime,
 }
@@ -109,10 +148,28 @@ pub struct AccountWithSecret {
 
   pub confidential_account: Vec<u8>,
   pub secret_key: Vec<u8>,
+  pub locked: bool,
+  /// The secret key is split into escrow shares.  `secret_key` is empty until the
+  /// shares are reassembled.
+  pub escrowed: bool,
 }
 
 #[cfg(feature = "backend")]
 impl AccountWithSecret {
+  /// Returns an error if the account has been locked by an admin or its secret key
+  /// is in escrow.
+  pub fn ensure_unlocked(&self) -> Result<()> {
+    if self.locked {
+      return Err(Error::forbidden("Account is locked"));
+    }
+    if self.escrowed && self.secret_key.is_empty() {
+      return Err(Error::forbidden(
+        "Account secret key is in escrow, reassemble it first",
+      ));
+    }
+    Ok(())
+  }
+
   pub fn match_confidential_account(&self, confidential_account: &PublicKey) -> bool {
     self.confidential_account.as_slice() == &confidential_account.0[..]
   }
@@ -135,9 +192,8 @@ impl AccountWithSecret {
     // Decode ConfidentialAccount from database.
     let keys = self.encryption_keys()?;
     // Decrypt value.
-    let value = keys
-      .secret
-      .decrypt_with_hint(enc_value, 0, MAX_TOTAL_SUPPLY)
+    let value = DecryptionCache::global()
+      .decrypt(&keys, enc_value)
       .ok_or_else(|| Error::other("Failed to decrypt value."))?;
     Ok(value)
   }
@@ -150,9 +206,8 @@ impl AccountWithSecret {
     // Decode ConfidentialAccount from database.
     let keys = self.encryption_keys()?;
     // Decrypt incoming balance.
-    let incoming_balance = keys
-      .secret
-      .decrypt_with_hint(&enc_incoming, 0, MAX_TOTAL_SUPPLY)
+    let incoming_balance = DecryptionCache::global()
+      .decrypt(&keys, &enc_incoming)
       .ok_or_else(|| Error::other("Failed to decrypt incoming balance."))?;
     // Update account balance.
     Ok(UpdateAccountAsset {
@@ -161,6 +216,12 @@ impl AccountWithSecret {
       asset_id,
       balance: incoming_balance,
       enc_balance: enc_incoming,
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: None,
+      action: BalanceAction::ApplyIncoming,
+      amount: Some(incoming_balance as i64),
+      history_source: None,
     })
   }
 
@@ -171,21 +232,37 @@ impl AccountWithSecret {
       asset_id,
       balance: 0,
       enc_balance: CipherText::zero(),
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: None,
+      action: BalanceAction::Init,
+      amount: Some(0),
+      history_source: None,
     }
   }
 
   pub fn auditor_verify_proof(
     &self,
     req: &AuditorVerifyRequest,
+  ) -> Result<SenderProofVerifyResult> {
+    self.auditor_verify_sender_proof(&req.sender_proof, req.auditor_id, req.amount)
+  }
+
+  /// Verify a sender proof as the auditor with index `auditor_id` in the leg's auditors.
+  pub fn auditor_verify_sender_proof(
+    &self,
+    sender_proof: &SenderProof,
+    auditor_id: u32,
+    amount: Option<Balance>,
   ) -> Result<SenderProofVerifyResult> {
     // Decode ConfidentialAccount from database.
     let auditor = self.encryption_keys()?;
 
-    // Decode sender proof from request.
-    let sender_proof = req.sender_proof()?;
+    // Decode sender proof.
+    let sender_proof = sender_proof.decode()?;
 
     let res = sender_proof
-      .auditor_verify(req.auditor_id as u8, &auditor, req.amount)
+      .auditor_verify(auditor_id as u8, &auditor, amount)
       .map(|b| Some(b));
     Ok(SenderProofVerifyResult::from_result(res))
   }
@@ -203,9 +280,8 @@ impl AccountWithSecret {
 
     // Decrypted balance.
     let balance = match balance {
-      None => sender
-        .secret
-        .decrypt_with_hint(&enc_balance, 0, MAX_TOTAL_SUPPLY)
+      None => DecryptionCache::global()
+        .decrypt(&sender, &enc_balance)
         .ok_or_else(|| Error::other("Failed to decrypt balance."))?,
       Some(balance) => balance,
     };
@@ -235,9 +311,8 @@ impl AccountWithSecret {
 
     // Decrypted balance.
     let balance = match balance {
-      None => issuer
-        .secret
-        .decrypt_with_hint(&enc_balance, 0, MAX_TOTAL_SUPPLY)
+      None => DecryptionCache::global()
+        .decrypt(&issuer, &enc_balance)
         .ok_or_else(|| Error::other("Failed to decrypt balance."))?,
       Some(balance) => balance,
     };
@@ -268,19 +343,71 @@ impl AccountWithSecret {
     Ok(SenderProofVerifyResult::from_result(res))
   }
 
+  /// Verify a sender proof as the receiver.
+  pub fn receiver_verify_sender_proof(
+    &self,
+    sender_proof: &SenderProof,
+    amount: Option<Balance>,
+  ) -> Result<SenderProofVerifyResult> {
+    // Decode ConfidentialAccount from database.
+    let receiver = self.encryption_keys()?;
+
+    // Decode sender proof.
+    let sender_proof = sender_proof.decode()?;
+
+    let res = sender_proof
+      .receiver_verify(receiver, amount)
+      .map(|b| Some(b));
+    Ok(SenderProofVerifyResult::from_result(res))
+  }
+
+  /// Decrypt the amount of our own sender proof.
+  pub fn sender_decrypt_sender_proof(&self, sender_proof: &SenderProof) -> Result<Balance> {
+    let sender_proof = sender_proof.decode()?;
+    self.decrypt(&sender_proof.sender_amount())
+  }
+
   pub fn decrypt_request(&self, req: &AccountDecryptRequest) -> Result<DecryptedResponse> {
     // Decode `req`.
     let enc_value = req.encrypted_value()?;
     // Decode ConfidentialAccount from database.
     let keys = self.encryption_keys()?;
     // Decrypt value.
-    let value = keys
-      .secret
-      .decrypt_with_hint(&enc_value, 0, MAX_TOTAL_SUPPLY)
+    let value = DecryptionCache::global()
+      .decrypt(&keys, &enc_value)
       .ok_or_else(|| Error::other("Failed to decrypt value."))?;
     // Return the decrypted value.
     Ok(DecryptedResponse { value })
   }
+
+  /// Decrypt a value, unless it is larger than `max`.
+  ///
+  /// Returns `None` if the value wasn't found, use `decrypt` to search the full range.
+  pub fn decrypt_in_range(&self, enc_value: &CipherText, max: Balance) -> Result<Option<Balance>> {
+    let keys = self.encryption_keys()?;
+    Ok(DecryptionCache::global().decrypt_in_range(&keys, enc_value, max))
+  }
+
+  /// Derive the account of one asset from this (master) account's secret key.
+  ///
+  /// The derivation is deterministic and domain-separated by the asset id.  It is one-way,
+  /// so the derived secret key doesn't expose the master secret key or the keys of other
+  /// assets.
+  pub fn derive_asset_account(&self, asset_id: Uuid) -> Result<CreateAccount> {
+    let keys = self.encryption_keys()?;
+    let mut transcript = Transcript::new(ASSET_KEY_LABEL);
+    transcript.append_message(b"public_key", &self.confidential_account);
+    transcript.append_message(b"secret_key", &keys.secret.encode());
+    transcript.append_message(b"asset_id", asset_id.as_bytes());
+    let mut bytes = zeroize::Zeroizing::new([0u8; 64]);
+    transcript.challenge_bytes(b"asset_secret_key", &mut bytes[..]);
+    let secret = ElgamalSecretKey::new(Scalar::from_bytes_mod_order_wide(&bytes));
+    let public = secret.get_public_key();
+    Ok(CreateAccount {
+      confidential_account: public.encode(),
+      secret_key: secret.encode(),
+    })
+  }
 }
 
 /// Create a new account.  Not allowed to be serialized.
@@ -299,6 +426,16 @@ impl CreateAccount {
     ElgamalKeys { public, secret }
   }
 
+  /// Import an existing account from it's SCALE encoded secret key.
+  pub fn from_secret(secret_key: &[u8]) -> Result<Self> {
+    let secret = ElgamalSecretKey::decode(&mut &secret_key[..])?;
+    let public = secret.get_public_key();
+    Ok(Self {
+      confidential_account: public.encode(),
+      secret_key: secret.encode(),
+    })
+  }
+
   pub fn new() -> Self {
     let enc_keys = Self::create_secret_account();
 
@@ -309,6 +446,21 @@ impl CreateAccount {
   }
 }
 
+/// Account of one asset, derived from a master account.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AssetAccount {
+  /// Asset id.
+  pub asset_id: Uuid,
+
+  /// Derived confidential account (Elgamal public key).  Used like any other account.
+  #[schema(example = "0xdeadbeef00000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub confidential_account: Vec<u8>,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
 /// Account asset.
 #[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
@@ -350,10 +502,114 @@ impl AccountAsset {
       asset_id: self.asset_id.clone(),
       balance: (self.balance as u64) + amount,
       enc_balance: enc_balance + CipherText::value(amount.into()),
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: Some(self.enc_balance.clone()),
+      action: BalanceAction::Mint,
+      amount: Some(amount as i64),
+      history_source: None,
     })
   }
 }
 
+/// What changed an account asset's balance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceAction {
+  /// Account asset added with a zero balance.
+  #[default]
+  Init,
+  /// Minted locally.
+  Mint,
+  /// Sender proof generated.
+  Send,
+  /// Burn proof generated.
+  Burn,
+  /// Incoming balance applied.
+  ApplyIncoming,
+  /// Set with the `update_balance` endpoint.
+  UpdateBalance,
+  /// Chain deposit.
+  Deposit,
+  /// Chain withdrawal.
+  Withdrawal,
+  /// Unused pooled sender proofs released.
+  ProofPoolRelease,
+  /// Balance refreshed or imported from the chain.
+  Refresh,
+  /// Held balance conflict resolved.
+  ResolveConflict,
+}
+
+impl BalanceAction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Init => "init",
+      Self::Mint => "mint",
+      Self::Send => "send",
+      Self::Burn => "burn",
+      Self::ApplyIncoming => "apply_incoming",
+      Self::UpdateBalance => "update_balance",
+      Self::Deposit => "deposit",
+      Self::Withdrawal => "withdrawal",
+      Self::ProofPoolRelease => "proof_pool_release",
+      Self::Refresh => "refresh",
+      Self::ResolveConflict => "resolve_conflict",
+    }
+  }
+}
+
+/// Account asset balance history.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct BalanceHistory {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// What changed the balance (see `BalanceAction`), null for changes recorded before the
+  /// action was tracked.
+  #[schema(example = "send")]
+  pub action: Option<String>,
+  /// Amount added to (positive) or removed from (negative) the balance, if known.
+  #[schema(example = json!(-100))]
+  pub amount: Option<i64>,
+  /// Transaction hash of chain changes, or the API call (or proof pool) of local changes.
+  #[schema(example = "POST /accounts/{confidential_account}/assets/{asset_id}/send")]
+  pub source: Option<String>,
+
+  /// Balance.
+  #[schema(example = 1000)]
+  pub balance: i64,
+  /// Balance encryted.
+  #[schema(value_type = String, format = Binary, example = "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub enc_balance: Vec<u8>,
+  /// Block the balance change was included in (null for local-only changes).
+  #[schema(example = 1)]
+  pub block_number: Option<u32>,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// A local confidential account holding an asset.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AssetHolder {
+  /// Confidential account (Elgamal public key).
+  #[schema(example = "0xdeadbeef00000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub confidential_account: Vec<u8>,
+
+  /// Current balance.
+  #[schema(example = 1000)]
+  pub balance: i64,
+  /// Current balance encryted.
+  #[schema(value_type = String, format = Binary, example = "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub enc_balance: Vec<u8>,
+
+  pub updated_at: chrono::NaiveDateTime,
+}
+
 /// Account asset with account secret key.  Not allowed to be serialized.
 #[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
 #[derive(Clone, Debug, Default)]
@@ -406,6 +662,12 @@ impl AccountAssetWithSecret {
       asset_id: self.asset_id.clone(),
       balance: (balance as u64) - amount,
       enc_balance: enc_balance - proof.sender_amount(),
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: Some(self.enc_balance.clone()),
+      action: BalanceAction::Send,
+      amount: Some(-(amount as i64)),
+      history_source: None,
     };
 
     Ok((update, proof))
@@ -430,6 +692,12 @@ impl AccountAssetWithSecret {
       asset_id: self.asset_id.clone(),
       balance: (balance as u64) - amount,
       enc_balance: enc_balance - enc_amount,
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: Some(self.enc_balance.clone()),
+      action: BalanceAction::Burn,
+      amount: Some(-(amount as i64)),
+      history_source: None,
     };
 
     Ok((update, proof))
@@ -452,9 +720,8 @@ impl AccountAssetWithSecret {
     // Decode ConfidentialAccount from database.
     let keys = self.account.encryption_keys()?;
     // Decrypt value.
-    let value = keys
-      .secret
-      .decrypt_with_hint(&enc_value, 0, MAX_TOTAL_SUPPLY)
+    let value = DecryptionCache::global()
+      .decrypt(&keys, &enc_value)
       .ok_or_else(|| Error::other("Failed to decrypt value."))?;
     // Return the decrypted value.
     Ok(DecryptedResponse { value })
@@ -469,9 +736,8 @@ impl AccountAssetWithSecret {
     // Decode ConfidentialAccount from database.
     let keys = self.account.encryption_keys()?;
     // Decrypt balance.
-    let balance = keys
-      .secret
-      .decrypt_with_hint(&enc_balance, 0, MAX_TOTAL_SUPPLY)
+    let balance = DecryptionCache::global()
+      .decrypt(&keys, &enc_balance)
       .ok_or_else(|| Error::other("Failed to decrypt balance."))?;
     // Update account balance.
     Ok(UpdateAccountAsset {
@@ -480,6 +746,12 @@ impl AccountAssetWithSecret {
       asset_id: self.asset_id.clone(),
       balance,
       enc_balance,
+      block_number: None,
+      source: BalanceSource::Manual,
+      base_enc_balance: None,
+      action: BalanceAction::UpdateBalance,
+      amount: Some(balance as i64 - self.balance),
+      history_source: None,
     })
   }
 
@@ -487,9 +759,8 @@ impl AccountAssetWithSecret {
     // Decode ConfidentialAccount from database.
     let keys = self.account.encryption_keys()?;
     // Decrypt incoming balance.
-    let incoming_balance = keys
-      .secret
-      .decrypt_with_hint(&enc_incoming, 0, MAX_TOTAL_SUPPLY)
+    let incoming_balance = DecryptionCache::global()
+      .decrypt(&keys, &enc_incoming)
       .ok_or_else(|| Error::other("Failed to decrypt incoming balance."))?;
     // Decode `enc_balance` from local DB.
     let enc_balance = self.enc_balance()?;
@@ -500,6 +771,12 @@ impl AccountAssetWithSecret {
       asset_id: self.asset_id.clone(),
       balance: (self.balance as u64) + incoming_balance,
       enc_balance: enc_balance + enc_incoming,
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: Some(self.enc_balance.clone()),
+      action: BalanceAction::ApplyIncoming,
+      amount: Some(incoming_balance as i64),
+      history_source: None,
     })
   }
 }
@@ -521,6 +798,18 @@ pub struct UpdateAccountAsset {
 
   pub balance: Balance,
   pub enc_balance: CipherText,
+  /// Block the update was included in, if it came from the chain.
+  pub block_number: Option<u32>,
+  /// Where the update came from.
+  pub source: BalanceSource,
+  /// Stored encrypted balance the update was computed from.
+  pub base_enc_balance: Option<Vec<u8>>,
+  /// What changed the balance.
+  pub action: BalanceAction,
+  /// Amount added to (positive) or removed from (negative) the balance, if known.
+  pub amount: Option<i64>,
+  /// Transaction hash or API call, recorded as the balance history's `source`.
+  pub history_source: Option<String>,
 }
 
 #[cfg(feature = "backend")]
@@ -532,12 +821,39 @@ impl UpdateAccountAsset {
       asset_id,
       balance,
       enc_balance: CipherText::value(balance.into()),
+      block_number: None,
+      source: BalanceSource::Local,
+      base_enc_balance: None,
+      action: BalanceAction::Init,
+      amount: Some(balance as i64),
+      history_source: None,
     }
   }
 
   pub fn enc_balance(&self) -> Vec<u8> {
     self.enc_balance.encode()
   }
+
+  /// Set the transaction hash or API call the update came from.
+  pub fn with_source(mut self, source: impl Into<String>) -> Self {
+    self.history_source = Some(source.into());
+    self
+  }
+
+  /// Does the update disagree with the stored balance?
+  ///
+  /// An update computed from the stored balance only conflicts if the balance changed in
+  /// the meantime.  Other updates conflict with a different balance written by another
+  /// source.
+  pub fn conflicts_with(&self, balance: i64, enc_balance: &[u8], source: BalanceSource) -> bool {
+    if self.balance as i64 == balance {
+      return false;
+    }
+    match &self.base_enc_balance {
+      Some(base) => base.as_slice() != enc_balance,
+      None => self.source != source,
+    }
+  }
 }
 
 /// Decrypt a `CipherText` value request.
@@ -562,6 +878,25 @@ pub struct DecryptedIncomingBalance {
   /// Decrypted incoming balance.
   #[schema(example = 1000)]
   pub incoming_balance: Option<u64>,
+  /// Storage proof of the encrypted incoming balance, if requested.
+  #[cfg(feature = "storage_proof")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub proof: Option<crate::StorageReadProof>,
+}
+
+/// Decrypted on-chain balance at a block.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct DecryptedBalanceAtBlock {
+  /// Block hash the balance was queried at.
+  #[schema(example = "0x0000000000000000000000000000000000000000000000000000000000000000")]
+  pub block_hash: String,
+  /// Decrypted balance.  `None` if the account didn't have a balance for the asset at that block.
+  #[schema(example = 1000)]
+  pub balance: Option<u64>,
+  /// Storage proof of the encrypted balance, if requested.
+  #[cfg(feature = "storage_proof")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub proof: Option<crate::StorageReadProof>,
 }
 
 /// Decrypted value response.
@@ -572,6 +907,107 @@ pub struct DecryptedResponse {
   pub value: u64,
 }
 
+/// Status of a background decryption job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DecryptJobStatus {
+  Running,
+  Completed,
+  Failed,
+}
+
+/// Background decryption of a large value.
+///
+/// Returned by the `decrypt` endpoints when the value can't be found quickly.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct DecryptJob {
+  /// Job id.  Use `/jobs/{job_id}` to get the result.
+  pub job_id: Uuid,
+  /// Job status.
+  pub status: DecryptJobStatus,
+  /// Decrypted value, when completed.
+  #[schema(example = 1000)]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub value: Option<u64>,
+  /// Error message, when failed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub err_msg: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub completed_at: Option<chrono::NaiveDateTime>,
+}
+
+impl DecryptJob {
+  pub fn new(job_id: Uuid) -> Self {
+    Self {
+      job_id,
+      status: DecryptJobStatus::Running,
+      value: None,
+      err_msg: None,
+      created_at: chrono::Utc::now().naive_utc(),
+      completed_at: None,
+    }
+  }
+
+  pub fn complete(&mut self, res: Result<Balance>) {
+    match res {
+      Ok(value) => {
+        self.status = DecryptJobStatus::Completed;
+        self.value = Some(value);
+      }
+      Err(err) => {
+        self.status = DecryptJobStatus::Failed;
+        self.err_msg = Some(err.to_string());
+      }
+    }
+    self.completed_at = Some(chrono::Utc::now().naive_utc());
+  }
+}
+
+/// Server-signed receipt of a transaction submission or proof generation.
+///
+/// The signature is Ed25519 over `Receipt::message()`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct Receipt {
+  /// HTTP method and path of the request.
+  #[schema(example = "POST /api/v1/tx/assets/create_asset")]
+  pub action: String,
+  /// Blake2-256 hash of the JSON request body (hex).
+  pub request_hash: String,
+  /// Transaction hash, if a transaction was submitted.
+  #[schema(example = json!(null))]
+  pub tx_hash: Option<String>,
+  /// Unix timestamp (seconds).
+  #[schema(example = 1700000000)]
+  pub timestamp: i64,
+  /// Server's Ed25519 public key (hex).
+  pub public_key: String,
+  /// Ed25519 signature (hex).
+  pub signature: String,
+}
+
+impl Receipt {
+  /// The canonical message that is signed.
+  pub fn message(&self) -> Vec<u8> {
+    format!(
+      "{}\n{}\n{}\n{}",
+      self.action,
+      self.request_hash,
+      self.tx_hash.as_deref().unwrap_or_default(),
+      self.timestamp
+    )
+    .into_bytes()
+  }
+}
+
+/// Receipt verification result.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReceiptVerifyResult {
+  /// Is the signature valid and was it signed by this server.
+  #[schema(example = true)]
+  pub valid: bool,
+}
+
 /// Update account asset balance request.
 #[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateAccountAssetBalanceRequest {
@@ -663,13 +1099,43 @@ impl PublicKey {
   }
 }
 
+/// SCALE encode a `Uuid` as it's 16 bytes.
+#[cfg(feature = "backend")]
+#[derive(Encode)]
+pub struct UuidBytes([u8; 16]);
+
+#[cfg(feature = "backend")]
+impl From<&Uuid> for UuidBytes {
+  fn from(id: &Uuid) -> Self {
+    Self(id.into_bytes())
+  }
+}
+
+#[cfg(feature = "backend")]
+impl<'a> codec::EncodeAsRef<'a, Uuid> for UuidBytes {
+  type RefType = UuidBytes;
+}
+
 /// Confidential transfer proofs.
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct TransferProofs {
   pub proofs: Vec<(Uuid, SenderProof)>,
 }
 
+#[cfg(feature = "backend")]
+impl Encode for TransferProofs {
+  fn encode_to<T: codec::Output + ?Sized>(&self, dest: &mut T) {
+    let proofs = self
+      .proofs
+      .iter()
+      .map(|(asset_id, proof)| (asset_id.into_bytes(), proof))
+      .collect::<Vec<_>>();
+    proofs.encode_to(dest);
+  }
+}
+
 /// Confidential transfer sender proof.
+#[cfg_attr(feature = "backend", derive(Encode))]
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct SenderProof(
   #[schema(example = "<Hex encoded sender proof>")]
@@ -688,6 +1154,23 @@ impl SenderProof {
       &mut self.0.as_slice(),
     )?)
   }
+
+  /// Verify the proof against the sender's balance it was generated for.
+  pub fn verify(
+    &self,
+    sender: &ElgamalPublicKey,
+    sender_balance: &CipherText,
+    receiver: &ElgamalPublicKey,
+    auditors: &BTreeSet<ElgamalPublicKey>,
+  ) -> Result<SenderProofVerifyResult> {
+    let mut rng = rand::thread_rng();
+    let sender_proof = self.decode()?;
+
+    let res = sender_proof
+      .verify(sender, sender_balance, receiver, auditors, &mut rng)
+      .map(|_| None);
+    Ok(SenderProofVerifyResult::from_result(res))
+  }
 }
 
 /// Generate a new sender proof.
@@ -707,6 +1190,18 @@ pub struct SenderProofRequest {
   /// Transaction amount.
   #[schema(example = 1000, value_type = u64)]
   pub amount: Balance,
+  /// Deduct the amount from the account asset's tracked balance (default).  With `false`
+  /// the proof is generated from the tracked balance without changing it, so a failed
+  /// submission doesn't need a rollback.  Once the transaction is on-chain, apply the new
+  /// balance with `update_balance`.  Ignored for pooled proofs, their amounts are reserved
+  /// by the pool, and by the account endpoint, which doesn't track balances.
+  #[schema(example = true)]
+  #[serde(default = "default_update_balance")]
+  pub update_balance: bool,
+}
+
+fn default_update_balance() -> bool {
+  true
 }
 
 #[cfg(feature = "backend")]
@@ -730,6 +1225,20 @@ impl SenderProofRequest {
     }
     Ok(auditors)
   }
+
+  /// Are the auditors in a different order than the proof's auditor ids (the sorted set)?
+  pub fn auditors_reordered(&self) -> Result<bool> {
+    let auditors = self.auditors()?;
+    if auditors.len() != self.auditors.len() {
+      return Ok(true);
+    }
+    for (auditor, k) in auditors.iter().zip(&self.auditors) {
+      if &k.decode()? != auditor {
+        return Ok(true);
+      }
+    }
+    Ok(false)
+  }
 }
 
 /// SenderProof verify sender proof.
@@ -779,6 +1288,14 @@ impl SenderProofVerifyRequest {
     self.sender_proof.decode()
   }
 
+  pub fn proof_size(&self) -> usize {
+    self.sender_proof.0.len()
+  }
+
+  pub fn auditor_count(&self) -> usize {
+    self.auditors.len()
+  }
+
   pub fn verify_proof(&self) -> Result<SenderProofVerifyResult> {
     // Decode sender's balance.
     let sender_balance = self.sender_balance()?;
@@ -813,6 +1330,18 @@ pub struct SenderProofVerifyResult {
 
 #[cfg(feature = "backend")]
 impl SenderProofVerifyResult {
+  pub fn is_valid(&self) -> bool {
+    self.is_valid
+  }
+
+  pub fn amount(&self) -> Option<Balance> {
+    self.amount
+  }
+
+  pub fn err_msg(&self) -> Option<&str> {
+    self.err_msg.as_deref()
+  }
+
   pub fn from_result<E: core::fmt::Debug>(res: Result<Option<Balance>, E>) -> Self {
     match res {
       Ok(amount) => Self {
@@ -847,6 +1376,10 @@ impl AuditorVerifyRequest {
   pub fn sender_proof(&self) -> Result<ConfidentialTransferProof> {
     self.sender_proof.decode()
   }
+
+  pub fn proof_size(&self) -> usize {
+    self.sender_proof.0.len()
+  }
 }
 
 /// Receiver verify sender proof.
@@ -864,6 +1397,10 @@ impl ReceiverVerifyRequest {
   pub fn sender_proof(&self) -> Result<ConfidentialTransferProof> {
     self.sender_proof.decode()
   }
+
+  pub fn proof_size(&self) -> usize {
+    self.sender_proof.0.len()
+  }
 }
 
 /// Confidential burn burn proof.
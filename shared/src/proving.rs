@@ -0,0 +1,97 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::error::{Error, Result};
+
+static PROVING_THREADS: OnceLock<ProvingThreads> = OnceLock::new();
+
+/// Proving thread pool statistics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProvingThreadsStats {
+  /// Number of threads.
+  pub threads: usize,
+  /// Proofs generated or verified, or waiting for a thread.
+  pub active: u64,
+}
+
+/// Dedicated thread pool for generating and verifying proofs.
+///
+/// `ConfidentialTransferProof::new` and proof verification take from milliseconds to seconds
+/// of CPU time.  Running them in the request handlers blocks the actix workers (and all
+/// their other requests), so the handlers `await` them on this pool instead.  Its size is
+/// set with `PROOF_THREADS` (default: the number of CPUs), requests over that are queued.
+pub struct ProvingThreads {
+  pool: ThreadPool,
+  active: AtomicU64,
+}
+
+impl ProvingThreads {
+  fn new(threads: usize) -> Self {
+    let pool = ThreadPoolBuilder::new()
+      .num_threads(threads)
+      .thread_name(|idx| format!("proving-{idx}"))
+      .build()
+      .expect("Failed to start the proving threads");
+    Self {
+      pool,
+      active: AtomicU64::new(0),
+    }
+  }
+
+  /// The process wide pool.  Uses the default size if `init` wasn't called.
+  pub fn global() -> &'static Self {
+    PROVING_THREADS.get_or_init(|| Self::new(0))
+  }
+
+  /// Start the process wide pool with `PROOF_THREADS` threads (default: the number of CPUs).
+  /// Must be called before any proof is generated.
+  pub fn init_from_env() -> &'static Self {
+    let threads = std::env::var("PROOF_THREADS")
+      .ok()
+      .and_then(|v| v.parse().ok())
+      .unwrap_or(0);
+    let proving = PROVING_THREADS.get_or_init(|| Self::new(threads));
+    log::info!("Proving threads: {}", proving.pool.current_num_threads());
+    proving
+  }
+
+  /// Run `f` on a proving thread and wait for its result.
+  pub async fn run<F, R>(&'static self, f: F) -> Result<R>
+  where
+    F: FnOnce() -> Result<R> + Send + 'static,
+    R: Send + 'static,
+  {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    self.active.fetch_add(1, Ordering::Relaxed);
+    self.pool.spawn(move || {
+      // Don't abort the process if the proof panics.
+      let res = catch_unwind(AssertUnwindSafe(f));
+      self.active.fetch_sub(1, Ordering::Relaxed);
+      // The request was dropped if the receiver is gone.
+      let _ = tx.send(res);
+    });
+    match rx.await {
+      Ok(Ok(res)) => res,
+      _ => Err(Error::other("Proof generation panicked")),
+    }
+  }
+
+  pub fn stats(&self) -> ProvingThreadsStats {
+    ProvingThreadsStats {
+      threads: self.pool.current_num_threads(),
+      active: self.active.load(Ordering::Relaxed),
+    }
+  }
+}
+
+/// Run `f` on the process wide proving threads.
+pub async fn spawn_proof<F, R>(f: F) -> Result<R>
+where
+  F: FnOnce() -> Result<R> + Send + 'static,
+  R: Send + 'static,
+{
+  ProvingThreads::global().run(f).await
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use codec::{Compact, Decode};
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, TypeDefPrimitive};
+use sp_core::hashing::twox_128;
+
+use polymesh_api::{
+  client::{block::EventRecord, block::Phase, rpc_params, BlockHash},
+  types::runtime::RuntimeEvent,
+  Api,
+};
+
+use crate::error::{Error, Result};
+
+/// Maximum nesting of the decoded types.
+const MAX_DEPTH: usize = 64;
+
+/// Event decoders by runtime spec version, so the metadata is only downloaded once per
+/// runtime upgrade.
+static DECODERS: OnceLock<Mutex<BTreeMap<u32, Arc<EventDecoder>>>> = OnceLock::new();
+
+/// Chain event that the compiled `RuntimeEvent` can't decode (i.e. from a pallet or event
+/// added by a runtime upgrade).
+#[derive(Clone, Debug)]
+pub struct RawEvent {
+  pub phase: Phase,
+  pub pallet_index: u8,
+  pub variant_index: u8,
+  /// Pallet name from the runtime metadata.
+  pub pallet: Option<String>,
+  /// Event name from the runtime metadata.
+  pub variant: Option<String>,
+  /// SCALE encoded event fields.
+  pub bytes: Vec<u8>,
+}
+
+/// A block's events, decoded with the runtime metadata of the block.
+///
+/// Events the compiled `RuntimeEvent` knows are decoded as usual, the others are kept as
+/// `RawEvent`s.  The metadata is only needed to find where each event ends.
+#[derive(Default)]
+pub struct BlockEvents {
+  pub events: Vec<EventRecord<RuntimeEvent>>,
+  pub raw: Vec<RawEvent>,
+}
+
+impl BlockEvents {
+  /// Get the block's events from the node and decode them with the block's runtime metadata.
+  ///
+  /// The metadata is fetched once per runtime spec version.
+  pub async fn fetch(api: &Api, block: BlockHash) -> Result<Self> {
+    let client = api.client();
+    let decoder = EventDecoder::for_block(api, block).await?;
+    let mut key = twox_128(b"System").to_vec();
+    key.extend_from_slice(&twox_128(b"Events"));
+    let events: Option<String> = client
+      .request(
+        "state_getStorage",
+        rpc_params!(format!("0x{}", hex::encode(key)), block),
+      )
+      .await?;
+    match events {
+      Some(events) => decoder.decode(&decode_hex(&events)?),
+      None => Ok(Self::default()),
+    }
+  }
+}
+
+/// Splits the encoded `System::Events` with the runtime metadata's type registry.
+struct EventDecoder {
+  types: PortableRegistry,
+  /// Pallet name and event type of each pallet index.
+  pallets: BTreeMap<u8, (String, u32)>,
+}
+
+impl EventDecoder {
+  /// Get the cached decoder of the block's runtime version, fetching its metadata on a miss.
+  async fn for_block(api: &Api, block: BlockHash) -> Result<Arc<Self>> {
+    let client = api.client();
+    let version: serde_json::Value = client
+      .request("state_getRuntimeVersion", rpc_params!(block))
+      .await?;
+    let spec_version = version["specVersion"]
+      .as_u64()
+      .ok_or_else(|| Error::other("Missing `specVersion` in the runtime version"))?
+      as u32;
+    let decoders = DECODERS.get_or_init(Default::default);
+    let cached = decoders
+      .lock()
+      .expect("Event decoders poisoned")
+      .get(&spec_version)
+      .cloned();
+    if let Some(decoder) = cached {
+      return Ok(decoder);
+    }
+    let metadata: String = client
+      .request("state_getMetadata", rpc_params!(block))
+      .await?;
+    let decoder = Arc::new(Self::from_metadata(&decode_hex(&metadata)?)?);
+    decoders
+      .lock()
+      .expect("Event decoders poisoned")
+      .insert(spec_version, decoder.clone());
+    Ok(decoder)
+  }
+
+  fn from_metadata(metadata: &[u8]) -> Result<Self> {
+    let metadata = match RuntimeMetadataPrefixed::decode(&mut &metadata[..])?.1 {
+      RuntimeMetadata::V14(metadata) => metadata,
+      _ => return Err(Error::other("Unsupported runtime metadata version")),
+    };
+    let pallets = metadata
+      .pallets
+      .iter()
+      .filter_map(|pallet| {
+        let event = pallet.event.as_ref()?;
+        Some((pallet.index, (pallet.name.clone(), event.ty.id)))
+      })
+      .collect();
+    Ok(Self {
+      types: metadata.types,
+      pallets,
+    })
+  }
+
+  fn decode(&self, data: &[u8]) -> Result<BlockEvents> {
+    let mut input = data;
+    let count = <Compact<u32>>::decode(&mut input)?.0;
+    let mut events = BlockEvents::default();
+    for _ in 0..count {
+      let record = input;
+      let phase = Phase::decode(&mut input)?;
+      let event = input;
+      let pallet_index = u8::decode(&mut input)?;
+      let (pallet, ty) = self
+        .pallets
+        .get(&pallet_index)
+        .ok_or_else(|| Error::Other(format!("Unknown pallet index: {pallet_index}")))?;
+      let variant_index = input.first().copied().unwrap_or_default();
+      let variant = self.variant_name(*ty, variant_index);
+      self.skip(*ty, &mut input, 0)?;
+      let event_len = event.len() - input.len();
+      // Topics.
+      let topics = <Compact<u32>>::decode(&mut input)?.0 as usize;
+      advance(&mut input, topics * 32)?;
+      let record = &record[..record.len() - input.len()];
+
+      let mut typed = record;
+      match EventRecord::<RuntimeEvent>::decode(&mut typed) {
+        Ok(rec) if typed.is_empty() => events.events.push(rec),
+        _ => events.raw.push(RawEvent {
+          phase,
+          pallet_index,
+          variant_index,
+          pallet: Some(pallet.clone()),
+          variant,
+          // Without the pallet and variant indexes.
+          bytes: event[2.min(event_len)..event_len].to_vec(),
+        }),
+      }
+    }
+    Ok(events)
+  }
+
+  fn variant_name(&self, ty: u32, index: u8) -> Option<String> {
+    match &self.types.resolve(ty)?.type_def {
+      TypeDef::Variant(def) => def
+        .variants
+        .iter()
+        .find(|variant| variant.index == index)
+        .map(|variant| variant.name.clone()),
+      _ => None,
+    }
+  }
+
+  /// Skip a value of the type.
+  fn skip(&self, ty: u32, input: &mut &[u8], depth: usize) -> Result<()> {
+    if depth > MAX_DEPTH {
+      return Err(Error::other("Event type nested too deeply"));
+    }
+    let def: &TypeDef<PortableForm> = &self
+      .types
+      .resolve(ty)
+      .ok_or_else(|| Error::Other(format!("Unknown type id: {ty}")))?
+      .type_def;
+    match def {
+      TypeDef::Composite(def) => {
+        for field in &def.fields {
+          self.skip(field.ty.id, input, depth + 1)?;
+        }
+      }
+      TypeDef::Variant(def) => {
+        let index = u8::decode(input)?;
+        let variant = def
+          .variants
+          .iter()
+          .find(|variant| variant.index == index)
+          .ok_or_else(|| Error::Other(format!("Unknown variant index: {index}")))?;
+        for field in &variant.fields {
+          self.skip(field.ty.id, input, depth + 1)?;
+        }
+      }
+      TypeDef::Sequence(def) => {
+        let len = <Compact<u32>>::decode(input)?.0 as usize;
+        // Every item is at least one byte.
+        if len > input.len() {
+          return Err(Error::other("Invalid sequence length"));
+        }
+        self.skip_items(def.type_param.id, len, input, depth)?;
+      }
+      TypeDef::Array(def) => {
+        self.skip_items(def.type_param.id, def.len as usize, input, depth)?;
+      }
+      TypeDef::Tuple(def) => {
+        for field in &def.fields {
+          self.skip(field.id, input, depth + 1)?;
+        }
+      }
+      TypeDef::Primitive(def) => {
+        let len = match def {
+          TypeDefPrimitive::Bool | TypeDefPrimitive::U8 | TypeDefPrimitive::I8 => 1,
+          TypeDefPrimitive::U16 | TypeDefPrimitive::I16 => 2,
+          TypeDefPrimitive::Char | TypeDefPrimitive::U32 | TypeDefPrimitive::I32 => 4,
+          TypeDefPrimitive::U64 | TypeDefPrimitive::I64 => 8,
+          TypeDefPrimitive::U128 | TypeDefPrimitive::I128 => 16,
+          TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => 32,
+          TypeDefPrimitive::Str => <Compact<u32>>::decode(input)?.0 as usize,
+        };
+        advance(input, len)?;
+      }
+      TypeDef::Compact(_) => {
+        <Compact<u128>>::decode(input)?;
+      }
+      TypeDef::BitSequence(def) => {
+        let bits = <Compact<u32>>::decode(input)?.0 as usize;
+        let store_bytes = match &self
+          .types
+          .resolve(def.bit_store_type.id)
+          .map(|ty| &ty.type_def)
+        {
+          Some(TypeDef::Primitive(TypeDefPrimitive::U8)) => 1,
+          Some(TypeDef::Primitive(TypeDefPrimitive::U16)) => 2,
+          Some(TypeDef::Primitive(TypeDefPrimitive::U32)) => 4,
+          Some(TypeDef::Primitive(TypeDefPrimitive::U64)) => 8,
+          _ => return Err(Error::other("Unsupported bit sequence store type")),
+        };
+        let store_bits = store_bytes * 8;
+        advance(input, (bits + store_bits - 1) / store_bits * store_bytes)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn skip_items(&self, ty: u32, len: usize, input: &mut &[u8], depth: usize) -> Result<()> {
+    // Skip byte arrays at once.
+    if let Some(TypeDef::Primitive(TypeDefPrimitive::U8)) =
+      self.types.resolve(ty).map(|ty| &ty.type_def)
+    {
+      return advance(input, len);
+    }
+    for _ in 0..len {
+      self.skip(ty, input, depth + 1)?;
+    }
+    Ok(())
+  }
+}
+
+fn advance(input: &mut &[u8], len: usize) -> Result<()> {
+  if input.len() < len {
+    return Err(Error::other("Unexpected end of the encoded events"));
+  }
+  *input = &input[len..];
+  Ok(())
+}
+
+fn decode_hex(val: &str) -> Result<Vec<u8>> {
+  Ok(hex::decode(val.strip_prefix("0x").unwrap_or(val))?)
+}
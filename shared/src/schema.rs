@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::SqlitePool;
+
+use crate::error::*;
+
+/// Compatibility of the database schema with the migrations embedded in a binary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaStatus {
+  /// All migrations have been applied.
+  UpToDate,
+  /// Migrations that haven't been applied yet.
+  Pending(Vec<i64>),
+  /// Applied migrations unknown to this binary, the database was upgraded by a newer release.
+  Newer(Vec<i64>),
+  /// Applied migrations that differ from the migrations in this binary.
+  Modified(Vec<i64>),
+  /// A migration failed part way.
+  Dirty(i64),
+}
+
+impl SchemaStatus {
+  /// Returns an error if this binary can't run against the database schema.
+  ///
+  /// Pending migrations are compatible, they can be applied.
+  pub fn ensure_compatible(&self) -> Result<()> {
+    match self {
+      Self::UpToDate | Self::Pending(_) => Ok(()),
+      Self::Newer(versions) => Err(Error::Other(format!(
+        "Database schema is newer than this release (unknown migrations: {versions:?})"
+      ))),
+      Self::Modified(versions) => Err(Error::Other(format!(
+        "Applied migrations don't match this release: {versions:?}"
+      ))),
+      Self::Dirty(version) => Err(Error::Other(format!(
+        "Migration {version} failed part way, the database needs to be fixed manually"
+      ))),
+    }
+  }
+}
+
+/// Compare the migrations applied to the database with `migrator`.
+pub async fn check_schema(pool: &SqlitePool, migrator: &Migrator) -> Result<SchemaStatus> {
+  let mut conn = pool.acquire().await?;
+  conn.ensure_migrations_table().await?;
+  if let Some(version) = conn.dirty_version().await? {
+    return Ok(SchemaStatus::Dirty(version));
+  }
+  let applied = conn.list_applied_migrations().await?;
+
+  let known = migrator
+    .iter()
+    .filter(|m| !m.migration_type.is_down_migration())
+    .map(|m| (m.version, m.checksum.as_ref()))
+    .collect::<BTreeMap<_, _>>();
+  let newer = applied
+    .iter()
+    .filter(|m| !known.contains_key(&m.version))
+    .map(|m| m.version)
+    .collect::<Vec<_>>();
+  if !newer.is_empty() {
+    return Ok(SchemaStatus::Newer(newer));
+  }
+  let modified = applied
+    .iter()
+    .filter(|m| known.get(&m.version) != Some(&m.checksum.as_ref()))
+    .map(|m| m.version)
+    .collect::<Vec<_>>();
+  if !modified.is_empty() {
+    return Ok(SchemaStatus::Modified(modified));
+  }
+  let applied = applied.iter().map(|m| m.version).collect::<BTreeSet<_>>();
+  let pending = known
+    .keys()
+    .filter(|version| !applied.contains(version))
+    .copied()
+    .collect::<Vec<_>>();
+  if pending.is_empty() {
+    Ok(SchemaStatus::UpToDate)
+  } else {
+    Ok(SchemaStatus::Pending(pending))
+  }
+}
+
+/// Apply the pending migrations.  Returns the applied versions.
+///
+/// Refuses to touch a database with an incompatible schema.
+pub async fn migrate(pool: &SqlitePool, migrator: &Migrator) -> Result<Vec<i64>> {
+  match check_schema(pool, migrator).await? {
+    SchemaStatus::Pending(versions) => {
+      migrator.run(pool).await?;
+      Ok(versions)
+    }
+    status => {
+      status.ensure_compatible()?;
+      Ok(vec![])
+    }
+  }
+}
+
+/// Pre-flight check at startup.
+///
+/// Refuses to start against a newer or modified schema.  Pending migrations are only
+/// applied when `auto_migrate` is set, otherwise they have to be applied first with
+/// the `migrate` subcommand.  Returns the applied versions.
+pub async fn prepare_schema(
+  pool: &SqlitePool,
+  migrator: &Migrator,
+  auto_migrate: bool,
+) -> Result<Vec<i64>> {
+  match check_schema(pool, migrator).await? {
+    SchemaStatus::Pending(versions) if !auto_migrate => Err(Error::Other(format!(
+      "Database schema is missing migrations {versions:?}, run the `migrate` subcommand first"
+    ))),
+    SchemaStatus::Pending(_) => migrate(pool, migrator).await,
+    status => {
+      status.ensure_compatible()?;
+      Ok(vec![])
+    }
+  }
+}
+
+/// `AUTO_MIGRATE` environment variable (default: true).
+pub fn auto_migrate_from_env() -> bool {
+  std::env::var("AUTO_MIGRATE")
+    .map(|v| !matches!(v.as_str(), "false" | "0"))
+    .unwrap_or(true)
+}
@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+
+use crate::error::*;
+use crate::PublicKey;
+
+/// Screening list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningList {
+  /// Receivers that can be sent to (with `SCREENING=allow_list`).
+  Allow,
+  /// Receivers that can't be sent to.
+  #[default]
+  Deny,
+}
+
+impl ScreeningList {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Allow => "allow",
+      Self::Deny => "deny",
+    }
+  }
+}
+
+impl FromStr for ScreeningList {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "allow" => Ok(Self::Allow),
+      "deny" => Ok(Self::Deny),
+      _ => Err(Error::Other(format!("Unknown screening list: {s}"))),
+    }
+  }
+}
+
+/// Entry of the static screening lists.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ScreeningEntry {
+  /// Confidential account or identity (DID).
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub subject: String,
+  /// List: `allow` or `deny`.
+  #[schema(example = "deny")]
+  pub list: String,
+  /// Why the subject is on the list.
+  #[schema(example = "Sanctioned")]
+  pub reason: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Add a confidential account or identity to a screening list.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateScreeningEntry {
+  /// Confidential account or identity (DID).
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub subject: PublicKey,
+  /// List.
+  pub list: ScreeningList,
+  /// Why the subject is on the list.
+  #[schema(example = "Sanctioned")]
+  #[serde(default)]
+  pub reason: Option<String>,
+}
+
+impl CreateScreeningEntry {
+  /// The subject as stored (`0x` prefixed hex).
+  pub fn subject(&self) -> String {
+    format!("0x{}", hex::encode(self.subject.0))
+  }
+}
+
+/// Update a screening list entry.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct UpdateScreeningEntry {
+  /// List.
+  pub list: ScreeningList,
+  /// Why the subject is on the list.
+  #[schema(example = "Sanctioned")]
+  #[serde(default)]
+  pub reason: Option<String>,
+}
+
+/// Receiver to screen before sending to it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ScreeningSubject {
+  /// Receiver's confidential account (`0x` prefixed hex).
+  pub receiver: String,
+  /// Receiver's identity (`0x` prefixed hex), if known.
+  pub did: Option<String>,
+}
@@ -0,0 +1,158 @@
+#[cfg(feature = "backend")]
+use std::collections::BTreeSet;
+#[cfg(feature = "backend")]
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[cfg(feature = "backend")]
+use confidential_assets::{
+  burn::ConfidentialBurnProof, transaction::ConfidentialTransferProof, Balance, ElgamalKeys,
+  ElgamalSecretKey, Scalar,
+};
+
+#[cfg(feature = "backend")]
+use crate::decrypt_cache::DecryptionCache;
+#[cfg(feature = "backend")]
+use crate::error::*;
+
+/// Balance of the throwaway sender account.
+#[cfg(feature = "backend")]
+const SELFTEST_BALANCE: Balance = 1000;
+/// Amount sent and burned.
+#[cfg(feature = "backend")]
+const SELFTEST_AMOUNT: Balance = 100;
+
+/// Self-test step.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SelfTestStep {
+  /// Step: `generate_keys`, `encrypt_balance`, `decrypt_balance`, `sender_proof`,
+  /// `sender_verify`, `receiver_verify`, `auditor_verify`, `burn_proof` or `burn_verify`.
+  #[schema(example = "sender_proof")]
+  pub name: String,
+  /// Did the step succeed.
+  #[schema(example = true)]
+  pub ok: bool,
+  /// Duration in milliseconds.
+  #[schema(example = 25.5)]
+  pub duration_ms: f64,
+  /// Why the step failed.
+  #[schema(example = json!(null))]
+  pub error: Option<String>,
+}
+
+/// Self-test report.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct SelfTestReport {
+  /// Did all steps succeed.
+  #[schema(example = true)]
+  pub ok: bool,
+  /// Total duration in milliseconds.
+  #[schema(example = 120.3)]
+  pub duration_ms: f64,
+  /// Steps in order.  Stops at the first failed step.
+  pub steps: Vec<SelfTestStep>,
+}
+
+#[cfg(feature = "backend")]
+fn duration_ms(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(feature = "backend")]
+fn new_keys() -> ElgamalKeys {
+  let secret = ElgamalSecretKey::new(Scalar::random(&mut rand::thread_rng()));
+  let public = secret.get_public_key();
+  ElgamalKeys { public, secret }
+}
+
+#[cfg(feature = "backend")]
+impl SelfTestReport {
+  /// Generate and verify a small sender proof and burn proof with throwaway accounts.
+  ///
+  /// Nothing is stored and the proofs aren't counted in the proof statistics.
+  pub fn run() -> Self {
+    let started = Instant::now();
+    let mut report = Self::default();
+    report.ok = report.run_steps().is_ok();
+    report.duration_ms = duration_ms(started.elapsed());
+    report
+  }
+
+  /// Run and time one step.
+  fn step<T>(&mut self, name: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let started = Instant::now();
+    let res = f();
+    self.steps.push(SelfTestStep {
+      name: name.to_string(),
+      ok: res.is_ok(),
+      duration_ms: duration_ms(started.elapsed()),
+      error: res.as_ref().err().map(|err| err.to_string()),
+    });
+    res
+  }
+
+  fn run_steps(&mut self) -> Result<()> {
+    let mut rng = rand::thread_rng();
+    let (sender, receiver, auditor) =
+      self.step("generate_keys", || Ok((new_keys(), new_keys(), new_keys())))?;
+    let enc_balance = self.step("encrypt_balance", || {
+      let (_, enc_balance) = sender
+        .public
+        .encrypt_value(Scalar::from(SELFTEST_BALANCE), &mut rng);
+      Ok(enc_balance)
+    })?;
+    // Uses the decryption lookup tables.
+    self.step("decrypt_balance", || {
+      match DecryptionCache::global().decrypt(&sender, &enc_balance) {
+        Some(SELFTEST_BALANCE) => Ok(()),
+        _ => Err(Error::other("Decrypted the wrong balance.")),
+      }
+    })?;
+
+    let auditors = BTreeSet::from([auditor.public]);
+    let proof = self.step("sender_proof", || {
+      Ok(ConfidentialTransferProof::new(
+        &sender,
+        &enc_balance,
+        SELFTEST_BALANCE,
+        &receiver.public,
+        &auditors,
+        SELFTEST_AMOUNT,
+        &mut rng,
+      )?)
+    })?;
+    self.step("sender_verify", || {
+      Ok(proof.verify(
+        &sender.public,
+        &enc_balance,
+        &receiver.public,
+        &auditors,
+        &mut rng,
+      )?)
+    })?;
+    self.step("receiver_verify", || {
+      proof.receiver_verify(receiver, Some(SELFTEST_AMOUNT))?;
+      Ok(())
+    })?;
+    self.step("auditor_verify", || {
+      proof.auditor_verify(0, &auditor, Some(SELFTEST_AMOUNT))?;
+      Ok(())
+    })?;
+
+    let burn_proof = self.step("burn_proof", || {
+      Ok(ConfidentialBurnProof::new(
+        &sender,
+        &enc_balance,
+        SELFTEST_BALANCE,
+        SELFTEST_AMOUNT,
+        &mut rng,
+      )?)
+    })?;
+    self.step("burn_verify", || {
+      Ok(burn_proof.verify(&sender.public, &enc_balance, SELFTEST_AMOUNT, &mut rng)?)
+    })?;
+    Ok(())
+  }
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+
+use codec::{Compact, Decode};
+use sp_core::{
+  hashing::{blake2_128, blake2_256, twox_128},
+  Blake2Hasher,
+};
+use sp_trie::{read_trie_value, LayoutV1, StorageProof};
+
+#[cfg(feature = "tx_backend")]
+use polymesh_api::{
+  client::{rpc_params, BlockHash},
+  Api,
+};
+
+use crate::error::{Error, Result};
+
+/// Storage read proof for a single storage key.
+///
+/// Can be used to check an on-chain value returned by the API against a known block hash,
+/// without trusting the API's node.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct StorageReadProof {
+  /// Block hash the value was read at.
+  #[schema(example = "0x0000000000000000000000000000000000000000000000000000000000000000")]
+  pub block_hash: String,
+  /// SCALE encoded block header (hex).  Its hash must match `block_hash`.
+  pub header: String,
+  /// Storage key (hex).
+  pub key: String,
+  /// Trie nodes of the read proof (hex).
+  pub proof: Vec<String>,
+}
+
+impl StorageReadProof {
+  /// Verify the proof and return the raw SCALE encoded storage value.
+  ///
+  /// Only proves the value is part of the state at `block_hash`.  The caller must check
+  /// that `block_hash` is a block they trust (i.e. a finalized block from their own node).
+  pub fn verify(&self) -> Result<Option<Vec<u8>>> {
+    let block_hash = decode_hex(&self.block_hash)?;
+    let header = decode_hex(&self.header)?;
+    if blake2_256(&header).as_slice() != block_hash.as_slice() {
+      return Err(Error::other("Block header doesn't match the block hash"));
+    }
+    // The header starts with: parent_hash, number, state_root.
+    let mut input = header.as_slice();
+    let _parent_hash = <[u8; 32]>::decode(&mut input)?;
+    let _number = <Compact<u32>>::decode(&mut input)?;
+    let state_root = <[u8; 32]>::decode(&mut input)?;
+
+    let key = decode_hex(&self.key)?;
+    let nodes = self
+      .proof
+      .iter()
+      .map(|node| decode_hex(node))
+      .collect::<Result<Vec<_>>>()?;
+    let db = StorageProof::new(nodes).into_memory_db::<Blake2Hasher>();
+    read_trie_value::<LayoutV1<Blake2Hasher>, _>(&db, &state_root.into(), &key, None, None)
+      .map_err(|err| Error::other(&format!("Invalid storage proof: {err:?}")))
+  }
+
+  /// Verify the proof and check that it proves `value`.
+  pub fn verify_value(&self, value: Option<&[u8]>) -> Result<()> {
+    if self.verify()?.as_deref() != value {
+      return Err(Error::other("Storage proof doesn't match the value"));
+    }
+    Ok(())
+  }
+
+  /// Get a read proof for `key` at `block` from the node.
+  #[cfg(feature = "tx_backend")]
+  pub async fn fetch(api: &Api, block: BlockHash, key: Vec<u8>) -> Result<Self> {
+    #[derive(Deserialize)]
+    struct ReadProof {
+      proof: Vec<String>,
+    }
+
+    let client = api.client();
+    let header = client
+      .get_block_header(Some(block))
+      .await?
+      .ok_or_else(|| Error::not_found("Block"))?;
+    let key = format!("0x{}", hex::encode(&key));
+    // The nodes are already hex encoded.
+    let read_proof: ReadProof = client
+      .request("state_getReadProof", rpc_params!(vec![key.clone()], block))
+      .await?;
+    Ok(Self {
+      block_hash: format!("{block:#x}"),
+      header: format!("0x{}", hex::encode(codec::Encode::encode(&header))),
+      key,
+      proof: read_proof.proof,
+    })
+  }
+}
+
+fn decode_hex(val: &str) -> Result<Vec<u8>> {
+  Ok(hex::decode(val.strip_prefix("0x").unwrap_or(val))?)
+}
+
+/// Storage key for a `Blake2_128Concat, Blake2_128Concat` double map.
+fn double_map_key(pallet: &str, storage: &str, key1: &[u8], key2: &[u8]) -> Vec<u8> {
+  let mut key = Vec::with_capacity(32 + 16 + key1.len() + 16 + key2.len());
+  key.extend_from_slice(&twox_128(pallet.as_bytes()));
+  key.extend_from_slice(&twox_128(storage.as_bytes()));
+  key.extend_from_slice(&blake2_128(key1));
+  key.extend_from_slice(key1);
+  key.extend_from_slice(&blake2_128(key2));
+  key.extend_from_slice(key2);
+  key
+}
+
+/// Storage key of a confidential account's encrypted balance.
+pub fn account_balance_key(account: &[u8; 32], asset_id: &[u8; 16]) -> Vec<u8> {
+  double_map_key("ConfidentialAsset", "AccountBalance", account, asset_id)
+}
+
+/// Storage key of a confidential account's encrypted incoming balance.
+pub fn incoming_balance_key(account: &[u8; 32], asset_id: &[u8; 16]) -> Vec<u8> {
+  double_map_key("ConfidentialAsset", "IncomingBalance", account, asset_id)
+}
+
+/// Storage key of a confidential transaction leg.
+pub fn transaction_leg_key(transaction_id: u64, leg_id: u32) -> Vec<u8> {
+  double_map_key(
+    "ConfidentialAsset",
+    "TransactionLegs",
+    &transaction_id.to_le_bytes(),
+    &leg_id.to_le_bytes(),
+  )
+}
@@ -1,10 +1,16 @@
 use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "backend")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "backend")]
+use std::time::Duration;
 use uuid::Uuid;
 
 use serde::{Deserialize, Serialize};
-use serde_hex::{SerHex, StrictPfx};
+use serde_hex::{SerHex, SerHexSeq, StrictPfx};
 
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use codec::{Decode, Encode};
 
@@ -13,7 +19,7 @@ use polymesh_api::{
   client::{
     basic_types::{AccountId, IdentityId},
     block::{EventRecord, ExtrinsicV4, Header, Phase},
-    EnumInfo, ExtrinsicResult,
+    BlockHash, EnumInfo, ExtrinsicResult,
   },
   types::{
     pallet_confidential_asset::{
@@ -34,10 +40,16 @@ use polymesh_api::{
 #[cfg(feature = "backend")]
 use confidential_assets::{Balance, CipherText, ElgamalPublicKey};
 
-use crate::error::Result;
+use crate::balance_conflicts::BalanceSource;
+use crate::error::{Error, Result};
+#[cfg(feature = "backend")]
+use crate::formatting::AmountFormatter;
 use crate::proofs::{
-  AccountWithSecret, PublicKey, SenderProof, TransferProofs, UpdateAccountAsset,
+  Account, AccountAsset, AccountWithSecret, AddAsset, BalanceAction, PublicKey, Receipt,
+  SenderProof, SenderProofVerifyResult, TransferProofs, UpdateAccountAsset, UuidBytes,
 };
+use crate::raw_events::BlockEvents;
+use crate::valuation::PortfolioValuation;
 
 pub fn scale_convert<T1: Encode, T2: Decode>(t1: &T1) -> T2 {
   let buf = t1.encode();
@@ -52,6 +64,11 @@ pub fn auditor_account_to_key(account: &AuditorAccount) -> ElgamalPublicKey {
   scale_convert(account)
 }
 
+/// Hex encode a DID, as stored on the account record.
+pub fn did_to_hex(did: &IdentityId) -> String {
+  format!("0x{}", hex::encode(did.0))
+}
+
 pub fn join_auditors(
   mediators: &[IdentityId],
   auditors: &[PublicKey],
@@ -79,8 +96,6 @@ pub struct SettlementRecord {
   pub settlement_id: u32,
   /// Venue id.
   pub venue_id: u32,
-  /// Legs.
-  pub legs: String,
   /// Memo.
   pub memo: Option<String>,
 
@@ -93,7 +108,6 @@ impl SettlementRecord {
     Ok(Self {
       settlement_id: tx.transaction_id.0 as _,
       venue_id: tx.venue_id.0 as _,
-      legs: serde_json::to_string(&tx.legs)?,
       memo: if tx.memo.len() > 0 {
         Some(tx.memo.clone())
       } else {
@@ -104,6 +118,647 @@ impl SettlementRecord {
   }
 }
 
+/// Settlement leg.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SettlementLeg {
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: u32,
+  /// Leg id.
+  #[schema(example = 0)]
+  pub leg_id: u32,
+  /// Leg details.
+  #[serde(flatten)]
+  pub leg: TransactionLegDetails,
+  /// Sender from the contacts directory.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sender_contact: Option<Contact>,
+  /// Receiver from the contacts directory.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub receiver_contact: Option<Contact>,
+}
+
+#[cfg(feature = "backend")]
+impl SettlementLeg {
+  pub fn from_tx(tx: &TransactionCreated) -> Vec<Self> {
+    tx.legs
+      .iter()
+      .enumerate()
+      .map(|(leg_id, leg)| Self {
+        settlement_id: tx.transaction_id.0 as _,
+        leg_id: leg_id as _,
+        leg: leg.clone(),
+        ..Default::default()
+      })
+      .collect()
+  }
+
+  /// Rebuild the legs from rows ordered by `(settlement_id, leg_id)`.
+  pub fn from_rows(rows: Vec<SettlementLegRow>) -> Result<Vec<Self>> {
+    let mut legs: Vec<Self> = Vec::new();
+    for row in rows {
+      let leg = match legs.last_mut() {
+        Some(leg) if leg.settlement_id == row.settlement_id && leg.leg_id == row.leg_id => leg,
+        _ => {
+          legs.push(Self {
+            settlement_id: row.settlement_id,
+            leg_id: row.leg_id,
+            leg: TransactionLegDetails {
+              assets_and_auditors: Default::default(),
+              sender: PublicKey::from_str(&row.sender)?,
+              receiver: PublicKey::from_str(&row.receiver)?,
+              mediators: serde_json::from_str(&row.mediators)?,
+            },
+            ..Default::default()
+          });
+          legs.last_mut().expect("Just added")
+        }
+      };
+      if let Some(asset_id) = &row.asset_id {
+        let auditors = leg
+          .leg
+          .assets_and_auditors
+          .entry(Uuid::parse_str(asset_id).map_err(|_| Error::other("Invalid asset id"))?)
+          .or_default();
+        if let Some(auditor) = &row.auditor {
+          auditors.insert(PublicKey::from_str(auditor)?);
+        }
+      }
+    }
+    Ok(legs)
+  }
+}
+
+/// Confidential transaction leg queried from the chain.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ChainTransactionLeg {
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: u32,
+  /// Leg id.
+  #[schema(example = 0)]
+  pub leg_id: u32,
+  /// Block hash the leg was read at.  Only set with a storage proof.
+  #[schema(example = json!(null))]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub block_hash: Option<String>,
+  /// Leg details.
+  #[serde(flatten)]
+  pub leg: TransactionLegDetails,
+  /// Storage proof of the leg, if requested.
+  #[cfg(feature = "storage_proof")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub proof: Option<crate::StorageReadProof>,
+}
+
+/// Settlement leg joined with one of its assets and auditors.
+#[cfg(feature = "backend")]
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct SettlementLegRow {
+  pub settlement_id: u32,
+  pub leg_id: u32,
+  pub sender: String,
+  pub receiver: String,
+  pub mediators: String,
+  pub asset_id: Option<String>,
+  pub auditor: Option<String>,
+}
+
+/// Settlement leg filter.  All filters must match.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, IntoParams)]
+pub struct SettlementLegFilter {
+  /// Only legs of this settlement.
+  pub settlement_id: Option<u32>,
+  /// Only legs sending from this confidential account.
+  pub sender: Option<String>,
+  /// Only legs sending to this confidential account.
+  pub receiver: Option<String>,
+  /// Only legs sending from or to this confidential account.
+  pub account: Option<String>,
+  /// Only legs with this auditor.
+  pub auditor: Option<String>,
+  /// Only legs transferring this asset.
+  pub asset_id: Option<Uuid>,
+}
+
+/// Settlement with its legs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SettlementDetails {
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: u32,
+  /// Venue id.
+  #[schema(example = 1)]
+  pub venue_id: u32,
+  /// Settlement legs.
+  pub legs: Vec<SettlementLeg>,
+  /// Memo.
+  #[schema(example = json!(null))]
+  pub memo: Option<String>,
+  /// Latest status annotation.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub annotation: Option<SettlementAnnotation>,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+impl SettlementDetails {
+  pub fn from_record(rec: &SettlementRecord, legs: Vec<SettlementLeg>) -> Self {
+    Self {
+      settlement_id: rec.settlement_id,
+      venue_id: rec.venue_id,
+      legs,
+      memo: rec.memo.clone(),
+      annotation: None,
+      created_at: rec.created_at,
+    }
+  }
+}
+
+/// Settlement filter.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, IntoParams)]
+pub struct SettlementFilter {
+  /// Only settlements with this latest annotation status.
+  pub status: Option<SettlementAnnotationStatus>,
+}
+
+/// Status of a settlement annotation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementAnnotationStatus {
+  /// The settlement is disputed.
+  #[default]
+  Disputed,
+  /// The settlement is being investigated.
+  UnderInvestigation,
+  /// The dispute was resolved.
+  Resolved,
+}
+
+impl SettlementAnnotationStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Disputed => "disputed",
+      Self::UnderInvestigation => "under_investigation",
+      Self::Resolved => "resolved",
+    }
+  }
+}
+
+/// Status annotation of a settlement.  Only stored locally.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SettlementAnnotation {
+  /// Annotation id.
+  #[schema(example = 1)]
+  pub annotation_id: i64,
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: i64,
+  /// Status: `disputed`, `under_investigation` or `resolved`.
+  #[schema(example = "disputed")]
+  pub status: String,
+  /// Notes.
+  #[schema(example = "Receiver reports the wrong amount")]
+  pub notes: Option<String>,
+  /// User that added the annotation.
+  #[schema(example = "alice")]
+  pub author: String,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Annotate a settlement.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateSettlementAnnotation {
+  /// Status.
+  pub status: SettlementAnnotationStatus,
+  /// Notes.
+  #[schema(example = "Receiver reports the wrong amount")]
+  #[serde(default)]
+  pub notes: Option<String>,
+}
+
+/// An account's leg of a pending settlement.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PendingSettlementLeg {
+  /// Leg id.
+  #[schema(example = 0)]
+  pub leg_id: u32,
+  /// The account's role in the leg.
+  pub role: TransactionParty,
+  /// Has the account affirmed the leg.
+  #[schema(example = false)]
+  pub affirmed: bool,
+  /// Leg details.
+  #[serde(flatten)]
+  pub leg: TransactionLegDetails,
+  /// The leg's asset amounts.  Only available after the sender affirmed, when the
+  /// account's secret key is stored here.
+  #[schema(example = json!(null))]
+  pub amounts: Option<Vec<TransactionAssetAmount>>,
+}
+
+/// Pending settlement involving an account.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PendingSettlement {
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: u32,
+  /// Venue id.
+  #[schema(example = 1)]
+  pub venue_id: u32,
+  /// Memo.
+  #[schema(example = json!(null))]
+  pub memo: Option<String>,
+  /// Affirmations still needed before the settlement can be executed.
+  #[schema(example = 2)]
+  pub pending_affirms: u32,
+  /// The legs involving the account.
+  pub legs: Vec<PendingSettlementLeg>,
+}
+
+/// Auditor verification of a leg's sender proof.
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+pub struct LegAuditorVerifyResult {
+  /// Auditor's Elgamal public key.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub auditor: PublicKey,
+  /// Auditor id (index in the asset's auditors of the leg).
+  #[schema(example = 0)]
+  pub auditor_id: u32,
+  /// Verify result.
+  pub result: SenderProofVerifyResult,
+}
+
+/// Verification of a leg's sender proof for one asset.
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+pub struct LegAssetVerifyResult {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Sender proof verified against the sender's balance when the leg was affirmed.
+  pub sender: SenderProofVerifyResult,
+  /// Receiver verification, if the receiver's secret key is stored here.
+  pub receiver: Option<SenderProofVerifyResult>,
+  /// Verification by the auditors with secret keys stored here.
+  pub auditors: Vec<LegAuditorVerifyResult>,
+}
+
+/// Verification of a settlement leg.
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+pub struct SettlementLegVerifyReport {
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: u32,
+  /// Leg id.
+  #[schema(example = 0)]
+  pub leg_id: u32,
+  /// Are all proofs of the leg valid.
+  #[schema(example = true)]
+  pub is_valid: bool,
+  /// Per-asset results.
+  pub assets: Vec<LegAssetVerifyResult>,
+}
+
+#[cfg(feature = "backend")]
+impl SettlementLegVerifyReport {
+  pub fn new(settlement_id: u32, leg_id: u32, assets: Vec<LegAssetVerifyResult>) -> Self {
+    let is_valid = assets.iter().all(|asset| {
+      asset.sender.is_valid()
+        && asset.receiver.iter().all(|res| res.is_valid())
+        && asset
+          .auditors
+          .iter()
+          .all(|auditor| auditor.result.is_valid())
+    });
+    Self {
+      settlement_id,
+      leg_id,
+      is_valid,
+      assets,
+    }
+  }
+}
+
+/// Verify a settlement leg's sender proofs as an auditor.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AuditorVerifyLegRequest {
+  /// The auditor account.  Must be a local confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub auditor: PublicKey,
+  /// Expected asset amounts.  The decrypted amounts are checked against them.
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub amounts: Option<Vec<TransactionAssetAmount>>,
+}
+
+/// Auditor verification of a leg's sender proof for one asset.
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+pub struct AuditorVerifyLegResult {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Auditor id (index in the asset's auditors of the leg).
+  #[schema(example = 0)]
+  pub auditor_id: u32,
+  /// Verify result.
+  pub result: SenderProofVerifyResult,
+}
+
+/// Counterparty contact.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct Contact {
+  /// Confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: String,
+  /// Display name.
+  #[schema(example = "Acme treasury")]
+  pub display_name: String,
+  /// Organization.
+  #[schema(example = "Acme Corp.")]
+  pub organization: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Add a counterparty contact.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateContact {
+  /// Confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// Display name.
+  #[schema(example = "Acme treasury")]
+  pub display_name: String,
+  /// Organization.
+  #[schema(example = "Acme Corp.")]
+  #[serde(default)]
+  pub organization: Option<String>,
+}
+
+/// Update a counterparty contact.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct UpdateContact {
+  /// Display name.
+  #[schema(example = "Acme treasury")]
+  pub display_name: String,
+  /// Organization.
+  #[schema(example = "Acme Corp.")]
+  #[serde(default)]
+  pub organization: Option<String>,
+}
+
+/// Payment request (invoice) of a receiver.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct Invoice {
+  /// Invoice id.
+  #[schema(example = 1)]
+  pub invoice_id: i64,
+  /// Shareable reference.  Used as the settlement memo.
+  #[schema(example = "0x5ba1d3b3a0a5f6f5c1f0e4f7b0c0d1a2b3c4d5e6f708192a3b4c5d6e7f809102")]
+  pub reference: String,
+  /// Receiver's confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub receiver: String,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Requested amount.
+  #[schema(example = 1000)]
+  pub amount: i64,
+  /// Memo for the sender (not sent on-chain).
+  #[schema(example = "Order 1234")]
+  pub memo: Option<String>,
+  /// Status: `open`, `settling`, `paid` or `cancelled`.
+  #[schema(example = "open")]
+  pub status: String,
+  /// Settlement paying the invoice.
+  #[schema(example = json!(null))]
+  pub settlement_id: Option<i64>,
+
+  pub expires_at: chrono::NaiveDateTime,
+  pub paid_at: Option<chrono::NaiveDateTime>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+impl Invoice {
+  pub fn is_expired(&self) -> bool {
+    self.expires_at < chrono::Utc::now().naive_utc()
+  }
+}
+
+/// Create a payment request (invoice).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateInvoice {
+  /// Receiver's confidential account.  Must be one of our accounts.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub receiver: PublicKey,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Requested amount.
+  #[schema(example = 1000)]
+  pub amount: u64,
+  /// Seconds until the invoice expires (default: one day).
+  #[schema(example = 86400)]
+  #[serde(default)]
+  pub expires_in: Option<u64>,
+  /// Memo for the sender (not sent on-chain).
+  #[schema(example = "Order 1234")]
+  #[serde(default)]
+  pub memo: Option<String>,
+}
+
+/// Pay an invoice, by creating a settlement from the sender to the invoice's receiver.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PayInvoice {
+  /// Signer of the transaction.
+  #[schema(example = "Alice")]
+  pub signer: String,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+  /// Confidential venue.
+  #[schema(example = 1)]
+  pub venue_id: u64,
+  /// Set of venue mediator identities for the leg.
+  #[schema(example = json!([]))]
+  #[serde(default)]
+  pub mediators: BTreeSet<IdentityId>,
+  /// Set of venue auditor Elgamal public keys for the leg.
+  #[schema(example = json!(["0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114"]))]
+  #[serde(default)]
+  pub auditors: BTreeSet<PublicKey>,
+}
+
+impl PayInvoice {
+  /// Settlement paying `invoice` from `sender`.  The invoice reference is the memo.
+  pub fn settlement(
+    &self,
+    sender: PublicKey,
+    invoice: &Invoice,
+  ) -> Result<CreateConfidentialSettlement> {
+    Ok(CreateConfidentialSettlement {
+      signer: self.signer.clone(),
+      finalize: self.finalize,
+      dry_run: false,
+      legs: vec![ConfidentialSettlementLeg {
+        assets: [invoice.asset_id].into(),
+        sender,
+        receiver: PublicKey::from_str(&invoice.receiver)?,
+        mediators: self.mediators.clone(),
+        auditors: self.auditors.clone(),
+      }],
+      memo: invoice.reference.clone(),
+    })
+  }
+}
+
+/// Deposit account.  Incoming transfers are credited automatically (see `Deposit`).
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct DepositAccount {
+  /// Confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: String,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Register a deposit account.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateDepositAccount {
+  /// Confidential account.  Must be one of our accounts.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+}
+
+/// Account with its incoming balances applied automatically by the chain watcher.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AutoApplyAccount {
+  /// Confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: String,
+  /// Signer of the `apply_incoming_balance` transactions.
+  #[schema(example = "Alice")]
+  pub signer: String,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Apply an account's incoming balances automatically.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateAutoApplyAccount {
+  /// Confidential account.  Must be one of our accounts.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// Signer of the `apply_incoming_balance` transactions.  Must be the signing key of the
+  /// account's identity.
+  #[schema(example = "Alice")]
+  pub signer: String,
+}
+
+/// Default signer of one of our accounts.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AccountSigner {
+  /// Confidential account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: String,
+  /// Signer used when the account's transaction requests omit `signer`.
+  #[schema(example = "Alice")]
+  pub signer: String,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Set the default signer of one of our accounts.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateAccountSigner {
+  /// Confidential account.  Must be one of our accounts.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// Signer of the account's transactions.  Must be a key of the account's identity, once
+  /// the account is on-chain.
+  #[schema(example = "Alice")]
+  pub signer: String,
+}
+
+/// Transfer credited to a deposit account.
+///
+/// Credited when the sender affirms the leg, before the settlement is executed.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct Deposit {
+  /// Deposit id.
+  #[schema(example = 1)]
+  pub deposit_id: i64,
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: i64,
+  /// Leg id.
+  #[schema(example = 0)]
+  pub leg_id: i64,
+  /// Deposit account.
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: String,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Decrypted amount.
+  #[schema(example = 1000)]
+  pub amount: i64,
+  /// Internal reference credited: the memo of the invoice paid by the settlement, or the
+  /// settlement memo.
+  #[schema(example = "user-1234")]
+  pub reference: Option<String>,
+  /// Invoice paid by the settlement.
+  #[schema(example = json!(null))]
+  pub invoice_id: Option<i64>,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Add a deposit.
+#[derive(Clone, Debug, Default)]
+pub struct AddDeposit {
+  pub settlement_id: i64,
+  pub leg_id: i64,
+  pub confidential_account: String,
+  pub asset_id: Uuid,
+  pub amount: i64,
+  pub reference: Option<String>,
+  pub invoice_id: Option<i64>,
+}
+
+/// Local confidential account and its balances.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PortfolioAccount {
+  /// Confidential account.
+  pub account: Account,
+  /// Decrypted balances.
+  pub balances: Vec<AccountAsset>,
+}
+
+/// All local confidential accounts of an identity.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct IdentityPortfolio {
+  /// Identity (DID).
+  #[schema(example = "0x0600000000000000000000000000000000000000000000000000000000000000")]
+  pub did: String,
+  /// The identity's local confidential accounts.
+  pub accounts: Vec<PortfolioAccount>,
+  /// Pending settlements with a leg sending from or to one of the accounts.
+  pub pending_settlements: Vec<SettlementDetails>,
+  /// Aggregated value of the balances, if requested.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub valuation: Option<PortfolioValuation>,
+}
+
 /// Settlement event record.
 #[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -112,13 +767,15 @@ pub struct SettlementEventRecord {
   pub settlement_id: u32,
   /// Settlement event.
   pub event: String,
+  /// Block number.  Not set for events recorded before it was added.
+  pub block_number: Option<u32>,
 
   pub created_at: chrono::NaiveDateTime,
 }
 
 #[cfg(feature = "backend")]
 impl SettlementEventRecord {
-  pub fn from_events(processed_events: &ProcessedEvents) -> Result<Vec<Self>> {
+  pub fn from_events(block_number: u32, processed_events: &ProcessedEvents) -> Result<Vec<Self>> {
     let mut events = Vec::new();
     for ev in &processed_events.0 {
       match ev {
@@ -134,6 +791,7 @@ impl SettlementEventRecord {
         | ProcessedEvent::ConfidentialTransactionExecuted { transaction_id } => events.push(Self {
           settlement_id: transaction_id.0 as _,
           event: serde_json::to_string(ev)?,
+          block_number: Some(block_number),
           ..Default::default()
         }),
         _ => (),
@@ -143,6 +801,455 @@ impl SettlementEventRecord {
   }
 }
 
+/// Build an audit report of an asset's settlement sender proofs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AuditReportRequest {
+  /// The issuer's auditor account.  Must be a local confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub auditor: PublicKey,
+  /// First block (inclusive).
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub from_block: Option<u32>,
+  /// Last block (inclusive).
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub to_block: Option<u32>,
+  /// Only proofs recorded at or after this time (UTC).
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub from_date: Option<chrono::NaiveDateTime>,
+  /// Only proofs recorded before this time (UTC).
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub to_date: Option<chrono::NaiveDateTime>,
+}
+
+/// Auditor verification of one sender proof.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AuditedProof {
+  /// Settlement id.
+  #[schema(example = 1)]
+  pub settlement_id: u32,
+  /// Leg id.
+  #[schema(example = 0)]
+  pub leg_id: u32,
+  /// Block number.
+  #[schema(example = 1000)]
+  pub block_number: Option<u32>,
+  /// Is the sender proof valid.
+  #[schema(example = true)]
+  pub is_valid: bool,
+  /// The decrypted transaction amount.
+  #[schema(example = 1000)]
+  pub amount: Option<u64>,
+  /// Formatted amount, if requested.
+  #[schema(example = "1,000.000000")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub formatted_amount: Option<String>,
+  /// If `is_valid` is false, then provide an error message.
+  #[schema(example = json!(null))]
+  pub err_msg: Option<String>,
+}
+
+/// Audit report of an asset's settlement sender proofs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AuditReport {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// The auditor account used to verify the proofs.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub auditor: PublicKey,
+  /// First block of the report (inclusive).
+  pub from_block: Option<u32>,
+  /// Last block of the report (inclusive).
+  pub to_block: Option<u32>,
+  /// Start time of the report (UTC).
+  pub from_date: Option<chrono::NaiveDateTime>,
+  /// End time of the report (UTC).
+  pub to_date: Option<chrono::NaiveDateTime>,
+  /// Number of verified sender proofs.
+  #[schema(example = 10)]
+  pub proof_count: u32,
+  /// Number of valid sender proofs.
+  #[schema(example = 10)]
+  pub valid_count: u32,
+  /// Number of invalid sender proofs.
+  #[schema(example = 0)]
+  pub invalid_count: u32,
+  /// Number of sender proofs for legs that the auditor isn't an auditor of.
+  #[schema(example = 0)]
+  pub skipped_count: u32,
+  /// Total amount of the valid sender proofs.
+  #[schema(example = 10000)]
+  pub total_volume: u64,
+  /// Formatted total volume, if requested.
+  #[schema(example = "10,000.000000")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub formatted_total_volume: Option<String>,
+  /// Each verified sender proof.
+  pub proofs: Vec<AuditedProof>,
+
+  pub generated_at: chrono::NaiveDateTime,
+}
+
+#[cfg(feature = "backend")]
+impl AuditReport {
+  pub fn new(asset_id: Uuid, req: &AuditReportRequest) -> Self {
+    Self {
+      asset_id,
+      auditor: req.auditor.clone(),
+      from_block: req.from_block,
+      to_block: req.to_block,
+      from_date: req.from_date,
+      to_date: req.to_date,
+      generated_at: chrono::Utc::now().naive_utc(),
+      ..Default::default()
+    }
+  }
+
+  /// Add a verified sender proof.
+  pub fn add_proof(
+    &mut self,
+    settlement_id: u32,
+    leg_id: u32,
+    block_number: Option<u32>,
+    res: SenderProofVerifyResult,
+  ) {
+    self.proof_count += 1;
+    if res.is_valid() {
+      self.valid_count += 1;
+      self.total_volume = self
+        .total_volume
+        .saturating_add(res.amount().unwrap_or_default());
+    } else {
+      self.invalid_count += 1;
+    }
+    self.proofs.push(AuditedProof {
+      settlement_id,
+      leg_id,
+      block_number,
+      is_valid: res.is_valid(),
+      amount: res.amount(),
+      formatted_amount: None,
+      err_msg: res.err_msg().map(|msg| msg.to_string()),
+    });
+  }
+
+  /// Add the formatted amounts.
+  pub fn format_amounts(&mut self, formatter: &AmountFormatter) {
+    let asset_id = Some(self.asset_id);
+    self.formatted_total_volume = Some(formatter.format(asset_id, self.total_volume));
+    for proof in &mut self.proofs {
+      proof.formatted_amount = proof
+        .amount
+        .map(|amount| formatter.format(asset_id, amount));
+    }
+  }
+}
+
+/// Audit report with a signed receipt over its JSON encoding.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SignedAuditReport {
+  pub report: AuditReport,
+  /// Receipt with `request_hash` set to the Blake2-256 hash of the report's JSON.
+  /// Only available when receipt signing is enabled.
+  pub receipt: Option<Receipt>,
+}
+
+/// Webhook outbox record.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct WebhookOutboxRecord {
+  /// Outbox id.
+  #[schema(example = 1)]
+  pub id: i64,
+  /// Registered endpoint, or `None` for `WEBHOOK_URL`.
+  #[schema(example = json!(null))]
+  pub endpoint_id: Option<i64>,
+  /// Replayed by the endpoint's backfill.
+  #[schema(example = false)]
+  pub backfill: bool,
+  /// Webhook event (JSON).
+  pub event: String,
+  /// Delivery status: `pending`, `delivered` or `dead`.
+  #[schema(example = "pending")]
+  pub status: String,
+  /// Number of failed delivery attempts.
+  #[schema(example = 0)]
+  pub attempts: i64,
+  /// Error from the last failed delivery attempt.
+  #[schema(example = json!(null))]
+  pub last_error: Option<String>,
+  /// When the next delivery attempt is due.
+  pub next_attempt_at: chrono::NaiveDateTime,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Webhooks waiting for delivery in the outbox.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct WebhookBacklog {
+  /// Number of pending webhooks.
+  pub pending: i64,
+  /// When the oldest pending webhook was queued.
+  pub oldest_created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Registered webhook endpoint.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct WebhookEndpoint {
+  /// Endpoint id.
+  #[schema(example = 1)]
+  pub endpoint_id: i64,
+  /// Url the webhooks are posted to.
+  #[schema(example = "https://example.com/webhooks")]
+  pub url: String,
+  /// Status: `backfilling` (replaying stored chain events) or `live`.
+  #[schema(example = "live")]
+  pub status: String,
+  /// First block replayed by the backfill.
+  #[schema(example = json!(null))]
+  pub backfill_from: Option<i64>,
+  /// Chain events of blocks after this one are sent live, older ones are only replayed by
+  /// the backfill.
+  #[schema(example = 1000)]
+  pub live_from: i64,
+  /// Last block replayed by the backfill.
+  #[schema(example = json!(null))]
+  pub backfill_cursor: Option<i64>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Register a webhook endpoint.
+///
+/// Without a backfill the endpoint only receives new events.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateWebhookEndpoint {
+  /// Url to post the webhooks to.
+  #[schema(example = "https://example.com/webhooks")]
+  pub url: String,
+  /// Replay the stored chain events from this block.
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub from_block: Option<u32>,
+  /// Replay the stored chain events from the block that created this settlement.
+  #[schema(example = json!(null))]
+  #[serde(default)]
+  pub from_settlement: Option<u32>,
+}
+
+/// Ledger entry.
+///
+/// Each balance update of a local confidential account is recorded as a debit and
+/// a credit entry.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct LedgerEntry {
+  /// Entry id.
+  #[schema(example = 1)]
+  pub id: i64,
+  /// Transaction hash.
+  #[schema(example = "0xea549dcdadacb5678e37a336e44c581ade562b696159bf8fd846fee7e7fe1dc3")]
+  pub tx_hash: String,
+  /// Block number.
+  #[schema(example = 1)]
+  pub block_number: u32,
+  /// Index of the balance update in the transaction's processed events.
+  #[schema(example = 0)]
+  pub event_index: u32,
+  /// Confidential account (hex) or system account (`issuance`, `burn`, `counterparty`).
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub ledger_account: String,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Debit amount.
+  #[schema(example = 1000)]
+  pub debit: i64,
+  /// Credit amount.
+  #[schema(example = 0)]
+  pub credit: i64,
+  /// Cause of the balance update: `mint`, `burn`, `settlement` or `apply_incoming`.
+  #[schema(example = "mint")]
+  pub cause: String,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Trial balance of one ledger account and asset.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct TrialBalance {
+  /// Confidential account (hex) or system account (`issuance`, `burn`, `counterparty`).
+  #[schema(example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub ledger_account: String,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Total debits.
+  #[schema(example = 1000)]
+  pub debits: i64,
+  /// Total credits.
+  #[schema(example = 0)]
+  pub credits: i64,
+  /// Debits minus credits.
+  #[schema(example = 1000)]
+  pub balance: i64,
+}
+
+/// Maintenance mode.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct MaintenanceMode {
+  /// Is maintenance mode enabled.
+  #[schema(example = false)]
+  pub enabled: bool,
+  /// Seconds clients should wait before retrying (`Retry-After` header).
+  #[schema(example = 60)]
+  pub retry_after: i64,
+
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Chain watcher progress.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct WatcherStatus {
+  /// Latest block seen by the chain watcher.
+  #[schema(example = 1000)]
+  pub head_block: u32,
+  /// Latest block processed by the chain watcher.
+  #[schema(example = 900)]
+  pub processed_block: u32,
+  /// Hash of the latest processed block.
+  #[schema(example = "0x6a2f5e2d3f8b7d8e4c1f3b0a7d2e9c5b1a4f8e3d2c7b6a5f4e3d2c1b0a9f8e7d")]
+  pub processed_hash: Option<String>,
+  /// Is the watcher catching up on missed blocks.
+  #[schema(example = true)]
+  pub catching_up: bool,
+  /// Number of times the watcher reconnected to the node.
+  #[schema(example = 0)]
+  pub reconnects: u32,
+  /// Error that caused the last reconnect.
+  pub last_error: Option<String>,
+  pub last_reconnect_at: Option<chrono::NaiveDateTime>,
+
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+impl WatcherStatus {
+  /// Number of blocks the watcher is behind the chain head.
+  pub fn blocks_behind(&self) -> u32 {
+    self.head_block.saturating_sub(self.processed_block)
+  }
+}
+
+/// Lease of a background task, held by the instance running it.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct Lease {
+  /// Background task.
+  #[schema(example = "chain_watcher")]
+  pub name: String,
+  /// Instance holding the lease.
+  #[schema(example = "api-blue-1")]
+  pub holder: String,
+
+  pub acquired_at: chrono::NaiveDateTime,
+  /// The lease is free after this time, unless it is renewed.
+  pub expires_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Periodic background job, run by the scheduler.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ScheduledJob {
+  /// Job name.
+  #[schema(example = "proof_pools")]
+  pub name: String,
+  /// Seconds between successful runs.
+  #[schema(example = 30)]
+  pub interval_secs: i64,
+  /// Next run, sooner after a failed run.
+  pub next_run_at: chrono::NaiveDateTime,
+  pub last_run_at: Option<chrono::NaiveDateTime>,
+  /// Error of the last run, if it failed.
+  #[schema(example = json!(null))]
+  pub last_error: Option<String>,
+  /// Consecutive failed runs.
+  #[schema(example = 0)]
+  pub attempts: i64,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Enable or disable maintenance mode.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SetMaintenanceMode {
+  /// Enable maintenance mode.
+  #[schema(example = true)]
+  pub enabled: bool,
+  /// Seconds clients should wait before retrying (`Retry-After` header).
+  #[schema(example = 60)]
+  #[serde(default = "default_retry_after")]
+  pub retry_after: u32,
+  /// When enabling, wait up to this many seconds for in-flight requests to finish.
+  #[schema(example = 30)]
+  #[serde(default = "default_drain_timeout")]
+  pub drain_timeout: u32,
+}
+
+fn default_retry_after() -> u32 {
+  60
+}
+
+fn default_drain_timeout() -> u32 {
+  30
+}
+
+/// Maintenance mode status.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct MaintenanceStatus {
+  /// Is maintenance mode enabled.
+  #[schema(example = false)]
+  pub enabled: bool,
+  /// Seconds clients should wait before retrying (`Retry-After` header).
+  #[schema(example = 60)]
+  pub retry_after: i64,
+  /// Number of mutating requests still in-flight.
+  #[schema(example = 0)]
+  pub in_flight: usize,
+}
+
+/// Health of a Polymesh node.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct NodeStatus {
+  /// Node url.
+  #[schema(example = "ws://localhost:9944/")]
+  pub url: String,
+  /// Is the node used for requests and block subscriptions.
+  #[schema(example = true)]
+  pub current: bool,
+  /// Did the last health check pass.
+  #[schema(example = true)]
+  pub healthy: bool,
+  /// Chain head reported by the node.
+  #[schema(example = 1000)]
+  pub best_block: Option<u32>,
+  /// Duration of the last health check in milliseconds.
+  #[schema(example = 12)]
+  pub latency_ms: Option<u64>,
+  /// Error of the last failed health check.
+  pub last_error: Option<String>,
+  pub checked_at: Option<chrono::NaiveDateTime>,
+}
+
 /// Confidential asset transaction leg details.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct TransactionLegDetails {
@@ -162,8 +1269,43 @@ pub struct TransactionLegDetails {
   pub mediators: BTreeSet<IdentityId>,
 }
 
+impl Encode for TransactionLegDetails {
+  fn encode_to<T: codec::Output + ?Sized>(&self, dest: &mut T) {
+    let assets_and_auditors = self
+      .assets_and_auditors
+      .iter()
+      .map(|(asset_id, auditors)| (asset_id.into_bytes(), auditors))
+      .collect::<Vec<_>>();
+    assets_and_auditors.encode_to(dest);
+    self.sender.encode_to(dest);
+    self.receiver.encode_to(dest);
+    self.mediators.encode_to(dest);
+  }
+}
+
+#[cfg(feature = "backend")]
+impl TransactionLegDetails {
+  pub fn from_leg(leg: &TransactionLeg) -> Self {
+    Self {
+      assets_and_auditors: leg
+        .auditors
+        .iter()
+        .map(|(id, keys)| {
+          (
+            Uuid::from_bytes(*id),
+            keys.iter().map(|k| scale_convert(k)).collect(),
+          )
+        })
+        .collect(),
+      sender: scale_convert(&leg.sender),
+      receiver: scale_convert(&leg.receiver),
+      mediators: leg.mediators.clone().into(),
+    }
+  }
+}
+
 /// A Confidential asset transaction was created.
-#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, Encode)]
 pub struct TransactionCreated {
   /// Confidential venue id.
   #[schema(value_type = u64)]
@@ -180,7 +1322,7 @@ pub struct TransactionCreated {
 }
 
 /// The transaction party.
-#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema, Encode)]
 pub enum TransactionParty {
   #[default]
   Sender,
@@ -189,7 +1331,7 @@ pub enum TransactionParty {
 }
 
 /// A Confidential asset transaction was affirmed.
-#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Encode)]
 pub struct TransactionAffirmed {
   /// Confidential transaction id.
   #[schema(value_type = u64)]
@@ -207,7 +1349,7 @@ pub struct TransactionAffirmed {
 }
 
 /// Type of balance update.
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema, Encode)]
 pub enum BalanceUpdateAction {
   #[default]
   Withdraw,
@@ -216,12 +1358,13 @@ pub enum BalanceUpdateAction {
 }
 
 /// A Confidential account's balance has updated.
-#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Encode)]
 pub struct BalanceUpdated {
   /// Confidential account.
   #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
   pub account: PublicKey,
   /// Asset id.
+  #[codec(encoded_as = "UuidBytes")]
   pub asset_id: Uuid,
   /// The update action.
   pub action: BalanceUpdateAction,
@@ -261,7 +1404,7 @@ impl BalanceUpdated {
 }
 
 /// Processed event from the transaction.
-#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Encode)]
 pub enum ProcessedEvent {
   /// An identity was created.
   #[schema(value_type = Object, example = json!(Self::IdentityCreated(Default::default())))]
@@ -285,11 +1428,15 @@ pub enum ProcessedEvent {
   #[schema(value_type = u64)]
   ScheduleCreated(ScheduleId),
   /// A Confidential asset was created.
-  ConfidentialAssetCreated { asset_id: Uuid },
+  ConfidentialAssetCreated {
+    #[codec(encoded_as = "UuidBytes")]
+    asset_id: Uuid,
+  },
   /// A Confidential asset minted.
   ///
   /// (asset_id, amount minted, total_supply)
   ConfidentialAssetMinted {
+    #[codec(encoded_as = "UuidBytes")]
     asset_id: Uuid,
     amount: u64,
     total_supply: u64,
@@ -299,6 +1446,13 @@ pub enum ProcessedEvent {
     #[schema(value_type = u64)]
     venue_id: VenueId,
   },
+  /// A Confidential account was created.
+  ConfidentialAccountCreated {
+    #[schema(value_type = Object, example = json!(IdentityId::default()))]
+    did: IdentityId,
+    #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+    account: PublicKey,
+  },
   /// A Confidential account's balance was updated.
   ConfidentialAccountBalanceUpdated(BalanceUpdated),
   /// A Confidential asset transaction was created.
@@ -315,13 +1469,109 @@ pub enum ProcessedEvent {
   },
   /// A Confidential asset transaction was affirmed.
   ConfidentialTransactionAffirmed(TransactionAffirmed),
+  /// Chain event without a typed variant (yet), decoded generically.
+  #[schema(example = json!({"Other": {"pallet": "Settlement", "variant": "InstructionExecuted", "fields": [{"did": "0x01"}, 1]}}))]
+  Other {
+    /// Pallet of the event.
+    pallet: String,
+    /// Event name.
+    variant: String,
+    /// Event fields, as JSON.
+    #[schema(value_type = Object)]
+    fields: UntypedEventFields,
+  },
+  /// Chain event this release can't decode (i.e. added by a runtime upgrade).  The names
+  /// are from the runtime metadata.
+  #[schema(example = json!({"Raw": {"pallet_index": 50, "variant_index": 0, "pallet": "NewPallet", "variant": "NewEvent", "bytes": "0x01"}}))]
+  Raw {
+    /// Pallet index.
+    pallet_index: u8,
+    /// Event index in the pallet.
+    variant_index: u8,
+    /// Pallet name.
+    pallet: Option<String>,
+    /// Event name.
+    variant: Option<String>,
+    /// SCALE encoded event fields (hex).
+    bytes: String,
+  },
+}
+
+/// Pallets whose events aren't reported as untyped events (extrinsic results and fees are
+/// already part of the transaction result).
+const UNTYPED_EVENT_SKIP_PALLETS: &[&str] = &["System", "TransactionPayment"];
+
+/// JSON fields of an untyped chain event.  SCALE encoded as the JSON string.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UntypedEventFields(pub serde_json::Value);
+
+impl Encode for UntypedEventFields {
+  fn encode_to<T: codec::Output + ?Sized>(&self, dest: &mut T) {
+    self.0.to_string().encode_to(dest);
+  }
+}
+
+impl ProcessedEvent {
+  /// Decode any chain event from its JSON representation (`{"Pallet": {"Variant": fields}}`).
+  ///
+  /// New chain events are reported as `Other` until they get a typed variant.  Returns
+  /// `None` for the skipped pallets.
+  pub fn untyped(event: &RuntimeEvent) -> Option<Self> {
+    let (pallet, event) = single_entry(serde_json::to_value(event).ok()?)?;
+    if UNTYPED_EVENT_SKIP_PALLETS.contains(&pallet.as_str()) {
+      return None;
+    }
+    let (variant, fields) = match event {
+      // Events without fields.
+      serde_json::Value::String(variant) => (variant, serde_json::Value::Null),
+      event => single_entry(event)?,
+    };
+    Some(Self::Other {
+      pallet,
+      variant,
+      fields: UntypedEventFields(fields),
+    })
+  }
+}
+
+/// The key and value of a JSON object with one entry.
+fn single_entry(value: serde_json::Value) -> Option<(String, serde_json::Value)> {
+  match value {
+    serde_json::Value::Object(map) if map.len() == 1 => map.into_iter().next(),
+    _ => None,
+  }
 }
 
 /// Processed events from the transaction.
-#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, Encode)]
 pub struct ProcessedEvents(pub Vec<ProcessedEvent>);
 
 impl ProcessedEvents {
+  /// Confidential venue created by the transaction.
+  pub fn created_venue_id(&self) -> Option<u64> {
+    self.0.iter().find_map(|event| match event {
+      ProcessedEvent::ConfidentialVenueCreated { venue_id } => Some(venue_id.0),
+      _ => None,
+    })
+  }
+
+  /// Confidential settlement created by the transaction.
+  pub fn created_settlement_id(&self) -> Option<u64> {
+    self.0.iter().find_map(|event| match event {
+      ProcessedEvent::ConfidentialTransactionCreated(created) => Some(created.transaction_id.0),
+      _ => None,
+    })
+  }
+
+  /// Confidential asset created by the transaction.
+  pub fn created_asset_id(&self) -> Option<Uuid> {
+    self.0.iter().find_map(|event| match event {
+      ProcessedEvent::ConfidentialAssetCreated { asset_id } => Some(*asset_id),
+      _ => None,
+    })
+  }
+
   /// Get ids from *Created events.
   pub fn from_events(events: &[EventRecord<RuntimeEvent>]) -> Result<Self> {
     let mut processed = Vec::new();
@@ -355,6 +1605,16 @@ impl ProcessedEvents {
             venue_id: *venue_id,
           });
         }
+        RuntimeEvent::ConfidentialAsset(ConfidentialAssetEvent::AccountCreated {
+          caller_did,
+          account,
+          ..
+        }) => {
+          processed.push(ProcessedEvent::ConfidentialAccountCreated {
+            did: *caller_did,
+            account: scale_convert(account),
+          });
+        }
         RuntimeEvent::ConfidentialAsset(ConfidentialAssetEvent::AssetCreated {
           asset_id, ..
         }) => {
@@ -431,21 +1691,7 @@ impl ProcessedEvents {
         }) => {
           let legs = legs
             .into_iter()
-            .map(|l| TransactionLegDetails {
-              assets_and_auditors: l
-                .auditors
-                .iter()
-                .map(|(id, keys)| {
-                  (
-                    Uuid::from_bytes(*id),
-                    keys.iter().map(|k| scale_convert(k)).collect(),
-                  )
-                })
-                .collect(),
-              sender: scale_convert(&l.sender),
-              receiver: scale_convert(&l.receiver),
-              mediators: l.mediators.clone().into(),
-            })
+            .map(TransactionLegDetails::from_leg)
             .collect();
           processed.push(ProcessedEvent::ConfidentialTransactionCreated(
             TransactionCreated {
@@ -522,13 +1768,100 @@ impl ProcessedEvents {
             ));
           }
         },
-        _ => (),
+        event => processed.extend(ProcessedEvent::untyped(event)),
       }
     }
     Ok(Self(processed))
   }
 }
 
+/// A processed event streamed to subscribers.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChainEvent {
+  /// Block number.
+  #[schema(example = 1)]
+  pub block_number: u32,
+  /// Transaction hash.
+  #[schema(example = "0x0000000000000000000000000000000000000000000000000000000000000000")]
+  pub tx_hash: String,
+  /// The event.
+  pub event: ProcessedEvent,
+  /// Confidential accounts (senders, receivers and auditors) involved in the event.
+  #[serde(default)]
+  pub accounts: BTreeSet<PublicKey>,
+  /// Assets involved in the event.
+  #[serde(default)]
+  pub asset_ids: BTreeSet<Uuid>,
+}
+
+#[cfg(feature = "backend")]
+impl ChainEvent {
+  /// Build the event with the accounts and assets found in the event itself.
+  ///
+  /// Events that only reference a settlement (affirmations, executions and rejections) need
+  /// the settlement's legs added with `add_leg`.
+  pub fn new(tx: &TransactionResult, event: &ProcessedEvent) -> Self {
+    let mut chain_event = Self {
+      block_number: tx.block_number,
+      tx_hash: tx.tx_hash.clone(),
+      event: event.clone(),
+      accounts: Default::default(),
+      asset_ids: Default::default(),
+    };
+    match event {
+      ProcessedEvent::ConfidentialAssetCreated { asset_id }
+      | ProcessedEvent::ConfidentialAssetMinted { asset_id, .. } => {
+        chain_event.asset_ids.insert(*asset_id);
+      }
+      ProcessedEvent::ConfidentialAccountCreated { account, .. } => {
+        chain_event.accounts.insert(account.clone());
+      }
+      ProcessedEvent::ConfidentialAccountBalanceUpdated(updated) => {
+        chain_event.accounts.insert(updated.account.clone());
+        chain_event.asset_ids.insert(updated.asset_id);
+      }
+      ProcessedEvent::ConfidentialTransactionCreated(created) => {
+        for leg in &created.legs {
+          chain_event.add_leg(leg);
+        }
+      }
+      _ => (),
+    }
+    chain_event
+  }
+
+  /// Add the accounts and assets of a settlement leg.
+  pub fn add_leg(&mut self, leg: &TransactionLegDetails) {
+    self.accounts.insert(leg.sender.clone());
+    self.accounts.insert(leg.receiver.clone());
+    for (asset_id, auditors) in &leg.assets_and_auditors {
+      self.asset_ids.insert(*asset_id);
+      self.accounts.extend(auditors.iter().cloned());
+    }
+  }
+
+  /// Settlement and leg (`None` for all legs) the event references without listing its legs.
+  pub fn settlement_ref(&self) -> Option<(u32, Option<u32>)> {
+    match &self.event {
+      ProcessedEvent::ConfidentialTransactionAffirmed(affirmed) => Some((
+        affirmed.transaction_id.0 as u32,
+        Some(affirmed.leg_id.0 as u32),
+      )),
+      ProcessedEvent::ConfidentialTransactionExecuted { transaction_id }
+      | ProcessedEvent::ConfidentialTransactionRejected { transaction_id } => {
+        Some((transaction_id.0 as u32, None))
+      }
+      _ => None,
+    }
+  }
+
+  /// Does the event involve the account and the asset.  `None` matches everything.
+  pub fn matches(&self, account: Option<&PublicKey>, asset_id: Option<Uuid>) -> bool {
+    account.map_or(true, |account| self.accounts.contains(account))
+      && asset_id.map_or(true, |asset_id| self.asset_ids.contains(&asset_id))
+  }
+}
+
 /// Account asset incoming balance.
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct AccountAssetIncomingBalance {
@@ -539,10 +1872,215 @@ pub struct AccountAssetIncomingBalance {
   pub incoming_amount: Balance,
 }
 
-/// Account asset balance updated.
+/// Import confidential accounts and their on-chain balances.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema, Zeroize, ZeroizeOnDrop)]
+pub struct ImportAccountsRequest {
+  /// Hex encoded secret keys of the confidential accounts.
+  #[schema(example = json!(["0x0000000000000000000000000000000000000000000000000000000000000000"]))]
+  #[serde(default)]
+  pub secrets: Vec<String>,
+  /// Also import the secret keys stored under this path of the Vault KV mount (see
+  /// `ACCOUNT_KEY_STORE_KV_MOUNT`).  Each secret has a hex encoded `secret_key` field.
+  #[schema(example = "polymesh-private/accounts")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub vault_path: Option<String>,
+}
+
+/// Account asset balance imported from the chain.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccountAssetImportedBalance {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Decrypted balance.
+  #[schema(example = 1000, value_type = u64)]
+  pub balance: Balance,
+}
+
+/// Confidential account imported from the chain.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportedAccount {
+  /// Confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// Was the account already in the database.
+  #[schema(example = false)]
+  pub existing: bool,
+  /// Imported balances.
+  pub balances: Vec<AccountAssetImportedBalance>,
+  /// Incoming balances (not yet applied on-chain).
+  pub incoming_balances: Vec<AccountAssetIncomingBalance>,
+}
+
+/// Refresh local account balances from the chain.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct RefreshBalancesRequest {
+  /// Confidential accounts to refresh.  All local accounts if empty.
+  #[schema(value_type = Vec<String>, example = json!(["0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114"]))]
+  #[serde(default)]
+  pub accounts: Vec<PublicKey>,
+}
+
+/// Difference between the local and on-chain balance of an account asset.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AssetBalanceDrift {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Local balance before the refresh.
+  #[schema(example = 900, value_type = Option<u64>)]
+  pub local_balance: Option<Balance>,
+  /// Decrypted on-chain balance.
+  #[schema(example = 1000, value_type = u64)]
+  pub chain_balance: Balance,
+  /// `chain_balance - local_balance`.
+  #[schema(example = 100)]
+  pub drift: i64,
+  /// Was the local balance updated.
+  #[schema(example = true)]
+  pub updated: bool,
+}
+
+/// Local and on-chain balance of an account asset.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReconciledBalance {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Locally tracked balance, null if the account asset isn't tracked.
+  #[schema(example = 900, value_type = Option<u64>)]
+  pub local_balance: Option<Balance>,
+  /// Decrypted on-chain balance, null if the account has no on-chain balance.
+  #[schema(example = 1000, value_type = Option<u64>)]
+  pub chain_balance: Option<Balance>,
+  /// `chain_balance - local_balance`.
+  #[schema(example = 100)]
+  pub diff: i64,
+  /// Do the local and on-chain encrypted balances match.
+  #[schema(example = false)]
+  pub matches: bool,
+  /// Was the local balance rewritten with the on-chain balance (`POST`).
+  #[schema(example = false)]
+  pub repaired: bool,
+}
+
+/// Balances of a confidential account refreshed from the chain.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshedAccount {
+  /// Confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub confidential_account: PublicKey,
+  /// On-chain asset balances.
+  pub balances: Vec<AssetBalanceDrift>,
+  /// Why the account couldn't be refreshed.
+  #[schema(example = json!(null))]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub err_msg: Option<String>,
+}
+
+impl RefreshedAccount {
+  /// Number of updated balances.
+  pub fn updated(&self) -> usize {
+    self.balances.iter().filter(|b| b.updated).count()
+  }
+}
+
+/// Result of refreshing local balances from the chain.
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshBalancesResult {
+  /// Refreshed accounts.
+  pub accounts: Vec<RefreshedAccount>,
+  /// Number of updated balances.
+  #[schema(example = 1)]
+  pub updated: usize,
+  /// Number of accounts that failed to refresh.
+  #[schema(example = 0)]
+  pub failed: usize,
+}
+
+impl RefreshBalancesResult {
+  pub fn new(accounts: Vec<RefreshedAccount>) -> Self {
+    Self {
+      updated: accounts.iter().map(|acc| acc.updated()).sum(),
+      failed: accounts.iter().filter(|acc| acc.err_msg.is_some()).count(),
+      accounts,
+    }
+  }
+}
+
+/// Move a compromised confidential account's balances to a new account.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct KeyCompromiseRequest {
+  /// Signer of the transactions.
+  #[schema(example = "Alice")]
+  pub signer: String,
+  /// Venue to create the self-settlement in.
+  #[schema(example = 1)]
+  pub venue_id: u64,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+}
+
+/// A step of the key compromise workflow.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct KeyCompromiseStep {
+  /// Step name.
+  #[schema(example = "lock_account")]
+  pub step: String,
+  /// Did the step succeed.
+  #[schema(example = true)]
+  pub success: bool,
+  /// Transaction hash, if the step submitted a transaction.
+  #[schema(example = json!(null))]
+  pub tx_hash: Option<String>,
+  /// Error or details.
+  #[schema(example = json!(null))]
+  pub message: Option<String>,
+}
+
+/// Progress of the key compromise workflow.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct KeyCompromiseReport {
+  /// The compromised (now locked) confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub compromised_account: PublicKey,
+  /// The replacement confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub replacement_account: Option<PublicKey>,
+  /// Self-settlement moving the balances.
+  #[schema(example = json!(null))]
+  pub settlement_id: Option<u64>,
+  /// Balances moved to the replacement account.
+  pub balances: Vec<AccountAssetImportedBalance>,
+  /// Steps completed so far.
+  pub steps: Vec<KeyCompromiseStep>,
+  /// Did all steps complete.
+  #[schema(example = true)]
+  pub completed: bool,
+}
+
+impl KeyCompromiseReport {
+  /// Start a new step.
+  pub fn start(&mut self, step: &str) {
+    self.steps.push(KeyCompromiseStep {
+      step: step.to_string(),
+      ..Default::default()
+    });
+  }
+
+  /// Mark the current step as successful.
+  pub fn done(&mut self, tx_hash: Option<String>) {
+    if let Some(step) = self.steps.last_mut() {
+      step.success = true;
+      step.tx_hash = tx_hash;
+    }
+  }
+}
+
+/// Account asset balance updated.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Encode)]
 pub struct AccountAssetBalanceUpdated {
   /// Asset id.
+  #[codec(encoded_as = "UuidBytes")]
   pub asset_id: Uuid,
   /// The update action.
   pub action: BalanceUpdateAction,
@@ -554,53 +2092,376 @@ pub struct AccountAssetBalanceUpdated {
   pub balance: Balance,
 }
 
+impl AccountAssetBalanceUpdated {
+  /// Balance history action and amount of the update.
+  pub fn history_change(&self) -> (BalanceAction, i64) {
+    match self.action {
+      BalanceUpdateAction::Withdraw => (BalanceAction::Withdrawal, -(self.amount as i64)),
+      BalanceUpdateAction::Deposit | BalanceUpdateAction::DepositIncoming => {
+        (BalanceAction::Deposit, self.amount as i64)
+      }
+    }
+  }
+}
+
 /// Account asset balances updated.
-#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema, Encode)]
 pub struct AccountAssetBalancesUpdated {
   pub updates: Vec<AccountAssetBalanceUpdated>,
 }
 
-/// Block transaction record.
-#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-pub struct BlockTransactionRecord {
-  /// Block hash.
-  pub block_hash: String,
-  /// Block number.
-  pub block_number: u32,
-  /// Transaction hash.
-  pub tx_hash: String,
-  /// Was the transaction sucessful.
-  pub success: bool,
-  /// If `success` is false, then provide an error message.
-  pub error: Option<String>,
-  /// Events.
-  pub events: Option<String>,
+/// Block transaction record.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct BlockTransactionRecord {
+  /// Block hash.
+  pub block_hash: String,
+  /// Block number.
+  pub block_number: u32,
+  /// Transaction hash.
+  pub tx_hash: String,
+  /// Was the transaction sucessful.
+  pub success: bool,
+  /// If `success` is false, then provide an error message.
+  pub error: Option<String>,
+  /// Events.
+  pub events: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+#[cfg(feature = "backend")]
+impl BlockTransactionRecord {
+  pub fn from_tx(tx: &TransactionResult) -> Result<Self> {
+    Ok(Self {
+      block_hash: tx.block_hash.clone(),
+      block_number: tx.block_number,
+      tx_hash: tx.tx_hash.clone(),
+      events: if tx.processed_events.0.len() > 0 {
+        Some(serde_json::to_string(&tx.processed_events)?)
+      } else {
+        None
+      },
+      success: tx.success,
+      error: tx.err_msg.clone(),
+      ..Default::default()
+    })
+  }
+
+  /// Transaction results from the record.  Balance updates and fees aren't recorded.
+  pub fn to_tx_result(&self) -> Result<TransactionResult> {
+    let mut res = TransactionResult {
+      block_hash: self.block_hash.clone(),
+      block_number: self.block_number,
+      tx_hash: self.tx_hash.clone(),
+      success: self.success,
+      err_msg: self.error.clone(),
+      processed_events: match &self.events {
+        Some(events) => serde_json::from_str(events)?,
+        None => Default::default(),
+      },
+      ..Default::default()
+    };
+    res.set_created_ids();
+    Ok(res)
+  }
+}
+
+/// Status of a background transaction job.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TxJobStatus {
+  /// The transaction was submitted, waiting for it to be included in a block.
+  #[default]
+  Submitted,
+  /// The transaction is in a block.  Waiting for finalization or processing the results.
+  InBlock,
+  /// The results have been processed.
+  Completed,
+  /// Tracking the transaction or processing the results failed.
+  Failed,
+}
+
+impl TxJobStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Submitted => "submitted",
+      Self::InBlock => "in_block",
+      Self::Completed => "completed",
+      Self::Failed => "failed",
+    }
+  }
+}
+
+impl std::str::FromStr for TxJobStatus {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "submitted" => Ok(Self::Submitted),
+      "in_block" => Ok(Self::InBlock),
+      "completed" => Ok(Self::Completed),
+      "failed" => Ok(Self::Failed),
+      _ => Err(Error::Other(format!("Unknown transaction job status: {s}"))),
+    }
+  }
+}
+
+/// Background job tracking a submitted transaction.
+///
+/// Returned (`202 Accepted`) by the transaction endpoints when called with `async=true`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct TxJob {
+  /// Job id.  Use `/tx/jobs/{job_id}` to get the results.
+  #[schema(example = 1)]
+  pub job_id: i64,
+  /// Endpoint that submitted the transaction.
+  #[schema(example = "create_venue")]
+  pub operation: String,
+  /// Job status.
+  pub status: TxJobStatus,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  pub finalize: bool,
+  /// Transaction hash.
+  #[schema(example = "0xea549dcdadacb5678e37a336e44c581ade562b696159bf8fd846fee7e7fe1dc3")]
+  pub tx_hash: String,
+  /// Block hash, once the transaction is in a block.
+  #[schema(example = json!(null))]
+  pub block_hash: Option<String>,
+  /// Error message, when failed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub err_msg: Option<String>,
+  /// Transaction results, when completed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub result: Option<TransactionResult>,
+  /// Progress of a multi-step job (i.e. `key_compromised`).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  #[schema(value_type = Object)]
+  pub progress: Option<serde_json::Value>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+#[cfg(feature = "backend")]
+impl TxJob {
+  pub fn from_row(row: TxJobRow) -> Result<Self> {
+    Ok(Self {
+      job_id: row.job_id,
+      operation: row.operation,
+      status: row.status.parse()?,
+      finalize: row.finalize,
+      tx_hash: row.tx_hash,
+      block_hash: row.block_hash,
+      err_msg: row.err_msg,
+      result: row
+        .result
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()?,
+      progress: row
+        .progress
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()?,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
+    })
+  }
+
+  pub fn is_finished(&self) -> bool {
+    matches!(self.status, TxJobStatus::Completed | TxJobStatus::Failed)
+  }
+}
+
+/// Transaction job row.  The results are stored as JSON.
+#[cfg(feature = "backend")]
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct TxJobRow {
+  pub job_id: i64,
+  pub operation: String,
+  pub status: String,
+  pub finalize: bool,
+  pub tx_hash: String,
+  pub block_hash: Option<String>,
+  pub err_msg: Option<String>,
+  pub result: Option<String>,
+  pub progress: Option<String>,
+
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Status of a transaction in the outbox.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TxOutboxStatus {
+  /// Submitting failed, waiting for the next attempt.
+  #[default]
+  Pending,
+  /// The transaction was submitted, waiting for its results.
+  Submitted,
+  /// The transaction is in a block (and finalized if requested).
+  Completed,
+  /// Gave up after too many attempts, or the transaction can't be submitted.
+  Failed,
+}
+
+impl TxOutboxStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Pending => "pending",
+      Self::Submitted => "submitted",
+      Self::Completed => "completed",
+      Self::Failed => "failed",
+    }
+  }
+}
+
+impl std::str::FromStr for TxOutboxStatus {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "pending" => Ok(Self::Pending),
+      "submitted" => Ok(Self::Submitted),
+      "completed" => Ok(Self::Completed),
+      "failed" => Ok(Self::Failed),
+      _ => Err(Error::Other(format!("Unknown outbox status: {s}"))),
+    }
+  }
+}
+
+/// Transaction that failed to submit and is retried in the background.
+///
+/// Returned (`202 Accepted`) by the transaction endpoints when the node couldn't accept
+/// the transaction (i.e. the node is down or the transaction priority was too low).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct TxOutboxEntry {
+  /// Outbox id.  Use `/tx/outbox/{outbox_id}` to get the status.
+  #[schema(example = 1)]
+  pub outbox_id: i64,
+  /// Endpoint that submitted the transaction.
+  #[schema(example = "create_venue")]
+  pub operation: String,
+  /// Signer that signs the retries.
+  #[schema(example = "Alice")]
+  pub signer: String,
+  /// Extrinsic.
+  #[schema(example = "create_venue")]
+  pub extrinsic: String,
+  /// SCALE encoded call.
+  #[schema(value_type = String, format = Binary, example = "0x2f00")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub call: Vec<u8>,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  pub finalize: bool,
+  /// Status.
+  pub status: TxOutboxStatus,
+  /// Number of failed submissions.
+  #[schema(example = 1)]
+  pub attempts: i64,
+  /// Error of the last attempt.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub err_msg: Option<String>,
+  /// Nonce the retries are signed with, if a failed submission may have reached the node.
+  #[schema(example = json!(null))]
+  pub nonce: Option<u32>,
+  /// Transaction hash, once submitted.
+  #[schema(example = json!(null))]
+  pub tx_hash: Option<String>,
+  /// Transaction results, when completed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub result: Option<TransactionResult>,
 
+  /// Time (UTC) of the next attempt, while pending.
+  pub next_attempt_at: chrono::NaiveDateTime,
   pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
 }
 
 #[cfg(feature = "backend")]
-impl BlockTransactionRecord {
-  pub fn from_tx(tx: &TransactionResult) -> Result<Self> {
+impl TxOutboxEntry {
+  pub fn from_row(row: TxOutboxRow) -> Result<Self> {
     Ok(Self {
-      block_hash: tx.block_hash.clone(),
-      block_number: tx.block_number,
-      tx_hash: tx.tx_hash.clone(),
-      events: if tx.processed_events.0.len() > 0 {
-        Some(serde_json::to_string(&tx.processed_events)?)
-      } else {
-        None
-      },
-      success: tx.success,
-      error: tx.err_msg.clone(),
-      ..Default::default()
+      outbox_id: row.outbox_id,
+      operation: row.operation,
+      signer: row.signer,
+      extrinsic: row.extrinsic,
+      call: row.call,
+      finalize: row.finalize,
+      status: row.status.parse()?,
+      attempts: row.attempts,
+      err_msg: row.err_msg,
+      nonce: row.nonce.map(|nonce| nonce as u32),
+      tx_hash: row.tx_hash,
+      result: row
+        .result
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()?,
+      next_attempt_at: row.next_attempt_at,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
     })
   }
 }
 
+/// Transaction outbox row.  The results are stored as JSON.
+#[cfg(feature = "backend")]
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct TxOutboxRow {
+  pub outbox_id: i64,
+  pub operation: String,
+  pub signer: String,
+  pub extrinsic: String,
+  pub call: Vec<u8>,
+  pub finalize: bool,
+  pub status: String,
+  pub attempts: i64,
+  pub err_msg: Option<String>,
+  pub nonce: Option<i64>,
+  pub tx_hash: Option<String>,
+  pub result: Option<String>,
+
+  pub next_attempt_at: chrono::NaiveDateTime,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Dry-run of a transaction.  Nothing was submitted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct DryRunResult {
+  /// Extrinsic that would be submitted.
+  #[schema(example = "mint")]
+  pub extrinsic: String,
+  /// Signer's account.
+  #[schema(example = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")]
+  pub account: String,
+  /// Estimated weight (`ref_time`).
+  #[schema(example = 1000000000)]
+  pub weight: u64,
+  /// Estimated proof size.
+  #[schema(example = 3593)]
+  pub proof_size: u64,
+  /// Dispatch class: `normal`, `operational` or `mandatory`.
+  #[schema(example = "normal")]
+  pub class: String,
+  /// Estimated fee (without tip).
+  #[schema(value_type = u64, example = 52000)]
+  pub partial_fee: u128,
+  /// Signer's free balance.
+  #[schema(value_type = u64, example = 100000000)]
+  pub free_balance: u128,
+  /// The free balance covers the estimated fee.
+  #[schema(example = true)]
+  pub sufficient_balance: bool,
+}
+
 /// Transaction results
-#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema, Encode)]
 pub struct TransactionResult {
   /// Block hash.
   #[schema(example = "0xea549dcdadacb5678e37a336e44c581ade562b696159bf8fd846fee7e7fe1dc3")]
@@ -623,13 +2484,75 @@ pub struct TransactionResult {
   /// Account balances updated.
   #[schema(example = json!([]))]
   pub balances_updated: Option<AccountAssetBalancesUpdated>,
+  /// Transaction fee paid.
+  #[schema(example = json!(null))]
+  pub fee: Option<u64>,
+  /// The transaction wasn't finalized before the finalization timeout.  Use `tx_hash`
+  /// to look up the results later.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub pending: bool,
+  /// Transaction status when `pending` is true (i.e. `InBlock`, `Broadcast`).
+  #[schema(example = json!(null))]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub status: Option<String>,
+  /// Confidential venue created by the transaction.
+  #[schema(example = json!(null))]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  #[codec(skip)]
+  pub created_venue_id: Option<u64>,
+  /// Confidential settlement created by the transaction.
+  #[schema(example = json!(null))]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  #[codec(skip)]
+  pub created_settlement_id: Option<u64>,
+  /// Confidential asset created by the transaction.
+  #[schema(example = json!(null))]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  #[codec(skip)]
+  pub created_asset_id: Option<Uuid>,
 }
 
+/// Maximum time to wait for finalization in seconds (0 = no limit).
+#[cfg(feature = "backend")]
+static FINALIZATION_TIMEOUT: AtomicU64 = AtomicU64::new(0);
+
 #[cfg(feature = "backend")]
 impl TransactionResult {
+  /// Set the maximum time to wait for a transaction to be finalized.
+  ///
+  /// `None` waits forever.
+  pub fn set_finalization_timeout(timeout: Option<Duration>) {
+    let secs = timeout.map(|t| t.as_secs()).unwrap_or(0);
+    FINALIZATION_TIMEOUT.store(secs, Ordering::Relaxed);
+  }
+
+  /// Maximum time to wait for a transaction to be finalized, if set.
+  pub fn finalization_timeout() -> Option<Duration> {
+    match FINALIZATION_TIMEOUT.load(Ordering::Relaxed) {
+      0 => None,
+      secs => Some(Duration::from_secs(secs)),
+    }
+  }
+
+  /// Get the block's transactions and their events.
+  ///
+  /// If the block has events this release can't decode, the events are decoded with the
+  /// block's runtime metadata and the unknown ones are reported as `Raw` events (after the
+  /// transaction's other events).
   pub async fn get_block_transactions(api: &Api, header: Header) -> Result<Vec<Self>> {
     let block_hash = header.hash();
-    let block_events = api.block_events(Some(block_hash)).await?;
+    let (block_events, raw_events) = match api.block_events(Some(block_hash)).await {
+      Ok(events) => (events, Vec::new()),
+      Err(err) => {
+        log::warn!(
+          "Failed to decode the events of block {}, using the runtime metadata: {err:?}",
+          header.number
+        );
+        let events = BlockEvents::fetch(api, block_hash).await?;
+        (events.events, events.raw)
+      }
+    };
     let block = api.client().get_block(Some(block_hash)).await?;
 
     let mut transactions = Vec::new();
@@ -650,15 +2573,32 @@ impl TransactionResult {
           }
           None => (false, Some(format!("Unknown transaction results"))),
         };
-        transactions.push(Self {
+        let mut processed_events = ProcessedEvents::from_events(&events)?;
+        processed_events.0.extend(
+          raw_events
+            .iter()
+            .filter(|ev| ev.phase == Phase::ApplyExtrinsic(idx as u32))
+            .map(|ev| ProcessedEvent::Raw {
+              pallet_index: ev.pallet_index,
+              variant_index: ev.variant_index,
+              pallet: ev.pallet.clone(),
+              variant: ev.variant.clone(),
+              bytes: format!("0x{}", hex::encode(&ev.bytes)),
+            }),
+        );
+        let mut tx = Self {
           block_hash: block_hash.clone(),
           block_number: header.number,
           tx_hash: format!("{:#x}", tx_hash),
           success,
           err_msg,
-          processed_events: ProcessedEvents::from_events(&events)?,
+          processed_events,
           balances_updated: None,
-        })
+          fee: fee_paid(&events),
+          ..Default::default()
+        };
+        tx.set_created_ids();
+        transactions.push(tx);
       }
     }
     Ok(transactions)
@@ -669,12 +2609,41 @@ impl TransactionResult {
 
     // Wait for transaction to execute.
     let block_hash = if finalize {
-      tx_res.wait_finalized().await?
+      match Self::finalization_timeout() {
+        Some(timeout) => {
+          match actix_web::rt::time::timeout(timeout, tx_res.wait_finalized()).await {
+            Ok(block_hash) => block_hash?,
+            Err(_) => {
+              // Don't hold the connection, return the current status.
+              res.tx_hash = format!("{:#x}", tx_res.hash());
+              res.pending = true;
+              res.status = Some(format!("{:?}", tx_res.status()));
+              return Ok(res);
+            }
+          }
+        }
+        None => tx_res.wait_finalized().await?,
+      }
     } else {
       tx_res.wait_in_block().await?
-    }
-    .unwrap_or_default();
-    res.block_hash = format!("{block_hash:#x}");
+    };
+    Self::from_tx_results(tx_res, block_hash).await
+  }
+
+  /// Set the ids of the venue, settlement or asset created by the transaction.
+  pub fn set_created_ids(&mut self) {
+    self.created_venue_id = self.processed_events.created_venue_id();
+    self.created_settlement_id = self.processed_events.created_settlement_id();
+    self.created_asset_id = self.processed_events.created_asset_id();
+  }
+
+  /// Results of a transaction that was included in block `block_hash`.
+  pub async fn from_tx_results(
+    mut tx_res: TransactionResults,
+    block_hash: Option<BlockHash>,
+  ) -> Result<Self> {
+    let mut res = Self::default();
+    res.block_hash = format!("{:#x}", block_hash.unwrap_or_default());
     res.tx_hash = format!("{:#x}", tx_res.hash());
 
     if let Some(header) = tx_res.get_block_header().await? {
@@ -684,7 +2653,9 @@ impl TransactionResult {
     // Process events.
     if let Some(events) = tx_res.events().await? {
       res.processed_events = ProcessedEvents::from_events(&events.0)?;
+      res.fee = fee_paid(&events.0);
     }
+    res.set_created_ids();
 
     match tx_res.extrinsic_result().await? {
       Some(ExtrinsicResult::Success(_info)) => {
@@ -710,6 +2681,7 @@ impl TransactionResult {
       match event {
         ProcessedEvent::ConfidentialAccountBalanceUpdated(balance_updated) => {
           if let Some(update) = balance_updated.try_decrypt(account) {
+            let (action, amount) = update.history_change();
             asset_updates.insert(
               update.asset_id,
               UpdateAccountAsset {
@@ -718,6 +2690,12 @@ impl TransactionResult {
                 asset_id: update.asset_id,
                 balance: update.balance,
                 enc_balance: balance_updated.balance().ok()?,
+                block_number: Some(self.block_number),
+                source: BalanceSource::Chain,
+                base_enc_balance: None,
+                action,
+                amount: Some(amount),
+                history_source: Some(self.tx_hash.clone()),
               },
             );
             updates.push(update);
@@ -735,6 +2713,17 @@ impl TransactionResult {
   }
 }
 
+/// Get the fee paid by the transaction from it's events.
+pub fn fee_paid(events: &[EventRecord<RuntimeEvent>]) -> Option<u64> {
+  events.iter().find_map(|rec| match &rec.event {
+    RuntimeEvent::TransactionPayment(TransactionPaymentEvent::TransactionFeePaid {
+      actual_fee,
+      ..
+    }) => Some(*actual_fee as u64),
+    _ => None,
+  })
+}
+
 pub fn bytes_to_memo(val: &[u8]) -> Memo {
   let mut memo = [0u8; 32];
   for (idx, b) in val.iter().take(32).enumerate() {
@@ -780,6 +2769,15 @@ pub struct ConfidentialAssetDetails {
   pub auditors: Vec<PublicKey>,
 }
 
+/// Details of one asset of a bulk asset details request.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AssetDetailsResult {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Asset details, null if the asset doesn't exist.
+  pub details: Option<ConfidentialAssetDetails>,
+}
+
 /// Create confidential asset on-chain.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct CreateConfidentialAsset {
@@ -798,6 +2796,18 @@ pub struct CreateConfidentialAsset {
   #[schema(example = json!(["0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114"]))]
   #[serde(default)]
   pub auditors: Vec<PublicKey>,
+  /// Local asset name.
+  #[schema(example = "Private Fund A")]
+  #[serde(default)]
+  pub name: Option<String>,
+  /// Local asset ticker.  Must be unique.
+  #[schema(example = "PFA")]
+  #[serde(default)]
+  pub ticker: Option<String>,
+  /// Number of decimals of the asset's amounts.  Only used to format amounts.
+  #[schema(example = 6)]
+  #[serde(default)]
+  pub decimals: Option<u8>,
 }
 
 #[cfg(feature = "backend")]
@@ -805,18 +2815,78 @@ impl CreateConfidentialAsset {
   pub fn auditors(&self) -> Result<ConfidentialAuditors> {
     join_auditors(&self.mediators, &self.auditors)
   }
+
+  /// Local asset record for the created asset.
+  pub fn add_asset(&self, asset_id: Uuid) -> AddAsset {
+    AddAsset {
+      asset_id,
+      name: self.name.clone(),
+      ticker: self.ticker.clone(),
+      decimals: self.decimals,
+    }
+  }
+}
+
+/// Create a confidential venue.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateVenue {
+  /// Signer of the transaction.
+  #[schema(example = "Alice")]
+  pub signer: String,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+  /// Local label for the venue.
+  #[schema(example = "OTC desk")]
+  #[serde(default)]
+  pub label: Option<String>,
+}
+
+/// Locally known confidential venue.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct Venue {
+  /// Confidential venue id.
+  #[schema(example = 1)]
+  pub venue_id: i64,
+  /// Signer that created the venue.
+  #[schema(example = "Alice")]
+  pub signer: String,
+  /// Local label.
+  #[schema(example = "OTC desk")]
+  pub label: Option<String>,
+  /// Execute the venue's settlements once all parties have affirmed.
+  #[schema(example = false)]
+  pub auto_execute: bool,
+
+  pub created_at: chrono::NaiveDateTime,
+}
+
+/// Enable/disable auto-execution of a venue's settlements.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SetVenueAutoExecute {
+  /// Execute the venue's settlements with the venue signer once all parties have affirmed.
+  #[schema(example = true)]
+  pub auto_execute: bool,
 }
 
 /// Transaction signer.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct TransactionArgs {
-  /// Signer of the transaction.
+  /// Signer of the transaction.  Optional for the `/tx/accounts/{public_key}` endpoints,
+  /// which default to the account's signer (see `/account_signers`).
   #[schema(example = "Alice")]
+  #[serde(default)]
   pub signer: String,
   /// Wait for block finalization.
   #[schema(example = false)]
   #[serde(default)]
   pub finalize: bool,
+  /// Only estimate the fees and validate the transaction, don't submit it.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub dry_run: bool,
 }
 
 /// Confidential asset settlement leg.
@@ -869,6 +2939,10 @@ pub struct CreateConfidentialSettlement {
   #[schema(example = false)]
   #[serde(default)]
   pub finalize: bool,
+  /// Only estimate the fees and validate the transaction, don't submit it.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub dry_run: bool,
   /// Settlement legs.
   pub legs: Vec<ConfidentialSettlementLeg>,
   /// Settlement memo.
@@ -901,6 +2975,138 @@ impl CreateConfidentialSettlement {
   }
 }
 
+/// Max. assets in a settlement leg (the chain's `MaxAssetsPerLeg`).
+pub const MAX_ASSETS_PER_LEG: u32 = 4;
+/// Max. legs in a settlement (the chain's `MaxNumberOfLegs`).
+pub const MAX_SETTLEMENT_LEGS: usize = 10;
+
+/// Desired transfer of one asset.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SettlementTransfer {
+  /// Sender's confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub sender: PublicKey,
+  /// Receiver's confidential account.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub receiver: PublicKey,
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// The asset amount.
+  #[schema(example = 1000, value_type = u64)]
+  pub amount: Balance,
+  /// Venue mediator identities.
+  #[schema(example = json!([]))]
+  #[serde(default)]
+  pub mediators: BTreeSet<IdentityId>,
+  /// Venue auditor Elgamal public keys.
+  #[schema(example = json!([]))]
+  #[serde(default)]
+  pub auditors: BTreeSet<PublicKey>,
+}
+
+/// Group transfers into settlement legs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AssignSettlementLegsRequest {
+  /// Signer of the settlement.
+  #[schema(example = "Alice")]
+  pub signer: String,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+  /// Settlement memo.
+  #[schema(example = "")]
+  #[serde(default)]
+  pub memo: String,
+  /// Max. assets per leg.  Defaults to the chain's limit.
+  #[schema(example = json!(null))]
+  pub max_assets_per_leg: Option<u32>,
+  /// Transfers.
+  pub transfers: Vec<SettlementTransfer>,
+}
+
+impl AssignSettlementLegsRequest {
+  /// Group the transfers into legs.
+  ///
+  /// Transfers share a leg when they have the same sender, receiver, mediators and
+  /// auditors.  A leg can't have the same asset twice or more than `max_assets_per_leg`
+  /// assets, the other transfers go into a new leg.
+  pub fn assign(&self) -> Result<AssignedSettlementLegs> {
+    if self.transfers.is_empty() {
+      return Err(Error::other("No transfers"));
+    }
+    let max_assets = self
+      .max_assets_per_leg
+      .unwrap_or(MAX_ASSETS_PER_LEG)
+      .clamp(1, MAX_ASSETS_PER_LEG) as usize;
+    let mut legs: Vec<ConfidentialSettlementLeg> = Vec::new();
+    let mut amounts: Vec<Vec<TransactionAssetAmount>> = Vec::new();
+    for transfer in &self.transfers {
+      let idx = legs.iter().position(|leg| {
+        leg.sender == transfer.sender
+          && leg.receiver == transfer.receiver
+          && leg.mediators == transfer.mediators
+          && leg.auditors == transfer.auditors
+          && leg.assets.len() < max_assets
+          && !leg.assets.contains(&transfer.asset_id)
+      });
+      let idx = match idx {
+        Some(idx) => idx,
+        None => {
+          legs.push(ConfidentialSettlementLeg {
+            assets: Default::default(),
+            sender: transfer.sender.clone(),
+            receiver: transfer.receiver.clone(),
+            mediators: transfer.mediators.clone(),
+            auditors: transfer.auditors.clone(),
+          });
+          amounts.push(Vec::new());
+          legs.len() - 1
+        }
+      };
+      legs[idx].assets.insert(transfer.asset_id);
+      amounts[idx].push(TransactionAssetAmount {
+        asset_id: transfer.asset_id,
+        amount: transfer.amount,
+      });
+    }
+    if legs.len() > MAX_SETTLEMENT_LEGS {
+      return Err(Error::Other(format!(
+        "The transfers need {} legs, a settlement can only have {MAX_SETTLEMENT_LEGS}",
+        legs.len()
+      )));
+    }
+
+    Ok(AssignedSettlementLegs {
+      settlement: CreateConfidentialSettlement {
+        signer: self.signer.clone(),
+        finalize: self.finalize,
+        dry_run: false,
+        legs,
+        memo: self.memo.clone(),
+      },
+      sender_affirms: amounts
+        .into_iter()
+        .enumerate()
+        .map(|(leg_id, amounts)| AffirmTransactionLeg {
+          leg_id: TransactionLegId(leg_id as _),
+          amounts: Some(amounts),
+          party: TransactionParty::Sender,
+        })
+        .collect(),
+    })
+  }
+}
+
+/// Transfers grouped into settlement legs.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AssignedSettlementLegs {
+  /// Body for `/tx/venues/{venue_id}/settlement/create`.
+  pub settlement: CreateConfidentialSettlement,
+  /// The sender affirmation of each leg, with the leg's asset amounts.
+  pub sender_affirms: Vec<AffirmTransactionLeg>,
+}
+
 /// Asset id and amount.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct TransactionAssetAmount {
@@ -911,6 +3117,31 @@ pub struct TransactionAssetAmount {
   pub amount: Balance,
 }
 
+impl TransactionAssetAmount {
+  /// Check that there is exactly one amount for each asset of the leg.
+  pub fn validate_leg(amounts: &[Self], leg_assets: impl IntoIterator<Item = Uuid>) -> Result<()> {
+    let mut assets = BTreeSet::new();
+    for amount in amounts {
+      if !assets.insert(amount.asset_id) {
+        return Err(Error::Other(format!(
+          "Duplicate asset amount: {}",
+          amount.asset_id
+        )));
+      }
+    }
+    let leg_assets: BTreeSet<_> = leg_assets.into_iter().collect();
+    if let Some(asset_id) = assets.difference(&leg_assets).next() {
+      return Err(Error::Other(format!("Invalid asset in leg: {asset_id}")));
+    }
+    if let Some(asset_id) = leg_assets.difference(&assets).next() {
+      return Err(Error::Other(format!(
+        "Missing amount for leg asset: {asset_id}"
+      )));
+    }
+    Ok(())
+  }
+}
+
 /// Affirm Confidential asset transaction leg as the sender/receiver/mediator.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct AffirmTransactionLeg {
@@ -936,8 +3167,10 @@ pub struct AffirmTransactionRequest {
 /// Affirm Confidential asset transactions as the sender/receiver/mediator.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct AffirmTransactionsRequest {
-  /// Signer of the transaction.
+  /// Signer of the transaction.  Optional for the `/tx/accounts/{public_key}` endpoints,
+  /// which default to the account's signer (see `/account_signers`).
   #[schema(example = "Alice")]
+  #[serde(default)]
   pub signer: String,
   /// Wait for block finalization.
   #[schema(example = false)]
@@ -950,8 +3183,10 @@ pub struct AffirmTransactionsRequest {
 /// Affirm Confidential asset transaction leg as the sender/receiver.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct AffirmTransactionLegRequest {
-  /// Signer of the transaction.
+  /// Signer of the transaction.  Optional for the `/tx/accounts/{public_key}` endpoints,
+  /// which default to the account's signer (see `/account_signers`).
   #[schema(example = "Alice")]
+  #[serde(default)]
   pub signer: String,
   /// Wait for block finalization.
   #[schema(example = false)]
@@ -963,9 +3198,69 @@ pub struct AffirmTransactionLegRequest {
   /// Confidential transaction leg id.
   #[schema(value_type = u32)]
   pub leg_id: TransactionLegId,
-  /// Transaction Amount.
-  #[schema(example = 1000, value_type = u64)]
-  pub amount: Balance,
+  /// The amount for each asset in the leg (sender only).
+  #[serde(default)]
+  pub amounts: Option<Vec<TransactionAssetAmount>>,
+  /// Deprecated: use `amounts`.  The amount of the path's asset, only for single asset legs.
+  #[schema(example = 1000, value_type = Option<u64>)]
+  #[serde(default)]
+  pub amount: Option<Balance>,
+}
+
+impl AffirmTransactionLegRequest {
+  /// The sender's asset amounts.  The deprecated `amount` is for `asset_id`.
+  pub fn asset_amounts(&self, asset_id: Uuid) -> Result<Vec<TransactionAssetAmount>> {
+    match (&self.amounts, self.amount) {
+      (Some(_), Some(_)) => Err(Error::other(
+        "Only one of 'amounts' or 'amount' is allowed.",
+      )),
+      (Some(amounts), None) => Ok(amounts.clone()),
+      (None, Some(amount)) => Ok(vec![TransactionAssetAmount { asset_id, amount }]),
+      (None, None) => Err(Error::other("Missing asset amounts.")),
+    }
+  }
+}
+
+/// Reject a confidential asset settlement.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct RejectTransactionRequest {
+  /// Signer of the transaction.  Optional for the `/tx/accounts/{public_key}` endpoints,
+  /// which default to the account's signer (see `/account_signers`).
+  #[schema(example = "Alice")]
+  #[serde(default)]
+  pub signer: String,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+  /// Confidential transaction id.
+  #[schema(value_type = u64)]
+  pub transaction_id: TransactionId,
+  /// Settlement leg count.
+  #[schema(example = 1)]
+  pub leg_count: u32,
+}
+
+/// Withdraw the affirmation of a confidential asset settlement leg.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct WithdrawAffirmationRequest {
+  /// Signer of the transaction.  Optional for the `/tx/accounts/{public_key}` endpoints,
+  /// which default to the account's signer (see `/account_signers`).
+  #[schema(example = "Alice")]
+  #[serde(default)]
+  pub signer: String,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+  /// Confidential transaction id.
+  #[schema(value_type = u64)]
+  pub transaction_id: TransactionId,
+  /// Confidential transaction leg id.
+  #[schema(value_type = u32)]
+  pub leg_id: TransactionLegId,
+  /// The party whose affirmation is withdrawn.
+  pub party: TransactionParty,
 }
 
 /// Execute confidential asset settlement.
@@ -986,18 +3281,45 @@ pub struct ExecuteConfidentialSettlement {
 /// Confidential asset mint request.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct MintRequest {
-  /// Signer of the transaction.
+  /// Signer of the transaction.  Optional for the `/tx/accounts/{public_key}` endpoints,
+  /// which default to the account's signer (see `/account_signers`).
   #[schema(example = "Alice")]
+  #[serde(default)]
   pub signer: String,
   /// Wait for block finalization.
   #[schema(example = false)]
   #[serde(default)]
   pub finalize: bool,
+  /// Only estimate the fees and validate the transaction, don't submit it.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub dry_run: bool,
   /// Amount to mint.
   #[schema(example = 1000, value_type = u64)]
   pub amount: Balance,
 }
 
+/// Confidential asset burn request.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct BurnRequest {
+  /// Signer of the transaction.  Optional for the `/tx/accounts/{public_key}` endpoints,
+  /// which default to the account's signer (see `/account_signers`).
+  #[schema(example = "Alice")]
+  #[serde(default)]
+  pub signer: String,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+  /// Only estimate the fees and validate the transaction, don't submit it.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub dry_run: bool,
+  /// Amount to burn.
+  #[schema(example = 1000, value_type = u64)]
+  pub amount: Balance,
+}
+
 /// Allow venues.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct AllowVenues {
@@ -0,0 +1,123 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::AccountAsset;
+
+/// Optionally include a fiat-equivalent valuation of the balances.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+pub struct ValuationQuery {
+  /// Currency to value the balances in, i.e. `USD`.  Requires a price source (see
+  /// `VALUATION`).
+  pub currency: Option<String>,
+}
+
+/// Price of one unit of an asset.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AssetPrice {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Currency of the price.
+  #[schema(example = "USD")]
+  pub currency: String,
+  /// Price of one unit of the asset.
+  #[schema(example = "1.25")]
+  pub price: Decimal,
+}
+
+/// Fiat-equivalent value of an asset balance.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AssetValuation {
+  /// Asset id.
+  pub asset_id: Uuid,
+  /// Decrypted balance.
+  #[schema(example = 1000)]
+  pub balance: u64,
+  /// Currency of the price and value.
+  #[schema(example = "USD")]
+  pub currency: String,
+  /// Price of one unit of the asset.
+  #[schema(example = "1.25")]
+  pub price: Decimal,
+  /// Value of the balance.
+  #[schema(example = "1250.00")]
+  pub value: Decimal,
+}
+
+impl AssetValuation {
+  pub fn new(asset_id: Uuid, balance: u64, currency: &str, price: Decimal) -> Self {
+    Self {
+      asset_id,
+      balance,
+      currency: currency.to_string(),
+      price,
+      value: price.saturating_mul(Decimal::from(balance)),
+    }
+  }
+}
+
+/// Account asset with the valuation of its balance.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ValuedAccountAsset {
+  #[serde(flatten)]
+  pub account_asset: AccountAsset,
+  /// Valuation, if requested and the asset has a price.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub valuation: Option<AssetValuation>,
+  /// Formatted balance, if requested.
+  #[schema(example = "1,000.000000")]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub formatted_balance: Option<String>,
+}
+
+/// Aggregated valuation of balances in one currency.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PortfolioValuation {
+  /// Currency of the values.
+  #[schema(example = "USD")]
+  pub currency: String,
+  /// Total value of the priced assets.
+  #[schema(example = "1250.00")]
+  pub total: Decimal,
+  /// Value of each priced asset.  Balances of the same asset are added up.
+  pub assets: Vec<AssetValuation>,
+  /// Assets without a price.  Not included in the total.
+  pub unpriced: Vec<Uuid>,
+}
+
+impl PortfolioValuation {
+  pub fn new(currency: &str) -> Self {
+    Self {
+      currency: currency.to_string(),
+      ..Default::default()
+    }
+  }
+
+  /// Add an asset balance.  `price` is `None` if the asset has no price.
+  pub fn add(&mut self, asset_id: Uuid, balance: u64, price: Option<Decimal>) {
+    let price = match price {
+      Some(price) => price,
+      None => {
+        if !self.unpriced.contains(&asset_id) {
+          self.unpriced.push(asset_id);
+        }
+        return;
+      }
+    };
+    match self.assets.iter_mut().find(|v| v.asset_id == asset_id) {
+      Some(valuation) => {
+        let balance = valuation.balance.saturating_add(balance);
+        self.total -= valuation.value;
+        *valuation = AssetValuation::new(asset_id, balance, &self.currency, price);
+        self.total = self.total.saturating_add(valuation.value);
+      }
+      None => {
+        let valuation = AssetValuation::new(asset_id, balance, &self.currency, price);
+        self.total = self.total.saturating_add(valuation.value);
+        self.assets.push(valuation);
+      }
+    }
+  }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+
+#[cfg(feature = "backend")]
+use codec::{Decode, Encode};
+#[cfg(feature = "backend")]
+use confidential_assets::{elgamal::CipherText, ElgamalPublicKey, ElgamalSecretKey, Scalar};
+#[cfg(feature = "backend")]
+use merlin::Transcript;
+
+#[cfg(feature = "backend")]
+use crate::decrypt_cache::DecryptionCache;
+use crate::PublicKey;
+#[cfg(feature = "backend")]
+use crate::{error::*, AccountWithSecret};
+
+/// Transcript label of decryption proofs.
+#[cfg(feature = "backend")]
+const DECRYPTION_PROOF_LABEL: &[u8] = b"PolymeshPrivateDecryptionProof";
+
+/// Decrypted value with a zero-knowledge proof that it was decrypted correctly.
+///
+/// Anyone can verify the proof against the account's public key and the encrypted value,
+/// without the account's secret key.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifiableDecryption {
+  /// Confidential account the value was encrypted for.
+  #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+  pub public_key: PublicKey,
+  /// Encrypted value.
+  #[schema(value_type = String, format = Binary, example = "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub encrypted_value: Vec<u8>,
+  /// Decrypted value.
+  #[schema(example = 1000)]
+  pub value: u64,
+  /// Proof of correct decryption.
+  #[schema(value_type = String, format = Binary)]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub proof: Vec<u8>,
+}
+
+/// Decryption proof verification result.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct DecryptionVerifyResult {
+  /// Does `encrypted_value` decrypt to `value` with the account's secret key.
+  #[schema(example = true)]
+  pub valid: bool,
+}
+
+/// Chaum-Pedersen proof that a ciphertext decrypts to a value.
+///
+/// `enc_value - CipherText::value(value)` is an encryption of zero, `(x, y) = (r * pk, r * G)`,
+/// only if `enc_value` encrypts `value`.  The proof shows that `x = secret * y` with the same
+/// secret as `pk = secret * G`, without revealing the secret.
+#[cfg(feature = "backend")]
+#[derive(Encode, Decode)]
+struct DecryptionProof {
+  /// Commitments `k * G` and `k * y` to the random nonce `k`.
+  commitments: CipherText,
+  /// Response `k + challenge * secret`.
+  response: ElgamalSecretKey,
+}
+
+/// Base point `G` of the account public keys.
+#[cfg(feature = "backend")]
+fn public_key_base() -> ElgamalPublicKey {
+  ElgamalSecretKey::new(Scalar::from(1u64)).get_public_key()
+}
+
+/// Fiat-Shamir challenge of a decryption proof.
+#[cfg(feature = "backend")]
+fn challenge(
+  public: &ElgamalPublicKey,
+  encrypted_value: &[u8],
+  value: u64,
+  commitments: &CipherText,
+) -> Scalar {
+  let mut transcript = Transcript::new(DECRYPTION_PROOF_LABEL);
+  transcript.append_message(b"public_key", &public.encode());
+  transcript.append_message(b"encrypted_value", encrypted_value);
+  transcript.append_message(b"value", &value.to_le_bytes());
+  transcript.append_message(b"commitments", &commitments.encode());
+  let mut bytes = [0u8; 64];
+  transcript.challenge_bytes(b"challenge", &mut bytes);
+  Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+#[cfg(feature = "backend")]
+impl AccountWithSecret {
+  /// Decrypt a value and prove that it was decrypted correctly.
+  pub fn decrypt_with_proof(&self, enc_value: CipherText) -> Result<VerifiableDecryption> {
+    let keys = self.encryption_keys()?;
+    let value = DecryptionCache::global()
+      .decrypt(&keys, &enc_value)
+      .ok_or_else(|| Error::other("Failed to decrypt value."))?;
+    let encrypted_value = enc_value.encode();
+    let zero = enc_value - CipherText::value(Scalar::from(value));
+
+    let nonce = Scalar::random(&mut rand::thread_rng());
+    let commitments = CipherText {
+      x: nonce * public_key_base().pub_key,
+      y: nonce * zero.y,
+    };
+    let challenge = challenge(&keys.public, &encrypted_value, value, &commitments);
+    let proof = DecryptionProof {
+      commitments,
+      response: ElgamalSecretKey::new(nonce + challenge * keys.secret.secret),
+    };
+    Ok(VerifiableDecryption {
+      public_key: <PublicKey as Decode>::decode(&mut self.confidential_account.as_slice())?,
+      encrypted_value,
+      value,
+      proof: proof.encode(),
+    })
+  }
+}
+
+#[cfg(feature = "backend")]
+impl VerifiableDecryption {
+  /// Verify the decryption proof.  Fails if the public key, encrypted value or proof
+  /// doesn't decode.
+  pub fn verify(&self) -> Result<bool> {
+    let public = self.public_key.decode()?;
+    let enc_value = CipherText::decode(&mut self.encrypted_value.as_slice())?;
+    let proof = DecryptionProof::decode(&mut self.proof.as_slice())?;
+    let zero = enc_value - CipherText::value(Scalar::from(self.value));
+
+    let challenge = challenge(
+      &public,
+      &self.encrypted_value,
+      self.value,
+      &proof.commitments,
+    );
+    let response = proof.response.secret;
+    Ok(
+      response * public_key_base().pub_key == proof.commitments.x + challenge * public.pub_key
+        && response * zero.y == proof.commitments.y + challenge * zero.x,
+    )
+  }
+}
+
+#[cfg(all(test, feature = "backend"))]
+mod tests {
+  use super::*;
+
+  fn new_account() -> AccountWithSecret {
+    let secret = ElgamalSecretKey::new(Scalar::random(&mut rand::thread_rng()));
+    AccountWithSecret {
+      account_id: 1,
+      confidential_account: secret.get_public_key().encode(),
+      secret_key: secret.encode(),
+      locked: false,
+      escrowed: false,
+    }
+  }
+
+  fn encrypt(account: &AccountWithSecret, value: u64) -> CipherText {
+    let public = account.encryption_keys().expect("keys").public;
+    public
+      .encrypt_value(Scalar::from(value), &mut rand::thread_rng())
+      .1
+  }
+
+  #[test]
+  fn prove_verify_round_trip() {
+    let account = new_account();
+    let decryption = account
+      .decrypt_with_proof(encrypt(&account, 1000))
+      .expect("decrypt");
+    assert_eq!(decryption.value, 1000);
+    assert!(decryption.verify().expect("verify"));
+  }
+
+  #[test]
+  fn verify_rejects_wrong_value() {
+    let account = new_account();
+    let mut decryption = account
+      .decrypt_with_proof(encrypt(&account, 1000))
+      .expect("decrypt");
+    decryption.value = 1001;
+    assert!(!decryption.verify().expect("verify"));
+  }
+
+  #[test]
+  fn verify_rejects_wrong_public_key() {
+    let account = new_account();
+    let mut decryption = account
+      .decrypt_with_proof(encrypt(&account, 1000))
+      .expect("decrypt");
+    let other = new_account();
+    decryption.public_key =
+      <PublicKey as Decode>::decode(&mut other.confidential_account.as_slice()).expect("key");
+    assert!(!decryption.verify().expect("verify"));
+  }
+
+  #[test]
+  fn verify_rejects_tampered_ciphertext() {
+    let account = new_account();
+    let mut decryption = account
+      .decrypt_with_proof(encrypt(&account, 1000))
+      .expect("decrypt");
+    // Re-randomize the ciphertext: it still encrypts the same value, but the proof is
+    // bound to the original one.
+    let enc_value = CipherText::decode(&mut decryption.encrypted_value.as_slice())
+      .expect("ciphertext")
+      + encrypt(&account, 0);
+    decryption.encrypted_value = enc_value.encode();
+    assert!(!decryption.verify().expect("verify"));
+  }
+}
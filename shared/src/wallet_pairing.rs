@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHexSeq, StrictPfx};
+
+use utoipa::ToSchema;
+
+use crate::error::Result;
+use crate::offline_tx::OfflineCall;
+
+/// External wallet paired to approve signing requests for an account.
+#[cfg_attr(feature = "backend", derive(sqlx::FromRow))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct WalletPairing {
+  /// Pairing id.
+  #[schema(example = 1)]
+  pub pairing_id: i64,
+  /// Name of the pairing.
+  #[schema(example = "Treasury approver")]
+  pub name: String,
+  /// Account (SS58 address) the wallet signs for.
+  #[schema(example = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")]
+  pub account: String,
+  /// Signing requests are posted to this url.
+  #[schema(example = "https://wallet.example.com/requests")]
+  pub callback_url: Option<String>,
+  /// Status: `pending` (waiting for the wallet to connect), `active` or `revoked`.
+  #[schema(example = "active")]
+  pub status: String,
+
+  pub connected_at: Option<chrono::NaiveDateTime>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Create a wallet pairing.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateWalletPairing {
+  /// Name of the pairing.
+  #[schema(example = "Treasury approver")]
+  pub name: String,
+  /// Account (SS58 address) the wallet signs for.
+  #[schema(example = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")]
+  pub account: String,
+}
+
+/// New wallet pairing.  The secret is only returned once.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct WalletPairingCreated {
+  pub pairing: WalletPairing,
+  /// One-time pairing secret.
+  #[schema(example = "0x5ba1d3b3a0a5f6f5c1f0e4f7b0c0d1a2b3c4d5e6f708192a3b4c5d6e7f809102")]
+  pub secret: String,
+  /// Wallet payload (deep-link) with the pairing id and secret, for the wallet to scan.
+  #[schema(example = "polymesh-private:v1:AgQ")]
+  pub payload: String,
+}
+
+/// Connect a wallet to a pending pairing.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ConnectWallet {
+  /// Pairing id.
+  #[schema(example = 1)]
+  pub pairing_id: i64,
+  /// One-time pairing secret.
+  #[schema(example = "0x5ba1d3b3a0a5f6f5c1f0e4f7b0c0d1a2b3c4d5e6f708192a3b4c5d6e7f809102")]
+  pub secret: String,
+  /// Post new signing requests to this url.  Without it the wallet has to poll
+  /// `/wallet/requests`.
+  #[schema(example = "https://wallet.example.com/requests")]
+  #[serde(default)]
+  pub callback_url: Option<String>,
+}
+
+/// Session of a connected wallet.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct WalletSession {
+  pub pairing: WalletPairing,
+  /// Session token, sent by the wallet in the `X-Wallet-Session` header.
+  #[schema(example = "0x9d5c7b2e4f1a3c6d8e0b2a4c6e8f0a1b3c5d7e9f1a2b4c6d8e0f1a3b5c7d9e0f")]
+  pub session_token: String,
+}
+
+/// Request a paired wallet to sign a transaction.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct CreateWalletSigningRequest {
+  /// Pairing of the wallet that should sign the transaction.
+  #[schema(example = 1)]
+  pub pairing_id: i64,
+  /// Human-readable summary shown to the wallet user (default: generated from the call).
+  #[schema(example = "Execute settlement 12 (2 leg(s))")]
+  #[serde(default)]
+  pub summary: Option<String>,
+  /// Seconds until the request expires (default: `WALLET_REQUEST_TTL_SECS`).
+  #[schema(example = 3600)]
+  #[serde(default)]
+  pub expires_in: Option<u64>,
+  /// Extrinsic and its arguments.
+  #[serde(flatten)]
+  pub call: OfflineCall,
+}
+
+/// Signing request sent to a paired wallet.
+///
+/// The wallet signs `payload` with the account's key and approves the request with the
+/// signature, or rejects it.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct WalletSigningRequest {
+  /// Request id.
+  #[schema(example = 1)]
+  pub request_id: i64,
+  /// Pairing id.
+  #[schema(example = 1)]
+  pub pairing_id: i64,
+  /// Build id of the unsigned transaction.
+  #[schema(example = 1)]
+  pub build_id: i64,
+  /// Account (SS58 address) that needs to sign the transaction.
+  #[schema(example = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")]
+  pub account: String,
+  /// Extrinsic and its arguments.
+  #[serde(flatten)]
+  pub call: OfflineCall,
+  /// Human-readable summary.
+  #[schema(example = "Execute settlement 12 (2 leg(s))")]
+  pub summary: String,
+  /// Account nonce the transaction was built with.
+  #[schema(example = 0)]
+  pub nonce: u32,
+  /// SCALE encoded signing payload.
+  #[schema(value_type = String, format = Binary, example = "0x2f0000")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub payload: Vec<u8>,
+  /// Status: `pending`, `approved`, `rejected`, `failed` or `expired`.
+  #[schema(example = "pending")]
+  pub status: String,
+  /// Rejection reason or submission error.
+  #[schema(example = json!(null))]
+  pub error: Option<String>,
+  /// Transaction hash, once submitted.
+  #[schema(example = json!(null))]
+  pub tx_hash: Option<String>,
+
+  pub expires_at: chrono::NaiveDateTime,
+  pub decided_at: Option<chrono::NaiveDateTime>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+impl WalletSigningRequest {
+  pub fn from_row(row: WalletSigningRequestRow) -> Result<Self> {
+    Ok(Self {
+      request_id: row.request_id,
+      pairing_id: row.pairing_id,
+      build_id: row.build_id,
+      account: row.account,
+      call: serde_json::from_str(&row.call)?,
+      summary: row.summary,
+      nonce: row.nonce as u32,
+      payload: row.payload,
+      status: row.status,
+      error: row.error,
+      tx_hash: row.tx_hash,
+      expires_at: row.expires_at,
+      decided_at: row.decided_at,
+      created_at: row.created_at,
+      updated_at: row.updated_at,
+    })
+  }
+
+  pub fn is_expired(&self) -> bool {
+    self.expires_at < chrono::Utc::now().naive_utc()
+  }
+}
+
+/// Wallet signing request row, joined with its unsigned transaction.
+#[derive(Clone, Debug, Default, sqlx::FromRow)]
+pub struct WalletSigningRequestRow {
+  pub request_id: i64,
+  pub pairing_id: i64,
+  pub build_id: i64,
+  pub account: String,
+  pub call: String,
+  pub summary: String,
+  pub nonce: i64,
+  pub payload: Vec<u8>,
+  pub status: String,
+  pub error: Option<String>,
+  pub tx_hash: Option<String>,
+  pub expires_at: chrono::NaiveDateTime,
+  pub decided_at: Option<chrono::NaiveDateTime>,
+  pub created_at: chrono::NaiveDateTime,
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// Approve a signing request with the wallet's signature.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ApproveWalletSigningRequest {
+  /// SCALE encoded `MultiSignature` of the signing payload.
+  #[schema(value_type = String, format = Binary, example = "0x01d4e2a5")]
+  #[serde(with = "SerHexSeq::<StrictPfx>")]
+  pub signature: Vec<u8>,
+  /// Wait for block finalization.
+  #[schema(example = false)]
+  #[serde(default)]
+  pub finalize: bool,
+}
+
+/// Reject a signing request.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct RejectWalletSigningRequest {
+  /// Reason shown to the requester.
+  #[schema(example = "Unexpected counterparty")]
+  #[serde(default)]
+  pub reason: Option<String>,
+}
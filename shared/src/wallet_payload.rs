@@ -0,0 +1,131 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use serde_hex::{SerHex, StrictPfx};
+use uuid::Uuid;
+
+use utoipa::ToSchema;
+
+use crate::error::{Error, Result};
+use crate::proofs::{PublicKey, UuidBytes};
+use crate::tx::Invoice;
+
+/// URI scheme of wallet payloads.
+pub const WALLET_PAYLOAD_SCHEME: &str = "polymesh-private";
+/// Current version of the wallet payload encoding.
+pub const WALLET_PAYLOAD_VERSION: u8 = 1;
+
+/// Data shared with client wallets, i.e. by QR code or deep-link.
+///
+/// Encoded as `polymesh-private:v1:<base64url of the SCALE encoded payload>`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WalletPayload {
+  /// Confidential account to send to.
+  Account {
+    /// Confidential account.
+    #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+    confidential_account: PublicKey,
+  },
+  /// Payment request (invoice).
+  Invoice {
+    /// Invoice reference.
+    #[schema(value_type = String, format = Binary, example = "0x5ba1d3b3a0a5f6f5c1f0e4f7b0c0d1a2b3c4d5e6f708192a3b4c5d6e7f809102")]
+    #[serde(with = "SerHex::<StrictPfx>")]
+    reference: [u8; 32],
+    /// Receiver's confidential account.
+    #[schema(value_type = String, format = Binary, example = "0xceae8587b3e968b9669df8eb715f73bcf3f7a9cd3c61c515a4d80f2ca59c8114")]
+    receiver: PublicKey,
+    /// Asset id.
+    #[codec(encoded_as = "UuidBytes")]
+    asset_id: Uuid,
+    /// Requested amount.
+    #[schema(example = 1000)]
+    #[codec(compact)]
+    amount: u64,
+    /// Expiry (unix timestamp).
+    #[schema(example = 1714521600)]
+    expires_at: i64,
+    /// Memo for the sender.
+    #[schema(example = "Order 1234")]
+    memo: Option<String>,
+  },
+  /// Pair an external wallet to receive signing requests.
+  Pairing {
+    /// Pairing id.
+    #[schema(example = 1)]
+    #[codec(compact)]
+    pairing_id: u64,
+    /// One-time pairing secret.
+    #[schema(value_type = String, format = Binary, example = "0x5ba1d3b3a0a5f6f5c1f0e4f7b0c0d1a2b3c4d5e6f708192a3b4c5d6e7f809102")]
+    #[serde(with = "SerHex::<StrictPfx>")]
+    secret: [u8; 32],
+    /// Base url of the API the wallet connects to.
+    #[schema(example = "https://api.example.com/api/v1")]
+    api_url: Option<String>,
+  },
+}
+
+impl WalletPayload {
+  pub fn account(confidential_account: PublicKey) -> Self {
+    Self::Account {
+      confidential_account,
+    }
+  }
+
+  pub fn invoice(invoice: &Invoice) -> Result<Self> {
+    let reference = PublicKey::from_str(&invoice.reference)?;
+    Ok(Self::Invoice {
+      reference: reference.0,
+      receiver: PublicKey::from_str(&invoice.receiver)?,
+      asset_id: invoice.asset_id,
+      amount: invoice.amount as u64,
+      expires_at: invoice.expires_at.timestamp(),
+      memo: invoice.memo.clone(),
+    })
+  }
+
+  pub fn pairing(pairing_id: i64, secret: [u8; 32], api_url: Option<String>) -> Self {
+    Self::Pairing {
+      pairing_id: pairing_id as u64,
+      secret,
+      api_url,
+    }
+  }
+
+  /// Encode as a versioned payload string.
+  pub fn to_payload(&self) -> String {
+    format!(
+      "{WALLET_PAYLOAD_SCHEME}:v{WALLET_PAYLOAD_VERSION}:{}",
+      URL_SAFE_NO_PAD.encode(self.encode())
+    )
+  }
+
+  /// Decode a payload string.
+  pub fn from_payload(payload: &str) -> Result<Self> {
+    let mut parts = payload.trim().splitn(3, ':');
+    if parts.next() != Some(WALLET_PAYLOAD_SCHEME) {
+      return Err(Error::other("Not a wallet payload"));
+    }
+    let version = parts
+      .next()
+      .and_then(|v| v.strip_prefix('v'))
+      .and_then(|v| v.parse::<u8>().ok())
+      .ok_or_else(|| Error::other("Missing wallet payload version"))?;
+    if version != WALLET_PAYLOAD_VERSION {
+      return Err(Error::Other(format!(
+        "Unsupported wallet payload version: {version}"
+      )));
+    }
+    let data = URL_SAFE_NO_PAD.decode(parts.next().unwrap_or_default())?;
+    Ok(Self::decode(&mut data.as_slice())?)
+  }
+}
+
+/// Encoded wallet payload.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct EncodedWalletPayload {
+  /// Versioned payload (also usable as a deep-link).
+  #[schema(example = "polymesh-private:v1:AM6uhYez6Wi5Zp346xWvc7zz96nNPGHFFaTYLS-siBQ")]
+  pub payload: String,
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use utoipa::ToSchema;
+
+/// The local balance doesn't match the balance the request used.
+pub const WARNING_BALANCE_DRIFT: &str = "balance_drift";
+/// The auditors were sorted, auditor ids are their index in the sorted list.
+pub const WARNING_AUDITORS_REORDERED: &str = "auditors_reordered";
+/// The generated proof is near the proof size limit.
+pub const WARNING_PROOF_SIZE: &str = "proof_size";
+
+/// Non-fatal condition of a request.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ResponseWarning {
+  /// Warning: `balance_drift`, `auditors_reordered` or `proof_size`.
+  #[schema(example = "balance_drift")]
+  pub code: String,
+  /// Details.
+  #[schema(example = "The local balance doesn't match the chain balance")]
+  pub message: String,
+}
+
+impl ResponseWarning {
+  pub fn new(code: &str, message: impl Into<String>) -> Self {
+    Self {
+      code: code.to_string(),
+      message: message.into(),
+    }
+  }
+}
+
+/// Response envelope, returned with the `X-Response-Envelope: true` request header.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ResponseEnvelope<T> {
+  /// The endpoint's response.
+  pub data: T,
+  /// Non-fatal conditions of the request.
+  pub warnings: Vec<ResponseWarning>,
+}